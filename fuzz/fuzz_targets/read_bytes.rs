@@ -0,0 +1,21 @@
+//! Parses arbitrary bytes, which must fail with an error rather than panic.
+//!
+//! Run with `cargo +nightly fuzz run read_bytes` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Limit the tracks, samples and nesting, so that small inputs can't make the fuzzer run out
+    // of memory or stack.
+    let limits = re_mp4::Limits::UNLIMITED
+        .with_max_tracks(16)
+        .with_max_total_samples(1 << 16)
+        .with_max_box_depth(64);
+    drop(re_mp4::Mp4::read_with_limits(
+        std::io::Cursor::new(data),
+        data.len() as u64,
+        limits,
+    ));
+});
@@ -0,0 +1,303 @@
+//! The AV1 sequence header, which the `configOBUs` of `av1C` usually carry, see
+//! [`Av1CBox::sequence_header`].
+
+use crate::bits::BitReader;
+use crate::{Av1CBox, Error, Result};
+
+/// The OBU type of sequence headers.
+const OBU_SEQUENCE_HEADER: u8 = 1;
+
+/// The fields of an AV1 sequence header that describe what a decoder must support, see the
+/// AV1 specification section 5.5.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Av1SequenceHeader {
+    /// `seq_profile`: 0 for Main, 1 for High and 2 for Professional.
+    pub profile: u8,
+
+    /// Whether the stream is a single picture.
+    pub still_picture: bool,
+
+    /// Whether the stream uses the reduced headers of still pictures, which have a single
+    /// operating point and no timing information.
+    pub reduced_still_picture_header: bool,
+
+    /// The operating points of the stream, the first of which is the one that decoders
+    /// choose by default.
+    pub operating_points: Vec<Av1OperatingPoint>,
+
+    /// The largest frame width of the stream in pixels.
+    pub max_frame_width: u32,
+
+    /// The largest frame height of the stream in pixels.
+    pub max_frame_height: u32,
+
+    pub color: Av1ColorConfig,
+
+    /// Whether frames may carry film grain parameters, for decoders to synthesize grain.
+    pub film_grain_params_present: bool,
+}
+
+/// An operating point of an AV1 stream, which is a subset of its layers that can be decoded
+/// on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Av1OperatingPoint {
+    /// `operating_point_idc`: the temporal layers in the low 8 bits and the spatial layers in
+    /// the high 4 bits, or 0 if the stream has no layers.
+    pub idc: u16,
+
+    /// `seq_level_idx`, like 8 for level 4.0, or 31 for no level constraints.
+    pub level: u8,
+
+    /// Whether the operating point uses the High tier, rather than the Main tier.
+    pub high_tier: bool,
+}
+
+/// The `color_config` of an AV1 sequence header, see the AV1 specification section 5.5.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Av1ColorConfig {
+    /// Bits per sample: 8, 10 or 12.
+    pub bit_depth: u8,
+    pub monochrome: bool,
+
+    /// Whether the chroma planes have half the width of the luma plane.
+    pub subsampling_x: bool,
+
+    /// Whether the chroma planes have half the height of the luma plane.
+    pub subsampling_y: bool,
+
+    /// Like 1 for BT.709 or 9 for BT.2020, or 2 if unspecified.
+    pub color_primaries: u8,
+
+    /// Like 1 for BT.709, 16 for PQ or 18 for HLG, or 2 if unspecified.
+    pub transfer_characteristics: u8,
+
+    /// Like 1 for BT.709 or 0 for RGB, or 2 if unspecified.
+    pub matrix_coefficients: u8,
+
+    /// Whether the samples use the full range of values, rather than the limited "TV" range.
+    pub full_range: bool,
+
+    /// Where the chroma samples of 4:2:0 video are: 0 if unknown, 1 for vertically between
+    /// two luma samples and 2 for at the top-left luma sample.
+    pub chroma_sample_position: u8,
+}
+
+impl Av1SequenceHeader {
+    /// Parses the payload of a sequence header OBU, without its OBU header.
+    pub fn parse(payload: &[u8]) -> Result<Self> {
+        let mut bits = BitReader::new(payload, "AV1 sequence header is too short");
+
+        let profile = bits.read(3)? as u8;
+        let still_picture = bits.read_bit()?;
+        let reduced_still_picture_header = bits.read_bit()?;
+        let mut operating_points = Vec::new();
+        if reduced_still_picture_header {
+            operating_points.push(Av1OperatingPoint {
+                idc: 0,
+                level: bits.read(5)? as u8,
+                high_tier: false,
+            });
+        } else {
+            let mut decoder_model_info_present = false;
+            let mut buffer_delay_length = 0;
+            if bits.read_bit()? {
+                // timing_info_present_flag
+                bits.skip(64)?; // num_units_in_display_tick, time_scale
+                if bits.read_bit()? {
+                    // equal_picture_interval, followed by num_ticks_per_picture_minus_1 as uvlc()
+                    let mut leading_zeros = 0;
+                    while !bits.read_bit()? {
+                        leading_zeros += 1;
+                    }
+                    if leading_zeros < 32 {
+                        bits.skip(leading_zeros)?;
+                    }
+                }
+                decoder_model_info_present = bits.read_bit()?;
+                if decoder_model_info_present {
+                    buffer_delay_length = bits.read(5)? as usize + 1;
+                    bits.skip(32 + 5 + 5)?; // decoding tick, removal and presentation time lengths
+                }
+            }
+            let initial_display_delay_present = bits.read_bit()?;
+            let count = bits.read(5)? + 1;
+            for _ in 0..count {
+                let idc = bits.read(12)? as u16;
+                let level = bits.read(5)? as u8;
+                let high_tier = level > 7 && bits.read_bit()?;
+                if decoder_model_info_present && bits.read_bit()? {
+                    // decoder_model_present_for_this_op
+                    bits.skip(2 * buffer_delay_length + 1)?; // operating_parameters_info
+                }
+                if initial_display_delay_present && bits.read_bit()? {
+                    // initial_display_delay_present_for_this_op
+                    bits.skip(4)?; // initial_display_delay_minus_1
+                }
+                operating_points.push(Av1OperatingPoint {
+                    idc,
+                    level,
+                    high_tier,
+                });
+            }
+        }
+
+        let frame_width_bits = bits.read(4)? as usize + 1;
+        let frame_height_bits = bits.read(4)? as usize + 1;
+        let max_frame_width = bits.read(frame_width_bits)? + 1;
+        let max_frame_height = bits.read(frame_height_bits)? + 1;
+        if !reduced_still_picture_header && bits.read_bit()? {
+            // frame_id_numbers_present_flag
+            bits.skip(4 + 3)?; // delta_frame_id_length_minus_2, additional_frame_id_length_minus_1
+        }
+        bits.skip(3)?; // use_128x128_superblock, enable_filter_intra, enable_intra_edge_filter
+        if !reduced_still_picture_header {
+            // enable_interintra_compound, enable_masked_compound, enable_warped_motion and
+            // enable_dual_filter
+            bits.skip(4)?;
+            let enable_order_hint = bits.read_bit()?;
+            if enable_order_hint {
+                bits.skip(2)?; // enable_jnt_comp, enable_ref_frame_mvs
+            }
+            let seq_force_screen_content_tools = if bits.read_bit()? {
+                // seq_choose_screen_content_tools
+                2
+            } else {
+                bits.read(1)?
+            };
+            if seq_force_screen_content_tools > 0 && !bits.read_bit()? {
+                // seq_choose_integer_mv
+                bits.skip(1)?; // seq_force_integer_mv
+            }
+            if enable_order_hint {
+                bits.skip(3)?; // order_hint_bits_minus_1
+            }
+        }
+        bits.skip(3)?; // enable_superres, enable_cdef, enable_restoration
+
+        let color = Av1ColorConfig::parse(&mut bits, profile)?;
+        let film_grain_params_present = bits.read_bit()?;
+
+        Ok(Self {
+            profile,
+            still_picture,
+            reduced_still_picture_header,
+            operating_points,
+            max_frame_width,
+            max_frame_height,
+            color,
+            film_grain_params_present,
+        })
+    }
+}
+
+impl Av1ColorConfig {
+    fn parse(bits: &mut BitReader<'_>, profile: u8) -> Result<Self> {
+        let high_bitdepth = bits.read_bit()?;
+        let twelve_bit = profile == 2 && high_bitdepth && bits.read_bit()?;
+        let bit_depth = match (twelve_bit, high_bitdepth) {
+            (true, _) => 12,
+            (false, true) => 10,
+            (false, false) => 8,
+        };
+        let monochrome = profile != 1 && bits.read_bit()?;
+        let (color_primaries, transfer_characteristics, matrix_coefficients) = if bits.read_bit()? {
+            // color_description_present_flag
+            (
+                bits.read(8)? as u8,
+                bits.read(8)? as u8,
+                bits.read(8)? as u8,
+            )
+        } else {
+            (2, 2, 2)
+        };
+        let mut config = Self {
+            bit_depth,
+            monochrome,
+            subsampling_x: true,
+            subsampling_y: true,
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            full_range: false,
+            chroma_sample_position: 0,
+        };
+
+        if monochrome {
+            config.full_range = bits.read_bit()?;
+            return Ok(config);
+        }
+        if (
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+        ) == (1, 13, 0)
+        {
+            // sRGB, which is always full range 4:4:4.
+            config.full_range = true;
+            config.subsampling_x = false;
+            config.subsampling_y = false;
+        } else {
+            config.full_range = bits.read_bit()?;
+            (config.subsampling_x, config.subsampling_y) = match profile {
+                0 => (true, true),
+                1 => (false, false),
+                _ if twelve_bit => {
+                    let subsampling_x = bits.read_bit()?;
+                    (subsampling_x, subsampling_x && bits.read_bit()?)
+                }
+                _ => (true, false),
+            };
+            if config.subsampling_x && config.subsampling_y {
+                config.chroma_sample_position = bits.read(2)? as u8;
+            }
+        }
+        bits.skip(1)?; // separate_uv_delta_q
+        Ok(config)
+    }
+}
+
+impl Av1CBox {
+    /// The sequence header among [`Self::config_obus`], or `None` if there is none.
+    pub fn sequence_header(&self) -> Result<Option<Av1SequenceHeader>> {
+        sequence_header_obu(&self.config_obus)?
+            .map(Av1SequenceHeader::parse)
+            .transpose()
+    }
+}
+
+/// The payload of the first sequence header OBU among `obus`, see the AV1 specification
+/// section 5.3.
+pub(crate) fn sequence_header_obu(mut obus: &[u8]) -> Result<Option<&[u8]>> {
+    const TOO_SHORT: Error = Error::InvalidData("AV1 OBU is too short");
+    while let Some((&header, rest)) = obus.split_first() {
+        let obu_type = (header >> 3) & 0xf;
+        let mut rest = if header & 0x04 != 0 {
+            rest.get(1..).ok_or(TOO_SHORT)? // obu_extension_header
+        } else {
+            rest
+        };
+        let size = if header & 0x02 != 0 {
+            // obu_has_size_field
+            let mut size = 0_u64;
+            let mut length = 0;
+            loop {
+                let byte = *rest.get(length).ok_or(TOO_SHORT)?;
+                size |= u64::from(byte & 0x7f) << (7 * length);
+                length += 1;
+                if byte & 0x80 == 0 || length == 8 {
+                    break;
+                }
+            }
+            rest = &rest[length..];
+            usize::try_from(size).map_err(|_err| TOO_SHORT)?
+        } else {
+            rest.len()
+        };
+        let payload = rest.get(..size).ok_or(TOO_SHORT)?;
+        if obu_type == OBU_SEQUENCE_HEADER {
+            return Ok(Some(payload));
+        }
+        obus = &rest[size..];
+    }
+    Ok(None)
+}
@@ -0,0 +1,151 @@
+//! Prints the structure of an MP4 file: its boxes, tracks, and optionally their sample tables.
+//!
+//! ```text
+//! mp4dump [--json] [--samples] <file>
+//! ```
+
+use std::process::ExitCode;
+
+use re_mp4::{BoxNode, JsonOptions, Mp4, Mp4Box as _, Track};
+
+const USAGE: &str = "Usage: mp4dump [--json] [--samples] <file>
+
+Prints the box tree and a summary of every track of an MP4 file.
+
+Options:
+  --json     Print JSON instead of text
+  --samples  Include the sample table of every track";
+
+struct Args {
+    path: String,
+    json: bool,
+    samples: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut path = None;
+    let mut json = false;
+    let mut samples = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--samples" => samples = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ if path.is_some() => return Err("expected a single file".to_owned()),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.ok_or_else(|| "missing file".to_owned())?;
+    Ok(Args {
+        path,
+        json,
+        samples,
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("error: {error}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mp4 = match Mp4::read_file(&args.path) {
+        Ok((mp4, _)) => mp4,
+        Err(error) => {
+            eprintln!("error: failed to read {}: {error}", args.path);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.json {
+        let options = JsonOptions {
+            sample_tables: args.samples,
+            samples: args.samples,
+            pretty: true,
+        };
+        println!("{}", mp4.to_json(options));
+    } else {
+        println!("boxes:");
+        for node in mp4.box_tree() {
+            print_box(node, 1);
+        }
+        println!("tracks:");
+        for track in mp4.tracks().values() {
+            print_track(&mp4, track, args.samples);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn print_box(node: &BoxNode, depth: usize) {
+    let unknown = if node.known { "" } else { " (unknown)" };
+    println!(
+        "{:indent$}{} @{} size={}{unknown}",
+        "",
+        node.box_type,
+        node.offset,
+        node.size,
+        indent = depth * 2
+    );
+    for child in &node.children {
+        print_box(child, depth + 1);
+    }
+}
+
+fn print_track(mp4: &Mp4, track: &Track, samples: bool) {
+    let kind = track
+        .kind
+        .map_or_else(|| "unknown".to_owned(), |kind| format!("{kind:?}"));
+    println!("  track {}: {kind}", track.track_id);
+    if let Some(codec) = track.codec_string() {
+        println!("    codec: {codec}");
+    }
+    if let Some(Ok(summary)) = track
+        .trak(mp4)
+        .map(|trak| trak.mdia.minf.stbl.stsd.summary())
+    {
+        println!("    sample entry: {summary}");
+    }
+    if track.width != 0 || track.height != 0 {
+        println!("    resolution: {}x{}", track.width, track.height);
+    }
+    println!("    timescale: {}", track.timescale);
+    println!(
+        "    duration: {} ({:.3} s)",
+        track.duration,
+        track.duration as f64 / track.timescale.max(1) as f64
+    );
+    println!("    samples: {}", track.samples.len());
+    if let Some(frame_rate) = track.frame_rate() {
+        println!("    frame rate: {frame_rate:.3}");
+    }
+    if let Some(audio) = track.audio_info(mp4) {
+        println!(
+            "    audio: {} Hz, {} channels",
+            audio.sample_rate, audio.channel_count
+        );
+    }
+
+    if samples {
+        println!("    index      offset       size        dts        cts   duration  sync");
+        for (index, sample) in track.samples.iter().enumerate() {
+            println!(
+                "    {index:>5} {:>11} {:>10} {:>10} {:>10} {:>10}  {}",
+                sample.offset,
+                sample.size,
+                sample.decode_timestamp,
+                sample.composition_timestamp,
+                sample.duration,
+                if sample.is_sync { "yes" } else { "no" },
+            );
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Reading the bit fields of codec configurations and bitstream headers.
+
+use crate::{Error, Result};
+
+/// Reads big-endian bit fields, failing with `error` when running past the end of `bytes`.
+pub(crate) struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    error: &'static str,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8], error: &'static str) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            error,
+        }
+    }
+
+    /// Reads an unsigned number of `count` bits, at most 32.
+    pub fn read(&mut self, count: usize) -> Result<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | u32::from(self.read_bit()?);
+        }
+        Ok(value)
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or(Error::InvalidData(self.error))?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit == 1)
+    }
+
+    pub fn skip(&mut self, count: usize) -> Result<()> {
+        self.position += count;
+        if self.position > self.bytes.len() * 8 {
+            return Err(Error::InvalidData(self.error));
+        }
+        Ok(())
+    }
+
+    /// Reads an unsigned Exp-Golomb code, `ue(v)` in the AVC and HEVC specifications.
+    pub fn read_ue(&mut self) -> Result<u32> {
+        let mut leading_zeros = 0;
+        while !self.read_bit()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return Err(Error::InvalidData(self.error));
+            }
+        }
+        Ok(((1u64 << leading_zeros) - 1 + u64::from(self.read(leading_zeros)?)) as u32)
+    }
+
+    /// Reads a signed Exp-Golomb code, `se(v)` in the AVC and HEVC specifications.
+    pub fn read_se(&mut self) -> Result<i32> {
+        let code = i64::from(self.read_ue()?);
+        Ok(if code % 2 == 1 {
+            (code + 1) / 2
+        } else {
+            -code / 2
+        } as i32)
+    }
+}
+
+/// The payload of a NAL unit, without the emulation prevention bytes.
+pub(crate) fn rbsp(payload: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(payload.len());
+    let mut zeros = 0;
+    for &byte in payload {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    rbsp
+}
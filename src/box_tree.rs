@@ -0,0 +1,189 @@
+//! The hierarchy of boxes in a file, whether this crate understands them or not.
+//!
+//! This is what tools like mp4box.js dump, and is enough to build an inspector on top of
+//! without parsing the file again.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+
+use crate::mp4box::meta;
+use crate::{BoxType, Error, FourCC, Result};
+
+/// A box in the file, see [`crate::Mp4::box_tree`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoxNode {
+    pub box_type: FourCC,
+
+    /// Offset of the start of the box header in the file.
+    pub offset: u64,
+
+    /// Size of the whole box, including its header.
+    pub size: u64,
+
+    /// Size of the header: 8 bytes, plus 8 for a 64-bit size and 16 for the user type of a
+    /// `uuid` box.
+    pub header_size: u64,
+
+    /// Whether this crate knows the box type, as opposed to skipping it.
+    pub known: bool,
+
+    /// The boxes nested in this one, empty for boxes that only contain data.
+    pub children: Vec<Self>,
+}
+
+impl BoxNode {
+    /// Byte range of the contents of the box in the file, after the header.
+    pub fn data_range(&self) -> Range<u64> {
+        self.offset + self.header_size..self.offset + self.size
+    }
+
+    /// This box and all boxes nested in it, depth first.
+    pub fn descendants(&self) -> impl Iterator<Item = &Self> + '_ {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+/// Reads the headers of the boxes between `start` and `end`, and of everything nested in them.
+///
+/// Never fails on malformed boxes, it stops listing the boxes of a parent at the first one that
+/// doesn't fit in it instead. Only I/O errors are returned, and [`Error::LimitExceeded`] if boxes
+/// are nested deeper than `max_depth`.
+pub(crate) fn read_box_tree<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    max_depth: usize,
+) -> Result<Vec<BoxNode>> {
+    read_children(reader, None, start, end, 1, max_depth)
+}
+
+fn read_children<R: Read + Seek>(
+    reader: &mut R,
+    parent: Option<[u8; 4]>,
+    start: u64,
+    end: u64,
+    depth: usize,
+    max_depth: usize,
+) -> Result<Vec<BoxNode>> {
+    if depth > max_depth && start + 8 <= end {
+        return Err(Error::LimitExceeded("max_box_depth", max_depth as u64));
+    }
+    let mut nodes = Vec::new();
+    let mut offset = start;
+    while offset + 8 <= end {
+        reader.seek(SeekFrom::Start(offset))?;
+        let size = reader.read_u32::<BigEndian>()?;
+        let box_type = reader.read_u32::<BigEndian>()?.to_be_bytes();
+        let (size, mut header_size) = match size {
+            // The box extends to the end of its parent.
+            0 => (end - offset, 8),
+            1 => {
+                if offset + 16 > end {
+                    break;
+                }
+                (reader.read_u64::<BigEndian>()?, 16)
+            }
+            size => (u64::from(size), 8),
+        };
+        if &box_type == b"uuid" {
+            header_size += 16;
+        }
+        if size < header_size || size > end - offset {
+            break;
+        }
+
+        let mut node = BoxNode {
+            box_type: FourCC::from(box_type),
+            offset,
+            size,
+            header_size,
+            known: !matches!(
+                BoxType::from(u32::from_be_bytes(box_type)),
+                BoxType::UnknownBox(_)
+            ),
+            children: Vec::new(),
+        };
+        let data = node.data_range();
+        if let Some(children_offset) = children_offset(reader, parent, box_type, data.clone())? {
+            if children_offset <= data.end - data.start {
+                node.children = read_children(
+                    reader,
+                    Some(box_type),
+                    data.start + children_offset,
+                    data.end,
+                    depth + 1,
+                    max_depth,
+                )?;
+            }
+        }
+        nodes.push(node);
+        offset += size;
+    }
+    Ok(nodes)
+}
+
+/// Where the first child of a box starts, relative to its contents, if it has children.
+fn children_offset<R: Read + Seek>(
+    reader: &mut R,
+    parent: Option<[u8; 4]>,
+    box_type: [u8; 4],
+    data: Range<u64>,
+) -> Result<Option<u64>> {
+    // The items of `ilst` and the keys of `mebx` have arbitrary types, and contain boxes.
+    if parent == Some(*b"ilst") || parent == Some(*b"keys") {
+        return Ok(Some(0));
+    }
+
+    Ok(match &box_type {
+        b"moov" | b"trak" | b"edts" | b"mdia" | b"minf" | b"dinf" | b"stbl" | b"mvex" | b"moof"
+        | b"traf" | b"mfra" | b"udta" | b"ilst" | b"wave" | b"iprp" | b"ipco" | b"tref"
+        | b"gmhd" => Some(0),
+
+        // The entry count follows the version and flags.
+        b"stsd" | b"dref" => Some(8),
+
+        // `QuickTime` files leave out the version and flags, and start with the `hdlr` box.
+        b"meta" => {
+            if data.end - data.start < 8 {
+                return Ok(None);
+            }
+            reader.seek(SeekFrom::Start(data.start))?;
+            let mut contents = [0; 8];
+            reader.read_exact(&mut contents)?;
+            meta::children_offset(&contents)
+        }
+
+        // Sample entries, whose fields come before their children.
+        b"avc1" | b"avc3" | b"hvc1" | b"hev1" | b"dvh1" | b"dvhe" | b"av01" | b"vp08" | b"vp09"
+        | b"encv"
+            if parent == Some(*b"stsd") =>
+        {
+            Some(78)
+        }
+        b"mp4a" | b"enca" | b"alac" | b"Opus" | b"fLaC" | b"ac-3" | b"ec-3"
+            if parent == Some(*b"stsd") =>
+        {
+            if data.end - data.start < 28 {
+                return Ok(None);
+            }
+            // Version 1 of the `QuickTime` sound sample description has 16 more bytes.
+            reader.seek(SeekFrom::Start(data.start + 8))?;
+            let version = reader.read_u16::<BigEndian>()?;
+            Some(if version == 1 { 44 } else { 28 })
+        }
+        b"tx3g" if parent == Some(*b"stsd") => Some(38),
+        b"wvtt" | b"mebx" if parent == Some(*b"stsd") => Some(8),
+        // The key table of `mebx`, whose entries are boxes named by the local ID of each key.
+        b"keys" if parent == Some(*b"mebx") => Some(0),
+
+        _ => None,
+    })
+}
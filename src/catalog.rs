@@ -0,0 +1,211 @@
+//! Summarizing all MP4 files in a directory, e.g. to index a dataset.
+//!
+//! [`scan`] walks a directory and [`probe`]s every file with an MP4 extension. Only the
+//! boxes are read, media data is skipped over, so probing a file costs about as much as
+//! reading its `moov`. A file that fails to parse doesn't stop the scan, its error is
+//! returned in place of its [`FileInfo`].
+
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FileKind, FourCC, Limits, Mp4, Result, TrackId, TrackKind};
+
+/// Options for [`scan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Whether to descend into subdirectories.
+    pub recursive: bool,
+
+    /// Extensions of the files to probe, compared without regard to case.
+    pub extensions: Vec<String>,
+
+    /// Limits that every file is parsed with, see [`Mp4::read_with_limits`].
+    pub limits: Limits,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            extensions: ["mp4", "m4v", "m4a", "mov"].map(String::from).to_vec(),
+            limits: Limits::UNLIMITED,
+        }
+    }
+}
+
+/// A summary of a file, see [`probe`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileInfo {
+    /// Size of the file in bytes.
+    pub size: u64,
+
+    pub kind: FileKind,
+    pub major_brand: FourCC,
+
+    /// Whether the file has movie fragments.
+    pub fragmented: bool,
+
+    /// Duration of the longest track in seconds.
+    pub duration: f64,
+
+    pub tracks: Vec<TrackInfo>,
+}
+
+/// A summary of a track of a file, see [`FileInfo`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackInfo {
+    pub track_id: TrackId,
+    pub kind: Option<TrackKind>,
+
+    /// The codec string of the track, like `avc1.64001f`, if it is known.
+    pub codec: Option<String>,
+
+    pub width: u16,
+    pub height: u16,
+    pub num_samples: usize,
+
+    /// Duration of the track in seconds.
+    pub duration: f64,
+
+    /// See [`crate::Track::frame_rate`].
+    pub frame_rate: Option<f64>,
+}
+
+impl FileInfo {
+    /// Summarizes `mp4`, a file of `size` bytes.
+    pub fn new(mp4: &Mp4, size: u64) -> Self {
+        let tracks: Vec<TrackInfo> = mp4
+            .tracks()
+            .values()
+            .map(|track| TrackInfo {
+                track_id: track.track_id,
+                kind: track.kind,
+                codec: track.codec_string(),
+                width: track.width,
+                height: track.height,
+                num_samples: track.samples.len(),
+                duration: if track.timescale == 0 {
+                    0.0
+                } else {
+                    track.duration as f64 / track.timescale as f64
+                },
+                frame_rate: track.frame_rate(),
+            })
+            .collect();
+
+        Self {
+            size,
+            kind: mp4.file_kind(),
+            major_brand: mp4.ftyp.major_brand,
+            fragmented: !mp4.moofs.is_empty(),
+            duration: tracks.iter().map(|t| t.duration).fold(0.0, f64::max),
+            tracks,
+        }
+    }
+}
+
+/// Parses the boxes of the file at `path` and summarizes it, without reading its media data.
+pub fn probe(path: impl AsRef<Path>, limits: Limits) -> Result<FileInfo> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mp4 = Mp4::read_with_limits(BufReader::new(file), size, limits)?;
+    Ok(FileInfo::new(&mp4, size))
+}
+
+/// Probes every file in `dir` whose extension is one of [`ScanOptions::extensions`].
+///
+/// Returns the results sorted by path, with an error in place of the [`FileInfo`] of every
+/// file that failed to parse, and of every subdirectory that couldn't be read. Only fails
+/// if `dir` itself can't be read. The files are probed in parallel, on the `rayon` thread pool
+/// with the `rayon` feature and on one thread per core otherwise.
+pub fn scan(
+    dir: impl AsRef<Path>,
+    options: &ScanOptions,
+) -> Result<Vec<(PathBuf, Result<FileInfo>)>> {
+    let mut paths = Vec::new();
+    let mut results = Vec::new();
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+    let mut is_root = true;
+    while let Some(dir) = dirs.pop() {
+        match list_dir(&dir) {
+            Ok(entries) => {
+                for (path, is_dir) in entries {
+                    if is_dir {
+                        if options.recursive {
+                            dirs.push(path);
+                        }
+                    } else if has_extension(&path, &options.extensions) {
+                        paths.push(path);
+                    }
+                }
+            }
+            Err(err) if is_root => return Err(err.into()),
+            Err(err) => results.push((dir, Err(err.into()))),
+        }
+        is_root = false;
+    }
+
+    let probe_path = |path: PathBuf| {
+        let info = probe(&path, options.limits);
+        (path, info)
+    };
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        results.par_extend(paths.into_par_iter().map(probe_path));
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Every thread takes the next path that no other thread has taken yet.
+        let next = AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(paths.len());
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut probed = Vec::new();
+                        while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            probed.push(probe_path(path.clone()));
+                        }
+                        probed
+                    })
+                })
+                .collect();
+            for worker in workers {
+                match worker.join() {
+                    Ok(probed) => results.extend(probed),
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            }
+        });
+    }
+
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(results)
+}
+
+/// The paths in `dir`, and whether they are directories.
+///
+/// Symbolic links to directories are not followed, so that cycles can't be walked forever.
+fn list_dir(dir: &Path) -> std::io::Result<Vec<(PathBuf, bool)>> {
+    std::fs::read_dir(dir)?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.path(), entry.file_type()?.is_dir()))
+        })
+        .collect()
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|e| e.eq_ignore_ascii_case(extension)))
+}
@@ -0,0 +1,75 @@
+//! Chapters, from a chapter track or a Nero chapter list (`chpl`).
+
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{tx3g, FourCC, Mp4};
+
+/// A chapter of a file, see [`Mp4::chapters`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Time from the start of the presentation at which the chapter starts.
+    pub start_time: Duration,
+    pub title: String,
+}
+
+/// Samples of chapter tracks larger than this are not titles, and the track is ignored.
+const MAX_TITLE_SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// The chapters of the chapter track that a track refers to with `tref/chap`, whose samples
+/// are read from `reader`.
+///
+/// Returns `None` if there is no chapter track, or its samples can't be read, such as when
+/// `reader` doesn't hold the media data.
+pub(crate) fn read_track_chapters<R: Read + Seek>(
+    mp4: &Mp4,
+    reader: &mut R,
+) -> Option<Vec<Chapter>> {
+    let chap = FourCC::from(*b"chap");
+    let track = mp4
+        .moov
+        .traks
+        .iter()
+        .filter_map(|trak| trak.tref.as_ref())
+        .flat_map(|tref| tref.track_ids(chap))
+        .find_map(|track_id| mp4.tracks().get(track_id))?;
+
+    let mut chapters = Vec::with_capacity(track.samples.len());
+    for sample in &track.samples {
+        if sample.size > MAX_TITLE_SAMPLE_SIZE {
+            return None;
+        }
+        let mut bytes = vec![0; sample.size as usize];
+        reader.seek(SeekFrom::Start(sample.offset)).ok()?;
+        reader.read_exact(&mut bytes).ok()?;
+
+        // Chapter tracks are QuickTime text or tx3g tracks, whose samples have the same layout.
+        let title = tx3g::decode_sample(&bytes)
+            .map(|sample| sample.text)
+            .unwrap_or_default();
+        let nanos = u128::from(sample.composition_timestamp.unsigned_abs()) * 1_000_000_000
+            / u128::from(sample.timescale.max(1));
+        chapters.push(Chapter {
+            start_time: Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX)),
+            title,
+        });
+    }
+    Some(chapters)
+}
+
+/// The chapters in the `chpl` box of `moov/udta`, if there is one.
+pub(crate) fn chpl_chapters(mp4: &Mp4) -> Vec<Chapter> {
+    let Some(chpl) = mp4.moov.udta.as_ref().and_then(|udta| udta.chpl.as_ref()) else {
+        return Vec::new();
+    };
+    chpl.chapters
+        .iter()
+        .map(|chapter| Chapter {
+            // In units of 100 nanoseconds.
+            start_time: Duration::from_nanos(chapter.start_time.saturating_mul(100)),
+            title: chapter.title.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,323 @@
+//! A typed summary of the codec of a track, see [`Track::codec`].
+
+use crate::{AudioObjectType, BtrtBox, FourCC, PcmFormat, SampleFreqIndex, StsdBoxContent, Track};
+
+/// The `objectTypeIndication` values of MPEG audio in `esds`, of which all but MP3 are AAC.
+pub(crate) const OBJECT_TYPES_AAC: [u8; 4] = [0x40, 0x66, 0x67, 0x68];
+pub(crate) const OBJECT_TYPES_MP3: [u8; 2] = [0x69, 0x6b];
+
+/// The AVC profiles whose `avcC` has the chroma format and bit depths after the parameter sets.
+const AVC_HIGH_PROFILES: [u8; 4] = [100, 110, 122, 144];
+
+/// The codec of a track with its main parameters, see [`Track::codec`].
+///
+/// The details of all video codecs have a `profile`, `level` and `bit_depth`, so that code that
+/// only cares about those doesn't need to know the configuration boxes of every codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    H264(AvcDetails),
+
+    /// H.265, including Dolby Vision on top of it (`dvh1` and `dvhe`).
+    H265(HevcDetails),
+
+    Av1(Av1Details),
+    Vp8(VpxDetails),
+    Vp9(VpxDetails),
+    Aac(AacDetails),
+
+    /// MPEG-1 or MPEG-2 Layer III audio, from `.mp3` or `mp4a` with object type 0x69 or 0x6B.
+    Mp3,
+
+    /// Dolby Digital (AC-3).
+    Ac3,
+
+    /// Dolby Digital Plus (E-AC-3).
+    Ec3,
+
+    /// Uncompressed audio, like `lpcm` or `sowt`.
+    Pcm(PcmFormat),
+
+    /// Any other codec, by the type of its sample entry.
+    Other(FourCC),
+}
+
+/// The parameters of an H.264 track, from `avcC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AvcDetails {
+    /// `profile_idc`, like 66 for Baseline, 77 for Main and 100 for High.
+    pub profile: u8,
+
+    /// The constraint flags of the SPS, like `constraint_set1_flag` for Constrained Baseline.
+    pub constraints: u8,
+
+    /// `level_idc`, which is ten times the level, like 31 for level 3.1.
+    pub level: u8,
+
+    /// Bits per luma sample, which is only signaled for the High profiles and else 8.
+    pub bit_depth: u8,
+
+    /// 0 for monochrome, 1 for 4:2:0, 2 for 4:2:2 and 3 for 4:4:4, which is only signaled for
+    /// the High profiles and else 1.
+    pub chroma_format: u8,
+
+    /// The bitrates and decoding buffer size that the `btrt` box of the sample entry declares.
+    pub bitrate: Option<BtrtBox>,
+}
+
+/// The parameters of an H.265 track, from `hvcC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HevcDetails {
+    /// `general_profile_idc`, like 1 for Main and 2 for Main 10.
+    pub profile: u8,
+
+    /// Whether the stream uses the High tier, rather than the Main tier.
+    pub high_tier: bool,
+
+    /// `general_level_idc`, which is thirty times the level, like 93 for level 3.1.
+    pub level: u8,
+
+    /// Bits per luma sample.
+    pub bit_depth: u8,
+
+    /// 0 for monochrome, 1 for 4:2:0, 2 for 4:2:2 and 3 for 4:4:4.
+    pub chroma_format: u8,
+
+    /// Whether the track is Dolby Vision (`dvh1` or `dvhe`), or has a Dolby Vision configuration.
+    pub dolby_vision: bool,
+
+    /// See [`AvcDetails::bitrate`].
+    pub bitrate: Option<BtrtBox>,
+}
+
+/// The parameters of an AV1 track, from `av1C`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Av1Details {
+    /// `seq_profile`: 0 for Main, 1 for High and 2 for Professional.
+    pub profile: u8,
+
+    /// `seq_level_idx_0`, like 8 for level 4.0.
+    pub level: u8,
+
+    /// Whether the stream uses the High tier, rather than the Main tier.
+    pub high_tier: bool,
+
+    /// Bits per sample: 8, 10 or 12.
+    pub bit_depth: u8,
+    pub monochrome: bool,
+    pub chroma_subsampling_x: bool,
+    pub chroma_subsampling_y: bool,
+
+    /// See [`AvcDetails::bitrate`].
+    pub bitrate: Option<BtrtBox>,
+}
+
+/// The parameters of a VP8 or VP9 track, from `vpcC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VpxDetails {
+    pub profile: u8,
+
+    /// The level, which is ten times the level number, like 31 for level 3.1.
+    pub level: u8,
+
+    /// Bits per sample: 8, 10 or 12.
+    pub bit_depth: u8,
+
+    /// 0 and 1 for 4:2:0 with different chroma positions, 2 for 4:2:2 and 3 for 4:4:4.
+    pub chroma_subsampling: u8,
+
+    /// See [`AvcDetails::bitrate`].
+    pub bitrate: Option<BtrtBox>,
+}
+
+/// The parameters of an AAC track, from the decoder specific info in `esds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AacDetails {
+    /// The audio object type, or `None` if it is not one that this crate knows.
+    pub object_type: Option<AudioObjectType>,
+
+    /// Samples per second, or `None` if it is not one of the standard sample rates.
+    pub sample_rate: Option<u32>,
+
+    /// The channel configuration, like 2 for stereo, or 0 if it is signaled in the stream.
+    pub channel_config: u8,
+
+    /// Like [`AvcDetails::bitrate`], or else the bitrates of the decoder configuration in `esds`,
+    /// if it declares any.
+    pub bitrate: Option<BtrtBox>,
+}
+
+impl Codec {
+    /// The codec of a sample entry.
+    pub fn from_sample_entry(entry: &StsdBoxContent) -> Self {
+        match entry {
+            StsdBoxContent::Avc1(avc1) => {
+                let avcc = &avc1.avcc.contents;
+                let high_profile = AVC_HIGH_PROFILES.contains(&avcc.avc_profile_indication);
+                let (chroma_format, bit_depth) = match avcc.ext.get(..2) {
+                    Some(&[chroma_format, bit_depth_luma]) if high_profile => {
+                        (chroma_format & 0x03, (bit_depth_luma & 0x07) + 8)
+                    }
+                    _ => (1, 8),
+                };
+                Self::H264(AvcDetails {
+                    profile: avcc.avc_profile_indication,
+                    constraints: avcc.profile_compatibility,
+                    level: avcc.avc_level_indication,
+                    bit_depth,
+                    chroma_format,
+                    bitrate: avc1.btrt,
+                })
+            }
+            StsdBoxContent::Hvc1(hevc)
+            | StsdBoxContent::Hev1(hevc)
+            | StsdBoxContent::Dvh1(hevc)
+            | StsdBoxContent::Dvhe(hevc) => {
+                let hvcc = &hevc.hvcc.contents;
+                Self::H265(HevcDetails {
+                    profile: hvcc.general_profile_idc,
+                    high_tier: hvcc.general_tier_flag,
+                    level: hvcc.general_level_idc,
+                    bit_depth: (hvcc.bit_depth_luma_minus8 & 0x07) + 8,
+                    chroma_format: hvcc.chroma_format_idc & 0x03,
+                    dolby_vision: hevc.dovi.is_some()
+                        || matches!(entry, StsdBoxContent::Dvh1(_) | StsdBoxContent::Dvhe(_)),
+                    bitrate: hevc.btrt,
+                })
+            }
+            StsdBoxContent::Av01(av01) => {
+                let av1c = &av01.av1c.contents;
+                Self::Av1(Av1Details {
+                    profile: av1c.profile,
+                    level: av1c.level,
+                    high_tier: av1c.tier != 0,
+                    bit_depth: av1c.bit_depth,
+                    monochrome: av1c.monochrome,
+                    chroma_subsampling_x: av1c.chroma_subsampling_x != 0,
+                    chroma_subsampling_y: av1c.chroma_subsampling_y != 0,
+                    bitrate: av01.btrt,
+                })
+            }
+            StsdBoxContent::Vp08(vp08) => {
+                Self::Vp8(VpxDetails::new(&vp08.vpcc.contents, vp08.btrt))
+            }
+            StsdBoxContent::Vp09(vp09) => {
+                Self::Vp9(VpxDetails::new(&vp09.vpcc.contents, vp09.btrt))
+            }
+            StsdBoxContent::Mp4a(mp4a) => {
+                let Some(dec_config) = mp4a.esds.as_ref().map(|esds| &esds.es_desc.dec_config)
+                else {
+                    return Self::Other(entry.fourcc());
+                };
+                let dec_specific = &dec_config.dec_specific;
+                if OBJECT_TYPES_MP3.contains(&dec_config.object_type_indication) {
+                    Self::Mp3
+                } else if OBJECT_TYPES_AAC.contains(&dec_config.object_type_indication) {
+                    Self::Aac(AacDetails {
+                        object_type: AudioObjectType::try_from(dec_specific.profile).ok(),
+                        sample_rate: SampleFreqIndex::try_from(dec_specific.freq_index)
+                            .ok()
+                            .map(|freq| freq.freq()),
+                        channel_config: dec_specific.chan_conf,
+                        bitrate: mp4a.btrt.or_else(|| {
+                            let bitrate = BtrtBox {
+                                buffer_size_db: dec_config.buffer_size_db,
+                                max_bitrate: dec_config.max_bitrate,
+                                avg_bitrate: dec_config.avg_bitrate,
+                            };
+                            (bitrate != BtrtBox::default()).then_some(bitrate)
+                        }),
+                    })
+                } else {
+                    Self::Other(entry.fourcc())
+                }
+            }
+            StsdBoxContent::Mp3(_) => Self::Mp3,
+            StsdBoxContent::Ac3(_) => Self::Ac3,
+            StsdBoxContent::Ec3(_) => Self::Ec3,
+            StsdBoxContent::Pcm(pcm) => Self::Pcm(pcm.format()),
+            StsdBoxContent::Tx3g(_)
+            | StsdBoxContent::Wvtt(_)
+            | StsdBoxContent::Stpp(_)
+            | StsdBoxContent::Mett(_)
+            | StsdBoxContent::Urim(_)
+            | StsdBoxContent::Gpmd(_)
+            | StsdBoxContent::Mebx(_)
+            | StsdBoxContent::Unknown(_) => Self::Other(entry.fourcc()),
+        }
+    }
+
+    /// The profile of a video codec, see the details of each codec for what it means.
+    pub fn profile(&self) -> Option<u8> {
+        match self {
+            Self::H264(details) => Some(details.profile),
+            Self::H265(details) => Some(details.profile),
+            Self::Av1(details) => Some(details.profile),
+            Self::Vp8(details) | Self::Vp9(details) => Some(details.profile),
+            Self::Aac(_) | Self::Mp3 | Self::Ac3 | Self::Ec3 | Self::Pcm(_) | Self::Other(_) => {
+                None
+            }
+        }
+    }
+
+    /// The level of a video codec, see the details of each codec for what it means.
+    pub fn level(&self) -> Option<u8> {
+        match self {
+            Self::H264(details) => Some(details.level),
+            Self::H265(details) => Some(details.level),
+            Self::Av1(details) => Some(details.level),
+            Self::Vp8(details) | Self::Vp9(details) => Some(details.level),
+            Self::Aac(_) | Self::Mp3 | Self::Ac3 | Self::Ec3 | Self::Pcm(_) | Self::Other(_) => {
+                None
+            }
+        }
+    }
+
+    /// The declared bitrates of a video or AAC track, see the details of each codec.
+    pub fn bitrate(&self) -> Option<BtrtBox> {
+        match self {
+            Self::H264(details) => details.bitrate,
+            Self::H265(details) => details.bitrate,
+            Self::Av1(details) => details.bitrate,
+            Self::Vp8(details) | Self::Vp9(details) => details.bitrate,
+            Self::Aac(details) => details.bitrate,
+            Self::Mp3 | Self::Ac3 | Self::Ec3 | Self::Pcm(_) | Self::Other(_) => None,
+        }
+    }
+
+    /// Bits per luma sample of a video codec.
+    pub fn bit_depth(&self) -> Option<u8> {
+        match self {
+            Self::H264(details) => Some(details.bit_depth),
+            Self::H265(details) => Some(details.bit_depth),
+            Self::Av1(details) => Some(details.bit_depth),
+            Self::Vp8(details) | Self::Vp9(details) => Some(details.bit_depth),
+            Self::Aac(_) | Self::Mp3 | Self::Ac3 | Self::Ec3 | Self::Pcm(_) | Self::Other(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl VpxDetails {
+    fn new(vpcc: &crate::VpccBox, bitrate: Option<BtrtBox>) -> Self {
+        Self {
+            profile: vpcc.profile,
+            level: vpcc.level,
+            bit_depth: vpcc.bit_depth,
+            chroma_subsampling: vpcc.chroma_subsampling,
+            bitrate,
+        }
+    }
+}
+
+impl Track {
+    /// The codec of this track with its main parameters, like the profile and level of video.
+    pub fn codec(&self) -> Codec {
+        Codec::from_sample_entry(self.sample_entry())
+    }
+}
@@ -0,0 +1,146 @@
+//! RFC 6381 codec strings, like `avc1.64001F`, built from decoder configuration records.
+//!
+//! [`crate::StsdBoxContent::codec_string`] uses these for parsed sample entries, while the
+//! `codec_string_from_*` functions take the raw record without its box header, as found in the
+//! payload of the configuration box or sent out of band in an RTP session description.
+
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+use crate::hevc::HevcDecoderConfigurationRecord;
+use crate::mp4box::{Av1CBox, DoviConfigBox, ReadBox as _, StppBox, HEADER_SIZE};
+use crate::{Error, Result};
+
+/// Builds the codec string of an `AVCDecoderConfigurationRecord`, the payload of an `avcC` box.
+pub fn codec_string_from_avcc(avcc: &[u8]) -> Result<String> {
+    let [configuration_version, profile, constraint, level, ..] = *avcc else {
+        return Err(Error::InvalidData("avcC record is too short"));
+    };
+    if configuration_version != 1 {
+        return Err(Error::InvalidData("unsupported avcC configuration version"));
+    }
+    Ok(avc(profile, constraint, level))
+}
+
+/// Builds the codec string of an `HEVCDecoderConfigurationRecord`, the payload of an `hvcC` box.
+///
+/// The string starts with `hvc1`. Streams that carry their parameter sets in band are `hev1`
+/// instead, with the rest of the string unchanged.
+pub fn codec_string_from_hvcc(hvcc: &[u8]) -> Result<String> {
+    let record =
+        HevcDecoderConfigurationRecord::read_box(&mut Cursor::new(hvcc), hvcc.len() as u64)?;
+    Ok(hevc("hvc1", &record))
+}
+
+/// Builds the codec string of an `AV1CodecConfigurationRecord`, the payload of an `av1C` box.
+pub fn codec_string_from_av1c(av1c: &[u8]) -> Result<String> {
+    let record = Av1CBox::read_box(&mut Cursor::new(av1c), HEADER_SIZE + av1c.len() as u64)?;
+    Ok(av1(&record))
+}
+
+/// Builds the codec string of a `VPCodecConfigurationRecord`, the payload of a `vpcC` box
+/// including its version and flags.
+pub fn codec_string_from_vpcc(vpcc: &[u8]) -> Result<String> {
+    let [_version, _, _, _, profile, level, bit_depth, ..] = *vpcc else {
+        return Err(Error::InvalidData("vpcC record is too short"));
+    };
+    Ok(vp9(profile, level, bit_depth >> 4))
+}
+
+pub(crate) fn avc(profile: u8, constraint: u8, level: u8) -> String {
+    format!("avc1.{profile:02X}{constraint:02X}{level:02X}")
+}
+
+// https://aomediacodec.github.io/av1-isobmff/#codecsparam
+pub(crate) fn av1(av1c: &Av1CBox) -> String {
+    let profile = av1c.profile;
+    let level = av1c.level;
+    let tier = if av1c.tier == 0 { "M" } else { "H" };
+    let bit_depth = av1c.bit_depth;
+
+    format!("av01.{profile}.{level:02}{tier}.{bit_depth:02}")
+}
+
+pub(crate) fn vp9(profile: u8, level: u8, bit_depth: u8) -> String {
+    format!("vp09.{profile:02}.{level:02}.{bit_depth:02}")
+}
+
+/// Codes of the TTML profiles, keyed by the URIs that designate them, see
+/// <https://www.w3.org/TR/ttml-profile-registry/>.
+const TTML_PROFILES: [(&str, &str); 5] = [
+    ("http://www.w3.org/ns/ttml/profile/imsc1/text", "im1t"),
+    ("http://www.w3.org/ns/ttml/profile/imsc1/image", "im1i"),
+    ("http://www.w3.org/ns/ttml/profile/imsc1.1/text", "im2t"),
+    ("http://www.w3.org/ns/ttml/profile/imsc1.1/image", "im2i"),
+    ("urn:ebu:tt:distribution:2014-01", "etd1"),
+];
+
+/// `stpp.ttml.` followed by the TTML profiles named in the namespaces or schema locations of
+/// the sample entry, like `stpp.ttml.im1t`, or just `stpp` if there are none.
+pub(crate) fn stpp(stpp: &StppBox) -> String {
+    let profiles: Vec<&str> = stpp
+        .namespace
+        .split_whitespace()
+        .chain(stpp.schema_location.split_whitespace())
+        .filter_map(|uri| {
+            TTML_PROFILES
+                .iter()
+                .find(|(designator, _)| *designator == uri)
+                .map(|(_, code)| *code)
+        })
+        .collect();
+    if profiles.is_empty() {
+        String::from("stpp")
+    } else {
+        format!("stpp.ttml.{}", profiles.join("+"))
+    }
+}
+
+/// Dolby Vision codecs use the profile and level of the `dvcC` or `dvvC` box, like `dvh1.05.06`.
+pub(crate) fn dolby_vision(fourcc: &str, dovi: &DoviConfigBox) -> String {
+    format!("{fourcc}.{:02}.{:02}", dovi.profile, dovi.level)
+}
+
+// ISO/IEC 14496-15 Annex E, like `hvc1.2.4.H120.B0` for Main 10 at High tier level 4.
+pub(crate) fn hevc(fourcc: &str, hvcc: &HevcDecoderConfigurationRecord) -> String {
+    let mut codec = String::from(fourcc);
+    match hvcc.general_profile_space {
+        1 => codec.push_str(".A"),
+        2 => codec.push_str(".B"),
+        3 => codec.push_str(".C"),
+        _ => {}
+    }
+    write!(&mut codec, ".{}", hvcc.general_profile_idc).ok();
+
+    let mut val = hvcc.general_profile_compatibility_flags;
+    let mut reversed = 0;
+    for i in 0..32 {
+        reversed |= val & 1;
+        if i == 31 {
+            break;
+        }
+        reversed <<= 1;
+        val >>= 1;
+    }
+    write!(&mut codec, ".{reversed:X}").ok();
+
+    if hvcc.general_tier_flag {
+        codec.push_str(".H");
+    } else {
+        codec.push_str(".L");
+    }
+    write!(&mut codec, "{}", hvcc.general_level_idc).ok();
+
+    // The constraint bytes in stream order, starting with the one that has
+    // `general_progressive_source_flag`, without the trailing zero bytes.
+    let constraint = &hvcc.general_constraint_indicator_flag.to_be_bytes()[2..];
+    let len = constraint
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+    for byte in &constraint[..len] {
+        write!(&mut codec, ".{byte:X}").ok();
+    }
+
+    codec
+}
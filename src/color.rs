@@ -0,0 +1,573 @@
+//! Comparing the color signaling of the container with that of the video bitstream.
+//!
+//! Some players take the colors from the sample entry and others from the bitstream, so when
+//! the two disagree, the same file looks different depending on where it is played. Washed out
+//! or oversaturated colors are the usual symptom.
+
+use crate::av1::{sequence_header_obu, Av1SequenceHeader};
+use crate::bits::{rbsp, BitReader};
+use crate::mp4box::vpcc::vp9_color_space_matrix;
+use crate::sync_samples::nal_units;
+use crate::{ColrBox, Error, Result, StsdBoxContent, VpccBox};
+
+/// How the colors of a video are signaled, as code points of ITU-T H.273.
+///
+/// Fields are `None` where they are not signaled. The unspecified code point 2 of the colour
+/// primaries, transfer characteristics and matrix coefficients counts as not signaled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorSignaling {
+    /// Like 1 for BT.709 or 9 for BT.2020.
+    pub color_primaries: Option<u16>,
+
+    /// Like 1 for BT.709, 16 for PQ or 18 for HLG.
+    pub transfer_characteristics: Option<u16>,
+
+    /// Like 1 for BT.709, 6 for BT.601 or 9 for BT.2020 with non-constant luminance.
+    pub matrix_coefficients: Option<u16>,
+
+    /// Whether the samples use the full range of values, rather than the limited "TV" range.
+    pub full_range: Option<bool>,
+
+    /// Where the chroma samples are relative to the luma samples in 4:2:0 video, as a
+    /// `ChromaSampleLocType`: 0 for left, like in MPEG-2, or 2 for top-left.
+    pub chroma_location: Option<u8>,
+}
+
+/// A field on which the container and the bitstream disagree, see [`ColorReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMismatch {
+    ColorPrimaries { container: u16, bitstream: u16 },
+    TransferCharacteristics { container: u16, bitstream: u16 },
+    MatrixCoefficients { container: u16, bitstream: u16 },
+    FullRange { container: bool, bitstream: bool },
+    ChromaLocation { container: u8, bitstream: u8 },
+}
+
+/// The color signaling of the sample entry of a video track next to that of its bitstream,
+/// see [`crate::Track::color_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorReport {
+    /// From the `colr` box of the sample entry, which takes precedence, and the decoder
+    /// configuration: `vpcC` for VP9, and the chroma sample position of `av1C` for AV1.
+    pub container: ColorSignaling,
+
+    /// From the sequence parameter set of AVC and HEVC, the sequence header of AV1 or the
+    /// frame header of VP9.
+    ///
+    /// AVC and HEVC bitstreams without a video signal type are limited range, as decoders
+    /// infer it.
+    pub bitstream: ColorSignaling,
+
+    /// The fields that are signaled by both and differ.
+    ///
+    /// Code points that stand for the same thing are treated as equal, like the matrix
+    /// coefficients 5 and 6, which both stand for BT.601.
+    pub mismatches: Vec<ColorMismatch>,
+}
+
+impl ColorReport {
+    /// Whether the container and the bitstream agree on everything that both signal.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// See [`crate::Track::color_report`].
+pub(crate) fn color_report(
+    entry: &StsdBoxContent,
+    sync_sample: Option<&[u8]>,
+) -> Result<Option<ColorReport>> {
+    let Some(container) = container_signaling(entry) else {
+        return Ok(None);
+    };
+    let bitstream = bitstream_signaling(entry, sync_sample.unwrap_or_default())?;
+    Ok(Some(ColorReport {
+        container,
+        bitstream,
+        mismatches: mismatches(&container, &bitstream),
+    }))
+}
+
+fn container_signaling(entry: &StsdBoxContent) -> Option<ColorSignaling> {
+    let (colr, mut signaling) = match entry {
+        StsdBoxContent::Avc1(entry) => (entry.colr.as_ref(), ColorSignaling::default()),
+        StsdBoxContent::Hvc1(entry)
+        | StsdBoxContent::Hev1(entry)
+        | StsdBoxContent::Dvh1(entry)
+        | StsdBoxContent::Dvhe(entry) => (entry.colr.as_ref(), ColorSignaling::default()),
+        StsdBoxContent::Av01(entry) => (
+            entry.colr.as_ref(),
+            ColorSignaling {
+                chroma_location: av1_chroma_location(entry.av1c.chroma_sample_position),
+                ..Default::default()
+            },
+        ),
+        StsdBoxContent::Vp09(entry) => (entry.colr.as_ref(), vpcc_signaling(&entry.vpcc)),
+        _ => return None,
+    };
+
+    match colr {
+        Some(ColrBox::Nclx {
+            colour_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            full_range,
+        }) => {
+            signaling.color_primaries = specified(*colour_primaries);
+            signaling.transfer_characteristics = specified(*transfer_characteristics);
+            signaling.matrix_coefficients = specified(*matrix_coefficients);
+            signaling.full_range = Some(*full_range);
+        }
+        Some(ColrBox::Nclc {
+            colour_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+        }) => {
+            signaling.color_primaries = specified(*colour_primaries);
+            signaling.transfer_characteristics = specified(*transfer_characteristics);
+            signaling.matrix_coefficients = specified(*matrix_coefficients);
+        }
+        Some(ColrBox::Icc { .. } | ColrBox::Unknown { .. }) | None => {}
+    }
+    Some(signaling)
+}
+
+fn vpcc_signaling(vpcc: &VpccBox) -> ColorSignaling {
+    ColorSignaling {
+        color_primaries: specified(vpcc.color_primaries.into()),
+        transfer_characteristics: specified(vpcc.transfer_characteristics.into()),
+        matrix_coefficients: specified(vpcc.matrix_coefficients.into()),
+        full_range: Some(vpcc.video_full_range_flag),
+        chroma_location: match vpcc.chroma_subsampling {
+            0 => Some(0), // 4:2:0 vertical
+            1 => Some(2), // 4:2:0 colocated with luma (0, 0)
+            _ => None,
+        },
+    }
+}
+
+fn bitstream_signaling(entry: &StsdBoxContent, sample: &[u8]) -> Result<ColorSignaling> {
+    match entry {
+        StsdBoxContent::Avc1(entry) => {
+            let length_size = usize::from(entry.avcc.length_size_minus_one & 0x3) + 1;
+            let sps = nal_units(sample, length_size)
+                .find(|nal| nal[0] & 0x1f == 7)
+                .or_else(|| {
+                    let sps = entry.avcc.sequence_parameter_sets.first()?;
+                    Some(sps.bytes.as_slice())
+                });
+            sps.map_or_else(|| Ok(ColorSignaling::default()), avc_sps)
+        }
+        StsdBoxContent::Hvc1(entry)
+        | StsdBoxContent::Hev1(entry)
+        | StsdBoxContent::Dvh1(entry)
+        | StsdBoxContent::Dvhe(entry) => {
+            let length_size = usize::from(entry.hvcc.length_size_minus_one & 0x3) + 1;
+            let sps = nal_units(sample, length_size)
+                .find(|nal| (nal[0] >> 1) & 0x3f == 33)
+                .or_else(|| {
+                    let array = entry.hvcc.arrays.iter().find(|a| a.nal_unit_type == 33)?;
+                    Some(array.nalus.first()?.data.as_slice())
+                });
+            sps.map_or_else(|| Ok(ColorSignaling::default()), hevc_sps)
+        }
+        StsdBoxContent::Av01(entry) => {
+            let sequence_header = match sequence_header_obu(sample)? {
+                Some(sequence_header) => Some(sequence_header),
+                None => sequence_header_obu(&entry.av1c.config_obus)?,
+            };
+            sequence_header.map_or_else(|| Ok(ColorSignaling::default()), av1_sequence_header_color)
+        }
+        StsdBoxContent::Vp09(_) if !sample.is_empty() => vp9_frame_header(sample),
+        _ => Ok(ColorSignaling::default()),
+    }
+}
+
+fn mismatches(container: &ColorSignaling, bitstream: &ColorSignaling) -> Vec<ColorMismatch> {
+    let mut mismatches = Vec::new();
+    if let (Some(container), Some(bitstream)) =
+        (container.color_primaries, bitstream.color_primaries)
+    {
+        if container != bitstream {
+            mismatches.push(ColorMismatch::ColorPrimaries {
+                container,
+                bitstream,
+            });
+        }
+    }
+    if let (Some(container), Some(bitstream)) = (
+        container.transfer_characteristics,
+        bitstream.transfer_characteristics,
+    ) {
+        // BT.601 and BT.2020 use the transfer function of BT.709.
+        let canonical = |transfer| {
+            if matches!(transfer, 6 | 14 | 15) {
+                1
+            } else {
+                transfer
+            }
+        };
+        if canonical(container) != canonical(bitstream) {
+            mismatches.push(ColorMismatch::TransferCharacteristics {
+                container,
+                bitstream,
+            });
+        }
+    }
+    if let (Some(container), Some(bitstream)) =
+        (container.matrix_coefficients, bitstream.matrix_coefficients)
+    {
+        // Both are BT.601.
+        let canonical = |matrix| if matrix == 5 { 6 } else { matrix };
+        if canonical(container) != canonical(bitstream) {
+            mismatches.push(ColorMismatch::MatrixCoefficients {
+                container,
+                bitstream,
+            });
+        }
+    }
+    if let (Some(container), Some(bitstream)) = (container.full_range, bitstream.full_range) {
+        if container != bitstream {
+            mismatches.push(ColorMismatch::FullRange {
+                container,
+                bitstream,
+            });
+        }
+    }
+    if let (Some(container), Some(bitstream)) =
+        (container.chroma_location, bitstream.chroma_location)
+    {
+        if container != bitstream {
+            mismatches.push(ColorMismatch::ChromaLocation {
+                container,
+                bitstream,
+            });
+        }
+    }
+    mismatches
+}
+
+fn specified(code_point: u16) -> Option<u16> {
+    (code_point != 2).then_some(code_point)
+}
+
+/// Maps the `chroma_sample_position` of AV1 to a `ChromaSampleLocType`.
+fn av1_chroma_location(chroma_sample_position: u8) -> Option<u8> {
+    match chroma_sample_position {
+        1 => Some(0), // CSP_VERTICAL
+        2 => Some(2), // CSP_COLOCATED
+        _ => None,
+    }
+}
+
+/// Parses an AVC sequence parameter set up to the color fields, see ITU-T H.264 section 7.3.2.1.1.
+fn avc_sps(nal: &[u8]) -> Result<ColorSignaling> {
+    let rbsp = rbsp(nal.get(1..).unwrap_or_default());
+    let mut bits = BitReader::new(&rbsp, "AVC sequence parameter set is too short");
+
+    let profile_idc = bits.read(8)?;
+    bits.skip(16)?; // constraint flags, level_idc
+    bits.read_ue()?; // seq_parameter_set_id
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        let chroma_format_idc = bits.read_ue()?;
+        if chroma_format_idc == 3 {
+            bits.skip(1)?; // separate_colour_plane_flag
+        }
+        bits.read_ue()?; // bit_depth_luma_minus8
+        bits.read_ue()?; // bit_depth_chroma_minus8
+        bits.skip(1)?; // qpprime_y_zero_transform_bypass_flag
+        if bits.read_bit()? {
+            // seq_scaling_matrix_present_flag
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if bits.read_bit()? {
+                    skip_avc_scaling_list(&mut bits, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+    bits.read_ue()?; // log2_max_frame_num_minus4
+    match bits.read_ue()? {
+        // pic_order_cnt_type
+        0 => {
+            bits.read_ue()?; // log2_max_pic_order_cnt_lsb_minus4
+        }
+        1 => {
+            bits.skip(1)?; // delta_pic_order_always_zero_flag
+            bits.read_se()?; // offset_for_non_ref_pic
+            bits.read_se()?; // offset_for_top_to_bottom_field
+            for _ in 0..bits.read_ue()? {
+                bits.read_se()?; // offset_for_ref_frame
+            }
+        }
+        _ => {}
+    }
+    bits.read_ue()?; // max_num_ref_frames
+    bits.skip(1)?; // gaps_in_frame_num_value_allowed_flag
+    bits.read_ue()?; // pic_width_in_mbs_minus1
+    bits.read_ue()?; // pic_height_in_map_units_minus1
+    if !bits.read_bit()? {
+        // frame_mbs_only_flag
+        bits.skip(1)?; // mb_adaptive_frame_field_flag
+    }
+    bits.skip(1)?; // direct_8x8_inference_flag
+    if bits.read_bit()? {
+        // frame_cropping_flag
+        for _ in 0..4 {
+            bits.read_ue()?;
+        }
+    }
+    vui(&mut bits)
+}
+
+fn skip_avc_scaling_list(bits: &mut BitReader<'_>, size: usize) -> Result<()> {
+    let mut last_scale = 8;
+    let mut next_scale = 8;
+    for _ in 0..size {
+        if next_scale != 0 {
+            next_scale = (last_scale + bits.read_se()? + 256) % 256;
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+    Ok(())
+}
+
+/// Parses an HEVC sequence parameter set up to the color fields, see ITU-T H.265 section 7.3.2.2.
+fn hevc_sps(nal: &[u8]) -> Result<ColorSignaling> {
+    let rbsp = rbsp(nal.get(2..).unwrap_or_default());
+    let mut bits = BitReader::new(&rbsp, "HEVC sequence parameter set is too short");
+
+    bits.skip(4)?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = bits.read(3)? as usize;
+    bits.skip(1)?; // sps_temporal_id_nesting_flag
+
+    // profile_tier_level
+    bits.skip(88 + 8)?; // general profile and level
+    let mut sub_layers = Vec::with_capacity(max_sub_layers_minus1);
+    for _ in 0..max_sub_layers_minus1 {
+        sub_layers.push((bits.read_bit()?, bits.read_bit()?));
+    }
+    if max_sub_layers_minus1 > 0 {
+        bits.skip(2 * (8 - max_sub_layers_minus1))?; // reserved_zero_2bits
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            bits.skip(88)?;
+        }
+        if level_present {
+            bits.skip(8)?;
+        }
+    }
+
+    bits.read_ue()?; // sps_seq_parameter_set_id
+    if bits.read_ue()? == 3 {
+        // chroma_format_idc
+        bits.skip(1)?; // separate_colour_plane_flag
+    }
+    bits.read_ue()?; // pic_width_in_luma_samples
+    bits.read_ue()?; // pic_height_in_luma_samples
+    if bits.read_bit()? {
+        // conformance_window_flag
+        for _ in 0..4 {
+            bits.read_ue()?;
+        }
+    }
+    bits.read_ue()?; // bit_depth_luma_minus8
+    bits.read_ue()?; // bit_depth_chroma_minus8
+    let log2_max_pic_order_cnt_lsb = bits.read_ue()? as usize + 4;
+    let sub_layer_ordering_info_present = bits.read_bit()?;
+    let orderings = if sub_layer_ordering_info_present {
+        max_sub_layers_minus1 + 1
+    } else {
+        1
+    };
+    for _ in 0..orderings {
+        bits.read_ue()?; // sps_max_dec_pic_buffering_minus1
+        bits.read_ue()?; // sps_max_num_reorder_pics
+        bits.read_ue()?; // sps_max_latency_increase_plus1
+    }
+    for _ in 0..6 {
+        // Coding and transform block sizes, and transform hierarchy depths.
+        bits.read_ue()?;
+    }
+    if bits.read_bit()? && bits.read_bit()? {
+        // scaling_list_enabled_flag, sps_scaling_list_data_present_flag
+        skip_hevc_scaling_list_data(&mut bits)?;
+    }
+    bits.skip(2)?; // amp_enabled_flag, sample_adaptive_offset_enabled_flag
+    if bits.read_bit()? {
+        // pcm_enabled_flag
+        bits.skip(8)?; // PCM sample bit depths
+        bits.read_ue()?; // log2_min_pcm_luma_coding_block_size_minus3
+        bits.read_ue()?; // log2_diff_max_min_pcm_luma_coding_block_size
+        bits.skip(1)?; // pcm_loop_filter_disabled_flag
+    }
+
+    let num_short_term_ref_pic_sets = bits.read_ue()?;
+    if num_short_term_ref_pic_sets > 64 {
+        return Err(Error::InvalidData(
+            "too many short-term reference picture sets",
+        ));
+    }
+    let mut num_delta_pocs: Vec<u32> = Vec::new();
+    for index in 0..num_short_term_ref_pic_sets as usize {
+        // st_ref_pic_set
+        if index > 0 && bits.read_bit()? {
+            // inter_ref_pic_set_prediction_flag, predicting from the previous set
+            bits.skip(1)?; // delta_rps_sign
+            bits.read_ue()?; // abs_delta_rps_minus1
+            let mut count = 0;
+            for _ in 0..=num_delta_pocs[index - 1] {
+                let used_by_curr_pic = bits.read_bit()?;
+                if used_by_curr_pic || bits.read_bit()? {
+                    // use_delta_flag
+                    count += 1;
+                }
+            }
+            num_delta_pocs.push(count);
+        } else {
+            let num_negative_pics = bits.read_ue()?;
+            let num_positive_pics = bits.read_ue()?;
+            let count = num_negative_pics.saturating_add(num_positive_pics);
+            for _ in 0..count {
+                bits.read_ue()?; // delta_poc_minus1
+                bits.skip(1)?; // used_by_curr_pic_flag
+            }
+            num_delta_pocs.push(count);
+        }
+    }
+    if bits.read_bit()? {
+        // long_term_ref_pics_present_flag
+        for _ in 0..bits.read_ue()? {
+            bits.skip(log2_max_pic_order_cnt_lsb + 1)?; // lt_ref_pic_poc_lsb_sps, used flag
+        }
+    }
+    bits.skip(2)?; // sps_temporal_mvp_enabled_flag, strong_intra_smoothing_enabled_flag
+    vui(&mut bits)
+}
+
+fn skip_hevc_scaling_list_data(bits: &mut BitReader<'_>) -> Result<()> {
+    for size_id in 0..4 {
+        let matrices = if size_id == 3 { 2 } else { 6 };
+        for _ in 0..matrices {
+            if bits.read_bit()? {
+                // scaling_list_pred_mode_flag
+                if size_id > 1 {
+                    bits.read_se()?; // scaling_list_dc_coef_minus8
+                }
+                for _ in 0..(1 << (4 + (size_id << 1))).min(64) {
+                    bits.read_se()?; // scaling_list_delta_coef
+                }
+            } else {
+                bits.read_ue()?; // scaling_list_pred_matrix_id_delta
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `vui_parameters_present_flag` and the start of the VUI, whose color fields are
+/// the same in AVC and HEVC.
+fn vui(bits: &mut BitReader<'_>) -> Result<ColorSignaling> {
+    // Without a video signal type, decoders infer limited range.
+    let mut signaling = ColorSignaling {
+        full_range: Some(false),
+        ..Default::default()
+    };
+    if !bits.read_bit()? {
+        // vui_parameters_present_flag
+        return Ok(signaling);
+    }
+    if bits.read_bit()? && bits.read(8)? == 255 {
+        // aspect_ratio_info_present_flag, aspect_ratio_idc is Extended_SAR
+        bits.skip(32)?; // sar_width, sar_height
+    }
+    if bits.read_bit()? {
+        // overscan_info_present_flag
+        bits.skip(1)?; // overscan_appropriate_flag
+    }
+    if bits.read_bit()? {
+        // video_signal_type_present_flag
+        bits.skip(3)?; // video_format
+        signaling.full_range = Some(bits.read_bit()?);
+        if bits.read_bit()? {
+            // colour_description_present_flag
+            signaling.color_primaries = specified(bits.read(8)? as u16);
+            signaling.transfer_characteristics = specified(bits.read(8)? as u16);
+            signaling.matrix_coefficients = specified(bits.read(8)? as u16);
+        }
+    }
+    if bits.read_bit()? {
+        // chroma_loc_info_present_flag
+        let top_field = bits.read_ue()?;
+        signaling.chroma_location = Some(u8::try_from(top_field).unwrap_or(u8::MAX));
+        bits.read_ue()?; // chroma_sample_loc_type_bottom_field
+    }
+    Ok(signaling)
+}
+
+/// The color signaling of an AV1 sequence header, see the AV1 specification section 5.5.2.
+fn av1_sequence_header_color(sequence_header: &[u8]) -> Result<ColorSignaling> {
+    let color = Av1SequenceHeader::parse(sequence_header)?.color;
+    let srgb = (
+        color.color_primaries,
+        color.transfer_characteristics,
+        color.matrix_coefficients,
+    ) == (1, 13, 0);
+    Ok(ColorSignaling {
+        color_primaries: specified(u16::from(color.color_primaries)),
+        transfer_characteristics: specified(u16::from(color.transfer_characteristics)),
+        matrix_coefficients: specified(u16::from(color.matrix_coefficients)),
+        full_range: Some(color.full_range),
+        chroma_location: if color.monochrome || srgb {
+            None
+        } else {
+            av1_chroma_location(color.chroma_sample_position)
+        },
+    })
+}
+
+/// Parses the color config of a VP9 key frame, see the VP9 bitstream specification section
+/// 6.2. Other frames signal nothing.
+fn vp9_frame_header(frame: &[u8]) -> Result<ColorSignaling> {
+    let mut bits = BitReader::new(frame, "VP9 frame header is too short");
+
+    if bits.read(2)? != 2 {
+        return Err(Error::InvalidData("invalid VP9 frame marker"));
+    }
+    let profile_low_bit = bits.read(1)?;
+    let profile = (bits.read(1)? << 1) | profile_low_bit;
+    if profile == 3 {
+        bits.skip(1)?; // reserved_zero
+    }
+    if bits.read_bit()? || bits.read_bit()? {
+        // show_existing_frame, or a frame_type that is not KEY_FRAME
+        return Ok(ColorSignaling::default());
+    }
+    bits.skip(2)?; // show_frame, error_resilient_mode
+    if bits.read(24)? != 0x49_83_42 {
+        return Err(Error::InvalidData("invalid VP9 frame sync code"));
+    }
+
+    // color_config
+    if profile >= 2 {
+        bits.skip(1)?; // ten_or_twelve_bit
+    }
+    let color_space = bits.read(3)?;
+    let full_range = if color_space == 7 {
+        // CS_RGB
+        true
+    } else {
+        bits.read_bit()?
+    };
+    Ok(ColorSignaling {
+        matrix_coefficients: vp9_color_space_matrix(color_space as u8).map(u16::from),
+        full_range: Some(full_range),
+        ..Default::default()
+    })
+}
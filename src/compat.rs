@@ -0,0 +1,265 @@
+//! Checking whether the samples of two tracks can be decoded with one codec configuration.
+//!
+//! That is what it takes to concatenate the tracks of two files without re-encoding, or to
+//! append both to one MSE source buffer without an init segment in between.
+
+use crate::{Av1CBox, AvcCBox, FourCC, HevcDecoderConfigurationRecord, StsdBoxContent, VpccBox};
+
+/// A difference between two codec configurations, see [`codec_config_compatible`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatIssue {
+    /// The sample entries are of different types, e.g. `avc1` and `hvc1`.
+    ///
+    /// Nothing else is compared in that case.
+    SampleEntry { first: FourCC, second: FourCC },
+
+    /// The width or height of the pictures differ.
+    Resolution,
+
+    /// The profiles, or the profile compatibility flags, differ.
+    Profile,
+
+    /// The second track needs a higher level or tier than the first one signals.
+    Level,
+
+    /// The chroma subsampling differs, or only one of the tracks is monochrome.
+    ChromaFormat,
+
+    /// The bit depths differ.
+    BitDepth,
+
+    /// The sizes of the NAL unit length fields differ, so the samples are split differently.
+    NalLengthSize,
+
+    /// The parameter sets, or the configuration OBUs of AV1, differ.
+    ParameterSets,
+
+    /// The sample entries of a codec whose configuration is not inspected differ.
+    Other,
+}
+
+/// The differences between the codec configurations of two tracks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    pub issues: Vec<CompatIssue>,
+}
+
+impl CompatReport {
+    /// Whether the samples of the second track can be decoded with the configuration of the
+    /// first one.
+    pub fn is_compatible(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Compares the sample entry of `second` against that of `first`.
+///
+/// For AVC, HEVC, AV1, VP8 and VP9 the decoder configurations are compared field by field.
+/// The comparison is not symmetric: a lower level in `second` is fine, since a decoder
+/// configured for the level of `first` can decode it. Other codecs are only compatible if
+/// their sample entries are identical.
+pub fn codec_config_compatible(first: &StsdBoxContent, second: &StsdBoxContent) -> CompatReport {
+    let mut issues = Vec::new();
+    if first.fourcc() != second.fourcc() {
+        issues.push(CompatIssue::SampleEntry {
+            first: first.fourcc(),
+            second: second.fourcc(),
+        });
+        return CompatReport { issues };
+    }
+
+    check(
+        &mut issues,
+        CompatIssue::Resolution,
+        first.resolution() != second.resolution(),
+    );
+    match (first, second) {
+        (StsdBoxContent::Av01(first), StsdBoxContent::Av01(second)) => {
+            compare_av1c(&mut issues, &first.av1c, &second.av1c);
+        }
+        (StsdBoxContent::Avc1(first), StsdBoxContent::Avc1(second)) => {
+            compare_avcc(&mut issues, &first.avcc, &second.avcc);
+        }
+        (StsdBoxContent::Hvc1(first), StsdBoxContent::Hvc1(second))
+        | (StsdBoxContent::Hev1(first), StsdBoxContent::Hev1(second))
+        | (StsdBoxContent::Dvh1(first), StsdBoxContent::Dvh1(second))
+        | (StsdBoxContent::Dvhe(first), StsdBoxContent::Dvhe(second)) => {
+            compare_hvcc(&mut issues, &first.hvcc, &second.hvcc);
+        }
+        (StsdBoxContent::Vp08(first), StsdBoxContent::Vp08(second)) => {
+            compare_vpcc(&mut issues, &first.vpcc, &second.vpcc);
+        }
+        (StsdBoxContent::Vp09(first), StsdBoxContent::Vp09(second)) => {
+            compare_vpcc(&mut issues, &first.vpcc, &second.vpcc);
+        }
+        _ => {
+            if first != second {
+                issues.push(CompatIssue::Other);
+            }
+        }
+    }
+
+    CompatReport { issues }
+}
+
+/// Pushes `issue` if `differs`, so the comparisons read as a table.
+fn check(issues: &mut Vec<CompatIssue>, issue: CompatIssue, differs: bool) {
+    if differs {
+        issues.push(issue);
+    }
+}
+
+/// Chroma format and luma and chroma bit depths (minus 8) of an `avcC`.
+///
+/// These are only stored for the High profiles, the others are always 8-bit 4:2:0.
+fn avc_chroma_and_bit_depth(avcc: &AvcCBox) -> (u8, u8, u8) {
+    let high_profile = matches!(avcc.avc_profile_indication, 100 | 110 | 122 | 144);
+    match avcc.ext.as_slice() {
+        [chroma_format, bit_depth_luma, bit_depth_chroma, ..] if high_profile => (
+            chroma_format & 0b11,
+            bit_depth_luma & 0b111,
+            bit_depth_chroma & 0b111,
+        ),
+        _ => (1, 0, 0),
+    }
+}
+
+fn compare_avcc(issues: &mut Vec<CompatIssue>, first: &AvcCBox, second: &AvcCBox) {
+    let (first_chroma, first_luma_depth, first_chroma_depth) = avc_chroma_and_bit_depth(first);
+    let (second_chroma, second_luma_depth, second_chroma_depth) = avc_chroma_and_bit_depth(second);
+
+    check(
+        issues,
+        CompatIssue::Profile,
+        (first.avc_profile_indication, first.profile_compatibility)
+            != (second.avc_profile_indication, second.profile_compatibility),
+    );
+    check(
+        issues,
+        CompatIssue::Level,
+        second.avc_level_indication > first.avc_level_indication,
+    );
+    check(
+        issues,
+        CompatIssue::ChromaFormat,
+        first_chroma != second_chroma,
+    );
+    check(
+        issues,
+        CompatIssue::BitDepth,
+        (first_luma_depth, first_chroma_depth) != (second_luma_depth, second_chroma_depth),
+    );
+    check(
+        issues,
+        CompatIssue::NalLengthSize,
+        first.length_size_minus_one != second.length_size_minus_one,
+    );
+    check(
+        issues,
+        CompatIssue::ParameterSets,
+        first.sequence_parameter_sets != second.sequence_parameter_sets
+            || first.picture_parameter_sets != second.picture_parameter_sets,
+    );
+}
+
+fn compare_hvcc(
+    issues: &mut Vec<CompatIssue>,
+    first: &HevcDecoderConfigurationRecord,
+    second: &HevcDecoderConfigurationRecord,
+) {
+    check(
+        issues,
+        CompatIssue::Profile,
+        (
+            first.general_profile_space,
+            first.general_profile_idc,
+            first.general_profile_compatibility_flags,
+        ) != (
+            second.general_profile_space,
+            second.general_profile_idc,
+            second.general_profile_compatibility_flags,
+        ),
+    );
+    check(
+        issues,
+        CompatIssue::Level,
+        (second.general_tier_flag, second.general_level_idc)
+            > (first.general_tier_flag, first.general_level_idc),
+    );
+    check(
+        issues,
+        CompatIssue::ChromaFormat,
+        first.chroma_format_idc != second.chroma_format_idc,
+    );
+    check(
+        issues,
+        CompatIssue::BitDepth,
+        (first.bit_depth_luma_minus8, first.bit_depth_chroma_minus8)
+            != (second.bit_depth_luma_minus8, second.bit_depth_chroma_minus8),
+    );
+    check(
+        issues,
+        CompatIssue::NalLengthSize,
+        first.length_size_minus_one != second.length_size_minus_one,
+    );
+    check(
+        issues,
+        CompatIssue::ParameterSets,
+        first.arrays != second.arrays,
+    );
+}
+
+fn compare_av1c(issues: &mut Vec<CompatIssue>, first: &Av1CBox, second: &Av1CBox) {
+    check(
+        issues,
+        CompatIssue::Profile,
+        first.profile != second.profile,
+    );
+    check(
+        issues,
+        CompatIssue::Level,
+        (second.tier, second.level) > (first.tier, first.level),
+    );
+    check(
+        issues,
+        CompatIssue::ChromaFormat,
+        (
+            first.monochrome,
+            first.chroma_subsampling_x,
+            first.chroma_subsampling_y,
+        ) != (
+            second.monochrome,
+            second.chroma_subsampling_x,
+            second.chroma_subsampling_y,
+        ),
+    );
+    check(
+        issues,
+        CompatIssue::BitDepth,
+        first.bit_depth != second.bit_depth,
+    );
+    check(
+        issues,
+        CompatIssue::ParameterSets,
+        first.config_obus != second.config_obus,
+    );
+}
+
+fn compare_vpcc(issues: &mut Vec<CompatIssue>, first: &VpccBox, second: &VpccBox) {
+    check(
+        issues,
+        CompatIssue::Profile,
+        first.profile != second.profile,
+    );
+    check(issues, CompatIssue::Level, second.level > first.level);
+    check(
+        issues,
+        CompatIssue::ChromaFormat,
+        first.chroma_subsampling != second.chroma_subsampling,
+    );
+    check(
+        issues,
+        CompatIssue::BitDepth,
+        first.bit_depth != second.bit_depth,
+    );
+}
@@ -0,0 +1,153 @@
+//! Checking a file against the rules of the brands it declares in `ftyp`.
+//!
+//! Players tend to be stricter than this crate, so files that parse fine can still fail to
+//! play. Most of these rules come from CMAF, which players for adaptive streaming rely on.
+
+use crate::{BoxNode, FourCC, Mp4, TrackId};
+
+/// A rule of a brand of the file that it breaks, see [`Mp4::conformance_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConformanceIssue {
+    /// Another box than a space box (`free`, `skip` or `wide`) comes before `ftyp`.
+    FtypNotFirst { first: FourCC },
+
+    /// A file without the `qt  ` brand has boxes that only `QuickTime` defines, like a `wave`
+    /// box in a sound sample entry, which ISO players may not understand.
+    QuickTimeBox { box_type: FourCC },
+
+    /// A CMAF file is not fragmented, it has no `mvex`.
+    NotFragmented,
+
+    /// A CMAF track has samples in `moov`, where it must only have them in fragments.
+    SamplesInMoov { track_id: TrackId },
+
+    /// A movie fragment of a CMAF file comes before `moov`.
+    FragmentBeforeMoov { fragment: usize },
+
+    /// A movie fragment of a CMAF file has the samples of more than one track.
+    MultipleTracksInFragment { fragment: usize },
+
+    /// A track fragment of a CMAF file has no `tfdt`, so its samples can only be placed on the
+    /// timeline by adding up the durations of all earlier fragments.
+    MissingTfdt { fragment: usize, track_id: TrackId },
+
+    /// A movie fragment of a CMAF file is not directly followed by the `mdat` with its samples.
+    FragmentWithoutMdat { fragment: usize },
+}
+
+/// The rules of the brands of a file that it breaks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub issues: Vec<ConformanceIssue>,
+}
+
+impl ConformanceReport {
+    /// Whether the file follows all rules that were checked.
+    pub fn is_conformant(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Brands of CMAF tracks and their structural constraints, see ISO/IEC 23000-19.
+const CMAF_BRANDS: [&[u8; 4]; 2] = [b"cmfc", b"cmf2"];
+
+/// Boxes that only `QuickTime` defines.
+const QUICKTIME_BOXES: [&[u8; 4]; 2] = [b"wave", b"gmhd"];
+
+impl Mp4 {
+    /// Checks the file against the rules that its brands in `ftyp` imply.
+    ///
+    /// Every file must start with `ftyp`, and files that don't declare the `qt  ` brand must
+    /// not rely on `QuickTime` boxes. CMAF files (`cmfc` or `cmf2`) must be fragmented with
+    /// one track per fragment, every fragment with a `tfdt` and followed by its `mdat`.
+    ///
+    /// The order of the boxes is only checked for files that were read, not for an [`Mp4`]
+    /// built with [`Mp4::from_parts`].
+    pub fn conformance_check(&self) -> ConformanceReport {
+        let mut issues = Vec::new();
+        let box_tree = self.box_tree();
+        let has_brand = |brand: &[u8; 4]| {
+            self.ftyp.major_brand.value == *brand
+                || self
+                    .ftyp
+                    .compatible_brands
+                    .iter()
+                    .any(|compatible| compatible.value == *brand)
+        };
+
+        if let Some(first) = box_tree
+            .iter()
+            .find(|node| !matches!(&node.box_type.value, b"free" | b"skip" | b"wide"))
+        {
+            // Media segments start with `styp` instead.
+            if !matches!(&first.box_type.value, b"ftyp" | b"styp") {
+                issues.push(ConformanceIssue::FtypNotFirst {
+                    first: first.box_type,
+                });
+            }
+        }
+
+        if !has_brand(b"qt  ") {
+            for node in box_tree.iter().flat_map(BoxNode::descendants) {
+                let issue = ConformanceIssue::QuickTimeBox {
+                    box_type: node.box_type,
+                };
+                if QUICKTIME_BOXES.contains(&&node.box_type.value) && !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+        }
+
+        if CMAF_BRANDS.iter().any(|brand| has_brand(brand)) {
+            self.check_cmaf(&mut issues);
+        }
+
+        ConformanceReport { issues }
+    }
+
+    fn check_cmaf(&self, issues: &mut Vec<ConformanceIssue>) {
+        if self.moov.mvex.is_none() {
+            issues.push(ConformanceIssue::NotFragmented);
+        }
+        for trak in &self.moov.traks {
+            if trak.mdia.minf.stbl.stsz.count() > 0 {
+                issues.push(ConformanceIssue::SamplesInMoov {
+                    track_id: trak.tkhd.track_id,
+                });
+            }
+        }
+
+        for (fragment, moof) in self.moofs.iter().enumerate() {
+            if moof.trafs.len() > 1 {
+                issues.push(ConformanceIssue::MultipleTracksInFragment { fragment });
+            }
+            for traf in &moof.trafs {
+                if traf.tfdt.is_none() {
+                    issues.push(ConformanceIssue::MissingTfdt {
+                        fragment,
+                        track_id: traf.tfhd.track_id,
+                    });
+                }
+            }
+        }
+
+        // The fragments in the box tree are in the same order as `moofs`.
+        let box_tree = self.box_tree();
+        let moov = box_tree
+            .iter()
+            .position(|node| &node.box_type.value == b"moov");
+        let moofs = box_tree
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| &node.box_type.value == b"moof");
+        for (fragment, (index, _)) in moofs.enumerate() {
+            if moov.is_some_and(|moov| moov > index) {
+                issues.push(ConformanceIssue::FragmentBeforeMoov { fragment });
+            }
+            let next = box_tree.get(index + 1).map(|node| &node.box_type.value);
+            if next != Some(b"mdat") {
+                issues.push(ConformanceIssue::FragmentWithoutMdat { fragment });
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+use crate::load::read_sample;
+use crate::{Error, Mp4, Result, Sample, Track, TrackId};
+
+/// The files that hold the media data of the tracks of an [`Mp4`].
+///
+/// Most files are self-contained, but `QuickTime` reference movies store their samples in other
+/// files (see [`Track::external_reference`]). Readers for those are added with [`Self::with_external`].
+pub struct DataSources<R> {
+    main: R,
+    external: BTreeMap<(TrackId, u16), R>,
+}
+
+impl<R: Read + Seek> DataSources<R> {
+    /// Sources with only the file that the [`Mp4`] was parsed from.
+    pub fn new(main: R) -> Self {
+        Self {
+            main,
+            external: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the file that a data reference of a track points to.
+    ///
+    /// `data_reference_index` is the 1-based index of the entry in the `dref` of the track, as
+    /// used by its sample entry. Every track has its own `dref`.
+    pub fn with_external(
+        mut self,
+        track_id: TrackId,
+        data_reference_index: u16,
+        reader: R,
+    ) -> Self {
+        self.external
+            .insert((track_id, data_reference_index), reader);
+        self
+    }
+
+    /// Reads the data of a sample of `track`, from whichever file it is stored in.
+    ///
+    /// Returns [`Error::ExternalDataReference`] if the sample is in another file, and no reader
+    /// was added for it.
+    pub fn read_sample(&mut self, mp4: &Mp4, track: &Track, sample: &Sample) -> Result<Vec<u8>> {
+        // Tracks without a `trak`, see `Limits::synthesize_missing_tracks`, are in the main file.
+        let external_trak = track.trak(mp4).filter(|trak| {
+            trak.data_reference()
+                .is_some_and(|entry| !entry.is_self_contained())
+        });
+        let reader = if let Some(trak) = external_trak {
+            let index = trak.mdia.minf.stbl.stsd.contents.data_reference_index();
+            self.external
+                .get_mut(&(track.track_id, index))
+                .ok_or(Error::ExternalDataReference(track.track_id))?
+        } else {
+            &mut self.main
+        };
+
+        let mut data = Vec::new();
+        read_sample(reader, sample, &mut data)?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,155 @@
+//! Snapshots of parsed tracks, for testing that files parse the same over time.
+//!
+//! This is what the tests of this crate compare against the output of `mp4box.js`. A
+//! [`TrackSnapshot`] sums up a track in a few fields, and its JSON only changes when what
+//! was parsed changes, so it can be checked in next to a file and compared in a test.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Mp4, Result, Track, TrackId, TrackKind};
+
+/// A summary of a parsed track, see [`snapshot_tracks`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackSnapshot {
+    pub track_id: TrackId,
+    pub kind: Option<TrackKind>,
+
+    /// The codec string of the track, like `avc1.64001f`, if it is known.
+    pub codec: Option<String>,
+
+    pub width: u16,
+    pub height: u16,
+    pub num_samples: usize,
+
+    /// Digest of the timing, sync flags and data of all samples, see [`samples_digest`].
+    #[serde(with = "hex")]
+    pub samples_digest: [u8; 8],
+
+    /// The decoder configuration of the track, like the contents of its `avcC` box.
+    #[serde(with = "hex_option")]
+    pub description: Option<Vec<u8>>,
+}
+
+impl TrackSnapshot {
+    /// Summarizes `track`, whose samples are read from `data`, the file it was parsed from.
+    ///
+    /// Fails if a sample lies beyond the end of `data`.
+    pub fn new(track: &Track, data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            track_id: track.track_id,
+            kind: track.kind,
+            codec: track.codec_string(),
+            width: track.width,
+            height: track.height,
+            num_samples: track.samples.len(),
+            samples_digest: samples_digest(track, data)?,
+            description: track.raw_codec_config(),
+        })
+    }
+
+    /// The snapshot as pretty-printed JSON, with fields in a fixed order.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to convert to JSON")
+    }
+}
+
+/// Snapshots of all tracks of `mp4`, ordered by track id.
+pub fn snapshot_tracks(mp4: &Mp4, data: &[u8]) -> Result<Vec<TrackSnapshot>> {
+    mp4.tracks()
+        .values()
+        .map(|track| TrackSnapshot::new(track, data))
+        .collect()
+}
+
+/// A 64-bit FNV-1a digest of the sync flag, size, timestamps, duration and data of every
+/// sample of `track`, in order.
+///
+/// Sample offsets are left out, so a file keeps its digest when its boxes are moved around,
+/// such as by [`crate::faststart`].
+pub fn samples_digest(track: &Track, data: &[u8]) -> Result<[u8; 8]> {
+    let mut hasher = Fnv1a::default();
+    for sample in &track.samples {
+        let bytes = data
+            .get(sample.byte_range())
+            .ok_or(Error::InvalidData("sample lies beyond the end of the data"))?;
+        hasher.write(&[u8::from(sample.is_sync)]);
+        hasher.write(&sample.size.to_be_bytes());
+        hasher.write(&sample.decode_timestamp.to_be_bytes());
+        hasher.write(&sample.composition_timestamp.to_be_bytes());
+        hasher.write(&sample.duration.to_be_bytes());
+        hasher.write(bytes);
+    }
+    Ok(hasher.0.to_be_bytes())
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hashers of `std` is the same on every platform
+/// and release.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex<E: serde::de::Error>(s: &str) -> std::result::Result<Vec<u8>, E> {
+    if !s.is_ascii() || !s.len().is_multiple_of(2) {
+        return Err(E::custom("invalid hex string"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(E::custom))
+        .collect()
+}
+
+/// Bytes as a lowercase hex string, which is easier to diff than an array of numbers.
+mod hex {
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::to_hex(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 8], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::from_hex(&s)?
+            .try_into()
+            .map_err(|_err| serde::de::Error::custom("expected 8 bytes"))
+    }
+}
+
+mod hex_option {
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    #[expect(clippy::ref_option)] // The signature serde expects.
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => serializer.serialize_some(&super::to_hex(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| super::from_hex(&s))
+            .transpose()
+    }
+}
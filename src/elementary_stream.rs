@@ -0,0 +1,261 @@
+//! Writing the samples of a track as a raw elementary stream, which decoders and tools like
+//! `ffprobe` read without a container.
+
+use std::io::{Read, Seek, Write};
+
+use crate::codec::{OBJECT_TYPES_AAC, OBJECT_TYPES_MP3};
+use crate::load::read_sample;
+use crate::{Error, Mp4, Result, StsdBoxContent, Track};
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// The OBU type of AV1 temporal delimiters, which start every temporal unit of a stream.
+const OBU_TEMPORAL_DELIMITER: u8 = 2;
+
+/// The flag of an OBU header that signals an `obu_size` field.
+const OBU_HAS_SIZE_FIELD: u8 = 0x02;
+
+/// How the samples of a track are written as an elementary stream.
+enum StreamFormat<'a> {
+    /// Length-prefixed NAL units, written with start codes (Annex B of H.264 and H.265) and
+    /// with the parameter sets of the decoder configuration before every sync sample.
+    AnnexB {
+        extension: &'static str,
+        length_size: usize,
+        parameter_sets: Vec<&'a [u8]>,
+    },
+
+    /// AV1 in the low-overhead bitstream format, where every OBU has a size field and every
+    /// temporal unit starts with a temporal delimiter.
+    Obu { config_obus: &'a [u8] },
+
+    /// AAC frames, each with an ADTS header.
+    Adts {
+        profile: u8,
+        freq_index: u8,
+        chan_conf: u8,
+    },
+
+    /// Samples that are already self-delimiting, like MP3 frames.
+    Raw { extension: &'static str },
+}
+
+impl Track {
+    /// The file extension commonly used for the elementary stream of this track, like `h264`,
+    /// or `None` if [`Self::write_elementary_stream`] doesn't support its codec.
+    pub fn elementary_stream_extension(&self) -> Option<&'static str> {
+        Some(match stream_format(self)? {
+            StreamFormat::AnnexB { extension, .. } | StreamFormat::Raw { extension } => extension,
+            StreamFormat::Obu { .. } => "obu",
+            StreamFormat::Adts { .. } => "aac",
+        })
+    }
+
+    /// Writes the samples of this track, read from `reader`, as an elementary stream that
+    /// can be played or decoded without a container.
+    ///
+    /// H.264 and H.265 are written as Annex B byte streams, AV1 in the low-overhead bitstream
+    /// format, AAC with ADTS headers, and MP3 as is. `reader` must be the file `mp4` was
+    /// parsed from.
+    pub fn write_elementary_stream<R: Read + Seek, W: Write>(
+        &self,
+        mp4: &Mp4,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<()> {
+        let format = stream_format(self)
+            .ok_or_else(|| Error::UnsupportedCodec(self.track_id, self.sample_entry().fourcc()))?;
+        if self.has_external_data(mp4) {
+            return Err(Error::ExternalDataReference(self.track_id));
+        }
+
+        let mut data = Vec::new();
+        for (index, sample) in self.samples.iter().enumerate() {
+            read_sample(reader, sample, &mut data)?;
+
+            match &format {
+                StreamFormat::AnnexB {
+                    length_size,
+                    parameter_sets,
+                    ..
+                } => {
+                    if sample.is_sync {
+                        for parameter_set in parameter_sets {
+                            writer.write_all(&START_CODE)?;
+                            writer.write_all(parameter_set)?;
+                        }
+                    }
+                    write_annex_b(writer, &data, *length_size)?;
+                }
+                StreamFormat::Obu { config_obus } => {
+                    writer.write_all(&[(OBU_TEMPORAL_DELIMITER << 3) | OBU_HAS_SIZE_FIELD, 0])?;
+                    if index == 0 {
+                        write_obus(writer, config_obus)?;
+                    }
+                    write_obus(writer, &data)?;
+                }
+                StreamFormat::Adts {
+                    profile,
+                    freq_index,
+                    chan_conf,
+                } => {
+                    let frame_length = u16::try_from(data.len() + 7)
+                        .ok()
+                        .filter(|&length| length < 1 << 13)
+                        .ok_or(Error::InvalidData("AAC frame too large for ADTS"))?;
+                    writer.write_all(&[
+                        0xff,
+                        0xf1, // MPEG-4, no CRC
+                        (profile << 6) | (freq_index << 2) | (chan_conf >> 2),
+                        ((chan_conf & 3) << 6) | (frame_length >> 11) as u8,
+                        (frame_length >> 3) as u8,
+                        (frame_length << 5) as u8 | 0x1f,
+                        0xfc, // variable bitrate, one raw data block
+                    ])?;
+                    writer.write_all(&data)?;
+                }
+                StreamFormat::Raw { .. } => writer.write_all(&data)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stream_format(track: &Track) -> Option<StreamFormat<'_>> {
+    Some(match track.sample_entry() {
+        StsdBoxContent::Avc1(avc1) => {
+            let avcc = &avc1.avcc.contents;
+            StreamFormat::AnnexB {
+                extension: "h264",
+                length_size: usize::from(avcc.length_size_minus_one & 3) + 1,
+                parameter_sets: avcc
+                    .sequence_parameter_sets
+                    .iter()
+                    .chain(&avcc.picture_parameter_sets)
+                    .map(|nal_unit| nal_unit.bytes.as_slice())
+                    .collect(),
+            }
+        }
+        StsdBoxContent::Hvc1(hevc)
+        | StsdBoxContent::Hev1(hevc)
+        | StsdBoxContent::Dvh1(hevc)
+        | StsdBoxContent::Dvhe(hevc) => {
+            let hvcc = &hevc.hvcc.contents;
+            StreamFormat::AnnexB {
+                extension: "h265",
+                length_size: usize::from(hvcc.length_size_minus_one & 3) + 1,
+                parameter_sets: hvcc
+                    .arrays
+                    .iter()
+                    .flat_map(|array| &array.nalus)
+                    .map(|nalu| nalu.data.as_slice())
+                    .collect(),
+            }
+        }
+        StsdBoxContent::Av01(av01) => StreamFormat::Obu {
+            config_obus: &av01.av1c.contents.config_obus,
+        },
+        StsdBoxContent::Mp3(_) => StreamFormat::Raw { extension: "mp3" },
+        StsdBoxContent::Mp4a(mp4a) => {
+            let dec_config = &mp4a.esds.as_ref()?.es_desc.dec_config;
+            if OBJECT_TYPES_MP3.contains(&dec_config.object_type_indication) {
+                return Some(StreamFormat::Raw { extension: "mp3" });
+            }
+            if !OBJECT_TYPES_AAC.contains(&dec_config.object_type_indication) {
+                return None;
+            }
+            let dec_specific = &dec_config.dec_specific;
+            // ADTS only has the profiles of the first four audio object types. HE-AAC is
+            // written as its AAC-LC core, which players upgrade implicitly.
+            let profile = match dec_specific.profile {
+                profile @ 1..=4 => profile - 1,
+                5 | 29 => 1,
+                _ => return None,
+            };
+            (dec_specific.freq_index < 13 && dec_specific.chan_conf < 8).then_some(
+                StreamFormat::Adts {
+                    profile,
+                    freq_index: dec_specific.freq_index,
+                    chan_conf: dec_specific.chan_conf,
+                },
+            )?
+        }
+        _ => return None,
+    })
+}
+
+/// Writes the NAL units of `data`, each prefixed by its length in `length_size` bytes, with
+/// start codes instead.
+fn write_annex_b<W: Write>(writer: &mut W, mut data: &[u8], length_size: usize) -> Result<()> {
+    while !data.is_empty() {
+        let (length, rest) = data
+            .split_at_checked(length_size)
+            .ok_or(Error::InvalidData(
+                "NAL unit length past the end of its sample",
+            ))?;
+        let length = length
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | usize::from(byte));
+        let (nal_unit, rest) = rest.split_at_checked(length).ok_or(Error::InvalidData(
+            "NAL unit extends past the end of its sample",
+        ))?;
+        writer.write_all(&START_CODE)?;
+        writer.write_all(nal_unit)?;
+        data = rest;
+    }
+    Ok(())
+}
+
+/// Writes the OBUs of `data`, adding the size field to those without one and leaving out
+/// temporal delimiters, which are written once per sample instead.
+fn write_obus<W: Write>(writer: &mut W, mut data: &[u8]) -> Result<()> {
+    while let Some(&header) = data.first() {
+        let has_extension = header & 0x04 != 0;
+        let (headers, rest) = data
+            .split_at_checked(1 + usize::from(has_extension))
+            .ok_or(Error::InvalidData("OBU header past the end of its sample"))?;
+        let (size, rest) = if header & OBU_HAS_SIZE_FIELD != 0 {
+            read_leb128(rest)?
+        } else {
+            (rest.len(), rest)
+        };
+        let (payload, rest) = rest
+            .split_at_checked(size)
+            .ok_or(Error::InvalidData("OBU extends past the end of its sample"))?;
+
+        if (header >> 3) & 0x0f != OBU_TEMPORAL_DELIMITER {
+            writer.write_all(&[header | OBU_HAS_SIZE_FIELD])?;
+            writer.write_all(&headers[1..])?;
+            write_leb128(writer, size)?;
+            writer.write_all(payload)?;
+        }
+        data = rest;
+    }
+    Ok(())
+}
+
+/// Reads an unsigned LEB128 number of at most 8 bytes, as used by AV1.
+fn read_leb128(data: &[u8]) -> Result<(usize, &[u8])> {
+    let mut value = 0u64;
+    for (index, &byte) in data.iter().enumerate().take(8) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            let value =
+                usize::try_from(value).map_err(|_err| Error::InvalidData("OBU size too large"))?;
+            return Ok((value, &data[index + 1..]));
+        }
+    }
+    Err(Error::InvalidData("invalid OBU size"))
+}
+
+fn write_leb128<W: Write>(writer: &mut W, mut value: usize) -> Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[byte])?;
+            return Ok(());
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
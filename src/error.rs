@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use crate::mp4box::BoxType;
+use crate::FourCC;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -34,6 +35,50 @@ pub enum Error {
     #[error("traf[{0}].trun.{1}.entry[{2}] not found")]
     EntryInTrunNotFound(u32, BoxType, u32),
 
+    #[error("trak[{0}] refers to media data in another file")]
+    ExternalDataReference(u32),
+
+    #[error("file exceeds the {0} limit of {1}")]
+    LimitExceeded(&'static str, u64),
+
+    #[error("trak[{0}] codec {1} is not supported")]
+    UnsupportedCodec(u32, FourCC),
+
     #[error("{0} version {1} is not supported")]
     UnsupportedBoxVersion(BoxType, u8),
+
+    /// Parsing a box failed, with where in the file it happened.
+    #[error("{source} (in {} at offset {offset})", format_path(.path))]
+    InBox {
+        /// The types of the innermost box that was being read when parsing failed, and of the
+        /// boxes it is nested in, outermost first, like `moov/trak/mdia/minf/stbl/stsz`.
+        path: Vec<FourCC>,
+
+        /// Offset in the file at which reading stopped.
+        offset: u64,
+
+        source: Box<Self>,
+    },
+
+    /// None of the samples are within the file, which needs to be `expected_bytes` long to
+    /// hold them, e.g. because its `mdat` was moved or cut off while it was being written.
+    #[error("media data is missing, the samples need a file of {expected_bytes} bytes")]
+    MediaDataMissing { expected_bytes: u64 },
+}
+
+impl Error {
+    /// The error without the context of [`Self::InBox`], for matching on what went wrong.
+    pub fn without_context(&self) -> &Self {
+        match self {
+            Self::InBox { source, .. } => source.without_context(),
+            _ => self,
+        }
+    }
+}
+
+fn format_path(path: &[FourCC]) -> String {
+    path.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
 }
@@ -0,0 +1,110 @@
+//! The events of `emsg` boxes, like the ad markers and ID3 tags of DASH and CMAF streams, see
+//! ISO/IEC 23009-1 section 5.10.3.3.
+
+use crate::{EmsgBox, FourCC, Mp4};
+
+/// The event duration of `emsg` that means the duration is unknown.
+const UNKNOWN_DURATION: u32 = 0xffff_ffff;
+
+/// An event of an `emsg` box with its times resolved, see [`Mp4::events`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmsgEvent<'a> {
+    pub emsg: &'a EmsgBox,
+
+    /// Index in [`Mp4::moofs`] of the first movie fragment after the `emsg`, which starts the
+    /// segment that the event was sent in, or `None` if there is none or the [`Mp4`] was not read
+    /// from a file.
+    pub moof_index: Option<usize>,
+
+    /// Time on the presentation timeline at which the event starts, in the timescale passed to
+    /// [`Mp4::events`].
+    ///
+    /// `None` for events of version 0, whose time is relative to their segment, if the segment is
+    /// not known, see [`Self::moof_index`].
+    pub presentation_time: Option<u64>,
+
+    /// Duration of the event in the timescale passed to [`Mp4::events`], or `None` if unknown.
+    pub duration: Option<u64>,
+}
+
+impl EmsgEvent<'_> {
+    /// The scheme of the event, like `urn:scte:scte35:2013:bin` for SCTE-35 splice information.
+    pub fn scheme_id_uri(&self) -> &str {
+        &self.emsg.scheme_id_uri
+    }
+
+    /// The payload of the event, whose format depends on [`Self::scheme_id_uri`].
+    pub fn message_data(&self) -> &[u8] {
+        &self.emsg.message_data
+    }
+}
+
+impl Mp4 {
+    /// The events of the top-level `emsg` boxes, in file order, with times in units of
+    /// `1 / timescale` seconds.
+    ///
+    /// Events of version 1 have an absolute time. The time of events of version 0 is relative to
+    /// the start of their segment, which is taken to be the earliest `tfdt` of the movie fragment
+    /// that follows the `emsg`.
+    pub fn events(&self, timescale: u32) -> impl Iterator<Item = EmsgEvent<'_>> + '_ {
+        let emsg_type = FourCC::from(*b"emsg");
+        let offsets: Vec<u64> = self
+            .box_tree()
+            .iter()
+            .filter(|node| node.box_type == emsg_type)
+            .map(|node| node.offset)
+            .collect();
+
+        self.emsgs.iter().enumerate().map(move |(index, emsg)| {
+            let moof_index = offsets
+                .get(index)
+                .filter(|_| offsets.len() == self.emsgs.len())
+                .and_then(|&offset| self.moofs.iter().position(|moof| moof.start > offset));
+            let presentation_time = match (emsg.presentation_time, emsg.presentation_time_delta) {
+                (Some(time), _) => Some(rescale(time, emsg.timescale.into(), timescale)),
+                (None, Some(delta)) => moof_index
+                    .and_then(|moof_index| self.segment_start(moof_index, timescale))
+                    .map(|start| {
+                        start.saturating_add(rescale(
+                            delta.into(),
+                            emsg.timescale.into(),
+                            timescale,
+                        ))
+                    }),
+                (None, None) => None,
+            };
+            let duration = (emsg.event_duration != UNKNOWN_DURATION)
+                .then(|| rescale(emsg.event_duration.into(), emsg.timescale.into(), timescale));
+            EmsgEvent {
+                emsg,
+                moof_index,
+                presentation_time,
+                duration,
+            }
+        })
+    }
+
+    /// The earliest `tfdt` of the movie fragment at `moof_index` in units of `1 / timescale`
+    /// seconds, or `None` if it has none.
+    fn segment_start(&self, moof_index: usize, timescale: u32) -> Option<u64> {
+        self.moofs[moof_index]
+            .trafs
+            .iter()
+            .filter_map(|traf| {
+                let tfdt = traf.tfdt.as_ref()?;
+                let track = self.tracks().get(&traf.tfhd.track_id)?;
+                Some(rescale(
+                    tfdt.base_media_decode_time,
+                    track.timescale,
+                    timescale,
+                ))
+            })
+            .min()
+    }
+}
+
+/// Converts `time` from units of `1 / from` seconds to units of `1 / to` seconds.
+fn rescale(time: u64, from: u64, to: u32) -> u64 {
+    let time = u128::from(time) * u128::from(to) / u128::from(from.max(1));
+    u64::try_from(time).unwrap_or(u64::MAX)
+}
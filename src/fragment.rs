@@ -0,0 +1,307 @@
+use std::ops::Range;
+
+use crate::{
+    BoxType, Error, MoofBox, MoovBox, Result, SampleFlags, TfhdBox, TrackId, TrafBox, TrexBox,
+    TrunBox,
+};
+
+/// Which box a fragment sample default was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultSource {
+    /// The track fragment header (`tfhd`) of the fragment.
+    Tfhd,
+
+    /// The track extends box (`trex`) in `moov`, which applies to all fragments of the track.
+    Trex,
+}
+
+/// A sample default, together with the box it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentDefault {
+    pub value: u32,
+    pub source: DefaultSource,
+}
+
+/// The sample defaults that apply to one track in one fragment.
+///
+/// Samples use these for every value their `trun` does not specify. A default is `None` if
+/// neither `tfhd` nor `trex` provides it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FragmentDefaults {
+    pub sample_duration: Option<FragmentDefault>,
+    pub sample_size: Option<FragmentDefault>,
+    pub sample_flags: Option<FragmentDefault>,
+}
+
+impl FragmentDefaults {
+    /// Resolves the defaults of a track fragment: values in `tfhd` take precedence over `trex`.
+    pub(crate) fn resolve(tfhd: &TfhdBox, trex: Option<&TrexBox>) -> Self {
+        let resolve = |tfhd_value: Option<u32>, trex_value: fn(&TrexBox) -> u32| {
+            let from_tfhd = tfhd_value.map(|value| FragmentDefault {
+                value,
+                source: DefaultSource::Tfhd,
+            });
+            from_tfhd.or_else(|| {
+                trex.map(|trex| FragmentDefault {
+                    value: trex_value(trex),
+                    source: DefaultSource::Trex,
+                })
+            })
+        };
+
+        Self {
+            sample_duration: resolve(tfhd.default_sample_duration, |trex| {
+                trex.default_sample_duration
+            }),
+            sample_size: resolve(tfhd.default_sample_size, |trex| trex.default_sample_size),
+            sample_flags: resolve(tfhd.default_sample_flags, |trex| trex.default_sample_flags),
+        }
+    }
+}
+
+/// A movie fragment (`moof`) of a fragmented file, see [`crate::Mp4::fragments`].
+///
+/// Meant for diagnosing how the samples of a fragment were derived.
+#[derive(Debug, Clone, Copy)]
+pub struct FragmentInfo<'a> {
+    pub(crate) moov: &'a MoovBox,
+    pub(crate) moof: &'a MoofBox,
+
+    /// See [`crate::Mp4::data_limit`].
+    pub(crate) data_limit: Option<u64>,
+}
+
+impl<'a> FragmentInfo<'a> {
+    pub fn moof(&self) -> &'a MoofBox {
+        self.moof
+    }
+
+    /// The track fragment of the given track, if the track has samples in this fragment.
+    pub fn traf(&self, track_id: TrackId) -> Option<&'a TrafBox> {
+        self.moof
+            .trafs
+            .iter()
+            .find(|traf| traf.tfhd.track_id == track_id)
+    }
+
+    /// The defaults declared for the given track in `moov`, regardless of whether this fragment overrides them.
+    pub fn trex(&self, track_id: TrackId) -> Option<&'a TrexBox> {
+        self.moov
+            .mvex
+            .as_ref()?
+            .trexs
+            .iter()
+            .find(|trex| trex.track_id == track_id)
+    }
+
+    /// The defaults that apply to the samples of the given track in this fragment.
+    ///
+    /// Returns `None` if the track has no samples in this fragment.
+    pub fn effective_defaults(&self, track_id: TrackId) -> Option<FragmentDefaults> {
+        let traf = self.traf(track_id)?;
+        Some(FragmentDefaults::resolve(&traf.tfhd, self.trex(track_id)))
+    }
+
+    /// The samples of the given track in this fragment, as described by the fragment alone,
+    /// before they are added to the samples of the track.
+    ///
+    /// Empty if the track has no samples in this fragment. Fails if a sample has no duration
+    /// or size, neither in its `trun` nor in the defaults, or if a run declares more samples
+    /// than the file holds.
+    pub fn samples(&self, track_id: TrackId) -> Result<Vec<FragmentSample>> {
+        let mut samples = Vec::new();
+        let mut data_end = self.moof.start;
+        for traf in &self.moof.trafs {
+            let trex = self.trex(traf.tfhd.track_id);
+            let traf_samples = traf_samples(self.moof, traf, trex, self.data_limit, &mut data_end)?;
+            if traf.tfhd.track_id == track_id {
+                samples.extend(traf_samples);
+            }
+        }
+        Ok(samples)
+    }
+}
+
+/// A sample of a track fragment, see [`FragmentInfo::samples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentSample {
+    /// Offset of the sample data in the file, resolved from the base data offset of `tfhd`, or
+    /// the start of `moof`, and the data offset of `trun`.
+    pub offset: u64,
+    pub size: u64,
+    pub duration: u64,
+
+    /// The `tfdt` of the track fragment plus the durations of the samples before this one, or
+    /// `None` if the track fragment has no `tfdt`.
+    pub decode_time: Option<u64>,
+
+    /// The composition time offset of `trun`, which is signed in version 1 and read as signed
+    /// in version 0 too, see [`crate::Sample::composition_timestamp`].
+    pub composition_offset: i32,
+
+    /// The sample flags, from `trun` or the defaults.
+    pub flags: SampleFlags,
+}
+
+impl FragmentSample {
+    /// Whether the sample is a sync sample, see [`SampleFlags::is_sync`].
+    pub fn is_sync(&self) -> bool {
+        self.flags.is_sync()
+    }
+
+    /// The range of bytes of the sample in the file.
+    pub fn file_range(&self) -> Range<u64> {
+        self.offset..self.offset.saturating_add(self.size)
+    }
+}
+
+/// Resolves the samples of `traf`, one of the track fragments of `moof`.
+///
+/// `data_end` is the end of the data of the previous track fragment of `moof`, or the start of
+/// `moof` for the first one, and is set to the end of the data of `traf`. Track fragments
+/// flagged as empty in `tfhd` have no samples.
+///
+/// Runs whose samples all have the default size must end before `data_limit`, counting empty
+/// samples as one byte each. Nothing else bounds their sample count, which may be in the
+/// billions for a run of a few bytes.
+pub(crate) fn traf_samples(
+    moof: &MoofBox,
+    traf: &TrafBox,
+    trex: Option<&TrexBox>,
+    data_limit: Option<u64>,
+    data_end: &mut u64,
+) -> Result<Vec<FragmentSample>> {
+    let tfhd = &traf.tfhd;
+    if tfhd.flags & TfhdBox::FLAG_DURATION_IS_EMPTY != 0 {
+        return Ok(Vec::new());
+    }
+
+    // Without `trex`, values that are in neither `trun` nor `tfhd` are missing.
+    let defaults = FragmentDefaults::resolve(tfhd, trex);
+    let default_sample_duration = defaults.sample_duration.map(|d| d.value);
+    let default_sample_size = defaults.sample_size.map(|d| d.value);
+    let default_sample_flags = defaults.sample_flags.map_or(0, |d| d.value);
+
+    // Data offsets of runs are relative to the base data offset of `tfhd` if it has one, else
+    // to the start of `moof` for the first track fragment or with `default-base-is-moof`, and
+    // else to the end of the data of the previous track fragment.
+    let base_data_offset = if tfhd.flags & TfhdBox::FLAG_BASE_DATA_OFFSET != 0 {
+        tfhd.base_data_offset.unwrap_or(moof.start)
+    } else if tfhd.flags & TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF != 0 {
+        moof.start
+    } else {
+        *data_end
+    };
+
+    let mut samples = Vec::new();
+    let mut decode_time = traf.tfdt.as_ref().map(|tfdt| tfdt.base_media_decode_time);
+    for (trun_index, trun) in traf.truns.iter().enumerate() {
+        // A run without a data offset starts where the previous run of the track fragment ends.
+        let mut offset = match trun.data_offset {
+            Some(data_offset) if trun.flags & TrunBox::FLAG_DATA_OFFSET != 0 => {
+                base_data_offset.saturating_add_signed(i64::from(data_offset))
+            }
+            _ if trun_index == 0 => base_data_offset,
+            _ => *data_end,
+        };
+
+        if let (Some(data_limit), Some(size)) = (data_limit, default_sample_size) {
+            if trun.flags & TrunBox::FLAG_SAMPLE_SIZE == 0 {
+                let run_size = u64::from(trun.sample_count) * u64::from(size.max(1));
+                if run_size > data_limit.saturating_sub(offset) {
+                    return Err(Error::InvalidData(
+                        "trun declares more samples than the file holds",
+                    ));
+                }
+            }
+        }
+
+        for sample_n in 0..trun.sample_count as usize {
+            let mut flags = default_sample_flags;
+            if trun.flags & TrunBox::FLAG_SAMPLE_FLAGS != 0 {
+                flags = trun.sample_flags.get(sample_n).copied().unwrap_or(flags);
+            } else if sample_n == 0 && (trun.flags & TrunBox::FLAG_FIRST_SAMPLE_FLAGS != 0) {
+                flags = trun.first_sample_flags.unwrap_or(flags);
+            }
+
+            let composition_offset = if trun.flags & TrunBox::FLAG_SAMPLE_CTS != 0 {
+                trun.sample_cts
+                    .get(sample_n)
+                    .copied()
+                    .unwrap_or(0)
+                    .cast_signed()
+            } else {
+                0
+            };
+
+            let duration = u64::from(
+                trun.sample_durations
+                    .get(sample_n)
+                    .copied()
+                    .or(default_sample_duration)
+                    .ok_or(Error::BoxInTrafNotFound(tfhd.track_id, BoxType::TrexBox))?,
+            );
+            let size = u64::from(
+                trun.sample_sizes
+                    .get(sample_n)
+                    .copied()
+                    .or(default_sample_size)
+                    .ok_or(Error::BoxInTrafNotFound(tfhd.track_id, BoxType::TrexBox))?,
+            );
+
+            samples.push(FragmentSample {
+                offset,
+                size,
+                duration,
+                decode_time,
+                composition_offset,
+                flags: SampleFlags::from_u32(flags),
+            });
+            offset = offset.saturating_add(size);
+            decode_time = decode_time.map(|time| time.saturating_add(duration));
+        }
+        *data_end = offset;
+    }
+    Ok(samples)
+}
+
+/// The duration of a fragmented movie as declared in `mehd`, next to the duration its tracks
+/// actually have, both in the timescale of `mvhd`. See [`crate::Mp4::fragment_duration`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentDuration {
+    pub declared: u64,
+
+    /// The duration of the longest track, including the samples of all fragments.
+    pub actual: u64,
+}
+
+impl FragmentDuration {
+    /// Whether the declared duration is the actual one, give or take the rounding of the track
+    /// durations to the timescale of the movie.
+    pub fn is_consistent(&self) -> bool {
+        self.declared.abs_diff(self.actual) <= 1
+    }
+}
+
+/// How the samples of a track are spread over the fragments of a file, see
+/// [`crate::Track::fragment_stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TrackFragmentStats {
+    /// The number of samples in each fragment of the track, in file order.
+    ///
+    /// Fragments without a `traf` of the track are left out, while those with one but no
+    /// samples, like fragments flagged as empty in `tfhd`, count as fragments without samples.
+    pub samples_per_fragment: Vec<u64>,
+
+    /// The smallest `tfdt` of the fragments of the track, in the timescale of the track.
+    pub earliest_tfdt: Option<u64>,
+
+    /// The largest `tfdt` of the fragments of the track, in the timescale of the track.
+    pub latest_tfdt: Option<u64>,
+}
+
+impl TrackFragmentStats {
+    pub fn fragment_count(&self) -> usize {
+        self.samples_per_fragment.len()
+    }
+}
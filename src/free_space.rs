@@ -0,0 +1,123 @@
+//! The free space of a file, for rewriting boxes in place, see [`FreeSpace`].
+
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::{BoxHeader, BoxType, FourCC, Mp4, Result, HEADER_SIZE};
+
+/// The top-level space of a file that may be overwritten, like `free` and `skip` boxes and the
+/// boxes that an edit replaces, for finding room for a box that grew.
+///
+/// Space is handed out like by an allocator: [`Self::release`] marks a box as free, and
+/// [`Self::allocate`] finds room for a new box, growing the file if needed. Adjacent runs of
+/// free space are merged, and once the new boxes are written, [`Self::write_free_boxes`] turns
+/// every run into a single `free` box, which readers skip as the standard requires.
+///
+/// Runs are only used if they fit a box exactly or leave room for the `free` box that marks the
+/// rest, since boxes can't be smaller than their header. Only the run at the end of the file may
+/// be outgrown, in which case the file grows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeSpace {
+    /// Offset and size of every run of free space, in file order.
+    runs: Vec<(u64, u64)>,
+
+    file_size: u64,
+}
+
+impl FreeSpace {
+    /// No free space in a file of `file_size` bytes.
+    pub fn new(file_size: u64) -> Self {
+        Self {
+            runs: Vec::new(),
+            file_size,
+        }
+    }
+
+    /// Offset and size of every run of free space, in file order.
+    pub fn runs(&self) -> &[(u64, u64)] {
+        &self.runs
+    }
+
+    /// The size of the file, including the boxes that [`Self::allocate`] appended.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Total size of all runs of free space.
+    pub fn total(&self) -> u64 {
+        self.runs.iter().map(|&(_, size)| size).sum()
+    }
+
+    /// Marks the `size` bytes at `offset`, usually a whole box, as free.
+    pub fn release(&mut self, offset: u64, size: u64) {
+        if size == 0 {
+            return;
+        }
+        let index = self.runs.partition_point(|&(start, _)| start < offset);
+        self.runs.insert(index, (offset, size));
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.runs.len());
+        for &(start, size) in &self.runs {
+            match merged.last_mut() {
+                Some((last_start, last_size)) if *last_start + *last_size >= start => {
+                    *last_size = (*last_size).max(start + size - *last_start);
+                }
+                _ => merged.push((start, size)),
+            }
+        }
+        self.runs = merged;
+        self.file_size = self.file_size.max(offset + size);
+    }
+
+    /// Finds room for a box of `size` bytes and returns its offset.
+    ///
+    /// Takes the first run that fits, or else appends the box to the end of the file.
+    pub fn allocate(&mut self, size: u64) -> u64 {
+        for index in 0..self.runs.len() {
+            let (offset, run_size) = self.runs[index];
+            let at_end = offset + run_size >= self.file_size;
+            if size == run_size || size + HEADER_SIZE <= run_size || (at_end && size > run_size) {
+                if size < run_size {
+                    self.runs[index] = (offset + size, run_size - size);
+                } else {
+                    self.runs.remove(index);
+                }
+                self.file_size = self.file_size.max(offset + size);
+                return offset;
+            }
+        }
+        let offset = self.file_size;
+        self.file_size += size;
+        offset
+    }
+
+    /// Writes a `free` box header at the start of every run, over the boxes it covers.
+    pub fn write_free_boxes<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        for &(offset, size) in &self.runs {
+            writer.seek(SeekFrom::Start(offset))?;
+            BoxHeader::new(BoxType::FreeBox, size).write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Mp4 {
+    /// The top-level `free`, `skip` and `wide` boxes of the file, see [`FreeSpace`].
+    ///
+    /// Empty if the [`Mp4`] was not read from a file.
+    pub fn free_space(&self) -> FreeSpace {
+        let top_level = self.box_tree();
+        let file_size = top_level.last().map_or(0, |node| node.offset + node.size);
+        let mut space = FreeSpace::new(file_size);
+        for node in top_level {
+            if is_space(node.box_type) {
+                space.release(node.offset, node.size);
+            }
+        }
+        space
+    }
+}
+
+/// Whether boxes of `box_type` only take up space.
+pub(crate) fn is_space(box_type: impl Into<FourCC>) -> bool {
+    matches!(&box_type.into().value, b"free" | b"skip" | b"wide")
+}
@@ -0,0 +1,96 @@
+//! The size and shape of the pictures of video tracks.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ClapBox, PaspBox};
+
+/// The coded size of the pictures of a visual sample entry, along with its pixel aspect ratio
+/// (`pasp`) and clean aperture (`clap`), see [`crate::StsdBoxContent::video_geometry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoGeometry {
+    /// Coded width in pixels.
+    pub width: u16,
+
+    /// Coded height in pixels.
+    pub height: u16,
+
+    pub pasp: Option<PaspBox>,
+    pub clap: Option<ClapBox>,
+}
+
+/// A rectangle of a picture in whole pixels, see [`VideoGeometry::clean_aperture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl VideoGeometry {
+    /// The part of the coded picture that is meant to be displayed.
+    ///
+    /// That is the clean aperture rounded to whole pixels and clamped to the picture, or the
+    /// whole picture if there is no `clap` or it has a zero denominator.
+    pub fn clean_aperture(&self) -> CropRect {
+        let (width, height) = (u32::from(self.width), u32::from(self.height));
+        let whole = CropRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        let Some(clap) = &self.clap else {
+            return whole;
+        };
+        let (Some(clean_width), Some(clean_height), Some(horiz_off), Some(vert_off)) = (
+            ratio(
+                f64::from(clap.clean_aperture_width_n),
+                clap.clean_aperture_width_d,
+            ),
+            ratio(
+                f64::from(clap.clean_aperture_height_n),
+                clap.clean_aperture_height_d,
+            ),
+            ratio(f64::from(clap.horiz_off_n), clap.horiz_off_d),
+            ratio(f64::from(clap.vert_off_n), clap.vert_off_d),
+        ) else {
+            return whole;
+        };
+
+        // The offsets are those of the center of the clean aperture from the center of the picture.
+        let (x, clean_width) = clamp_span(horiz_off, clean_width, width);
+        let (y, clean_height) = clamp_span(vert_off, clean_height, height);
+        CropRect {
+            x,
+            y,
+            width: clean_width,
+            height: clean_height,
+        }
+    }
+
+    /// The width and height to display the [`Self::clean_aperture`] at, with its width stretched
+    /// by the pixel aspect ratio.
+    pub fn display_size(&self) -> (u32, u32) {
+        let clean = self.clean_aperture();
+        let width = self
+            .pasp
+            .map_or(clean.width, |pasp| pasp.scale_width(clean.width));
+        (width, clean.height)
+    }
+}
+
+fn ratio(n: f64, d: u32) -> Option<f64> {
+    (d != 0).then(|| n / f64::from(d))
+}
+
+/// The start and length in whole pixels of a span of `length` pixels of a picture `size` pixels
+/// long, whose center is `offset` pixels from the center of the picture.
+fn clamp_span(offset: f64, length: f64, size: u32) -> (u32, u32) {
+    let size_f = f64::from(size);
+    let start = (offset + (size_f - length) / 2.0)
+        .round()
+        .clamp(0.0, size_f);
+    let end = (start + length.round()).clamp(start, size_f);
+    (start as u32, (end - start) as u32)
+}
@@ -0,0 +1,317 @@
+//! Image items of HEIF and AVIF files, see ISO/IEC 23008-12.
+//!
+//! Unlike video, the images of these files are not samples of a track, but items described by
+//! the boxes in the file-level `meta` box: `iinf` lists the items, `iloc` tells where their
+//! data is, `iprp` holds their properties such as the codec configuration, and `pitm` names
+//! the primary item, which is the image to show.
+
+use std::io::Cursor;
+use std::ops::Range;
+
+use byteorder::{BigEndian, ReadBytesExt as _};
+
+use crate::{
+    Av1CBox, BoxType, Error, FourCC, HevcDecoderConfigurationRecord, MetaBox, Mp4, RawBox,
+    ReadBox as _, Result, HEADER_SIZE,
+};
+
+/// An item in the `meta` box of a HEIF or AVIF file, see [`Mp4::image_items`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageItem {
+    pub item_id: u32,
+
+    /// Such as `av01` or `hvc1` for coded images, `grid` for images made of tiles, or `Exif`.
+    ///
+    /// Zero for items described by version 0 or 1 of `infe`, which have no type.
+    pub item_type: FourCC,
+
+    pub name: String,
+
+    /// Whether the item is not meant to be shown on its own, like the tiles of a grid.
+    pub hidden: bool,
+
+    /// The decoder configuration of a coded image, from its `av1C` or `hvcC` property.
+    pub codec_config: Option<ImageCodecConfig>,
+
+    /// Width and height of the image in pixels, from its `ispe` property.
+    pub dimensions: Option<(u32, u32)>,
+
+    /// Ranges of the file that hold the data of the item, in order.
+    pub extents: Vec<Range<u64>>,
+}
+
+/// The decoder configuration of an image item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageCodecConfig {
+    Av1(Av1CBox),
+    Hevc(RawBox<HevcDecoderConfigurationRecord>),
+}
+
+impl ImageItem {
+    /// Size of the data of the item in bytes.
+    pub fn data_size(&self) -> u64 {
+        self.extents
+            .iter()
+            .map(|extent| extent.end - extent.start)
+            .sum()
+    }
+
+    /// Copies the data of the item out of `data`, the file it was parsed from.
+    ///
+    /// Returns `None` if an extent lies beyond the end of `data`.
+    pub fn bytes(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(usize::try_from(self.data_size()).ok()?);
+        for extent in &self.extents {
+            let start = usize::try_from(extent.start).ok()?;
+            let end = usize::try_from(extent.end).ok()?;
+            bytes.extend_from_slice(data.get(start..end)?);
+        }
+        Some(bytes)
+    }
+}
+
+/// The items of the file-level `meta` box, ordered like `iinf`.
+pub(crate) fn image_items(mp4: &Mp4) -> Result<Vec<ImageItem>> {
+    let Some(meta) = &mp4.meta else {
+        return Ok(Vec::new());
+    };
+    let Some(iinf) = meta.iinf() else {
+        return Ok(Vec::new());
+    };
+
+    let mut items = read_iinf(iinf)?;
+
+    if let Some(iloc) = meta.iloc() {
+        // Offsets of items with construction method 1 are relative to the `idat` box of `meta`.
+        let idat_start = mp4
+            .box_tree()
+            .iter()
+            .find(|node| node.box_type == BoxType::MetaBox.into())
+            .and_then(|node| {
+                node.children
+                    .iter()
+                    .find(|child| child.box_type.value == *b"idat")
+            })
+            .map(|idat| idat.data_range().start);
+        for (item_id, extents) in read_iloc(iloc, idat_start)? {
+            if let Some(item) = items.iter_mut().find(|item| item.item_id == item_id) {
+                item.extents = extents;
+            }
+        }
+    }
+
+    if let Some(iprp) = meta.child_data(BoxType::IprpBox) {
+        read_iprp(iprp, &mut items)?;
+    }
+
+    Ok(items)
+}
+
+/// The item id in `pitm`, if there is one.
+pub(crate) fn primary_item_id(meta: &MetaBox) -> Result<Option<u32>> {
+    let Some(mut pitm) = meta.child_data(BoxType::PitmBox) else {
+        return Ok(None);
+    };
+    let version = pitm.read_u32::<BigEndian>()? >> 24;
+    Ok(Some(if version == 0 {
+        u32::from(pitm.read_u16::<BigEndian>()?)
+    } else {
+        pitm.read_u32::<BigEndian>()?
+    }))
+}
+
+fn read_iinf(mut iinf: &[u8]) -> Result<Vec<ImageItem>> {
+    let version = iinf.read_u32::<BigEndian>()? >> 24;
+    if version == 0 {
+        iinf.read_u16::<BigEndian>()?;
+    } else {
+        iinf.read_u32::<BigEndian>()?;
+    }
+
+    let mut items = Vec::new();
+    for (box_type, mut infe) in child_boxes(iinf)? {
+        if box_type.value != *b"infe" {
+            continue;
+        }
+        let version_and_flags = infe.read_u32::<BigEndian>()?;
+        let version = version_and_flags >> 24;
+        let item_id = match version {
+            0..=2 => u32::from(infe.read_u16::<BigEndian>()?),
+            _ => infe.read_u32::<BigEndian>()?,
+        };
+        infe.read_u16::<BigEndian>()?; // item_protection_index
+        let item_type = if version >= 2 {
+            FourCC::from(infe.read_u32::<BigEndian>()?)
+        } else {
+            FourCC::default()
+        };
+        let name = infe.split(|&byte| byte == 0).next().unwrap_or_default();
+
+        items.push(ImageItem {
+            item_id,
+            item_type,
+            name: String::from_utf8_lossy(name).into_owned(),
+            hidden: version_and_flags & 1 != 0,
+            codec_config: None,
+            dimensions: None,
+            extents: Vec::new(),
+        });
+    }
+    Ok(items)
+}
+
+fn read_iloc(mut iloc: &[u8], idat_start: Option<u64>) -> Result<Vec<(u32, Vec<Range<u64>>)>> {
+    let version = iloc.read_u32::<BigEndian>()? >> 24;
+    let sizes = iloc.read_u8()?;
+    let (offset_size, length_size) = (sizes >> 4, sizes & 0xf);
+    let sizes = iloc.read_u8()?;
+    let base_offset_size = sizes >> 4;
+    let index_size = if version == 0 { 0 } else { sizes & 0xf };
+    let item_count = if version < 2 {
+        u32::from(iloc.read_u16::<BigEndian>()?)
+    } else {
+        iloc.read_u32::<BigEndian>()?
+    };
+
+    let mut locations = Vec::new();
+    for _ in 0..item_count {
+        let item_id = if version < 2 {
+            u32::from(iloc.read_u16::<BigEndian>()?)
+        } else {
+            iloc.read_u32::<BigEndian>()?
+        };
+        let construction_method = if version == 0 {
+            0
+        } else {
+            iloc.read_u16::<BigEndian>()? & 0xf
+        };
+        let data_reference_index = iloc.read_u16::<BigEndian>()?;
+        let base_offset = read_sized(&mut iloc, base_offset_size)?;
+        let extent_count = iloc.read_u16::<BigEndian>()?;
+
+        let base_offset = match (construction_method, data_reference_index) {
+            (0, 0) => base_offset,
+            (1, _) => {
+                idat_start.ok_or(Error::InvalidData("iloc refers to a missing idat box"))?
+                    + base_offset
+            }
+            (0, _) => return Err(Error::InvalidData("image item data in another file")),
+            _ => return Err(Error::InvalidData("unsupported iloc construction method")),
+        };
+
+        let mut extents = Vec::with_capacity(usize::from(extent_count).min(iloc.len()));
+        for _ in 0..extent_count {
+            read_sized(&mut iloc, index_size)?;
+            let offset = read_sized(&mut iloc, offset_size)?;
+            let length = read_sized(&mut iloc, length_size)?;
+            if length == 0 {
+                return Err(Error::InvalidData("iloc extent of unknown length"));
+            }
+            let start = base_offset
+                .checked_add(offset)
+                .ok_or(Error::InvalidData("iloc extent out of range"))?;
+            let end = start
+                .checked_add(length)
+                .ok_or(Error::InvalidData("iloc extent out of range"))?;
+            extents.push(start..end);
+        }
+        locations.push((item_id, extents));
+    }
+    Ok(locations)
+}
+
+/// Reads the properties in `ipco`, and assigns them to the items as `ipma` says.
+fn read_iprp(iprp: &[u8], items: &mut [ImageItem]) -> Result<()> {
+    let children = child_boxes(iprp)?;
+    let Some((_, ipco)) = children
+        .iter()
+        .find(|(box_type, _)| box_type.value == *b"ipco")
+    else {
+        return Ok(());
+    };
+    let properties = child_boxes(ipco)?;
+
+    for (_, mut ipma) in children
+        .iter()
+        .filter(|(box_type, _)| box_type.value == *b"ipma")
+    {
+        let version_and_flags = ipma.read_u32::<BigEndian>()?;
+        let version = version_and_flags >> 24;
+        let large_indices = version_and_flags & 1 != 0;
+        let entry_count = ipma.read_u32::<BigEndian>()?;
+        for _ in 0..entry_count {
+            let item_id = if version < 1 {
+                u32::from(ipma.read_u16::<BigEndian>()?)
+            } else {
+                ipma.read_u32::<BigEndian>()?
+            };
+            let association_count = ipma.read_u8()?;
+            let mut item = items.iter_mut().find(|item| item.item_id == item_id);
+            for _ in 0..association_count {
+                // The top bit tells whether the property is essential.
+                let index = if large_indices {
+                    usize::from(ipma.read_u16::<BigEndian>()? & 0x7fff)
+                } else {
+                    usize::from(ipma.read_u8()? & 0x7f)
+                };
+                // Index 0 means no property, the others are 1-based.
+                let (Some(item), Some(&(box_type, contents))) = (
+                    item.as_deref_mut(),
+                    index.checked_sub(1).and_then(|i| properties.get(i)),
+                ) else {
+                    continue;
+                };
+                read_property(item, box_type, contents)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_property(item: &mut ImageItem, box_type: FourCC, mut contents: &[u8]) -> Result<()> {
+    let size = contents.len() as u64 + HEADER_SIZE;
+    match &box_type.value {
+        b"av1C" => {
+            let av1c = Av1CBox::read_box(&mut Cursor::new(contents), size)?;
+            item.codec_config = Some(ImageCodecConfig::Av1(av1c));
+        }
+        b"hvcC" => {
+            let hvcc = RawBox::read_box(&mut Cursor::new(contents), size)?;
+            item.codec_config = Some(ImageCodecConfig::Hevc(hvcc));
+        }
+        b"ispe" => {
+            contents.read_u32::<BigEndian>()?; // version and flags
+            let width = contents.read_u32::<BigEndian>()?;
+            let height = contents.read_u32::<BigEndian>()?;
+            item.dimensions = Some((width, height));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The type and contents of the boxes in `data`.
+fn child_boxes(mut data: &[u8]) -> Result<Vec<(FourCC, &[u8])>> {
+    let mut boxes = Vec::new();
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let box_type = FourCC::from([data[4], data[5], data[6], data[7]]);
+        let size = usize::try_from(size).map_err(|_err| Error::InvalidData("box too large"))?;
+        if size < 8 || size > data.len() {
+            return Err(Error::InvalidData("invalid box size in meta"));
+        }
+        boxes.push((box_type, &data[8..size]));
+        data = &data[size..];
+    }
+    Ok(boxes)
+}
+
+/// Reads an unsigned integer of 0, 4 or 8 bytes, as used for the offsets and lengths in `iloc`.
+fn read_sized(reader: &mut &[u8], size: u8) -> Result<u64> {
+    match size {
+        0 => Ok(0),
+        4 => Ok(u64::from(reader.read_u32::<BigEndian>()?)),
+        8 => Ok(reader.read_u64::<BigEndian>()?),
+        _ => Err(Error::InvalidData("invalid iloc field size")),
+    }
+}
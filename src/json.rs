@@ -0,0 +1,154 @@
+//! Exporting a whole parsed file as one JSON document, see [`crate::Mp4::to_json`].
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{BoxNode, Mp4, Track};
+
+/// What [`Mp4::to_json`] includes, beyond the boxes and a summary of every track.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Include the entries of the sample tables (`stts`, `ctts`, `stss`, `stsc`, `stsz`, `stco`,
+    /// `co64`), edit lists, and track runs, which [`crate::Mp4Box::to_json`] leaves out.
+    pub sample_tables: bool,
+
+    /// Include every sample of every track, with its offset, size, and timestamps.
+    pub samples: bool,
+
+    /// Indent the output, for reading and diffing.
+    pub pretty: bool,
+}
+
+pub(crate) fn to_json(mp4: &Mp4, options: JsonOptions) -> String {
+    let mut moov = to_value(&mp4.moov);
+    let mut moofs = to_value(&mp4.moofs);
+    if options.sample_tables {
+        add_sample_tables(mp4, &mut moov, &mut moofs);
+    }
+
+    let document = json!({
+        "boxes": mp4.box_tree().iter().map(box_node).collect::<Vec<_>>(),
+        "ftyp": to_value(&mp4.ftyp),
+        "styps": to_value(&mp4.styps),
+        "moov": moov,
+        "moofs": moofs,
+        "emsgs": to_value(&mp4.emsgs),
+        "meta": to_value(&mp4.meta),
+        "mdats": mp4
+            .mdats
+            .iter()
+            .map(|&(offset, size)| json!({ "offset": offset, "size": size }))
+            .collect::<Vec<_>>(),
+        "tracks": mp4
+            .tracks()
+            .values()
+            .map(|track| track_summary(track, options.samples))
+            .collect::<Vec<_>>(),
+    });
+
+    // The maps of `serde_json` are sorted by key, so the output doesn't depend on the order of
+    // hash maps such as the items of `ilst`.
+    if options.pretty {
+        format!("{document:#}")
+    } else {
+        document.to_string()
+    }
+}
+
+fn to_value(value: &impl Serialize) -> Value {
+    serde_json::to_value(value).expect("Failed to convert to JSON")
+}
+
+/// Sets `value[key]`, if `value` is an object.
+fn set(value: &mut Value, key: &str, field: &impl Serialize) {
+    if let Some(object) = value.as_object_mut() {
+        object.insert(key.to_owned(), to_value(field));
+    }
+}
+
+fn add_sample_tables(mp4: &Mp4, moov: &mut Value, moofs: &mut Value) {
+    for (index, trak) in mp4.moov.traks.iter().enumerate() {
+        let trak_value = &mut moov["trak"][index];
+        if let Some(elst) = &trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            set(&mut trak_value["edts"]["elst"], "entries", &elst.entries);
+        }
+
+        let stbl = &trak.mdia.minf.stbl;
+        let stbl_value = &mut trak_value["mdia"]["minf"]["stbl"];
+        set(&mut stbl_value["stts"], "entries", &stbl.stts.entries);
+        set(&mut stbl_value["stsc"], "entries", &stbl.stsc.entries);
+        set(
+            &mut stbl_value["stsz"],
+            "sample_sizes",
+            &stbl.stsz.sample_sizes,
+        );
+        if let Some(ctts) = &stbl.ctts {
+            set(&mut stbl_value["ctts"], "entries", &ctts.entries);
+        }
+        if let Some(stss) = &stbl.stss {
+            set(&mut stbl_value["stss"], "entries", &stss.entries);
+        }
+        if let Some(stco) = &stbl.stco {
+            set(&mut stbl_value["stco"], "entries", &stco.entries);
+        }
+        if let Some(co64) = &stbl.co64 {
+            set(&mut stbl_value["co64"], "entries", &co64.entries);
+        }
+        if let Some(sdtp) = &stbl.sdtp {
+            set(&mut stbl_value["sdtp"], "entries", &sdtp.entries);
+        }
+    }
+
+    for (moof_index, moof) in mp4.moofs.iter().enumerate() {
+        for (traf_index, traf) in moof.trafs.iter().enumerate() {
+            for (trun_index, trun) in traf.truns.iter().enumerate() {
+                let trun_value = &mut moofs[moof_index]["traf"][traf_index]["truns"][trun_index];
+                set(trun_value, "sample_durations", &trun.sample_durations);
+                set(trun_value, "sample_sizes", &trun.sample_sizes);
+                set(trun_value, "sample_flags", &trun.sample_flags);
+                set(trun_value, "sample_cts", &trun.sample_cts);
+            }
+        }
+    }
+}
+
+fn box_node(node: &BoxNode) -> Value {
+    json!({
+        "type": node.box_type.to_string(),
+        "offset": node.offset,
+        "size": node.size,
+        "known": node.known,
+        "children": node.children.iter().map(box_node).collect::<Vec<_>>(),
+    })
+}
+
+fn track_summary(track: &Track, samples: bool) -> Value {
+    let mut summary = json!({
+        "track_id": track.track_id,
+        "kind": track.kind.map(|kind| format!("{kind:?}")),
+        "codec": track.codec_string(),
+        "width": track.width,
+        "height": track.height,
+        "timescale": track.timescale,
+        "duration": track.duration,
+        "sample_count": track.samples.len(),
+        "frame_rate": track.frame_rate(),
+    });
+    if samples {
+        summary["samples"] = track
+            .samples
+            .iter()
+            .map(|sample| {
+                json!({
+                    "offset": sample.offset,
+                    "size": sample.size,
+                    "decode_timestamp": sample.decode_timestamp,
+                    "composition_timestamp": sample.composition_timestamp,
+                    "duration": sample.duration,
+                    "is_sync": sample.is_sync,
+                })
+            })
+            .collect();
+    }
+    summary
+}
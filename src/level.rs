@@ -0,0 +1,310 @@
+//! Checking video tracks against the limits of the level signaled in their sample entry.
+//!
+//! Hardware decoders are typically configured from the signaled level alone,
+//! so a file that exceeds it may fail to play even though it is otherwise valid.
+
+use crate::{Av1CBox, AvcCBox, HevcDecoderConfigurationRecord};
+
+/// What a decoder has to sustain to play a video track.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DecoderRequirements {
+    /// Width of the pictures, in luma samples.
+    pub width: u64,
+
+    /// Height of the pictures, in luma samples.
+    pub height: u64,
+
+    /// Average number of frames per second.
+    pub frame_rate: f64,
+
+    /// Highest number of bits in the samples decoded during any one second.
+    pub max_bitrate: u64,
+}
+
+impl DecoderRequirements {
+    /// Number of luma samples per picture.
+    pub fn picture_size(&self) -> u64 {
+        self.width * self.height
+    }
+
+    /// Number of luma samples per second.
+    pub fn luma_sample_rate(&self) -> u64 {
+        (self.picture_size() as f64 * self.frame_rate).ceil() as u64
+    }
+}
+
+/// A limit imposed by a level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelLimit {
+    /// Maximum picture width, in luma samples.
+    Width,
+
+    /// Maximum picture height, in luma samples.
+    Height,
+
+    /// Maximum number of luma samples per picture.
+    PictureSize,
+
+    /// Maximum number of luma samples per second.
+    LumaSampleRate,
+
+    /// Maximum bitrate, in bits per second.
+    Bitrate,
+}
+
+/// A requirement of a track that exceeds what its signaled level allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelViolation {
+    pub limit: LevelLimit,
+
+    /// The value required by the track.
+    pub actual: u64,
+
+    /// The highest value the level allows.
+    pub maximum: u64,
+}
+
+/// Collects the limits that `actual` exceeds.
+fn violations(checks: &[(LevelLimit, u64, u64)]) -> Vec<LevelViolation> {
+    checks
+        .iter()
+        .filter(|(_, actual, maximum)| actual > maximum)
+        .map(|&(limit, actual, maximum)| LevelViolation {
+            limit,
+            actual,
+            maximum,
+        })
+        .collect()
+}
+
+/// Checks an AVC track against Table A-1 of ITU-T H.264.
+///
+/// Pictures are padded to whole macroblocks, as the limits are defined in macroblocks.
+pub(crate) fn avc_violations(
+    avcc: &AvcCBox,
+    requirements: &DecoderRequirements,
+) -> Option<Vec<LevelViolation>> {
+    let level = avcc.avc_level_indication;
+    let profile = avcc.avc_profile_indication;
+    // Level 1b is signaled as level 1.1 with constraint_set3_flag in the Baseline, Main and Extended profiles.
+    let is_level_1b = level == 9
+        || (level == 11
+            && matches!(profile, 66 | 77 | 88)
+            && avcc.profile_compatibility & 0x10 != 0);
+
+    // MaxMBPS, MaxFS and MaxBR (in units of cpbBrVclFactor bits per second).
+    let (max_mbps, max_fs, max_br): (u64, u64, u64) = match level {
+        _ if is_level_1b => (1_485, 99, 128),
+        10 => (1_485, 99, 64),
+        11 => (3_000, 396, 192),
+        12 => (6_000, 396, 384),
+        13 => (11_880, 396, 768),
+        20 => (11_880, 396, 2_000),
+        21 => (19_800, 792, 4_000),
+        22 => (20_250, 1_620, 4_000),
+        30 => (40_500, 1_620, 10_000),
+        31 => (108_000, 3_600, 14_000),
+        32 => (216_000, 5_120, 20_000),
+        40 => (245_760, 8_192, 20_000),
+        41 => (245_760, 8_192, 50_000),
+        42 => (522_240, 8_704, 50_000),
+        50 => (589_824, 22_080, 135_000),
+        51 => (983_040, 36_864, 240_000),
+        52 => (2_073_600, 36_864, 240_000),
+        60 => (4_177_920, 139_264, 240_000),
+        61 => (8_355_840, 139_264, 480_000),
+        62 => (16_711_680, 139_264, 800_000),
+        _ => return None,
+    };
+    let cpb_br_vcl_factor = match profile {
+        100 => 1_250,
+        110 => 3_000,
+        122 | 244 | 44 => 4_000,
+        _ => 1_000,
+    };
+
+    let padded = DecoderRequirements {
+        width: requirements.width.div_ceil(16) * 16,
+        height: requirements.height.div_ceil(16) * 16,
+        ..*requirements
+    };
+    // Neither dimension may exceed sqrt(8 * MaxFS) macroblocks.
+    let max_dimension = (8 * max_fs).isqrt() * 16;
+    Some(violations(&[
+        (LevelLimit::Width, padded.width, max_dimension),
+        (LevelLimit::Height, padded.height, max_dimension),
+        (LevelLimit::PictureSize, padded.picture_size(), max_fs * 256),
+        (
+            LevelLimit::LumaSampleRate,
+            padded.luma_sample_rate(),
+            max_mbps * 256,
+        ),
+        (
+            LevelLimit::Bitrate,
+            requirements.max_bitrate,
+            max_br * cpb_br_vcl_factor,
+        ),
+    ]))
+}
+
+/// Checks an HEVC track against Tables A.8 and A.9 of ITU-T H.265.
+///
+/// Bitrates are checked against the limits of the Main and Main 10 profiles.
+pub(crate) fn hevc_violations(
+    hvcc: &HevcDecoderConfigurationRecord,
+    requirements: &DecoderRequirements,
+) -> Option<Vec<LevelViolation>> {
+    // MaxLumaPs, MaxLumaSr and MaxBR for the Main and High tier (in units of 1000 bits per second).
+    // There is no High tier below level 4.
+    let (max_luma_ps, max_luma_sr, main_br, high_br): (u64, u64, u64, u64) =
+        match hvcc.general_level_idc {
+            30 => (36_864, 552_960, 128, 128),
+            60 => (122_880, 3_686_400, 1_500, 1_500),
+            63 => (245_760, 7_372_800, 3_000, 3_000),
+            90 => (552_960, 16_588_800, 6_000, 6_000),
+            93 => (983_040, 33_177_600, 10_000, 10_000),
+            120 => (2_228_224, 66_846_720, 12_000, 30_000),
+            123 => (2_228_224, 133_693_440, 20_000, 50_000),
+            150 => (8_912_896, 267_386_880, 25_000, 100_000),
+            153 => (8_912_896, 534_773_760, 40_000, 160_000),
+            156 => (8_912_896, 1_069_547_520, 60_000, 240_000),
+            180 => (35_651_584, 1_069_547_520, 60_000, 240_000),
+            183 => (35_651_584, 2_139_095_040, 120_000, 480_000),
+            186 => (35_651_584, 4_278_190_080, 240_000, 800_000),
+            _ => return None,
+        };
+    let max_br = if hvcc.general_tier_flag {
+        high_br
+    } else {
+        main_br
+    };
+
+    // Neither dimension may exceed sqrt(8 * MaxLumaPs).
+    let max_dimension = (8 * max_luma_ps).isqrt();
+    Some(violations(&[
+        (LevelLimit::Width, requirements.width, max_dimension),
+        (LevelLimit::Height, requirements.height, max_dimension),
+        (
+            LevelLimit::PictureSize,
+            requirements.picture_size(),
+            max_luma_ps,
+        ),
+        (
+            LevelLimit::LumaSampleRate,
+            requirements.luma_sample_rate(),
+            max_luma_sr,
+        ),
+        (
+            LevelLimit::Bitrate,
+            requirements.max_bitrate,
+            max_br * 1_000,
+        ),
+    ]))
+}
+
+/// Checks an AV1 track against Annex A.3 of the AV1 specification.
+pub(crate) fn av1_violations(
+    av1c: &Av1CBox,
+    requirements: &DecoderRequirements,
+) -> Option<Vec<LevelViolation>> {
+    // MaxPicSize, MaxHSize, MaxVSize, MaxDisplayRate, and MainMbps and HighMbps (in units of 0.1 Mbps).
+    // There is no High tier below level 4.0.
+    let limits: [u64; 6] = match av1c.level {
+        0 => [147_456, 2_048, 1_152, 4_423_680, 15, 15],
+        1 => [278_784, 2_816, 1_584, 8_363_520, 30, 30],
+        4 => [665_856, 4_352, 2_448, 19_975_680, 60, 60],
+        5 => [1_065_024, 5_504, 3_096, 31_950_720, 100, 100],
+        8 => [2_359_296, 6_144, 3_456, 70_778_880, 120, 300],
+        9 => [2_359_296, 6_144, 3_456, 141_557_760, 200, 500],
+        12 => [8_912_896, 8_192, 4_352, 267_386_880, 300, 1_000],
+        13 => [8_912_896, 8_192, 4_352, 534_773_760, 400, 1_600],
+        14 | 15 => [8_912_896, 8_192, 4_352, 1_069_547_520, 600, 2_400],
+        16 => [35_651_584, 16_384, 8_704, 1_069_547_520, 600, 2_400],
+        17 => [35_651_584, 16_384, 8_704, 2_139_095_040, 1_000, 4_800],
+        18 | 19 => [35_651_584, 16_384, 8_704, 4_278_190_080, 1_600, 8_000],
+        // Level 31 places no limits on the stream.
+        31 => return Some(Vec::new()),
+        _ => return None,
+    };
+    let [max_pic_size, max_h_size, max_v_size, max_display_rate, main_mbps, high_mbps] = limits;
+    let mbps = if av1c.tier == 0 { main_mbps } else { high_mbps };
+    let bitrate_profile_factor = match av1c.profile {
+        0 => 1,
+        1 => 2,
+        _ => 3,
+    };
+
+    Some(violations(&[
+        (LevelLimit::Width, requirements.width, max_h_size),
+        (LevelLimit::Height, requirements.height, max_v_size),
+        (
+            LevelLimit::PictureSize,
+            requirements.picture_size(),
+            max_pic_size,
+        ),
+        (
+            LevelLimit::LumaSampleRate,
+            requirements.luma_sample_rate(),
+            max_display_rate,
+        ),
+        (
+            LevelLimit::Bitrate,
+            requirements.max_bitrate,
+            mbps * bitrate_profile_factor * 100_000,
+        ),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn avcc(profile: u8, level: u8) -> AvcCBox {
+        AvcCBox {
+            avc_profile_indication: profile,
+            avc_level_indication: level,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_avc_level() {
+        let hd = DecoderRequirements {
+            width: 1920,
+            height: 1080,
+            frame_rate: 30.0,
+            max_bitrate: 8_000_000,
+        };
+
+        // 1080p30 fits level 4.0, where the picture is padded to 1088 lines.
+        assert_eq!(avc_violations(&avcc(100, 40), &hd), Some(Vec::new()));
+
+        // … but not level 3.1.
+        let limits: Vec<_> = avc_violations(&avcc(100, 31), &hd)
+            .unwrap()
+            .into_iter()
+            .map(|violation| violation.limit)
+            .collect();
+        assert_eq!(
+            limits,
+            [LevelLimit::PictureSize, LevelLimit::LumaSampleRate]
+        );
+
+        // At 60 fps it needs level 4.2.
+        let hd60 = DecoderRequirements {
+            frame_rate: 60.0,
+            ..hd
+        };
+        assert_eq!(
+            avc_violations(&avcc(100, 41), &hd60),
+            Some(vec![LevelViolation {
+                limit: LevelLimit::LumaSampleRate,
+                actual: 1920 * 1088 * 60,
+                maximum: 245_760 * 256,
+            }])
+        );
+        assert_eq!(avc_violations(&avcc(100, 42), &hd60), Some(Vec::new()));
+
+        assert_eq!(avc_violations(&avcc(100, 7), &hd), None);
+    }
+}
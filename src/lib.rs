@@ -17,7 +17,109 @@ pub use types::*;
 mod mp4box;
 pub use mp4box::*;
 
+mod av1;
+pub use av1::{Av1ColorConfig, Av1OperatingPoint, Av1SequenceHeader};
+
+mod bits;
+
+mod box_tree;
+pub use box_tree::BoxNode;
+
+pub mod catalog;
+
+mod chapters;
+pub use chapters::Chapter;
+
+mod codec;
+pub use codec::{AacDetails, Av1Details, AvcDetails, Codec, HevcDetails, VpxDetails};
+
+mod codec_string;
+pub use codec_string::{
+    codec_string_from_av1c, codec_string_from_avcc, codec_string_from_hvcc, codec_string_from_vpcc,
+};
+
+mod color;
+pub use color::{ColorMismatch, ColorReport, ColorSignaling};
+
+mod compat;
+pub use compat::{codec_config_compatible, CompatIssue, CompatReport};
+
+mod conformance;
+pub use conformance::{ConformanceIssue, ConformanceReport};
+
+pub mod debug;
+
+mod data_source;
+pub use data_source::DataSources;
+
+mod elementary_stream;
+
+mod events;
+pub use events::EmsgEvent;
+
+mod fragment;
+pub use fragment::{
+    DefaultSource, FragmentDefault, FragmentDefaults, FragmentDuration, FragmentInfo,
+    FragmentSample, TrackFragmentStats,
+};
+
+mod free_space;
+pub use free_space::FreeSpace;
+
+mod geometry;
+pub use geometry::{CropRect, VideoGeometry};
+
+mod heif;
+pub use heif::{ImageCodecConfig, ImageItem};
+
+mod json;
+pub use json::JsonOptions;
+
+mod load;
+#[cfg(feature = "rayon")]
+pub use load::load_all_track_data;
+pub use load::{load_track_data, load_track_data_aligned, LoadOptions, TrackData};
+
+mod level;
+pub use level::{DecoderRequirements, LevelLimit, LevelViolation};
+
+mod sample_flags;
+pub use sample_flags::SampleFlags;
+
+mod sample_table;
+
+mod sniff;
+pub use sniff::{sniff, SniffResult, SNIFF_LEN};
+
+mod skipped;
+pub use skipped::{set_skip_logger, SkipLogger, Skipped};
+
+mod stats;
+pub use stats::TrackStats;
+
+mod sync_samples;
+
+mod trace;
+
+mod persist;
+
+mod progressive;
+pub use progressive::ProgressiveDownload;
+
+mod range;
+pub use range::RangeReader;
+
 mod reader;
-pub use reader::{Mp4, Sample, Track};
+pub use reader::{AudioInfo, AudioPriming, ExtraBox, Limits, Mp4, Sample, SampleOrder, Track};
+
+pub mod remux;
+pub use remux::faststart;
+
+pub mod subtitle;
+
+pub mod tx3g;
 
 pub use types::{TrackId, TrackKind};
+
+#[cfg(all(feature = "mmap", not(target_family = "wasm")))]
+pub use memmap2::Mmap;
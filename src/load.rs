@@ -0,0 +1,187 @@
+//! Reading the data of all samples of a track at once.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use crate::trace::span;
+use crate::{Bytes, Error, Mp4, Result, Sample, Track};
+
+/// How the samples are laid out in the buffer returned by [`load_track_data_aligned`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Every sample starts at a multiple of this many bytes from the start of the buffer,
+    /// e.g. 4 or 16 for GPU upload buffers. 0 is treated as 1.
+    pub alignment: usize,
+
+    /// Number of zero bytes that follow every sample, for decoders that read past the end
+    /// of their input.
+    pub padding: usize,
+}
+
+impl Default for LoadOptions {
+    /// Samples are packed back to back.
+    fn default() -> Self {
+        Self {
+            alignment: 1,
+            padding: 0,
+        }
+    }
+}
+
+/// The data of all samples of a track, in one buffer.
+#[derive(Clone, Debug)]
+pub struct TrackData {
+    /// The buffer holding all samples, including alignment gaps and padding.
+    pub data: Bytes,
+
+    /// The range of every sample within [`Self::data`], in sample order.
+    ///
+    /// Unlike [`crate::Sample::offset`], which is the offset in the file, these are offsets
+    /// into the buffer, and they follow [`LoadOptions::alignment`].
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl TrackData {
+    /// The data of the sample at `index`, without padding.
+    pub fn sample(&self, index: usize) -> Option<Bytes> {
+        let range = self.ranges.get(index)?;
+        Some(self.data.slice(range.clone()))
+    }
+}
+
+/// Reads the data of every sample of `track` from `reader`, in sample order.
+///
+/// `reader` must be the file `mp4` was parsed from. The samples are slices of one buffer
+/// per track, so holding on to them is cheap. Returns [`Error::ExternalDataReference`] if
+/// the samples are stored in another file, see [`crate::DataSources`] for reading those.
+pub fn load_track_data<R: Read + Seek>(
+    mp4: &Mp4,
+    track: &Track,
+    reader: &mut R,
+) -> Result<Vec<Bytes>> {
+    let track_data = load_track_data_aligned(mp4, track, reader, LoadOptions::default())?;
+    Ok(track_data
+        .ranges
+        .iter()
+        .map(|range| track_data.data.slice(range.clone()))
+        .collect())
+}
+
+/// Like [`load_track_data`], but lays the samples out according to `options`, so that the
+/// buffer can be copied into a GPU upload buffer as is.
+pub fn load_track_data_aligned<R: Read + Seek>(
+    mp4: &Mp4,
+    track: &Track,
+    reader: &mut R,
+    options: LoadOptions,
+) -> Result<TrackData> {
+    span!(
+        "load_track_data",
+        track_id = track.track_id,
+        samples = track.samples.len()
+    );
+
+    if track.has_external_data(mp4) {
+        return Err(Error::ExternalDataReference(track.track_id));
+    }
+
+    let too_large = || Error::InvalidData("track data too large");
+    let alignment = options.alignment.max(1);
+    let mut ranges = Vec::with_capacity(track.samples.len());
+    let mut total_size = 0_usize;
+
+    // Sample sizes are untrusted, so check them against the file before allocating the buffer.
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    for sample in &track.samples {
+        if sample.file_range().end > file_size {
+            return Err(Error::InvalidData("sample lies beyond the end of the file"));
+        }
+        let start = total_size
+            .checked_next_multiple_of(alignment)
+            .ok_or_else(too_large)?;
+        let end = usize::try_from(sample.size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .ok_or_else(too_large)?;
+        total_size = end.checked_add(options.padding).ok_or_else(too_large)?;
+        ranges.push(start..end);
+    }
+    let mut data = vec![0; total_size];
+
+    // Samples that are stored back to back, usually those of one chunk, are read at once.
+    // If they are not packed in the buffer, they are read into `scratch` and copied.
+    let mut scratch = Vec::new();
+    let mut samples = track.samples.iter().zip(&ranges).peekable();
+    while let Some((first, first_range)) = samples.next() {
+        let mut end = first.offset + first.size;
+        let mut run = vec![first_range.clone()];
+        while let Some((next, range)) = samples.next_if(|(next, _)| next.offset == end) {
+            end += next.size;
+            run.push(range.clone());
+        }
+        let size = usize::try_from(end - first.offset).map_err(|_err| too_large())?;
+        reader.seek(SeekFrom::Start(first.offset))?;
+
+        let packed = run.windows(2).all(|pair| pair[0].end == pair[1].start);
+        if packed {
+            reader.read_exact(&mut data[first_range.start..first_range.start + size])?;
+        } else {
+            scratch.resize(size, 0);
+            reader.read_exact(&mut scratch)?;
+            let mut position = 0;
+            for range in run {
+                let len = range.len();
+                data[range].copy_from_slice(&scratch[position..position + len]);
+                position += len;
+            }
+        }
+    }
+
+    Ok(TrackData {
+        data: Bytes::from(data),
+        ranges,
+    })
+}
+
+/// Reads the data of `sample` from `reader` into `buffer`, replacing its contents.
+///
+/// The buffer only grows as the data is read, so a bogus sample size fails at the end of the
+/// file instead of allocating that much up front.
+pub(crate) fn read_sample<R: Read + Seek>(
+    reader: &mut R,
+    sample: &Sample,
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
+    buffer.clear();
+    reader.seek(SeekFrom::Start(sample.offset))?;
+    reader.take(sample.size).read_to_end(buffer)?;
+    if buffer.len() as u64 != sample.size {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(())
+}
+
+/// Loads the data of all tracks concurrently, with one task per track.
+///
+/// `open` is called once per track to get a reader of the file `mp4` was parsed from,
+/// for example by opening the file again, so that the tracks can be read independently.
+#[cfg(feature = "rayon")]
+pub fn load_all_track_data<R, F>(
+    mp4: &Mp4,
+    open: F,
+) -> Result<std::collections::BTreeMap<crate::TrackId, Vec<Bytes>>>
+where
+    R: Read + Seek,
+    F: Fn() -> std::io::Result<R> + Sync,
+{
+    use rayon::prelude::*;
+
+    let tracks: Vec<&Track> = mp4.tracks().values().collect();
+    tracks
+        .into_par_iter()
+        .map(|track| {
+            let mut reader = open()?;
+            Ok((track.track_id, load_track_data(mp4, track, &mut reader)?))
+        })
+        .collect()
+}
@@ -0,0 +1,318 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, skip_bytes_to, value_u32, BoxHeader, BoxType, Error, FixedPointU16, Mp4Box, ReadBox,
+    Result, HEADER_SIZE,
+};
+use crate::skipped::{log_skipped, Skipped};
+
+/// Sample entry of Dolby Digital (AC-3) audio, see ETSI TS 102 366 annex F.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ac3Box {
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+
+    #[serde(with = "value_u32")]
+    pub samplerate: FixedPointU16,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dac3: Option<Dac3Box>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+/// The AC-3 specific box, which describes the bit stream.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Dac3Box {
+    /// Sample rate code: 48, 44.1 or 32 kHz.
+    pub fscod: u8,
+    pub bsid: u8,
+
+    /// Bit stream mode, like complete main or commentary.
+    pub bsmod: u8,
+
+    /// Audio coding mode, which says which full bandwidth channels there are.
+    pub acmod: u8,
+
+    /// Whether there is a low frequency effects channel.
+    pub lfeon: bool,
+    pub bit_rate_code: u8,
+}
+
+impl Ac3Box {
+    pub fn get_type() -> BoxType {
+        BoxType::Ac3Box
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + 20;
+        if self.dac3.is_some() {
+            size += HEADER_SIZE + 3;
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for Ac3Box {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "channel_count={} sample_size={} sample_rate={}",
+            self.channelcount,
+            self.samplesize,
+            self.samplerate.value()
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for Ac3Box {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        let fields = AudioSampleEntry::read(reader)?;
+
+        let mut dac3 = None;
+        let mut btrt = None;
+        read_children(reader, BoxType::Ac3Box, end, |reader, name, s| {
+            match name {
+                BoxType::Dac3Box if dac3.is_none() => {
+                    dac3 = Some(Dac3Box::read(reader)?);
+                }
+                BoxType::BtrtBox if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        Ok(Self {
+            data_reference_index: fields.data_reference_index,
+            channelcount: fields.channelcount,
+            samplesize: fields.samplesize,
+            samplerate: fields.samplerate,
+            dac3,
+            btrt,
+        })
+    }
+}
+
+impl Dac3Box {
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let bits = reader.read_u24::<BigEndian>()?;
+        Ok(Self {
+            fscod: (bits >> 22) as u8 & 0x3,
+            bsid: (bits >> 17) as u8 & 0x1f,
+            bsmod: (bits >> 14) as u8 & 0x7,
+            acmod: (bits >> 11) as u8 & 0x7,
+            lfeon: (bits >> 10) & 0x1 == 1,
+            bit_rate_code: (bits >> 5) as u8 & 0x1f,
+        })
+    }
+
+    /// The number of channels, including the low frequency effects channel.
+    pub fn channel_count(&self) -> u16 {
+        acmod_channel_count(self.acmod) + u16::from(self.lfeon)
+    }
+
+    /// The sample rate in Hz, or `None` for the reserved code.
+    pub fn sample_rate(&self) -> Option<u32> {
+        fscod_sample_rate(self.fscod)
+    }
+
+    /// The bitrate in bits per second, or `None` for an invalid code.
+    pub fn bitrate(&self) -> Option<u32> {
+        const KBPS: [u32; 19] = [
+            32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640,
+        ];
+        KBPS.get(usize::from(self.bit_rate_code))
+            .map(|kbps| kbps * 1000)
+    }
+}
+
+/// The number of full bandwidth channels of an audio coding mode.
+pub(crate) fn acmod_channel_count(acmod: u8) -> u16 {
+    // Mode 0 is two independent mono channels.
+    [2, 1, 2, 3, 3, 4, 4, 5][usize::from(acmod & 0x7)]
+}
+
+pub(crate) fn fscod_sample_rate(fscod: u8) -> Option<u32> {
+    match fscod {
+        0 => Some(48000),
+        1 => Some(44100),
+        2 => Some(32000),
+        _ => None,
+    }
+}
+
+/// The fields that all audio sample entries start with, see ISO/IEC 14496-12 section 12.2.3,
+/// including the extra fields of versions 1 and 2 of the `QuickTime` sound sample description.
+///
+/// For version 2, the channel count, sample size and sample rate are taken from its fields,
+/// since the ones of version 0 hold fixed values then.
+pub(crate) struct AudioSampleEntry {
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+    pub samplerate: FixedPointU16,
+    pub qt_sound: Option<QtSoundDescription>,
+}
+
+impl AudioSampleEntry {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+        let version = reader.read_u16::<BigEndian>()?;
+        reader.read_u16::<BigEndian>()?; // reserved
+        reader.read_u32::<BigEndian>()?; // reserved
+        let mut channelcount = reader.read_u16::<BigEndian>()?;
+        let mut samplesize = reader.read_u16::<BigEndian>()?;
+        reader.read_u32::<BigEndian>()?; // pre-defined, reserved
+        let mut samplerate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+
+        let qt_sound = match version {
+            1 => Some(QtSoundDescription::V1 {
+                samples_per_packet: reader.read_u32::<BigEndian>()?,
+                bytes_per_packet: reader.read_u32::<BigEndian>()?,
+                bytes_per_frame: reader.read_u32::<BigEndian>()?,
+                bytes_per_sample: reader.read_u32::<BigEndian>()?,
+            }),
+            2 => {
+                reader.read_u32::<BigEndian>()?; // sizeOfStructOnly
+                let sample_rate_bits = reader.read_u64::<BigEndian>()?;
+                let channel_count = reader.read_u32::<BigEndian>()?;
+                reader.read_u32::<BigEndian>()?; // always 0x7f000000
+                let bits_per_channel = reader.read_u32::<BigEndian>()?;
+                let qt_sound = QtSoundDescription::V2 {
+                    sample_rate_bits,
+                    channel_count,
+                    bits_per_channel,
+                    format_specific_flags: reader.read_u32::<BigEndian>()?,
+                    bytes_per_packet: reader.read_u32::<BigEndian>()?,
+                    frames_per_packet: reader.read_u32::<BigEndian>()?,
+                };
+                channelcount = u16::try_from(channel_count).unwrap_or(u16::MAX);
+                samplesize = u16::try_from(bits_per_channel).unwrap_or(u16::MAX);
+                let sample_rate = f64::from_bits(sample_rate_bits);
+                samplerate = if (0.0..=f64::from(u16::MAX)).contains(&sample_rate) {
+                    FixedPointU16::new_raw((sample_rate * f64::from(0x10000)) as u32)
+                } else {
+                    FixedPointU16::new_raw(0)
+                };
+                Some(qt_sound)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            data_reference_index,
+            channelcount,
+            samplesize,
+            samplerate,
+            qt_sound,
+        })
+    }
+}
+
+/// The fields that versions 1 and 2 of the `QuickTime` sound sample description add to an
+/// audio sample entry, see the `QuickTime` File Format Specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QtSoundDescription {
+    /// Version 1, which describes how compressed samples are packed.
+    V1 {
+        /// The number of uncompressed frames in a compressed packet.
+        samples_per_packet: u32,
+        bytes_per_packet: u32,
+
+        /// The number of bytes of a frame of all channels.
+        bytes_per_frame: u32,
+        bytes_per_sample: u32,
+    },
+
+    /// Version 2, which moves the sample rate and channel count out of the fields of version 0,
+    /// so that they can exceed 65535 Hz and 65535 channels. Required for `lpcm`.
+    V2 {
+        /// The sample rate in Hz as the bits of a 64-bit float, see [`Self::sample_rate`].
+        sample_rate_bits: u64,
+        channel_count: u32,
+
+        /// Bits per sample of uncompressed audio of a constant size, or else 0.
+        bits_per_channel: u32,
+
+        /// The layout of `lpcm` samples, like whether they are floats or little endian.
+        format_specific_flags: u32,
+
+        /// The size of a packet if all have the same, or else 0.
+        bytes_per_packet: u32,
+
+        /// The number of frames in a packet if all have the same, or else 0.
+        frames_per_packet: u32,
+    },
+}
+
+impl QtSoundDescription {
+    /// The sample rate of version 2 in Hz, or `None` for version 1.
+    pub fn sample_rate(&self) -> Option<f64> {
+        match self {
+            Self::V1 { .. } => None,
+            Self::V2 {
+                sample_rate_bits, ..
+            } => Some(f64::from_bits(*sample_rate_bits)),
+        }
+    }
+}
+
+/// Calls `read_child` with the type and size of every child box until `end`, with the reader
+/// positioned after the header of the child. Children for which it returns `false` are logged
+/// as skipped.
+pub(crate) fn read_children<R: Read + Seek>(
+    reader: &mut R,
+    parent: BoxType,
+    end: u64,
+    mut read_child: impl FnMut(&mut R, BoxType, u64) -> Result<bool>,
+) -> Result<()> {
+    loop {
+        let current = reader.stream_position()?;
+        if current + HEADER_SIZE > end {
+            break;
+        }
+        let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+        if s < HEADER_SIZE || current + s > end {
+            return Err(Error::InvalidData(
+                "sample entry contains a box with a larger size than it",
+            ));
+        }
+        if !read_child(reader, name, s)? {
+            log_skipped(&Skipped::Box {
+                parent: parent.into(),
+                box_type: name.into(),
+                offset: current,
+                size: s,
+            });
+        }
+        skip_bytes_to(reader, current + s)?;
+    }
+    skip_bytes_to(reader, end)
+}
@@ -1,13 +1,17 @@
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
+use crate::mp4box::btrt::{read_btrt_with, BtrtBox};
+use crate::mp4box::clap::read_clap;
+use crate::mp4box::colr::read_colr;
+use crate::mp4box::pasp::read_pasp;
 use crate::mp4box::{
-    box_start, skip_bytes, skip_bytes_to, value_u32, BoxHeader, BoxType, Error, FixedPointU16,
-    Mp4Box, RawBox, ReadBox, Result, HEADER_SIZE,
+    box_start, read_bytes, skip_bytes, value_u32, BoxHeader, BoxType, ClapBox, ColrBox, Error,
+    FixedPointU16, Mp4Box, PaspBox, RawBox, ReadBox, Result, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Av01Box {
     pub data_reference_index: u16,
     pub width: u16,
@@ -21,6 +25,14 @@ pub struct Av01Box {
     pub frame_count: u16,
     pub depth: u16, // This is usually 24, even for HDR with bit_depth=10
     pub av1c: RawBox<Av1CBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pasp: Option<PaspBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clap: Option<ClapBox>,
 }
 
 impl Av01Box {
@@ -29,7 +41,20 @@ impl Av01Box {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.av1c.box_size()
+        let mut size = HEADER_SIZE + 8 + 70 + self.av1c.box_size();
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        if let Some(colr) = &self.colr {
+            size += colr.get_size();
+        }
+        if self.pasp.is_some() {
+            size += PaspBox::get_size();
+        }
+        if self.clap.is_some() {
+            size += ClapBox::get_size();
+        }
+        size
     }
 }
 
@@ -82,7 +107,14 @@ impl<R: Read + Seek> ReadBox<&mut R> for Av01Box {
         if name == BoxType::Av1CBox {
             let av1c = RawBox::<Av1CBox>::read_box(reader, s)?;
 
-            skip_bytes_to(reader, start + size)?;
+            let mut colr = None;
+            let mut pasp = None;
+            let mut clap = None;
+            let btrt = read_btrt_with(reader, start, start + size, |reader, name, s| {
+                Ok(read_colr(reader, name, s, &mut colr)?
+                    || read_pasp(reader, name, s, &mut pasp)?
+                    || read_clap(reader, name, s, &mut clap)?)
+            })?;
 
             Ok(Self {
                 data_reference_index,
@@ -93,6 +125,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for Av01Box {
                 frame_count,
                 depth,
                 av1c,
+                btrt,
+                colr,
+                pasp,
+                clap,
             })
         } else {
             Err(Error::InvalidData("av1c not found"))
@@ -100,7 +136,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Av01Box {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Av1CBox {
     pub profile: u8,
     pub level: u8,
@@ -171,8 +207,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Av1CBox {
             .checked_sub(HEADER_SIZE + 4) // header bytes + fixed field bytes
             .ok_or(Error::InvalidData("invalid box size"))?;
 
-        let mut config_obus = vec![0u8; config_obus_size as usize];
-        reader.read_exact(&mut config_obus)?;
+        let config_obus = read_bytes(reader, config_obus_size)?;
 
         Ok(Self {
             profile,
@@ -1,13 +1,18 @@
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
+use crate::mp4box::btrt::{read_btrt_with, BtrtBox};
+use crate::mp4box::clap::read_clap;
+use crate::mp4box::colr::read_colr;
+use crate::mp4box::pasp::read_pasp;
 use crate::mp4box::{
-    box_start, skip_bytes, skip_bytes_to, value_u32, BoxHeader, BoxType, Error, FixedPointU16,
-    Mp4Box, RawBox, ReadBox, Result, HEADER_SIZE,
+    box_start, read_bytes, skip_bytes, skip_bytes_to, value_u32, BoxHeader, BoxType, ClapBox,
+    ColrBox, Error, FixedPointU16, Mp4Box, PaspBox, RawBox, ReadBox, Result, HEADER_SIZE,
 };
+use crate::skipped::{log_skipped, Skipped};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Avc1Box {
     pub data_reference_index: u16,
     pub width: u16,
@@ -21,6 +26,14 @@ pub struct Avc1Box {
     pub frame_count: u16,
     pub depth: u16, // This is usually 24, even for HDR with bit_depth=10
     pub avcc: RawBox<AvcCBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pasp: Option<PaspBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clap: Option<ClapBox>,
 }
 
 impl Default for Avc1Box {
@@ -34,6 +47,10 @@ impl Default for Avc1Box {
             frame_count: 1,
             depth: 0x0018,
             avcc: RawBox::default(),
+            btrt: None,
+            colr: None,
+            pasp: None,
+            clap: None,
         }
     }
 }
@@ -44,7 +61,20 @@ impl Avc1Box {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.avcc.box_size()
+        let mut size = HEADER_SIZE + 8 + 70 + self.avcc.box_size();
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        if let Some(colr) = &self.colr {
+            size += colr.get_size();
+        }
+        if self.pasp.is_some() {
+            size += PaspBox::get_size();
+        }
+        if self.clap.is_some() {
+            size += ClapBox::get_size();
+        }
+        size
     }
 }
 
@@ -107,7 +137,14 @@ impl<R: Read + Seek> ReadBox<&mut R> for Avc1Box {
             if name == BoxType::AvcCBox {
                 let avcc = RawBox::<AvcCBox>::read_box(reader, s)?;
 
-                skip_bytes_to(reader, start + size)?;
+                let mut colr = None;
+                let mut pasp = None;
+                let mut clap = None;
+                let btrt = read_btrt_with(reader, start, start + size, |reader, name, s| {
+                    Ok(read_colr(reader, name, s, &mut colr)?
+                        || read_pasp(reader, name, s, &mut pasp)?
+                        || read_clap(reader, name, s, &mut clap)?)
+                })?;
 
                 return Ok(Self {
                     data_reference_index,
@@ -118,15 +155,25 @@ impl<R: Read + Seek> ReadBox<&mut R> for Avc1Box {
                     frame_count,
                     depth,
                     avcc,
+                    btrt,
+                    colr,
+                    pasp,
+                    clap,
                 });
             } else {
+                log_skipped(&Skipped::Box {
+                    parent: BoxType::Avc1Box.into(),
+                    box_type: name.into(),
+                    offset: current,
+                    size: s,
+                });
                 skip_bytes_to(reader, current + s)?;
             }
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct AvcCBox {
     pub configuration_version: u8,
     pub avc_profile_indication: u8,
@@ -203,9 +250,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for AvcCBox {
         }
 
         let content_end = reader.stream_position()?;
-        let remainder = size - HEADER_SIZE - (content_end - content_start);
-        let mut ext = vec![0u8; remainder as usize];
-        reader.read_exact(&mut ext)?;
+        let remainder = size
+            .checked_sub(HEADER_SIZE + (content_end - content_start))
+            .ok_or(Error::InvalidData("avcC size too small"))?;
+        let ext = read_bytes(reader, remainder)?;
 
         skip_bytes_to(reader, start + size)?;
 
@@ -222,7 +270,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for AvcCBox {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct NalUnit {
     pub bytes: Vec<u8>,
 }
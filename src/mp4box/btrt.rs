@@ -0,0 +1,140 @@
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::mp4box::{
+    box_start, skip_bytes_to, BoxHeader, BoxType, Mp4Box, ReadBox, Result, WriteBox, HEADER_SIZE,
+};
+use crate::skipped::{is_logging, log_skipped, Skipped};
+
+/// Bitrate box, which may follow the decoder configuration in a sample entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BtrtBox {
+    /// Size of the decoding buffer for the elementary stream in bytes.
+    pub buffer_size_db: u32,
+
+    /// Maximum rate in bits per second over any window of one second.
+    pub max_bitrate: u32,
+
+    /// Average rate in bits per second over the entire presentation.
+    pub avg_bitrate: u32,
+}
+
+impl BtrtBox {
+    pub fn get_type() -> BoxType {
+        BoxType::BtrtBox
+    }
+
+    pub fn get_size() -> u64 {
+        HEADER_SIZE + 12
+    }
+}
+
+impl Mp4Box for BtrtBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        Self::get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "buffer_size_db={} max_bitrate={} avg_bitrate={}",
+            self.buffer_size_db, self.max_bitrate, self.avg_bitrate
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for BtrtBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let buffer_size_db = reader.read_u32::<BigEndian>()?;
+        let max_bitrate = reader.read_u32::<BigEndian>()?;
+        let avg_bitrate = reader.read_u32::<BigEndian>()?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self {
+            buffer_size_db,
+            max_bitrate,
+            avg_bitrate,
+        })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for BtrtBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(self.buffer_size_db)?;
+        writer.write_u32::<BigEndian>(self.max_bitrate)?;
+        writer.write_u32::<BigEndian>(self.avg_bitrate)?;
+
+        Ok(size)
+    }
+}
+
+/// Reads the `btrt` box among the remaining children of the sample entry that starts at
+/// `parent_start` and ends at `end`, and skips all others, like [`crate::skipped::skip_children`].
+pub(crate) fn read_btrt<R: Read + Seek>(
+    reader: &mut R,
+    parent_start: u64,
+    end: u64,
+) -> Result<Option<BtrtBox>> {
+    read_btrt_with(reader, parent_start, end, |_, _, _| Ok(false))
+}
+
+/// Like [`read_btrt`], but hands the other children to `read_child` with their type and size,
+/// which returns whether it read them.
+pub(crate) fn read_btrt_with<R: Read + Seek>(
+    reader: &mut R,
+    parent_start: u64,
+    end: u64,
+    mut read_child: impl FnMut(&mut R, BoxType, u64) -> Result<bool>,
+) -> Result<Option<BtrtBox>> {
+    let parent = if is_logging() {
+        let first_child = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(parent_start))?;
+        let parent = BoxHeader::read(reader)?.name;
+        skip_bytes_to(reader, first_child)?;
+        Some(parent)
+    } else {
+        None
+    };
+
+    let mut btrt = None;
+    loop {
+        let offset = reader.stream_position()?;
+        if offset + HEADER_SIZE > end {
+            break;
+        }
+        let header = BoxHeader::read(reader)?;
+        if header.size < HEADER_SIZE || offset + header.size > end {
+            break;
+        }
+        if header.name == BoxType::BtrtBox && btrt.is_none() {
+            btrt = Some(BtrtBox::read_box(reader, header.size)?);
+        } else if !read_child(reader, header.name, header.size)? {
+            if let Some(parent) = parent {
+                log_skipped(&Skipped::Box {
+                    parent: parent.into(),
+                    box_type: header.name.into(),
+                    offset,
+                    size: header.size,
+                });
+            }
+        }
+        skip_bytes_to(reader, offset + header.size)?;
+    }
+    skip_bytes_to(reader, end)?;
+    Ok(btrt)
+}
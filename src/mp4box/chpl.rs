@@ -0,0 +1,126 @@
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
+
+use crate::mp4box::{
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
+};
+
+/// Nero chapter list, found in `moov/udta`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChplBox {
+    pub version: u8,
+    pub flags: u32,
+    pub chapters: Vec<ChplEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChplEntry {
+    /// Start of the chapter in units of 100 nanoseconds.
+    pub start_time: u64,
+    pub title: String,
+}
+
+impl ChplBox {
+    pub fn get_type() -> BoxType {
+        BoxType::ChplBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let reserved = if self.version > 0 { 4 } else { 0 };
+        HEADER_SIZE
+            + HEADER_EXT_SIZE
+            + reserved
+            + 1
+            + self
+                .chapters
+                .iter()
+                .map(|chapter| 8 + 1 + title_len(&chapter.title) as u64)
+                .sum::<u64>()
+    }
+}
+
+impl Mp4Box for ChplBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("chapters={}", self.chapters.len());
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for ChplBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        if version > 0 {
+            reader.read_u32::<BigEndian>()?; // reserved
+        }
+
+        let chapter_count = reader.read_u8()?;
+        let mut chapters = Vec::with_capacity(chapter_count as usize);
+        for _ in 0..chapter_count {
+            let start_time = reader.read_u64::<BigEndian>()?;
+            let title_len = reader.read_u8()?;
+            let mut title = vec![0; title_len as usize];
+            reader.read_exact(&mut title)?;
+            chapters.push(ChplEntry {
+                start_time,
+                title: String::from_utf8_lossy(&title).into_owned(),
+            });
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self {
+            version,
+            flags,
+            chapters,
+        })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for ChplBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        if self.version > 0 {
+            writer.write_u32::<BigEndian>(0)?; // reserved
+        }
+
+        let chapter_count = u8::try_from(self.chapters.len())
+            .map_err(|_err| Error::InvalidData("too many chapters for chpl"))?;
+        writer.write_u8(chapter_count)?;
+        for chapter in &self.chapters {
+            writer.write_u64::<BigEndian>(chapter.start_time)?;
+            let title_len = title_len(&chapter.title);
+            writer.write_u8(title_len as u8)?;
+            writer.write_all(&chapter.title.as_bytes()[..title_len])?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// Titles are cut off at 255 bytes, at a character boundary.
+fn title_len(title: &str) -> usize {
+    let mut len = title.len().min(u8::MAX as usize);
+    while !title.is_char_boundary(len) {
+        len -= 1;
+    }
+    len
+}
@@ -0,0 +1,96 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::{box_start, skip_bytes_to, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+
+/// The clean aperture of a visual sample entry, see ISO/IEC 14496-12 section 12.1.4.
+///
+/// The part of the coded picture that is meant to be displayed, as fractions of pixels. The
+/// offsets move its center away from the center of the coded picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ClapBox {
+    pub clean_aperture_width_n: u32,
+    pub clean_aperture_width_d: u32,
+    pub clean_aperture_height_n: u32,
+    pub clean_aperture_height_d: u32,
+    pub horiz_off_n: i32,
+    pub horiz_off_d: u32,
+    pub vert_off_n: i32,
+    pub vert_off_d: u32,
+}
+
+impl ClapBox {
+    pub fn get_type() -> BoxType {
+        BoxType::ClapBox
+    }
+
+    pub fn get_size() -> u64 {
+        HEADER_SIZE + 32
+    }
+}
+
+impl Mp4Box for ClapBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        Self::get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        Ok(format!(
+            "width={}/{} height={}/{} horiz_off={}/{} vert_off={}/{}",
+            self.clean_aperture_width_n,
+            self.clean_aperture_width_d,
+            self.clean_aperture_height_n,
+            self.clean_aperture_height_d,
+            self.horiz_off_n,
+            self.horiz_off_d,
+            self.vert_off_n,
+            self.vert_off_d
+        ))
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for ClapBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let clap = Self {
+            clean_aperture_width_n: reader.read_u32::<BigEndian>()?,
+            clean_aperture_width_d: reader.read_u32::<BigEndian>()?,
+            clean_aperture_height_n: reader.read_u32::<BigEndian>()?,
+            clean_aperture_height_d: reader.read_u32::<BigEndian>()?,
+            horiz_off_n: reader.read_i32::<BigEndian>()?,
+            horiz_off_d: reader.read_u32::<BigEndian>()?,
+            vert_off_n: reader.read_i32::<BigEndian>()?,
+            vert_off_d: reader.read_u32::<BigEndian>()?,
+        };
+
+        skip_bytes_to(reader, start + size)?;
+        Ok(clap)
+    }
+}
+
+/// Reads the child box `name` of a visual sample entry into `clap` if it is the first `clap`
+/// box, and returns whether it was a `clap` box.
+pub(crate) fn read_clap<R: Read + Seek>(
+    reader: &mut R,
+    name: BoxType,
+    size: u64,
+    clap: &mut Option<ClapBox>,
+) -> Result<bool> {
+    if name != BoxType::ClapBox {
+        return Ok(false);
+    }
+    if clap.is_none() {
+        *clap = Some(ClapBox::read_box(reader, size)?);
+    }
+    Ok(true)
+}
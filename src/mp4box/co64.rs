@@ -1,19 +1,19 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Co64Box {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub entries: Vec<u64>,
 }
 
@@ -81,3 +81,19 @@ impl<R: Read + Seek> ReadBox<&mut R> for Co64Box {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for Co64Box {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for chunk_offset in &self.entries {
+            writer.write_u64::<BigEndian>(*chunk_offset)?;
+        }
+
+        Ok(size)
+    }
+}
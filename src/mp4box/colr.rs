@@ -0,0 +1,161 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::{
+    box_start, read_bytes, skip_bytes_to, BoxType, FourCC, Mp4Box, ReadBox, Result, HEADER_SIZE,
+};
+
+/// The colour information of a visual sample entry, see ISO/IEC 14496-12 section 12.1.5.
+///
+/// The code points are those of ITU-T H.273, the same as in the VUI of AVC and HEVC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColrBox {
+    /// Code points for the colour primaries, transfer function and matrix (`nclx`).
+    Nclx {
+        colour_primaries: u16,
+        transfer_characteristics: u16,
+        matrix_coefficients: u16,
+        full_range: bool,
+    },
+
+    /// The `QuickTime` variant of `nclx` (`nclc`), which leaves out the range.
+    Nclc {
+        colour_primaries: u16,
+        transfer_characteristics: u16,
+        matrix_coefficients: u16,
+    },
+
+    /// An ICC profile, which is restricted to the monochrome and three-component matrix based
+    /// profiles for `rICC` and unrestricted for `prof`.
+    Icc { restricted: bool, profile: Vec<u8> },
+
+    /// A colour type that is not known.
+    Unknown { colour_type: FourCC, data: Vec<u8> },
+}
+
+impl ColrBox {
+    pub fn get_type() -> BoxType {
+        BoxType::ColrBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE
+            + 4
+            + match self {
+                Self::Nclx { .. } => 7,
+                Self::Nclc { .. } => 6,
+                Self::Icc { profile: data, .. } | Self::Unknown { data, .. } => data.len() as u64,
+            }
+    }
+}
+
+impl Mp4Box for ColrBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = match self {
+            Self::Nclx {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+                full_range,
+            } => format!(
+                "nclx primaries={colour_primaries} transfer={transfer_characteristics} matrix={matrix_coefficients} full_range={full_range}"
+            ),
+            Self::Nclc {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+            } => format!(
+                "nclc primaries={colour_primaries} transfer={transfer_characteristics} matrix={matrix_coefficients}"
+            ),
+            Self::Icc { restricted, profile } => {
+                format!("icc restricted={restricted} size={}", profile.len())
+            }
+            Self::Unknown { colour_type, .. } => format!("colour_type={colour_type}"),
+        };
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for ColrBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        let colour_type = reader.read_u32::<BigEndian>()?.to_be_bytes();
+        let colr = match &colour_type {
+            b"nclx" | b"nclc" => {
+                let colour_primaries = reader.read_u16::<BigEndian>()?;
+                let transfer_characteristics = reader.read_u16::<BigEndian>()?;
+                let matrix_coefficients = reader.read_u16::<BigEndian>()?;
+                if &colour_type == b"nclx" {
+                    Self::Nclx {
+                        colour_primaries,
+                        transfer_characteristics,
+                        matrix_coefficients,
+                        full_range: reader.read_u8()? & 0x80 != 0,
+                    }
+                } else {
+                    Self::Nclc {
+                        colour_primaries,
+                        transfer_characteristics,
+                        matrix_coefficients,
+                    }
+                }
+            }
+            _ => {
+                let data_start = reader.stream_position()?;
+                let data = read_bytes(reader, end.saturating_sub(data_start))?;
+                match &colour_type {
+                    b"rICC" | b"prof" => Self::Icc {
+                        restricted: &colour_type == b"rICC",
+                        profile: data,
+                    },
+                    _ => Self::Unknown {
+                        colour_type: colour_type.into(),
+                        data,
+                    },
+                }
+            }
+        };
+
+        skip_bytes_to(reader, end)?;
+        Ok(colr)
+    }
+}
+
+/// Reads the child box `name` of a visual sample entry into `colr` if it is a `colr` box, and
+/// returns whether it was one.
+///
+/// Entries may have several `colr` boxes. The first one is kept, except that code points take
+/// precedence over an ICC profile.
+pub(crate) fn read_colr<R: Read + Seek>(
+    reader: &mut R,
+    name: BoxType,
+    size: u64,
+    colr: &mut Option<ColrBox>,
+) -> Result<bool> {
+    if name != BoxType::ColrBox {
+        return Ok(false);
+    }
+    let has_code_points = matches!(colr, Some(ColrBox::Nclx { .. } | ColrBox::Nclc { .. }));
+    if !has_code_points {
+        let new = ColrBox::read_box(reader, size)?;
+        if colr.is_none() || matches!(new, ColrBox::Nclx { .. } | ColrBox::Nclc { .. }) {
+            *colr = Some(new);
+        }
+    }
+    Ok(true)
+}
@@ -1,19 +1,19 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct CttsBox {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub entries: Vec<CttsEntry>,
 }
 
@@ -27,7 +27,7 @@ impl CttsBox {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct CttsEntry {
     pub sample_count: u32,
     pub sample_offset: i32,
@@ -91,3 +91,19 @@ impl<R: Read + Seek> ReadBox<&mut R> for CttsBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for CttsBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in &self.entries {
+            writer.write_u32::<BigEndian>(entry.sample_count)?;
+            writer.write_i32::<BigEndian>(entry.sample_offset)?;
+        }
+        Ok(size)
+    }
+}
@@ -3,14 +3,14 @@ use std::{
     io::{Read, Seek},
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::mp4box::{
-    box_start, BigEndian, BoxType, DataType, Mp4Box, ReadBox, ReadBytesExt as _, Result,
-    HEADER_SIZE,
+    box_start, read_bytes, BigEndian, BoxType, DataType, Error, Mp4Box, ReadBox, ReadBytesExt as _,
+    Result, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct DataBox {
     pub data: Vec<u8>,
     pub data_type: DataType,
@@ -58,8 +58,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for DataBox {
         reader.read_u32::<BigEndian>()?; // reserved = 0
 
         let current = reader.stream_position()?;
-        let mut data = vec![0u8; (start + size - current) as usize];
-        reader.read_exact(&mut data)?;
+        let data_size = (start + size)
+            .checked_sub(current)
+            .ok_or(Error::InvalidData("data size too small"))?;
+        let data = read_bytes(reader, data_size)?;
 
         Ok(Self { data, data_type })
     }
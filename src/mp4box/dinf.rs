@@ -1,14 +1,16 @@
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::WriteBytesExt as _;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_box, skip_bytes_to, BigEndian, BoxHeader, BoxType, Error,
-    Mp4Box, ReadBox, ReadBytesExt as _, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, read_bytes, skip_box, skip_bytes_to, write_box_header_ext,
+    BigEndian, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox, ReadBytesExt as _, Result,
+    WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct DinfBox {
-    dref: DrefBox,
+    pub dref: DrefBox,
 }
 
 impl DinfBox {
@@ -81,13 +83,23 @@ impl<R: Read + Seek> ReadBox<&mut R> for DinfBox {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+impl<W: Write> WriteBox<&mut W> for DinfBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+        self.dref.write_box(writer)?;
+        Ok(size)
+    }
+}
+
+/// Data references, which tell where the media data of a track is stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DrefBox {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub url: Option<UrlBox>,
+    #[serde(rename = "entry")]
+    pub entries: Vec<DataEntry>,
 }
 
 impl Default for DrefBox {
@@ -95,7 +107,7 @@ impl Default for DrefBox {
         Self {
             version: 0,
             flags: 0,
-            url: Some(UrlBox::default()),
+            entries: vec![DataEntry::Url(UrlBox::default())],
         }
     }
 }
@@ -106,11 +118,24 @@ impl DrefBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 4;
-        if let Some(ref url) = self.url {
-            size += url.box_size();
-        }
-        size
+        HEADER_SIZE
+            + HEADER_EXT_SIZE
+            + 4
+            + self
+                .entries
+                .iter()
+                .map(|entry| match entry {
+                    DataEntry::Url(url) => url.box_size(),
+                    DataEntry::Urn(urn) => urn.box_size(),
+                    DataEntry::Unknown { size, .. } => *size,
+                })
+                .sum::<u64>()
+    }
+
+    /// The entry that a sample entry refers to with its `data_reference_index`, which is 1-based.
+    pub fn entry(&self, data_reference_index: u16) -> Option<&DataEntry> {
+        self.entries
+            .get(usize::from(data_reference_index).checked_sub(1)?)
     }
 }
 
@@ -128,7 +153,7 @@ impl Mp4Box for DrefBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = String::new();
+        let s = format!("entries={}", self.entries.len());
         Ok(s)
     }
 }
@@ -137,14 +162,13 @@ impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
-        let mut current = reader.stream_position()?;
-
         let (version, flags) = read_box_header_ext(reader)?;
         let end = start + size;
 
-        let mut url = None;
+        let mut entries = Vec::new();
 
         let entry_count = reader.read_u32::<BigEndian>()?;
+        let mut current = reader.stream_position()?;
         for _i in 0..entry_count {
             if current >= end {
                 break;
@@ -155,18 +179,26 @@ impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
             let BoxHeader { name, size: s } = header;
             if s > size {
                 return Err(Error::InvalidData(
-                    "dinf box contains a box with a larger size than it",
+                    "dref box contains a box with a larger size than it",
                 ));
             }
 
-            match name {
-                BoxType::UrlBox => {
-                    url = Some(UrlBox::read_box(reader, s)?);
-                }
+            let entry = match name {
+                BoxType::UrlBox => DataEntry::Url(UrlBox::read_box(reader, s)?),
+                BoxType::UrnBox => DataEntry::Urn(UrnBox::read_box(reader, s)?),
                 _ => {
-                    skip_box(reader, s)?;
+                    // All data entries are full boxes, so the self-contained flag can be read
+                    // even from entry types this crate doesn't know, such as QuickTime's `alis`.
+                    let (_, flags) = read_box_header_ext(reader)?;
+                    skip_bytes_to(reader, current + s)?;
+                    DataEntry::Unknown {
+                        entry_type: name.into(),
+                        flags,
+                        size: s,
+                    }
                 }
-            }
+            };
+            entries.push(entry);
 
             current = reader.stream_position()?;
         }
@@ -176,12 +208,99 @@ impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
         Ok(Self {
             version,
             flags,
-            url,
+            entries,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+impl<W: Write> WriteBox<&mut W> for DrefBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in &self.entries {
+            match entry {
+                DataEntry::Url(url) => url.write_box(writer)?,
+                DataEntry::Urn(urn) => urn.write_box(writer)?,
+                DataEntry::Unknown { .. } => {
+                    return Err(Error::InvalidData("unknown data entries cannot be written"));
+                }
+            };
+        }
+
+        Ok(size)
+    }
+}
+
+/// An entry of a [`DrefBox`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataEntry {
+    Url(UrlBox),
+    Urn(UrnBox),
+
+    /// An entry type that is not parsed, of which only the flags are known.
+    Unknown {
+        entry_type: FourCC,
+        flags: u32,
+        size: u64,
+    },
+}
+
+impl DataEntry {
+    /// Flag of all data entries, set if the media data is in the same file as the `moov` box.
+    pub const FLAG_SELF_CONTAINED: u32 = 0x01;
+
+    pub fn flags(&self) -> u32 {
+        match self {
+            Self::Url(url) => url.flags,
+            Self::Urn(urn) => urn.flags,
+            Self::Unknown { flags, .. } => *flags,
+        }
+    }
+
+    /// Whether the media data is in the same file, rather than at the location of this entry.
+    pub fn is_self_contained(&self) -> bool {
+        self.flags() & Self::FLAG_SELF_CONTAINED != 0
+    }
+
+    /// Where the media data is stored, if it is not in the same file.
+    ///
+    /// Returns `None` for self-contained entries, and for entry types that are not parsed.
+    pub fn external_reference(&self) -> Option<ExternalDataRef> {
+        if self.is_self_contained() {
+            return None;
+        }
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_owned());
+        match self {
+            Self::Url(url) => Some(ExternalDataRef {
+                url: non_empty(&url.location),
+                name: None,
+            }),
+            Self::Urn(urn) => Some(ExternalDataRef {
+                url: non_empty(&urn.location),
+                name: non_empty(&urn.name),
+            }),
+            Self::Unknown { .. } => None,
+        }
+    }
+}
+
+/// The location of media data that is stored outside of the file, see [`DataEntry::external_reference`].
+///
+/// URLs are usually relative to the file that refers to them, as in `QuickTime` reference movies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExternalDataRef {
+    /// URL of the file that holds the media data.
+    pub url: Option<String>,
+
+    /// Name (URN) of the media data, only set for `urn ` entries.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UrlBox {
     pub version: u8,
     pub flags: u32,
@@ -243,8 +362,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for UrlBox {
             .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
             .ok_or(Error::InvalidData("url size too small"))?;
 
-        let mut buf = vec![0u8; buf_size as usize];
-        reader.read_exact(&mut buf)?;
+        let mut buf = read_bytes(reader, buf_size)?;
         if let Some(end) = buf.iter().position(|&b| b == b'\0') {
             buf.truncate(end);
         }
@@ -259,3 +377,109 @@ impl<R: Read + Seek> ReadBox<&mut R> for UrlBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for UrlBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        if !self.location.is_empty() {
+            writer.write_all(self.location.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// A data entry that names the media data by URN, optionally with a URL to find it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UrnBox {
+    pub version: u8,
+    pub flags: u32,
+    pub name: String,
+    pub location: String,
+}
+
+impl UrnBox {
+    pub fn get_type() -> BoxType {
+        BoxType::UrnBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + self.name.len() as u64 + 1;
+
+        if !self.location.is_empty() {
+            size += self.location.len() as u64 + 1;
+        }
+
+        size
+    }
+}
+
+impl Mp4Box for UrnBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("name={} location={}", self.name, self.location);
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for UrnBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let buf_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
+            .ok_or(Error::InvalidData("urn size too small"))?;
+
+        let buf = read_bytes(reader, buf_size)?;
+        let mut strings = buf
+            .split(|&b| b == b'\0')
+            .map(|s| String::from_utf8(s.to_vec()).unwrap_or_default());
+        let name = strings.next().unwrap_or_default();
+        let location = strings.next().unwrap_or_default();
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self {
+            version,
+            flags,
+            name,
+            location,
+        })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for UrnBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_all(self.name.as_bytes())?;
+        writer.write_u8(0)?;
+        if !self.location.is_empty() {
+            writer.write_all(self.location.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
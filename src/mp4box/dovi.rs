@@ -0,0 +1,56 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use crate::mp4box::{Result, HEADER_SIZE};
+
+/// The Dolby Vision configuration of a sample entry, from a `dvcC`, `dvvC` or `dvwC` box.
+///
+/// These boxes share the `DOVIDecoderConfigurationRecord`, see "Dolby Vision streams within
+/// the ISO base media file format". Which of them is used depends on the profile.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DoviConfigBox {
+    pub version_major: u8,
+    pub version_minor: u8,
+
+    /// The Dolby Vision profile, like 5 for a single layer in its own color space, or 8 for a
+    /// single layer that is compatible with HDR10, HLG or SDR.
+    pub profile: u8,
+
+    /// The Dolby Vision level, which bounds the resolution and frame rate.
+    pub level: u8,
+
+    /// Whether there is a reference processing unit, the dynamic metadata, in the stream.
+    pub rpu_present: bool,
+
+    /// Whether there is an enhancement layer in the stream.
+    pub el_present: bool,
+
+    /// Whether there is a base layer in the stream.
+    pub bl_present: bool,
+
+    /// What a decoder that ignores the Dolby Vision metadata gets out of the base layer:
+    /// 0 for nothing usable, 1 for HDR10, 2 for SDR, 4 for HLG and 6 for BT.2100 PQ.
+    pub bl_signal_compatibility_id: u8,
+}
+
+impl DoviConfigBox {
+    pub(crate) const SIZE: u64 = HEADER_SIZE + 24;
+
+    pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let version_major = reader.read_u8()?;
+        let version_minor = reader.read_u8()?;
+        let bits = reader.read_u16::<BigEndian>()?;
+        let compatibility = reader.read_u8()?;
+        Ok(Self {
+            version_major,
+            version_minor,
+            profile: (bits >> 9) as u8 & 0x7f,
+            level: (bits >> 3) as u8 & 0x3f,
+            rpu_present: (bits >> 2) & 0x1 == 1,
+            el_present: (bits >> 1) & 0x1 == 1,
+            bl_present: bits & 0x1 == 1,
+            bl_signal_compatibility_id: compatibility >> 4,
+        })
+    }
+}
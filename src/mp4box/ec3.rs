@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::bits::BitReader;
+use crate::mp4box::ac3::{acmod_channel_count, fscod_sample_rate, read_children, AudioSampleEntry};
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, read_bytes, value_u32, BoxType, FixedPointU16, Mp4Box, ReadBox, Result, HEADER_SIZE,
+};
+
+/// Sample entry of Dolby Digital Plus (E-AC-3) audio, see ETSI TS 102 366 annex F.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ec3Box {
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+
+    #[serde(with = "value_u32")]
+    pub samplerate: FixedPointU16,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dec3: Option<Dec3Box>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+/// The E-AC-3 specific box, which describes the substreams of the bit stream.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Dec3Box {
+    /// Data rate of the bit stream in kbit/s.
+    pub data_rate: u16,
+
+    /// The independent substreams, each of which is a separate program.
+    pub substreams: Vec<Ec3Substream>,
+}
+
+/// An independent substream of an E-AC-3 bit stream, together with its dependent substreams.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Ec3Substream {
+    pub fscod: u8,
+    pub bsid: u8,
+
+    /// Whether this substream is an associated service, like commentary, rather than a main one.
+    pub asvc: bool,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+
+    /// Number of dependent substreams that add channels to this one.
+    pub num_dep_sub: u8,
+
+    /// The channels that the dependent substreams add, as a bit mask, see table F.6.1.
+    pub chan_loc: u16,
+}
+
+impl Ec3Box {
+    pub fn get_type() -> BoxType {
+        BoxType::Ec3Box
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + 20;
+        if let Some(dec3) = &self.dec3 {
+            size += HEADER_SIZE + 2;
+            for substream in &dec3.substreams {
+                size += if substream.num_dep_sub > 0 { 4 } else { 3 };
+            }
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for Ec3Box {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "channel_count={} sample_size={} sample_rate={}",
+            self.channelcount,
+            self.samplesize,
+            self.samplerate.value()
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for Ec3Box {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        let fields = AudioSampleEntry::read(reader)?;
+
+        let mut dec3 = None;
+        let mut btrt = None;
+        read_children(reader, BoxType::Ec3Box, end, |reader, name, s| {
+            match name {
+                BoxType::Dec3Box if dec3.is_none() => {
+                    let contents = read_bytes(reader, s - HEADER_SIZE)?;
+                    dec3 = Some(Dec3Box::parse(&contents)?);
+                }
+                BoxType::BtrtBox if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        Ok(Self {
+            data_reference_index: fields.data_reference_index,
+            channelcount: fields.channelcount,
+            samplesize: fields.samplesize,
+            samplerate: fields.samplerate,
+            dec3,
+            btrt,
+        })
+    }
+}
+
+impl Dec3Box {
+    fn parse(contents: &[u8]) -> Result<Self> {
+        let mut bits = BitReader::new(contents, "dec3 box is too short");
+        let data_rate = bits.read(13)? as u16;
+        let num_ind_sub = bits.read(3)? + 1;
+
+        let mut substreams = Vec::with_capacity(num_ind_sub as usize);
+        for _ in 0..num_ind_sub {
+            let fscod = bits.read(2)? as u8;
+            let bsid = bits.read(5)? as u8;
+            bits.read(1)?; // reserved
+            let asvc = bits.read(1)? == 1;
+            let bsmod = bits.read(3)? as u8;
+            let acmod = bits.read(3)? as u8;
+            let lfeon = bits.read(1)? == 1;
+            bits.read(3)?; // reserved
+            let num_dep_sub = bits.read(4)? as u8;
+            let chan_loc = if num_dep_sub > 0 {
+                bits.read(9)? as u16
+            } else {
+                bits.read(1)?; // reserved
+                0
+            };
+            substreams.push(Ec3Substream {
+                fscod,
+                bsid,
+                asvc,
+                bsmod,
+                acmod,
+                lfeon,
+                num_dep_sub,
+                chan_loc,
+            });
+        }
+
+        Ok(Self {
+            data_rate,
+            substreams,
+        })
+    }
+
+    /// The number of channels of the first program, including low frequency effects channels.
+    pub fn channel_count(&self) -> Option<u16> {
+        let substream = self.substreams.first()?;
+        // Bits 0, 1, 4, 5 and 6 stand for pairs of channels, the others for single ones.
+        let pairs = (substream.chan_loc & 0b0_0111_0011).count_ones() as u16;
+        let singles = (substream.chan_loc & 0b1_1000_1100).count_ones() as u16;
+        Some(
+            acmod_channel_count(substream.acmod) + u16::from(substream.lfeon) + 2 * pairs + singles,
+        )
+    }
+
+    /// The sample rate in Hz of the first program, or `None` if it is not signaled.
+    pub fn sample_rate(&self) -> Option<u32> {
+        fscod_sample_rate(self.substreams.first()?.fscod)
+    }
+
+    /// The bitrate in bits per second.
+    pub fn bitrate(&self) -> u32 {
+        u32::from(self.data_rate) * 1000
+    }
+}
@@ -1,12 +1,13 @@
-use serde::Serialize;
-use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::elst::ElstBox;
 use crate::mp4box::{
-    box_start, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result, HEADER_SIZE,
+    box_start, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result, WriteBox,
+    HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct EdtsBox {
     pub elst: Option<ElstBox>,
 }
@@ -72,3 +73,15 @@ impl<R: Read + Seek> ReadBox<&mut R> for EdtsBox {
         Ok(edts)
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for EdtsBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(ref elst) = self.elst {
+            elst.write_box(writer)?;
+        }
+        Ok(size)
+    }
+}
@@ -1,23 +1,23 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct ElstBox {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub entries: Vec<ElstEntry>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct ElstEntry {
     pub segment_duration: u64,
     pub media_time: u64,
@@ -39,6 +39,16 @@ impl ElstBox {
         }
         size
     }
+
+    /// Whether `entry` is an empty edit, which inserts `segment_duration` of nothing into the
+    /// presentation, signaled by a `media_time` of -1.
+    pub fn is_empty_edit(&self, entry: &ElstEntry) -> bool {
+        if self.version == 1 {
+            entry.media_time == u64::MAX
+        } else {
+            entry.media_time == u64::from(u32::MAX)
+        }
+    }
 }
 
 impl Mp4Box for ElstBox {
@@ -121,3 +131,26 @@ impl<R: Read + Seek> ReadBox<&mut R> for ElstBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for ElstBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in &self.entries {
+            if self.version == 1 {
+                writer.write_u64::<BigEndian>(entry.segment_duration)?;
+                writer.write_u64::<BigEndian>(entry.media_time)?;
+            } else {
+                writer.write_u32::<BigEndian>(entry.segment_duration as u32)?;
+                writer.write_u32::<BigEndian>(entry.media_time as u32)?;
+            }
+            writer.write_u16::<BigEndian>(entry.media_rate)?;
+            writer.write_u16::<BigEndian>(entry.media_rate_fraction)?;
+        }
+        Ok(size)
+    }
+}
@@ -2,14 +2,14 @@ use std::ffi::CStr;
 use std::io::{Read, Seek};
 
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::mp4box::{
     box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
     HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct EmsgBox {
     pub version: u8,
     pub flags: u32,
@@ -100,7 +100,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for EmsgBox {
             _ => return Err(Error::InvalidData("version must be 0 or 1")),
         };
 
-        let message_size = size - Self::size_without_message(version, &scheme_id_uri, &value);
+        let message_size = size
+            .checked_sub(Self::size_without_message(version, &scheme_id_uri, &value))
+            .ok_or(Error::InvalidData("emsg size too small"))?;
         let mut message_data = Vec::with_capacity(message_size as usize);
         for _ in 0..message_size {
             message_data.push(reader.read_u8()?);
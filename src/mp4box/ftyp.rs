@@ -1,12 +1,13 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, skip_bytes_to, BoxType, Error, FourCC, Mp4Box, ReadBox, Result, HEADER_SIZE,
+    box_start, skip_bytes_to, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox, Result, WriteBox,
+    HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct FtypBox {
     pub major_brand: FourCC,
     pub minor_version: u32,
@@ -77,3 +78,16 @@ impl<R: Read + Seek> ReadBox<&mut R> for FtypBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for FtypBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+        writer.write_u32::<BigEndian>(self.major_brand.into())?;
+        writer.write_u32::<BigEndian>(self.minor_version)?;
+        for brand in &self.compatible_brands {
+            writer.write_u32::<BigEndian>((*brand).into())?;
+        }
+        Ok(size)
+    }
+}
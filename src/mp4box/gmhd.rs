@@ -0,0 +1,187 @@
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
+
+use crate::mp4box::{
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
+};
+use crate::skipped::{log_skipped, Skipped};
+
+/// `QuickTime` base media header, used instead of `vmhd` or `smhd` by tracks that are neither
+/// video nor sound, like the GPMF telemetry tracks of GoPro cameras.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GmhdBox {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gmin: Option<GminBox>,
+}
+
+/// `QuickTime` base media info, which says how to draw the track.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GminBox {
+    pub version: u8,
+    pub flags: u32,
+    pub graphics_mode: u16,
+    pub opcolor: [u16; 3],
+    pub balance: i16,
+}
+
+impl GmhdBox {
+    pub fn get_type() -> BoxType {
+        BoxType::GmhdBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        if self.gmin.is_some() {
+            size += GminBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for GmhdBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = String::new();
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for GmhdBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut gmin = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+            if s < HEADER_SIZE || current + s > end {
+                return Err(Error::InvalidData(
+                    "gmhd box contains a box with a larger size than it",
+                ));
+            }
+
+            if name == BoxType::GminBox && gmin.is_none() {
+                gmin = Some(GminBox::read_box(reader, s)?);
+            } else {
+                // Such as the `tmcd` box of timecode tracks, or `text` of text tracks.
+                log_skipped(&Skipped::Box {
+                    parent: BoxType::GmhdBox.into(),
+                    box_type: name.into(),
+                    offset: current,
+                    size: s,
+                });
+            }
+
+            skip_bytes_to(reader, current + s)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, end)?;
+
+        Ok(Self { gmin })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for GmhdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(gmin) = &self.gmin {
+            gmin.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+impl GminBox {
+    pub fn get_type() -> BoxType {
+        BoxType::GminBox
+    }
+
+    pub fn get_size() -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 12
+    }
+}
+
+impl Mp4Box for GminBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        Self::get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "graphics_mode={} opcolor={:?} balance={}",
+            self.graphics_mode, self.opcolor, self.balance
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for GminBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let graphics_mode = reader.read_u16::<BigEndian>()?;
+        let opcolor = [
+            reader.read_u16::<BigEndian>()?,
+            reader.read_u16::<BigEndian>()?,
+            reader.read_u16::<BigEndian>()?,
+        ];
+        let balance = reader.read_i16::<BigEndian>()?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self {
+            version,
+            flags,
+            graphics_mode,
+            opcolor,
+            balance,
+        })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for GminBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u16::<BigEndian>(self.graphics_mode)?;
+        for component in self.opcolor {
+            writer.write_u16::<BigEndian>(component)?;
+        }
+        writer.write_i16::<BigEndian>(self.balance)?;
+        writer.write_u16::<BigEndian>(0)?; // reserved
+
+        Ok(size)
+    }
+}
@@ -0,0 +1,63 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::{box_start, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+use crate::skipped::skip_children;
+
+/// Sample entry of the GoPro Metadata Format (GPMF) tracks of GoPro cameras, whose samples
+/// hold telemetry such as GPS, accelerometer and gyroscope readings.
+///
+/// The samples are GPMF key-length-value streams, see <https://github.com/gopro/gpmf-parser>.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GpmdBox {
+    pub data_reference_index: u16,
+}
+
+impl GpmdBox {
+    pub fn get_type() -> BoxType {
+        BoxType::GpmdBox
+    }
+
+    pub fn get_size() -> u64 {
+        HEADER_SIZE + 8 + 4
+    }
+}
+
+impl Mp4Box for GpmdBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        Self::get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("data_reference_index={}", self.data_reference_index);
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for GpmdBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+        if reader.stream_position()? + 4 <= start + size {
+            reader.read_u32::<BigEndian>()?; // reserved
+        }
+
+        skip_children(reader, start, start + size)?;
+
+        Ok(Self {
+            data_reference_index,
+        })
+    }
+}
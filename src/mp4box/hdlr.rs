@@ -1,13 +1,14 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes, skip_bytes_to, BoxType, Error, FourCC, Mp4Box,
-    ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, read_bytes, skip_bytes, skip_bytes_to, write_box_header_ext,
+    write_zeros, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox, Result, WriteBox,
+    HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct HdlrBox {
     pub version: u8,
     pub flags: u32,
@@ -39,7 +40,15 @@ impl Mp4Box for HdlrBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = format!("handler_type={} name={}", self.handler_type, self.name);
+        let description = self
+            .handler_type
+            .description()
+            .map(|description| format!(" ({description})"))
+            .unwrap_or_default();
+        let s = format!(
+            "handler_type={}{description} name={}",
+            self.handler_type, self.name
+        );
         Ok(s)
     }
 }
@@ -59,8 +68,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for HdlrBox {
             .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE + 20)
             .ok_or(Error::InvalidData("hdlr size too small"))?;
 
-        let mut buf = vec![0u8; buf_size as usize];
-        reader.read_exact(&mut buf)?;
+        let mut buf = read_bytes(reader, buf_size)?;
         if let Some(end) = buf.iter().position(|&b| b == b'\0') {
             buf.truncate(end);
         }
@@ -76,3 +84,21 @@ impl<R: Read + Seek> ReadBox<&mut R> for HdlrBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for HdlrBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(0)?; // pre-defined
+        writer.write_u32::<BigEndian>(self.handler_type.into())?;
+        write_zeros(writer, 12)?; // reserved
+
+        writer.write_all(self.name.as_bytes())?;
+        writer.write_u8(0)?;
+
+        Ok(size)
+    }
+}
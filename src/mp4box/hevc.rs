@@ -1,14 +1,21 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
+use crate::bits::{rbsp, BitReader};
+
+use crate::mp4box::btrt::{read_btrt_with, BtrtBox};
+use crate::mp4box::clap::read_clap;
+use crate::mp4box::colr::read_colr;
+use crate::mp4box::dovi::DoviConfigBox;
+use crate::mp4box::pasp::read_pasp;
 use crate::mp4box::{
-    box_start, skip_bytes, skip_bytes_to, value_u32, BoxHeader, BoxType, Error, FixedPointU16,
-    Mp4Box, RawBox, ReadBox, Result, HEADER_SIZE,
+    box_start, skip_bytes, value_u32, BoxHeader, BoxType, ClapBox, ColrBox, Error, FixedPointU16,
+    Mp4Box, PaspBox, RawBox, ReadBox, Result, WriteBox, HEADER_SIZE,
 };
 
-/// HEVC/H.265 box found for both `avc1` and `hvc1`.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// HEVC/H.265 box found for `hvc1` and `hev1`, and for the Dolby Vision `dvh1` and `dvhe`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HevcBox {
     pub data_reference_index: u16,
     pub width: u16,
@@ -22,6 +29,19 @@ pub struct HevcBox {
     pub frame_count: u16,
     pub depth: u16, // This is usually 24, even for HDR with bit_depth=10
     pub hvcc: RawBox<HevcDecoderConfigurationRecord>,
+
+    /// The Dolby Vision configuration, from a `dvcC`, `dvvC` or `dvwC` box.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dovi: Option<DoviConfigBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pasp: Option<PaspBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clap: Option<ClapBox>,
 }
 
 impl Default for HevcBox {
@@ -35,6 +55,11 @@ impl Default for HevcBox {
             frame_count: 1,
             depth: 0x0018,
             hvcc: RawBox::default(),
+            dovi: None,
+            btrt: None,
+            colr: None,
+            pasp: None,
+            clap: None,
         }
     }
 }
@@ -45,7 +70,23 @@ impl HevcBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.hvcc.box_size()
+        let mut size = HEADER_SIZE + 8 + 70 + self.hvcc.box_size();
+        if self.dovi.is_some() {
+            size += DoviConfigBox::SIZE;
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        if let Some(colr) = &self.colr {
+            size += colr.get_size();
+        }
+        if self.pasp.is_some() {
+            size += PaspBox::get_size();
+        }
+        if self.clap.is_some() {
+            size += ClapBox::get_size();
+        }
+        size
     }
 }
 
@@ -102,7 +143,25 @@ impl<R: Read + Seek> ReadBox<&mut R> for HevcBox {
         if name == BoxType::HvcCBox {
             let hvcc = RawBox::<HevcDecoderConfigurationRecord>::read_box(reader, s)?;
 
-            skip_bytes_to(reader, start + size)?;
+            let mut dovi = None;
+            let mut colr = None;
+            let mut pasp = None;
+            let mut clap = None;
+            let btrt = read_btrt_with(reader, start, start + size, |reader, name, s| {
+                if read_colr(reader, name, s, &mut colr)?
+                    || read_pasp(reader, name, s, &mut pasp)?
+                    || read_clap(reader, name, s, &mut clap)?
+                {
+                    return Ok(true);
+                }
+                match name {
+                    BoxType::DvcCBox | BoxType::DvvCBox | BoxType::DvwCBox if dovi.is_none() => {
+                        dovi = Some(DoviConfigBox::read(reader)?);
+                    }
+                    _ => return Ok(false),
+                }
+                Ok(true)
+            })?;
 
             Ok(Self {
                 data_reference_index,
@@ -113,6 +172,11 @@ impl<R: Read + Seek> ReadBox<&mut R> for HevcBox {
                 frame_count,
                 depth,
                 hvcc,
+                dovi,
+                btrt,
+                colr,
+                pasp,
+                clap,
             })
         } else {
             Err(Error::InvalidData("hvcc not found"))
@@ -124,7 +188,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for HevcBox {
 // Hvcc box but in fact it is shared by 'hvc1' and 'hev1'.
 // Going with what ffmpeg does here and be more explicit.
 // https://ffmpeg.org/doxygen/6.0/structHEVCDecoderConfigurationRecord.html
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HevcDecoderConfigurationRecord {
     pub configuration_version: u8,
     pub general_profile_space: u8,
@@ -153,8 +217,110 @@ impl HevcDecoderConfigurationRecord {
             ..Default::default()
         }
     }
+
+    /// The configuration of a stream with the given parameter sets, which are NAL units
+    /// without a start code or length prefix.
+    ///
+    /// The profile, tier, level, chroma format and bit depths are taken from the SPS, and the
+    /// samples are expected to prefix their NAL units with a 4 byte length.
+    pub fn from_parameter_sets(vps: &[u8], sps: &[u8], pps: &[u8]) -> Result<Self> {
+        let rbsp = rbsp(sps.get(2..).unwrap_or_default());
+        let mut bits = BitReader::new(&rbsp, "HEVC sequence parameter set is too short");
+
+        bits.skip(4)?; // sps_video_parameter_set_id
+        let max_sub_layers_minus1 = bits.read(3)? as usize;
+        let temporal_id_nested = bits.read_bit()?;
+
+        // profile_tier_level, see ITU-T H.265 section 7.3.3.
+        let general_profile_space = bits.read(2)? as u8;
+        let general_tier_flag = bits.read_bit()?;
+        let general_profile_idc = bits.read(5)? as u8;
+        let general_profile_compatibility_flags = bits.read(32)?;
+        let general_constraint_indicator_flag =
+            (u64::from(bits.read(16)?) << 32) | u64::from(bits.read(32)?);
+        let general_level_idc = bits.read(8)? as u8;
+        let mut sub_layers = Vec::with_capacity(max_sub_layers_minus1);
+        for _ in 0..max_sub_layers_minus1 {
+            sub_layers.push((bits.read_bit()?, bits.read_bit()?));
+        }
+        if max_sub_layers_minus1 > 0 {
+            bits.skip(2 * (8 - max_sub_layers_minus1))?; // reserved_zero_2bits
+        }
+        for (profile_present, level_present) in sub_layers {
+            if profile_present {
+                bits.skip(88)?;
+            }
+            if level_present {
+                bits.skip(8)?;
+            }
+        }
+
+        bits.read_ue()?; // sps_seq_parameter_set_id
+        let chroma_format_idc = bits.read_ue()?;
+        if chroma_format_idc == 3 {
+            bits.skip(1)?; // separate_colour_plane_flag
+        }
+        bits.read_ue()?; // pic_width_in_luma_samples
+        bits.read_ue()?; // pic_height_in_luma_samples
+        if bits.read_bit()? {
+            // conformance_window_flag
+            for _ in 0..4 {
+                bits.read_ue()?;
+            }
+        }
+        let bit_depth_luma_minus8 = bits.read_ue()?;
+        let bit_depth_chroma_minus8 = bits.read_ue()?;
+        if chroma_format_idc > 3 || bit_depth_luma_minus8 > 8 || bit_depth_chroma_minus8 > 8 {
+            return Err(Error::InvalidData(
+                "HEVC sequence parameter set with an invalid format",
+            ));
+        }
+
+        let mut arrays = Vec::with_capacity(3);
+        for (nal_unit_type, nal_unit) in [
+            (NAL_UNIT_TYPE_VPS, vps),
+            (NAL_UNIT_TYPE_SPS, sps),
+            (NAL_UNIT_TYPE_PPS, pps),
+        ] {
+            let size = u16::try_from(nal_unit.len())
+                .map_err(|_err| Error::InvalidData("HEVC parameter set too large for hvcC"))?;
+            arrays.push(HvcCArray {
+                completeness: true,
+                nal_unit_type,
+                nalus: vec![HvcCArrayNalu {
+                    size,
+                    data: nal_unit.to_vec(),
+                }],
+            });
+        }
+
+        Ok(Self {
+            configuration_version: 1,
+            general_profile_space,
+            general_tier_flag,
+            general_profile_idc,
+            general_profile_compatibility_flags,
+            general_constraint_indicator_flag,
+            general_level_idc,
+            min_spatial_segmentation_idc: 0,
+            parallelism_type: 0,
+            chroma_format_idc: chroma_format_idc as u8,
+            bit_depth_luma_minus8: bit_depth_luma_minus8 as u8,
+            bit_depth_chroma_minus8: bit_depth_chroma_minus8 as u8,
+            avg_frame_rate: 0,
+            constant_frame_rate: 0,
+            num_temporal_layers: max_sub_layers_minus1 as u8 + 1,
+            temporal_id_nested,
+            length_size_minus_one: 3,
+            arrays,
+        })
+    }
 }
 
+const NAL_UNIT_TYPE_VPS: u8 = 32;
+const NAL_UNIT_TYPE_SPS: u8 = 33;
+const NAL_UNIT_TYPE_PPS: u8 = 34;
+
 impl Mp4Box for HevcDecoderConfigurationRecord {
     fn box_type(&self) -> BoxType {
         BoxType::HvcCBox
@@ -197,13 +363,13 @@ impl Mp4Box for HevcDecoderConfigurationRecord {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct HvcCArrayNalu {
     pub size: u16,
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct HvcCArray {
     pub completeness: bool,
     pub nal_unit_type: u8,
@@ -280,3 +446,52 @@ impl<R: Read + Seek> ReadBox<&mut R> for HevcDecoderConfigurationRecord {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for HevcDecoderConfigurationRecord {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u8(self.configuration_version)?;
+        writer.write_u8(
+            (self.general_profile_space << 6)
+                | (u8::from(self.general_tier_flag) << 5)
+                | (self.general_profile_idc & 0b11111),
+        )?;
+        writer.write_u32::<BigEndian>(self.general_profile_compatibility_flags)?;
+        writer.write_u48::<BigEndian>(self.general_constraint_indicator_flag)?;
+        writer.write_u8(self.general_level_idc)?;
+
+        // The reserved bits before the narrower fields are all ones.
+        writer.write_u16::<BigEndian>(0xF000 | self.min_spatial_segmentation_idc)?;
+        writer.write_u8(0b11111100 | self.parallelism_type)?;
+        writer.write_u8(0b11111100 | self.chroma_format_idc)?;
+        writer.write_u8(0b11111000 | self.bit_depth_luma_minus8)?;
+        writer.write_u8(0b11111000 | self.bit_depth_chroma_minus8)?;
+        writer.write_u16::<BigEndian>(self.avg_frame_rate)?;
+        writer.write_u8(
+            (self.constant_frame_rate << 6)
+                | (self.num_temporal_layers << 3)
+                | (u8::from(self.temporal_id_nested) << 2)
+                | self.length_size_minus_one,
+        )?;
+
+        let num_of_arrays = u8::try_from(self.arrays.len())
+            .map_err(|_err| Error::InvalidData("too many arrays in hvcC"))?;
+        writer.write_u8(num_of_arrays)?;
+        for array in &self.arrays {
+            let num_nalus = u16::try_from(array.nalus.len())
+                .map_err(|_err| Error::InvalidData("too many NAL units in an hvcC array"))?;
+            writer.write_u8((u8::from(array.completeness) << 7) | (array.nal_unit_type & 0x3f))?;
+            writer.write_u16::<BigEndian>(num_nalus)?;
+            for nalu in &array.nalus {
+                let nalu_size = u16::try_from(nalu.data.len())
+                    .map_err(|_err| Error::InvalidData("NAL unit too large for hvcC"))?;
+                writer.write_u16::<BigEndian>(nalu_size)?;
+                writer.write_all(&nalu.data)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
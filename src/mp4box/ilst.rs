@@ -1,19 +1,23 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::io::{Read, Seek};
 
 use byteorder::ByteOrder as _;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::mp4box::data::DataBox;
 use crate::mp4box::{
-    box_start, skip_box, skip_bytes_to, BigEndian, BoxHeader, BoxType, DataType, Error, Metadata,
-    MetadataKey, Mp4Box, ReadBox, Result, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_box, skip_bytes_to, BigEndian, BoxHeader, BoxType,
+    DataType, Error, Metadata, MetadataKey, Mp4Box, ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct IlstBox {
-    pub items: HashMap<MetadataKey, IlstItemBox>,
+    /// Items in file order. A key may appear more than once, as some files repeat items.
+    pub items: Vec<(MetadataKey, IlstItemBox)>,
+
+    /// Freeform items (`----`), in file order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub freeform: Vec<IlstFreeformItem>,
 }
 
 impl IlstBox {
@@ -22,7 +26,39 @@ impl IlstBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + self.items.values().map(|item| item.get_size()).sum::<u64>()
+        HEADER_SIZE
+            + self
+                .items
+                .iter()
+                .map(|(_, item)| item.get_size())
+                .sum::<u64>()
+            + self
+                .freeform
+                .iter()
+                .map(|item| item.get_size())
+                .sum::<u64>()
+    }
+
+    /// The first item with `key`, which is the one players show.
+    pub fn item(&self, key: MetadataKey) -> Option<&IlstItemBox> {
+        self.items_with_key(key).next()
+    }
+
+    /// All items with `key`, in file order.
+    pub fn items_with_key(&self, key: MetadataKey) -> impl Iterator<Item = &IlstItemBox> {
+        self.items
+            .iter()
+            .filter(move |(item_key, _)| *item_key == key)
+            .map(|(_, item)| item)
+    }
+
+    /// The value of the freeform item with the given `mean` and `name`, like
+    /// `com.apple.iTunes` and `iTunSMPB`.
+    pub fn freeform_item(&self, mean: &str, name: &str) -> Option<&DataBox> {
+        self.freeform
+            .iter()
+            .find(|item| item.mean == mean && item.name == name)
+            .map(|item| &item.data)
     }
 }
 
@@ -40,7 +76,11 @@ impl Mp4Box for IlstBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = format!("item_count={}", self.items.len());
+        let s = format!(
+            "item_count={} freeform_count={}",
+            self.items.len(),
+            self.freeform.len()
+        );
         Ok(s)
     }
 }
@@ -49,7 +89,8 @@ impl<R: Read + Seek> ReadBox<&mut R> for IlstBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
-        let mut items = HashMap::new();
+        let mut items = Vec::new();
+        let mut freeform = Vec::new();
 
         let mut current = reader.stream_position()?;
         let end = start + size;
@@ -65,16 +106,19 @@ impl<R: Read + Seek> ReadBox<&mut R> for IlstBox {
 
             match name {
                 BoxType::NameBox => {
-                    items.insert(MetadataKey::Title, IlstItemBox::read_box(reader, s)?);
+                    items.push((MetadataKey::Title, IlstItemBox::read_box(reader, s)?));
                 }
                 BoxType::DayBox => {
-                    items.insert(MetadataKey::Year, IlstItemBox::read_box(reader, s)?);
+                    items.push((MetadataKey::Year, IlstItemBox::read_box(reader, s)?));
                 }
                 BoxType::CovrBox => {
-                    items.insert(MetadataKey::Poster, IlstItemBox::read_box(reader, s)?);
+                    items.push((MetadataKey::Poster, IlstItemBox::read_box(reader, s)?));
                 }
                 BoxType::DescBox => {
-                    items.insert(MetadataKey::Summary, IlstItemBox::read_box(reader, s)?);
+                    items.push((MetadataKey::Summary, IlstItemBox::read_box(reader, s)?));
+                }
+                BoxType::FreeformBox => {
+                    freeform.push(IlstFreeformItem::read_box(reader, s)?);
                 }
                 _ => {
                     // XXX warn!()
@@ -87,11 +131,11 @@ impl<R: Read + Seek> ReadBox<&mut R> for IlstBox {
 
         skip_bytes_to(reader, start + size)?;
 
-        Ok(Self { items })
+        Ok(Self { items, freeform })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct IlstItemBox {
     pub data: DataBox,
 }
@@ -143,21 +187,96 @@ impl<R: Read + Seek> ReadBox<&mut R> for IlstItemBox {
     }
 }
 
+/// An item of an [`IlstBox`] that is identified by strings rather than a four character code.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct IlstFreeformItem {
+    /// The reverse DNS namespace of the item, like `com.apple.iTunes`.
+    pub mean: String,
+
+    pub name: String,
+    pub data: DataBox,
+}
+
+impl IlstFreeformItem {
+    fn get_size(&self) -> u64 {
+        // The `mean` and `name` boxes are full boxes.
+        HEADER_SIZE
+            + (HEADER_SIZE + HEADER_EXT_SIZE + self.mean.len() as u64)
+            + (HEADER_SIZE + HEADER_EXT_SIZE + self.name.len() as u64)
+            + self.data.box_size()
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for IlstFreeformItem {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut mean = None;
+        let mut name = None;
+        let mut data = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name: typ, size: s } = header;
+            if s > size {
+                return Err(Error::InvalidData(
+                    "freeform item box contains a box with a larger size than it",
+                ));
+            }
+
+            match typ {
+                BoxType::MeanBox => mean = Some(read_string(reader, s)?),
+                BoxType::FreeformNameBox => name = Some(read_string(reader, s)?),
+                BoxType::DataBox => data = Some(DataBox::read_box(reader, s)?),
+                _ => skip_box(reader, s)?,
+            }
+
+            current = reader.stream_position()?;
+        }
+
+        let (Some(mean), Some(name), Some(data)) = (mean, name, data) else {
+            return Err(Error::InvalidData(
+                "freeform item lacks a mean, name or data box",
+            ));
+        };
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self { mean, name, data })
+    }
+}
+
+/// Reads the UTF-8 string that fills the rest of a full box, like `mean` and `name`.
+fn read_string<R: Read + Seek>(reader: &mut R, size: u64) -> Result<String> {
+    let start = box_start(reader)?;
+    read_box_header_ext(reader)?;
+    let len = size
+        .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
+        .ok_or(Error::InvalidData("freeform item string box too small"))?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    skip_bytes_to(reader, start + size)?;
+    String::from_utf8(bytes).map_err(|_err| Error::InvalidData("freeform item string is not UTF-8"))
+}
+
 impl Metadata<'_> for IlstBox {
     fn title(&self) -> Option<Cow<'_, str>> {
-        self.items.get(&MetadataKey::Title).map(item_to_str)
+        self.item(MetadataKey::Title).map(item_to_str)
     }
 
     fn year(&self) -> Option<u32> {
-        self.items.get(&MetadataKey::Year).and_then(item_to_u32)
+        self.item(MetadataKey::Year).and_then(item_to_u32)
     }
 
     fn poster(&self) -> Option<&[u8]> {
-        self.items.get(&MetadataKey::Poster).map(item_to_bytes)
+        self.item(MetadataKey::Poster).map(item_to_bytes)
     }
 
     fn summary(&self) -> Option<Cow<'_, str>> {
-        self.items.get(&MetadataKey::Summary).map(item_to_str)
+        self.item(MetadataKey::Summary).map(item_to_str)
     }
 }
 
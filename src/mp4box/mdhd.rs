@@ -1,14 +1,14 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
 use std::char::{decode_utf16, REPLACEMENT_CHARACTER};
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MdhdBox {
     pub version: u8,
     pub flags: u32,
@@ -113,6 +113,43 @@ impl<R: Read + Seek> ReadBox<&mut R> for MdhdBox {
     }
 }
 
+impl<W: Write> WriteBox<&mut W> for MdhdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        if self.version == 1 {
+            writer.write_u64::<BigEndian>(self.creation_time)?;
+            writer.write_u64::<BigEndian>(self.modification_time)?;
+            writer.write_u32::<BigEndian>(self.timescale)?;
+            writer.write_u64::<BigEndian>(self.duration)?;
+        } else if self.version == 0 {
+            writer.write_u32::<BigEndian>(self.creation_time as u32)?;
+            writer.write_u32::<BigEndian>(self.modification_time as u32)?;
+            writer.write_u32::<BigEndian>(self.timescale)?;
+            writer.write_u32::<BigEndian>(self.duration as u32)?;
+        } else {
+            return Err(Error::InvalidData("version must be 0 or 1"));
+        }
+
+        writer.write_u16::<BigEndian>(language_code(&self.language))?;
+        writer.write_u16::<BigEndian>(0)?; // pre-defined
+        Ok(size)
+    }
+}
+
+fn language_code(language: &str) -> u16 {
+    let mut lang = language.encode_utf16();
+    let mut code = 0;
+    for shift in [10, 5, 0] {
+        let c = lang.next().unwrap_or(0x60);
+        code |= (c.wrapping_sub(0x60) & 0x1F) << shift;
+    }
+    code
+}
+
 fn language_string(language: u16) -> String {
     let mut lang: [u16; 3] = [0; 3];
 
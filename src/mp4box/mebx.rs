@@ -0,0 +1,230 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, read_bytes, skip_bytes_to, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox,
+    Result, HEADER_SIZE,
+};
+use crate::skipped::{log_skipped, Skipped};
+
+/// Sample entry of Apple timed metadata tracks, like the detected faces, motion and Live Photo
+/// information of iPhone videos.
+///
+/// Every sample is a sequence of boxes whose types are the local IDs of the [`Self::keys`] they
+/// hold values for, see [`Self::items`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MebxBox {
+    pub data_reference_index: u16,
+
+    /// The key table from the `keys` box, in file order.
+    pub keys: Vec<MebxKey>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+/// A key of the [`MebxBox::keys`] table.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MebxKey {
+    /// The ID that the items of samples use as their box type.
+    pub local_id: u32,
+
+    /// Namespace of the key from `keyd`, usually `mdta` for reverse DNS names.
+    pub namespace: FourCC,
+
+    /// The key from `keyd`, like `com.apple.quicktime.detected-face` in the `mdta` namespace.
+    pub value: Vec<u8>,
+
+    /// The well-known data type of the values of this key from `dtyp`, like 1 for UTF-8 or 23
+    /// for a 32-bit float, see the `QuickTime` file format specification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub well_known_type: Option<u32>,
+}
+
+impl MebxKey {
+    /// [`Self::value`] as text, if it is valid UTF-8.
+    pub fn name(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+}
+
+impl MebxBox {
+    pub fn get_type() -> BoxType {
+        BoxType::MebxBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + HEADER_SIZE;
+        for key in &self.keys {
+            size += HEADER_SIZE + HEADER_SIZE + 4 + key.value.len() as u64;
+            if key.well_known_type.is_some() {
+                size += HEADER_SIZE + 8;
+            }
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+
+    /// The key with the local ID `local_id`.
+    pub fn key(&self, local_id: u32) -> Option<&MebxKey> {
+        self.keys.iter().find(|key| key.local_id == local_id)
+    }
+
+    /// Splits the data of a sample into its items, each with its key and value.
+    ///
+    /// Fails if an item doesn't fit in the sample, or refers to a key that is not in the table.
+    pub fn items<'a>(&'a self, mut sample: &'a [u8]) -> Result<Vec<(&'a MebxKey, &'a [u8])>> {
+        let mut items = Vec::new();
+        while !sample.is_empty() {
+            let header = sample.first_chunk::<8>().ok_or(Error::InvalidData(
+                "mebx item header past the end of its sample",
+            ))?;
+            let [size @ .., a, b, c, d] = *header;
+            let size = u32::from_be_bytes(size) as usize;
+            let local_id = u32::from_be_bytes([a, b, c, d]);
+            if size < HEADER_SIZE as usize || size > sample.len() {
+                return Err(Error::InvalidData(
+                    "mebx item extends past the end of its sample",
+                ));
+            }
+            let key = self
+                .key(local_id)
+                .ok_or(Error::InvalidData("mebx item with an unknown key"))?;
+            items.push((key, &sample[HEADER_SIZE as usize..size]));
+            sample = &sample[size..];
+        }
+        Ok(items)
+    }
+}
+
+impl Mp4Box for MebxBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("keys={}", self.keys.len());
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for MebxBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        let mut keys = None;
+        let mut btrt = None;
+        loop {
+            let current = reader.stream_position()?;
+            if current + HEADER_SIZE > end {
+                break;
+            }
+            let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+            if s < HEADER_SIZE || current + s > end {
+                return Err(Error::InvalidData(
+                    "mebx box contains a box with a larger size than it",
+                ));
+            }
+            match &u32::from(name).to_be_bytes() {
+                b"keys" if keys.is_none() => {
+                    keys = Some(read_keys(reader, current + s)?);
+                }
+                b"btrt" if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => {
+                    log_skipped(&Skipped::Box {
+                        parent: BoxType::MebxBox.into(),
+                        box_type: name.into(),
+                        offset: current,
+                        size: s,
+                    });
+                }
+            }
+            skip_bytes_to(reader, current + s)?;
+        }
+        skip_bytes_to(reader, end)?;
+
+        Ok(Self {
+            data_reference_index,
+            keys: keys.ok_or(Error::InvalidData("mebx box without a keys box"))?,
+            btrt,
+        })
+    }
+}
+
+/// Reads the entries of a `keys` box up to `end`, each a box whose type is the local ID of the
+/// key and that holds a `keyd` box.
+fn read_keys<R: Read + Seek>(reader: &mut R, end: u64) -> Result<Vec<MebxKey>> {
+    let mut keys = Vec::new();
+    loop {
+        let current = reader.stream_position()?;
+        if current + HEADER_SIZE > end {
+            break;
+        }
+        let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+        if s < HEADER_SIZE || current + s > end {
+            return Err(Error::InvalidData(
+                "keys box contains a box with a larger size than it",
+            ));
+        }
+        keys.push(read_key(reader, u32::from(name), current + s)?);
+        skip_bytes_to(reader, current + s)?;
+    }
+    Ok(keys)
+}
+
+fn read_key<R: Read + Seek>(reader: &mut R, local_id: u32, end: u64) -> Result<MebxKey> {
+    let mut key = None;
+    let mut well_known_type = None;
+    loop {
+        let current = reader.stream_position()?;
+        if current + HEADER_SIZE > end {
+            break;
+        }
+        let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+        if s < HEADER_SIZE + 4 || current + s > end {
+            return Err(Error::InvalidData(
+                "mebx key contains a box with an invalid size",
+            ));
+        }
+        let namespace = reader.read_u32::<BigEndian>()?;
+        match &u32::from(name).to_be_bytes() {
+            b"keyd" if key.is_none() => {
+                let value = read_bytes(reader, s - HEADER_SIZE - 4)?;
+                key = Some((FourCC::from(namespace), value));
+            }
+            // Types in other namespaces are named by strings, like UTIs.
+            b"dtyp" if namespace == 0 && s >= HEADER_SIZE + 8 => {
+                well_known_type = Some(reader.read_u32::<BigEndian>()?);
+            }
+            _ => {}
+        }
+        skip_bytes_to(reader, current + s)?;
+    }
+
+    let (namespace, value) = key.ok_or(Error::InvalidData("mebx key without a keyd box"))?;
+    Ok(MebxKey {
+        local_id,
+        namespace,
+        value,
+        well_known_type,
+    })
+}
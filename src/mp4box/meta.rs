@@ -1,15 +1,15 @@
 use std::io::{Read, Seek};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::mp4box::hdlr::HdlrBox;
 use crate::mp4box::ilst::IlstBox;
 use crate::mp4box::{
-    box_start, skip_box, BigEndian, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox,
-    ReadBytesExt as _, Result, SeekFrom, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_bytes, skip_box, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox, Result,
+    SeekFrom, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "hdlr")]
 #[serde(rename_all = "lowercase")]
 pub enum MetaBox {
@@ -18,12 +18,13 @@ pub enum MetaBox {
         ilst: Option<IlstBox>,
     },
 
-    #[serde(skip)]
+    /// A meta box with a handler other than `mdir`, or without `hdlr`, as written by some
+    /// Android devices.
     Unknown {
-        #[serde(skip)]
-        hdlr: HdlrBox,
+        // Renamed so it doesn't clash with the tag of the enum.
+        #[serde(rename = "handler", default, skip_serializing_if = "Option::is_none")]
+        hdlr: Option<HdlrBox>,
 
-        #[serde(skip)]
         data: Vec<(BoxType, Vec<u8>)>,
     },
 }
@@ -35,6 +36,37 @@ impl MetaBox {
         BoxType::MetaBox
     }
 
+    /// The iTunes-style item list, if this is an `mdir` meta box that has one.
+    pub fn ilst(&self) -> Option<&IlstBox> {
+        match self {
+            Self::Mdir { ilst } => ilst.as_ref(),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// The contents of the item location box (`iloc`), as in HEIF images.
+    pub fn iloc(&self) -> Option<&[u8]> {
+        self.child_data(BoxType::IlocBox)
+    }
+
+    /// The contents of the item information box (`iinf`), as in HEIF images.
+    pub fn iinf(&self) -> Option<&[u8]> {
+        self.child_data(BoxType::IinfBox)
+    }
+
+    /// The contents of the first child box of the given type, without its header.
+    ///
+    /// Only child boxes of meta boxes with a handler other than `mdir` are kept.
+    pub fn child_data(&self, box_type: BoxType) -> Option<&[u8]> {
+        match self {
+            Self::Mdir { .. } => None,
+            Self::Unknown { data, .. } => data
+                .iter()
+                .find(|(name, _)| *name == box_type)
+                .map(|(_, data)| data.as_slice()),
+        }
+    }
+
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE + HEADER_EXT_SIZE;
         match self {
@@ -45,7 +77,7 @@ impl MetaBox {
                 }
             }
             Self::Unknown { hdlr, data } => {
-                size += hdlr.box_size()
+                size += hdlr.as_ref().map_or(0, |hdlr| hdlr.box_size())
                     + data
                         .iter()
                         .map(|(_, data)| data.len() as u64 + HEADER_SIZE)
@@ -72,9 +104,13 @@ impl Mp4Box for MetaBox {
     fn summary(&self) -> Result<String> {
         let s = match self {
             Self::Mdir { .. } => "hdlr=ilst".to_owned(),
-            Self::Unknown { hdlr, data } => {
+            Self::Unknown {
+                hdlr: Some(hdlr),
+                data,
+            } => {
                 format!("hdlr={} data_len={}", hdlr.handler_type, data.len())
             }
+            Self::Unknown { hdlr: None, data } => format!("data_len={}", data.len()),
         };
         Ok(s)
     }
@@ -83,7 +119,7 @@ impl Mp4Box for MetaBox {
 impl Default for MetaBox {
     fn default() -> Self {
         Self::Unknown {
-            hdlr: Default::default(),
+            hdlr: None,
             data: Default::default(),
         }
     }
@@ -92,26 +128,20 @@ impl Default for MetaBox {
 impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
-
-        let extended_header = reader.read_u32::<BigEndian>()?;
-        if extended_header != 0 {
-            // ISO mp4 requires this header (version & flags) to be 0. Some
-            // files skip the extended header and directly start the hdlr box.
-            let possible_hdlr = BoxType::from(reader.read_u32::<BigEndian>()?);
-            if possible_hdlr == BoxType::HdlrBox {
-                // This file skipped the extended header! Go back to start.
-                reader.seek(SeekFrom::Current(-8))?;
-            } else {
-                // Looks like we actually have a bad version number or flags.
-                let v = (extended_header >> 24) as u8;
-                return Err(Error::UnsupportedBoxVersion(BoxType::MetaBox, v));
-            }
-        }
-
-        let mut current = reader.stream_position()?;
         let end = start + size;
 
-        let content_start = current;
+        let contents_start = start + HEADER_SIZE;
+        let mut first_bytes = vec![0; end.saturating_sub(contents_start).min(8) as usize];
+        reader.read_exact(&mut first_bytes)?;
+        let Some(offset) = children_offset(&first_bytes) else {
+            return Err(Error::UnsupportedBoxVersion(
+                BoxType::MetaBox,
+                first_bytes[0],
+            ));
+        };
+        let content_start = (contents_start + offset).min(end);
+        reader.seek(SeekFrom::Start(content_start))?;
+        let mut current = content_start;
 
         // find the hdlr box
         let mut hdlr = None;
@@ -119,9 +149,14 @@ impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
             // Get box header.
             let header = BoxHeader::read(reader)?;
             let BoxHeader { name, size: s } = header;
+            if s > size {
+                return Err(Error::InvalidData(
+                    "meta box contains a box with a larger size than it",
+                ));
+            }
 
             match name {
-                BoxType::HdlrBox => {
+                BoxType::HdlrBox if hdlr.is_none() => {
                     hdlr = Some(HdlrBox::read_box(reader, s)?);
                 }
                 _ => {
@@ -133,17 +168,13 @@ impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
             current = reader.stream_position()?;
         }
 
-        let Some(hdlr) = hdlr else {
-            return Err(Error::BoxNotFound(BoxType::HdlrBox));
-        };
-
         // rewind and handle the other boxes
         reader.seek(SeekFrom::Start(content_start))?;
         current = reader.stream_position()?;
 
         let mut ilst = None;
 
-        if hdlr.handler_type == MDIR {
+        if hdlr.as_ref().is_some_and(|hdlr| hdlr.handler_type == MDIR) {
             while current < end {
                 // Get box header.
                 let header = BoxHeader::read(reader)?;
@@ -164,6 +195,8 @@ impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
 
             Ok(Self::Mdir { ilst })
         } else {
+            // Without `hdlr` there is no telling what the children mean, so they are all kept
+            // as they are.
             let mut data = Vec::new();
 
             while current < end {
@@ -174,8 +207,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
                 if name == BoxType::HdlrBox {
                     skip_box(reader, s)?;
                 } else {
-                    let mut box_data = vec![0; (s - HEADER_SIZE) as usize];
-                    reader.read_exact(&mut box_data)?;
+                    let box_data = read_bytes(reader, s - HEADER_SIZE)?;
 
                     data.push((name, box_data));
                 }
@@ -187,3 +219,26 @@ impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
         }
     }
 }
+
+/// The offset of the first child of a `meta` box from the start of its contents, given the
+/// first 8 bytes of the contents, or `None` if it has a version or flags other than 0.
+///
+/// ISO/IEC 14496-12 makes `meta` a full box, but `QuickTime` leaves out the version and flags,
+/// so that the contents start with the header of a child box, usually `hdlr`.
+pub(crate) fn children_offset(contents: &[u8]) -> Option<u64> {
+    if contents.len() < 4 || contents[..4] == [0; 4] {
+        return Some(HEADER_EXT_SIZE);
+    }
+    contents
+        .get(4..8)
+        .filter(|box_type| is_box_type(box_type))
+        .map(|_| 0)
+}
+
+/// Whether `bytes` look like a box type, which are printable ASCII apart from the `©` of
+/// `QuickTime` metadata.
+fn is_box_type(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|&byte| byte.is_ascii_graphic() || byte == b' ' || byte == 0xa9)
+}
@@ -0,0 +1,101 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::btrt::{read_btrt, BtrtBox};
+use crate::mp4box::{box_start, BoxType, Error, Mp4Box, ReadBox, Result, HEADER_SIZE};
+
+/// Sample entry of a timed metadata track whose samples are text, such as JSON or XML, see
+/// ISO/IEC 14496-12 section 12.3.3.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MettBox {
+    pub data_reference_index: u16,
+
+    /// Content encoding of the samples, like `gzip`, or empty if they are not encoded.
+    pub content_encoding: String,
+
+    /// MIME type of the samples, like `application/json`.
+    pub mime_format: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+impl MettBox {
+    pub fn get_type() -> BoxType {
+        BoxType::MettBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE
+            + 8
+            + self.content_encoding.len() as u64
+            + 1
+            + self.mime_format.len() as u64
+            + 1;
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for MettBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "mime_format={} content_encoding={}",
+            self.mime_format, self.content_encoding
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for MettBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        let content_encoding = read_string(reader, end)?;
+        let mime_format = read_string(reader, end)?;
+
+        let btrt = read_btrt(reader, start, end)?;
+
+        Ok(Self {
+            data_reference_index,
+            content_encoding,
+            mime_format,
+            btrt,
+        })
+    }
+}
+
+/// Reads a nul-terminated UTF-8 string that ends before `end`.
+pub(crate) fn read_string<R: Read + Seek>(reader: &mut R, end: u64) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        if reader.stream_position()? >= end {
+            return Err(Error::InvalidData("string without a nul terminator"));
+        }
+        match reader.read_u8()? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_err| Error::InvalidData("invalid utf8"))
+}
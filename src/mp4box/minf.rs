@@ -1,13 +1,13 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
 use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
     HEADER_SIZE,
 };
-use crate::mp4box::{dinf::DinfBox, smhd::SmhdBox, stbl::StblBox, vmhd::VmhdBox};
+use crate::mp4box::{dinf::DinfBox, gmhd::GmhdBox, smhd::SmhdBox, stbl::StblBox, vmhd::VmhdBox};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct MinfBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vmhd: Option<VmhdBox>,
@@ -15,6 +15,10 @@ pub struct MinfBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub smhd: Option<SmhdBox>,
 
+    /// The media header of tracks that are neither video nor sound, in `QuickTime` files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gmhd: Option<GmhdBox>,
+
     pub dinf: DinfBox,
     pub stbl: StblBox,
 }
@@ -32,6 +36,9 @@ impl MinfBox {
         if let Some(ref smhd) = self.smhd {
             size += smhd.box_size();
         }
+        if let Some(ref gmhd) = self.gmhd {
+            size += gmhd.box_size();
+        }
         size += self.dinf.box_size();
         size += self.stbl.box_size();
         size
@@ -63,6 +70,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
 
         let mut vmhd = None;
         let mut smhd = None;
+        let mut gmhd = None;
         let mut dinf = None;
         let mut stbl = None;
 
@@ -85,6 +93,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
                 BoxType::SmhdBox => {
                     smhd = Some(SmhdBox::read_box(reader, s)?);
                 }
+                BoxType::GmhdBox => {
+                    gmhd = Some(GmhdBox::read_box(reader, s)?);
+                }
                 BoxType::DinfBox => {
                     dinf = Some(DinfBox::read_box(reader, s)?);
                 }
@@ -112,6 +123,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
         Ok(Self {
             vmhd,
             smhd,
+            gmhd,
             dinf,
             stbl,
         })
@@ -25,19 +25,50 @@
 //!                 stbl
 //!                     stsd
 //!                         avc1
+//!                             btrt
+//!                             colr
+//!                             pasp
+//!                             clap
 //!                         hev1
+//!                             hvcC
+//!                             dvcC
+//!                             dvvC
+//!                         dvh1
+//!                         dvhe
 //!                         mp4a
+//!                         .mp3
+//!                         ac-3
+//!                             dac3
+//!                         ec-3
+//!                             dec3
+//!                         lpcm, sowt, twos, in24, in32, fl32, fl64
+//!                             wave
+//!                                 enda
 //!                         tx3g
+//!                         wvtt
+//!                             vttC
+//!                             vlab
+//!                         stpp
+//!                         gpmd
+//!                         mett
+//!                         urim
+//!                             uri
+//!                             uriI
+//!                         mebx
+//!                             keys
 //!                     stts
 //!                     stsc
 //!                     stsz
 //!                     stss
+//!                     sdtp
 //!                     stco
 //!                     co64
 //!                     ctts
 //!                 dinf
 //!                     dref
 //!                 smhd
+//!                 gmhd
+//!                     gmin
 //!                 vmhd
 //!         edts
 //!             elst
@@ -55,43 +86,59 @@
 //! free
 //! ```
 
-use byteorder::{BigEndian, ReadBytesExt};
-use serde::Serialize;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
 use std::convert::TryInto as _;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 use crate::{
     AacConfig, DataType, Error, FixedPointI8, FixedPointU16, FixedPointU8, FourCC, Metadata,
     MetadataKey, Result, TrackKind,
 };
 
+pub(crate) mod ac3;
 pub(crate) mod av01;
 pub(crate) mod avc1;
+pub(crate) mod btrt;
+pub(crate) mod chpl;
+pub(crate) mod clap;
 pub(crate) mod co64;
+pub(crate) mod colr;
 pub(crate) mod ctts;
 pub(crate) mod data;
 pub(crate) mod dinf;
+pub(crate) mod dovi;
+pub(crate) mod ec3;
 pub(crate) mod edts;
 pub(crate) mod elst;
 pub(crate) mod emsg;
 pub(crate) mod ftyp;
+pub(crate) mod gmhd;
+pub(crate) mod gpmd;
 pub(crate) mod hdlr;
 pub(crate) mod hevc;
 pub(crate) mod ilst;
 pub(crate) mod mdhd;
 pub(crate) mod mdia;
+pub(crate) mod mebx;
 pub(crate) mod mehd;
 pub(crate) mod meta;
+pub(crate) mod mett;
 pub(crate) mod mfhd;
 pub(crate) mod minf;
 pub(crate) mod moof;
 pub(crate) mod moov;
+pub(crate) mod mp3;
 pub(crate) mod mp4a;
 pub(crate) mod mvex;
 pub(crate) mod mvhd;
+pub(crate) mod pasp;
+pub(crate) mod pcm;
+pub(crate) mod sdtp;
 pub(crate) mod smhd;
 pub(crate) mod stbl;
 pub(crate) mod stco;
+pub(crate) mod stpp;
 pub(crate) mod stsc;
 pub(crate) mod stsd;
 pub(crate) mod stss;
@@ -102,60 +149,85 @@ pub(crate) mod tfhd;
 pub(crate) mod tkhd;
 pub(crate) mod traf;
 pub(crate) mod trak;
+pub(crate) mod tref;
 pub(crate) mod trex;
 pub(crate) mod trun;
 pub(crate) mod tx3g;
 pub(crate) mod udta;
+pub(crate) mod urim;
 pub(crate) mod vmhd;
 pub(crate) mod vp08;
 pub(crate) mod vp09;
 pub(crate) mod vpcc;
-
-pub use av01::Av01Box;
-pub use avc1::Avc1Box;
+pub(crate) mod wvtt;
+
+pub use ac3::{Ac3Box, Dac3Box, QtSoundDescription};
+pub use av01::{Av01Box, Av1CBox};
+pub use avc1::{Avc1Box, AvcCBox, NalUnit};
+pub use btrt::BtrtBox;
+pub use chpl::{ChplBox, ChplEntry};
+pub use clap::ClapBox;
 pub use co64::Co64Box;
-pub use ctts::CttsBox;
+pub use colr::ColrBox;
+pub use ctts::{CttsBox, CttsEntry};
 pub use data::DataBox;
-pub use dinf::DinfBox;
+pub use dinf::{DataEntry, DinfBox, DrefBox, ExternalDataRef, UrlBox, UrnBox};
+pub use dovi::DoviConfigBox;
+pub use ec3::{Dec3Box, Ec3Box, Ec3Substream};
 pub use edts::EdtsBox;
-pub use elst::ElstBox;
+pub use elst::{ElstBox, ElstEntry};
 pub use emsg::EmsgBox;
 pub use ftyp::FtypBox;
+pub use gmhd::{GmhdBox, GminBox};
+pub use gpmd::GpmdBox;
 pub use hdlr::HdlrBox;
-pub use hevc::HevcBox;
-pub use ilst::IlstBox;
+pub use hevc::{HevcBox, HevcDecoderConfigurationRecord, HvcCArray, HvcCArrayNalu};
+pub use ilst::{IlstBox, IlstFreeformItem, IlstItemBox};
 pub use mdhd::MdhdBox;
 pub use mdia::MdiaBox;
+pub use mebx::{MebxBox, MebxKey};
 pub use mehd::MehdBox;
 pub use meta::MetaBox;
+pub use mett::MettBox;
 pub use mfhd::MfhdBox;
 pub use minf::MinfBox;
 pub use moof::MoofBox;
 pub use moov::MoovBox;
-pub use mp4a::Mp4aBox;
+pub use mp3::Mp3Box;
+pub use mp4a::{
+    DecoderConfigDescriptor, DecoderSpecificDescriptor, ESDescriptor, EsdsBox, Mp4aBox,
+    SLConfigDescriptor,
+};
 pub use mvex::MvexBox;
 pub use mvhd::MvhdBox;
+pub use pasp::PaspBox;
+pub use pcm::{PcmBox, PcmFormat};
+pub use sdtp::SdtpBox;
 pub use smhd::SmhdBox;
 pub use stbl::StblBox;
 pub use stco::StcoBox;
-pub use stsc::StscBox;
+pub use stpp::StppBox;
+pub use stsc::{StscBox, StscEntry};
 pub use stsd::{StsdBox, StsdBoxContent};
 pub use stss::StssBox;
 pub use stsz::StszBox;
-pub use stts::SttsBox;
+pub use stts::{SttsBox, SttsEntry};
 pub use tfdt::TfdtBox;
 pub use tfhd::TfhdBox;
-pub use tkhd::TkhdBox;
+pub use tkhd::{Matrix, TkhdBox, TkhdFlags, TrackFlag};
 pub use traf::TrafBox;
 pub use trak::TrakBox;
+pub use tref::{TrackReference, TrefBox};
 pub use trex::TrexBox;
 pub use trun::TrunBox;
-pub use tx3g::Tx3gBox;
+pub use tx3g::{RgbaColor, Tx3gBox};
 pub use udta::UdtaBox;
-pub use vmhd::VmhdBox;
+pub use urim::UrimBox;
+pub use vmhd::{RgbColor, VmhdBox};
 pub use vp08::Vp08Box;
 pub use vp09::Vp09Box;
 pub use vpcc::VpccBox;
+pub use wvtt::WvttBox;
 
 pub const HEADER_SIZE: u64 = 8;
 // const HEADER_LARGE_SIZE: u64 = 16;
@@ -164,7 +236,8 @@ pub const HEADER_EXT_SIZE: u64 = 4;
 macro_rules! boxtype {
     ($( $name:ident => $value:expr ),*) => {
         #[expect(clippy::enum_variant_names, reason = "MP4 box type variants keep Box suffix for clarity/API")]
-        #[derive(Clone, Copy, PartialEq, Eq)]
+        #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(from = "u32", into = "u32")]
         pub enum BoxType {
             $( $name, )*
             UnknownBox(u32),
@@ -195,6 +268,7 @@ boxtype! {
     MvhdBox => 0x6d766864,
     MfhdBox => 0x6d666864,
     FreeBox => 0x66726565,
+    SkipBox => 0x736b6970,
     MdatBox => 0x6d646174,
     MoovBox => 0x6d6f6f76,
     MvexBox => 0x6d766578,
@@ -202,6 +276,9 @@ boxtype! {
     TrexBox => 0x74726578,
     EmsgBox => 0x656d7367,
     MoofBox => 0x6d6f6f66,
+    MfraBox => 0x6d667261,
+    SidxBox => 0x73696478,
+    StypBox => 0x73747970,
     TkhdBox => 0x746b6864,
     TfhdBox => 0x74666864,
     TfdtBox => 0x74666474,
@@ -217,6 +294,7 @@ boxtype! {
     SttsBox => 0x73747473,
     CttsBox => 0x63747473,
     StssBox => 0x73747373,
+    SdtpBox => 0x73647470,
     StscBox => 0x73747363,
     StszBox => 0x7374737A,
     StcoBox => 0x7374636F,
@@ -229,6 +307,7 @@ boxtype! {
     DinfBox => 0x64696e66,
     DrefBox => 0x64726566,
     UrlBox  => 0x75726C20,
+    UrnBox  => 0x75726E20,
     SmhdBox => 0x736d6864,
     Avc1Box => 0x61766331,
     // Avc3Box => 0x61766333,
@@ -237,21 +316,60 @@ boxtype! {
     Av1CBox => 0x61763143,
     Hev1Box => 0x68657631,
     Hvc1Box => 0x68766331,
+    Dvh1Box => 0x64766831,
+    DvheBox => 0x64766865,
+    DvcCBox => 0x64766343,
+    DvvCBox => 0x64767643,
+    DvwCBox => 0x64767743,
     HvcCBox => 0x68766343,
     Mp4aBox => 0x6d703461,
+    Mp3Box => 0x2e6d7033,
+    Ac3Box => 0x61632d33,
+    Dac3Box => 0x64616333,
+    Ec3Box => 0x65632d33,
+    Dec3Box => 0x64656333,
     EsdsBox => 0x65736473,
     Tx3gBox => 0x74783367,
+    MettBox => 0x6d657474,
+    UrimBox => 0x7572696d,
+    WvttBox => 0x77767474,
+    StppBox => 0x73747070,
+    GpmdBox => 0x67706d64,
+    MebxBox => 0x6d656278,
+    GmhdBox => 0x676d6864,
+    GminBox => 0x676d696e,
+    BtrtBox => 0x62747274,
     VpccBox => 0x76706343,
+    ColrBox => 0x636f6c72,
+    PaspBox => 0x70617370,
+    ClapBox => 0x636c6170,
     Vp08Box => 0x76703038,
     Vp09Box => 0x76703039,
     DataBox => 0x64617461,
     IlstBox => 0x696c7374,
+    ChplBox => 0x6368706c,
+    TrefBox => 0x74726566,
+    IlocBox => 0x696c6f63,
+    IinfBox => 0x69696e66,
+    IprpBox => 0x69707270,
+    PitmBox => 0x7069746d,
     NameBox => 0xa96e616d,
     DayBox => 0xa9646179,
     CovrBox => 0x636f7672,
     DescBox => 0x64657363,
+    FreeformBox => 0x2d2d2d2d,
+    MeanBox => 0x6d65616e,
+    FreeformNameBox => 0x6e616d65,
     WideBox => 0x77696465,
-    WaveBox => 0x77617665
+    WaveBox => 0x77617665,
+    LpcmBox => 0x6c70636d,
+    SowtBox => 0x736f7774,
+    TwosBox => 0x74776f73,
+    In24Box => 0x696e3234,
+    In32Box => 0x696e3332,
+    Fl32Box => 0x666c3332,
+    Fl64Box => 0x666c3634,
+    EndaBox => 0x656e6461
 }
 
 pub trait Mp4Box: Sized {
@@ -265,6 +383,11 @@ pub trait ReadBox<T>: Sized {
     fn read_box(_: T, size: u64) -> Result<Self>;
 }
 
+pub trait WriteBox<T>: Sized {
+    /// Serializes the box, including its header, and returns the number of bytes written.
+    fn write_box(&self, _: T) -> Result<u64>;
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct BoxHeader {
     pub name: BoxType,
@@ -304,9 +427,15 @@ impl BoxHeader {
                 size: match largesize {
                     0 => 0,
                     1..=15 => return Err(Error::InvalidData("64-bit box size too small")),
-                    16..=u64::MAX => largesize - 8,
+                    // Larger boxes can't be seeked past, and their end would overflow.
+                    0x8000_0000_0000_0000..=u64::MAX => {
+                        return Err(Error::InvalidData("64-bit box size too large"))
+                    }
+                    16.. => largesize - 8,
                 },
             })
+        } else if (2..HEADER_SIZE as u32).contains(&size) {
+            Err(Error::InvalidData("box size too small"))
         } else {
             Ok(Self {
                 name: BoxType::from(typ),
@@ -314,6 +443,22 @@ impl BoxHeader {
             })
         }
     }
+
+    /// Writes the header, using a 64-bit `largesize` if `size` does not fit in 32 bits.
+    ///
+    /// Here `size` is the size of the whole box, including the header itself.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        if let Ok(size) = u32::try_from(self.size) {
+            writer.write_u32::<BigEndian>(size)?;
+            writer.write_u32::<BigEndian>(self.name.into())?;
+            Ok(HEADER_SIZE)
+        } else {
+            writer.write_u32::<BigEndian>(1)?;
+            writer.write_u32::<BigEndian>(self.name.into())?;
+            writer.write_u64::<BigEndian>(self.size)?;
+            Ok(HEADER_SIZE + 8)
+        }
+    }
 }
 
 pub fn read_box_header_ext<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
@@ -322,6 +467,19 @@ pub fn read_box_header_ext<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
     Ok((version, flags))
 }
 
+pub fn write_box_header_ext<W: Write>(writer: &mut W, version: u8, flags: u32) -> Result<u64> {
+    writer.write_u8(version)?;
+    writer.write_u24::<BigEndian>(flags)?;
+    Ok(HEADER_EXT_SIZE)
+}
+
+pub fn write_zeros<W: Write>(writer: &mut W, size: u64) -> Result<()> {
+    for _ in 0..size {
+        writer.write_u8(0)?;
+    }
+    Ok(())
+}
+
 pub fn box_start<R: Seek>(seeker: &mut R) -> Result<u64> {
     Ok(seeker.stream_position()? - HEADER_SIZE)
 }
@@ -332,18 +490,36 @@ pub fn skip_bytes<S: Seek>(seeker: &mut S, size: u64) -> Result<()> {
     Ok(())
 }
 
+/// Skips ahead to `pos`, which may not lie before the current position, or else a box with a
+/// size that is too small could make its parent read the same boxes over and over.
 pub fn skip_bytes_to<S: Seek>(seeker: &mut S, pos: u64) -> Result<()> {
+    if pos < seeker.stream_position()? {
+        return Err(Error::InvalidData("box is smaller than its contents"));
+    }
     seeker.seek(SeekFrom::Start(pos))?;
     Ok(())
 }
 
+/// Reads `size` bytes whose size comes from the file, like the rest of a box.
+///
+/// The buffer only grows as the bytes are read, so a bogus size fails at the end of the input
+/// instead of allocating that much up front.
+pub(crate) fn read_bytes<R: Read>(reader: &mut R, size: u64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(size).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < size {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(bytes)
+}
+
 pub fn skip_box<S: Seek>(seeker: &mut S, size: u64) -> Result<()> {
     let start = box_start(seeker)?;
     skip_bytes_to(seeker, start + size)?;
     Ok(())
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawBox<T> {
     pub contents: T,
     pub raw: Vec<u8>,
@@ -378,39 +554,86 @@ impl<T> std::ops::DerefMut for RawBox<T> {
     }
 }
 
+/// Fixed-point fields are serialized as their integer part in human-readable formats such as
+/// JSON, and as their raw value otherwise, so that binary formats round-trip them exactly.
 mod value_u32 {
     use crate::types::FixedPointU16;
-    use serde::{self, Serializer};
+    use serde::{self, Deserialize as _, Deserializer, Serializer};
 
     pub fn serialize<S>(fixed: &FixedPointU16, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_u16(fixed.value())
+        if serializer.is_human_readable() {
+            serializer.serialize_u16(fixed.value())
+        } else {
+            serializer.serialize_u32(fixed.raw_value())
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<FixedPointU16, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            u16::deserialize(deserializer).map(FixedPointU16::new)
+        } else {
+            u32::deserialize(deserializer).map(FixedPointU16::new_raw)
+        }
     }
 }
 
 mod value_i16 {
     use crate::types::FixedPointI8;
-    use serde::{self, Serializer};
+    use serde::{self, Deserialize as _, Deserializer, Serializer};
 
     pub fn serialize<S>(fixed: &FixedPointI8, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_i8(fixed.value())
+        if serializer.is_human_readable() {
+            serializer.serialize_i8(fixed.value())
+        } else {
+            serializer.serialize_i16(fixed.raw_value())
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<FixedPointI8, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            i8::deserialize(deserializer).map(FixedPointI8::new)
+        } else {
+            i16::deserialize(deserializer).map(FixedPointI8::new_raw)
+        }
     }
 }
 
 mod value_u8 {
     use crate::types::FixedPointU8;
-    use serde::{self, Serializer};
+    use serde::{self, Deserialize as _, Deserializer, Serializer};
 
     pub fn serialize<S>(fixed: &FixedPointU8, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_u8(fixed.value())
+        if serializer.is_human_readable() {
+            serializer.serialize_u8(fixed.value())
+        } else {
+            serializer.serialize_u16(fixed.raw_value())
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<FixedPointU8, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            u8::deserialize(deserializer).map(FixedPointU8::new)
+        } else {
+            u16::deserialize(deserializer).map(FixedPointU8::new_raw)
+        }
     }
 }
 
@@ -427,6 +650,95 @@ mod tests {
         assert_eq!(ftyp_fcc, ftyp_fcc2);
     }
 
+    #[test]
+    fn test_fourcc_description() {
+        let hev1 = FourCC::from(BoxType::Hev1Box);
+        assert_eq!(
+            hev1.description(),
+            Some("HEVC (H.265) sample entry, parameter sets in-band")
+        );
+        assert_eq!(
+            FourCC::from(BoxType::BtrtBox).description(),
+            Some("Bitrate")
+        );
+        assert_eq!(FourCC::from(*b"zzzz").description(), None);
+    }
+
+    fn roundtrip<T>(written: &T) -> T
+    where
+        T: Mp4Box
+            + for<'a> WriteBox<&'a mut Vec<u8>>
+            + for<'a> ReadBox<&'a mut std::io::Cursor<Vec<u8>>>,
+    {
+        let mut buf = Vec::new();
+        let size = written.write_box(&mut buf).unwrap();
+        assert_eq!(size, buf.len() as u64);
+        assert_eq!(size, written.box_size());
+
+        let mut reader = std::io::Cursor::new(buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, written.box_type());
+        T::read_box(&mut reader, header.size).unwrap()
+    }
+
+    #[test]
+    fn test_write_roundtrip() {
+        let mvhd = MvhdBox {
+            version: 1,
+            duration: u64::from(u32::MAX) + 1,
+            timescale: 1000,
+            next_track_id: 3,
+            ..Default::default()
+        };
+        assert_eq!(roundtrip(&mvhd), mvhd);
+
+        let tkhd = TkhdBox {
+            track_id: 2,
+            duration: 1234,
+            width: FixedPointU16::new(640),
+            height: FixedPointU16::new(480),
+            ..Default::default()
+        };
+        assert_eq!(roundtrip(&tkhd), tkhd);
+
+        let mdhd = MdhdBox {
+            timescale: 90000,
+            duration: 900_000,
+            language: "swe".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(roundtrip(&mdhd), mdhd);
+
+        let elst = ElstBox {
+            version: 0,
+            flags: 0,
+            entries: vec![ElstEntry {
+                segment_duration: 1000,
+                media_time: 512,
+                media_rate: 1,
+                media_rate_fraction: 0,
+            }],
+        };
+        assert_eq!(roundtrip(&elst), elst);
+
+        let stsz = StszBox {
+            sample_count: 3,
+            sample_sizes: vec![1, 2, 3],
+            ..Default::default()
+        };
+        assert_eq!(roundtrip(&stsz), stsz);
+
+        let ctts = CttsBox {
+            version: 1,
+            flags: 0,
+            entries: vec![CttsEntry {
+                sample_count: 2,
+                sample_offset: -1,
+            }],
+        };
+        assert_eq!(roundtrip(&ctts), ctts);
+    }
+
     #[test]
     fn test_largesize_too_small() {
         let error = BoxHeader::read(&mut &[0, 0, 0, 1, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 7][..]);
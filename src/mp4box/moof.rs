@@ -1,13 +1,13 @@
-use serde::Serialize;
-use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_SIZE,
+    WriteBox, HEADER_SIZE,
 };
 use crate::mp4box::{mfhd::MfhdBox, traf::TrafBox};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct MoofBox {
     /// The start position of the box in the stream.
     pub start: u64,
@@ -95,3 +95,16 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoofBox {
         Ok(Self { start, mfhd, trafs })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for MoofBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        self.mfhd.write_box(writer)?;
+        for traf in &self.trafs {
+            traf.write_box(writer)?;
+        }
+        Ok(size)
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
 use crate::meta::MetaBox;
@@ -7,8 +7,9 @@ use crate::mp4box::{
     HEADER_SIZE,
 };
 use crate::mp4box::{mvex::MvexBox, mvhd::MvhdBox, trak::TrakBox, udta::UdtaBox};
+use crate::ExtraBox;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct MoovBox {
     pub mvhd: MvhdBox,
 
@@ -23,6 +24,11 @@ pub struct MoovBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub udta: Option<UdtaBox>,
+
+    /// The boxes in `moov` that this crate doesn't parse, like `iods` or vendor boxes, in file
+    /// order. Space boxes (`free`, `skip` and `wide`) are left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extras: Vec<ExtraBox>,
 }
 
 impl MoovBox {
@@ -41,6 +47,9 @@ impl MoovBox {
         if let Some(udta) = &self.udta {
             size += udta.box_size();
         }
+        for extra in &self.extras {
+            size += extra.size;
+        }
         size
     }
 }
@@ -73,6 +82,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
         let mut udta = None;
         let mut mvex = None;
         let mut traks = Vec::new();
+        let mut extras = Vec::new();
 
         let mut current = reader.stream_position()?;
         let end = start + size;
@@ -103,10 +113,12 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
                 BoxType::UdtaBox => {
                     udta = Some(UdtaBox::read_box(reader, s)?);
                 }
-                _ => {
-                    // XXX warn!()
+                BoxType::FreeBox | BoxType::SkipBox | BoxType::WideBox => {
                     skip_box(reader, s)?;
                 }
+                _ => {
+                    extras.push(ExtraBox::read(reader, name, current, s)?);
+                }
             }
 
             current = reader.stream_position()?;
@@ -124,6 +136,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
             mvex,
             traks,
             udta,
+            extras,
         })
     }
 }
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::ac3::{read_children, AudioSampleEntry};
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, value_u32, BoxType, FixedPointU16, Mp4Box, QtSoundDescription, ReadBox, Result,
+    HEADER_SIZE,
+};
+
+/// Sample entry of MPEG-1 Layer III (MP3) audio, as written by `QuickTime` and some muxers
+/// instead of an `mp4a` entry with an `esds`.
+///
+/// The samples are MP3 frames, whose headers tell everything that is needed for decoding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mp3Box {
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+
+    #[serde(with = "value_u32")]
+    pub samplerate: FixedPointU16,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qt_sound: Option<QtSoundDescription>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+impl Mp3Box {
+    pub fn get_type() -> BoxType {
+        BoxType::Mp3Box
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + 20;
+        size += match self.qt_sound {
+            Some(QtSoundDescription::V1 { .. }) => 16,
+            Some(QtSoundDescription::V2 { .. }) => 36,
+            None => 0,
+        };
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+
+    /// The sample rate in Hz, from version 2 of the `QuickTime` sound sample description if
+    /// there is one.
+    pub fn sample_rate(&self) -> u32 {
+        match self.qt_sound.and_then(|qt_sound| qt_sound.sample_rate()) {
+            Some(sample_rate) => sample_rate.round() as u32,
+            None => u32::from(self.samplerate.value()),
+        }
+    }
+}
+
+impl Mp4Box for Mp3Box {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "channel_count={} sample_size={} sample_rate={}",
+            self.channelcount,
+            self.samplesize,
+            self.sample_rate()
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for Mp3Box {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        let fields = AudioSampleEntry::read(reader)?;
+
+        let mut btrt = None;
+        read_children(reader, BoxType::Mp3Box, end, |reader, name, s| {
+            match name {
+                BoxType::BtrtBox if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        Ok(Self {
+            data_reference_index: fields.data_reference_index,
+            channelcount: fields.channelcount,
+            samplesize: fields.samplesize,
+            samplerate: fields.samplerate,
+            qt_sound: fields.qt_sound,
+            btrt,
+        })
+    }
+}
@@ -1,21 +1,35 @@
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
+use crate::mp4box::ac3::AudioSampleEntry;
+use crate::mp4box::btrt::{read_btrt, BtrtBox};
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes, skip_bytes_to, value_u32, AacConfig, BoxHeader,
-    BoxType, Error, FixedPointU16, Mp4Box, ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, value_u32, AacConfig, BoxHeader, BoxType, Error,
+    FixedPointU16, Mp4Box, QtSoundDescription, ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
 };
+use crate::skipped::{log_skipped, Skipped};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mp4aBox {
     pub data_reference_index: u16,
     pub channelcount: u16,
     pub samplesize: u16,
 
+    /// The sample rate in Hz. For version 2 of the `QuickTime` sound sample description this
+    /// is 0 if the rate doesn't fit, see [`Self::sample_rate`].
     #[serde(with = "value_u32")]
     pub samplerate: FixedPointU16,
+
+    /// The extra fields of files written by `QuickTime` and `ffmpeg` with `-movflags`, whose
+    /// sample entry has version 1 or 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qt_sound: Option<QtSoundDescription>,
+
     pub esds: Option<EsdsBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
 }
 
 impl Default for Mp4aBox {
@@ -25,7 +39,9 @@ impl Default for Mp4aBox {
             channelcount: 2,
             samplesize: 16,
             samplerate: FixedPointU16::new(48000),
+            qt_sound: None,
             esds: Some(EsdsBox::default()),
+            btrt: None,
         }
     }
 }
@@ -37,7 +53,9 @@ impl Mp4aBox {
             channelcount: config.chan_conf as u16,
             samplesize: 16,
             samplerate: FixedPointU16::new(config.freq_index.freq() as u16),
+            qt_sound: None,
             esds: Some(EsdsBox::new(config)),
+            btrt: None,
         }
     }
 
@@ -45,11 +63,28 @@ impl Mp4aBox {
         BoxType::Mp4aBox
     }
 
+    /// The sample rate in Hz, which for version 2 of the `QuickTime` sound sample description
+    /// is taken from [`QtSoundDescription::sample_rate`].
+    pub fn sample_rate(&self) -> u32 {
+        match self.qt_sound.and_then(|qt_sound| qt_sound.sample_rate()) {
+            Some(sample_rate) => sample_rate.round() as u32,
+            None => u32::from(self.samplerate.value()),
+        }
+    }
+
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE + 8 + 20;
+        size += match self.qt_sound {
+            Some(QtSoundDescription::V1 { .. }) => 16,
+            Some(QtSoundDescription::V2 { .. }) => 36,
+            None => 0,
+        };
         if let Some(ref esds) = self.esds {
             size += esds.box_size();
         }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
         size
     }
 }
@@ -82,22 +117,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Mp4aBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
-        reader.read_u32::<BigEndian>()?; // reserved
-        reader.read_u16::<BigEndian>()?; // reserved
-        let data_reference_index = reader.read_u16::<BigEndian>()?;
-        let version = reader.read_u16::<BigEndian>()?;
-        reader.read_u16::<BigEndian>()?; // reserved
-        reader.read_u32::<BigEndian>()?; // reserved
-        let channelcount = reader.read_u16::<BigEndian>()?;
-        let samplesize = reader.read_u16::<BigEndian>()?;
-        reader.read_u32::<BigEndian>()?; // pre-defined, reserved
-        let samplerate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
-
-        if version == 1 {
-            // Skip QTFF
-            reader.read_u64::<BigEndian>()?;
-            reader.read_u64::<BigEndian>()?;
-        }
+        let fields = AudioSampleEntry::read(reader)?;
 
         // Find esds in mp4a or wave
         let mut esds = None;
@@ -121,24 +141,32 @@ impl<R: Read + Seek> ReadBox<&mut R> for Mp4aBox {
                 // Typically contains frma, mp4a, esds, and a terminator atom
             } else {
                 // Skip boxes
+                log_skipped(&Skipped::Box {
+                    parent: BoxType::Mp4aBox.into(),
+                    box_type: name.into(),
+                    offset: current,
+                    size: s,
+                });
                 let skip_to = current + s;
                 skip_bytes_to(reader, skip_to)?;
             }
         }
 
-        skip_bytes_to(reader, end)?;
+        let btrt = read_btrt(reader, start, end)?;
 
         Ok(Self {
-            data_reference_index,
-            channelcount,
-            samplesize,
-            samplerate,
+            data_reference_index: fields.data_reference_index,
+            channelcount: fields.channelcount,
+            samplesize: fields.samplesize,
+            samplerate: fields.samplerate,
+            qt_sound: fields.qt_sound,
             esds,
+            btrt,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct EsdsBox {
     pub version: u8,
     pub flags: u32,
@@ -212,7 +240,6 @@ impl<R: Read + Seek> ReadBox<&mut R> for EsdsBox {
     }
 }
 
-#[expect(dead_code)]
 trait Descriptor: Sized {
     fn desc_tag() -> u8;
     fn desc_size() -> u32;
@@ -237,6 +264,24 @@ fn read_desc<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
     Ok((tag, size))
 }
 
+/// Skips the contents of a descriptor that starts at `offset` and is not parsed.
+fn skip_descriptor<R: Seek>(
+    reader: &mut R,
+    parent_tag: u8,
+    tag: u8,
+    offset: u64,
+    size: u32,
+) -> Result<()> {
+    let end = reader.stream_position()? + u64::from(size);
+    log_skipped(&Skipped::Descriptor {
+        parent_tag,
+        tag,
+        offset,
+        size: end - offset,
+    });
+    skip_bytes_to(reader, end)
+}
+
 fn size_of_length(size: u32) -> u32 {
     match size {
         0x0..=0x7F => 1,
@@ -246,7 +291,7 @@ fn size_of_length(size: u32) -> u32 {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct ESDescriptor {
     pub es_id: u16,
 
@@ -301,7 +346,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for ESDescriptor {
                     sl_config = Some(SLConfigDescriptor::read_desc(reader, desc_size)?);
                 }
                 _ => {
-                    skip_bytes(reader, desc_size as u64)?;
+                    skip_descriptor(reader, Self::desc_tag(), desc_tag, current, desc_size)?;
                 }
             }
             current = reader.stream_position()?;
@@ -315,7 +360,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for ESDescriptor {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct DecoderConfigDescriptor {
     pub object_type_indication: u8,
     pub stream_type: u8,
@@ -376,7 +421,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
                     dec_specific = Some(DecoderSpecificDescriptor::read_desc(reader, desc_size)?);
                 }
                 _ => {
-                    skip_bytes(reader, desc_size as u64)?;
+                    skip_descriptor(reader, Self::desc_tag(), desc_tag, current, desc_size)?;
                 }
             }
             current = reader.stream_position()?;
@@ -394,7 +439,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct DecoderSpecificDescriptor {
     pub profile: u8,
     pub freq_index: u8,
@@ -474,7 +519,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderSpecificDescriptor {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct SLConfigDescriptor {}
 
 impl SLConfigDescriptor {
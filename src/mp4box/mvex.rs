@@ -1,13 +1,13 @@
-use serde::Serialize;
-use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_SIZE,
+    WriteBox, HEADER_SIZE,
 };
 use crate::mp4box::{mehd::MehdBox, trex::TrexBox};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct MvexBox {
     pub mehd: Option<MehdBox>,
     pub trexs: Vec<TrexBox>,
@@ -15,7 +15,7 @@ pub struct MvexBox {
 
 impl MvexBox {
     pub fn get_type() -> BoxType {
-        BoxType::MdiaBox
+        BoxType::MvexBox
     }
 
     pub fn get_size(&self) -> u64 {
@@ -88,3 +88,18 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvexBox {
         Ok(Self { mehd, trexs })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for MvexBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(mehd) = &self.mehd {
+            mehd.write_box(writer)?;
+        }
+        for trex in &self.trexs {
+            trex.write_box(writer)?;
+        }
+        Ok(size)
+    }
+}
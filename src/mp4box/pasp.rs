@@ -0,0 +1,88 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::{box_start, skip_bytes_to, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+
+/// The pixel aspect ratio of a visual sample entry, see ISO/IEC 14496-12 section 12.1.4.
+///
+/// A pixel is `h_spacing / v_spacing` times as wide as it is high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PaspBox {
+    pub h_spacing: u32,
+    pub v_spacing: u32,
+}
+
+impl PaspBox {
+    pub fn get_type() -> BoxType {
+        BoxType::PaspBox
+    }
+
+    pub fn get_size() -> u64 {
+        HEADER_SIZE + 8
+    }
+
+    /// Scales `width` by the pixel aspect ratio, or returns it as it is if the ratio is not
+    /// valid, i.e. has a zero in it.
+    pub fn scale_width(&self, width: u32) -> u32 {
+        if self.h_spacing == 0 || self.v_spacing == 0 {
+            return width;
+        }
+        let scaled = (u64::from(width) * u64::from(self.h_spacing) + u64::from(self.v_spacing) / 2)
+            / u64::from(self.v_spacing);
+        u32::try_from(scaled).unwrap_or(u32::MAX)
+    }
+}
+
+impl Mp4Box for PaspBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        Self::get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        Ok(format!(
+            "h_spacing={} v_spacing={}",
+            self.h_spacing, self.v_spacing
+        ))
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for PaspBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let h_spacing = reader.read_u32::<BigEndian>()?;
+        let v_spacing = reader.read_u32::<BigEndian>()?;
+
+        skip_bytes_to(reader, start + size)?;
+        Ok(Self {
+            h_spacing,
+            v_spacing,
+        })
+    }
+}
+
+/// Reads the child box `name` of a visual sample entry into `pasp` if it is the first `pasp`
+/// box, and returns whether it was a `pasp` box.
+pub(crate) fn read_pasp<R: Read + Seek>(
+    reader: &mut R,
+    name: BoxType,
+    size: u64,
+    pasp: &mut Option<PaspBox>,
+) -> Result<bool> {
+    if name != BoxType::PaspBox {
+        return Ok(false);
+    }
+    if pasp.is_none() {
+        *pasp = Some(PaspBox::read_box(reader, size)?);
+    }
+    Ok(true)
+}
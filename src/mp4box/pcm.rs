@@ -0,0 +1,253 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::mp4box::ac3::{read_children, AudioSampleEntry};
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, value_u32, BoxType, FixedPointU16, FourCC, Mp4Box, QtSoundDescription, ReadBox,
+    Result, HEADER_SIZE,
+};
+
+/// The format flags of `lpcm` in [`QtSoundDescription::V2`], see `CoreAudio`.
+const LPCM_FLAG_FLOAT: u32 = 1 << 0;
+const LPCM_FLAG_BIG_ENDIAN: u32 = 1 << 1;
+const LPCM_FLAG_SIGNED_INTEGER: u32 = 1 << 2;
+
+/// Sample entry of uncompressed audio in one of the `QuickTime` formats.
+///
+/// These are `lpcm`, which describes its format in [`QtSoundDescription::V2`], 16-bit little
+/// endian `sowt`, big endian `twos`, 24 and 32-bit integers `in24` and `in32`, and 32 and
+/// 64-bit floats `fl32` and `fl64`.
+///
+/// See [`Self::format`] for the layout of the samples.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcmBox {
+    /// The type of the sample entry, which tells the format of the samples.
+    pub fourcc: FourCC,
+
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+
+    #[serde(with = "value_u32")]
+    pub samplerate: FixedPointU16,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qt_sound: Option<QtSoundDescription>,
+
+    /// Whether an `enda` box says that the samples of `in24`, `in32`, `fl32` or `fl64` are
+    /// little endian rather than big endian.
+    #[serde(default)]
+    pub little_endian: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+/// The layout of uncompressed audio samples, see [`PcmBox::format`].
+///
+/// Each sample of the track holds frames of [`Self::channel_count`] interleaved values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    /// Bits per value, like 16 or 24.
+    pub bits_per_sample: u16,
+
+    /// Whether values are IEEE floats, rather than integers.
+    pub float: bool,
+
+    /// Whether integer values are signed. Unsigned values are offset by half their range.
+    pub signed: bool,
+    pub little_endian: bool,
+    pub channel_count: u16,
+    pub sample_rate: u32,
+}
+
+impl PcmBox {
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + 20;
+        size += match self.qt_sound {
+            Some(QtSoundDescription::V1 { .. }) => 16,
+            Some(QtSoundDescription::V2 { .. }) => 36,
+            None => 0,
+        };
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+
+    /// Whether `box_type` is the type of a sample entry of uncompressed audio.
+    pub(crate) fn is_pcm(box_type: BoxType) -> bool {
+        matches!(
+            box_type,
+            BoxType::LpcmBox
+                | BoxType::SowtBox
+                | BoxType::TwosBox
+                | BoxType::In24Box
+                | BoxType::In32Box
+                | BoxType::Fl32Box
+                | BoxType::Fl64Box
+        )
+    }
+
+    /// The layout of the samples, which depends on the type of the sample entry.
+    pub fn format(&self) -> PcmFormat {
+        let sample_rate = match self.qt_sound.and_then(|qt_sound| qt_sound.sample_rate()) {
+            Some(sample_rate) => sample_rate.round() as u32,
+            None => u32::from(self.samplerate.value()),
+        };
+        let mut format = PcmFormat {
+            bits_per_sample: self.samplesize,
+            float: false,
+            signed: true,
+            little_endian: self.little_endian,
+            channel_count: self.channelcount,
+            sample_rate,
+        };
+        match &self.fourcc.value {
+            b"lpcm" => {
+                let flags = match self.qt_sound {
+                    Some(QtSoundDescription::V2 {
+                        format_specific_flags,
+                        ..
+                    }) => format_specific_flags,
+                    _ => LPCM_FLAG_BIG_ENDIAN | LPCM_FLAG_SIGNED_INTEGER,
+                };
+                format.float = flags & LPCM_FLAG_FLOAT != 0;
+                format.signed = format.float || flags & LPCM_FLAG_SIGNED_INTEGER != 0;
+                format.little_endian = flags & LPCM_FLAG_BIG_ENDIAN == 0;
+            }
+            b"sowt" => format.little_endian = true,
+            b"twos" => format.little_endian = false,
+            b"in24" => format.bits_per_sample = 24,
+            b"in32" => format.bits_per_sample = 32,
+            b"fl32" => (format.bits_per_sample, format.float) = (32, true),
+            b"fl64" => (format.bits_per_sample, format.float) = (64, true),
+            _ => {}
+        }
+        format
+    }
+}
+
+impl PcmFormat {
+    /// The size of a frame, with one value of every channel, in bytes.
+    pub fn bytes_per_frame(&self) -> u64 {
+        u64::from(self.bits_per_sample).div_ceil(8) * u64::from(self.channel_count)
+    }
+
+    /// Converts the values in `data` to floats from -1.0 to 1.0, for playing or mixing the
+    /// audio regardless of its format.
+    ///
+    /// The channels stay interleaved. Bytes at the end that don't make up a whole value are
+    /// ignored.
+    pub fn to_f32(self, data: &[u8]) -> Vec<f32> {
+        let bytes_per_value = usize::from(self.bits_per_sample.div_ceil(8)).max(1);
+        data.chunks_exact(bytes_per_value)
+            .map(|value| {
+                let mut bytes = [0; 8];
+                let value_bytes = &mut bytes[8 - value.len()..];
+                value_bytes.copy_from_slice(value);
+                if self.little_endian {
+                    value_bytes.reverse();
+                }
+                let bits = u64::from_be_bytes(bytes);
+                if self.float {
+                    return match value.len() {
+                        4 => f32::from_bits(bits as u32),
+                        8 => f64::from_bits(bits) as f32,
+                        _ => 0.0,
+                    };
+                }
+                // Moves the value to the top of 64 bits, so that it can be scaled by one factor.
+                let shift = 64 - 8 * value.len() as u32;
+                let value = if self.signed {
+                    (bits << shift).cast_signed()
+                } else {
+                    ((bits << shift) ^ (1 << 63)).cast_signed()
+                };
+                value as f32 / -(i64::MIN as f32)
+            })
+            .collect()
+    }
+}
+
+impl Mp4Box for PcmBox {
+    fn box_type(&self) -> BoxType {
+        BoxType::from(u32::from_be_bytes(self.fourcc.value))
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let format = self.format();
+        let s = format!(
+            "channel_count={} bits_per_sample={} sample_rate={} float={} little_endian={}",
+            format.channel_count,
+            format.bits_per_sample,
+            format.sample_rate,
+            format.float,
+            format.little_endian
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for PcmBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.seek(SeekFrom::Start(start + 4))?;
+        let box_type = BoxType::from(reader.read_u32::<BigEndian>()?);
+        let fields = AudioSampleEntry::read(reader)?;
+
+        let mut little_endian = false;
+        let mut btrt = None;
+        read_pcm_children(reader, box_type, end, &mut little_endian, &mut btrt)?;
+
+        Ok(Self {
+            fourcc: box_type.into(),
+            data_reference_index: fields.data_reference_index,
+            channelcount: fields.channelcount,
+            samplesize: fields.samplesize,
+            samplerate: fields.samplerate,
+            qt_sound: fields.qt_sound,
+            little_endian,
+            btrt,
+        })
+    }
+}
+
+/// Reads the `enda` and `btrt` boxes of a PCM sample entry, where `enda` usually is a child of
+/// the `wave` box of `QuickTime`.
+fn read_pcm_children<R: Read + Seek>(
+    reader: &mut R,
+    parent: BoxType,
+    end: u64,
+    little_endian: &mut bool,
+    btrt: &mut Option<BtrtBox>,
+) -> Result<()> {
+    read_children(reader, parent, end, |reader, name, s| {
+        match name {
+            BoxType::EndaBox => {
+                *little_endian = reader.read_u16::<BigEndian>()? != 0;
+            }
+            BoxType::WaveBox => {
+                let wave_end = reader.stream_position()? + s - HEADER_SIZE;
+                read_pcm_children(reader, name, wave_end, little_endian, btrt)?;
+            }
+            BoxType::BtrtBox if btrt.is_none() => {
+                *btrt = Some(BtrtBox::read_box(reader, s)?);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })
+}
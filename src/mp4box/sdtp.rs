@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::{
+    box_start, read_box_header_ext, skip_bytes_to, BoxType, Mp4Box, ReadBox, Result,
+    HEADER_EXT_SIZE, HEADER_SIZE,
+};
+use crate::SampleFlags;
+
+/// The dependencies between the samples of a track, see ISO/IEC 14496-12 section 8.6.4.
+///
+/// There is one entry per sample, with the same fields as [`SampleFlags`] has for the samples of
+/// movie fragments.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SdtpBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// `is_leading`, `sample_depends_on`, `sample_is_depended_on` and `sample_has_redundancy`
+    /// of each sample, two bits each from the most significant.
+    #[serde(skip_serializing, default)]
+    pub entries: Vec<u8>,
+}
+
+impl SdtpBox {
+    pub fn get_type() -> BoxType {
+        BoxType::SdtpBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + self.entries.len() as u64
+    }
+
+    /// The dependency fields of sample `index`, counting from zero, with the others left at 0.
+    pub fn sample_flags(&self, index: usize) -> Option<SampleFlags> {
+        let entry = *self.entries.get(index)?;
+        Some(SampleFlags {
+            is_leading: (entry >> 6) & 0b11,
+            depends_on: (entry >> 4) & 0b11,
+            is_depended_on: (entry >> 2) & 0b11,
+            has_redundancy: entry & 0b11,
+            ..Default::default()
+        })
+    }
+}
+
+impl Mp4Box for SdtpBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("entries={}", self.entries.len());
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for SdtpBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        // There is no entry count, the entries fill the rest of the box.
+        let entry_count = size.saturating_sub(HEADER_SIZE + HEADER_EXT_SIZE);
+        let mut entries = Vec::new();
+        reader.take(entry_count).read_to_end(&mut entries)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self {
+            version,
+            flags,
+            entries,
+        })
+    }
+}
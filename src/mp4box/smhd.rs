@@ -1,13 +1,13 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, value_i16, BoxType, FixedPointI8, Mp4Box,
-    ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, value_i16, write_box_header_ext, BoxHeader,
+    BoxType, FixedPointI8, Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SmhdBox {
     pub version: u8,
     pub flags: u32,
@@ -72,3 +72,17 @@ impl<R: Read + Seek> ReadBox<&mut R> for SmhdBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for SmhdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_i16::<BigEndian>(self.balance.raw_value())?;
+        writer.write_u16::<BigEndian>(0)?; // reserved
+
+        Ok(size)
+    }
+}
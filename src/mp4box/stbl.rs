@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
 use crate::mp4box::{
@@ -6,11 +6,11 @@ use crate::mp4box::{
     HEADER_SIZE,
 };
 use crate::mp4box::{
-    co64::Co64Box, ctts::CttsBox, stco::StcoBox, stsc::StscBox, stsd::StsdBox, stss::StssBox,
-    stsz::StszBox, stts::SttsBox,
+    co64::Co64Box, ctts::CttsBox, sdtp::SdtpBox, stco::StcoBox, stsc::StscBox, stsd::StsdBox,
+    stss::StssBox, stsz::StszBox, stts::SttsBox,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct StblBox {
     pub stsd: StsdBox,
     pub stts: SttsBox,
@@ -20,6 +20,9 @@ pub struct StblBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stss: Option<StssBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdtp: Option<SdtpBox>,
     pub stsc: StscBox,
     pub stsz: StszBox,
 
@@ -45,6 +48,9 @@ impl StblBox {
         if let Some(ref stss) = self.stss {
             size += stss.box_size();
         }
+        if let Some(ref sdtp) = self.sdtp {
+            size += sdtp.box_size();
+        }
         size += self.stsc.box_size();
         size += self.stsz.box_size();
         if let Some(ref stco) = self.stco {
@@ -55,6 +61,74 @@ impl StblBox {
         }
         size
     }
+
+    /// Merges adjacent runs of `stts`, `ctts` and `stsc` that describe the same thing, like
+    /// those left behind by editing or concatenating tracks, and drops empty `stts` and `ctts`
+    /// runs.
+    ///
+    /// The `stsc` entries are sorted by `first_chunk` and their `first_sample` is recomputed.
+    /// Fails if the `stsc` entries do not start at chunk 1 or two of them start at the same chunk,
+    /// as there is no telling which of them is meant.
+    pub fn normalize(&mut self) -> Result<()> {
+        self.stts.entries.retain(|entry| entry.sample_count != 0);
+        self.stts.entries.dedup_by(|next, prev| {
+            prev.sample_delta == next.sample_delta
+                && merge_count(&mut prev.sample_count, next.sample_count)
+        });
+
+        if let Some(ctts) = &mut self.ctts {
+            ctts.entries.retain(|entry| entry.sample_count != 0);
+            ctts.entries.dedup_by(|next, prev| {
+                prev.sample_offset == next.sample_offset
+                    && merge_count(&mut prev.sample_count, next.sample_count)
+            });
+        }
+
+        let entries = &mut self.stsc.entries;
+        entries.sort_by_key(|entry| entry.first_chunk);
+        if entries.first().is_some_and(|entry| entry.first_chunk != 1) {
+            return Err(Error::InvalidData("stsc entries do not start at chunk 1"));
+        }
+        if entries
+            .windows(2)
+            .any(|pair| pair[0].first_chunk == pair[1].first_chunk)
+        {
+            return Err(Error::InvalidData(
+                "stsc has several entries for the same chunk",
+            ));
+        }
+        entries.dedup_by(|next, prev| {
+            prev.samples_per_chunk == next.samples_per_chunk
+                && prev.sample_description_index == next.sample_description_index
+        });
+
+        let mut first_sample = 1u32;
+        for i in 0..entries.len() {
+            entries[i].first_sample = first_sample;
+            if let Some(next) = entries.get(i + 1) {
+                let entry = &entries[i];
+                first_sample = (next.first_chunk - entry.first_chunk)
+                    .checked_mul(entry.samples_per_chunk)
+                    .and_then(|n| n.checked_add(first_sample))
+                    .ok_or(Error::InvalidData(
+                        "attempt to calculate stsc sample_id with overflow",
+                    ))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds `count` to the run length `total`, unless that would overflow it.
+fn merge_count(total: &mut u32, count: u32) -> bool {
+    match total.checked_add(count) {
+        Some(sum) => {
+            *total = sum;
+            true
+        }
+        None => false,
+    }
 }
 
 impl Mp4Box for StblBox {
@@ -84,6 +158,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
         let mut stts = None;
         let mut ctts = None;
         let mut stss = None;
+        let mut sdtp = None;
         let mut stsc = None;
         let mut stsz = None;
         let mut stco = None;
@@ -114,6 +189,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
                 BoxType::StssBox => {
                     stss = Some(StssBox::read_box(reader, s)?);
                 }
+                BoxType::SdtpBox => {
+                    sdtp = Some(SdtpBox::read_box(reader, s)?);
+                }
                 BoxType::StscBox => {
                     stsc = Some(StscBox::read_box(reader, s)?);
                 }
@@ -157,6 +235,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
             stts,
             ctts,
             stss,
+            sdtp,
             stsc,
             stsz,
             stco,
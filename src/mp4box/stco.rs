@@ -1,19 +1,19 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, co64, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, co64, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType,
+    Error, Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct StcoBox {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub entries: Vec<u32>,
 }
 
@@ -82,6 +82,22 @@ impl<R: Read + Seek> ReadBox<&mut R> for StcoBox {
     }
 }
 
+impl<W: Write> WriteBox<&mut W> for StcoBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for chunk_offset in &self.entries {
+            writer.write_u32::<BigEndian>(*chunk_offset)?;
+        }
+
+        Ok(size)
+    }
+}
+
 impl std::convert::TryFrom<&co64::Co64Box> for StcoBox {
     type Error = std::num::TryFromIntError;
 
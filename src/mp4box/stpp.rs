@@ -0,0 +1,111 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::mp4box::btrt::{read_btrt, BtrtBox};
+use crate::mp4box::mett::read_string;
+use crate::mp4box::{box_start, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+
+/// Sample entry of XML subtitles, in practice TTML, see ISO/IEC 14496-30 section 6.5.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StppBox {
+    pub data_reference_index: u16,
+
+    /// Space-separated XML namespaces of the samples.
+    pub namespace: String,
+
+    /// Space-separated URLs of the schemas of the samples, or empty.
+    pub schema_location: String,
+
+    /// Space-separated MIME types of the resources the samples refer to, like images, or empty.
+    pub auxiliary_mime_types: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+impl StppBox {
+    pub fn get_type() -> BoxType {
+        BoxType::StppBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE
+            + 8
+            + self.namespace.len() as u64
+            + 1
+            + self.schema_location.len() as u64
+            + 1
+            + self.auxiliary_mime_types.len() as u64
+            + 1;
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for StppBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!(
+            "namespace={} schema_location={}",
+            self.namespace, self.schema_location
+        );
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for StppBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        let namespace = read_string(reader, end)?;
+        // Both are optional, and left out entirely by some writers.
+        let schema_location = read_optional_string(reader, end)?;
+        let auxiliary_mime_types = read_optional_string(reader, end)?;
+
+        let btrt = read_btrt(reader, start, end)?;
+
+        Ok(Self {
+            data_reference_index,
+            namespace,
+            schema_location,
+            auxiliary_mime_types,
+            btrt,
+        })
+    }
+}
+
+/// Reads a nul-terminated string, or returns an empty one if the box ends or its children start
+/// first.
+fn read_optional_string<R: Read + Seek>(reader: &mut R, end: u64) -> Result<String> {
+    let current = reader.stream_position()?;
+    if current + HEADER_SIZE <= end {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        reader.seek(SeekFrom::Start(current))?;
+        if &header[4..] == b"btrt" {
+            return Ok(String::new());
+        }
+    } else if current >= end {
+        return Ok(String::new());
+    }
+    read_string(reader, end)
+}
@@ -1,15 +1,20 @@
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
 
+use crate::codec::OBJECT_TYPES_MP3;
+use crate::codec_string;
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, Av01Box, Avc1Box, BoxHeader, BoxType, Error,
-    FourCC, HevcBox, Mp4Box, Mp4aBox, ReadBox, Result, TrackKind, Tx3gBox, Vp08Box, Vp09Box,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, Ac3Box, Av01Box, Avc1Box, BoxHeader, BoxType, BtrtBox, Ec3Box,
+    Error, FourCC, GpmdBox, HevcBox, MebxBox, MettBox, Mp3Box, Mp4Box, Mp4aBox, PcmBox, ReadBox,
+    Result, StppBox, TrackKind, Tx3gBox, UrimBox, Vp08Box, Vp09Box, WvttBox, HEADER_EXT_SIZE,
+    HEADER_SIZE,
 };
+use crate::skipped::skip_children;
+use crate::VideoGeometry;
 
 /// Codec dependent contents of the stsd box.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StsdBoxContent {
     /// AV1 video codec
     Av01(Av01Box),
@@ -29,18 +34,56 @@ pub enum StsdBoxContent {
     /// (i.e. SPS/PPS/VPS NAL units in the bitstream/ mdat box)
     Hev1(HevcBox),
 
+    /// Dolby Vision on top of HVC1 (h.265), whose base layer is not meant to be played without
+    /// the Dolby Vision metadata
+    Dvh1(HevcBox),
+
+    /// Dolby Vision on top of HEV1 (h.265), whose base layer is not meant to be played without
+    /// the Dolby Vision metadata
+    Dvhe(HevcBox),
+
     /// VP8 video codec
     Vp08(Vp08Box),
 
     /// VP9 video codec
     Vp09(Vp09Box),
 
-    /// AAC audio codec
+    /// AAC audio codec, or MP3 with an `esds` of object type 0x69 or 0x6B
     Mp4a(Mp4aBox),
 
+    /// MP3 audio codec (`.mp3`)
+    Mp3(Mp3Box),
+
+    /// Dolby Digital audio codec (AC-3)
+    Ac3(Ac3Box),
+
+    /// Dolby Digital Plus audio codec (E-AC-3)
+    Ec3(Ec3Box),
+
+    /// Uncompressed audio, like `lpcm` or `sowt`
+    Pcm(PcmBox),
+
     /// TTXT subtitle codec
     Tx3g(Tx3gBox),
 
+    /// WebVTT subtitles
+    Wvtt(WvttBox),
+
+    /// XML subtitles, like TTML
+    Stpp(StppBox),
+
+    /// Timed metadata as text, like JSON or XML
+    Mett(MettBox),
+
+    /// Timed metadata in a format named by a URI
+    Urim(UrimBox),
+
+    /// GoPro telemetry (GPMF)
+    Gpmd(GpmdBox),
+
+    /// Apple timed metadata, with a table of keys
+    Mebx(MebxBox),
+
     /// Unrecognized codecs
     Unknown(FourCC),
 }
@@ -52,6 +95,123 @@ impl Default for StsdBoxContent {
 }
 
 impl StsdBoxContent {
+    /// The four character code of the sample entry, e.g. `avc1` or `mp4a`.
+    pub fn fourcc(&self) -> FourCC {
+        match self {
+            Self::Av01(_) => BoxType::Av01Box.into(),
+            Self::Avc1(_) => BoxType::Avc1Box.into(),
+            Self::Hvc1(_) => BoxType::Hvc1Box.into(),
+            Self::Hev1(_) => BoxType::Hev1Box.into(),
+            Self::Dvh1(_) => BoxType::Dvh1Box.into(),
+            Self::Dvhe(_) => BoxType::DvheBox.into(),
+            Self::Vp08(_) => BoxType::Vp08Box.into(),
+            Self::Vp09(_) => BoxType::Vp09Box.into(),
+            Self::Mp4a(_) => BoxType::Mp4aBox.into(),
+            Self::Mp3(_) => BoxType::Mp3Box.into(),
+            Self::Ac3(_) => BoxType::Ac3Box.into(),
+            Self::Ec3(_) => BoxType::Ec3Box.into(),
+            Self::Pcm(entry) => entry.fourcc,
+            Self::Tx3g(_) => BoxType::Tx3gBox.into(),
+            Self::Wvtt(_) => BoxType::WvttBox.into(),
+            Self::Stpp(_) => BoxType::StppBox.into(),
+            Self::Mett(_) => BoxType::MettBox.into(),
+            Self::Urim(_) => BoxType::UrimBox.into(),
+            Self::Gpmd(_) => BoxType::GpmdBox.into(),
+            Self::Mebx(_) => BoxType::MebxBox.into(),
+            Self::Unknown(fourcc) => *fourcc,
+        }
+    }
+
+    /// 1-based index of the [`crate::DataEntry`] that tells where the samples are stored.
+    ///
+    /// Unrecognized sample entries are assumed to use the first entry.
+    pub fn data_reference_index(&self) -> u16 {
+        match self {
+            Self::Av01(entry) => entry.data_reference_index,
+            Self::Avc1(entry) => entry.data_reference_index,
+            Self::Hvc1(entry) | Self::Hev1(entry) | Self::Dvh1(entry) | Self::Dvhe(entry) => {
+                entry.data_reference_index
+            }
+            Self::Vp08(entry) => entry.data_reference_index,
+            Self::Vp09(entry) => entry.data_reference_index,
+            Self::Mp4a(entry) => entry.data_reference_index,
+            Self::Mp3(entry) => entry.data_reference_index,
+            Self::Ac3(entry) => entry.data_reference_index,
+            Self::Ec3(entry) => entry.data_reference_index,
+            Self::Pcm(entry) => entry.data_reference_index,
+            Self::Tx3g(entry) => entry.data_reference_index,
+            Self::Wvtt(entry) => entry.data_reference_index,
+            Self::Stpp(entry) => entry.data_reference_index,
+            Self::Mett(entry) => entry.data_reference_index,
+            Self::Urim(entry) => entry.data_reference_index,
+            Self::Gpmd(entry) => entry.data_reference_index,
+            Self::Mebx(entry) => entry.data_reference_index,
+            Self::Unknown(_) => 1,
+        }
+    }
+
+    /// The bitrate box of the sample entry, if it has one.
+    pub fn btrt(&self) -> Option<&BtrtBox> {
+        match self {
+            Self::Av01(entry) => entry.btrt.as_ref(),
+            Self::Avc1(entry) => entry.btrt.as_ref(),
+            Self::Hvc1(entry) | Self::Hev1(entry) | Self::Dvh1(entry) | Self::Dvhe(entry) => {
+                entry.btrt.as_ref()
+            }
+            Self::Vp08(entry) => entry.btrt.as_ref(),
+            Self::Vp09(entry) => entry.btrt.as_ref(),
+            Self::Mp4a(entry) => entry.btrt.as_ref(),
+            Self::Mp3(entry) => entry.btrt.as_ref(),
+            Self::Ac3(entry) => entry.btrt.as_ref(),
+            Self::Ec3(entry) => entry.btrt.as_ref(),
+            Self::Pcm(entry) => entry.btrt.as_ref(),
+            Self::Wvtt(entry) => entry.btrt.as_ref(),
+            Self::Stpp(entry) => entry.btrt.as_ref(),
+            Self::Mett(entry) => entry.btrt.as_ref(),
+            Self::Urim(entry) => entry.btrt.as_ref(),
+            Self::Mebx(entry) => entry.btrt.as_ref(),
+            Self::Tx3g(_) | Self::Gpmd(_) | Self::Unknown(_) => None,
+        }
+    }
+
+    /// The width and height in pixels of a visual sample entry.
+    pub fn resolution(&self) -> Option<(u16, u16)> {
+        self.video_geometry()
+            .map(|geometry| (geometry.width, geometry.height))
+    }
+
+    /// The coded size, pixel aspect ratio and clean aperture of a visual sample entry.
+    pub fn video_geometry(&self) -> Option<VideoGeometry> {
+        let (width, height, pasp, clap) = match self {
+            Self::Av01(entry) => (entry.width, entry.height, entry.pasp, entry.clap),
+            Self::Avc1(entry) => (entry.width, entry.height, entry.pasp, entry.clap),
+            Self::Hvc1(entry) | Self::Hev1(entry) | Self::Dvh1(entry) | Self::Dvhe(entry) => {
+                (entry.width, entry.height, entry.pasp, entry.clap)
+            }
+            Self::Vp08(entry) => (entry.width, entry.height, entry.pasp, entry.clap),
+            Self::Vp09(entry) => (entry.width, entry.height, entry.pasp, entry.clap),
+            Self::Mp4a(_)
+            | Self::Mp3(_)
+            | Self::Ac3(_)
+            | Self::Ec3(_)
+            | Self::Pcm(_)
+            | Self::Tx3g(_)
+            | Self::Wvtt(_)
+            | Self::Stpp(_)
+            | Self::Mett(_)
+            | Self::Urim(_)
+            | Self::Gpmd(_)
+            | Self::Mebx(_)
+            | Self::Unknown(_) => return None,
+        };
+        Some(VideoGeometry {
+            width,
+            height,
+            pasp,
+            clap,
+        })
+    }
+
     /// Per color component bit depth.
     ///
     /// Usually 8, but 10 for HDR (for example).
@@ -66,109 +226,104 @@ impl StsdBoxContent {
 
             Self::Hev1(_) => None, // TODO(emilk): figure out bit depth
 
+            Self::Dvh1(_) | Self::Dvhe(_) => None, // TODO(emilk): figure out bit depth
+
             Self::Vp08(bx) => Some(bx.vpcc.bit_depth),
 
             Self::Vp09(bx) => Some(bx.vpcc.bit_depth),
 
-            Self::Mp4a(_) | Self::Tx3g(_) | Self::Unknown(_) => None, // Not applicable
+            Self::Mp4a(_)
+            | Self::Mp3(_)
+            | Self::Ac3(_)
+            | Self::Ec3(_)
+            | Self::Pcm(_)
+            | Self::Tx3g(_)
+            | Self::Wvtt(_)
+            | Self::Stpp(_)
+            | Self::Mett(_)
+            | Self::Urim(_)
+            | Self::Gpmd(_)
+            | Self::Mebx(_)
+            | Self::Unknown(_) => {
+                None // Not applicable
+            }
         }
     }
 
     pub fn codec_string(&self) -> Option<String> {
         Some(match self {
-            Self::Av01(Av01Box { av1c, .. }) => {
-                let profile = av1c.profile;
-                let level = av1c.level;
-                let tier = if av1c.tier == 0 { "M" } else { "H" };
-                let bit_depth = av1c.bit_depth;
+            Self::Av01(Av01Box { av1c, .. }) => codec_string::av1(av1c),
 
-                format!("av01.{profile}.{level:02}{tier}.{bit_depth:02}")
-            }
+            Self::Avc1(Avc1Box { avcc, .. }) => codec_string::avc(
+                avcc.avc_profile_indication,
+                avcc.profile_compatibility,
+                avcc.avc_level_indication,
+            ),
 
-            Self::Avc1(Avc1Box { avcc, .. }) => {
-                let profile = avcc.avc_profile_indication;
-                let constraint = avcc.profile_compatibility;
-                let level = avcc.avc_level_indication;
+            Self::Hvc1(HevcBox { hvcc, .. }) => codec_string::hevc("hvc1", hvcc),
 
-                // https://aomediacodec.github.io/av1-isobmff/#codecsparam
-                format!("avc1.{profile:02X}{constraint:02X}{level:02X}")
-            }
+            Self::Hev1(HevcBox { hvcc, .. }) => codec_string::hevc("hev1", hvcc),
 
-            Self::Hvc1(HevcBox { hvcc, .. }) => {
-                format!("hvc1{}", hevc_codec_details(hvcc))
-            }
-
-            Self::Hev1(HevcBox { hvcc, .. }) => {
-                format!("hev1{}", hevc_codec_details(hvcc))
-            }
+            Self::Dvh1(_) | Self::Dvhe(_) => self
+                .dolby_vision_codec_string()
+                .unwrap_or_else(|| self.fourcc().to_string()),
 
             Self::Vp08(_) => {
                 // https://www.w3.org/TR/webcodecs-vp8-codec-registration/#fully-qualified-codec-strings
                 String::from("vp8")
             }
 
-            Self::Vp09(Vp09Box { vpcc, .. }) => {
-                let profile = vpcc.profile;
-                let level = vpcc.level;
-                let bit_depth = vpcc.bit_depth;
+            // https://www.w3.org/TR/webcodecs-ac3-codec-registration/
+            Self::Ac3(_) => String::from("ac-3"),
+
+            Self::Ec3(_) => String::from("ec-3"),
 
-                format!("vp09.{profile:02}.{level:02}.{bit_depth:02}")
+            // https://www.w3.org/TR/webcodecs-mp3-codec-registration/
+            Self::Mp3(_) => String::from("mp3"),
+
+            Self::Mp4a(Mp4aBox {
+                esds: Some(esds), ..
+            }) if OBJECT_TYPES_MP3.contains(&esds.es_desc.dec_config.object_type_indication) => {
+                String::from("mp3")
             }
 
-            Self::Mp4a(_) | Self::Tx3g(_) | Self::Unknown(_) => return None,
-        })
-    }
-}
+            Self::Wvtt(_) => String::from("wvtt"),
 
-fn hevc_codec_details(hvcc: &crate::hevc::HevcDecoderConfigurationRecord) -> String {
-    use std::fmt::Write as _;
+            Self::Stpp(stpp) => codec_string::stpp(stpp),
 
-    let mut codec = String::new();
-    match hvcc.general_profile_space {
-        1 => codec.push_str(".A"),
-        2 => codec.push_str(".B"),
-        3 => codec.push_str(".C"),
-        _ => {}
-    }
-    write!(&mut codec, ".{}", hvcc.general_profile_idc).ok();
-
-    let mut val = hvcc.general_profile_compatibility_flags;
-    let mut reversed = 0;
-    for i in 0..32 {
-        reversed |= val & 1;
-        if i == 31 {
-            break;
-        }
-        reversed <<= 1;
-        val >>= 1;
-    }
-    write!(&mut codec, ".{reversed:X}").ok();
+            Self::Vp09(Vp09Box { vpcc, .. }) => {
+                codec_string::vp9(vpcc.profile, vpcc.level, vpcc.bit_depth)
+            }
 
-    if hvcc.general_tier_flag {
-        codec.push_str(".H");
-    } else {
-        codec.push_str(".L");
-    }
-    write!(&mut codec, "{}", hvcc.general_level_idc).ok();
-
-    let mut constraint = [0u8; 6];
-    constraint.copy_from_slice(&hvcc.general_constraint_indicator_flag.to_be_bytes()[2..]);
-    let mut has_byte = false;
-    let mut i = 5isize;
-    while 0 <= i {
-        let v = constraint[i as usize];
-        if v > 0 || has_byte {
-            write!(&mut codec, ".{v:00X}").ok();
-            has_byte = true;
-        }
-        i -= 1;
+            Self::Mp4a(_)
+            | Self::Pcm(_)
+            | Self::Tx3g(_)
+            | Self::Mett(_)
+            | Self::Urim(_)
+            | Self::Gpmd(_)
+            | Self::Mebx(_)
+            | Self::Unknown(_) => return None,
+        })
     }
 
-    codec
+    /// The codec string of the Dolby Vision stream of an HEVC sample entry, like `dvh1.08.06`,
+    /// or `None` if the entry has no Dolby Vision configuration.
+    ///
+    /// For `hvc1` and `hev1` entries this differs from [`Self::codec_string`], which describes
+    /// the backwards compatible HEVC stream that players without Dolby Vision support can use.
+    pub fn dolby_vision_codec_string(&self) -> Option<String> {
+        let (fourcc, entry) = match self {
+            Self::Hvc1(entry) | Self::Dvh1(entry) => ("dvh1", entry),
+            Self::Hev1(entry) | Self::Dvhe(entry) => ("dvhe", entry),
+            _ => return None,
+        };
+        let dovi = entry.dovi.as_ref()?;
+        Some(codec_string::dolby_vision(fourcc, dovi))
+    }
 }
 
 /// Information about the video codec.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct StsdBox {
     pub version: u8,
     pub flags: u32,
@@ -182,10 +337,22 @@ impl StsdBox {
             | StsdBoxContent::Avc1(_)
             | StsdBoxContent::Hev1(_)
             | StsdBoxContent::Hvc1(_)
+            | StsdBoxContent::Dvh1(_)
+            | StsdBoxContent::Dvhe(_)
             | StsdBoxContent::Vp08(_)
             | StsdBoxContent::Vp09(_) => Some(TrackKind::Video),
-            StsdBoxContent::Mp4a(_) => Some(TrackKind::Audio),
-            StsdBoxContent::Tx3g(_) => Some(TrackKind::Subtitle),
+            StsdBoxContent::Mp4a(_)
+            | StsdBoxContent::Mp3(_)
+            | StsdBoxContent::Ac3(_)
+            | StsdBoxContent::Ec3(_)
+            | StsdBoxContent::Pcm(_) => Some(TrackKind::Audio),
+            StsdBoxContent::Tx3g(_) | StsdBoxContent::Wvtt(_) | StsdBoxContent::Stpp(_) => {
+                Some(TrackKind::Subtitle)
+            }
+            StsdBoxContent::Mett(_)
+            | StsdBoxContent::Urim(_)
+            | StsdBoxContent::Gpmd(_)
+            | StsdBoxContent::Mebx(_) => Some(TrackKind::Metadata),
             StsdBoxContent::Unknown(_) => None,
         }
     }
@@ -201,13 +368,24 @@ impl StsdBox {
             + match &self.contents {
                 StsdBoxContent::Av01(contents) => contents.box_size(),
                 StsdBoxContent::Avc1(contents) => contents.box_size(),
-                StsdBoxContent::Hev1(contents) | StsdBoxContent::Hvc1(contents) => {
-                    contents.box_size()
-                }
+                StsdBoxContent::Hev1(contents)
+                | StsdBoxContent::Hvc1(contents)
+                | StsdBoxContent::Dvh1(contents)
+                | StsdBoxContent::Dvhe(contents) => contents.box_size(),
                 StsdBoxContent::Vp08(contents) => contents.box_size(),
                 StsdBoxContent::Vp09(contents) => contents.box_size(),
                 StsdBoxContent::Mp4a(contents) => contents.box_size(),
+                StsdBoxContent::Mp3(contents) => contents.box_size(),
+                StsdBoxContent::Ac3(contents) => contents.box_size(),
+                StsdBoxContent::Ec3(contents) => contents.box_size(),
+                StsdBoxContent::Pcm(contents) => contents.box_size(),
                 StsdBoxContent::Tx3g(contents) => contents.box_size(),
+                StsdBoxContent::Wvtt(contents) => contents.box_size(),
+                StsdBoxContent::Stpp(contents) => contents.box_size(),
+                StsdBoxContent::Mett(contents) => contents.box_size(),
+                StsdBoxContent::Urim(contents) => contents.box_size(),
+                StsdBoxContent::Gpmd(contents) => contents.box_size(),
+                StsdBoxContent::Mebx(contents) => contents.box_size(),
                 StsdBoxContent::Unknown(_) => 0,
             }
     }
@@ -227,7 +405,11 @@ impl Mp4Box for StsdBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = String::new();
+        let fourcc = self.contents.fourcc();
+        let s = match fourcc.description() {
+            Some(description) => format!("entry={fourcc} ({description})"),
+            None => format!("entry={fourcc}"),
+        };
         Ok(s)
     }
 }
@@ -241,6 +423,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for StsdBox {
         reader.read_u32::<BigEndian>()?; // XXX entry_count
 
         // Get box header.
+        let entry_start = reader.stream_position()?;
         let header = BoxHeader::read(reader)?;
         let BoxHeader { name, size: s } = header;
         if s > size {
@@ -256,14 +439,31 @@ impl<R: Read + Seek> ReadBox<&mut R> for StsdBox {
             BoxType::Avc1Box => StsdBoxContent::Avc1(Avc1Box::read_box(reader, s)?),
             BoxType::Hvc1Box => StsdBoxContent::Hvc1(HevcBox::read_box(reader, s)?),
             BoxType::Hev1Box => StsdBoxContent::Hev1(HevcBox::read_box(reader, s)?),
+            BoxType::Dvh1Box => StsdBoxContent::Dvh1(HevcBox::read_box(reader, s)?),
+            BoxType::DvheBox => StsdBoxContent::Dvhe(HevcBox::read_box(reader, s)?),
             BoxType::Vp08Box => StsdBoxContent::Vp08(Vp08Box::read_box(reader, s)?),
             BoxType::Vp09Box => StsdBoxContent::Vp09(Vp09Box::read_box(reader, s)?),
             BoxType::Mp4aBox => StsdBoxContent::Mp4a(Mp4aBox::read_box(reader, s)?),
+            BoxType::Mp3Box => StsdBoxContent::Mp3(Mp3Box::read_box(reader, s)?),
+            BoxType::Ac3Box => StsdBoxContent::Ac3(Ac3Box::read_box(reader, s)?),
+            BoxType::Ec3Box => StsdBoxContent::Ec3(Ec3Box::read_box(reader, s)?),
+            name if PcmBox::is_pcm(name) => StsdBoxContent::Pcm(PcmBox::read_box(reader, s)?),
             BoxType::Tx3gBox => StsdBoxContent::Tx3g(Tx3gBox::read_box(reader, s)?),
-            _ => StsdBoxContent::Unknown(name.into()),
+            BoxType::WvttBox => StsdBoxContent::Wvtt(WvttBox::read_box(reader, s)?),
+            BoxType::StppBox => StsdBoxContent::Stpp(StppBox::read_box(reader, s)?),
+            BoxType::MettBox => StsdBoxContent::Mett(MettBox::read_box(reader, s)?),
+            BoxType::UrimBox => StsdBoxContent::Urim(UrimBox::read_box(reader, s)?),
+            BoxType::GpmdBox => StsdBoxContent::Gpmd(GpmdBox::read_box(reader, s)?),
+            BoxType::MebxBox => StsdBoxContent::Mebx(MebxBox::read_box(reader, s)?),
+            _ => {
+                // Rewind, so that the entry is logged together with the ones that follow it.
+                reader.seek(SeekFrom::Start(entry_start))?;
+                StsdBoxContent::Unknown(name.into())
+            }
         };
 
-        skip_bytes_to(reader, start + size)?;
+        // Only the first sample entry is parsed.
+        skip_children(reader, start, start + size)?;
 
         Ok(Self {
             version,
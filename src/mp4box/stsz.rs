@@ -1,21 +1,21 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct StszBox {
     pub version: u8,
     pub flags: u32,
     pub sample_size: u32,
     pub sample_count: u32,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub sample_sizes: Vec<u32>,
 }
 
@@ -27,6 +27,25 @@ impl StszBox {
     pub fn get_size(&self) -> u64 {
         HEADER_SIZE + HEADER_EXT_SIZE + 8 + (4 * self.sample_sizes.len() as u64)
     }
+
+    /// The number of samples, whether they all have [`Self::sample_size`] or each their own
+    /// size in [`Self::sample_sizes`].
+    pub fn count(&self) -> usize {
+        if self.sample_size == 0 {
+            self.sample_sizes.len()
+        } else {
+            self.sample_count as usize
+        }
+    }
+
+    /// The size of sample `index`, counting from zero, or `None` if there is no such sample.
+    pub fn sample_size(&self, index: usize) -> Option<u32> {
+        if self.sample_size == 0 {
+            self.sample_sizes.get(index).copied()
+        } else {
+            (index < self.sample_count as usize).then_some(self.sample_size)
+        }
+    }
 }
 
 impl Mp4Box for StszBox {
@@ -98,3 +117,24 @@ impl<R: Read + Seek> ReadBox<&mut R> for StszBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for StszBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.sample_size)?;
+        writer.write_u32::<BigEndian>(self.sample_count)?;
+        if self.sample_size == 0 {
+            if self.sample_count != self.sample_sizes.len() as u32 {
+                return Err(Error::InvalidData("sample count out of sync"));
+            }
+            for sample_number in &self.sample_sizes {
+                writer.write_u32::<BigEndian>(*sample_number)?;
+            }
+        }
+        Ok(size)
+    }
+}
@@ -1,19 +1,45 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, value_u32, value_u8, BoxType, Error,
-    FixedPointU16, FixedPointU8, Mp4Box, ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, value_u32, value_u8, write_box_header_ext,
+    BoxHeader, BoxType, Error, FixedPointU16, FixedPointU8, Mp4Box, ReadBox, Result, WriteBox,
+    HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
+/// The flags of a track header as single values, see [`TkhdFlags`] for combining them.
+#[expect(
+    clippy::enum_variant_names,
+    reason = "`TrackEnabled` was public before the other variants"
+)]
 pub enum TrackFlag {
     TrackEnabled = 0x000001,
-    // TrackInMovie = 0x000002,
-    // TrackInPreview = 0x000004,
+    TrackInMovie = 0x000002,
+    TrackInPreview = 0x000004,
+    TrackSizeIsAspectRatio = 0x000008,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+bitflags::bitflags! {
+    /// The flags of a track header, see ISO/IEC 14496-12 section 8.3.2.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct TkhdFlags: u32 {
+        /// The track is enabled. Players treat disabled tracks as if they were not there.
+        const ENABLED = 0x000001;
+
+        /// The track is used in the presentation.
+        const IN_MOVIE = 0x000002;
+
+        /// The track is used when previewing the presentation.
+        const IN_PREVIEW = 0x000004;
+
+        /// The width and height of the track header are an aspect ratio rather than a size in
+        /// pixels, as written by some muxers for tracks without a display size of their own.
+        const SIZE_IS_ASPECT_RATIO = 0x000008;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TkhdBox {
     pub version: u8,
     pub flags: u32,
@@ -39,7 +65,7 @@ impl Default for TkhdBox {
     fn default() -> Self {
         Self {
             version: 0,
-            flags: TrackFlag::TrackEnabled as u32,
+            flags: TkhdFlags::ENABLED.bits(),
             creation_time: 0,
             modification_time: 0,
             track_id: 0,
@@ -54,7 +80,7 @@ impl Default for TkhdBox {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Matrix {
     pub a: i32,
     pub b: i32,
@@ -94,6 +120,33 @@ impl Default for Matrix {
     }
 }
 
+impl Matrix {
+    /// The clockwise rotation in degrees, 0, 90, 180 or 270, that the matrix applies when the
+    /// video is displayed, as phones store it for videos filmed in portrait orientation.
+    ///
+    /// Returns `None` if the matrix is not a rotation by a multiple of 90 degrees, e.g. because
+    /// it mirrors the video. Scaling and translation are ignored.
+    pub fn rotation_degrees(&self) -> Option<u16> {
+        let Self { a, b, c, d, .. } = *self;
+        match (a.signum(), b.signum(), c.signum(), d.signum()) {
+            (1, 0, 0, 1) => Some(0),
+            (0, 1, -1, 0) => Some(90),
+            (-1, 0, 0, -1) => Some(180),
+            (0, -1, 1, 0) => Some(270),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for value in [
+            self.a, self.b, self.u, self.c, self.d, self.v, self.x, self.y, self.w,
+        ] {
+            writer.write_i32::<BigEndian>(value)?;
+        }
+        Ok(())
+    }
+}
+
 impl TkhdBox {
     pub fn get_type() -> BoxType {
         BoxType::TkhdBox
@@ -117,6 +170,32 @@ impl TkhdBox {
     pub fn set_height(&mut self, height: u16) {
         self.height = FixedPointU16::new(height);
     }
+
+    /// [`Self::flags`] as [`TkhdFlags`], keeping any bits that are not defined.
+    pub fn track_flags(&self) -> TkhdFlags {
+        TkhdFlags::from_bits_retain(self.flags)
+    }
+
+    pub fn set_track_flags(&mut self, flags: TkhdFlags) {
+        self.flags = flags.bits();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.track_flags().contains(TkhdFlags::ENABLED)
+    }
+
+    pub fn is_in_movie(&self) -> bool {
+        self.track_flags().contains(TkhdFlags::IN_MOVIE)
+    }
+
+    pub fn is_in_preview(&self) -> bool {
+        self.track_flags().contains(TkhdFlags::IN_PREVIEW)
+    }
+
+    /// Whether [`Self::width`] and [`Self::height`] are an aspect ratio rather than a size.
+    pub fn size_is_aspect_ratio(&self) -> bool {
+        self.track_flags().contains(TkhdFlags::SIZE_IS_ASPECT_RATIO)
+    }
 }
 
 impl Mp4Box for TkhdBox {
@@ -212,3 +291,41 @@ impl<R: Read + Seek> ReadBox<&mut R> for TkhdBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for TkhdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        if self.version == 1 {
+            writer.write_u64::<BigEndian>(self.creation_time)?;
+            writer.write_u64::<BigEndian>(self.modification_time)?;
+            writer.write_u32::<BigEndian>(self.track_id)?;
+            writer.write_u32::<BigEndian>(0)?; // reserved
+            writer.write_u64::<BigEndian>(self.duration)?;
+        } else if self.version == 0 {
+            writer.write_u32::<BigEndian>(self.creation_time as u32)?;
+            writer.write_u32::<BigEndian>(self.modification_time as u32)?;
+            writer.write_u32::<BigEndian>(self.track_id)?;
+            writer.write_u32::<BigEndian>(0)?; // reserved
+            writer.write_u32::<BigEndian>(self.duration as u32)?;
+        } else {
+            return Err(Error::InvalidData("version must be 0 or 1"));
+        }
+
+        writer.write_u64::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.layer)?;
+        writer.write_u16::<BigEndian>(self.alternate_group)?;
+        writer.write_u16::<BigEndian>(self.volume.raw_value())?;
+
+        writer.write_u16::<BigEndian>(0)?; // reserved
+
+        self.matrix.write(writer)?;
+
+        writer.write_u32::<BigEndian>(self.width.raw_value())?;
+        writer.write_u32::<BigEndian>(self.height.raw_value())?;
+        Ok(size)
+    }
+}
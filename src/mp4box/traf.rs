@@ -1,13 +1,13 @@
-use serde::Serialize;
-use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 
 use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_SIZE,
+    WriteBox, HEADER_SIZE,
 };
 use crate::mp4box::{tfdt::TfdtBox, tfhd::TfhdBox, trun::TrunBox};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct TrafBox {
     pub tfhd: TfhdBox,
     pub tfdt: Option<TfdtBox>,
@@ -99,3 +99,19 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrafBox {
         Ok(Self { tfhd, tfdt, truns })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for TrafBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        self.tfhd.write_box(writer)?;
+        if let Some(tfdt) = &self.tfdt {
+            tfdt.write_box(writer)?;
+        }
+        for trun in &self.truns {
+            trun.write_box(writer)?;
+        }
+        Ok(size)
+    }
+}
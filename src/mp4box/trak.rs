@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
 use crate::meta::MetaBox;
@@ -6,9 +6,10 @@ use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
     HEADER_SIZE,
 };
-use crate::mp4box::{edts::EdtsBox, mdia::MdiaBox, tkhd::TkhdBox};
+use crate::mp4box::{dinf::DataEntry, edts::EdtsBox, mdia::MdiaBox, tkhd::TkhdBox, tref::TrefBox};
+use crate::ExtraBox;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct TrakBox {
     pub tkhd: TkhdBox,
 
@@ -18,7 +19,15 @@ pub struct TrakBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<MetaBox>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tref: Option<TrefBox>,
+
     pub mdia: MdiaBox,
+
+    /// The boxes in `trak` that this crate doesn't parse, like a track-level `udta` or vendor
+    /// boxes, in file order. Space boxes (`free`, `skip` and `wide`) are left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extras: Vec<ExtraBox>,
 }
 
 impl TrakBox {
@@ -32,9 +41,23 @@ impl TrakBox {
         if let Some(ref edts) = self.edts {
             size += edts.box_size();
         }
+        if let Some(ref tref) = self.tref {
+            size += tref.box_size();
+        }
         size += self.mdia.box_size();
+        for extra in &self.extras {
+            size += extra.size;
+        }
         size
     }
+
+    /// The data reference that the sample entry of this track points to, if it exists.
+    pub fn data_reference(&self) -> Option<&DataEntry> {
+        let minf = &self.mdia.minf;
+        minf.dinf
+            .dref
+            .entry(minf.stbl.stsd.contents.data_reference_index())
+    }
 }
 
 impl Mp4Box for TrakBox {
@@ -63,7 +86,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
         let mut tkhd = None;
         let mut edts = None;
         let mut meta = None;
+        let mut tref = None;
         let mut mdia = None;
+        let mut extras = Vec::new();
 
         let mut current = reader.stream_position()?;
         let end = start + size;
@@ -87,13 +112,18 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
                 BoxType::MetaBox => {
                     meta = Some(MetaBox::read_box(reader, s)?);
                 }
+                BoxType::TrefBox => {
+                    tref = Some(TrefBox::read_box(reader, s)?);
+                }
                 BoxType::MdiaBox => {
                     mdia = Some(MdiaBox::read_box(reader, s)?);
                 }
-                _ => {
-                    // XXX warn!()
+                BoxType::FreeBox | BoxType::SkipBox | BoxType::WideBox => {
                     skip_box(reader, s)?;
                 }
+                _ => {
+                    extras.push(ExtraBox::read(reader, name, current, s)?);
+                }
             }
 
             current = reader.stream_position()?;
@@ -112,7 +142,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
             tkhd,
             edts,
             meta,
+            tref,
             mdia,
+            extras,
         })
     }
 }
@@ -0,0 +1,124 @@
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
+
+use crate::mp4box::{
+    box_start, skip_bytes_to, BoxHeader, BoxType, Error, FourCC, Mp4Box, ReadBox, Result, WriteBox,
+    HEADER_SIZE,
+};
+
+/// Track reference box, which links a track to others, e.g. to its chapter track with `chap`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TrefBox {
+    pub references: Vec<TrackReference>,
+}
+
+/// The tracks that a track refers to for one reason.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TrackReference {
+    /// Such as `chap` for chapter tracks, `hint` or `cdsc`.
+    pub reference_type: FourCC,
+    pub track_ids: Vec<u32>,
+}
+
+impl TrefBox {
+    pub fn get_type() -> BoxType {
+        BoxType::TrefBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE
+            + self
+                .references
+                .iter()
+                .map(|reference| HEADER_SIZE + 4 * reference.track_ids.len() as u64)
+                .sum::<u64>()
+    }
+
+    /// The tracks referred to with the given reference type.
+    pub fn track_ids(&self, reference_type: FourCC) -> &[u32] {
+        self.references
+            .iter()
+            .find(|reference| reference.reference_type == reference_type)
+            .map_or(&[], |reference| &reference.track_ids)
+    }
+}
+
+impl Mp4Box for TrefBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = self
+            .references
+            .iter()
+            .map(|reference| format!("{}={:?}", reference.reference_type, reference.track_ids))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for TrefBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut references = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+            if s < HEADER_SIZE || current + s > end {
+                return Err(Error::InvalidData(
+                    "tref box contains a box with a larger size than it",
+                ));
+            }
+
+            let track_count = (s - HEADER_SIZE) / 4;
+            let mut track_ids = Vec::with_capacity(track_count as usize);
+            for _ in 0..track_count {
+                track_ids.push(reader.read_u32::<BigEndian>()?);
+            }
+            references.push(TrackReference {
+                reference_type: name.into(),
+                track_ids,
+            });
+
+            skip_bytes_to(reader, current + s)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Self { references })
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for TrefBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        for reference in &self.references {
+            let reference_size = HEADER_SIZE + 4 * reference.track_ids.len() as u64;
+            BoxHeader::new(u32::from(reference.reference_type).into(), reference_size)
+                .write(writer)?;
+            for track_id in &reference.track_ids {
+                writer.write_u32::<BigEndian>(*track_id)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
@@ -1,14 +1,14 @@
-use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
-use std::io::{Read, Seek};
+use byteorder::{BigEndian, ReadBytesExt as _, WriteBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, Write};
 use std::mem::size_of;
 
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BoxType, Error, Mp4Box, ReadBox, Result,
-    HEADER_EXT_SIZE, HEADER_SIZE,
+    box_start, read_box_header_ext, skip_bytes_to, write_box_header_ext, BoxHeader, BoxType, Error,
+    Mp4Box, ReadBox, Result, WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct TrunBox {
     pub version: u8,
     pub flags: u32,
@@ -16,13 +16,13 @@ pub struct TrunBox {
     pub data_offset: Option<i32>,
     pub first_sample_flags: Option<u32>,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub sample_durations: Vec<u32>,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub sample_sizes: Vec<u32>,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub sample_flags: Vec<u32>,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub sample_cts: Vec<u32>,
 }
 
@@ -173,3 +173,45 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrunBox {
         })
     }
 }
+
+impl<W: Write> WriteBox<&mut W> for TrunBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.sample_count)?;
+        if let Some(data_offset) = self.data_offset {
+            writer.write_i32::<BigEndian>(data_offset)?;
+        }
+        if let Some(first_sample_flags) = self.first_sample_flags {
+            writer.write_u32::<BigEndian>(first_sample_flags)?;
+        }
+        for (flag, len) in [
+            (Self::FLAG_SAMPLE_DURATION, self.sample_durations.len()),
+            (Self::FLAG_SAMPLE_SIZE, self.sample_sizes.len()),
+            (Self::FLAG_SAMPLE_FLAGS, self.sample_flags.len()),
+            (Self::FLAG_SAMPLE_CTS, self.sample_cts.len()),
+        ] {
+            if flag & self.flags > 0 && len != self.sample_count as usize {
+                return Err(Error::InvalidData("sample count out of sync"));
+            }
+        }
+        for i in 0..self.sample_count as usize {
+            if Self::FLAG_SAMPLE_DURATION & self.flags > 0 {
+                writer.write_u32::<BigEndian>(self.sample_durations[i])?;
+            }
+            if Self::FLAG_SAMPLE_SIZE & self.flags > 0 {
+                writer.write_u32::<BigEndian>(self.sample_sizes[i])?;
+            }
+            if Self::FLAG_SAMPLE_FLAGS & self.flags > 0 {
+                writer.write_u32::<BigEndian>(self.sample_flags[i])?;
+            }
+            if Self::FLAG_SAMPLE_CTS & self.flags > 0 {
+                writer.write_u32::<BigEndian>(self.sample_cts[i])?;
+            }
+        }
+        Ok(size)
+    }
+}
@@ -1,10 +1,12 @@
 use byteorder::{BigEndian, ReadBytesExt as _};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Read, Seek};
 
-use crate::mp4box::{box_start, skip_bytes_to, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+use crate::mp4box::{box_start, BoxType, Mp4Box, ReadBox, Result, HEADER_SIZE};
+use crate::skipped::skip_children;
+use crate::tx3g::StyleRecord;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tx3gBox {
     pub data_reference_index: u16,
     pub display_flags: u32,
@@ -15,7 +17,7 @@ pub struct Tx3gBox {
     pub style_record: [u8; 12],
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct RgbaColor {
     pub red: u8,
     pub green: u8,
@@ -50,6 +52,11 @@ impl Tx3gBox {
     pub fn get_size() -> u64 {
         HEADER_SIZE + 6 + 32
     }
+
+    /// The style of text that samples don't style otherwise, see [`crate::tx3g::decode_sample`].
+    pub fn default_style(&self) -> StyleRecord {
+        StyleRecord::from_bytes(self.style_record)
+    }
 }
 
 impl Mp4Box for Tx3gBox {
@@ -112,7 +119,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Tx3gBox {
             reader.read_u8()?,
         ];
 
-        skip_bytes_to(reader, start + size)?;
+        skip_children(reader, start, start + size)?;
 
         Ok(Self {
             data_reference_index,
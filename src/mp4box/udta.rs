@@ -1,17 +1,27 @@
 use std::io::{Read, Seek};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::mp4box::chpl::ChplBox;
 use crate::mp4box::meta::MetaBox;
 use crate::mp4box::{
     box_start, skip_box, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
     HEADER_SIZE,
 };
+use crate::ExtraBox;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct UdtaBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<MetaBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chpl: Option<ChplBox>,
+
+    /// The boxes in `udta` that this crate doesn't parse, like `QuickTime` user data items or
+    /// `XMP_`, in file order. Space boxes (`free`, `skip` and `wide`) are left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extras: Vec<ExtraBox>,
 }
 
 impl UdtaBox {
@@ -24,6 +34,12 @@ impl UdtaBox {
         if let Some(meta) = &self.meta {
             size += meta.box_size();
         }
+        if let Some(chpl) = &self.chpl {
+            size += chpl.box_size();
+        }
+        for extra in &self.extras {
+            size += extra.size;
+        }
         size
     }
 }
@@ -51,6 +67,8 @@ impl<R: Read + Seek> ReadBox<&mut R> for UdtaBox {
         let start = box_start(reader)?;
 
         let mut meta = None;
+        let mut chpl = None;
+        let mut extras = Vec::new();
 
         let mut current = reader.stream_position()?;
         let end = start + size;
@@ -68,10 +86,15 @@ impl<R: Read + Seek> ReadBox<&mut R> for UdtaBox {
                 BoxType::MetaBox => {
                     meta = Some(MetaBox::read_box(reader, s)?);
                 }
-                _ => {
-                    // XXX warn!()
+                BoxType::ChplBox => {
+                    chpl = Some(ChplBox::read_box(reader, s)?);
+                }
+                BoxType::FreeBox | BoxType::SkipBox | BoxType::WideBox => {
                     skip_box(reader, s)?;
                 }
+                _ => {
+                    extras.push(ExtraBox::read(reader, name, current, s)?);
+                }
             }
 
             current = reader.stream_position()?;
@@ -79,6 +102,6 @@ impl<R: Read + Seek> ReadBox<&mut R> for UdtaBox {
 
         skip_bytes_to(reader, start + size)?;
 
-        Ok(Self { meta })
+        Ok(Self { meta, chpl, extras })
     }
 }
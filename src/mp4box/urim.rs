@@ -0,0 +1,122 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::mett::read_string;
+use crate::mp4box::{
+    box_start, read_box_header_ext, read_bytes, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box,
+    ReadBox, Result, HEADER_EXT_SIZE, HEADER_SIZE,
+};
+use crate::skipped::{log_skipped, Skipped};
+
+/// Sample entry of a timed metadata track whose format is named by a URI, see
+/// ISO/IEC 14496-12 section 12.3.3.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UrimBox {
+    pub data_reference_index: u16,
+
+    /// The URI that names the format of the samples, from the `uri ` box.
+    pub uri: String,
+
+    /// Data to initialize the reader of the samples with, from the `uriI` box.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri_init: Option<Vec<u8>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+impl UrimBox {
+    pub fn get_type() -> BoxType {
+        BoxType::UrimBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + HEADER_SIZE + HEADER_EXT_SIZE + self.uri.len() as u64 + 1;
+        if let Some(uri_init) = &self.uri_init {
+            size += HEADER_SIZE + HEADER_EXT_SIZE + uri_init.len() as u64;
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for UrimBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("uri={}", self.uri);
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for UrimBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        let mut uri = None;
+        let mut uri_init = None;
+        let mut btrt = None;
+        loop {
+            let current = reader.stream_position()?;
+            if current + HEADER_SIZE > end {
+                break;
+            }
+            let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+            if s < HEADER_SIZE || current + s > end {
+                return Err(Error::InvalidData(
+                    "urim box contains a box with a larger size than it",
+                ));
+            }
+            match &u32::from(name).to_be_bytes() {
+                b"uri " if uri.is_none() => {
+                    read_box_header_ext(reader)?;
+                    uri = Some(read_string(reader, current + s)?);
+                }
+                b"uriI" if uri_init.is_none() => {
+                    read_box_header_ext(reader)?;
+                    let data = read_bytes(reader, s.saturating_sub(HEADER_SIZE + HEADER_EXT_SIZE))?;
+                    uri_init = Some(data);
+                }
+                b"btrt" if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => {
+                    log_skipped(&Skipped::Box {
+                        parent: BoxType::UrimBox.into(),
+                        box_type: name.into(),
+                        offset: current,
+                        size: s,
+                    });
+                }
+            }
+            skip_bytes_to(reader, current + s)?;
+        }
+        skip_bytes_to(reader, end)?;
+
+        Ok(Self {
+            data_reference_index,
+            uri: uri.ok_or(Error::InvalidData("urim box without a uri box"))?,
+            uri_init,
+            btrt,
+        })
+    }
+}
@@ -1,13 +1,17 @@
+use crate::mp4box::btrt::{read_btrt_with, BtrtBox};
+use crate::mp4box::clap::read_clap;
+use crate::mp4box::colr::read_colr;
+use crate::mp4box::pasp::read_pasp;
 use crate::mp4box::vpcc::VpccBox;
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BigEndian, BoxHeader, BoxType, Error, RawBox,
-    Read, ReadBox, ReadBytesExt as _, Result, Seek,
+    box_start, read_box_header_ext, BigEndian, BoxHeader, BoxType, ClapBox, ColrBox, Error,
+    PaspBox, RawBox, Read, ReadBox, ReadBytesExt as _, Result, Seek,
 };
 use crate::Mp4Box;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Note: `Vp08Box` is identical to `Vp09Box`
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Vp09Box {
     pub version: u8,
     pub flags: u32,
@@ -24,6 +28,14 @@ pub struct Vp09Box {
     pub depth: u16, // This is usually 24, even for HDR with bit_depth=10
     pub end_code: u16,
     pub vpcc: RawBox<VpccBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pasp: Option<PaspBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clap: Option<ClapBox>,
 }
 
 impl Mp4Box for Vp09Box {
@@ -90,7 +102,14 @@ impl<R: Read + Seek> ReadBox<&mut R> for Vp09Box {
             RawBox::<VpccBox>::read_box(reader, header.size)?
         };
 
-        skip_bytes_to(reader, start + size)?;
+        let mut colr = None;
+        let mut pasp = None;
+        let mut clap = None;
+        let btrt = read_btrt_with(reader, start, start + size, |reader, name, s| {
+            Ok(read_colr(reader, name, s, &mut colr)?
+                || read_pasp(reader, name, s, &mut pasp)?
+                || read_clap(reader, name, s, &mut clap)?)
+        })?;
 
         Ok(Self {
             version,
@@ -108,6 +127,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for Vp09Box {
             depth,
             end_code,
             vpcc,
+            btrt,
+            colr,
+            pasp,
+            clap,
         })
     }
 }
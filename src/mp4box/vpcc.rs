@@ -1,11 +1,17 @@
 use crate::mp4box::{
-    box_start, read_box_header_ext, skip_bytes_to, BigEndian, BoxType, Read, ReadBox,
+    box_start, read_box_header_ext, skip_bytes_to, BigEndian, BoxType, Error, Read, ReadBox,
     ReadBytesExt as _, Result, Seek, HEADER_EXT_SIZE, HEADER_SIZE,
 };
 use crate::Mp4Box;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+/// The `VPCodecConfigurationRecord` of VP8 and VP9, see the VP Codec ISO Media File Format
+/// Binding.
+///
+/// Version 0 of the record, from an earlier draft of the binding, has a VP9 color space instead
+/// of code points of ITU-T H.273. It is read into [`Self::matrix_coefficients`], with the color
+/// primaries and transfer characteristics unspecified.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct VpccBox {
     pub version: u8,
     pub flags: u32,
@@ -18,6 +24,10 @@ pub struct VpccBox {
     pub transfer_characteristics: u8,
     pub matrix_coefficients: u8,
     pub codec_initialization_data_size: u16,
+
+    /// Must be empty for VP8 and VP9.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codec_initialization_data: Vec<u8>,
 }
 
 impl VpccBox {
@@ -31,7 +41,7 @@ impl Mp4Box for VpccBox {
     }
 
     fn box_size(&self) -> u64 {
-        HEADER_SIZE + HEADER_EXT_SIZE + 8
+        HEADER_SIZE + HEADER_EXT_SIZE + 8 + self.codec_initialization_data.len() as u64
     }
 
     fn to_json(&self) -> Result<String> {
@@ -50,13 +60,36 @@ impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
 
         let profile: u8 = reader.read_u8()?;
         let level: u8 = reader.read_u8()?;
-        let (bit_depth, chroma_subsampling, video_full_range_flag) = {
+        let b = reader.read_u8()?;
+        let bit_depth = b >> 4;
+        let (
+            chroma_subsampling,
+            video_full_range_flag,
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+        ) = if version == 0 {
+            let color_space = b & 0x0f;
             let b = reader.read_u8()?;
-            (b >> 4, b << 4 >> 5, b & 0x01 == 1)
+            let matrix_coefficients = vp9_color_space_matrix(color_space).unwrap_or(2);
+            (b >> 4, b & 0x01 == 1, 2, 2, matrix_coefficients)
+        } else {
+            (
+                (b >> 1) & 0x07,
+                b & 0x01 == 1,
+                reader.read_u8()?,
+                reader.read_u8()?,
+                reader.read_u8()?,
+            )
         };
-        let transfer_characteristics: u8 = reader.read_u8()?;
-        let matrix_coefficients: u8 = reader.read_u8()?;
         let codec_initialization_data_size: u16 = reader.read_u16::<BigEndian>()?;
+        if reader.stream_position()? + u64::from(codec_initialization_data_size) > start + size {
+            return Err(Error::InvalidData(
+                "vpcC codec initialization data extends past the end of the box",
+            ));
+        }
+        let mut codec_initialization_data = vec![0; codec_initialization_data_size as usize];
+        reader.read_exact(&mut codec_initialization_data)?;
 
         skip_bytes_to(reader, start + size)?;
 
@@ -68,10 +101,24 @@ impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
             bit_depth,
             chroma_subsampling,
             video_full_range_flag,
-            color_primaries: 0,
+            color_primaries,
             transfer_characteristics,
             matrix_coefficients,
             codec_initialization_data_size,
+            codec_initialization_data,
         })
     }
 }
+
+/// Maps the `color_space` of VP9 to the `MatrixCoefficients` of ITU-T H.273.
+pub(crate) fn vp9_color_space_matrix(color_space: u8) -> Option<u8> {
+    match color_space {
+        1 => Some(5), // CS_BT_601
+        2 => Some(1), // CS_BT_709
+        3 => Some(6), // CS_SMPTE_170
+        4 => Some(7), // CS_SMPTE_240
+        5 => Some(9), // CS_BT_2020
+        7 => Some(0), // CS_RGB
+        _ => None,
+    }
+}
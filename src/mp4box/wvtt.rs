@@ -0,0 +1,123 @@
+use byteorder::{BigEndian, ReadBytesExt as _};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::mp4box::btrt::BtrtBox;
+use crate::mp4box::{
+    box_start, read_bytes, skip_bytes_to, BoxHeader, BoxType, Error, Mp4Box, ReadBox, Result,
+    HEADER_SIZE,
+};
+use crate::skipped::{log_skipped, Skipped};
+
+/// Sample entry of WebVTT subtitles, see ISO/IEC 14496-30 section 7.5.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct WvttBox {
+    pub data_reference_index: u16,
+
+    /// The header of the WebVTT file, starting with `WEBVTT`, from the `vttC` box.
+    pub config: String,
+
+    /// Identifies the source of the cues, from the `vlab` box.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub btrt: Option<BtrtBox>,
+}
+
+impl WvttBox {
+    pub fn get_type() -> BoxType {
+        BoxType::WvttBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + HEADER_SIZE + self.config.len() as u64;
+        if let Some(label) = &self.label {
+            size += HEADER_SIZE + label.len() as u64;
+        }
+        if self.btrt.is_some() {
+            size += BtrtBox::get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for WvttBox {
+    fn box_type(&self) -> BoxType {
+        Self::get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).expect("Failed to convert to JSON"))
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("config={:?}", self.config);
+        Ok(s)
+    }
+}
+
+impl<R: Read + Seek> ReadBox<&mut R> for WvttBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        let mut config = None;
+        let mut label = None;
+        let mut btrt = None;
+        loop {
+            let current = reader.stream_position()?;
+            if current + HEADER_SIZE > end {
+                break;
+            }
+            let BoxHeader { name, size: s } = BoxHeader::read(reader)?;
+            if s < HEADER_SIZE || current + s > end {
+                return Err(Error::InvalidData(
+                    "wvtt box contains a box with a larger size than it",
+                ));
+            }
+            match &u32::from(name).to_be_bytes() {
+                b"vttC" if config.is_none() => {
+                    config = Some(read_text(reader, s - HEADER_SIZE)?);
+                }
+                b"vlab" if label.is_none() => {
+                    label = Some(read_text(reader, s - HEADER_SIZE)?);
+                }
+                b"btrt" if btrt.is_none() => {
+                    btrt = Some(BtrtBox::read_box(reader, s)?);
+                }
+                _ => {
+                    log_skipped(&Skipped::Box {
+                        parent: BoxType::WvttBox.into(),
+                        box_type: name.into(),
+                        offset: current,
+                        size: s,
+                    });
+                }
+            }
+            skip_bytes_to(reader, current + s)?;
+        }
+        skip_bytes_to(reader, end)?;
+
+        Ok(Self {
+            data_reference_index,
+            config: config.ok_or(Error::InvalidData("wvtt box without a vttC box"))?,
+            label,
+            btrt,
+        })
+    }
+}
+
+/// Reads `len` bytes of UTF-8 text that fill the rest of a box.
+fn read_text<R: Read>(reader: &mut R, len: u64) -> Result<String> {
+    let bytes = read_bytes(reader, len)?;
+    String::from_utf8(bytes).map_err(|_err| Error::InvalidData("invalid utf8"))
+}
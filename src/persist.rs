@@ -0,0 +1,171 @@
+//! Persisting a parsed [`crate::Mp4`] with serde.
+//!
+//! The boxes leave their sample tables out when serialized, to keep [`crate::Mp4Box::to_json`]
+//! short. [`SampleTables`] carries them alongside, so that a deserialized [`crate::Mp4`] is
+//! complete.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BoxNode, Chapter, CttsEntry, ElstEntry, EmsgBox, ExtraBox, FtypBox, Limits, MetaBox, MoofBox,
+    MoovBox, StscEntry, SttsEntry, Track, TrackId,
+};
+
+/// The serialized form of an [`crate::Mp4`].
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Mp4Repr<'a> {
+    pub ftyp: Cow<'a, FtypBox>,
+
+    #[serde(default)]
+    pub styps: Cow<'a, [FtypBox]>,
+
+    pub moov: Cow<'a, MoovBox>,
+    pub moofs: Cow<'a, [MoofBox]>,
+    pub emsgs: Cow<'a, [EmsgBox]>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Cow<'a, MetaBox>>,
+
+    pub mdats: Cow<'a, [(u64, u64)]>,
+    pub tracks: Cow<'a, BTreeMap<TrackId, Track>>,
+    pub box_tree: Cow<'a, [BoxNode]>,
+
+    #[serde(default)]
+    pub top_level_extras: Cow<'a, [ExtraBox]>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_chapters: Option<Cow<'a, [Chapter]>>,
+
+    pub limits: Limits,
+    pub sample_tables: SampleTables<'a>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<bytes::Bytes>,
+}
+
+/// The fields of the boxes in `moov` and `moofs` that are not serialized with the boxes.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SampleTables<'a> {
+    /// One per `trak`, in order.
+    traks: Vec<TrakTables<'a>>,
+
+    /// One per `trun` of every `traf` of every `moof`, in order.
+    truns: Vec<Vec<Vec<TrunTables<'a>>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TrakTables<'a> {
+    elst: Option<Cow<'a, [ElstEntry]>>,
+    stts: Cow<'a, [SttsEntry]>,
+    stsc: Cow<'a, [StscEntry]>,
+    stsz: Cow<'a, [u32]>,
+    ctts: Option<Cow<'a, [CttsEntry]>>,
+    stss: Option<Cow<'a, [u32]>>,
+    stco: Option<Cow<'a, [u32]>>,
+    co64: Option<Cow<'a, [u64]>>,
+
+    #[serde(default)]
+    sdtp: Option<Cow<'a, [u8]>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TrunTables<'a> {
+    sample_durations: Cow<'a, [u32]>,
+    sample_sizes: Cow<'a, [u32]>,
+    sample_flags: Cow<'a, [u32]>,
+    sample_cts: Cow<'a, [u32]>,
+}
+
+impl<'a> SampleTables<'a> {
+    pub fn of(moov: &'a MoovBox, moofs: &'a [MoofBox]) -> Self {
+        let traks = moov
+            .traks
+            .iter()
+            .map(|trak| {
+                let stbl = &trak.mdia.minf.stbl;
+                TrakTables {
+                    elst: trak
+                        .edts
+                        .as_ref()
+                        .and_then(|edts| edts.elst.as_ref())
+                        .map(|elst| Cow::Borrowed(elst.entries.as_slice())),
+                    stts: Cow::Borrowed(&stbl.stts.entries),
+                    stsc: Cow::Borrowed(&stbl.stsc.entries),
+                    stsz: Cow::Borrowed(&stbl.stsz.sample_sizes),
+                    ctts: stbl.ctts.as_ref().map(|ctts| Cow::Borrowed(&*ctts.entries)),
+                    stss: stbl.stss.as_ref().map(|stss| Cow::Borrowed(&*stss.entries)),
+                    stco: stbl.stco.as_ref().map(|stco| Cow::Borrowed(&*stco.entries)),
+                    co64: stbl.co64.as_ref().map(|co64| Cow::Borrowed(&*co64.entries)),
+                    sdtp: stbl.sdtp.as_ref().map(|sdtp| Cow::Borrowed(&*sdtp.entries)),
+                }
+            })
+            .collect();
+
+        let truns = moofs
+            .iter()
+            .map(|moof| {
+                moof.trafs
+                    .iter()
+                    .map(|traf| {
+                        traf.truns
+                            .iter()
+                            .map(|trun| TrunTables {
+                                sample_durations: Cow::Borrowed(&trun.sample_durations),
+                                sample_sizes: Cow::Borrowed(&trun.sample_sizes),
+                                sample_flags: Cow::Borrowed(&trun.sample_flags),
+                                sample_cts: Cow::Borrowed(&trun.sample_cts),
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { traks, truns }
+    }
+
+    /// Puts the tables back into the boxes they were taken from.
+    pub fn restore(self, moov: &mut MoovBox, moofs: &mut [MoofBox]) {
+        for (trak, tables) in moov.traks.iter_mut().zip(self.traks) {
+            if let (Some(elst), Some(entries)) = (
+                trak.edts.as_mut().and_then(|edts| edts.elst.as_mut()),
+                tables.elst,
+            ) {
+                elst.entries = entries.into_owned();
+            }
+            let stbl = &mut trak.mdia.minf.stbl;
+            stbl.stts.entries = tables.stts.into_owned();
+            stbl.stsc.entries = tables.stsc.into_owned();
+            stbl.stsz.sample_sizes = tables.stsz.into_owned();
+            if let (Some(ctts), Some(entries)) = (&mut stbl.ctts, tables.ctts) {
+                ctts.entries = entries.into_owned();
+            }
+            if let (Some(stss), Some(entries)) = (&mut stbl.stss, tables.stss) {
+                stss.entries = entries.into_owned();
+            }
+            if let (Some(stco), Some(entries)) = (&mut stbl.stco, tables.stco) {
+                stco.entries = entries.into_owned();
+            }
+            if let (Some(co64), Some(entries)) = (&mut stbl.co64, tables.co64) {
+                co64.entries = entries.into_owned();
+            }
+            if let (Some(sdtp), Some(entries)) = (&mut stbl.sdtp, tables.sdtp) {
+                sdtp.entries = entries.into_owned();
+            }
+        }
+
+        for (moof, trafs) in moofs.iter_mut().zip(self.truns) {
+            for (traf, truns) in moof.trafs.iter_mut().zip(trafs) {
+                for (trun, tables) in traf.truns.iter_mut().zip(truns) {
+                    trun.sample_durations = tables.sample_durations.into_owned();
+                    trun.sample_sizes = tables.sample_sizes.into_owned();
+                    trun.sample_flags = tables.sample_flags.into_owned();
+                    trun.sample_cts = tables.sample_cts.into_owned();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Estimating how a file plays while it downloads, see [`Mp4::progressive_download`].
+
+use std::time::Duration;
+
+use crate::{BoxType, FourCC, Mp4, Sample, TrackId};
+
+/// How a file plays while it is downloaded from start to end at a fixed bandwidth, see
+/// [`Mp4::progressive_download`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressiveDownload {
+    /// Whether `moov` comes before the data of all samples. Otherwise no sample can be played
+    /// until `moov` arrives, usually only once the whole file has been downloaded.
+    pub moov_before_media: bool,
+
+    /// How long to buffer before starting playback, so that every sample arrives before it
+    /// has to be decoded.
+    pub startup_delay: Duration,
+
+    /// How long downloading the whole file takes.
+    pub download_time: Duration,
+
+    /// The track and [`crate::Sample::id`] of the sample that arrives the latest compared to
+    /// when it is decoded, which determines [`Self::startup_delay`], or `None` if there are no
+    /// samples.
+    pub critical_sample: Option<(TrackId, u32)>,
+}
+
+impl ProgressiveDownload {
+    /// Whether playback can start before the download ends.
+    ///
+    /// This is the case if `moov` comes first and the tracks are interleaved closely enough
+    /// that no track has to wait for data of another one further into the file.
+    pub fn is_progressive(&self) -> bool {
+        self.moov_before_media && self.startup_delay < self.download_time
+    }
+}
+
+impl Mp4 {
+    /// Estimates how the file plays while it downloads at `bytes_per_second`, for checking
+    /// that a file is fit for publishing on the web.
+    ///
+    /// The file is taken to arrive in order at a constant rate, and to play at normal speed
+    /// once [`ProgressiveDownload::startup_delay`] has passed, with the samples of all tracks
+    /// decoded at their [`crate::Sample::decode_timestamp`]. A sample can only be decoded once
+    /// all of its bytes and `moov` have arrived. Movie fragments precede their media data, so
+    /// they don't delay samples any further.
+    ///
+    /// Where `moov` is and the data of the samples is only known if the [`Mp4`] was read from a
+    /// file.
+    pub fn progressive_download(&self, bytes_per_second: u64) -> ProgressiveDownload {
+        let moov_type = FourCC::from(BoxType::MoovBox);
+        let top_level = self.box_tree();
+        let moov_end = top_level
+            .iter()
+            .find(|node| node.box_type == moov_type)
+            .map_or(0, |node| node.offset + node.size);
+        let samples: Vec<(TrackId, &Sample)> = self
+            .tracks()
+            .iter()
+            .flat_map(|(&track_id, track)| {
+                track.samples.iter().map(move |sample| (track_id, sample))
+            })
+            .collect();
+        let file_size = top_level
+            .last()
+            .map(|node| node.offset + node.size)
+            .into_iter()
+            .chain(samples.iter().map(|(_, sample)| sample.file_range().end))
+            .max()
+            .unwrap_or(0);
+        let moov_before_media = samples.iter().all(|(_, sample)| sample.offset >= moov_end);
+
+        let seconds = |bytes: u64| bytes as f64 / bytes_per_second.max(1) as f64;
+        let duration =
+            |seconds: f64| Duration::try_from_secs_f64(seconds.max(0.0)).unwrap_or(Duration::MAX);
+        let first_decode_time = samples
+            .iter()
+            .map(|(_, sample)| sample.decode_time())
+            .fold(f64::INFINITY, f64::min);
+
+        // Playback has to wait for the sample whose bytes arrive the latest compared to when
+        // it is due.
+        let mut startup_delay = 0.0;
+        let mut critical_sample = None;
+        for (track_id, sample) in &samples {
+            let arrival = seconds(sample.file_range().end.max(moov_end));
+            let delay = arrival - (sample.decode_time() - first_decode_time);
+            if critical_sample.is_none() || delay > startup_delay {
+                startup_delay = delay;
+                critical_sample = Some((*track_id, sample.id));
+            }
+        }
+
+        ProgressiveDownload {
+            moov_before_media,
+            startup_delay: duration(startup_delay),
+            download_time: duration(seconds(file_size)),
+            critical_sample,
+        }
+    }
+}
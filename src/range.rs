@@ -0,0 +1,153 @@
+//! Parsing files that are fetched in pieces, e.g. with HTTP range requests from object storage.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{BoxType, Bytes, Error, Limits, Mp4, Result, Sample};
+
+/// A source of byte ranges of a file, see [`Mp4::read_with_ranges`].
+pub trait RangeReader {
+    /// Fetches the `len` bytes starting at `offset`.
+    ///
+    /// Ranges are always within the size of the file passed to [`Mp4::read_with_ranges`].
+    fn fetch(&mut self, offset: u64, len: u64) -> Result<Bytes>;
+}
+
+impl<R: RangeReader + ?Sized> RangeReader for &mut R {
+    fn fetch(&mut self, offset: u64, len: u64) -> Result<Bytes> {
+        (**self).fetch(offset, len)
+    }
+}
+
+/// Top-level boxes whose contents are not needed to parse a file.
+const SKIPPED_BOXES: [BoxType; 4] = [
+    BoxType::MdatBox,
+    BoxType::FreeBox,
+    BoxType::SkipBox,
+    BoxType::WideBox,
+];
+
+/// Largest header of a top-level box: size, type, and a 64-bit size.
+const MAX_HEADER_SIZE: u64 = 16;
+
+pub(crate) fn read_with_ranges<R: RangeReader>(
+    reader: &mut R,
+    size: u64,
+    limits: Limits,
+) -> Result<Mp4> {
+    let mut fetched = SparseFile::new(size);
+
+    let mut offset = 0;
+    while offset < size {
+        let header = fetched.fetch(reader, offset, MAX_HEADER_SIZE.min(size - offset))?;
+        if header.len() < 8 {
+            return Err(Error::InvalidData("file ends within a box header"));
+        }
+        let box_type = BoxType::from(u32::from_be_bytes([
+            header[4], header[5], header[6], header[7],
+        ]));
+        let box_size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            0 => size - offset,
+            1 if header.len() == 16 => u64::from_be_bytes([
+                header[8], header[9], header[10], header[11], header[12], header[13], header[14],
+                header[15],
+            ]),
+            1 => return Err(Error::InvalidData("file ends within a box header")),
+            box_size => u64::from(box_size),
+        };
+        if box_size < 8 || box_size > size - offset {
+            // Left for the parser to report.
+            break;
+        }
+
+        if !SKIPPED_BOXES.contains(&box_type) {
+            fetched.fetch(reader, offset, box_size)?;
+        }
+        offset += box_size;
+    }
+
+    Mp4::read_with_limits(fetched, size, limits)
+}
+
+/// The ranges of a file that have been fetched so far, readable as if it was the whole file.
+///
+/// Reading outside of them fails, so the parser can't silently read garbage.
+struct SparseFile {
+    /// Fetched ranges by their offset.
+    ranges: BTreeMap<u64, Bytes>,
+    size: u64,
+    position: u64,
+}
+
+impl SparseFile {
+    fn new(size: u64) -> Self {
+        Self {
+            ranges: BTreeMap::new(),
+            size,
+            position: 0,
+        }
+    }
+
+    /// Fetches a range, which either starts a new range or extends the one at `offset`.
+    fn fetch<R: RangeReader>(&mut self, reader: &mut R, offset: u64, len: u64) -> Result<Bytes> {
+        if let Some(bytes) = self.ranges.get(&offset) {
+            if bytes.len() as u64 >= len {
+                return Ok(bytes.slice(..len as usize));
+            }
+        }
+        let bytes = reader.fetch(offset, len)?;
+        if bytes.len() as u64 != len {
+            return Err(Error::InvalidData(
+                "range reader returned a different number of bytes than requested",
+            ));
+        }
+        self.ranges.insert(offset, bytes.clone());
+        Ok(bytes)
+    }
+}
+
+impl Read for SparseFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.position >= self.size {
+            return Ok(0);
+        }
+        let range = self
+            .ranges
+            .range(..=self.position)
+            .next_back()
+            .map(|(&start, bytes)| (start, bytes))
+            .filter(|(start, bytes)| self.position < start + bytes.len() as u64);
+        let Some((start, bytes)) = range else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "reading a byte range that was not fetched",
+            ));
+        };
+        let available = &bytes[(self.position - start) as usize..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for SparseFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek position")
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl Sample {
+    /// Fetches the data of this sample, e.g. from a file parsed with [`Mp4::read_with_ranges`].
+    pub fn fetch<R: RangeReader>(&self, reader: &mut R) -> Result<Bytes> {
+        reader.fetch(self.offset, self.size)
+    }
+}
@@ -1,18 +1,283 @@
+use std::borrow::Cow;
+use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, SeekFrom};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::box_tree::{read_box_tree, BoxNode};
+use crate::chapters::{self, Chapter};
+use crate::codec::OBJECT_TYPES_AAC;
+use crate::color::{self, ColorReport};
+use crate::compat::{codec_config_compatible, CompatReport};
+use crate::fragment::{
+    traf_samples, FragmentDefaults, FragmentDuration, FragmentInfo, TrackFragmentStats,
+};
+use crate::heif::{self, ImageItem};
+use crate::json::{self, JsonOptions};
+use crate::level::{self, DecoderRequirements, LevelViolation};
+use crate::persist::{Mp4Repr, SampleTables};
+use crate::range::{self, RangeReader};
+use crate::skipped::log_skipped;
+use crate::sync_samples;
+use crate::trace::span;
 use crate::{
-    skip_box, BoxHeader, BoxType, EmsgBox, Error, FtypBox, MoofBox, MoovBox, ReadBox as _, Result,
-    StblBox, StsdBoxContent, TfhdBox, TrackId, TrackKind, TrakBox, TrunBox,
+    skip_box, skip_bytes_to, AudioObjectType, BoxHeader, BoxType, Bytes, DataEntry, EmsgBox, Error,
+    ExternalDataRef, FileKind, FourCC, FtypBox, IlstBox, Language, MetaBox, Metadata, MoofBox,
+    MoovBox, ReadBox, Result, SampleFlags, SampleFreqIndex, Skipped, StblBox, StsdBoxContent,
+    StssBox, TfhdBox, TkhdFlags, TrackId, TrackKind, TrakBox, VideoGeometry, HEADER_SIZE,
 };
 
 #[derive(Debug)]
 pub struct Mp4 {
     pub ftyp: FtypBox,
+
+    /// The segment types (`styp`) of the media segments in the file, in file order.
+    pub styps: Vec<FtypBox>,
+
     pub moov: MoovBox,
     pub moofs: Vec<MoofBox>,
     pub emsgs: Vec<EmsgBox>,
+
+    /// The file-level `meta` box, which HEIF images keep their items in.
+    pub meta: Option<MetaBox>,
+
+    /// Offset and length of the contents of every `mdat` box, in file order.
+    ///
+    /// Empty if the [`Mp4`] was not read from a file.
+    pub mdats: Vec<(u64, u64)>,
+
     tracks: BTreeMap<TrackId, Track>,
+
+    /// The boxes in the file, see [`Self::box_tree`].
+    box_tree: Vec<BoxNode>,
+
+    /// See [`Self::top_level_extras`].
+    top_level_extras: Vec<ExtraBox>,
+
+    /// The chapters of the chapter track, if there is one and it was read, see [`Self::chapters`].
+    track_chapters: Option<Vec<Chapter>>,
+
+    /// The input buffer, if it was read with [`Self::read_shared`].
+    data: Option<Bytes>,
+
+    limits: Limits,
+}
+
+/// An [`Mp4`] serializes to everything that was parsed, including the sample tables that the
+/// boxes leave out on their own and the buffer it was read from, if any (see
+/// [`Mp4::take_data`]). It deserializes without having to parse the file again.
+impl Serialize for Mp4 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Mp4Repr {
+            ftyp: Cow::Borrowed(&self.ftyp),
+            styps: Cow::Borrowed(&self.styps),
+            moov: Cow::Borrowed(&self.moov),
+            moofs: Cow::Borrowed(&self.moofs),
+            emsgs: Cow::Borrowed(&self.emsgs),
+            meta: self.meta.as_ref().map(Cow::Borrowed),
+            mdats: Cow::Borrowed(&self.mdats),
+            tracks: Cow::Borrowed(&self.tracks),
+            box_tree: Cow::Borrowed(&self.box_tree),
+            top_level_extras: Cow::Borrowed(&self.top_level_extras),
+            track_chapters: self.track_chapters.as_deref().map(Cow::Borrowed),
+            limits: self.limits,
+            sample_tables: SampleTables::of(&self.moov, &self.moofs),
+            data: self.data.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mp4 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = Mp4Repr::deserialize(deserializer)?;
+        let mut moov = repr.moov.into_owned();
+        let mut moofs = repr.moofs.into_owned();
+        repr.sample_tables.restore(&mut moov, &mut moofs);
+        Ok(Self {
+            ftyp: repr.ftyp.into_owned(),
+            styps: repr.styps.into_owned(),
+            moov,
+            moofs,
+            emsgs: repr.emsgs.into_owned(),
+            meta: repr.meta.map(Cow::into_owned),
+            mdats: repr.mdats.into_owned(),
+            tracks: repr.tracks.into_owned(),
+            box_tree: repr.box_tree.into_owned(),
+            top_level_extras: repr.top_level_extras.into_owned(),
+            track_chapters: repr.track_chapters.map(Cow::into_owned),
+            data: repr.data,
+            limits: repr.limits,
+        })
+    }
+}
+
+/// Limits on what a file may contain, to protect against resource exhaustion when parsing
+/// untrusted files, see [`Mp4::read_with_limits`].
+///
+/// Files that exceed a limit fail to parse with [`Error::LimitExceeded`], which names the limit.
+/// Start from [`Self::UNLIMITED`] and set the limits that matter:
+///
+/// ```
+/// let limits = re_mp4::Limits::UNLIMITED
+///     .with_max_tracks(16)
+///     .with_max_box_depth(32);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Maximum number of tracks.
+    pub max_tracks: usize,
+
+    /// Maximum number of samples, summed over all tracks.
+    ///
+    /// Sample tables can declare billions of samples in a few bytes.
+    pub max_total_samples: u64,
+
+    /// Maximum number of samples of any one track.
+    pub max_samples_per_track: u64,
+
+    /// Maximum nesting depth of boxes, where top-level boxes like `moov` are at depth 1.
+    ///
+    /// Every level of nesting takes only 8 bytes, and is visited recursively.
+    pub max_box_depth: usize,
+
+    /// Maximum size in bytes of all sample tables, like `stsz` and `trun`, summed over the file.
+    ///
+    /// The entries of these tables are held in memory, so this bounds the memory that a file
+    /// takes beyond its samples, checked before any table is read.
+    pub max_table_bytes: u64,
+
+    /// Whether track fragments of a track that has no `trak` in `moov` get a track of their own,
+    /// rather than failing with [`Error::TrakNotFound`].
+    ///
+    /// Some broken muxers write such fragments, as do streams whose segments are served apart
+    /// from their init segment. The track is made up from the `trex` and `tfhd` defaults: it has
+    /// the timescale of `mvhd`, no sample entry and no [`Track::trak`], but its samples can still
+    /// be read.
+    pub synthesize_missing_tracks: bool,
+}
+
+impl Limits {
+    /// No limits at all, which is the default.
+    pub const UNLIMITED: Self = Self {
+        max_tracks: usize::MAX,
+        max_total_samples: u64::MAX,
+        max_samples_per_track: u64::MAX,
+        max_box_depth: usize::MAX,
+        max_table_bytes: u64::MAX,
+        synthesize_missing_tracks: false,
+    };
+
+    /// Sets [`Self::max_tracks`].
+    pub const fn with_max_tracks(mut self, max_tracks: usize) -> Self {
+        self.max_tracks = max_tracks;
+        self
+    }
+
+    /// Sets [`Self::max_total_samples`].
+    pub const fn with_max_total_samples(mut self, max_total_samples: u64) -> Self {
+        self.max_total_samples = max_total_samples;
+        self
+    }
+
+    /// Sets [`Self::max_samples_per_track`].
+    pub const fn with_max_samples_per_track(mut self, max_samples_per_track: u64) -> Self {
+        self.max_samples_per_track = max_samples_per_track;
+        self
+    }
+
+    /// Sets [`Self::max_box_depth`].
+    pub const fn with_max_box_depth(mut self, max_box_depth: usize) -> Self {
+        self.max_box_depth = max_box_depth;
+        self
+    }
+
+    /// Sets [`Self::max_table_bytes`].
+    pub const fn with_max_table_bytes(mut self, max_table_bytes: u64) -> Self {
+        self.max_table_bytes = max_table_bytes;
+        self
+    }
+
+    /// Sets [`Self::synthesize_missing_tracks`].
+    pub const fn with_synthesize_missing_tracks(mut self, synthesize_missing_tracks: bool) -> Self {
+        self.synthesize_missing_tracks = synthesize_missing_tracks;
+        self
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// A box that this crate doesn't parse, such as a vendor box of a camera or a file-level
+/// `udta`, see [`Mp4::top_level_extras`], [`crate::MoovBox::extras`] and
+/// [`crate::TrakBox::extras`].
+///
+/// These are kept so that they can be written back out unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtraBox {
+    pub box_type: FourCC,
+
+    /// The extended type of a `uuid` box, which follows its header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<[u8; 16]>,
+
+    /// Offset of the box header in the file.
+    pub offset: u64,
+
+    /// Size of the box, including its header.
+    pub size: u64,
+
+    /// The contents of the box after its header and [`Self::user_type`], if it is no larger
+    /// than [`ExtraBox::MAX_PAYLOAD_SIZE`].
+    pub payload: Option<Vec<u8>>,
+}
+
+impl ExtraBox {
+    /// Contents of larger boxes are not kept, since they are likely media data.
+    pub const MAX_PAYLOAD_SIZE: u64 = 1024 * 1024;
+
+    /// Reads the box of type `box_type` and `size` bytes that starts at `offset`, whose header
+    /// `reader` just read.
+    pub(crate) fn read<R: Read + Seek>(
+        reader: &mut R,
+        box_type: BoxType,
+        offset: u64,
+        size: u64,
+    ) -> Result<Self> {
+        let box_type = FourCC::from(box_type);
+        let end = offset.saturating_add(size);
+        let user_type =
+            if &box_type.value == b"uuid" && end.saturating_sub(reader.stream_position()?) >= 16 {
+                let mut user_type = [0; 16];
+                reader.read_exact(&mut user_type)?;
+                Some(user_type)
+            } else {
+                None
+            };
+
+        let payload_size = end.saturating_sub(reader.stream_position()?);
+        let payload = if payload_size <= Self::MAX_PAYLOAD_SIZE {
+            let mut payload = vec![0; payload_size as usize];
+            reader.read_exact(&mut payload)?;
+            Some(payload)
+        } else {
+            None
+        };
+        skip_bytes_to(reader, end)?;
+
+        Ok(Self {
+            box_type,
+            user_type,
+            offset,
+            size,
+            payload,
+        })
+    }
 }
 
 impl Mp4 {
@@ -24,6 +289,16 @@ impl Mp4 {
         Ok(mp4)
     }
 
+    /// Parses the contents of a shared buffer as MP4 data, and keeps a reference to it.
+    ///
+    /// Unlike with [`Self::read_bytes`], [`Sample::bytes`] can then return the data of samples
+    /// as cheap slices of `bytes`, without copying them.
+    pub fn read_shared(bytes: Bytes) -> Result<Self> {
+        let mut mp4 = Self::read_bytes(&bytes)?;
+        mp4.data = Some(bytes);
+        Ok(mp4)
+    }
+
     /// Reads the contents of a file as MP4 data, and returns both the parsed MP4 and its raw data.
     ///
     /// Sample ranges returned by the resulting [`Mp4`] should be used with the same input buffer.
@@ -33,95 +308,653 @@ impl Mp4 {
         Ok((Self::read_bytes(&bytes)?, bytes))
     }
 
-    pub fn read<R: Read + Seek>(mut reader: R, size: u64) -> Result<Self> {
-        let start = reader.stream_position()?;
+    /// Maps a file into memory and parses it as MP4 data, and returns both the parsed MP4 and the mapping.
+    ///
+    /// Unlike [`Self::read_file`], the file is not copied into memory up front: only the boxes
+    /// that are parsed are read, and sample ranges can be used with the mapping directly.
+    ///
+    /// The file must not be modified while it is mapped, or the contents of the mapping
+    /// change underneath the parsed MP4 and its users.
+    #[cfg(all(feature = "mmap", not(target_family = "wasm")))]
+    pub fn read_mmap(file_path: impl AsRef<std::path::Path>) -> Result<(Self, memmap2::Mmap)> {
+        let file = std::fs::File::open(file_path)?;
+
+        #[expect(unsafe_code)]
+        // SAFETY: the mapping is only read from. As with any memory-mapped file, modifications
+        // by other processes are visible through it, which is documented above.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        Ok((Self::read_bytes(&mmap)?, mmap))
+    }
 
-        let mut ftyp = None;
-        let mut moov = None;
-        let mut moofs = Vec::new();
-        let mut moof_offsets = Vec::new();
-        let mut emsgs = Vec::new();
+    pub fn read<R: Read + Seek>(reader: R, size: u64) -> Result<Self> {
+        Self::read_with_limits(reader, size, Limits::UNLIMITED)
+    }
 
-        let mut current = start;
-        while current < size {
-            // Get box header.
-            let header = BoxHeader::read(&mut reader)?;
-            let BoxHeader { name, size: s } = header;
-            if s > size {
-                return Err(Error::InvalidData(
-                    "file contains a box with a larger size than it",
-                ));
-            }
+    /// Parses a file of `size` bytes that is fetched in pieces, e.g. with HTTP range requests.
+    ///
+    /// Only the headers of the top-level boxes and the contents of the boxes that are parsed
+    /// (`ftyp`, `moov`, `moof`, …) are fetched, not the media data. Fetch the data of samples
+    /// on demand with [`Sample::fetch`].
+    pub fn read_with_ranges<R: RangeReader>(reader: &mut R, size: u64) -> Result<Self> {
+        range::read_with_ranges(reader, size, Limits::UNLIMITED)
+    }
 
-            // Break if size zero BoxHeader, which can result in dead-loop.
-            if s == 0 {
-                break;
+    /// Parses MP4 data from a reader that can't seek, such as a pipe or the body of an HTTP response.
+    ///
+    /// The top-level boxes are buffered up to the header of the first `mdat`, where reading stops,
+    /// so `reader` is left at the start of the sample data. Pass `&mut reader` to go on reading
+    /// the samples from it. This only works for progressive files in which `moov` comes before
+    /// `mdat`, and fails with [`Error::InvalidData`] for others. Of a fragmented file, only the
+    /// fragments before the first `mdat` are read.
+    pub fn read_stream<R: Read>(mut reader: R) -> Result<Self> {
+        let mut buffer = Vec::new();
+        let mut has_moov = false;
+        let size = loop {
+            let box_start = buffer.len() as u64;
+            let mut header = [0; 8];
+            if !read_exact_or_eof(&mut reader, &mut header)? {
+                break box_start;
             }
+            buffer.extend_from_slice(&header);
+
+            let name = BoxType::from(u32::from_be_bytes([
+                header[4], header[5], header[6], header[7],
+            ]));
+            let (size, header_size) =
+                match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                    1 => {
+                        let mut largesize = [0; 8];
+                        reader.read_exact(&mut largesize)?;
+                        buffer.extend_from_slice(&largesize);
+                        (u64::from_be_bytes(largesize), 16)
+                    }
+                    size => (u64::from(size), 8),
+                };
 
-            // Match and parse the atom boxes.
-            match name {
-                BoxType::FtypBox => {
-                    ftyp = Some(FtypBox::read_box(&mut reader, s)?);
-                }
-                BoxType::FreeBox | BoxType::MdatBox => {
-                    skip_box(&mut reader, s)?;
-                }
-                BoxType::MoovBox => {
-                    moov = Some(MoovBox::read_box(&mut reader, s)?);
+            if name == BoxType::MdatBox {
+                if !has_moov {
+                    return Err(Error::InvalidData(
+                        "moov must come before mdat to read a stream",
+                    ));
                 }
-                BoxType::MoofBox => {
-                    let moof_offset = reader.stream_position()? - 8;
-                    let moof = MoofBox::read_box(&mut reader, s)?;
-                    moofs.push(moof);
-                    moof_offsets.push(moof_offset);
-                }
-                BoxType::EmsgBox => {
-                    let emsg = EmsgBox::read_box(&mut reader, s)?;
-                    emsgs.push(emsg);
-                }
-                _ => {
-                    // XXX warn!()
-                    skip_box(&mut reader, s)?;
+                if size == 0 {
+                    // Without the size of `mdat`, there is no telling whether samples are missing.
+                    let size = buffer.len() as u64;
+                    return Self::read_with_missing_data(
+                        std::io::Cursor::new(buffer),
+                        size,
+                        Limits::UNLIMITED,
+                    );
                 }
+                break box_start + size;
+            }
+            if name == BoxType::MoovBox || name == BoxType::MetaBox {
+                has_moov = true;
+            }
+
+            if size == 0 {
+                // The box extends to the end of the stream.
+                reader.read_to_end(&mut buffer)?;
+                break buffer.len() as u64;
+            }
+            let Some(contents_size) = size.checked_sub(header_size) else {
+                return Err(Error::InvalidData("box size too small"));
+            };
+            let read = (&mut reader).take(contents_size).read_to_end(&mut buffer)?;
+            if read as u64 != contents_size {
+                return Err(Error::InvalidData("stream ends within a box"));
             }
-            current = reader.stream_position()?;
+        };
+
+        // The contents of `mdat` are beyond the end of the buffer, but they are never read.
+        Self::read(std::io::Cursor::new(buffer), size)
+    }
+
+    /// Like [`Self::read`], but fails with [`Error::LimitExceeded`] if the file exceeds `limits`.
+    ///
+    /// Fails with [`Error::MediaDataMissing`] if the file has samples, but none of them are within
+    /// its `size` bytes, see [`Self::read_with_missing_data`].
+    pub fn read_with_limits<R: Read + Seek>(reader: R, size: u64, limits: Limits) -> Result<Self> {
+        let this = Self::read_with_missing_data(reader, size, limits)?;
+        if let Some(expected_bytes) = this.missing_media_data(size) {
+            return Err(Error::MediaDataMissing { expected_bytes });
         }
+        Ok(this)
+    }
+
+    /// Like [`Self::read_with_limits`], but doesn't fail with [`Error::MediaDataMissing`].
+    ///
+    /// Meant for recovering files whose `moov` was written out separately from their media data:
+    /// the samples can still be read from the companion file that holds the `mdat`, as long as the
+    /// offsets in the sample tables match it, e.g. with [`crate::DataSources::new`] on that file.
+    pub fn read_with_missing_data<R: Read + Seek>(
+        mut reader: R,
+        size: u64,
+        limits: Limits,
+    ) -> Result<Self> {
+        let TopLevelBoxes {
+            ftyp,
+            styps,
+            moov,
+            moofs,
+            emsgs,
+            meta,
+            mdats,
+            box_tree,
+            top_level_extras,
+        } = read_top_level_boxes(&mut reader, size, limits)?;
 
         let Some(ftyp) = ftyp else {
             return Err(Error::BoxNotFound(BoxType::FtypBox));
         };
-        let Some(moov) = moov else {
-            return Err(Error::BoxNotFound(BoxType::MoovBox));
+        // HEIF images have no tracks, only the items in `meta`.
+        let moov = match (moov, &meta) {
+            (Some(moov), _) => moov,
+            (None, Some(_)) => MoovBox::default(),
+            (None, None) => return Err(Error::BoxNotFound(BoxType::MoovBox)),
         };
 
         let mut this = Self {
+            styps,
+            emsgs,
+            meta,
+            mdats,
+            box_tree,
+            top_level_extras,
+            limits,
+            ..Self::new(ftyp, moov, moofs)
+        };
+        this.rebuild_tracks()?;
+        this.track_chapters = chapters::read_track_chapters(&this, &mut reader);
+
+        Ok(this)
+    }
+
+    /// Parses a standalone media segment of `size` bytes, such as a segment of a DASH or HLS
+    /// stream, with the tracks of this [`Mp4`], which is its init segment.
+    ///
+    /// Media segments start with `styp` and maybe `sidx`, followed by movie fragments, but have
+    /// no `moov`. The returned [`Mp4`] has the `ftyp` and `moov` of the init segment and only the
+    /// samples of the segment, whose offsets are within the segment. The segment is read with the
+    /// [`Limits`] of the init segment.
+    ///
+    /// Fails with [`Error::InvalidData`] if the segment has its own `moov`, in which case it is a
+    /// whole file that can be read with [`Self::read`].
+    pub fn read_media_segment<R: Read + Seek>(&self, mut reader: R, size: u64) -> Result<Self> {
+        let TopLevelBoxes {
             ftyp,
+            styps,
             moov,
             moofs,
             emsgs,
-            tracks: Default::default(),
+            meta,
+            mdats,
+            box_tree,
+            top_level_extras,
+        } = read_top_level_boxes(&mut reader, size, self.limits)?;
+        if moov.is_some() {
+            return Err(Error::InvalidData("media segment contains a moov box"));
+        }
+
+        // The sample tables of the init segment refer to its own media data, if any.
+        let mut init_moov = self.moov.clone();
+        for trak in &mut init_moov.traks {
+            let stbl = &mut trak.mdia.minf.stbl;
+            stbl.stts = Default::default();
+            stbl.ctts = None;
+            stbl.stss = None;
+            stbl.stsc = Default::default();
+            stbl.stsz = Default::default();
+            stbl.stco = Some(Default::default());
+            stbl.co64 = None;
+        }
+
+        let mut this = Self {
+            styps,
+            emsgs,
+            meta,
+            mdats,
+            box_tree,
+            top_level_extras,
+            limits: self.limits,
+            ..Self::new(ftyp.unwrap_or_else(|| self.ftyp.clone()), init_moov, moofs)
         };
+        this.rebuild_tracks()?;
 
-        let mut tracks = this.build_tracks();
-        this.update_sample_list(&mut tracks)?;
-        this.tracks = tracks;
-        this.update_tracks();
+        Ok(this)
+    }
 
+    /// The size that a file needs to hold the samples, if it has samples but none of them are
+    /// within its first `size` bytes. Samples stored in other files are not taken into account.
+    fn missing_media_data(&self, size: u64) -> Option<u64> {
+        let mut expected_bytes = None;
+        for track in self.tracks.values() {
+            if track.has_external_data(self) {
+                continue;
+            }
+            for sample in &track.samples {
+                let end = sample.offset.saturating_add(sample.size);
+                if end <= size {
+                    return None;
+                }
+                expected_bytes = expected_bytes.max(Some(end));
+            }
+        }
+        expected_bytes
+    }
+
+    /// Assembles an [`Mp4`] from already parsed or programmatically constructed boxes.
+    ///
+    /// The sample lists of all tracks are derived from the sample tables in `moov`
+    /// and the track fragments in `moofs`, exactly as if the boxes had been read from a file.
+    /// [`MoofBox::start`] must hold the offset the fragment would have in the file,
+    /// since data offsets in fragments are relative to it.
+    pub fn from_parts(ftyp: FtypBox, moov: MoovBox, moofs: Vec<MoofBox>) -> Result<Self> {
+        let mut this = Self::new(ftyp, moov, moofs);
+        this.rebuild_tracks()?;
         Ok(this)
     }
 
+    /// An [`Mp4`] without any tracks yet, see [`Self::rebuild_tracks`].
+    fn new(ftyp: FtypBox, moov: MoovBox, moofs: Vec<MoofBox>) -> Self {
+        Self {
+            ftyp,
+            styps: Vec::new(),
+            moov,
+            moofs,
+            emsgs: Vec::new(),
+            meta: None,
+            mdats: Vec::new(),
+            tracks: Default::default(),
+            box_tree: Vec::new(),
+            top_level_extras: Vec::new(),
+            track_chapters: None,
+            data: None,
+            limits: Limits::UNLIMITED,
+        }
+    }
+
+    /// The top-level boxes of the file, with the boxes nested in them.
+    ///
+    /// This covers every box in the file, including those this crate doesn't parse, with their
+    /// offsets and sizes. Empty if the [`Mp4`] was not read from a file.
+    pub fn box_tree(&self) -> &[BoxNode] {
+        &self.box_tree
+    }
+
+    /// The chapters of the file, in order, for showing a chapter menu.
+    ///
+    /// They come from the chapter track that a track refers to with `tref/chap`, as written by
+    /// Apple software, or else from the Nero chapter list (`chpl`) in `moov/udta`. The titles of
+    /// a chapter track are only known if the [`Mp4`] was read from a reader that holds the media
+    /// data, unlike [`Self::read_stream`] and [`Self::read_with_ranges`].
+    pub fn chapters(&self) -> Vec<Chapter> {
+        self.track_chapters
+            .clone()
+            .unwrap_or_else(|| chapters::chpl_chapters(self))
+    }
+
+    /// The top-level boxes that this crate doesn't parse, in file order, such as the vendor
+    /// boxes some cameras write next to `moov`.
+    ///
+    /// Space boxes (`free`, `skip` and `wide`) are left out. Empty if the [`Mp4`] was not read
+    /// from a file.
+    pub fn top_level_extras(&self) -> &[ExtraBox] {
+        &self.top_level_extras
+    }
+
+    /// The whole parsed file as one JSON document: the box tree, the parsed boxes, and a
+    /// summary of every track.
+    ///
+    /// Object keys are sorted, so the output is stable and suitable for snapshot tests.
+    pub fn to_json(&self, options: JsonOptions) -> String {
+        json::to_json(self, options)
+    }
+
+    /// Adds an `stss` box with the [`Track::detected_sync_samples`] to every AVC and HEVC track
+    /// that lacks one, so that its samples are no longer all treated as sync samples.
+    ///
+    /// `reader` must be the file this [`Mp4`] was parsed from. Tracks whose samples are stored in
+    /// another file or in movie fragments are skipped, as are tracks in which every sample, or
+    /// none at all, was detected as a sync sample. Returns the IDs of the tracks that were changed.
+    pub fn add_missing_stss<R: Read + Seek>(&mut self, reader: &mut R) -> Result<Vec<TrackId>> {
+        let mut changed = Vec::new();
+        for track in self.tracks.values() {
+            let Some(trak) = track.trak(self) else {
+                continue;
+            };
+            let stbl = &trak.mdia.minf.stbl;
+            if stbl.stss.is_some()
+                || stbl.stsz.count() != track.samples.len()
+                || track.has_external_data(self)
+            {
+                continue;
+            }
+            let Some(entries) = track.detected_sync_samples(self, reader)? else {
+                continue;
+            };
+            if !entries.is_empty() && entries.len() < track.samples.len() {
+                changed.push((track.track_id, entries));
+            }
+        }
+
+        for (track_id, entries) in &mut changed {
+            if let Some(trak) = self
+                .moov
+                .traks
+                .iter_mut()
+                .find(|trak| trak.tkhd.track_id == *track_id)
+            {
+                trak.mdia.minf.stbl.stss = Some(StssBox {
+                    entries: std::mem::take(entries),
+                    ..Default::default()
+                });
+            }
+        }
+        if !changed.is_empty() {
+            self.rebuild_tracks()?;
+        }
+        Ok(changed.into_iter().map(|(track_id, _)| track_id).collect())
+    }
+
+    /// Removes the buffer the file was read from with [`Self::read_shared`], e.g. so that it is
+    /// left out when serializing this [`Mp4`].
+    pub fn take_data(&mut self) -> Option<Bytes> {
+        self.data.take()
+    }
+
+    /// Sets the buffer the file was read from, e.g. after deserializing an [`Mp4`] that was
+    /// serialized without it, so that [`Sample::bytes`] works again.
+    pub fn set_data(&mut self, data: Bytes) {
+        self.data = Some(data);
+    }
+
+    /// Recomputes the sample lists of all tracks from `moov` and `moofs`.
+    ///
+    /// Call this after modifying the boxes of an [`Mp4`] to bring [`Self::tracks`] up to date.
+    pub fn rebuild_tracks(&mut self) -> Result<()> {
+        let mut tracks = self.build_tracks()?;
+        self.update_sample_list(&mut tracks)?;
+        self.tracks = tracks;
+        self.update_tracks();
+        Ok(())
+    }
+
+    /// The items of a HEIF or AVIF file, such as its images, in the order of `iinf`.
+    ///
+    /// Empty if the file has no file-level `meta` box with items.
+    pub fn image_items(&self) -> Result<Vec<ImageItem>> {
+        heif::image_items(self)
+    }
+
+    /// The primary item of a HEIF or AVIF file, which is the image to show.
+    pub fn primary_image_item(&self) -> Result<Option<ImageItem>> {
+        let Some(meta) = &self.meta else {
+            return Ok(None);
+        };
+        let Some(item_id) = heif::primary_item_id(meta)? else {
+            return Ok(None);
+        };
+        Ok(self
+            .image_items()?
+            .into_iter()
+            .find(|item| item.item_id == item_id))
+    }
+
+    /// What kind of file this is, going by the brands in `ftyp`.
+    ///
+    /// Files without a brand that tells are classified by their contents: files with a `meta`
+    /// box but no tracks are HEIF images, and files with only audio tracks are audio files.
+    pub fn file_kind(&self) -> FileKind {
+        FileKind::from_brand(self.ftyp.major_brand)
+            .or_else(|| {
+                self.ftyp
+                    .compatible_brands
+                    .iter()
+                    .find_map(|&brand| FileKind::from_brand(brand))
+            })
+            .unwrap_or_else(|| {
+                if self.tracks.is_empty() && self.meta.is_some() {
+                    FileKind::Heif
+                } else if !self.tracks.is_empty()
+                    && self
+                        .tracks
+                        .values()
+                        .all(|track| track.kind == Some(TrackKind::Audio))
+                {
+                    FileKind::Audio
+                } else {
+                    FileKind::Video
+                }
+            })
+    }
+
+    /// The movie fragments of a fragmented file, in file order.
+    pub fn fragments(&self) -> impl Iterator<Item = FragmentInfo<'_>> {
+        let data_limit = self.data_limit();
+        self.moofs.iter().map(move |moof| FragmentInfo {
+            moov: &self.moov,
+            moof,
+            data_limit,
+        })
+    }
+
+    /// The end of the last top-level box, past which no sample data can be, or `None` if the
+    /// [`Mp4`] was not read from a file.
+    fn data_limit(&self) -> Option<u64> {
+        self.box_tree
+            .iter()
+            .map(|node| node.offset.saturating_add(node.size))
+            .max()
+    }
+
+    /// The duration of the whole movie that a fragmented file declares in `mehd`, along with the
+    /// duration of its tracks to check it against.
+    ///
+    /// Returns `None` if there is no `mehd`, or it declares a duration of zero, which muxers
+    /// write while the duration is not yet known.
+    pub fn fragment_duration(&self) -> Option<FragmentDuration> {
+        let declared = self.moov.mvex.as_ref()?.mehd.as_ref()?.fragment_duration;
+        if declared == 0 {
+            return None;
+        }
+        let movie_timescale = u128::from(self.moov.mvhd.timescale);
+        let actual = self
+            .tracks
+            .values()
+            .map(|track| {
+                let timescale = u128::from(track.timescale.max(1));
+                let duration =
+                    (u128::from(track.duration) * movie_timescale + timescale / 2) / timescale;
+                u64::try_from(duration).unwrap_or(u64::MAX)
+            })
+            .max()
+            .unwrap_or_default();
+        Some(FragmentDuration { declared, actual })
+    }
+
     pub fn tracks(&self) -> &BTreeMap<TrackId, Track> {
         &self.tracks
     }
 
+    /// The samples of all tracks, merged into one timeline in `order`.
+    ///
+    /// Timestamps of tracks with different timescales are compared exactly. Samples with the
+    /// same time keep their order within their track, and across tracks come in order of track
+    /// ID, so the result is deterministic.
+    pub fn interleaved_samples(
+        &self,
+        order: SampleOrder,
+    ) -> impl Iterator<Item = (TrackId, &Sample)> {
+        let time = |sample: &Sample| match order {
+            SampleOrder::Decode => sample.decode_timestamp,
+            SampleOrder::Presentation => sample.composition_timestamp,
+        };
+        let mut samples: Vec<(TrackId, &Sample)> = self
+            .tracks
+            .iter()
+            .flat_map(|(&track_id, track)| {
+                track.samples.iter().map(move |sample| (track_id, sample))
+            })
+            .collect();
+        // Stable, so ties keep the order they were collected in.
+        samples.sort_by(|(_, a), (_, b)| {
+            let a_time = i128::from(time(a)) * i128::from(b.timescale.max(1));
+            let b_time = i128::from(time(b)) * i128::from(a.timescale.max(1));
+            a_time.cmp(&b_time)
+        });
+        samples.into_iter()
+    }
+
+    /// The tracks that are enabled in their track header, see [`Track::is_enabled`].
+    ///
+    /// Players treat disabled tracks as if they were not there, e.g. the alternatives of a
+    /// track that are only played when selected.
+    pub fn enabled_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.values().filter(|track| track.is_enabled(self))
+    }
+
+    /// The [`Self::enabled_tracks`] that are video tracks.
+    pub fn enabled_video_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.enabled_tracks()
+            .filter(|track| track.kind == Some(TrackKind::Video))
+    }
+
+    /// Removes all tracks for which `keep` returns `false`, along with their `trak`, `trex` and `traf` boxes.
+    ///
+    /// The samples of the remaining tracks still refer to the original file.
+    /// Use [`crate::remux::write`] to produce a new file that only contains the remaining tracks.
+    pub fn retain_tracks(&mut self, mut keep: impl FnMut(&Track) -> bool) {
+        self.tracks.retain(|_, track| keep(track));
+
+        let tracks = &self.tracks;
+        self.moov
+            .traks
+            .retain(|trak| tracks.contains_key(&trak.tkhd.track_id));
+        if let Some(mvex) = &mut self.moov.mvex {
+            mvex.trexs
+                .retain(|trex| tracks.contains_key(&trex.track_id));
+        }
+        for moof in &mut self.moofs {
+            moof.trafs
+                .retain(|traf| tracks.contains_key(&traf.tfhd.track_id));
+        }
+    }
+
+    /// Movie-level metadata (title, year, cover art, …).
+    ///
+    /// This merges the item lists found in `moov.udta.meta` and `moov.meta`,
+    /// preferring `udta` when both specify the same item.
+    /// If neither is present, all accessors return `None`.
+    pub fn metadata(&self) -> impl Metadata<'_> + '_ {
+        MovieMetadata {
+            ilsts: self.metadata_ilsts(),
+        }
+    }
+
+    /// The XMP packet of the file, an XML document with metadata as written by Adobe tools and
+    /// some cameras.
+    ///
+    /// It is looked up in a top-level `uuid` box with the XMP user type, the `XMP_` box of
+    /// `moov.udta` that `QuickTime` files use, and a `uuid` box in `moov`, in that order. Returns
+    /// `None` if there is none, or it is larger than [`ExtraBox::MAX_PAYLOAD_SIZE`].
+    pub fn xmp(&self) -> Option<&[u8]> {
+        let is_xmp = |extra: &&ExtraBox| {
+            extra.user_type == Some(XMP_USER_TYPE) || &extra.box_type.value == b"XMP_"
+        };
+        let udta_extras = self.moov.udta.iter().flat_map(|udta| &udta.extras);
+        self.top_level_extras
+            .iter()
+            .chain(udta_extras)
+            .chain(&self.moov.extras)
+            .find(is_xmp)?
+            .payload
+            .as_deref()
+    }
+
+    /// The `ID3v2` tag of the file, from an `ID32` box, as the raw tag starting with `ID3`.
+    ///
+    /// It is looked up in the `meta` boxes at the top level, in `moov` and in `moov.udta`, and
+    /// directly in `moov.udta`, in that order. Returns `None` if there is none.
+    pub fn id3(&self) -> Option<&[u8]> {
+        let id32 = BoxType::from(u32::from_be_bytes(*b"ID32"));
+        let udta = self.moov.udta.as_ref();
+        let in_meta = [
+            self.meta.as_ref(),
+            self.moov.meta.as_ref(),
+            udta.and_then(|udta| udta.meta.as_ref()),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|meta| meta.child_data(id32));
+        let in_udta = || {
+            udta?
+                .extras
+                .iter()
+                .find(|extra| extra.box_type == FourCC::from(id32))?
+                .payload
+                .as_deref()
+        };
+        // Skip the version, flags and language of the `ID32` box.
+        in_meta.or_else(in_udta)?.get(6..)
+    }
+
+    /// The item lists in `moov.udta.meta` and `moov.meta`, in order of precedence.
+    fn metadata_ilsts(&self) -> [Option<&IlstBox>; 2] {
+        [
+            self.moov
+                .udta
+                .as_ref()
+                .and_then(|udta| udta.meta.as_ref())
+                .and_then(|meta| meta.ilst()),
+            self.moov.meta.as_ref().and_then(|meta| meta.ilst()),
+        ]
+    }
+
     /// Process each `trak` box to obtain a list of samples for each track.
     ///
     /// Note that the list will be incomplete if the file is fragmented.
-    fn build_tracks(&self) -> BTreeMap<TrackId, Track> {
+    fn build_tracks(&self) -> Result<BTreeMap<TrackId, Track>> {
+        if self.moov.traks.len() > self.limits.max_tracks {
+            return Err(Error::LimitExceeded(
+                "max_tracks",
+                self.limits.max_tracks as u64,
+            ));
+        }
+        // Checked up front, since the sample tables may declare far more samples than they hold.
+        let max_track_samples = self
+            .moov
+            .traks
+            .iter()
+            .map(|trak| trak.mdia.minf.stbl.stsz.count() as u64)
+            .max()
+            .unwrap_or_default();
+        if max_track_samples > self.limits.max_samples_per_track {
+            return Err(Error::LimitExceeded(
+                "max_samples_per_track",
+                self.limits.max_samples_per_track,
+            ));
+        }
+        let total_samples: u64 = self
+            .moov
+            .traks
+            .iter()
+            .map(|trak| trak.mdia.minf.stbl.stsz.count() as u64)
+            .sum();
+        if total_samples > self.limits.max_total_samples {
+            return Err(Error::LimitExceeded(
+                "max_total_samples",
+                self.limits.max_total_samples,
+            ));
+        }
+
         let mut tracks = BTreeMap::new();
 
         // load samples from traks
         for trak in &self.moov.traks {
+            span!("build_trak", track_id = trak.tkhd.track_id);
+
             let mut sample_n = 0usize;
             let mut chunk_index = 1u64;
             let mut chunk_run_index = 0usize;
@@ -148,14 +981,20 @@ impl Mp4 {
 
             let mut samples = Vec::<Sample>::new();
 
-            fn get_sample_chunk_offset(stbl: &StblBox, chunk_index: u64) -> u64 {
-                if let Some(stco) = &stbl.stco {
-                    stco.entries[chunk_index as usize - 1] as u64
+            fn get_sample_chunk_offset(
+                stbl: &StblBox,
+                track_id: TrackId,
+                chunk_index: u64,
+            ) -> Result<u64> {
+                let index = (chunk_index as usize).wrapping_sub(1);
+                let offset = if let Some(stco) = &stbl.stco {
+                    stco.entries.get(index).map(|&offset| offset as u64)
                 } else if let Some(co64) = &stbl.co64 {
-                    co64.entries[chunk_index as usize - 1]
+                    co64.entries.get(index).copied()
                 } else {
-                    panic!()
-                }
+                    return Err(Error::BoxInStblNotFound(track_id, BoxType::StcoBox));
+                };
+                offset.ok_or(Error::InvalidData("sample in a chunk past the end of stco"))
             }
 
             let stbl = &trak.mdia.minf.stbl;
@@ -163,79 +1002,100 @@ impl Mp4 {
             let stsz = &stbl.stsz;
             let stts = &stbl.stts;
 
-            // Could probably just always use sample count
-            while (sample_n < stsz.sample_sizes.len() && stsz.sample_size == 0)
-                || sample_n < stsz.sample_count as usize
-            {
+            // `QuickTime` files of uncompressed audio may give every frame a size of 1 byte.
+            let min_sample_size = match &stbl.stsd.contents {
+                StsdBoxContent::Pcm(pcm) if stsz.sample_size != 0 => pcm.format().bytes_per_frame(),
+                _ => 0,
+            };
+
+            let sample_count = described_sample_count(stbl, trak.tkhd.track_id)?;
+            while sample_n < sample_count {
                 // compute offset
                 if sample_n == 0 {
                     chunk_index = 1;
                     chunk_run_index = 0;
-                    last_sample_in_chunk = stsc.entries[chunk_run_index].samples_per_chunk as u64;
+                    last_sample_in_chunk = stsc
+                        .entries
+                        .get(chunk_run_index)
+                        .ok_or(Error::InvalidData("stsc has no entries"))?
+                        .samples_per_chunk as u64;
                     offset_in_chunk = 0;
 
                     if chunk_run_index + 1 < stsc.entries.len() {
-                        last_chunk_in_run =
-                            stsc.entries[chunk_run_index + 1].first_chunk as u64 - 1;
+                        last_chunk_in_run = (stsc.entries[chunk_run_index + 1].first_chunk as u64)
+                            .saturating_sub(1);
                     } else {
                         last_chunk_in_run = u64::MAX;
                     }
-                } else if sample_n < last_sample_in_chunk as usize {
-                    /* ... */
-                } else {
+                }
+                // Loops to skip chunks without samples.
+                while sample_n as u64 >= last_sample_in_chunk {
                     chunk_index += 1;
                     offset_in_chunk = 0;
                     if chunk_index > last_chunk_in_run {
                         chunk_run_index += 1;
                         if chunk_run_index + 1 < stsc.entries.len() {
-                            last_chunk_in_run =
-                                stsc.entries[chunk_run_index + 1].first_chunk as u64 - 1;
+                            last_chunk_in_run = (stsc.entries[chunk_run_index + 1].first_chunk
+                                as u64)
+                                .saturating_sub(1);
                         } else {
                             last_chunk_in_run = u64::MAX;
                         }
                     }
 
-                    last_sample_in_chunk += stsc.entries[chunk_run_index].samples_per_chunk as u64;
+                    last_sample_in_chunk += stsc
+                        .entries
+                        .get(chunk_run_index)
+                        .ok_or(Error::InvalidData("stsc first chunks are not increasing"))?
+                        .samples_per_chunk as u64;
                 }
 
                 // compute timestamp, duration, is_sync
                 let sample_n_i64 = i64::try_from(sample_n).unwrap_or(i64::MAX);
-                if sample_n_i64 > last_sample_in_stts_run {
+                while sample_n_i64 > last_sample_in_stts_run {
                     stts_run_index += 1;
                     if last_sample_in_stts_run < 0 {
                         last_sample_in_stts_run = 0;
                     }
-                    last_sample_in_stts_run +=
-                        stts.entries[stts_run_index as usize].sample_count as i64;
+                    last_sample_in_stts_run += stts
+                        .entries
+                        .get(stts_run_index as usize)
+                        .ok_or(Error::InvalidData("stts has fewer samples than stsz"))?
+                        .sample_count as i64;
                 }
 
                 let timescale = trak.mdia.mdhd.timescale as u64;
-                let size = if stsz.sample_size == 0 {
-                    stsz.sample_sizes[sample_n] as u64
-                } else {
-                    stsz.sample_size as u64
-                };
-                let offset = get_sample_chunk_offset(stbl, chunk_index) + offset_in_chunk;
-                offset_in_chunk += size;
+                let size = u64::from(
+                    stsz.sample_size(sample_n)
+                        .ok_or(Error::InvalidData("stsz has fewer sizes than samples"))?,
+                )
+                .max(min_sample_size);
+                let offset = get_sample_chunk_offset(stbl, trak.tkhd.track_id, chunk_index)?
+                    .saturating_add(offset_in_chunk);
+                offset_in_chunk = offset_in_chunk.saturating_add(size);
 
                 let decode_timestamp = if sample_n > 0 {
                     samples[sample_n - 1].duration =
                         stts.entries[stts_run_index as usize].sample_delta as u64;
 
-                    samples[sample_n - 1].decode_timestamp
-                        + samples[sample_n - 1].duration.cast_signed()
+                    samples[sample_n - 1]
+                        .decode_timestamp
+                        .saturating_add(samples[sample_n - 1].duration.cast_signed())
                 } else {
                     0
                 };
 
                 let composition_timestamp = if let Some(ctts) = &stbl.ctts {
-                    if sample_n_i64 >= last_sample_in_ctts_run {
+                    while sample_n_i64 >= last_sample_in_ctts_run {
                         ctts_run_index += 1;
                         if last_sample_in_ctts_run < 0 {
                             last_sample_in_ctts_run = 0;
                         }
-                        last_sample_in_ctts_run +=
-                            ctts.entries[ctts_run_index as usize].sample_count as i64;
+                        last_sample_in_ctts_run += ctts
+                            .entries
+                            .get(ctts_run_index as usize)
+                            .ok_or(Error::InvalidData("ctts has fewer samples than stsz"))?
+                            .sample_count as i64;
                     }
 
                     // dts shift is determined by the smallest negative sample offset:
@@ -245,7 +1105,7 @@ impl Mp4 {
                         dts_shift = dts_shift.max(-offset);
                     }
 
-                    decode_timestamp + offset
+                    decode_timestamp.saturating_add(offset)
                 } else {
                     decode_timestamp
                 };
@@ -253,7 +1113,7 @@ impl Mp4 {
 
                 let is_sync = if let Some(stss) = &stbl.stss {
                     if last_stss_index < stss.entries.len()
-                        && sample_n == stss.entries[last_stss_index] as usize - 1
+                        && sample_n + 1 == stss.entries[last_stss_index] as usize
                     {
                         last_stss_index += 1;
                         true
@@ -264,29 +1124,66 @@ impl Mp4 {
                     true
                 };
 
+                // `sdtp` has the same dependency fields as the sample flags of fragments.
+                let flags = stbl
+                    .sdtp
+                    .as_ref()
+                    .and_then(|sdtp| sdtp.sample_flags(sample_n))
+                    .unwrap_or_default();
+
                 samples.push(Sample {
                     id: samples.len() as u32,
                     timescale,
                     size,
                     offset,
                     decode_timestamp,
+                    media_decode_timestamp: 0, // filled once all timestamps are known
                     composition_timestamp,
                     is_sync,
+                    flags: SampleFlags {
+                        is_non_sync: !is_sync,
+                        ..flags
+                    },
                     duration: 0, // filled once we know next sample timestamp
                 });
                 sample_n += 1;
             }
 
+            // There is no next sample, so the last sample lasts until the end of the media.
+            // Files with fragments may leave the duration at zero, in which case the last `stts` delta is used.
             if let Some(last_sample) = samples.last_mut() {
-                last_sample.duration =
-                    trak.mdia.mdhd.duration - last_sample.decode_timestamp as u64;
+                last_sample.duration = trak
+                    .mdia
+                    .mdhd
+                    .duration
+                    .checked_sub(last_sample.decode_timestamp as u64)
+                    .filter(|&duration| duration > 0)
+                    .or_else(|| {
+                        stts.entries
+                            .last()
+                            .map(|entry| u64::from(entry.sample_delta))
+                    })
+                    .unwrap_or_default();
+            }
+
+            if distribute_zero_durations(&mut samples) {
+                min_composition_timestamp = samples
+                    .iter()
+                    .map(|sample| sample.composition_timestamp)
+                    .min()
+                    .unwrap_or(i64::MAX);
+            }
+
+            // The timestamps are not shifted yet, and start at zero.
+            for sample in &mut samples {
+                sample.media_decode_timestamp = sample.decode_timestamp.cast_unsigned();
             }
 
             // Fixup all DTS by the dts shift if there's one.
             // https://github.com/FFmpeg/FFmpeg/blob/455db6fe109cf905fe518ea2690495948937438f/libavformat/mov.c#L4271
             if dts_shift > 0 {
                 for sample in &mut samples {
-                    sample.decode_timestamp -= dts_shift;
+                    sample.decode_timestamp = sample.decode_timestamp.saturating_sub(dts_shift);
                 }
             }
 
@@ -294,8 +1191,12 @@ impl Mp4 {
             // For details, see declaration of `min_composition_timestamp` above.
             if min_composition_timestamp != 0 {
                 for sample in &mut samples {
-                    sample.decode_timestamp -= min_composition_timestamp;
-                    sample.composition_timestamp -= min_composition_timestamp;
+                    sample.decode_timestamp = sample
+                        .decode_timestamp
+                        .saturating_sub(min_composition_timestamp);
+                    sample.composition_timestamp = sample
+                        .composition_timestamp
+                        .saturating_sub(min_composition_timestamp);
                 }
             }
 
@@ -308,148 +1209,165 @@ impl Mp4 {
                     first_traf_merged: false,
                     timescale: trak.mdia.mdhd.timescale as u64,
                     duration: trak.mdia.mdhd.duration,
-                    kind: trak.mdia.minf.stbl.stsd.kind(),
+                    // Timed metadata tracks have many different sample entries, like `gpmd`
+                    // for GoPro telemetry, but share the handler.
+                    kind: trak.mdia.minf.stbl.stsd.kind().or_else(|| {
+                        (trak.mdia.hdlr.handler_type.value == *b"meta")
+                            .then_some(TrackKind::Metadata)
+                    }),
+                    sample_entry: trak.mdia.minf.stbl.stsd.contents.clone(),
                     samples,
                 },
             );
         }
 
-        tracks
+        Ok(tracks)
     }
 
     /// In case the input file is fragmented, it will contain one or more `moof` boxes,
     /// which must be processed to obtain the full list of samples for each track.
     fn update_sample_list(&self, tracks: &mut BTreeMap<TrackId, Track>) -> Result<()> {
-        let mut last_run_position = 0;
+        // Durations of `tfhd` fragments flagged as empty that precede the next sample of each track.
+        let mut empty_durations: BTreeMap<TrackId, u64> = BTreeMap::new();
+
+        let mut total_samples: u64 = tracks.values().map(|t| t.samples.len() as u64).sum();
+        let data_limit = self.data_limit();
+
+        // Tracks without a `trak` count towards `max_tracks` too, see `synthesize_missing_tracks`.
+        let mut track_count = tracks.len();
+
+        // The `moof` of every track fragment that has been merged so far, by track, `tfdt` and
+        // range of sample data. Of duplicate fragments, the first in the file is kept.
+        let mut merged_trafs: BTreeMap<(TrackId, Option<u64>, u64, u64), u64> = BTreeMap::new();
 
         for moof in &self.moofs {
+            span!("process_moof", offset = moof.start);
+
+            // The end of the data of the previous track fragment of the `moof`, see `traf_samples`.
+            let mut data_end = moof.start;
+
             // process moof to update sample list
             for traf in &moof.trafs {
                 let track_id = traf.tfhd.track_id;
-                let track = tracks
-                    .get_mut(&track_id)
-                    .ok_or(Error::TrakNotFound(track_id))?;
-                let trak = self
-                    .moov
-                    .traks
-                    .iter()
-                    .find(|trak| trak.tkhd.track_id == track_id)
-                    .ok_or(Error::TrakNotFound(track_id))?;
-                let trex = if let Some(mvex) = &self.moov.mvex {
-                    mvex.trexs
-                        .iter()
-                        .find(|trex| trex.track_id == track_id)
-                        .ok_or(Error::BoxInTrafNotFound(track_id, BoxType::TrexBox))?
-                        .clone()
-                } else {
-                    Default::default()
+                let track = match tracks.entry(track_id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) if self.limits.synthesize_missing_tracks => {
+                        if track_count >= self.limits.max_tracks {
+                            return Err(Error::LimitExceeded(
+                                "max_tracks",
+                                self.limits.max_tracks as u64,
+                            ));
+                        }
+                        track_count += 1;
+                        entry.insert(Track::synthesized(track_id, self.moov.mvhd.timescale))
+                    }
+                    Entry::Vacant(_) => return Err(Error::TrakNotFound(track_id)),
                 };
-
-                let default_sample_duration = traf
-                    .tfhd
-                    .default_sample_duration
-                    .unwrap_or(trex.default_sample_duration);
-                let default_sample_size = traf
-                    .tfhd
-                    .default_sample_size
-                    .unwrap_or(trex.default_sample_size);
-                let default_sample_flags = traf
-                    .tfhd
-                    .default_sample_flags
-                    .unwrap_or(trex.default_sample_flags);
-
-                for (traf_idx, trun) in traf.truns.iter().enumerate() {
-                    for sample_n in 0..trun.sample_count as usize {
-                        let mut sample_flags = default_sample_flags;
-                        if trun.flags & TrunBox::FLAG_SAMPLE_FLAGS != 0 {
-                            sample_flags = trun
-                                .sample_flags
-                                .get(sample_n)
-                                .copied()
-                                .unwrap_or(sample_flags);
-                        } else if sample_n == 0
-                            && (trun.flags & TrunBox::FLAG_FIRST_SAMPLE_FLAGS != 0)
-                        {
-                            sample_flags = trun.first_sample_flags.unwrap_or(sample_flags);
+                let trex = match &self.moov.mvex {
+                    Some(mvex) => {
+                        let trex = mvex.trexs.iter().find(|trex| trex.track_id == track_id);
+                        if trex.is_none() && !self.limits.synthesize_missing_tracks {
+                            return Err(Error::BoxInTrafNotFound(track_id, BoxType::TrexBox));
                         }
+                        trex
+                    }
+                    None => None,
+                };
 
-                        let decode_timestamp = if track.first_traf_merged || sample_n > 0 {
-                            let prev = &track.samples[track.samples.len() - 1];
-                            prev.decode_timestamp + prev.duration.cast_signed()
-                        } else {
-                            track.first_traf_merged = true;
-                            traf.tfdt
-                                .as_ref()
-                                .map_or(0, |tfdt| tfdt.base_media_decode_time.cast_signed())
-                        };
+                if traf.tfhd.flags & TfhdBox::FLAG_DURATION_IS_EMPTY != 0 {
+                    // There are no samples for the duration of this fragment,
+                    // so the next sample of the track starts that much later.
+                    let default_sample_duration = FragmentDefaults::resolve(&traf.tfhd, trex)
+                        .sample_duration
+                        .map_or(0, |d| d.value);
+                    let empty_duration = empty_durations.entry(track_id).or_default();
+                    *empty_duration =
+                        empty_duration.saturating_add(u64::from(default_sample_duration));
+                    continue;
+                }
 
-                        let composition_timestamp = if trun.flags & TrunBox::FLAG_SAMPLE_CTS != 0 {
-                            decode_timestamp
-                                + trun.sample_cts.get(sample_n).copied().unwrap_or(0) as i64
-                        } else {
-                            decode_timestamp
-                        };
+                // Checked before the samples are resolved, since `trun` may declare far more
+                // samples than it holds.
+                let sample_count: u64 = traf
+                    .truns
+                    .iter()
+                    .map(|trun| u64::from(trun.sample_count))
+                    .sum();
+                total_samples += sample_count;
+                if total_samples > self.limits.max_total_samples {
+                    return Err(Error::LimitExceeded(
+                        "max_total_samples",
+                        self.limits.max_total_samples,
+                    ));
+                }
+                if track.samples.len() as u64 + sample_count > self.limits.max_samples_per_track {
+                    return Err(Error::LimitExceeded(
+                        "max_samples_per_track",
+                        self.limits.max_samples_per_track,
+                    ));
+                }
 
-                        let duration = trun
-                            .sample_durations
-                            .get(sample_n)
-                            .copied()
-                            .unwrap_or(default_sample_duration)
-                            as u64;
-
-                        let base_data_offset_present =
-                            traf.tfhd.flags & TfhdBox::FLAG_BASE_DATA_OFFSET != 0;
-                        let default_base_is_moof =
-                            traf.tfhd.flags & TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF != 0;
-                        let data_offset_present = trun.flags & TrunBox::FLAG_DATA_OFFSET != 0;
-                        let base_data_offset = if !base_data_offset_present {
-                            if !default_base_is_moof {
-                                if sample_n == 0 {
-                                    // the first sample in the track fragment
-                                    moof.start // the position of the first byte of the enclosing Movie Fragment Box
-                                } else {
-                                    last_run_position // the offset of the previous sample
-                                }
-                            } else {
-                                moof.start
-                            }
+                let first_traf_sample = track.samples.len();
+                for sample in traf_samples(moof, traf, trex, data_limit, &mut data_end)? {
+                    let (decode_timestamp, media_decode_timestamp) =
+                        if track.first_traf_merged || track.samples.len() > first_traf_sample {
+                            let prev = &track.samples[track.samples.len() - 1];
+                            let elapsed = prev
+                                .duration
+                                .saturating_add(empty_durations.remove(&track_id).unwrap_or(0));
+                            (
+                                prev.decode_timestamp.saturating_add_unsigned(elapsed),
+                                prev.media_decode_timestamp.saturating_add(elapsed),
+                            )
                         } else {
-                            traf.tfhd.base_data_offset.unwrap_or(moof.start)
+                            track.first_traf_merged = true;
+                            let empty_duration = empty_durations.remove(&track_id).unwrap_or(0);
+                            let start = sample.decode_time.unwrap_or(empty_duration);
+                            (i64::try_from(start).unwrap_or(i64::MAX), start)
                         };
 
-                        let sample_size =
-                            trun.sample_sizes
-                                .get(sample_n)
-                                .copied()
-                                .unwrap_or(default_sample_size) as u64;
-
-                        // Sample offset in bytes. (Must be positive, otherwise this would be outside of the file.)
-                        let sample_offset = if traf_idx == 0 && sample_n == 0 {
-                            if data_offset_present {
-                                base_data_offset
-                                    .saturating_add_signed(trun.data_offset.unwrap_or(0) as i64)
-                            } else {
-                                base_data_offset
-                            }
-                        } else {
-                            last_run_position
-                        };
+                    // Composition offsets are signed in version 1 of `trun`, and like FFmpeg
+                    // we read them as signed in version 0 too, where muxers write negative
+                    // offsets all the same.
+                    let composition_timestamp =
+                        decode_timestamp.saturating_add(i64::from(sample.composition_offset));
+
+                    track.samples.push(Sample {
+                        id: track.samples.len() as u32,
+                        is_sync: sample.is_sync(),
+                        flags: sample.flags,
+                        size: sample.size,
+                        offset: sample.offset,
+                        timescale: track.timescale,
+                        decode_timestamp,
+                        media_decode_timestamp,
+                        composition_timestamp,
+                        duration: sample.duration,
+                    });
+                }
 
-                        last_run_position = sample_offset + sample_size;
-
-                        track.samples.push(Sample {
-                            id: track.samples.len() as u32,
-                            // Bit 16 of the 32-bit sample flags is `sample_is_non_sync_sample`
-                            // (ISO/IEC 14496-12 §8.8.3.1), so a sync sample (keyframe) is one
-                            // where that bit is *clear*.
-                            is_sync: (sample_flags >> 16) & 0x1 == 0,
-                            size: sample_size,
-                            offset: sample_offset,
-                            timescale: trak.mdia.mdhd.timescale as u64,
-                            decode_timestamp,
-                            composition_timestamp,
-                            duration,
-                        });
+                let traf_samples = &track.samples[first_traf_sample..];
+                if let (Some(start), Some(end)) = (
+                    traf_samples.iter().map(|sample| sample.offset).min(),
+                    traf_samples
+                        .iter()
+                        .map(|sample| sample.offset.saturating_add(sample.size))
+                        .max(),
+                ) {
+                    let tfdt = traf.tfdt.as_ref().map(|tfdt| tfdt.base_media_decode_time);
+                    match merged_trafs.entry((track_id, tfdt, start, end)) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(moof.start);
+                        }
+                        Entry::Occupied(entry) => {
+                            total_samples -= traf_samples.len() as u64;
+                            track.samples.truncate(first_traf_sample);
+                            log_skipped(&Skipped::DuplicateFragment {
+                                track_id,
+                                offset: moof.start,
+                                original_offset: *entry.get(),
+                            });
+                        }
                     }
                 }
             }
@@ -461,17 +1379,22 @@ impl Mp4 {
     /// Update track metadata after all samples have been read
     fn update_tracks(&mut self) {
         for track in self.tracks.values_mut() {
-            if track.duration == 0 {
-                track.duration = track
+            // The duration in `mdhd` only covers the samples in `moov`, so it must be extended by
+            // the samples in fragments.
+            if track.duration == 0 || track.first_traf_merged {
+                let end = track
                     .samples
-                    .last()
+                    .iter()
                     .map(|v| v.duration.saturating_add_signed(v.composition_timestamp))
+                    .max()
                     .unwrap_or_default();
+                track.duration = track.duration.max(end);
             }
         }
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Track {
     /// Internal field used when decoding a fragmented MP4 file.
     first_traf_merged: bool,
@@ -491,48 +1414,556 @@ pub struct Track {
 
     pub kind: Option<TrackKind>,
 
+    /// The sample entry of the track, see [`Self::sample_entry`].
+    #[serde(default)]
+    sample_entry: StsdBoxContent,
+
     /// List of samples in the track.
     pub samples: Vec<Sample>,
 }
 
 impl Track {
-    pub fn trak<'a>(&self, mp4: &'a Mp4) -> &'a TrakBox {
-        let Some(trak) = mp4
-            .moov
+    /// A track for the track fragments of `track_id`, which has no `trak`, see
+    /// [`Limits::synthesize_missing_tracks`].
+    fn synthesized(track_id: TrackId, movie_timescale: u32) -> Self {
+        Self {
+            first_traf_merged: false,
+            width: 0,
+            height: 0,
+            track_id,
+            timescale: u64::from(movie_timescale.max(1)),
+            duration: 0,
+            kind: None,
+            sample_entry: StsdBoxContent::default(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// The `trak` box of this track in `mp4`, or `None` if it was removed from `mp4.moov`.
+    pub fn trak<'a>(&self, mp4: &'a Mp4) -> Option<&'a TrakBox> {
+        mp4.moov
             .traks
             .iter()
             .find(|trak| trak.tkhd.track_id == self.track_id)
-        else {
-            // `Track` structs are only constructed when we have `trak` boxes,
-            // so unless the user removes the `trak` box from the `Mp4`, it
-            // will always be present.
-            unreachable!("track with id \"{}\" not found", self.track_id);
+    }
+
+    /// The sample entry of the track from `stsd`, which holds its codec configuration.
+    ///
+    /// This is a copy taken when the samples were read, see [`Mp4::rebuild_tracks`].
+    pub fn sample_entry(&self) -> &StsdBoxContent {
+        &self.sample_entry
+    }
+
+    /// The flags of the track header, see [`TkhdFlags`].
+    pub fn flags(&self, mp4: &Mp4) -> TkhdFlags {
+        self.trak(mp4)
+            .map(|trak| trak.tkhd.track_flags())
+            .unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self, mp4: &Mp4) -> bool {
+        self.trak(mp4).is_some_and(|trak| trak.tkhd.is_enabled())
+    }
+
+    /// The width in pixels that the track is displayed at, see [`Self::display_size`].
+    pub fn display_width(&self, mp4: &Mp4) -> u32 {
+        self.display_size(mp4).0
+    }
+
+    /// The height in pixels that the track is displayed at, see [`Self::display_size`].
+    pub fn display_height(&self, mp4: &Mp4) -> u32 {
+        self.display_size(mp4).1
+    }
+
+    /// The width and height in pixels that the track is displayed at.
+    ///
+    /// That is the [`VideoGeometry::display_size`] of the sample entry, which is cropped to the
+    /// clean aperture and stretched by the pixel aspect ratio, swapped if the matrix of the track
+    /// header rotates it by 90 or 270 degrees, see [`crate::Matrix::rotation_degrees`]. Falls back
+    /// to the size of the track header for tracks without a visual sample entry.
+    pub fn display_size(&self, mp4: &Mp4) -> (u32, u32) {
+        let (width, height) = match self.sample_entry.video_geometry() {
+            Some(geometry) => geometry.display_size(),
+            None => (u32::from(self.width), u32::from(self.height)),
         };
+        match self
+            .trak(mp4)
+            .and_then(|trak| trak.tkhd.matrix.rotation_degrees())
+        {
+            Some(90 | 270) => (height, width),
+            _ => (width, height),
+        }
+    }
 
-        trak
+    /// The coded size, pixel aspect ratio and clean aperture of a video track.
+    pub fn video_geometry(&self) -> Option<VideoGeometry> {
+        self.sample_entry.video_geometry()
     }
 
-    pub fn raw_codec_config(&self, mp4: &Mp4) -> Option<Vec<u8>> {
-        let sample_description = &self.trak(mp4).mdia.minf.stbl.stsd;
+    /// The language of the track from its media header, or `None` if it isn't a valid
+    /// ISO 639-2/T code, e.g. an old `QuickTime` file with a Macintosh language code.
+    pub fn language(&self, mp4: &Mp4) -> Option<Language> {
+        self.trak(mp4)?.mdia.mdhd.language.parse().ok()
+    }
 
-        match &sample_description.contents {
+    /// The decoder configuration record of a video track, like the contents of `avcC`.
+    pub fn raw_codec_config(&self) -> Option<Vec<u8>> {
+        match &self.sample_entry {
             StsdBoxContent::Av01(content) => Some(content.av1c.raw.clone()),
             StsdBoxContent::Avc1(content) => Some(content.avcc.raw.clone()),
-            StsdBoxContent::Hev1(content) | StsdBoxContent::Hvc1(content) => {
-                Some(content.hvcc.raw.clone())
-            }
+            StsdBoxContent::Hev1(content)
+            | StsdBoxContent::Hvc1(content)
+            | StsdBoxContent::Dvh1(content)
+            | StsdBoxContent::Dvhe(content) => Some(content.hvcc.raw.clone()),
             StsdBoxContent::Vp08(content) => Some(content.vpcc.raw.clone()),
             StsdBoxContent::Vp09(content) => Some(content.vpcc.raw.clone()),
-            StsdBoxContent::Mp4a(_) | StsdBoxContent::Tx3g(_) | StsdBoxContent::Unknown(_) => None,
+            StsdBoxContent::Mp4a(_)
+            | StsdBoxContent::Mp3(_)
+            | StsdBoxContent::Ac3(_)
+            | StsdBoxContent::Ec3(_)
+            | StsdBoxContent::Pcm(_)
+            | StsdBoxContent::Tx3g(_)
+            | StsdBoxContent::Wvtt(_)
+            | StsdBoxContent::Stpp(_)
+            | StsdBoxContent::Mett(_)
+            | StsdBoxContent::Urim(_)
+            | StsdBoxContent::Gpmd(_)
+            | StsdBoxContent::Mebx(_)
+            | StsdBoxContent::Unknown(_) => None,
         }
     }
 
-    pub fn codec_string(&self, mp4: &Mp4) -> Option<String> {
-        self.trak(mp4).mdia.minf.stbl.stsd.contents.codec_string()
+    /// The codec string of the track as used in the `codecs` parameter of MIME types, like
+    /// `avc1.640028`, see [`StsdBoxContent::codec_string`].
+    pub fn codec_string(&self) -> Option<String> {
+        self.sample_entry.codec_string()
+    }
+
+    /// The data reference that the sample entry of this track points to, if it exists.
+    pub fn data_reference<'a>(&self, mp4: &'a Mp4) -> Option<&'a DataEntry> {
+        self.trak(mp4)?.data_reference()
+    }
+
+    /// Whether the samples of this track are stored in another file.
+    ///
+    /// The sample offsets of such a track point into that other file, so they must not be used
+    /// with the data of this one.
+    pub fn has_external_data(&self, mp4: &Mp4) -> bool {
+        self.data_reference(mp4)
+            .is_some_and(|entry| !entry.is_self_contained())
+    }
+
+    /// Where the samples of this track are stored, if they are in another file.
+    pub fn external_reference(&self, mp4: &Mp4) -> Option<ExternalDataRef> {
+        self.data_reference(mp4)?.external_reference()
+    }
+
+    /// Average number of samples per second, e.g. the frame rate of a video track.
+    ///
+    /// Derived from the timestamps and durations of the samples, so it works the same for
+    /// fragmented files, whose sample tables in `moov` are empty. Returns `None` if the track
+    /// has no samples, or they have no duration.
+    pub fn frame_rate(&self) -> Option<f64> {
+        let (first, last) = (self.samples.first()?, self.samples.last()?);
+        let span = last
+            .decode_timestamp
+            .saturating_add_unsigned(last.duration)
+            .saturating_sub(first.decode_timestamp);
+        (span > 0).then(|| self.samples.len() as f64 * self.timescale as f64 / span as f64)
+    }
+
+    /// The samples of this track along with their data, sliced out of `data`, the file the
+    /// track was parsed from.
+    ///
+    /// Meant for reading timed metadata tracks like GPS or IMU readings, whose samples are
+    /// small and used as they are. Stops at the first sample that lies beyond the end of `data`.
+    pub fn samples_with_data<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (&'a Sample, &'a [u8])> + 'a {
+        self.samples
+            .iter()
+            .map_while(|sample| Some((sample, data.get(sample.byte_range())?)))
+    }
+
+    /// Size of the largest sample in bytes, or 0 if the track has no samples.
+    pub fn max_sample_size(&self) -> u64 {
+        self.samples
+            .iter()
+            .map(|sample| sample.size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Size of a buffer that can hold the input of the decoder, so that it can be allocated once.
+    ///
+    /// This is the larger of [`Self::max_sample_size`] and the decoding buffer size signaled in
+    /// the `btrt` box of the sample entry, if there is one.
+    pub fn recommended_decoder_buffer(&self) -> u64 {
+        let buffer_size_db = self
+            .sample_entry
+            .btrt()
+            .map_or(0, |btrt| u64::from(btrt.buffer_size_db));
+        self.max_sample_size().max(buffer_size_db)
+    }
+
+    /// Properties of an AAC or MP3 (`mp4a` or `.mp3`), AC-3 (`ac-3`) or E-AC-3 (`ec-3`) audio track.
+    ///
+    /// Sample rate and channel count are taken from the decoder configuration in `esds`, `dac3`
+    /// or `dec3` where available, since the fields of the sample entry itself are often left at
+    /// their defaults.
+    ///
+    /// `mp4` is needed for the language from `mdhd` and the balance from `smhd`, which, unlike
+    /// the sample entry, are not kept on the track. Returns `None` if the `trak` of this track
+    /// was removed from `mp4.moov`.
+    pub fn audio_info(&self, mp4: &Mp4) -> Option<AudioInfo> {
+        let trak = self.trak(mp4)?;
+        let (sample_rate, channel_count, sample_size, profile, bitrate) = match &self.sample_entry {
+            StsdBoxContent::Mp4a(mp4a) => {
+                let dec_config = mp4a.esds.as_ref().map(|esds| &esds.es_desc.dec_config);
+                // Other object types, like MP3, have no `AudioSpecificConfig`.
+                let dec_specific = dec_config
+                    .filter(|dec_config| {
+                        OBJECT_TYPES_AAC.contains(&dec_config.object_type_indication)
+                    })
+                    .map(|dec_config| &dec_config.dec_specific);
+
+                let sample_rate = dec_specific
+                    .and_then(|d| SampleFreqIndex::try_from(d.freq_index).ok())
+                    .map_or_else(|| mp4a.sample_rate(), |f| f.freq());
+                let channel_count = dec_specific
+                    .map(|d| u16::from(d.chan_conf))
+                    .filter(|&channels| channels != 0)
+                    .unwrap_or(mp4a.channelcount);
+                (
+                    sample_rate,
+                    channel_count,
+                    mp4a.samplesize,
+                    dec_specific.and_then(|d| AudioObjectType::try_from(d.profile).ok()),
+                    dec_config
+                        .map(|dec_config| dec_config.avg_bitrate)
+                        .filter(|&bitrate| bitrate != 0),
+                )
+            }
+            StsdBoxContent::Ac3(ac3) => {
+                let dac3 = ac3.dac3.as_ref();
+                (
+                    dac3.and_then(|dac3| dac3.sample_rate())
+                        .unwrap_or_else(|| u32::from(ac3.samplerate.value())),
+                    dac3.map_or(ac3.channelcount, |dac3| dac3.channel_count()),
+                    ac3.samplesize,
+                    None,
+                    dac3.and_then(|dac3| dac3.bitrate()),
+                )
+            }
+            StsdBoxContent::Mp3(mp3) => (
+                mp3.sample_rate(),
+                mp3.channelcount,
+                mp3.samplesize,
+                None,
+                mp3.btrt
+                    .map(|btrt| btrt.avg_bitrate)
+                    .filter(|&bitrate| bitrate != 0),
+            ),
+            StsdBoxContent::Pcm(pcm) => {
+                let format = pcm.format();
+                (
+                    format.sample_rate,
+                    format.channel_count,
+                    format.bits_per_sample,
+                    None,
+                    None,
+                )
+            }
+            StsdBoxContent::Ec3(ec3) => {
+                let dec3 = ec3.dec3.as_ref();
+                (
+                    dec3.and_then(|dec3| dec3.sample_rate())
+                        .unwrap_or_else(|| u32::from(ec3.samplerate.value())),
+                    dec3.and_then(|dec3| dec3.channel_count())
+                        .unwrap_or(ec3.channelcount),
+                    ec3.samplesize,
+                    None,
+                    dec3.map(|dec3| dec3.bitrate()),
+                )
+            }
+            _ => return None,
+        };
+
+        Some(AudioInfo {
+            sample_rate,
+            channel_count,
+            sample_size,
+            profile,
+            bitrate,
+            language: trak.mdia.mdhd.language.clone(),
+            balance: trak
+                .mdia
+                .minf
+                .smhd
+                .as_ref()
+                .map_or(0.0, |smhd| f32::from(smhd.balance.raw_value()) / 256.0),
+        })
+    }
+
+    /// How the samples of this track are spread over the fragments of the file, or `None` if
+    /// the track has no fragments.
+    pub fn fragment_stats(&self, mp4: &Mp4) -> Option<TrackFragmentStats> {
+        let mut stats = TrackFragmentStats::default();
+        for traf in mp4.moofs.iter().flat_map(|moof| &moof.trafs) {
+            if traf.tfhd.track_id != self.track_id {
+                continue;
+            }
+            stats.samples_per_fragment.push(
+                traf.truns
+                    .iter()
+                    .map(|trun| u64::from(trun.sample_count))
+                    .sum(),
+            );
+            if let Some(tfdt) = &traf.tfdt {
+                let time = tfdt.base_media_decode_time;
+                stats.earliest_tfdt = Some(stats.earliest_tfdt.map_or(time, |t| t.min(time)));
+                stats.latest_tfdt = Some(stats.latest_tfdt.map_or(time, |t| t.max(time)));
+            }
+        }
+        (stats.fragment_count() > 0).then_some(stats)
+    }
+
+    /// The encoder delay and padding of an audio track, which gapless players trim from the
+    /// start and end of the decoded audio.
+    ///
+    /// Taken from the `iTunSMPB` item of the movie metadata if there is one, or else from the
+    /// edit list: the delay is where the first edit that isn't empty starts in the media, and
+    /// the padding is what that edit leaves of the media at its end. Returns `None` for tracks
+    /// that aren't audio, and for audio tracks that signal neither.
+    pub fn audio_priming(&self, mp4: &Mp4) -> Option<AudioPriming> {
+        if self.kind != Some(TrackKind::Audio) {
+            return None;
+        }
+        if let Some(priming) = mp4
+            .metadata_ilsts()
+            .into_iter()
+            .flatten()
+            .find_map(|ilst| ilst.freeform_item("com.apple.iTunes", "iTunSMPB"))
+            .and_then(|data| parse_itunsmpb(&data.data))
+        {
+            return Some(priming);
+        }
+
+        let elst = self.trak(mp4)?.edts.as_ref()?.elst.as_ref()?;
+        let mut edits = elst.entries.iter().filter(|e| !elst.is_empty_edit(e));
+        let edit = edits.next()?;
+
+        // The media timescale of audio is usually its sample rate, but not always.
+        let sample_rate = self
+            .audio_info(mp4)
+            .map_or(self.timescale, |info| u64::from(info.sample_rate));
+        let to_samples = |ticks: u64, timescale: u64| {
+            let samples =
+                u128::from(ticks) * u128::from(sample_rate) / u128::from(timescale.max(1));
+            u64::try_from(samples).unwrap_or(u64::MAX)
+        };
+
+        // Edits after the first, or one that runs to the end, leave no padding to speak of.
+        let padding = if edits.next().is_none() && edit.segment_duration != 0 {
+            let presented = u128::from(edit.segment_duration) * u128::from(self.timescale)
+                / u128::from(mp4.moov.mvhd.timescale.max(1));
+            let end = u128::from(edit.media_time) + presented;
+            let padding =
+                u64::try_from(u128::from(self.duration).saturating_sub(end)).unwrap_or(u64::MAX);
+            to_samples(padding, self.timescale)
+        } else {
+            0
+        };
+        Some(AudioPriming {
+            delay_samples: to_samples(edit.media_time, self.timescale),
+            padding_samples: padding,
+        })
+    }
+
+    /// Computes the resolution, frame rate and peak bitrate a decoder has to handle for this track.
+    ///
+    /// The resolution is taken from the sample entry, falling back to the track header.
+    pub fn decoder_requirements(&self) -> DecoderRequirements {
+        let (width, height) = self
+            .sample_entry
+            .resolution()
+            .unwrap_or((self.width, self.height));
+
+        let frame_rate = self.frame_rate().unwrap_or(0.0);
+
+        // Sum up the samples decoded during each one second window starting at a sample.
+        let mut max_bytes = 0;
+        let mut window_bytes = 0;
+        let mut window_end = 0;
+        for (index, sample) in self.samples.iter().enumerate() {
+            let end = sample
+                .decode_timestamp
+                .saturating_add_unsigned(self.timescale);
+            while window_end < self.samples.len() && self.samples[window_end].decode_timestamp < end
+            {
+                window_bytes += self.samples[window_end].size;
+                window_end += 1;
+            }
+            max_bytes = max_bytes.max(window_bytes);
+            if window_end > index {
+                window_bytes -= sample.size;
+            } else {
+                window_end = index + 1;
+            }
+        }
+
+        DecoderRequirements {
+            width: u64::from(width),
+            height: u64::from(height),
+            frame_rate,
+            max_bitrate: max_bytes * 8,
+        }
+    }
+
+    /// Checks the [`Self::decoder_requirements`] of an AVC, HEVC or AV1 track against the limits
+    /// of the level signaled in its sample entry.
+    ///
+    /// Returns the limits that are exceeded, which is empty if the track conforms to its level.
+    /// Returns `None` for other codecs and for levels that are not known.
+    pub fn validate_level(&self) -> Option<Vec<LevelViolation>> {
+        let requirements = self.decoder_requirements();
+        match &self.sample_entry {
+            StsdBoxContent::Av01(entry) => level::av1_violations(&entry.av1c, &requirements),
+            StsdBoxContent::Avc1(entry) => level::avc_violations(&entry.avcc, &requirements),
+            StsdBoxContent::Hev1(entry)
+            | StsdBoxContent::Hvc1(entry)
+            | StsdBoxContent::Dvh1(entry)
+            | StsdBoxContent::Dvhe(entry) => level::hevc_violations(&entry.hvcc, &requirements),
+            StsdBoxContent::Vp08(_)
+            | StsdBoxContent::Vp09(_)
+            | StsdBoxContent::Mp4a(_)
+            | StsdBoxContent::Mp3(_)
+            | StsdBoxContent::Ac3(_)
+            | StsdBoxContent::Ec3(_)
+            | StsdBoxContent::Pcm(_)
+            | StsdBoxContent::Tx3g(_)
+            | StsdBoxContent::Wvtt(_)
+            | StsdBoxContent::Stpp(_)
+            | StsdBoxContent::Mett(_)
+            | StsdBoxContent::Urim(_)
+            | StsdBoxContent::Gpmd(_)
+            | StsdBoxContent::Mebx(_)
+            | StsdBoxContent::Unknown(_) => None,
+        }
+    }
+
+    /// The samples whose delta in `stts` is zero, by [`Sample::id`].
+    ///
+    /// Such samples are written by some muxers for samples that share a timestamp. Their
+    /// timestamps and durations are not the ones in the file, see [`Sample::duration`]. The
+    /// last sample is not included, since its duration doesn't depend on `stts`.
+    pub fn zero_delta_samples(&self, mp4: &Mp4) -> Vec<u32> {
+        let Some(trak) = self.trak(mp4) else {
+            return Vec::new();
+        };
+        let stts = &trak.mdia.minf.stbl.stts;
+        let moov_samples = self.samples.len().min(
+            stts.entries
+                .iter()
+                .map(|entry| entry.sample_count as usize)
+                .sum(),
+        );
+        let mut zero_delta_samples = Vec::new();
+        let mut first_sample = 0u32;
+        for entry in &stts.entries {
+            if entry.sample_delta == 0 {
+                zero_delta_samples.extend(
+                    (first_sample..first_sample.saturating_add(entry.sample_count))
+                        .take_while(|&id| (id as usize) + 1 < moov_samples),
+                );
+            }
+            first_sample = first_sample.saturating_add(entry.sample_count);
+        }
+        zero_delta_samples
     }
+
+    /// The 1-based numbers of the samples of an AVC or HEVC track that start with an IDR, CRA or
+    /// BLA picture, found by scanning the NAL units of every sample.
+    ///
+    /// Use this for files whose `stss` is missing although not every sample is a sync sample,
+    /// see also [`Mp4::add_missing_stss`]. `reader` must be the file `mp4` was parsed from.
+    /// Returns `None` for other codecs.
+    pub fn detected_sync_samples<R: Read + Seek>(
+        &self,
+        mp4: &Mp4,
+        reader: &mut R,
+    ) -> Result<Option<Vec<u32>>> {
+        sync_samples::detect_sync_samples(mp4, self, reader)
+    }
+
+    /// Compares the color signaling of the sample entry of an AVC, HEVC, AV1 or VP9 track with
+    /// that of the bitstream, see [`ColorReport`].
+    ///
+    /// The bitstream is read from the first sync sample, falling back to the parameter sets or
+    /// configuration OBUs of the decoder configuration. `data` is the file the track was parsed
+    /// from. Returns `None` for other codecs.
+    pub fn color_report(&self, data: &[u8]) -> Result<Option<ColorReport>> {
+        let sync_sample = self
+            .samples_with_data(data)
+            .find(|(sample, _)| sample.is_sync)
+            .map(|(_, bytes)| bytes);
+        color::color_report(&self.sample_entry, sync_sample)
+    }
+
+    /// Whether the samples of the track `other`, which may be from another file, can be decoded
+    /// with the codec configuration of this track, see [`codec_config_compatible`].
+    pub fn codec_config_compatible(&self, other: &Self) -> CompatReport {
+        codec_config_compatible(&self.sample_entry, &other.sample_entry)
+    }
+}
+
+/// Properties of an audio track, see [`Track::audio_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioInfo {
+    /// Samples per second.
+    pub sample_rate: u32,
+
+    pub channel_count: u16,
+
+    /// Bits per sample, as signaled in the sample entry.
+    pub sample_size: u16,
+
+    /// The AAC profile, if the decoder configuration is present.
+    pub profile: Option<AudioObjectType>,
+
+    /// Bits per second, if the decoder configuration signals it.
+    pub bitrate: Option<u32>,
+
+    /// ISO 639-2/T language code of the track, e.g. `eng`, or `und` if undetermined.
+    pub language: String,
+
+    /// Stereo balance from the sound media header, from -1.0 (full left) to 1.0 (full right).
+    pub balance: f32,
+}
+
+/// The samples that an audio encoder added before and after the audio, see
+/// [`Track::audio_priming`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioPriming {
+    /// Number of samples at the start that decoders produce before the audio begins.
+    pub delay_samples: u64,
+
+    /// Number of samples at the end that pad the last frame.
+    pub padding_samples: u64,
 }
 
-#[derive(Default, Clone, Copy)]
+/// The timestamps that [`Mp4::interleaved_samples`] orders samples by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleOrder {
+    /// [`Sample::decode_timestamp`], the order in which samples are fed to decoders.
+    Decode,
+
+    /// [`Sample::composition_timestamp`], the order in which samples are shown.
+    Presentation,
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Sample {
     /// Sample number.
     pub id: u32,
@@ -541,6 +1972,11 @@ pub struct Sample {
     /// or if it needs the context of other samples.
     pub is_sync: bool,
 
+    /// The sample flags of fragmented files. Progressive files have the sync flag and, if they
+    /// have an `sdtp` box, the dependency fields, the other fields are 0 as if unknown.
+    #[serde(default)]
+    pub flags: SampleFlags,
+
     /// Size of the sample in bytes.
     pub size: u64,
 
@@ -558,22 +1994,441 @@ pub struct Sample {
     /// This is offsetted:
     /// * with decode timestamp shift determined from negative sample offsets
     /// * such that the first [`Self::composition_timestamp`] is zero.
+    ///
+    /// Because of the shift, this is negative for the samples that are decoded before the first
+    /// one is shown, like the reference frames of B-frames.
     pub decode_timestamp: i64,
 
+    /// Timestamp of the sample at which it should be decoded, in time units on the media
+    /// timeline of the track, as stated by the file.
+    ///
+    /// This is the sum of the durations of the samples before it, or for the first sample of a
+    /// fragment the base media decode time in `tfdt`. Unlike [`Self::decode_timestamp`] it is not
+    /// shifted, so it is never negative.
+    #[serde(default)]
+    pub media_decode_timestamp: u64,
+
     /// Timestamp of the sample at which the sample should be displayed,
     /// in time units.
     ///
-    /// This is offsetted such that the first composition timestamp is zero.
+    /// This is offsetted such that the first composition timestamp is zero, so it may be
+    /// negative for samples shown before the first one in decode order, such as those of a
+    /// pre-roll in fragments with negative composition offsets.
     pub composition_timestamp: i64,
 
     /// Duration of the sample in time units.
+    ///
+    /// Samples with a zero delta in `stts` get an even share of the duration of the sample
+    /// after them instead, and their timestamps are spread out accordingly, so that timestamps
+    /// increase strictly. See [`Track::zero_delta_samples`].
     pub duration: u64,
 }
 
 impl Sample {
     /// Returns the range of bytes in the input data that this sample covers.
+    ///
+    /// This is [`Self::file_range`] as `usize`, for indexing the buffer the file was read from.
+    /// Offsets are 64-bit even on 32-bit targets, where files beyond 4 GiB can't be held in memory,
+    /// so offsets past `usize::MAX` are clamped to it. Use [`crate::DataSources`] to read samples
+    /// from such files instead.
     pub fn byte_range(&self) -> std::ops::Range<usize> {
-        self.offset as usize..(self.offset + self.size) as usize
+        let range = self.file_range();
+        let to_usize = |offset| usize::try_from(offset).unwrap_or(usize::MAX);
+        to_usize(range.start)..to_usize(range.end)
+    }
+
+    /// The range of bytes in the source file that this sample covers.
+    ///
+    /// This always refers to the file, also after the data of the track has been loaded with
+    /// [`crate::load_track_data_aligned`]. Use [`Self::data_range`] for the range in the loaded
+    /// data instead.
+    pub fn file_range(&self) -> std::ops::Range<u64> {
+        self.offset..self.offset.saturating_add(self.size)
+    }
+
+    /// The range of this sample within [`crate::TrackData::data`], the data of its track loaded
+    /// with [`crate::load_track_data_aligned`].
+    ///
+    /// Returns `None` if `data` has no sample at the index of this one, like when it was loaded
+    /// for another track.
+    pub fn data_range(&self, data: &crate::TrackData) -> Option<std::ops::Range<usize>> {
+        data.ranges.get(self.id as usize).cloned()
+    }
+
+    /// Whether decoding this sample needs other samples, from `sample_depends_on` in
+    /// [`Self::flags`].
+    ///
+    /// `None` if unknown, in which case [`Self::is_sync`] is the best guess.
+    pub fn depends_on(&self) -> Option<bool> {
+        match self.flags.depends_on {
+            1 => Some(true),
+            2 => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Whether no other sample depends on this one, so that it can be dropped without affecting
+    /// the others, as for frame dropping or fast seeking.
+    ///
+    /// `false` if unknown.
+    pub fn is_disposable(&self) -> bool {
+        self.flags.is_depended_on == 2
+    }
+
+    /// [`Self::composition_timestamp`] in seconds, which may be negative.
+    pub fn presentation_time(&self) -> f64 {
+        self.composition_timestamp as f64 / self.timescale.max(1) as f64
+    }
+
+    /// [`Self::decode_timestamp`] in seconds, which may be negative.
+    pub fn decode_time(&self) -> f64 {
+        self.decode_timestamp as f64 / self.timescale.max(1) as f64
+    }
+
+    /// The data of this sample, as a slice of the buffer that `mp4` was read from.
+    ///
+    /// Returns `None` if `mp4` was not read with [`Mp4::read_shared`], or if the sample
+    /// lies outside of the buffer.
+    pub fn bytes(&self, mp4: &Mp4) -> Option<Bytes> {
+        let data = mp4.data.as_ref()?;
+        let range = self.byte_range();
+        (range.end <= data.len()).then(|| data.slice(range))
+    }
+}
+
+/// The user type of `uuid` boxes that hold XMP, `BE7ACFCB-97A9-42E8-9C71-999491E3AFAC`.
+const XMP_USER_TYPE: [u8; 16] = [
+    0xbe, 0x7a, 0xcf, 0xcb, 0x97, 0xa9, 0x42, 0xe8, 0x9c, 0x71, 0x99, 0x94, 0x91, 0xe3, 0xaf, 0xac,
+];
+
+/// The top-level boxes of a file or media segment, see [`read_top_level_boxes`].
+struct TopLevelBoxes {
+    ftyp: Option<FtypBox>,
+    styps: Vec<FtypBox>,
+    moov: Option<MoovBox>,
+    moofs: Vec<MoofBox>,
+    emsgs: Vec<EmsgBox>,
+    meta: Option<MetaBox>,
+    mdats: Vec<(u64, u64)>,
+    box_tree: Vec<BoxNode>,
+    top_level_extras: Vec<ExtraBox>,
+}
+
+/// Parses the top-level boxes of the `size` bytes of `reader`, starting at its position.
+fn read_top_level_boxes<R: Read + Seek>(
+    reader: &mut R,
+    size: u64,
+    limits: Limits,
+) -> Result<TopLevelBoxes> {
+    let start = reader.stream_position()?;
+
+    let mut ftyp = None;
+    let mut styps = Vec::new();
+    let mut moov = None;
+    let mut moofs = Vec::new();
+    let mut emsgs = Vec::new();
+    let mut meta = None;
+    let mut mdats = Vec::new();
+    let mut top_level_extras = Vec::new();
+
+    // Only box headers are read for the tree, so the limits on the structure are checked
+    // before any of the boxes are parsed.
+    let box_tree = read_box_tree(reader, start, size, limits.max_box_depth)?;
+    if table_bytes(&box_tree) > limits.max_table_bytes {
+        return Err(Error::LimitExceeded(
+            "max_table_bytes",
+            limits.max_table_bytes,
+        ));
+    }
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut current = start;
+    while current < size {
+        // Get box header.
+        let header = BoxHeader::read(reader)?;
+        let BoxHeader { name, size: s } = header;
+        if s > size {
+            return Err(Error::InvalidData(
+                "file contains a box with a larger size than it",
+            ));
+        }
+
+        // A size of zero means that the box extends to the end of the file.
+        // Only `mdat` can be skipped without knowing its size, anything else is the end of the file.
+        if s == 0 {
+            if name == BoxType::MdatBox {
+                let data_start = reader.stream_position()?;
+                mdats.push((data_start, size.saturating_sub(data_start)));
+            }
+            break;
+        }
+
+        span!("read_box", box_type = %name, offset = current, size = s);
+
+        // Match and parse the atom boxes.
+        match name {
+            BoxType::FtypBox => {
+                ftyp = Some(read_top_level_box(reader, s, &box_tree)?);
+            }
+            // `styp` starts media segments and has the same layout as `ftyp`.
+            BoxType::StypBox => {
+                styps.push(read_top_level_box(reader, s, &box_tree)?);
+            }
+            BoxType::MdatBox => {
+                // The header may have a 64-bit `largesize`, but `s` is always `HEADER_SIZE` more
+                // than the length of the contents, see `BoxHeader::read`.
+                let data_start = reader.stream_position()?;
+                mdats.push((data_start, s.saturating_sub(HEADER_SIZE)));
+                skip_box(reader, s)?;
+            }
+            // `wide` is an 8-byte placeholder that is overwritten when the following `mdat` grows
+            // beyond 4 GiB and needs a 64-bit size.
+            BoxType::FreeBox | BoxType::SkipBox | BoxType::WideBox => {
+                skip_box(reader, s)?;
+            }
+            BoxType::MoovBox => {
+                moov = Some(read_top_level_box(reader, s, &box_tree)?);
+            }
+            BoxType::MoofBox => {
+                let moof = read_top_level_box(reader, s, &box_tree)?;
+                moofs.push(moof);
+            }
+            BoxType::EmsgBox => {
+                let emsg = read_top_level_box(reader, s, &box_tree)?;
+                emsgs.push(emsg);
+            }
+            BoxType::MetaBox => {
+                meta = Some(read_top_level_box(reader, s, &box_tree)?);
+            }
+            _ => {
+                top_level_extras.push(ExtraBox::read(reader, name, current, s)?);
+            }
+        }
+        current = reader.stream_position()?;
+    }
+
+    Ok(TopLevelBoxes {
+        ftyp,
+        styps,
+        moov,
+        moofs,
+        emsgs,
+        meta,
+        mdats,
+        box_tree,
+        top_level_extras,
+    })
+}
+
+/// Parses the top-level box of `size` bytes whose header `reader` just read, adding the path to
+/// the box that failed to parse to errors, see [`Error::InBox`].
+fn read_top_level_box<R: Read + Seek, B: for<'a> ReadBox<&'a mut R>>(
+    reader: &mut R,
+    size: u64,
+    box_tree: &[BoxNode],
+) -> Result<B> {
+    // `size` is `HEADER_SIZE` more than the length of the contents, even for 64-bit sizes.
+    let box_end = (reader.stream_position()? + size).saturating_sub(HEADER_SIZE);
+    B::read_box(reader, size).map_err(|err| {
+        let Ok(offset) = reader.stream_position() else {
+            return err;
+        };
+        let Some(mut node) = box_tree
+            .iter()
+            .find(|node| node.offset + node.size == box_end)
+        else {
+            return err;
+        };
+        let mut path = vec![node.box_type];
+        while let Some(child) = node
+            .children
+            .iter()
+            .find(|child| (child.offset..child.offset + child.size).contains(&offset))
+        {
+            path.push(child.box_type);
+            node = child;
+        }
+        Error::InBox {
+            path,
+            offset,
+            source: Box::new(err),
+        }
+    })
+}
+
+/// The boxes whose entries are held in memory, one per sample or chunk.
+const SAMPLE_TABLE_BOXES: [&[u8; 4]; 10] = [
+    b"stts", b"ctts", b"stss", b"stsc", b"stsz", b"stz2", b"stco", b"co64", b"sdtp", b"trun",
+];
+
+/// The size of all sample tables in `box_tree`, see [`Limits::max_table_bytes`].
+fn table_bytes(box_tree: &[BoxNode]) -> u64 {
+    box_tree
+        .iter()
+        .flat_map(BoxNode::descendants)
+        .filter(|node| SAMPLE_TABLE_BOXES.contains(&&node.box_type.value))
+        .fold(0, |bytes, node| bytes.saturating_add(node.size))
+}
+
+/// Spreads the duration of the first sample with a nonzero `stts` delta evenly over it and the
+/// samples with a zero delta right before it, which some muxers write for samples that share a
+/// timestamp. Composition timestamps move along with decode timestamps.
+///
+/// Runs that are longer than the duration to spread are left alone, since their samples can't
+/// all get a nonzero duration. Returns whether any timestamps changed.
+fn distribute_zero_durations(samples: &mut [Sample]) -> bool {
+    let mut changed = false;
+    let mut start = 0;
+    while start < samples.len() {
+        let Some(end) = samples[start..]
+            .iter()
+            .position(|sample| sample.duration > 0)
+            .map(|position| start + position)
+        else {
+            break;
+        };
+
+        let count = (end - start + 1) as u64;
+        let total = samples[end].duration;
+        if count > 1 && total >= count {
+            // Computed in 128 bits, a run whose timestamps don't fit is left alone.
+            let first_timestamp = i128::from(samples[start].decode_timestamp);
+            let offset = |k: u64| u128::from(total) * u128::from(k) / u128::from(count);
+            let timings = samples[start..=end]
+                .iter()
+                .zip(0..)
+                .map(|(sample, k)| {
+                    let decode_timestamp =
+                        i64::try_from(first_timestamp + offset(k).cast_signed()).ok()?;
+                    let shift = i128::from(decode_timestamp) - i128::from(sample.decode_timestamp);
+                    let composition_timestamp =
+                        i64::try_from(i128::from(sample.composition_timestamp) + shift).ok()?;
+                    let duration = (offset(k + 1) - offset(k)) as u64;
+                    Some((decode_timestamp, composition_timestamp, duration))
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(timings) = timings {
+                for (sample, (decode_timestamp, composition_timestamp, duration)) in
+                    samples[start..=end].iter_mut().zip(timings)
+                {
+                    sample.decode_timestamp = decode_timestamp;
+                    sample.composition_timestamp = composition_timestamp;
+                    sample.duration = duration;
+                }
+                changed = true;
+            }
+        }
+        start = end + 1;
+    }
+    changed
+}
+
+/// The number of samples that all sample tables of `stbl` describe.
+///
+/// Files in the wild may declare more samples in `stsz` than `stts`, `ctts` or the chunks
+/// hold, in which case the samples past the shortest table are dropped.
+fn described_sample_count(stbl: &StblBox, track_id: TrackId) -> Result<usize> {
+    let mut count = stbl.stsz.count() as u64;
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let stts_count: u64 = stbl
+        .stts
+        .entries
+        .iter()
+        .map(|e| u64::from(e.sample_count))
+        .sum();
+    count = count.min(stts_count);
+    if let Some(ctts) = &stbl.ctts {
+        let ctts_count: u64 = ctts.entries.iter().map(|e| u64::from(e.sample_count)).sum();
+        count = count.min(ctts_count);
+    }
+
+    let chunk_count = if let Some(stco) = &stbl.stco {
+        stco.entries.len() as u64
+    } else if let Some(co64) = &stbl.co64 {
+        co64.entries.len() as u64
+    } else {
+        return Err(Error::BoxInStblNotFound(track_id, BoxType::StcoBox));
+    };
+    let runs = &stbl.stsc.entries;
+    let mut chunked_count = 0u64;
+    for (i, run) in runs.iter().enumerate() {
+        // Like `build_tracks`, the first run starts at the first chunk whatever it says.
+        let first_chunk = if i == 0 {
+            1
+        } else {
+            u64::from(run.first_chunk)
+        };
+        let end_chunk = match runs.get(i + 1) {
+            Some(next) if next.first_chunk as u64 <= first_chunk => {
+                return Err(Error::InvalidData("stsc first chunks are not increasing"));
+            }
+            Some(next) => u64::from(next.first_chunk).min(chunk_count + 1),
+            None => chunk_count + 1,
+        };
+        let chunks = end_chunk.saturating_sub(first_chunk);
+        chunked_count =
+            chunked_count.saturating_add(chunks.saturating_mul(u64::from(run.samples_per_chunk)));
+    }
+    count = count.min(chunked_count);
+
+    Ok(count as usize)
+}
+
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(Error::InvalidData("stream ends within a box header")),
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(true)
+}
+
+/// Parses the delay and padding out of the text of an `iTunSMPB` item, which is made of
+/// hexadecimal numbers, like ` 00000000 00000840 000001CA 00000000000A5E36 ...`.
+fn parse_itunsmpb(data: &[u8]) -> Option<AudioPriming> {
+    let text = std::str::from_utf8(data).ok()?;
+    let mut fields = text
+        .split_whitespace()
+        .map(|field| u64::from_str_radix(field, 16).ok());
+    let (Some(_), Some(delay_samples), Some(padding_samples)) =
+        (fields.next()?, fields.next()?, fields.next()?)
+    else {
+        return None;
+    };
+    Some(AudioPriming {
+        delay_samples,
+        padding_samples,
+    })
+}
+
+/// Metadata merged from all movie-level item lists, in order of precedence.
+struct MovieMetadata<'a> {
+    ilsts: [Option<&'a IlstBox>; 2],
+}
+
+impl<'a> Metadata<'a> for MovieMetadata<'a> {
+    fn title(&self) -> Option<Cow<'_, str>> {
+        self.ilsts.iter().flatten().find_map(|ilst| ilst.title())
+    }
+
+    fn year(&self) -> Option<u32> {
+        self.ilsts.iter().flatten().find_map(|ilst| ilst.year())
+    }
+
+    fn poster(&self) -> Option<&[u8]> {
+        self.ilsts.iter().flatten().find_map(|ilst| ilst.poster())
+    }
+
+    fn summary(&self) -> Option<Cow<'_, str>> {
+        self.ilsts.iter().flatten().find_map(|ilst| ilst.summary())
     }
 }
 
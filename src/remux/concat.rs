@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+
+use super::{
+    box_size, check_self_contained, chunk_offset_box, push_run, read_children, read_range,
+    rewrite_box, rewrite_moov_until_stable, split_top_level, wrap_box, ChunkLayout, Rewrite,
+};
+use crate::{
+    BoxHeader, BoxType, CttsBox, CttsEntry, EdtsBox, ElstBox, ElstEntry, Error, MdhdBox, Mp4,
+    MvhdBox, ReadBox as _, Result, StblBox, StscBox, StscEntry, StssBox, StszBox, SttsBox,
+    SttsEntry, TkhdBox, TrakBox, WriteBox as _,
+};
+
+/// Joins recordings of the same streams into a single progressive file, without re-encoding.
+///
+/// Each input is a parsed file together with the source it was parsed from. All inputs must
+/// contain the same tracks (matched by track id) with identical sample descriptions and
+/// timescales. The samples of each track are appended in order, every input starting exactly
+/// where the previous one ended, so there are no gaps or overlaps between them.
+///
+/// The edit lists of the inputs are joined as well, so that what each input leaves out of its
+/// presentation, like the priming samples of audio, stays out of the joined one.
+///
+/// `moov` of the first input serves as a template: its sample tables and edit lists are replaced
+/// by the merged ones and its durations are extended, everything else is copied as-is. Sample grouping and
+/// dependency boxes (`sbgp`, `sgpd`, `sdtp`, …) are left out, since they would no longer match.
+///
+/// Fragmented inputs are not supported.
+pub fn concat<R: Read + Seek, W: Write>(inputs: &mut [(&Mp4, R)], writer: &mut W) -> Result<()> {
+    let Some((first, _)) = inputs.first() else {
+        return Err(Error::InvalidData("no files to concatenate"));
+    };
+    let first = *first;
+
+    // The traks of every input, in the order of the first one.
+    let mut traks: Vec<Vec<&TrakBox>> = Vec::with_capacity(inputs.len());
+    for (mp4, _) in inputs.iter() {
+        if !mp4.moofs.is_empty() {
+            return Err(Error::InvalidData(
+                "fragmented files cannot be concatenated",
+            ));
+        }
+        check_self_contained(mp4)?;
+        if mp4.moov.traks.len() != first.moov.traks.len() {
+            return Err(Error::InvalidData(
+                "files to concatenate have a different number of tracks",
+            ));
+        }
+        let mut input_traks = Vec::with_capacity(first.moov.traks.len());
+        for first_trak in &first.moov.traks {
+            let track_id = first_trak.tkhd.track_id;
+            let trak = mp4
+                .moov
+                .traks
+                .iter()
+                .find(|trak| trak.tkhd.track_id == track_id)
+                .ok_or(Error::TrakNotFound(track_id))?;
+            check_compatible(first_trak, trak)?;
+            input_traks.push(trak);
+        }
+        traks.push(input_traks);
+    }
+
+    let mut tracks = Vec::with_capacity(first.moov.traks.len());
+    for (trak_index, first_trak) in first.moov.traks.iter().enumerate() {
+        let input_traks: Vec<&TrakBox> = traks
+            .iter()
+            .map(|input_traks| input_traks[trak_index])
+            .collect();
+        tracks.push(MergedTrack::new(
+            first_trak,
+            &first.moov.mvhd,
+            &input_traks,
+        )?);
+    }
+    let mut mvhd = first.moov.mvhd.clone();
+    mvhd.duration = tracks
+        .iter()
+        .map(|track| track.tkhd.duration)
+        .max()
+        .unwrap_or(mvhd.duration);
+    if mvhd.duration > u64::from(u32::MAX) {
+        mvhd.version = 1;
+    }
+
+    let layouts = traks
+        .iter()
+        .map(|input_traks| ChunkLayout::new(input_traks))
+        .collect::<Result<Vec<_>>>()?;
+    let mdat_size: u64 = layouts.iter().map(|layout| layout.size).sum();
+    let mdat_header_size = box_size(mdat_size) - mdat_size;
+
+    let track_ids: HashMap<u32, usize> = first
+        .moov
+        .traks
+        .iter()
+        .enumerate()
+        .map(|(index, trak)| (trak.tkhd.track_id, index))
+        .collect();
+    let (prefix, moov) = split_top_level(&mut inputs[0].1)?;
+    let moov = rewrite_moov_until_stable(0, |moov_size| {
+        let mut base = prefix.len() as u64 + moov_size + mdat_header_size;
+        let mut chunk_offsets = vec![Vec::new(); tracks.len()];
+        for layout in &layouts {
+            for (offsets, relative_offsets) in
+                chunk_offsets.iter_mut().zip(&layout.relative_offsets)
+            {
+                offsets.extend(relative_offsets.iter().map(|offset| base + offset));
+            }
+            base += layout.size;
+        }
+
+        let mut current = 0;
+        rewrite_box(
+            &mut inputs[0].1,
+            &moov,
+            &mut Vec::new(),
+            &mut |reader, path, child| {
+                let mut bytes = Vec::new();
+                match (path, child.name) {
+                    ([BoxType::MoovBox], BoxType::MvhdBox) => {
+                        mvhd.write_box(&mut bytes)?;
+                    }
+                    ([BoxType::MoovBox], BoxType::TrakBox) => {
+                        let track_id = TrakBox::read_box(reader, child.size)?.tkhd.track_id;
+                        // Traks removed with `Mp4::retain_tracks` are still in the source `moov`.
+                        let Some(&index) = track_ids.get(&track_id) else {
+                            return Ok(Rewrite::Drop);
+                        };
+                        current = index;
+                        return Ok(Rewrite::Descend);
+                    }
+                    ([BoxType::MoovBox, BoxType::TrakBox], BoxType::TkhdBox) => {
+                        let track = &tracks[current];
+                        track.tkhd.write_box(&mut bytes)?;
+                        if let Some(edts) = track.edts.as_ref().filter(|_| track.insert_edts) {
+                            edts.write_box(&mut bytes)?;
+                        }
+                    }
+                    ([BoxType::MoovBox, BoxType::TrakBox], BoxType::EdtsBox) => {
+                        let Some(edts) = &tracks[current].edts else {
+                            return Ok(Rewrite::Copy);
+                        };
+                        edts.write_box(&mut bytes)?;
+                    }
+                    ([BoxType::MoovBox, BoxType::TrakBox], BoxType::MdiaBox)
+                    | ([.., BoxType::MdiaBox], BoxType::MinfBox) => return Ok(Rewrite::Descend),
+                    ([.., BoxType::MdiaBox], BoxType::MdhdBox) => {
+                        tracks[current].mdhd.write_box(&mut bytes)?;
+                    }
+                    ([.., BoxType::MinfBox], BoxType::StblBox) => {
+                        let stsd = read_children(reader, child.data_start, child.end)?
+                            .into_iter()
+                            .find(|stbl_child| stbl_child.name == BoxType::StsdBox)
+                            .ok_or(Error::BoxNotFound(BoxType::StsdBox))?;
+                        let stsd = read_range(reader, stsd.start, stsd.end)?;
+                        let chunk_offsets =
+                            chunk_offset_box(chunk_offsets[current].clone(), false)?;
+                        bytes = tracks[current].stbl(&stsd, &chunk_offsets)?;
+                    }
+                    _ => return Ok(Rewrite::Copy),
+                }
+                Ok(Rewrite::Replace(bytes))
+            },
+        )
+    })?;
+
+    writer.write_all(&prefix)?;
+    writer.write_all(&moov)?;
+    BoxHeader::new(BoxType::MdatBox, box_size(mdat_size)).write(writer)?;
+    for (layout, (_, source)) in layouts.iter().zip(inputs.iter_mut()) {
+        layout.copy(source, writer)?;
+    }
+
+    Ok(())
+}
+
+fn check_compatible(first: &TrakBox, other: &TrakBox) -> Result<()> {
+    if other.mdia.hdlr.handler_type != first.mdia.hdlr.handler_type {
+        return Err(Error::InvalidData(
+            "tracks to concatenate have different handler types",
+        ));
+    }
+    if other.mdia.mdhd.timescale != first.mdia.mdhd.timescale {
+        return Err(Error::InvalidData(
+            "tracks to concatenate have different timescales",
+        ));
+    }
+    if other.mdia.minf.stbl.stsd != first.mdia.minf.stbl.stsd {
+        return Err(Error::InvalidData(
+            "tracks to concatenate have different sample descriptions",
+        ));
+    }
+    Ok(())
+}
+
+/// The headers and sample tables of a track that spans all inputs.
+struct MergedTrack {
+    tkhd: TkhdBox,
+    edts: Option<EdtsBox>,
+
+    /// Whether `edts` is new, because only inputs after the first have an edit list.
+    insert_edts: bool,
+    mdhd: MdhdBox,
+    stts: SttsBox,
+    ctts: Option<CttsBox>,
+    stss: Option<StssBox>,
+    stsc: StscBox,
+    stsz: StszBox,
+}
+
+impl MergedTrack {
+    fn new(first: &TrakBox, mvhd: &MvhdBox, traks: &[&TrakBox]) -> Result<Self> {
+        let stbls: Vec<&StblBox> = traks.iter().map(|trak| &trak.mdia.minf.stbl).collect();
+        let mut stts = SttsBox::default();
+        let mut ctts = CttsBox::default();
+        let mut stss = StssBox::default();
+        let mut stsc = StscBox::default();
+        let mut stsz = StszBox {
+            sample_size: stbls.first().map_or(0, |stbl| stbl.stsz.sample_size),
+            ..Default::default()
+        };
+        let has_ctts = stbls.iter().any(|stbl| stbl.ctts.is_some());
+        let has_stss = stbls.iter().any(|stbl| stbl.stss.is_some());
+        let constant_sample_size = stbls
+            .iter()
+            .all(|stbl| stbl.stsz.sample_size != 0 && stbl.stsz.sample_size == stsz.sample_size);
+        if !constant_sample_size {
+            stsz.sample_size = 0;
+        }
+
+        let mut sample_count = 0u32;
+        let mut chunk_count = 0u32;
+        let mut media_durations = Vec::with_capacity(stbls.len());
+        for stbl in &stbls {
+            let samples = stbl.stsz.sample_count;
+            let end_sample = sample_count.checked_add(samples).ok_or_else(overflow)?;
+
+            let mut media_duration = 0u64;
+            for entry in &stbl.stts.entries {
+                push_run(&mut stts.entries, entry.clone(), |a, b| {
+                    let count = a.sample_count.checked_add(b.sample_count)?;
+                    (a.sample_delta == b.sample_delta).then_some(SttsEntry {
+                        sample_count: count,
+                        sample_delta: a.sample_delta,
+                    })
+                });
+                media_duration = media_duration
+                    .checked_add(u64::from(entry.sample_count) * u64::from(entry.sample_delta))
+                    .ok_or_else(overflow)?;
+            }
+            media_durations.push(media_duration);
+
+            if has_ctts {
+                let default_entries = [CttsEntry {
+                    sample_count: samples,
+                    sample_offset: 0,
+                }];
+                let entries = match &stbl.ctts {
+                    Some(input_ctts) => {
+                        ctts.version = ctts.version.max(input_ctts.version);
+                        &input_ctts.entries[..]
+                    }
+                    None => &default_entries[..],
+                };
+                for entry in entries {
+                    push_run(&mut ctts.entries, entry.clone(), |a, b| {
+                        let count = a.sample_count.checked_add(b.sample_count)?;
+                        (a.sample_offset == b.sample_offset).then_some(CttsEntry {
+                            sample_count: count,
+                            sample_offset: a.sample_offset,
+                        })
+                    });
+                }
+            }
+
+            if has_stss {
+                match &stbl.stss {
+                    Some(input_stss) => {
+                        for &n in &input_stss.entries {
+                            let n = n.checked_add(sample_count).ok_or_else(overflow)?;
+                            stss.entries.push(n);
+                        }
+                    }
+                    // Every sample of a track without `stss` is a sync sample.
+                    None => stss.entries.extend(sample_count + 1..=end_sample),
+                }
+            }
+
+            for entry in &stbl.stsc.entries {
+                push_run(
+                    &mut stsc.entries,
+                    StscEntry {
+                        first_chunk: entry
+                            .first_chunk
+                            .checked_add(chunk_count)
+                            .ok_or_else(overflow)?,
+                        first_sample: entry
+                            .first_sample
+                            .checked_add(sample_count)
+                            .ok_or_else(overflow)?,
+                        ..entry.clone()
+                    },
+                    |a, b| {
+                        (a.samples_per_chunk == b.samples_per_chunk
+                            && a.sample_description_index == b.sample_description_index)
+                            .then(|| a.clone())
+                    },
+                );
+            }
+
+            if !constant_sample_size {
+                for index in 0..samples as usize {
+                    stsz.sample_sizes.push(
+                        stbl.stsz
+                            .sample_size(index)
+                            .ok_or(Error::InvalidData("stsz has fewer sizes than samples"))?,
+                    );
+                }
+            }
+
+            sample_count = end_sample;
+            chunk_count = u32::try_from(super::chunk_count(stbl))
+                .ok()
+                .and_then(|chunks| chunk_count.checked_add(chunks))
+                .ok_or_else(overflow)?;
+        }
+        stsz.sample_count = sample_count;
+
+        let mut mdhd = first.mdia.mdhd.clone();
+        mdhd.duration = media_durations
+            .iter()
+            .try_fold(0u64, |sum, &duration| sum.checked_add(duration))
+            .ok_or_else(overflow)?;
+        if mdhd.duration > u64::from(u32::MAX) {
+            mdhd.version = 1;
+        }
+
+        let mut tkhd = first.tkhd.clone();
+        let edits = merged_edits(mvhd, traks, &media_durations)?;
+        let edts = if let Some(entries) = edits {
+            let mut edts = first.edts.clone().unwrap_or_default();
+            tkhd.duration = entries
+                .iter()
+                .try_fold(0u64, |sum, entry| sum.checked_add(entry.segment_duration))
+                .ok_or_else(overflow)?;
+            edts.elst = Some(elst_box(entries));
+            Some(edts)
+        } else {
+            // Everything after the first input extends the duration of the first input.
+            let media_timescale = u64::from(first.mdia.mdhd.timescale.max(1));
+            let extra_movie_duration = media_durations
+                .iter()
+                .skip(1)
+                .try_fold(0u64, |sum, &duration| sum.checked_add(duration))
+                .and_then(|duration| duration.checked_mul(u64::from(mvhd.timescale)))
+                .ok_or_else(overflow)?
+                / media_timescale;
+            tkhd.duration = tkhd
+                .duration
+                .checked_add(extra_movie_duration)
+                .ok_or_else(overflow)?;
+            None
+        };
+        if tkhd.duration > u64::from(u32::MAX) {
+            tkhd.version = 1;
+        }
+
+        Ok(Self {
+            tkhd,
+            insert_edts: first.edts.is_none() && edts.is_some(),
+            edts,
+            mdhd,
+            stts,
+            ctts: has_ctts.then_some(ctts),
+            stss: has_stss.then_some(stss),
+            stsc,
+            stsz,
+        })
+    }
+
+    /// Serializes `stbl` with the merged sample tables.
+    ///
+    /// `stsd` and `chunk_offsets` are complete, serialized boxes.
+    fn stbl(&self, stsd: &[u8], chunk_offsets: &[u8]) -> Result<Vec<u8>> {
+        let mut contents = stsd.to_vec();
+        self.stts.write_box(&mut contents)?;
+        if let Some(ctts) = &self.ctts {
+            ctts.write_box(&mut contents)?;
+        }
+        if let Some(stss) = &self.stss {
+            stss.write_box(&mut contents)?;
+        }
+        self.stsc.write_box(&mut contents)?;
+        self.stsz.write_box(&mut contents)?;
+        contents.extend(chunk_offsets);
+        wrap_box(BoxType::StblBox, contents)
+    }
+}
+
+/// The error for sample tables or durations that don't fit their fields once merged.
+fn overflow() -> Error {
+    Error::InvalidData("merged sample tables overflow")
+}
+
+/// The edit lists of `traks` joined into one, or `None` if none of them has one.
+///
+/// Inputs without an edit list present all of their media. The media times of each input are
+/// moved past the media of the inputs before it, whose durations are `media_durations`, and
+/// edits that continue where the previous one ends are merged.
+fn merged_edits(
+    mvhd: &MvhdBox,
+    traks: &[&TrakBox],
+    media_durations: &[u64],
+) -> Result<Option<Vec<ElstEntry>>> {
+    let elsts: Vec<Option<&ElstBox>> = traks
+        .iter()
+        .map(|trak| {
+            let elst = trak.edts.as_ref()?.elst.as_ref()?;
+            (!elst.entries.is_empty()).then_some(elst)
+        })
+        .collect();
+    if elsts.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+
+    let movie_timescale = u128::from(mvhd.timescale.max(1));
+    let media_timescale = u128::from(
+        traks
+            .first()
+            .map_or(1, |trak| trak.mdia.mdhd.timescale.max(1)),
+    );
+    // Durations in the movie timescale are rounded, so edits may end up to a tick apart.
+    let tolerance = media_timescale.div_ceil(movie_timescale);
+    let continues = |a: &ElstEntry, b: &ElstEntry| {
+        let end = u128::from(a.media_time)
+            + u128::from(a.segment_duration) * media_timescale / movie_timescale;
+        (a.media_rate, a.media_rate_fraction) == (b.media_rate, b.media_rate_fraction)
+            && end.abs_diff(u128::from(b.media_time)) < tolerance.max(1)
+    };
+
+    // Empty edits are `u64::MAX` until the version of the merged list is known.
+    let mut entries: Vec<ElstEntry> = Vec::new();
+    let mut media_offset = 0u64;
+    for (elst, &media_duration) in elsts.iter().zip(media_durations) {
+        let input_entries = if let Some(elst) = elst {
+            elst.entries
+                .iter()
+                .map(|entry| {
+                    let media_time = if elst.is_empty_edit(entry) {
+                        u64::MAX
+                    } else {
+                        entry
+                            .media_time
+                            .checked_add(media_offset)
+                            .filter(|&time| time != u64::MAX)
+                            .ok_or_else(overflow)?
+                    };
+                    Ok(ElstEntry {
+                        media_time,
+                        ..entry.clone()
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            let segment_duration =
+                u64::try_from(u128::from(media_duration) * movie_timescale / media_timescale)
+                    .map_err(|_err| overflow())?;
+            vec![ElstEntry {
+                segment_duration,
+                media_time: media_offset,
+                media_rate: 1,
+                media_rate_fraction: 0,
+            }]
+        };
+        for entry in input_entries {
+            push_run(&mut entries, entry, |a, b| {
+                let is_media = a.media_time != u64::MAX && b.media_time != u64::MAX;
+                let segment_duration = a.segment_duration.checked_add(b.segment_duration)?;
+                (is_media && continues(a, b)).then_some(ElstEntry {
+                    segment_duration,
+                    ..a.clone()
+                })
+            });
+        }
+        media_offset = media_offset
+            .checked_add(media_duration)
+            .ok_or_else(overflow)?;
+    }
+    Ok(Some(entries))
+}
+
+/// An `elst` with `entries` from [`merged_edits`], in the smallest version that holds them.
+fn elst_box(mut entries: Vec<ElstEntry>) -> ElstBox {
+    let is_wide = entries.iter().any(|entry| {
+        entry.segment_duration > u64::from(u32::MAX)
+            || (entry.media_time != u64::MAX && entry.media_time >= u64::from(u32::MAX))
+    });
+    if !is_wide {
+        for entry in &mut entries {
+            entry.media_time = entry.media_time.min(u64::from(u32::MAX));
+        }
+    }
+    ElstBox {
+        version: u8::from(is_wide),
+        flags: 0,
+        entries,
+    }
+}
@@ -0,0 +1,288 @@
+use std::io::{Read, Seek, Write};
+
+use super::{
+    box_size, check_self_contained, chunk_offset_box, push_run, read_children, read_range,
+    rewrite_box, rewrite_moov_until_stable, split_top_level, wrap_box, ChunkLayout, Rewrite,
+};
+use crate::{
+    BoxHeader, BoxType, CttsBox, CttsEntry, Error, MdhdBox, Mp4, ReadBox as _, Result, StscBox,
+    StscEntry, StssBox, StszBox, SttsBox, SttsEntry, TkhdBox, Track, TrakBox, WriteBox as _,
+};
+
+/// Flattens a fragmented file into a progressive one, with `moov` ahead of a single `mdat`.
+///
+/// `source` must be the file `mp4` was parsed from. The sample tables of every track are
+/// regenerated from its merged list of samples, so that tools which cannot handle `moof`
+/// see all samples. Samples that were stored back to back become one chunk, and chunks are
+/// copied in their original order. `mvex` and all fragment-level boxes are left out, and the
+/// durations in `mvhd`, `tkhd` and `mdhd` are set to the total duration of the samples.
+///
+/// Only the tracks still present in `mp4.moov` are written (see [`Mp4::retain_tracks`]).
+/// Progressive files are accepted as well, and come out with rebuilt sample tables.
+pub fn defragment<R: Read + Seek, W: Write>(
+    mp4: &Mp4,
+    source: &mut R,
+    writer: &mut W,
+) -> Result<()> {
+    check_self_contained(mp4)?;
+
+    let tracks = mp4
+        .moov
+        .traks
+        .iter()
+        .map(|trak| {
+            let track_id = trak.tkhd.track_id;
+            let track = mp4
+                .tracks()
+                .get(&track_id)
+                .ok_or(Error::TrakNotFound(track_id))?;
+            SampleTables::new(trak, track, mp4.moov.mvhd.timescale)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let chunks: Vec<Vec<(u64, u64)>> = tracks.iter().map(|t| t.chunks.clone()).collect();
+    let layout = ChunkLayout::from_chunks(&chunks);
+    let mdat_header_size = box_size(layout.size) - layout.size;
+
+    let mut mvhd = mp4.moov.mvhd.clone();
+    mvhd.duration = tracks.iter().map(|t| t.tkhd.duration).max().unwrap_or(0);
+    if mvhd.duration > u64::from(u32::MAX) {
+        mvhd.version = 1;
+    }
+
+    let (prefix, moov) = split_top_level(source)?;
+    let moov = rewrite_moov_until_stable(0, |moov_size| {
+        let base = prefix.len() as u64 + moov_size + mdat_header_size;
+        let mut current = None;
+        rewrite_box(
+            source,
+            &moov,
+            &mut Vec::new(),
+            &mut |reader, path, child| {
+                let mut bytes = Vec::new();
+                match (path, child.name) {
+                    ([BoxType::MoovBox], BoxType::MvhdBox) => {
+                        mvhd.write_box(&mut bytes)?;
+                    }
+                    ([BoxType::MoovBox], BoxType::MvexBox) => return Ok(Rewrite::Drop),
+                    ([BoxType::MoovBox], BoxType::TrakBox) => {
+                        let track_id = TrakBox::read_box(reader, child.size)?.tkhd.track_id;
+                        current = mp4
+                            .moov
+                            .traks
+                            .iter()
+                            .position(|trak| trak.tkhd.track_id == track_id);
+                        return Ok(if current.is_some() {
+                            Rewrite::Descend
+                        } else {
+                            Rewrite::Drop
+                        });
+                    }
+                    ([BoxType::MoovBox, BoxType::TrakBox], BoxType::MdiaBox)
+                    | ([.., BoxType::MdiaBox], BoxType::MinfBox) => return Ok(Rewrite::Descend),
+                    ([BoxType::MoovBox, BoxType::TrakBox], BoxType::TkhdBox) => {
+                        let Some(current) = current else {
+                            return Ok(Rewrite::Copy);
+                        };
+                        tracks[current].tkhd.write_box(&mut bytes)?;
+                    }
+                    ([.., BoxType::MdiaBox], BoxType::MdhdBox) => {
+                        let Some(current) = current else {
+                            return Ok(Rewrite::Copy);
+                        };
+                        tracks[current].mdhd.write_box(&mut bytes)?;
+                    }
+                    ([.., BoxType::MinfBox], BoxType::StblBox) => {
+                        let Some(current) = current else {
+                            return Ok(Rewrite::Copy);
+                        };
+                        let stsd = read_children(reader, child.data_start, child.end)?
+                            .into_iter()
+                            .find(|stbl_child| stbl_child.name == BoxType::StsdBox)
+                            .ok_or(Error::BoxNotFound(BoxType::StsdBox))?;
+                        let stsd = read_range(reader, stsd.start, stsd.end)?;
+                        let offsets = layout.relative_offsets[current]
+                            .iter()
+                            .map(|offset| base + offset)
+                            .collect();
+                        let chunk_offsets = chunk_offset_box(offsets, false)?;
+                        bytes = tracks[current].stbl(&stsd, &chunk_offsets)?;
+                    }
+                    _ => return Ok(Rewrite::Copy),
+                }
+                Ok(Rewrite::Replace(bytes))
+            },
+        )
+    })?;
+
+    writer.write_all(&prefix)?;
+    writer.write_all(&moov)?;
+    BoxHeader::new(BoxType::MdatBox, box_size(layout.size)).write(writer)?;
+    layout.copy(source, writer)?;
+
+    Ok(())
+}
+
+/// The headers and sample tables of a track, built from its list of samples.
+struct SampleTables {
+    tkhd: TkhdBox,
+    mdhd: MdhdBox,
+    stts: SttsBox,
+    ctts: Option<CttsBox>,
+    stss: Option<StssBox>,
+    stsc: StscBox,
+    stsz: StszBox,
+
+    /// Source offset and size of every chunk.
+    chunks: Vec<(u64, u64)>,
+}
+
+impl SampleTables {
+    fn new(trak: &TrakBox, track: &Track, movie_timescale: u32) -> Result<Self> {
+        let mut stts = SttsBox::default();
+        let mut ctts = CttsBox::default();
+        let mut stss = StssBox::default();
+        let mut stsc = StscBox::default();
+        let mut stsz = StszBox {
+            sample_count: track.samples.len() as u32,
+            ..Default::default()
+        };
+        let mut chunks: Vec<(u64, u64)> = Vec::new();
+        let mut samples_per_chunk = Vec::new();
+
+        let mut media_duration = 0u64;
+        for (index, sample) in track.samples.iter().enumerate() {
+            let sample_delta = u32::try_from(sample.duration)
+                .map_err(|_err| Error::InvalidData("sample duration does not fit in 32 bits"))?;
+            push_run(
+                &mut stts.entries,
+                SttsEntry {
+                    sample_count: 1,
+                    sample_delta,
+                },
+                |a, b| {
+                    (a.sample_delta == b.sample_delta).then(|| SttsEntry {
+                        sample_count: a.sample_count + b.sample_count,
+                        sample_delta: a.sample_delta,
+                    })
+                },
+            );
+            media_duration = media_duration
+                .checked_add(sample.duration)
+                .ok_or(Error::InvalidData("track duration does not fit in 64 bits"))?;
+
+            let sample_offset = i32::try_from(
+                i128::from(sample.composition_timestamp) - i128::from(sample.decode_timestamp),
+            )
+            .map_err(|_err| Error::InvalidData("composition offset does not fit in 32 bits"))?;
+            if sample_offset < 0 {
+                ctts.version = 1;
+            }
+            push_run(
+                &mut ctts.entries,
+                CttsEntry {
+                    sample_count: 1,
+                    sample_offset,
+                },
+                |a, b| {
+                    (a.sample_offset == b.sample_offset).then(|| CttsEntry {
+                        sample_count: a.sample_count + b.sample_count,
+                        sample_offset: a.sample_offset,
+                    })
+                },
+            );
+
+            if sample.is_sync {
+                stss.entries.push(index as u32 + 1);
+            }
+
+            let size = u32::try_from(sample.size)
+                .map_err(|_err| Error::InvalidData("sample size does not fit in 32 bits"))?;
+            stsz.sample_sizes.push(size);
+
+            // Samples that are stored back to back share a chunk.
+            match (chunks.last_mut(), samples_per_chunk.last_mut()) {
+                (Some((offset, chunk_size)), Some(count))
+                    if offset.checked_add(*chunk_size) == Some(sample.offset) =>
+                {
+                    *chunk_size += sample.size;
+                    *count += 1;
+                }
+                _ => {
+                    chunks.push((sample.offset, sample.size));
+                    samples_per_chunk.push(1);
+                }
+            }
+        }
+
+        let mut first_sample = 1;
+        for (index, &count) in samples_per_chunk.iter().enumerate() {
+            push_run(
+                &mut stsc.entries,
+                StscEntry {
+                    first_chunk: index as u32 + 1,
+                    samples_per_chunk: count,
+                    sample_description_index: 1,
+                    first_sample,
+                },
+                |a, b| (a.samples_per_chunk == b.samples_per_chunk).then(|| a.clone()),
+            );
+            first_sample += count;
+        }
+
+        if let Some(&size) = stsz.sample_sizes.first() {
+            if stsz.sample_sizes.iter().all(|&s| s == size) {
+                stsz.sample_size = size;
+                stsz.sample_sizes.clear();
+            }
+        }
+
+        let mut mdhd = trak.mdia.mdhd.clone();
+        mdhd.duration = media_duration;
+        if mdhd.duration > u64::from(u32::MAX) {
+            mdhd.version = 1;
+        }
+
+        let mut tkhd = trak.tkhd.clone();
+        tkhd.duration = u64::try_from(
+            u128::from(media_duration) * u128::from(movie_timescale)
+                / u128::from(mdhd.timescale.max(1)),
+        )
+        .map_err(|_err| Error::InvalidData("track duration does not fit in 64 bits"))?;
+        if tkhd.duration > u64::from(u32::MAX) {
+            tkhd.version = 1;
+        }
+
+        let has_ctts = ctts.entries.iter().any(|entry| entry.sample_offset != 0);
+        // Without `stss`, every sample is a sync sample.
+        let has_stss = stss.entries.len() != track.samples.len();
+        Ok(Self {
+            tkhd,
+            mdhd,
+            stts,
+            ctts: has_ctts.then_some(ctts),
+            stss: has_stss.then_some(stss),
+            stsc,
+            stsz,
+            chunks,
+        })
+    }
+
+    /// Serializes `stbl` with the new sample tables.
+    ///
+    /// `stsd` and `chunk_offsets` are complete, serialized boxes.
+    fn stbl(&self, stsd: &[u8], chunk_offsets: &[u8]) -> Result<Vec<u8>> {
+        let mut contents = stsd.to_vec();
+        self.stts.write_box(&mut contents)?;
+        if let Some(ctts) = &self.ctts {
+            ctts.write_box(&mut contents)?;
+        }
+        if let Some(stss) = &self.stss {
+            stss.write_box(&mut contents)?;
+        }
+        self.stsc.write_box(&mut contents)?;
+        self.stsz.write_box(&mut contents)?;
+        contents.extend(chunk_offsets);
+        wrap_box(BoxType::StblBox, contents)
+    }
+}
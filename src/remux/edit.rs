@@ -0,0 +1,244 @@
+//! Editing the iTunes tags of a file in place, without copying its media data.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::m4a::{cover_item, itunes_meta, text_item};
+use super::{read_children, read_range, wrap_box, BoxRange};
+use crate::free_space::is_space;
+use crate::mp4box::meta;
+use crate::{BoxType, Error, FreeSpace, Result};
+
+/// Changes the iTunes tags in `moov/udta/meta/ilst` of an existing file, like its title or
+/// cover art, by rewriting only `moov`.
+///
+/// The media data stays where it is, so no chunk offsets change. The new `moov` goes into the
+/// first space that fits it, which is the old `moov` together with any `free` boxes around it,
+/// or another `free` box, and the space that is left is marked as `free`, see [`FreeSpace`].
+/// Otherwise the new `moov` is appended to the end of the file.
+///
+/// ```no_run
+/// # fn main() -> re_mp4::Result<()> {
+/// let mut file = std::fs::File::options().read(true).write(true).open("song.m4a")?;
+/// let mut editor = re_mp4::remux::Mp4Editor::new();
+/// editor.set_title(Some("Intro")).set_year(Some(2024));
+/// editor.apply(&mut file)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mp4Editor {
+    /// The new values of the items of `ilst` by item type, or `None` for items to remove.
+    values: BTreeMap<[u8; 4], Option<Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Text(String),
+    Cover(Vec<u8>),
+}
+
+/// The serialized items of `ilst` by item type, or `None` for items to remove.
+struct Items(BTreeMap<[u8; 4], Option<Vec<u8>>>);
+
+impl Mp4Editor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the title (`©nam`), or removes it if `None`.
+    pub fn set_title(&mut self, title: Option<&str>) -> &mut Self {
+        let title = title.map(|title| Value::Text(title.to_owned()));
+        self.values.insert(*b"\xa9nam", title);
+        self
+    }
+
+    /// Sets the release year (`©day`), or removes it if `None`.
+    pub fn set_year(&mut self, year: Option<u32>) -> &mut Self {
+        let year = year.map(|year| Value::Text(year.to_string()));
+        self.values.insert(*b"\xa9day", year);
+        self
+    }
+
+    /// Sets the cover art (`covr`) to a JPEG or PNG image, or removes it if `None`.
+    pub fn set_cover(&mut self, cover: Option<&[u8]>) -> &mut Self {
+        let cover = cover.map(|cover| Value::Cover(cover.to_vec()));
+        self.values.insert(*b"covr", cover);
+        self
+    }
+
+    /// Writes the edits to `file`, which must hold a whole MP4 file and be open for writing.
+    ///
+    /// The file never shrinks. Items of `ilst` that aren't edited are kept as they are, as are
+    /// all other boxes. Items are added to `moov/udta/meta/ilst`, which is created if needed,
+    /// and edited items are removed from the `ilst` of `moov/meta` as well, so that they don't
+    /// take precedence.
+    pub fn apply<F: Read + Write + Seek>(&self, file: &mut F) -> Result<()> {
+        let mut items = BTreeMap::new();
+        for (&item_type, value) in &self.values {
+            let item = match value {
+                Some(Value::Text(text)) => Some(text_item(item_type, text)?),
+                Some(Value::Cover(cover)) => Some(cover_item(cover)?),
+                None => None,
+            };
+            items.insert(item_type, item);
+        }
+        let items = Items(items);
+
+        let end = file.seek(SeekFrom::End(0))?;
+        let top_level = read_children(file, 0, end)?;
+        let Some(moov_index) = top_level.iter().position(|b| b.name == BoxType::MoovBox) else {
+            return Err(Error::BoxNotFound(BoxType::MoovBox));
+        };
+        let moov = &top_level[moov_index];
+        let new_moov = items.rewrite_moov(file, moov)?;
+
+        // `moov` goes wherever it fits first, which may be where it was.
+        let mut space = FreeSpace::new(end);
+        for child in &top_level {
+            if is_space(child.name) {
+                space.release(child.start, child.end - child.start);
+            }
+        }
+        space.release(moov.start, moov.end - moov.start);
+        let offset = space.allocate(new_moov.len() as u64);
+        if offset >= end {
+            if let Some(last) = top_level.last() {
+                let mut size = [0; 4];
+                file.seek(SeekFrom::Start(last.start))?;
+                file.read_exact(&mut size)?;
+                if size == [0; 4] {
+                    return Err(Error::InvalidData(
+                        "the last box extends to the end of the file",
+                    ));
+                }
+            }
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&new_moov)?;
+        space.write_free_boxes(file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Items {
+    fn rewrite_moov<R: Read + Seek>(&self, reader: &mut R, moov: &BoxRange) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        let mut has_udta = false;
+        for child in read_children(reader, moov.data_start, moov.end)? {
+            match child.name {
+                BoxType::UdtaBox if !has_udta => {
+                    has_udta = true;
+                    contents.extend(self.rewrite_udta(reader, &child)?);
+                }
+                BoxType::MetaBox => contents.extend(self.rewrite_meta(reader, &child, false)?),
+                _ => contents.extend(read_range(reader, child.start, child.end)?),
+            }
+        }
+        if !has_udta {
+            let meta = self.new_meta()?;
+            if !meta.is_empty() {
+                contents.extend(wrap_box(BoxType::UdtaBox, meta)?);
+            }
+        }
+        wrap_box(BoxType::MoovBox, contents)
+    }
+
+    fn rewrite_udta<R: Read + Seek>(&self, reader: &mut R, udta: &BoxRange) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        let mut has_meta = false;
+        for child in read_children(reader, udta.data_start, udta.end)? {
+            if child.name == BoxType::MetaBox && !has_meta {
+                has_meta = true;
+                contents.extend(self.rewrite_meta(reader, &child, true)?);
+            } else {
+                contents.extend(read_range(reader, child.start, child.end)?);
+            }
+        }
+        if !has_meta {
+            contents.extend(self.new_meta()?);
+        }
+        wrap_box(BoxType::UdtaBox, contents)
+    }
+
+    /// Rewrites the `ilst` of `meta`, adding the new items if `add` is set and only removing the
+    /// edited ones otherwise.
+    fn rewrite_meta<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        meta: &BoxRange,
+        add: bool,
+    ) -> Result<Vec<u8>> {
+        // `QuickTime` leaves out the version and flags of `meta`, which then starts with `hdlr`.
+        let header = read_range(reader, meta.data_start, (meta.data_start + 8).min(meta.end))?;
+        let offset = meta::children_offset(&header)
+            .ok_or(Error::UnsupportedBoxVersion(BoxType::MetaBox, header[0]))?;
+        let children_start = (meta.data_start + offset).min(meta.end);
+        let mut contents = read_range(reader, meta.data_start, children_start)?;
+        let mut has_ilst = false;
+        for child in read_children(reader, children_start, meta.end)? {
+            if child.name == BoxType::IlstBox && !has_ilst {
+                has_ilst = true;
+                contents.extend(self.rewrite_ilst(reader, &child, add)?);
+            } else {
+                contents.extend(read_range(reader, child.start, child.end)?);
+            }
+        }
+        if !has_ilst && add {
+            let items = self.new_items(&[]);
+            if !items.is_empty() {
+                contents.extend(wrap_box(BoxType::IlstBox, items)?);
+            }
+        }
+        wrap_box(BoxType::MetaBox, contents)
+    }
+
+    fn rewrite_ilst<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        ilst: &BoxRange,
+        add: bool,
+    ) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        let mut replaced = Vec::new();
+        for child in read_children(reader, ilst.data_start, ilst.end)? {
+            reader.seek(SeekFrom::Start(child.start + 4))?;
+            let mut item_type = [0; 4];
+            reader.read_exact(&mut item_type)?;
+            match self.0.get(&item_type) {
+                // Edited items stay where they were, and duplicates are removed.
+                Some(item) => {
+                    if add && !replaced.contains(&item_type) {
+                        contents.extend(item.iter().flatten());
+                    }
+                    replaced.push(item_type);
+                }
+                None => contents.extend(read_range(reader, child.start, child.end)?),
+            }
+        }
+        if add {
+            contents.extend(self.new_items(&replaced));
+        }
+        wrap_box(BoxType::IlstBox, contents)
+    }
+
+    /// A `meta` box with the new items, or nothing if there are none.
+    fn new_meta(&self) -> Result<Vec<u8>> {
+        let items = self.new_items(&[]);
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+        itunes_meta(items)
+    }
+
+    /// The serialized new items, except those of the types in `skip`.
+    fn new_items(&self, skip: &[[u8; 4]]) -> Vec<u8> {
+        self.0
+            .iter()
+            .filter(|(item_type, _)| !skip.contains(item_type))
+            .flat_map(|(_, item)| item.iter().flatten().copied())
+            .collect()
+    }
+}
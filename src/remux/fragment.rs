@@ -0,0 +1,366 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use super::{
+    box_size, check_self_contained, copy_range, read_children, read_range, rewrite_children,
+    wrap_box, Rewrite,
+};
+use crate::{
+    BoxHeader, BoxType, Error, MehdBox, MfhdBox, MoofBox, Mp4, Mp4Box as _, MvexBox, ReadBox as _,
+    Result, Sample, SampleFlags, StcoBox, StscBox, StszBox, SttsBox, TfdtBox, TfhdBox, Track,
+    TrackKind, TrafBox, TrakBox, TrexBox, TrunBox, WriteBox as _,
+};
+
+/// A file split up by [`fragment`].
+#[derive(Debug, Clone, Default)]
+pub struct Fragments {
+    /// `ftyp` and a `moov` without any samples.
+    pub init_segment: Vec<u8>,
+
+    /// The samples of all tracks, as consecutive `moof` + `mdat` pairs.
+    pub media_segments: Vec<MediaSegment>,
+}
+
+/// A `moof` followed by its `mdat`.
+#[derive(Debug, Clone)]
+pub struct MediaSegment {
+    /// Decode time of the first sample of the reference track, relative to the first segment.
+    pub start: Duration,
+
+    /// Time until the next segment starts, or until the end of the reference track for the last one.
+    pub duration: Duration,
+
+    /// The encoded `moof` and `mdat` boxes.
+    pub data: Vec<u8>,
+}
+
+/// A piece of the output of [`fragment_streaming`].
+#[derive(Debug, Clone)]
+pub enum Segment {
+    /// `ftyp` and a `moov` without any samples, which always comes first.
+    Init(Vec<u8>),
+
+    /// The next media segment.
+    Media(MediaSegment),
+}
+
+/// Splits `mp4` into an init segment and media segments, as used for streaming with e.g. DASH or HLS.
+///
+/// `source` must be the file `mp4` was parsed from. Segments are cut at the sync samples of a
+/// reference track (the first video track, or else the first track with samples), each one
+/// starting at the first sync sample after at least `target_segment_duration` has passed.
+/// The samples of all other tracks go into the segment during which they are decoded.
+///
+/// The init segment keeps all boxes of `moov` except for the sample tables, which are emptied,
+/// and `mvex`, which is replaced. Only the tracks still present in `mp4.moov` are written
+/// (see [`Mp4::retain_tracks`]).
+pub fn fragment<R: Read + Seek>(
+    mp4: &Mp4,
+    source: &mut R,
+    target_segment_duration: Duration,
+) -> Result<Fragments> {
+    let mut fragments = Fragments::default();
+    fragment_streaming(mp4, source, target_segment_duration, |segment| {
+        match segment {
+            Segment::Init(data) => fragments.init_segment = data,
+            Segment::Media(segment) => fragments.media_segments.push(segment),
+        }
+        Ok(())
+    })?;
+    Ok(fragments)
+}
+
+/// Like [`fragment`], but hands every segment to `on_segment` as soon as it is written, instead
+/// of keeping all of them in memory.
+///
+/// This is meant for playing progressive files through Media Source Extensions: append the
+/// segments to a `SourceBuffer` in the order they arrive. Any error returned by `on_segment`
+/// stops the fragmentation and is passed on.
+pub fn fragment_streaming<R: Read + Seek>(
+    mp4: &Mp4,
+    source: &mut R,
+    target_segment_duration: Duration,
+    mut on_segment: impl FnMut(Segment) -> Result<()>,
+) -> Result<()> {
+    check_self_contained(mp4)?;
+
+    let tracks = mp4
+        .moov
+        .traks
+        .iter()
+        .map(|trak| {
+            let track_id = trak.tkhd.track_id;
+            mp4.tracks()
+                .get(&track_id)
+                .ok_or(Error::TrakNotFound(track_id))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    on_segment(Segment::Init(init_segment(mp4, source)?))?;
+
+    let reference = tracks
+        .iter()
+        .find(|track| track.kind == Some(TrackKind::Video) && !track.samples.is_empty())
+        .or_else(|| tracks.iter().find(|track| !track.samples.is_empty()));
+    let Some(reference) = reference else {
+        return Ok(());
+    };
+
+    let boundaries = segment_boundaries(reference, target_segment_duration);
+    let reference_end = match reference.samples.last() {
+        Some(sample) => i64::try_from(sample.duration)
+            .ok()
+            .and_then(|duration| sample.decode_timestamp.checked_add(duration))
+            .ok_or_else(timestamp_overflow)?,
+        None => 0,
+    };
+
+    // The samples of each track in each segment.
+    let mut segments: Vec<Vec<Vec<&Sample>>> =
+        vec![vec![Vec::new(); tracks.len()]; boundaries.len()];
+    for (track_index, track) in tracks.iter().enumerate() {
+        for sample in &track.samples {
+            let decode_time = i128::from(sample.decode_timestamp) * i128::from(reference.timescale);
+            let segment = boundaries
+                .partition_point(|&boundary| {
+                    i128::from(boundary) * i128::from(track.timescale) <= decode_time
+                })
+                .saturating_sub(1);
+            segments[segment][track_index].push(sample);
+        }
+    }
+
+    for (index, segment) in segments.iter().enumerate() {
+        let start = boundaries[index];
+        let end = boundaries.get(index + 1).copied().unwrap_or(reference_end);
+        let mut data = Vec::new();
+        write_media_segment(source, &tracks, segment, index as u32 + 1, &mut data)?;
+        let offset = start
+            .checked_sub(boundaries[0])
+            .ok_or_else(timestamp_overflow)?;
+        let duration = end.checked_sub(start).ok_or_else(timestamp_overflow)?;
+        on_segment(Segment::Media(MediaSegment {
+            start: to_duration(offset, reference.timescale),
+            duration: to_duration(duration, reference.timescale),
+            data,
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Writes `ftyp` and a `moov` with empty sample tables and a new `mvex`.
+fn init_segment<R: Read + Seek>(mp4: &Mp4, source: &mut R) -> Result<Vec<u8>> {
+    let end = source.seek(SeekFrom::End(0))?;
+    let top_level = read_children(source, 0, end)?;
+    let Some(moov) = top_level.iter().find(|b| b.name == BoxType::MoovBox) else {
+        return Err(Error::BoxNotFound(BoxType::MoovBox));
+    };
+
+    let mut bytes = Vec::new();
+    if let Some(ftyp) = top_level.iter().find(|b| b.name == BoxType::FtypBox) {
+        bytes.extend(read_range(source, ftyp.start, ftyp.end)?);
+    }
+
+    let keep = |track_id| {
+        mp4.moov
+            .traks
+            .iter()
+            .any(|trak| trak.tkhd.track_id == track_id)
+    };
+    let mut contents =
+        rewrite_children(source, moov, &mut Vec::new(), &mut |reader, path, child| {
+            Ok(match (path, child.name) {
+                ([BoxType::MoovBox], BoxType::MvexBox) => Rewrite::Drop,
+                ([BoxType::MoovBox], BoxType::TrakBox) => {
+                    if keep(TrakBox::read_box(reader, child.size)?.tkhd.track_id) {
+                        Rewrite::Descend
+                    } else {
+                        Rewrite::Drop
+                    }
+                }
+                ([BoxType::MoovBox, BoxType::TrakBox], BoxType::MdiaBox)
+                | ([.., BoxType::MdiaBox], BoxType::MinfBox) => Rewrite::Descend,
+                ([.., BoxType::MinfBox], BoxType::StblBox) => {
+                    let stsd = read_children(reader, child.data_start, child.end)?
+                        .into_iter()
+                        .find(|stbl_child| stbl_child.name == BoxType::StsdBox)
+                        .ok_or(Error::BoxNotFound(BoxType::StsdBox))?;
+                    let mut stbl = read_range(reader, stsd.start, stsd.end)?;
+                    SttsBox::default().write_box(&mut stbl)?;
+                    StscBox::default().write_box(&mut stbl)?;
+                    StszBox::default().write_box(&mut stbl)?;
+                    StcoBox::default().write_box(&mut stbl)?;
+                    Rewrite::Replace(wrap_box(BoxType::StblBox, stbl)?)
+                }
+                _ => Rewrite::Copy,
+            })
+        })?;
+
+    let duration = mp4.moov.mvhd.duration;
+    let mvex = MvexBox {
+        mehd: (duration != 0).then(|| MehdBox {
+            version: u8::from(duration > u64::from(u32::MAX)),
+            flags: 0,
+            fragment_duration: duration,
+        }),
+        trexs: mp4
+            .moov
+            .traks
+            .iter()
+            .map(|trak| TrexBox {
+                track_id: trak.tkhd.track_id,
+                default_sample_description_index: 1,
+                ..Default::default()
+            })
+            .collect(),
+    };
+    mvex.write_box(&mut contents)?;
+    bytes.extend(wrap_box(BoxType::MoovBox, contents)?);
+
+    Ok(bytes)
+}
+
+/// Decode timestamps of the reference track at which new segments start.
+fn segment_boundaries(reference: &Track, target_segment_duration: Duration) -> Vec<i64> {
+    let target = i128::try_from(target_segment_duration.as_nanos())
+        .unwrap_or(i128::MAX)
+        .saturating_mul(i128::from(reference.timescale));
+    let mut boundaries: Vec<i64> = Vec::new();
+    for sample in &reference.samples {
+        let starts_segment = boundaries.last().is_none_or(|&start| {
+            let elapsed = (i128::from(sample.decode_timestamp) - i128::from(start)) * 1_000_000_000;
+            sample.is_sync && elapsed >= target
+        });
+        if starts_segment {
+            boundaries.push(sample.decode_timestamp);
+        }
+    }
+    boundaries
+}
+
+/// Writes a `moof` with one `traf` for every track that has samples in the segment, followed by an `mdat` with those samples.
+fn write_media_segment<R: Read + Seek>(
+    source: &mut R,
+    tracks: &[&Track],
+    segment: &[Vec<&Sample>],
+    sequence_number: u32,
+    data: &mut Vec<u8>,
+) -> Result<()> {
+    let mut trafs = Vec::new();
+    let mut traf_sizes = Vec::new();
+    for (track, samples) in tracks.iter().zip(segment) {
+        let Some(first) = samples.first() else {
+            continue;
+        };
+        let track_start = track.samples.first().map_or(0, |s| s.decode_timestamp);
+        let base_media_decode_time =
+            u64::try_from(i128::from(first.decode_timestamp) - i128::from(track_start))
+                .map_err(|_err| Error::InvalidData("decode timestamps are not increasing"))?;
+
+        let mut trun = TrunBox {
+            flags: TrunBox::FLAG_DATA_OFFSET
+                | TrunBox::FLAG_SAMPLE_DURATION
+                | TrunBox::FLAG_SAMPLE_SIZE
+                | TrunBox::FLAG_SAMPLE_FLAGS,
+            sample_count: samples.len() as u32,
+            data_offset: Some(0),
+            ..Default::default()
+        };
+        for sample in samples {
+            let duration = u32::try_from(sample.duration)
+                .map_err(|_err| Error::InvalidData("sample duration does not fit in 32 bits"))?;
+            let size = u32::try_from(sample.size)
+                .map_err(|_err| Error::InvalidData("sample size does not fit in 32 bits"))?;
+            let cts =
+                i128::from(sample.composition_timestamp) - i128::from(sample.decode_timestamp);
+            let cts = i32::try_from(cts)
+                .map_err(|_err| Error::InvalidData("composition offset does not fit in 32 bits"))?;
+            if cts < 0 {
+                trun.version = 1;
+            }
+            trun.sample_durations.push(duration);
+            trun.sample_sizes.push(size);
+            trun.sample_flags.push(if sample.is_sync {
+                SampleFlags::SYNC.to_u32()
+            } else {
+                SampleFlags::NON_SYNC.to_u32()
+            });
+            trun.sample_cts.push(cts.cast_unsigned());
+        }
+        if trun.sample_cts.iter().any(|&cts| cts != 0) {
+            trun.flags |= TrunBox::FLAG_SAMPLE_CTS;
+        } else {
+            trun.sample_cts.clear();
+        }
+
+        trafs.push(TrafBox {
+            tfhd: TfhdBox {
+                flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+                track_id: track.track_id,
+                ..Default::default()
+            },
+            tfdt: Some(TfdtBox {
+                version: 1,
+                flags: 0,
+                base_media_decode_time,
+            }),
+            truns: vec![trun],
+        });
+        traf_sizes.push(
+            samples
+                .iter()
+                .try_fold(0u64, |sum, sample| sum.checked_add(sample.size))
+                .ok_or(Error::InvalidData("segment is too large"))?,
+        );
+    }
+
+    let mut moof = MoofBox {
+        start: 0,
+        mfhd: MfhdBox {
+            version: 0,
+            flags: 0,
+            sequence_number,
+        },
+        trafs,
+    };
+    let mdat_size = traf_sizes
+        .iter()
+        .try_fold(0u64, |sum, &size| sum.checked_add(size))
+        .ok_or(Error::InvalidData("segment is too large"))?;
+    let mut data_offset = moof.box_size() + box_size(mdat_size) - mdat_size;
+    for (traf, size) in moof.trafs.iter_mut().zip(&traf_sizes) {
+        for trun in &mut traf.truns {
+            trun.data_offset =
+                Some(i32::try_from(data_offset).map_err(|_err| {
+                    Error::InvalidData("segment is too large for a data offset")
+                })?);
+        }
+        data_offset += size;
+    }
+
+    moof.write_box(data)?;
+    BoxHeader::new(BoxType::MdatBox, box_size(mdat_size)).write(data)?;
+    for samples in segment {
+        for sample in samples {
+            let end = sample
+                .offset
+                .checked_add(sample.size)
+                .ok_or(Error::InvalidData(
+                    "sample extends past the end of the file",
+                ))?;
+            copy_range(source, data, sample.offset, end)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn timestamp_overflow() -> Error {
+    Error::InvalidData("sample timestamps do not fit in 64 bits")
+}
+
+fn to_duration(ticks: i64, timescale: u64) -> Duration {
+    let nanos = u128::from(ticks.unsigned_abs()) * 1_000_000_000 / u128::from(timescale.max(1));
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
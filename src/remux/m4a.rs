@@ -0,0 +1,470 @@
+//! Writing audio-only M4A files from scratch.
+
+use std::io::Write;
+use std::time::Duration;
+
+use super::{chunk_offset_box, push_run, wrap_box};
+use crate::{
+    BoxHeader, BoxType, DinfBox, Error, FourCC, FtypBox, HdlrBox, MdhdBox, MvhdBox, Result,
+    SmhdBox, StscBox, StscEntry, StszBox, SttsBox, SttsEntry, TkhdBox, WriteBox as _,
+};
+
+/// The audio codec of an [`M4aWriter`], with its decoder configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum M4aCodec {
+    /// AAC, configured by an `AudioSpecificConfig` from ISO/IEC 14496-3.
+    Aac { audio_specific_config: Vec<u8> },
+
+    /// Apple Lossless, configured by its 24-byte magic cookie (`ALACSpecificConfig`).
+    Alac { magic_cookie: Vec<u8> },
+}
+
+/// The iTunes tags of an M4A file, stored in `moov/udta/meta/ilst`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct M4aTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub composer: Option<String>,
+    pub genre: Option<String>,
+
+    /// Release date, usually just the year, e.g. `2024`.
+    pub year: Option<String>,
+
+    pub comment: Option<String>,
+
+    /// Track number, and total number of tracks if known.
+    pub track_number: Option<(u16, Option<u16>)>,
+
+    /// Cover art, as a JPEG or PNG image.
+    pub cover: Option<Vec<u8>>,
+}
+
+/// A chapter of an M4A file, see [`M4aWriter::add_chapter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chapter {
+    start: Duration,
+    title: String,
+}
+
+/// Writes audio-only M4A files, e.g. for music or podcasts, without having to know about the
+/// video oriented parts of MP4.
+///
+/// The samples are collected in memory, and written with [`Self::finish`] as a file with
+/// `moov` in front of the media data.
+#[derive(Debug, Clone)]
+pub struct M4aWriter {
+    codec: M4aCodec,
+    sample_rate: u32,
+    channel_count: u16,
+    tags: M4aTags,
+    chapters: Vec<Chapter>,
+    data: Vec<u8>,
+    sample_sizes: Vec<u32>,
+    stts: Vec<SttsEntry>,
+}
+
+impl M4aWriter {
+    /// Timestamps and durations of the samples are in units of `1 / sample_rate` seconds.
+    pub fn new(codec: M4aCodec, sample_rate: u32, channel_count: u16) -> Self {
+        Self {
+            codec,
+            sample_rate,
+            channel_count,
+            tags: M4aTags::default(),
+            chapters: Vec::new(),
+            data: Vec::new(),
+            sample_sizes: Vec::new(),
+            stts: Vec::new(),
+        }
+    }
+
+    pub fn set_tags(&mut self, tags: M4aTags) {
+        self.tags = tags;
+    }
+
+    /// Adds an encoded frame, e.g. an AAC access unit, that lasts `duration` audio samples.
+    ///
+    /// AAC frames are usually 1024 samples long, and ALAC frames 4096.
+    pub fn add_sample(&mut self, data: &[u8], duration: u32) -> Result<()> {
+        let size =
+            u32::try_from(data.len()).map_err(|_err| Error::InvalidData("sample too large"))?;
+        self.data.extend_from_slice(data);
+        self.sample_sizes.push(size);
+        push_run(
+            &mut self.stts,
+            SttsEntry {
+                sample_count: 1,
+                sample_delta: duration,
+            },
+            |last, entry| {
+                (last.sample_delta == entry.sample_delta).then(|| SttsEntry {
+                    sample_count: last.sample_count + 1,
+                    sample_delta: last.sample_delta,
+                })
+            },
+        );
+        Ok(())
+    }
+
+    /// Adds a chapter that starts at `start`, and lasts until the next one.
+    ///
+    /// Chapters are stored in a Nero `chpl` box, which holds at most 255 chapters with titles
+    /// of at most 255 bytes.
+    pub fn add_chapter(&mut self, start: Duration, title: impl Into<String>) -> Result<()> {
+        let title = title.into();
+        if self.chapters.len() == usize::from(u8::MAX) {
+            return Err(Error::InvalidData("too many chapters"));
+        }
+        if title.len() > usize::from(u8::MAX) {
+            return Err(Error::InvalidData("chapter title too long"));
+        }
+        self.chapters.push(Chapter { start, title });
+        Ok(())
+    }
+
+    /// Duration of the samples added so far, in units of `1 / sample_rate` seconds.
+    pub fn duration(&self) -> u64 {
+        self.stts
+            .iter()
+            .map(|entry| u64::from(entry.sample_count) * u64::from(entry.sample_delta))
+            .sum()
+    }
+
+    /// Writes the file.
+    pub fn finish<W: Write>(mut self, writer: &mut W) -> Result<()> {
+        self.chapters.sort_by_key(|chapter| chapter.start);
+
+        let mut ftyp = Vec::new();
+        FtypBox {
+            major_brand: FourCC::from(*b"M4A "),
+            minor_version: 0x200,
+            compatible_brands: [b"M4A ", b"mp42", b"isom"]
+                .into_iter()
+                .map(|brand| FourCC::from(*brand))
+                .collect(),
+        }
+        .write_box(&mut ftyp)?;
+
+        let mut mdat_header = Vec::new();
+        BoxHeader::new(BoxType::MdatBox, super::box_size(self.data.len() as u64))
+            .write(&mut mdat_header)?;
+
+        // The size of `moov` doesn't depend on the chunk offset, as long as it fits in `stco`.
+        let moov_size = self.moov(0)?.len() as u64;
+        let chunk_offset = ftyp.len() as u64 + moov_size + mdat_header.len() as u64;
+        let moov = self.moov(chunk_offset)?;
+
+        writer.write_all(&ftyp)?;
+        writer.write_all(&moov)?;
+        writer.write_all(&mdat_header)?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    fn moov(&self, chunk_offset: u64) -> Result<Vec<u8>> {
+        let duration = self.duration();
+
+        let mut contents = Vec::new();
+        MvhdBox {
+            timescale: self.sample_rate,
+            duration,
+            version: u8::from(duration > u64::from(u32::MAX)),
+            next_track_id: 2,
+            ..Default::default()
+        }
+        .write_box(&mut contents)?;
+        contents.extend(self.trak(chunk_offset)?);
+        contents.extend(self.udta()?);
+        wrap_box(BoxType::MoovBox, contents)
+    }
+
+    fn trak(&self, chunk_offset: u64) -> Result<Vec<u8>> {
+        let duration = self.duration();
+        let version = u8::from(duration > u64::from(u32::MAX));
+
+        let mut contents = Vec::new();
+        TkhdBox {
+            version,
+            track_id: 1,
+            duration,
+            alternate_group: 1,
+            ..Default::default()
+        }
+        .write_box(&mut contents)?;
+
+        let mut mdia = Vec::new();
+        MdhdBox {
+            version,
+            timescale: self.sample_rate,
+            duration,
+            ..Default::default()
+        }
+        .write_box(&mut mdia)?;
+        HdlrBox {
+            handler_type: FourCC::from(*b"soun"),
+            name: "SoundHandler".to_owned(),
+            ..Default::default()
+        }
+        .write_box(&mut mdia)?;
+
+        let mut minf = Vec::new();
+        SmhdBox::default().write_box(&mut minf)?;
+        DinfBox::default().write_box(&mut minf)?;
+        minf.extend(self.stbl(chunk_offset)?);
+
+        mdia.extend(wrap_box(BoxType::MinfBox, minf)?);
+        contents.extend(wrap_box(BoxType::MdiaBox, mdia)?);
+        wrap_box(BoxType::TrakBox, contents)
+    }
+
+    /// All samples are stored in a single chunk.
+    fn stbl(&self, chunk_offset: u64) -> Result<Vec<u8>> {
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1]; // version, flags, and entry count
+        stsd.extend(self.sample_entry()?);
+
+        let mut contents = wrap_box(BoxType::StsdBox, stsd)?;
+        SttsBox {
+            version: 0,
+            flags: 0,
+            entries: self.stts.clone(),
+        }
+        .write_box(&mut contents)?;
+        StscBox {
+            version: 0,
+            flags: 0,
+            entries: if self.sample_sizes.is_empty() {
+                Vec::new()
+            } else {
+                vec![StscEntry {
+                    first_chunk: 1,
+                    samples_per_chunk: self.sample_sizes.len() as u32,
+                    sample_description_index: 1,
+                    first_sample: 1,
+                }]
+            },
+        }
+        .write_box(&mut contents)?;
+        StszBox {
+            version: 0,
+            flags: 0,
+            sample_size: 0,
+            sample_count: self.sample_sizes.len() as u32,
+            sample_sizes: self.sample_sizes.clone(),
+        }
+        .write_box(&mut contents)?;
+        let chunk_offsets = if self.sample_sizes.is_empty() {
+            Vec::new()
+        } else {
+            vec![chunk_offset]
+        };
+        contents.extend(chunk_offset_box(chunk_offsets, false)?);
+
+        wrap_box(BoxType::StblBox, contents)
+    }
+
+    /// The `mp4a` or `alac` audio sample entry.
+    fn sample_entry(&self) -> Result<Vec<u8>> {
+        let (entry_type, sample_size, config) = match &self.codec {
+            M4aCodec::Aac {
+                audio_specific_config,
+            } => (*b"mp4a", 16, self.esds(audio_specific_config)?),
+            M4aCodec::Alac { magic_cookie } => {
+                // The bit depth is the sixth byte of the magic cookie.
+                let bit_depth = magic_cookie.get(5).copied().unwrap_or(16);
+                let mut alac = vec![0; 4]; // version and flags
+                alac.extend_from_slice(magic_cookie);
+                (*b"alac", u16::from(bit_depth), raw_box(*b"alac", alac)?)
+            }
+        };
+
+        let mut contents = vec![0; 6]; // reserved
+        contents.extend(1_u16.to_be_bytes()); // data reference index
+        contents.extend([0; 8]); // version, revision level, and vendor
+        contents.extend(self.channel_count.to_be_bytes());
+        contents.extend(sample_size.to_be_bytes());
+        contents.extend([0; 4]); // pre-defined and reserved
+
+        // Rates that don't fit in the integer part of the 16.16 fixed point number are left out,
+        // decoders take them from the decoder configuration instead.
+        let sample_rate = u16::try_from(self.sample_rate).unwrap_or(0);
+        contents.extend(sample_rate.to_be_bytes());
+        contents.extend([0; 2]);
+
+        contents.extend(config);
+        raw_box(entry_type, contents)
+    }
+
+    /// The `esds` box of an AAC track, see ISO/IEC 14496-1.
+    fn esds(&self, audio_specific_config: &[u8]) -> Result<Vec<u8>> {
+        let duration = self.duration();
+        let avg_bitrate = if duration == 0 {
+            0
+        } else {
+            self.data.len() as u64 * 8 * u64::from(self.sample_rate) / duration
+        };
+        let buffer_size = self.sample_sizes.iter().copied().max().unwrap_or(0);
+
+        let mut decoder_config = vec![
+            0x40, // Audio ISO/IEC 14496-3
+            0x15, // audio stream
+        ];
+        decoder_config.extend(&buffer_size.to_be_bytes()[1..]);
+        decoder_config.extend(self.max_bitrate().to_be_bytes());
+        decoder_config.extend(u32::try_from(avg_bitrate).unwrap_or(u32::MAX).to_be_bytes());
+        decoder_config.extend(descriptor(0x05, audio_specific_config)?);
+
+        let mut es = vec![0, 1, 0]; // ES_ID and flags
+        es.extend(descriptor(0x04, &decoder_config)?);
+        es.extend(descriptor(0x06, &[0x02])?); // SL config, predefined for MP4
+
+        let mut contents = vec![0; 4]; // version and flags
+        contents.extend(descriptor(0x03, &es)?);
+        raw_box(*b"esds", contents)
+    }
+
+    /// Highest number of bits in any one second of audio.
+    fn max_bitrate(&self) -> u32 {
+        let rate = u64::from(self.sample_rate.max(1));
+        let mut bits_per_second = std::collections::BTreeMap::<u64, u64>::new();
+        let mut time = 0;
+        let mut sizes = self.sample_sizes.iter();
+        for entry in &self.stts {
+            for size in sizes.by_ref().take(entry.sample_count as usize) {
+                *bits_per_second.entry(time / rate).or_default() += u64::from(*size) * 8;
+                time += u64::from(entry.sample_delta);
+            }
+        }
+        let max = bits_per_second.values().copied().max().unwrap_or(0);
+        u32::try_from(max).unwrap_or(u32::MAX)
+    }
+
+    fn udta(&self) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+
+        let ilst = self.ilst()?;
+        if !ilst.is_empty() {
+            contents.extend(itunes_meta(ilst)?);
+        }
+
+        if !self.chapters.is_empty() {
+            let mut chpl = vec![1, 0, 0, 0]; // version 1 and flags
+            chpl.extend([0; 4]); // reserved
+            chpl.push(self.chapters.len() as u8);
+            for chapter in &self.chapters {
+                let start = chapter.start.as_nanos() / 100;
+                chpl.extend(u64::try_from(start).unwrap_or(u64::MAX).to_be_bytes());
+                chpl.push(chapter.title.len() as u8);
+                chpl.extend(chapter.title.as_bytes());
+            }
+            contents.extend(raw_box(*b"chpl", chpl)?);
+        }
+
+        if contents.is_empty() {
+            return Ok(Vec::new());
+        }
+        wrap_box(BoxType::UdtaBox, contents)
+    }
+
+    /// The items of `ilst`, without its header.
+    fn ilst(&self) -> Result<Vec<u8>> {
+        const IMPLICIT: u32 = 0;
+
+        let tags = &self.tags;
+        let mut items = Vec::new();
+        let texts = [
+            (*b"\xa9nam", &tags.title),
+            (*b"\xa9ART", &tags.artist),
+            (*b"\xa9alb", &tags.album),
+            (*b"aART", &tags.album_artist),
+            (*b"\xa9wrt", &tags.composer),
+            (*b"\xa9gen", &tags.genre),
+            (*b"\xa9day", &tags.year),
+            (*b"\xa9cmt", &tags.comment),
+        ];
+        for (item_type, text) in texts {
+            if let Some(text) = text {
+                items.extend(text_item(item_type, text)?);
+            }
+        }
+        if let Some((number, total)) = tags.track_number {
+            let mut data = vec![0; 2];
+            data.extend(number.to_be_bytes());
+            data.extend(total.unwrap_or(0).to_be_bytes());
+            data.extend([0; 2]);
+            items.extend(ilst_item(*b"trkn", IMPLICIT, &data)?);
+        }
+        if let Some(cover) = &tags.cover {
+            items.extend(cover_item(cover)?);
+        }
+        Ok(items)
+    }
+}
+
+/// The `meta` box of iTunes tags, with the items of `ilst` without its header.
+pub(super) fn itunes_meta(ilst: Vec<u8>) -> Result<Vec<u8>> {
+    let mut meta = vec![0; 4]; // version and flags
+    let mut hdlr = Vec::new();
+    HdlrBox {
+        handler_type: FourCC::from(*b"mdir"),
+        ..Default::default()
+    }
+    .write_box(&mut hdlr)?;
+    // iTunes puts its vendor code in the first reserved field.
+    hdlr[20..24].copy_from_slice(b"appl");
+    meta.extend(hdlr);
+    meta.extend(wrap_box(BoxType::IlstBox, ilst)?);
+    wrap_box(BoxType::MetaBox, meta)
+}
+
+/// An `ilst` item holding a UTF-8 text.
+pub(super) fn text_item(item_type: [u8; 4], text: &str) -> Result<Vec<u8>> {
+    const TEXT: u32 = 1;
+    ilst_item(item_type, TEXT, text.as_bytes())
+}
+
+/// The `covr` item of `ilst` with a JPEG or PNG image.
+pub(super) fn cover_item(cover: &[u8]) -> Result<Vec<u8>> {
+    const JPEG: u32 = 13;
+    const PNG: u32 = 14;
+    let data_type = if cover.starts_with(b"\x89PNG") {
+        PNG
+    } else {
+        JPEG
+    };
+    ilst_item(*b"covr", data_type, cover)
+}
+
+/// An `ilst` item holding a single `data` box.
+fn ilst_item(item_type: [u8; 4], data_type: u32, value: &[u8]) -> Result<Vec<u8>> {
+    let mut data = data_type.to_be_bytes().to_vec();
+    data.extend([0; 4]); // locale
+    data.extend_from_slice(value);
+    raw_box(item_type, raw_box(*b"data", data)?)
+}
+
+/// Prepends a box header to `contents`, for boxes that have no [`BoxType`].
+pub(super) fn raw_box(box_type: [u8; 4], contents: Vec<u8>) -> Result<Vec<u8>> {
+    wrap_box(BoxType::from(u32::from_be_bytes(box_type)), contents)
+}
+
+/// An MPEG-4 descriptor, with its size in the shortest encoding.
+fn descriptor(tag: u8, contents: &[u8]) -> Result<Vec<u8>> {
+    let size = u32::try_from(contents.len())
+        .ok()
+        .filter(|&size| size < 1 << 28)
+        .ok_or(Error::InvalidData("descriptor too large"))?;
+    let mut bytes = vec![tag];
+    let mut shift = 21;
+    while shift > 0 && size >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        bytes.push(0x80 | ((size >> shift) & 0x7f) as u8);
+        shift -= 7;
+    }
+    bytes.push((size & 0x7f) as u8);
+    bytes.extend_from_slice(contents);
+    Ok(bytes)
+}
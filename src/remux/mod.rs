@@ -0,0 +1,538 @@
+//! Writing new MP4 files based on parsed ones.
+//!
+//! Boxes of the source file are copied verbatim wherever possible, and only the parts that
+//! actually change (e.g. chunk offsets) are re-serialized. This way boxes that this crate
+//! does not parse, such as codec specific extensions in `stsd`, survive the round trip.
+//!
+//! The exception is [`M4aWriter`], which writes audio-only files from scratch. [`Mp4Editor`]
+//! goes the other way, and edits the tags of a file in place.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+mod concat;
+mod defragment;
+mod edit;
+mod fragment;
+mod m4a;
+
+pub use concat::concat;
+pub use defragment::defragment;
+pub use edit::Mp4Editor;
+pub use fragment::{fragment, fragment_streaming, Fragments, MediaSegment, Segment};
+pub use m4a::{M4aCodec, M4aTags, M4aWriter};
+
+use crate::{
+    BoxHeader, BoxType, Co64Box, Error, Mp4, ReadBox as _, Result, StblBox, StcoBox, TrakBox,
+    TrexBox, WriteBox as _, HEADER_SIZE,
+};
+
+/// Writes `mp4` as a new progressive file, with `moov` ahead of a single `mdat`.
+///
+/// `source` must be the file `mp4` was parsed from. Only the tracks still present in `mp4.moov`
+/// are written (see [`Mp4::retain_tracks`]). Their chunks are copied into the new `mdat` in their
+/// original order, and `stco`/`co64` are rewritten to match (switching to `co64` if needed).
+/// All other boxes, except for `free` and `skip` padding, are copied as-is.
+///
+/// Fragmented files and files that refer to media data in other files are not supported.
+pub fn write<R: Read + Seek, W: Write>(mp4: &Mp4, source: &mut R, writer: &mut W) -> Result<()> {
+    if !mp4.moofs.is_empty() {
+        return Err(Error::InvalidData("fragmented files cannot be rewritten"));
+    }
+    check_self_contained(mp4)?;
+
+    let (prefix, moov) = split_top_level(source)?;
+    let traks: Vec<&TrakBox> = mp4.moov.traks.iter().collect();
+    let layout = ChunkLayout::new(&traks)?;
+    let mdat_header = BoxHeader::new(BoxType::MdatBox, box_size(layout.size));
+    let mdat_header_size = box_size(layout.size) - layout.size;
+
+    let track_ids: HashMap<u32, usize> = mp4
+        .moov
+        .traks
+        .iter()
+        .enumerate()
+        .map(|(index, trak)| (trak.tkhd.track_id, index))
+        .collect();
+    let moov = rewrite_moov_until_stable(0, |moov_size| {
+        let base = prefix.len() as u64 + moov_size + mdat_header_size;
+        rewrite_moov(
+            source,
+            &moov,
+            |track_id| track_ids.contains_key(&track_id),
+            |track_id, _| {
+                let offsets = &layout.relative_offsets[track_ids[&track_id]];
+                offsets.iter().map(|offset| base + offset).collect()
+            },
+        )
+    })?;
+
+    writer.write_all(&prefix)?;
+    writer.write_all(&moov)?;
+    mdat_header.write(writer)?;
+    layout.copy(source, writer)?;
+
+    Ok(())
+}
+
+/// Moves `moov` in front of the media data, so that the file can be played while it is still downloading.
+///
+/// All other boxes are copied unchanged and in their original order. Only the chunk offsets in
+/// `moov` are patched, switching from `stco` to `co64` where an offset no longer fits in 32 bits.
+/// Files in which `moov` already precedes the media data are copied as-is.
+pub fn faststart<R: Read + Seek, W: Write>(reader: &mut R, writer: &mut W) -> Result<()> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    let top_level = read_children(reader, 0, end)?;
+
+    let Some(moov_index) = top_level.iter().position(|b| b.name == BoxType::MoovBox) else {
+        return Err(Error::BoxNotFound(BoxType::MoovBox));
+    };
+    let first_media_index = top_level
+        .iter()
+        .position(|b| matches!(b.name, BoxType::MdatBox | BoxType::MoofBox))
+        .filter(|&index| index < moov_index);
+    let Some(first_media_index) = first_media_index else {
+        return copy_range(reader, writer, 0, end);
+    };
+    if top_level.iter().any(|b| b.name == BoxType::MoofBox) {
+        return Err(Error::InvalidData(
+            "moov of a fragmented file cannot be moved",
+        ));
+    }
+
+    let moov = &top_level[moov_index];
+    let old_moov_size = moov.end - moov.start;
+    let media_start = top_level[first_media_index].start;
+    let moov_bytes = rewrite_moov_until_stable(old_moov_size, |moov_size| {
+        rewrite_moov(
+            reader,
+            moov,
+            |_| true,
+            |_, offsets| {
+                // Everything between the media data and the old `moov` moves back by the size
+                // of the new `moov`, everything after it by the difference in size.
+                let relocate = |offset| {
+                    if offset < media_start {
+                        offset
+                    } else if offset < moov.start {
+                        offset + moov_size
+                    } else {
+                        offset + moov_size - old_moov_size
+                    }
+                };
+                offsets.into_iter().map(relocate).collect()
+            },
+        )
+    })?;
+
+    for (index, child) in top_level.iter().enumerate() {
+        if index == first_media_index {
+            writer.write_all(&moov_bytes)?;
+        }
+        if index != moov_index {
+            copy_range(reader, writer, child.start, child.end)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails if a track of `mp4` refers to media data in another file, which cannot be copied from the source.
+fn check_self_contained(mp4: &Mp4) -> Result<()> {
+    for trak in &mp4.moov.traks {
+        if trak
+            .data_reference()
+            .is_some_and(|entry| !entry.is_self_contained())
+        {
+            return Err(Error::ExternalDataReference(trak.tkhd.track_id));
+        }
+    }
+    Ok(())
+}
+
+/// Calls `rewrite` with the expected size of the new `moov` until that matches the size it produced.
+///
+/// Chunk offsets depend on the size of `moov` when it precedes the media data, but that size
+/// in turn only changes when an `stco` has to become a `co64`, so this settles quickly.
+fn rewrite_moov_until_stable(
+    mut moov_size: u64,
+    mut rewrite: impl FnMut(u64) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    loop {
+        let moov = rewrite(moov_size)?;
+        if moov.len() as u64 == moov_size {
+            return Ok(moov);
+        }
+        moov_size = moov.len() as u64;
+    }
+}
+
+/// Rewrites `moov`, dropping the tracks for which `keep` returns `false`
+/// and replacing the chunk offsets of the others with what `chunk_offsets` returns for them.
+///
+/// `chunk_offsets` is called with the track id and the current chunk offsets of the track.
+/// The new offsets are written as `co64` if the track used `co64` already or if they need 64 bits.
+fn rewrite_moov<R: Read + Seek>(
+    source: &mut R,
+    moov: &BoxRange,
+    keep: impl Fn(u32) -> bool,
+    mut chunk_offsets: impl FnMut(u32, Vec<u64>) -> Vec<u64>,
+) -> Result<Vec<u8>> {
+    let mut current_track_id = 0;
+    rewrite_box(source, moov, &mut Vec::new(), &mut |reader, path, child| {
+        Ok(match (path, child.name) {
+            ([BoxType::MoovBox], BoxType::TrakBox) => {
+                current_track_id = TrakBox::read_box(reader, child.size)?.tkhd.track_id;
+                if keep(current_track_id) {
+                    Rewrite::Descend
+                } else {
+                    Rewrite::Drop
+                }
+            }
+            ([BoxType::MoovBox, BoxType::MvexBox], BoxType::TrexBox) => {
+                if keep(TrexBox::read_box(reader, child.size)?.track_id) {
+                    Rewrite::Copy
+                } else {
+                    Rewrite::Drop
+                }
+            }
+            ([BoxType::MoovBox], BoxType::MvexBox)
+            | ([BoxType::MoovBox, BoxType::TrakBox], BoxType::MdiaBox)
+            | ([.., BoxType::MdiaBox], BoxType::MinfBox)
+            | ([.., BoxType::MinfBox], BoxType::StblBox) => Rewrite::Descend,
+            ([.., BoxType::StblBox], BoxType::StcoBox) => {
+                let stco = StcoBox::read_box(reader, child.size)?;
+                let offsets = stco.entries.into_iter().map(u64::from).collect();
+                let offsets = chunk_offsets(current_track_id, offsets);
+                Rewrite::Replace(chunk_offset_box(offsets, false)?)
+            }
+            ([.., BoxType::StblBox], BoxType::Co64Box) => {
+                let co64 = Co64Box::read_box(reader, child.size)?;
+                let offsets = chunk_offsets(current_track_id, co64.entries);
+                Rewrite::Replace(chunk_offset_box(offsets, true)?)
+            }
+            _ => Rewrite::Copy,
+        })
+    })
+}
+
+/// Serializes chunk offsets as `stco` if possible, or as `co64` if they need 64 bits or `co64` is preferred.
+fn chunk_offset_box(offsets: Vec<u64>, prefer_co64: bool) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let stco_entries = offsets
+        .iter()
+        .map(|&offset| u32::try_from(offset))
+        .collect::<std::result::Result<Vec<_>, _>>();
+    match stco_entries {
+        Ok(entries) if !prefer_co64 => {
+            StcoBox {
+                version: 0,
+                flags: 0,
+                entries,
+            }
+            .write_box(&mut bytes)?;
+        }
+        _ => {
+            Co64Box {
+                version: 0,
+                flags: 0,
+                entries: offsets,
+            }
+            .write_box(&mut bytes)?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Source offset and size of every chunk of a track.
+fn chunk_ranges(trak: &TrakBox) -> Result<Vec<(u64, u64)>> {
+    let track_id = trak.tkhd.track_id;
+    let stbl = &trak.mdia.minf.stbl;
+    let offsets: Vec<u64> = if let Some(stco) = &stbl.stco {
+        stco.entries.iter().copied().map(u64::from).collect()
+    } else if let Some(co64) = &stbl.co64 {
+        co64.entries.clone()
+    } else {
+        return Err(Error::BoxInStblNotFound(track_id, BoxType::StcoBox));
+    };
+
+    let stsz = &stbl.stsz;
+    let stsc = &stbl.stsc.entries;
+    let mut stsc_index = 0;
+    let mut sample = 0;
+    let mut chunks = Vec::with_capacity(offsets.len());
+    for (chunk_index, offset) in offsets.into_iter().enumerate() {
+        let chunk = chunk_index as u32 + 1;
+        while stsc_index + 1 < stsc.len() && stsc[stsc_index + 1].first_chunk <= chunk {
+            stsc_index += 1;
+        }
+        let samples_per_chunk = stsc
+            .get(stsc_index)
+            .map_or(0, |entry| entry.samples_per_chunk);
+
+        let mut size = 0;
+        for _ in 0..samples_per_chunk {
+            let sample_size =
+                stsz.sample_size(sample as usize)
+                    .ok_or(Error::EntryInStblNotFound(
+                        track_id,
+                        BoxType::StszBox,
+                        sample + 1,
+                    ))?;
+            size += u64::from(sample_size);
+            sample += 1;
+        }
+        chunks.push((offset, size));
+    }
+
+    Ok(chunks)
+}
+
+fn chunk_count(stbl: &StblBox) -> usize {
+    match (&stbl.stco, &stbl.co64) {
+        (Some(stco), _) => stco.entries.len(),
+        (None, Some(co64)) => co64.entries.len(),
+        (None, None) => 0,
+    }
+}
+
+/// Where the chunks of some tracks end up when they are copied into a new `mdat`, keeping their
+/// original interleaving.
+struct ChunkLayout {
+    /// Source offset and size of all chunks, in the order they are written.
+    chunks: Vec<(u64, u64)>,
+
+    /// New offsets of the chunks of each track, relative to the start of the `mdat` contents.
+    relative_offsets: Vec<Vec<u64>>,
+
+    /// Total size of all chunks.
+    size: u64,
+}
+
+impl ChunkLayout {
+    fn new(traks: &[&TrakBox]) -> Result<Self> {
+        let chunks = traks
+            .iter()
+            .map(|trak| chunk_ranges(trak))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_chunks(&chunks))
+    }
+
+    /// Lays out chunks given as the source offset and size of every chunk of each track.
+    fn from_chunks(tracks: &[Vec<(u64, u64)>]) -> Self {
+        let mut chunks = Vec::new();
+        for (track_index, track_chunks) in tracks.iter().enumerate() {
+            for (chunk_index, &(offset, size)) in track_chunks.iter().enumerate() {
+                chunks.push((offset, size, track_index, chunk_index));
+            }
+        }
+        chunks.sort_by_key(|&(offset, ..)| offset);
+
+        let mut relative_offsets: Vec<Vec<u64>> = tracks
+            .iter()
+            .map(|track_chunks| vec![0; track_chunks.len()])
+            .collect();
+        let mut size = 0;
+        for &(_, chunk_size, track_index, chunk_index) in &chunks {
+            relative_offsets[track_index][chunk_index] = size;
+            size += chunk_size;
+        }
+
+        Self {
+            chunks: chunks
+                .into_iter()
+                .map(|(offset, size, ..)| (offset, size))
+                .collect(),
+            relative_offsets,
+            size,
+        }
+    }
+
+    /// Copies the chunks from `source` to `writer`.
+    fn copy<R: Read + Seek, W: Write>(&self, source: &mut R, writer: &mut W) -> Result<()> {
+        for &(offset, size) in &self.chunks {
+            copy_range(source, writer, offset, offset + size)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads all top-level boxes that a rewritten file keeps ahead of `moov` (usually just `ftyp`),
+/// and locates `moov`.
+///
+/// `mdat`, padding and the boxes that only make sense in a fragmented file are left out.
+fn split_top_level<R: Read + Seek>(source: &mut R) -> Result<(Vec<u8>, BoxRange)> {
+    let end = source.seek(SeekFrom::End(0))?;
+    let mut prefix = Vec::new();
+    let mut moov = None;
+    for child in read_children(source, 0, end)? {
+        match child.name {
+            BoxType::MoovBox => moov = Some(child),
+            BoxType::MdatBox
+            | BoxType::FreeBox
+            | BoxType::SkipBox
+            | BoxType::WideBox
+            | BoxType::MoofBox
+            | BoxType::MfraBox
+            | BoxType::SidxBox
+            | BoxType::StypBox
+            | BoxType::EmsgBox => {}
+            _ => prefix.extend(read_range(source, child.start, child.end)?),
+        }
+    }
+    let Some(moov) = moov else {
+        return Err(Error::BoxNotFound(BoxType::MoovBox));
+    };
+    Ok((prefix, moov))
+}
+
+/// Location of a box in the source file.
+struct BoxRange {
+    name: BoxType,
+
+    /// Size as reported by [`BoxHeader::read`], to be passed to [`crate::ReadBox::read_box`].
+    size: u64,
+
+    /// Offset of the box header.
+    start: u64,
+
+    /// Offset of the box contents, right after the header.
+    data_start: u64,
+
+    /// Offset one past the last byte of the box.
+    end: u64,
+}
+
+/// Lists the boxes between `start` and `end`.
+fn read_children<R: Read + Seek>(reader: &mut R, start: u64, end: u64) -> Result<Vec<BoxRange>> {
+    let mut children = Vec::new();
+    let mut current = start;
+    while current < end {
+        reader.seek(SeekFrom::Start(current))?;
+        let BoxHeader { name, size } = BoxHeader::read(reader)?;
+        let data_start = reader.stream_position()?;
+        let (size, box_end) = if size == 0 {
+            // The box extends to the end of its parent.
+            (end - data_start + HEADER_SIZE, end)
+        } else {
+            (size, data_start - HEADER_SIZE + size)
+        };
+        if box_end < data_start || box_end > end {
+            return Err(Error::InvalidData(
+                "box size is smaller than its header or larger than its parent",
+            ));
+        }
+        children.push(BoxRange {
+            name,
+            size,
+            start: current,
+            data_start,
+            end: box_end,
+        });
+        current = box_end;
+    }
+    Ok(children)
+}
+
+fn copy_range<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    start: u64,
+    end: u64,
+) -> Result<()> {
+    reader.seek(SeekFrom::Start(start))?;
+    let copied = std::io::copy(&mut reader.by_ref().take(end - start), writer)?;
+    if copied != end - start {
+        return Err(Error::InvalidData(
+            "box or chunk extends beyond the end of the file",
+        ));
+    }
+    Ok(())
+}
+
+fn read_range<R: Read + Seek>(reader: &mut R, start: u64, end: u64) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; (end - start) as usize];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// What to do with a box while rewriting its parent.
+enum Rewrite {
+    /// Copy the box verbatim.
+    Copy,
+
+    /// Leave the box out.
+    Drop,
+
+    /// Write these bytes, which must form complete boxes, instead.
+    Replace(Vec<u8>),
+
+    /// Rewrite the children of the box, which must be a plain container without a full box header.
+    Descend,
+}
+
+/// Rewrites the box at `range`, asking `visit` what to do with each of its children.
+///
+/// `visit` is called with the reader positioned at the contents of the child, and the path of
+/// box types from the outermost rewritten box down to the parent of the child.
+fn rewrite_box<R: Read + Seek>(
+    reader: &mut R,
+    range: &BoxRange,
+    path: &mut Vec<BoxType>,
+    visit: &mut impl FnMut(&mut R, &[BoxType], &BoxRange) -> Result<Rewrite>,
+) -> Result<Vec<u8>> {
+    let contents = rewrite_children(reader, range, path, visit)?;
+    wrap_box(range.name, contents)
+}
+
+/// Like [`rewrite_box`], but returns only the rewritten contents, so that more children can be added.
+fn rewrite_children<R: Read + Seek>(
+    reader: &mut R,
+    range: &BoxRange,
+    path: &mut Vec<BoxType>,
+    visit: &mut impl FnMut(&mut R, &[BoxType], &BoxRange) -> Result<Rewrite>,
+) -> Result<Vec<u8>> {
+    path.push(range.name);
+    let mut contents = Vec::new();
+    for child in read_children(reader, range.data_start, range.end)? {
+        reader.seek(SeekFrom::Start(child.data_start))?;
+        match visit(reader, path, &child)? {
+            Rewrite::Copy => contents.extend(read_range(reader, child.start, child.end)?),
+            Rewrite::Drop => {}
+            Rewrite::Replace(bytes) => contents.extend(bytes),
+            Rewrite::Descend => contents.extend(rewrite_box(reader, &child, path, visit)?),
+        }
+    }
+    path.pop();
+    Ok(contents)
+}
+
+/// Prepends a box header to `contents`.
+fn wrap_box(name: BoxType, contents: Vec<u8>) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(contents.len() + 16);
+    BoxHeader::new(name, box_size(contents.len() as u64)).write(&mut bytes)?;
+    bytes.extend(contents);
+    Ok(bytes)
+}
+
+/// Appends a run-length encoded table entry, merging it into the last one if `merge` allows it.
+fn push_run<T>(entries: &mut Vec<T>, entry: T, merge: impl Fn(&T, &T) -> Option<T>) {
+    if let Some(merged) = entries.last().and_then(|last| merge(last, &entry)) {
+        if let Some(last) = entries.last_mut() {
+            *last = merged;
+        }
+    } else {
+        entries.push(entry);
+    }
+}
+
+/// Total size of a box with `contents_size` bytes of contents, including the header.
+fn box_size(contents_size: u64) -> u64 {
+    let size = HEADER_SIZE + contents_size;
+    if size > u64::from(u32::MAX) {
+        size + 8 // largesize
+    } else {
+        size
+    }
+}
@@ -0,0 +1,92 @@
+//! The sample flags of movie fragments, see ISO/IEC 14496-12 section 8.8.3.
+
+use serde::{Deserialize, Serialize};
+
+/// The 32-bit sample flags of `trex`, `tfhd` and `trun`.
+///
+/// Progressive files have the two bit fields in `sdtp` instead, see [`crate::SdtpBox`].
+///
+/// The two bit fields are 0 if unknown, like in `sdtp`: `depends_on` is 1 if the sample
+/// depends on others and 2 if it doesn't, `is_depended_on` is 1 if other samples depend on it
+/// and 2 if none do, and `has_redundancy` is 1 if the sample has redundant coding and 2 if it
+/// doesn't.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SampleFlags {
+    /// 1 if the sample is a leading sample that depends on samples before the preceding sync
+    /// sample, 2 if it is a leading sample that doesn't, and 3 if it is not a leading sample.
+    pub is_leading: u8,
+    pub depends_on: u8,
+    pub is_depended_on: u8,
+    pub has_redundancy: u8,
+
+    /// The number of padding bits at the end of the sample, 0 to 7.
+    pub padding: u8,
+
+    /// `sample_is_non_sync_sample`.
+    pub is_non_sync: bool,
+    pub degradation_priority: u16,
+}
+
+impl SampleFlags {
+    /// The flags of a sync sample, which does not depend on other samples.
+    pub const SYNC: Self = Self {
+        is_leading: 0,
+        depends_on: 2,
+        is_depended_on: 0,
+        has_redundancy: 0,
+        padding: 0,
+        is_non_sync: false,
+        degradation_priority: 0,
+    };
+
+    /// The flags of a non-sync sample, which depends on other samples.
+    pub const NON_SYNC: Self = Self {
+        is_leading: 0,
+        depends_on: 1,
+        is_depended_on: 0,
+        has_redundancy: 0,
+        padding: 0,
+        is_non_sync: true,
+        degradation_priority: 0,
+    };
+
+    pub fn from_u32(flags: u32) -> Self {
+        Self {
+            is_leading: ((flags >> 26) & 0b11) as u8,
+            depends_on: ((flags >> 24) & 0b11) as u8,
+            is_depended_on: ((flags >> 22) & 0b11) as u8,
+            has_redundancy: ((flags >> 20) & 0b11) as u8,
+            padding: ((flags >> 17) & 0b111) as u8,
+            is_non_sync: (flags >> 16) & 1 == 1,
+            degradation_priority: flags as u16,
+        }
+    }
+
+    /// The flags as written in boxes, with the fields that are out of range truncated.
+    pub fn to_u32(self) -> u32 {
+        (u32::from(self.is_leading & 0b11) << 26)
+            | (u32::from(self.depends_on & 0b11) << 24)
+            | (u32::from(self.is_depended_on & 0b11) << 22)
+            | (u32::from(self.has_redundancy & 0b11) << 20)
+            | (u32::from(self.padding & 0b111) << 17)
+            | (u32::from(self.is_non_sync) << 16)
+            | u32::from(self.degradation_priority)
+    }
+
+    /// Whether the sample is a sync sample, i.e. `sample_is_non_sync_sample` is clear.
+    pub fn is_sync(self) -> bool {
+        !self.is_non_sync
+    }
+}
+
+impl From<u32> for SampleFlags {
+    fn from(flags: u32) -> Self {
+        Self::from_u32(flags)
+    }
+}
+
+impl From<SampleFlags> for u32 {
+    fn from(flags: SampleFlags) -> Self {
+        flags.to_u32()
+    }
+}
@@ -0,0 +1,71 @@
+//! Read-only access to the sample tables of a track, see [`Track::stts_entries`] and friends.
+//!
+//! These describe the samples of `moov` only. The samples of movie fragments are in the `trun`
+//! boxes of each `moof`, see [`Mp4::fragments`].
+
+use crate::{CttsEntry, Mp4, StblBox, StscEntry, SttsEntry, Track};
+
+impl Track {
+    /// The sample table of this track in `mp4`, or `None` if it has no `trak`.
+    fn stbl<'a>(&self, mp4: &'a Mp4) -> Option<&'a StblBox> {
+        self.trak(mp4).map(|trak| &trak.mdia.minf.stbl)
+    }
+
+    /// The runs of samples with the same duration, from `stts`.
+    ///
+    /// Empty if the track has no `trak` in `mp4`, as for tracks that only exist in fragments.
+    pub fn stts_entries<'a>(&self, mp4: &'a Mp4) -> &'a [SttsEntry] {
+        self.stbl(mp4).map_or(&[], |stbl| &stbl.stts.entries)
+    }
+
+    /// The runs of samples with the same composition offset, from `ctts`.
+    ///
+    /// Empty if presentation and decode order are the same.
+    pub fn ctts_entries<'a>(&self, mp4: &'a Mp4) -> &'a [CttsEntry] {
+        self.stbl(mp4)
+            .and_then(|stbl| stbl.ctts.as_ref())
+            .map_or(&[], |ctts| &ctts.entries)
+    }
+
+    /// The runs of chunks with the same number of samples, from `stsc`.
+    pub fn stsc_entries<'a>(&self, mp4: &'a Mp4) -> &'a [StscEntry] {
+        self.stbl(mp4).map_or(&[], |stbl| &stbl.stsc.entries)
+    }
+
+    /// The file offset of every chunk, from `stco` or `co64`.
+    pub fn chunk_offsets<'a>(&self, mp4: &'a Mp4) -> impl Iterator<Item = u64> + 'a {
+        // `stco` takes precedence if there are both, as when reading the samples.
+        let (stco, co64): (&[u32], &[u64]) = match self.stbl(mp4) {
+            Some(StblBox {
+                stco: Some(stco), ..
+            }) => (&stco.entries, &[]),
+            Some(StblBox {
+                co64: Some(co64), ..
+            }) => (&[], &co64.entries),
+            _ => (&[], &[]),
+        };
+        stco.iter()
+            .map(|&offset| u64::from(offset))
+            .chain(co64.iter().copied())
+    }
+
+    /// The size of every sample in bytes, from `stsz`, whether it gives one size for all samples
+    /// or a size per sample.
+    pub fn sample_sizes<'a>(&self, mp4: &'a Mp4) -> impl ExactSizeIterator<Item = u32> + 'a {
+        let stsz = self.stbl(mp4).map(|stbl| &stbl.stsz);
+        let count = stsz.map_or(0, |stsz| stsz.count());
+        (0..count).map(move |index| {
+            stsz.and_then(|stsz| stsz.sample_size(index))
+                .unwrap_or_default()
+        })
+    }
+
+    /// The 1-based numbers of the sync samples, like key frames, from `stss`.
+    ///
+    /// `None` if there is no `stss`, which means that every sample is a sync sample.
+    pub fn sync_sample_numbers<'a>(&self, mp4: &'a Mp4) -> Option<&'a [u32]> {
+        self.stbl(mp4)
+            .and_then(|stbl| stbl.stss.as_ref())
+            .map(|stss| stss.entries.as_slice())
+    }
+}
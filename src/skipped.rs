@@ -0,0 +1,127 @@
+//! Reporting of the parts of a file that the parser skips over.
+//!
+//! Codec configuration is often spread over several boxes and descriptors, of which this crate
+//! only parses some. When a decoder configuration comes out incomplete, the skipped parts tell
+//! what was actually in the file.
+
+use std::cell::RefCell;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::mp4box::{skip_bytes_to, BoxHeader, HEADER_SIZE};
+use crate::{FourCC, Result, TrackId};
+
+/// Something the parser came across, but did not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Skipped {
+    /// A box that was skipped, such as a `pasp` in a sample entry, or a sample entry of an unsupported codec.
+    Box {
+        /// The box that contains the skipped box.
+        parent: FourCC,
+        box_type: FourCC,
+
+        /// Offset of the box header in the file.
+        offset: u64,
+
+        /// Size of the box, including its header.
+        size: u64,
+    },
+
+    /// An MPEG-4 descriptor in `esds` with a tag that is not parsed.
+    Descriptor {
+        /// Tag of the descriptor that contains the skipped one.
+        parent_tag: u8,
+        tag: u8,
+
+        /// Offset of the descriptor tag in the file.
+        offset: u64,
+
+        /// Size of the descriptor, including its tag and length.
+        size: u64,
+    },
+
+    /// A track fragment with the same `tfdt` and sample data as an earlier one of the same
+    /// track, as in recordings that were recovered or appended twice. Its samples are left out.
+    DuplicateFragment {
+        track_id: TrackId,
+
+        /// Offset of the `moof` that contains the duplicate.
+        offset: u64,
+
+        /// Offset of the `moof` that contains the fragment it duplicates.
+        original_offset: u64,
+    },
+}
+
+/// Called for everything that is skipped, see [`set_skip_logger`].
+pub type SkipLogger = Box<dyn FnMut(&Skipped)>;
+
+thread_local! {
+    static SKIP_LOGGER: RefCell<Option<SkipLogger>> = const { RefCell::new(None) };
+}
+
+/// Sets the logger that is called for everything skipped while parsing on the current thread.
+///
+/// Returns the previous logger. Pass `None` to stop logging.
+pub fn set_skip_logger(logger: Option<SkipLogger>) -> Option<SkipLogger> {
+    SKIP_LOGGER.with(|current| current.replace(logger))
+}
+
+pub(crate) fn is_logging() -> bool {
+    #[cfg(feature = "tracing")]
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        return true;
+    }
+    SKIP_LOGGER.with(|current| current.borrow().is_some())
+}
+
+pub(crate) fn log_skipped(skipped: &Skipped) {
+    // Boxes and descriptors that aren't parsed are common, while duplicate fragments are a
+    // problem with the file.
+    #[cfg(feature = "tracing")]
+    match skipped {
+        Skipped::DuplicateFragment { .. } => tracing::warn!(?skipped, "skipped"),
+        Skipped::Box { .. } | Skipped::Descriptor { .. } => tracing::debug!(?skipped, "skipped"),
+    }
+
+    // The logger is taken out while it runs, so that it may itself parse files.
+    let Some(mut logger) = SKIP_LOGGER.with(RefCell::take) else {
+        return;
+    };
+    logger(skipped);
+    SKIP_LOGGER.with(|current| {
+        current.borrow_mut().get_or_insert(logger);
+    });
+}
+
+/// Skips the remaining children of the box that starts at `parent_start` and ends at `end`,
+/// logging every one of them.
+pub(crate) fn skip_children<R: Read + Seek>(
+    reader: &mut R,
+    parent_start: u64,
+    end: u64,
+) -> Result<()> {
+    if is_logging() {
+        let first_child = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(parent_start))?;
+        let parent = BoxHeader::read(reader)?.name;
+        skip_bytes_to(reader, first_child)?;
+        loop {
+            let offset = reader.stream_position()?;
+            if offset + HEADER_SIZE > end {
+                break;
+            }
+            let header = BoxHeader::read(reader)?;
+            if header.size < HEADER_SIZE || offset + header.size > end {
+                break;
+            }
+            log_skipped(&Skipped::Box {
+                parent: parent.into(),
+                box_type: header.name.into(),
+                offset,
+                size: header.size,
+            });
+            skip_bytes_to(reader, offset + header.size)?;
+        }
+    }
+    skip_bytes_to(reader, end)
+}
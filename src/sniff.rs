@@ -0,0 +1,105 @@
+//! Cheap detection of MP4 data from the start of a file, without parsing it.
+
+use crate::FourCC;
+
+/// The number of bytes that [`sniff`] looks at, at most.
+pub const SNIFF_LEN: usize = 512;
+
+/// What [`sniff`] found at the start of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SniffResult<'a> {
+    /// Type of the first box.
+    ///
+    /// Usually `ftyp`, or `styp` for media segments. Older `QuickTime` files may start with
+    /// `moov`, `mdat`, `wide` or a `free` space box instead.
+    pub first_box: FourCC,
+
+    /// Major brand from `ftyp` or `styp`, if the file starts with one of them.
+    pub major_brand: Option<FourCC>,
+
+    /// Minor version from `ftyp` or `styp`, or zero if the file doesn't start with one of them.
+    pub minor_version: u32,
+
+    /// The compatible brands that are within the sniffed prefix.
+    compatible_brands: &'a [u8],
+}
+
+impl<'a> SniffResult<'a> {
+    /// The compatible brands from `ftyp` or `styp`.
+    ///
+    /// Brands beyond the first [`SNIFF_LEN`] bytes of the file are left out.
+    pub fn compatible_brands(&self) -> impl Iterator<Item = FourCC> + 'a {
+        self.compatible_brands
+            .chunks_exact(4)
+            .filter_map(|brand| read_u32(brand, 0))
+            .map(FourCC::from)
+    }
+
+    /// Whether `brand` is the major brand or one of the compatible brands.
+    pub fn has_brand(&self, brand: FourCC) -> bool {
+        self.major_brand == Some(brand) || self.compatible_brands().any(|b| b == brand)
+    }
+}
+
+/// Checks whether `prefix`, the first bytes of a file, look like MP4 or `QuickTime` data.
+///
+/// Only the first box header and the brands of `ftyp` or `styp` are inspected, so at most
+/// [`SNIFF_LEN`] bytes are needed, and nothing is allocated. Meant for rejecting other files
+/// early, e.g. in upload endpoints; a file that passes may still fail to parse.
+pub fn sniff(prefix: &[u8]) -> Option<SniffResult<'_>> {
+    let prefix = &prefix[..prefix.len().min(SNIFF_LEN)];
+    let size = u64::from(read_u32(prefix, 0)?);
+    let first_box = FourCC::from(read_u32(prefix, 4)?);
+    let (header_size, size) = match size {
+        // The box extends to the end of the file.
+        0 => (8, u64::MAX),
+        1 => (
+            16,
+            (u64::from(read_u32(prefix, 8)?) << 32) | u64::from(read_u32(prefix, 12)?),
+        ),
+        size => (8, size),
+    };
+    if size < header_size {
+        return None;
+    }
+
+    match &first_box.value {
+        b"ftyp" | b"styp" => {
+            if header_size != 8 || size < 16 || size % 4 != 0 {
+                return None;
+            }
+            let major_brand = FourCC::from(read_u32(prefix, 8)?);
+            let minor_version = read_u32(prefix, 12)?;
+
+            let end = prefix.len().min(usize::try_from(size).ok()?);
+            let compatible_brands = &prefix[16..16 + (end - 16) / 4 * 4];
+            let is_brand = |brand: &[u8]| {
+                brand
+                    .iter()
+                    .all(|&byte| byte.is_ascii_graphic() || byte == b' ')
+            };
+            if !is_brand(&major_brand.value) || !compatible_brands.chunks(4).all(is_brand) {
+                return None;
+            }
+
+            Some(SniffResult {
+                first_box,
+                major_brand: Some(major_brand),
+                minor_version,
+                compatible_brands,
+            })
+        }
+        b"moov" | b"mdat" | b"free" | b"skip" | b"wide" | b"pnot" => Some(SniffResult {
+            first_box,
+            major_brand: None,
+            minor_version: 0,
+            compatible_brands: &[],
+        }),
+        _ => None,
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
@@ -0,0 +1,125 @@
+//! Statistics of the samples of a track, see [`Track::stats`].
+
+use crate::{BtrtBox, Track};
+
+/// Bitrates, sizes and durations of the samples of a track, see [`Track::stats`].
+///
+/// Sizes are in bytes, durations in the timescale of the track and bitrates in bits per second.
+/// All are 0 for a track without samples.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TrackStats {
+    pub sample_count: usize,
+
+    /// The number of sync samples, like the key frames of video.
+    pub sync_sample_count: usize,
+
+    /// Total size of all samples.
+    pub total_size: u64,
+
+    pub min_sample_size: u64,
+    pub max_sample_size: u64,
+    pub mean_sample_size: f64,
+
+    /// Sum of the durations of all samples.
+    pub total_duration: u64,
+
+    pub min_sample_duration: u64,
+    pub max_sample_duration: u64,
+    pub mean_sample_duration: f64,
+
+    /// [`Self::total_size`] over [`Self::total_duration`].
+    pub avg_bitrate: f64,
+
+    /// The largest size of the samples that are decoded within any one second, like the
+    /// maximum bitrate of `btrt`.
+    pub max_bitrate: u64,
+
+    /// The `btrt` box of the sample entry of the track, with the bitrates that the encoder
+    /// declared.
+    pub btrt: Option<BtrtBox>,
+}
+
+impl TrackStats {
+    /// The average number of samples from one sync sample to the next, including the sync
+    /// sample, or `None` if there are no sync samples.
+    ///
+    /// For video this is the length of a group of pictures.
+    pub fn avg_gop_length(&self) -> Option<f64> {
+        (self.sync_sample_count > 0)
+            .then(|| self.sample_count as f64 / self.sync_sample_count as f64)
+    }
+
+    /// Whether the bitrates in [`Self::btrt`] agree with the samples, within a `tolerance` that
+    /// is relative to the declared bitrate, like 0.1 for 10 percent.
+    ///
+    /// The average bitrate must be within the tolerance of the declared one, and the maximum
+    /// bitrate may not exceed the declared one by more than it. Bitrates that `btrt` leaves at 0
+    /// are unknown and always agree, as do tracks without a `btrt`.
+    pub fn matches_btrt(&self, tolerance: f64) -> bool {
+        let Some(btrt) = &self.btrt else {
+            return true;
+        };
+        let avg_matches = btrt.avg_bitrate == 0
+            || (self.avg_bitrate - f64::from(btrt.avg_bitrate)).abs()
+                <= f64::from(btrt.avg_bitrate) * tolerance;
+        let max_matches = btrt.max_bitrate == 0
+            || self.max_bitrate as f64 <= f64::from(btrt.max_bitrate) * (1.0 + tolerance);
+        avg_matches && max_matches
+    }
+}
+
+impl Track {
+    /// Bitrates, sizes and durations of the samples of this track, computed from its sample
+    /// table, along with the bitrates that its `btrt` declares.
+    ///
+    /// This goes over all samples, so keep the result instead of calling it repeatedly.
+    pub fn stats(&self) -> TrackStats {
+        let mut stats = TrackStats {
+            sample_count: self.samples.len(),
+            min_sample_size: u64::MAX,
+            min_sample_duration: u64::MAX,
+            btrt: self.sample_entry().btrt().copied(),
+            ..Default::default()
+        };
+        for sample in &self.samples {
+            stats.sync_sample_count += usize::from(sample.is_sync);
+            stats.total_size = stats.total_size.saturating_add(sample.size);
+            stats.min_sample_size = stats.min_sample_size.min(sample.size);
+            stats.max_sample_size = stats.max_sample_size.max(sample.size);
+            stats.total_duration = stats.total_duration.saturating_add(sample.duration);
+            stats.min_sample_duration = stats.min_sample_duration.min(sample.duration);
+            stats.max_sample_duration = stats.max_sample_duration.max(sample.duration);
+        }
+        if self.samples.is_empty() {
+            stats.min_sample_size = 0;
+            stats.min_sample_duration = 0;
+            return stats;
+        }
+
+        let count = self.samples.len() as f64;
+        stats.mean_sample_size = stats.total_size as f64 / count;
+        stats.mean_sample_duration = stats.total_duration as f64 / count;
+        let timescale = self.timescale.max(1);
+        if stats.total_duration > 0 {
+            stats.avg_bitrate =
+                stats.total_size as f64 * 8.0 * timescale as f64 / stats.total_duration as f64;
+        }
+
+        // Slides a window of one second over the samples in decode order.
+        let mut window_start = 0;
+        let mut window_size = 0_u64;
+        for sample in &self.samples {
+            window_size += sample.size;
+            while self.samples[window_start].decode_timestamp
+                <= sample
+                    .decode_timestamp
+                    .saturating_sub(i64::try_from(timescale).unwrap_or(i64::MAX))
+            {
+                window_size -= self.samples[window_start].size;
+                window_start += 1;
+            }
+            stats.max_bitrate = stats.max_bitrate.max(window_size.saturating_mul(8));
+        }
+        stats
+    }
+}
@@ -0,0 +1,125 @@
+//! Decoding the samples of subtitle tracks into cues that are ready to be shown.
+//!
+//! Supports 3GPP timed text (`tx3g`), decoded with [`crate::tx3g`], and WebVTT (`wvtt`), see
+//! ISO/IEC 14496-30 section 7. The TTML documents of `stpp` tracks are left to an XML parser.
+
+use crate::tx3g::{self, Tx3gSample};
+use crate::{Error, Result, Sample, StsdBoxContent, Track};
+
+/// A piece of text to show for a while.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    /// When to show the cue, in seconds, like [`Sample::presentation_time`].
+    pub start: f64,
+
+    /// How long to show the cue for, in seconds.
+    pub duration: f64,
+
+    /// The text of the cue.
+    ///
+    /// For WebVTT this may contain markup, like `<i>` or `<v Speaker>`.
+    pub text: String,
+
+    pub styling: CueStyling,
+}
+
+/// How to lay out and style the text of a [`SubtitleCue`], which depends on the format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CueStyling {
+    /// The decoded `tx3g` sample, whose character ranges refer to [`SubtitleCue::text`].
+    Tx3g(Tx3gSample),
+
+    /// The identifier of a WebVTT cue (`iden`) and its settings (`sttg`), like
+    /// `align:start line:0`. Both are empty if the sample does not have them.
+    WebVtt { id: String, settings: String },
+}
+
+/// Decodes the data of a sample of a subtitle track with the sample entry `entry`.
+///
+/// A `tx3g` sample holds at most one cue, and a `wvtt` sample any number of cues that are
+/// shown at the same time. Samples that just clear the screen decode to no cues.
+/// Fails for sample entries of other formats.
+pub fn decode_sample(
+    entry: &StsdBoxContent,
+    sample: &Sample,
+    bytes: &[u8],
+) -> Result<Vec<SubtitleCue>> {
+    let cue = |text, styling| SubtitleCue {
+        start: sample.presentation_time(),
+        duration: sample.duration as f64 / sample.timescale.max(1) as f64,
+        text,
+        styling,
+    };
+
+    match entry {
+        StsdBoxContent::Tx3g(_) => {
+            let decoded = tx3g::decode_sample(bytes)?;
+            if decoded.text.is_empty() {
+                return Ok(Vec::new());
+            }
+            let text = decoded.text.clone();
+            Ok(vec![cue(text, CueStyling::Tx3g(decoded))])
+        }
+        StsdBoxContent::Wvtt(_) => Ok(decode_wvtt(bytes)?
+            .into_iter()
+            .map(|(text, id, settings)| cue(text, CueStyling::WebVtt { id, settings }))
+            .collect()),
+        _ => Err(Error::InvalidData("unsupported subtitle format")),
+    }
+}
+
+/// Decodes all samples of `track` into cues, in presentation order.
+///
+/// `data` is the file the track was parsed from.
+pub fn track_cues(track: &Track, data: &[u8]) -> Result<Vec<SubtitleCue>> {
+    let entry = track.sample_entry();
+    let mut cues = Vec::new();
+    for (sample, bytes) in track.samples_with_data(data) {
+        cues.extend(decode_sample(entry, sample, bytes)?);
+    }
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+/// The text, identifier and settings of each `vttc` box of a `wvtt` sample.
+///
+/// Other boxes, like `vtte` for empty samples and `vtta` for comments, are skipped.
+fn decode_wvtt(mut bytes: &[u8]) -> Result<Vec<(String, String, String)>> {
+    let mut cues = Vec::new();
+    while !bytes.is_empty() {
+        let (box_type, contents, rest) = split_box(bytes)?;
+        bytes = rest;
+        if &box_type != b"vttc" {
+            continue;
+        }
+
+        let (mut text, mut id, mut settings) = (String::new(), String::new(), String::new());
+        let mut children = contents;
+        while !children.is_empty() {
+            let (box_type, contents, rest) = split_box(children)?;
+            children = rest;
+            let string = String::from_utf8_lossy(contents).into_owned();
+            match &box_type {
+                b"payl" => text = string,
+                b"iden" => id = string,
+                b"sttg" => settings = string,
+                _ => {}
+            }
+        }
+        cues.push((text, id, settings));
+    }
+    Ok(cues)
+}
+
+/// Splits the box at the start of `bytes` into its type, its contents and the bytes after it.
+fn split_box(bytes: &[u8]) -> Result<([u8; 4], &[u8], &[u8])> {
+    let Some((header, _)) = bytes.split_first_chunk::<8>() else {
+        return Err(Error::InvalidData("wvtt sample ends within a box header"));
+    };
+    let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if size < 8 || size > bytes.len() {
+        return Err(Error::InvalidData("invalid wvtt box size"));
+    }
+    let box_type = [header[4], header[5], header[6], header[7]];
+    Ok((box_type, &bytes[8..size], &bytes[size..]))
+}
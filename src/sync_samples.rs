@@ -0,0 +1,114 @@
+//! Finding the sync samples of AVC and HEVC tracks from their NAL units, for files that lack `stss`.
+
+use std::io::{Read, Seek};
+
+use crate::load::read_sample;
+use crate::{Error, Mp4, Result, StsdBoxContent, Track};
+
+#[derive(Clone, Copy)]
+enum NalFormat {
+    Avc { length_size: usize },
+    Hevc { length_size: usize },
+}
+
+impl NalFormat {
+    fn of(contents: &StsdBoxContent) -> Option<Self> {
+        match contents {
+            StsdBoxContent::Avc1(entry) => Some(Self::Avc {
+                length_size: usize::from(entry.avcc.length_size_minus_one & 0x3) + 1,
+            }),
+            StsdBoxContent::Hev1(entry)
+            | StsdBoxContent::Hvc1(entry)
+            | StsdBoxContent::Dvh1(entry)
+            | StsdBoxContent::Dvhe(entry) => Some(Self::Hevc {
+                length_size: usize::from(entry.hvcc.length_size_minus_one & 0x3) + 1,
+            }),
+            StsdBoxContent::Av01(_)
+            | StsdBoxContent::Vp08(_)
+            | StsdBoxContent::Vp09(_)
+            | StsdBoxContent::Mp4a(_)
+            | StsdBoxContent::Mp3(_)
+            | StsdBoxContent::Ac3(_)
+            | StsdBoxContent::Ec3(_)
+            | StsdBoxContent::Pcm(_)
+            | StsdBoxContent::Tx3g(_)
+            | StsdBoxContent::Wvtt(_)
+            | StsdBoxContent::Stpp(_)
+            | StsdBoxContent::Mett(_)
+            | StsdBoxContent::Urim(_)
+            | StsdBoxContent::Gpmd(_)
+            | StsdBoxContent::Mebx(_)
+            | StsdBoxContent::Unknown(_) => None,
+        }
+    }
+
+    fn length_size(self) -> usize {
+        match self {
+            Self::Avc { length_size } | Self::Hevc { length_size } => length_size,
+        }
+    }
+
+    /// Whether a NAL unit with this header starts a picture that can be decoded on its own.
+    fn is_random_access(self, header: u8) -> bool {
+        match self {
+            // IDR slice.
+            Self::Avc { .. } => header & 0x1f == 5,
+            // IRAP pictures: BLA, IDR, CRA, and the reserved IRAP types.
+            Self::Hevc { .. } => (16..=23).contains(&((header >> 1) & 0x3f)),
+        }
+    }
+
+    /// Whether `sample` contains a random access NAL unit.
+    ///
+    /// Scanning stops at the first NAL unit whose length runs past the end of the sample.
+    fn is_sync(self, sample: &[u8]) -> bool {
+        nal_units(sample, self.length_size()).any(|nal| self.is_random_access(nal[0]))
+    }
+}
+
+/// The NAL units of a sample whose NAL units are prefixed with their length in `length_size`
+/// bytes, as in the AVC and HEVC sample formats.
+///
+/// Stops at the first NAL unit that is empty or whose length runs past the end of the sample.
+pub(crate) fn nal_units(sample: &[u8], length_size: usize) -> impl Iterator<Item = &[u8]> {
+    let mut rest = sample;
+    std::iter::from_fn(move || {
+        if rest.len() <= length_size {
+            return None;
+        }
+        let (length, nal) = rest.split_at(length_size);
+        let length = length
+            .iter()
+            .fold(0_usize, |length, &byte| (length << 8) | usize::from(byte));
+        if length == 0 || length > nal.len() {
+            return None;
+        }
+        let (nal, after) = nal.split_at(length);
+        rest = after;
+        Some(nal)
+    })
+}
+
+/// See [`Track::detected_sync_samples`].
+pub(crate) fn detect_sync_samples<R: Read + Seek>(
+    mp4: &Mp4,
+    track: &Track,
+    reader: &mut R,
+) -> Result<Option<Vec<u32>>> {
+    let Some(format) = NalFormat::of(track.sample_entry()) else {
+        return Ok(None);
+    };
+    if track.has_external_data(mp4) {
+        return Err(Error::ExternalDataReference(track.track_id));
+    }
+
+    let mut sync_samples = Vec::new();
+    let mut buffer = Vec::new();
+    for (index, sample) in track.samples.iter().enumerate() {
+        read_sample(reader, sample, &mut buffer)?;
+        if format.is_sync(&buffer) {
+            sync_samples.push(index as u32 + 1);
+        }
+    }
+    Ok(Some(sync_samples))
+}
@@ -0,0 +1,17 @@
+//! Optional instrumentation with `tracing`, enabled by the `tracing` feature.
+//!
+//! Spans are at the debug level, so that they cost next to nothing unless a subscriber asks
+//! for them.
+
+/// Enters a debug span that lasts until the end of the enclosing block.
+///
+/// Takes the same arguments as `tracing::debug_span!`, and expands to nothing without the
+/// `tracing` feature.
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($args)*).entered();
+    };
+}
+
+pub(crate) use span;
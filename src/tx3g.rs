@@ -0,0 +1,250 @@
+//! Decoding the samples of 3GPP timed text (`tx3g`) subtitle tracks, see 3GPP TS 26.245.
+//!
+//! Every sample holds the text of one cue, followed by optional modifier boxes that style it.
+//! The time at which a cue is shown, and for how long, is that of its [`crate::Sample`].
+
+use std::io::Read as _;
+use std::ops::Range;
+
+use byteorder::{BigEndian, ReadBytesExt as _};
+
+use crate::{Error, Result, RgbaColor};
+
+/// The decoded contents of a `tx3g` sample.
+///
+/// Character ranges are in characters of [`Self::text`], with exclusive ends.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tx3gSample {
+    /// The text of the cue. Empty if the previous cue should just be cleared.
+    pub text: String,
+
+    /// Styles of ranges of the text (`styl`), overriding the default style of the sample entry.
+    pub styles: Vec<StyleRecord>,
+
+    /// Range of the text to highlight (`hlit`).
+    pub highlight: Option<Range<u16>>,
+
+    /// Color of the highlighted text (`hclr`).
+    pub highlight_color: Option<RgbaColor>,
+
+    /// Karaoke timing (`krok`).
+    pub karaoke: Option<Karaoke>,
+
+    /// Delay of scrolling text in time units of the track (`dlay`).
+    pub scroll_delay: Option<u32>,
+
+    /// Ranges of the text that link to a URL (`href`).
+    pub hyperlinks: Vec<Hyperlink>,
+
+    /// The box to render the text in, as top, left, bottom, right (`tbox`).
+    ///
+    /// Overrides the default text box of the sample entry.
+    pub text_box: Option<[i16; 4]>,
+
+    /// Ranges of the text that blink (`blnk`).
+    pub blinks: Vec<Range<u16>>,
+
+    /// Whether the text is wrapped automatically (`twrp`).
+    pub wrap: Option<bool>,
+}
+
+/// The style of a range of text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleRecord {
+    pub chars: Range<u16>,
+
+    /// Refers to an entry of the font table of the sample entry.
+    pub font_id: u16,
+
+    /// Bold (0x1), italic (0x2) and underlined (0x4).
+    pub face_style_flags: u8,
+
+    /// Font size in pixels.
+    pub font_size: u8,
+
+    pub text_color: RgbaColor,
+}
+
+impl StyleRecord {
+    pub const BOLD: u8 = 0x1;
+    pub const ITALIC: u8 = 0x2;
+    pub const UNDERLINE: u8 = 0x4;
+
+    /// Parses a style record, as found in `styl` boxes and the [`crate::Tx3gBox::style_record`]
+    /// of the sample entry.
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        Self {
+            chars: u16::from_be_bytes([bytes[0], bytes[1]])
+                ..u16::from_be_bytes([bytes[2], bytes[3]]),
+            font_id: u16::from_be_bytes([bytes[4], bytes[5]]),
+            face_style_flags: bytes[6],
+            font_size: bytes[7],
+            text_color: RgbaColor {
+                red: bytes[8],
+                green: bytes[9],
+                blue: bytes[10],
+                alpha: bytes[11],
+            },
+        }
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.face_style_flags & Self::BOLD != 0
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.face_style_flags & Self::ITALIC != 0
+    }
+
+    pub fn is_underlined(&self) -> bool {
+        self.face_style_flags & Self::UNDERLINE != 0
+    }
+}
+
+/// Karaoke timing of a cue: the ranges of text are highlighted one after another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Karaoke {
+    /// When highlighting starts, in time units of the track relative to the start of the sample.
+    pub start_time: u32,
+
+    /// The time each range of text stays highlighted until, in order.
+    pub entries: Vec<KaraokeEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KaraokeEntry {
+    /// In time units of the track relative to the start of the sample.
+    pub end_time: u32,
+    pub chars: Range<u16>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hyperlink {
+    pub chars: Range<u16>,
+    pub url: String,
+    pub alt_text: String,
+}
+
+/// Decodes the data of a sample of a `tx3g` track.
+///
+/// The text is UTF-8, or UTF-16 if it starts with a byte order mark; invalid characters are
+/// replaced with U+FFFD. Modifier boxes this crate doesn't know are skipped. An empty sample
+/// decodes to an empty cue.
+pub fn decode_sample(bytes: &[u8]) -> Result<Tx3gSample> {
+    if bytes.is_empty() {
+        return Ok(Tx3gSample::default());
+    }
+
+    let mut reader = bytes;
+    let text_length = usize::from(reader.read_u16::<BigEndian>()?);
+    if text_length > reader.len() {
+        return Err(Error::InvalidData("tx3g text exceeds sample"));
+    }
+    let (text, mut modifiers) = reader.split_at(text_length);
+
+    let mut sample = Tx3gSample {
+        text: decode_text(text),
+        ..Default::default()
+    };
+
+    while modifiers.len() >= 8 {
+        let size = u32::from_be_bytes([modifiers[0], modifiers[1], modifiers[2], modifiers[3]]);
+        let size =
+            usize::try_from(size).map_err(|_err| Error::InvalidData("tx3g box too large"))?;
+        if size < 8 || size > modifiers.len() {
+            return Err(Error::InvalidData("invalid tx3g modifier box size"));
+        }
+        let box_type = [modifiers[4], modifiers[5], modifiers[6], modifiers[7]];
+        let mut contents = &modifiers[8..size];
+        modifiers = &modifiers[size..];
+
+        match &box_type {
+            b"styl" => {
+                let entry_count = contents.read_u16::<BigEndian>()?;
+                for _ in 0..entry_count {
+                    let mut record = [0; 12];
+                    contents.read_exact(&mut record)?;
+                    sample.styles.push(StyleRecord::from_bytes(record));
+                }
+            }
+            b"hlit" => sample.highlight = Some(read_range(&mut contents)?),
+            b"hclr" => sample.highlight_color = Some(read_color(&mut contents)?),
+            b"krok" => {
+                let start_time = contents.read_u32::<BigEndian>()?;
+                let entry_count = contents.read_u16::<BigEndian>()?;
+                let mut entries =
+                    Vec::with_capacity(usize::from(entry_count).min(contents.len() / 8));
+                for _ in 0..entry_count {
+                    entries.push(KaraokeEntry {
+                        end_time: contents.read_u32::<BigEndian>()?,
+                        chars: read_range(&mut contents)?,
+                    });
+                }
+                sample.karaoke = Some(Karaoke {
+                    start_time,
+                    entries,
+                });
+            }
+            b"dlay" => sample.scroll_delay = Some(contents.read_u32::<BigEndian>()?),
+            b"href" => {
+                let chars = read_range(&mut contents)?;
+                let url = read_string(&mut contents)?;
+                let alt_text = read_string(&mut contents)?;
+                sample.hyperlinks.push(Hyperlink {
+                    chars,
+                    url,
+                    alt_text,
+                });
+            }
+            b"tbox" => {
+                sample.text_box = Some([
+                    contents.read_i16::<BigEndian>()?,
+                    contents.read_i16::<BigEndian>()?,
+                    contents.read_i16::<BigEndian>()?,
+                    contents.read_i16::<BigEndian>()?,
+                ]);
+            }
+            b"blnk" => sample.blinks.push(read_range(&mut contents)?),
+            b"twrp" => sample.wrap = Some(contents.read_u8()? != 0),
+            _ => {}
+        }
+    }
+
+    Ok(sample)
+}
+
+fn decode_text(text: &[u8]) -> String {
+    if let Some(utf16) = text.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(text).into_owned()
+    }
+}
+
+fn read_range(reader: &mut &[u8]) -> Result<Range<u16>> {
+    Ok(reader.read_u16::<BigEndian>()?..reader.read_u16::<BigEndian>()?)
+}
+
+fn read_color(reader: &mut &[u8]) -> Result<RgbaColor> {
+    Ok(RgbaColor {
+        red: reader.read_u8()?,
+        green: reader.read_u8()?,
+        blue: reader.read_u8()?,
+        alpha: reader.read_u8()?,
+    })
+}
+
+/// A string with an 8-bit length prefix.
+fn read_string(reader: &mut &[u8]) -> Result<String> {
+    let length = usize::from(reader.read_u8()?);
+    if length > reader.len() {
+        return Err(Error::InvalidData("tx3g string exceeds box"));
+    }
+    let (string, rest) = reader.split_at(length);
+    *reader = rest;
+    Ok(String::from_utf8_lossy(string).into_owned())
+}
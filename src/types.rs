@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt;
@@ -9,7 +9,7 @@ use crate::{Error, Result};
 pub use bytes::Bytes;
 pub use num_rational::Ratio;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FixedPointU8(Ratio<u16>);
 
 impl FixedPointU8 {
@@ -30,7 +30,7 @@ impl FixedPointU8 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FixedPointI8(Ratio<i16>);
 
 impl FixedPointI8 {
@@ -51,7 +51,7 @@ impl FixedPointI8 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FixedPointU16(Ratio<u32>);
 
 impl FixedPointU16 {
@@ -86,7 +86,7 @@ impl fmt::Display for BoxType {
     }
 }
 
-#[derive(Default, PartialEq, Eq, Clone, Copy, Serialize)]
+#[derive(Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct FourCC {
     pub value: [u8; 4],
 }
@@ -152,9 +152,157 @@ impl fmt::Display for FourCC {
     }
 }
 
+impl FourCC {
+    /// Human readable description of this four character code, if it is a known box type,
+    /// sample entry, or handler type.
+    ///
+    /// Meant for diagnostics aimed at people who don't know the ISO-BMFF box zoo by heart.
+    pub fn description(&self) -> Option<&'static str> {
+        Some(match &self.value {
+            // File level
+            b"ftyp" => "File type and compatibility",
+            b"moov" => "Movie (container for all metadata)",
+            b"moof" => "Movie fragment",
+            b"mdat" => "Media data",
+            b"free" => "Free space",
+            b"skip" => "Free space (skip)",
+            b"wide" => "QuickTime 64-bit size placeholder",
+            b"emsg" => "Event message (DASH)",
+            b"styp" => "Segment type",
+            b"sidx" => "Segment index",
+            b"mfra" => "Movie fragment random access",
+
+            // Movie
+            b"mvhd" => "Movie header",
+            b"mvex" => "Movie extends (file is fragmented)",
+            b"mehd" => "Movie extends header (fragment duration)",
+            b"trex" => "Track extends (fragment sample defaults)",
+            b"udta" => "User data",
+            b"meta" => "Metadata",
+            b"ilst" => "iTunes metadata item list",
+            b"data" => "iTunes metadata value",
+            b"\xa9nam" => "iTunes metadata: title",
+            b"\xa9day" => "iTunes metadata: release date",
+            b"covr" => "iTunes metadata: cover art",
+            b"desc" => "iTunes metadata: description",
+            b"----" => "iTunes metadata: freeform item",
+            b"mean" => "iTunes metadata: freeform item namespace",
+            b"name" => "iTunes metadata: freeform item name",
+            b"chpl" => "Nero chapter list",
+
+            // Items (HEIF)
+            b"iloc" => "Item locations",
+            b"iinf" => "Item information",
+            b"pitm" => "Primary item",
+            b"iprp" => "Item properties",
+
+            // Track
+            b"trak" => "Track",
+            b"tkhd" => "Track header",
+            b"edts" => "Edit list container",
+            b"tref" => "Track references",
+            b"chap" => "Chapter track reference",
+            b"elst" => "Edit list",
+            b"mdia" => "Media",
+            b"mdhd" => "Media header",
+            b"hdlr" => "Handler reference",
+            b"minf" => "Media information",
+            b"vmhd" => "Video media header",
+            b"smhd" => "Sound media header",
+            b"gmhd" => "QuickTime base media header",
+            b"gmin" => "QuickTime base media info",
+            b"dinf" => "Data information",
+            b"dref" => "Data reference",
+            b"url " => "Data entry URL",
+            b"urn " => "Data entry URN",
+
+            // Sample table
+            b"stbl" => "Sample table",
+            b"stsd" => "Sample descriptions (codec configuration)",
+            b"stts" => "Decoding time-to-sample",
+            b"ctts" => "Composition time offsets",
+            b"stss" => "Sync samples (keyframes)",
+            b"sdtp" => "Independent and disposable samples",
+            b"stsc" => "Sample-to-chunk",
+            b"stsz" => "Sample sizes",
+            b"stco" => "Chunk offsets (32-bit)",
+            b"co64" => "Chunk offsets (64-bit)",
+
+            // Fragments
+            b"mfhd" => "Movie fragment header",
+            b"traf" => "Track fragment",
+            b"tfhd" => "Track fragment header",
+            b"tfdt" => "Track fragment decode time",
+            b"trun" => "Track fragment run",
+
+            // Sample entries & codec configuration
+            b"avc1" => "AVC (H.264) sample entry, parameter sets out-of-band",
+            b"avc3" => "AVC (H.264) sample entry, parameter sets in-band",
+            b"avcC" => "AVC (H.264) decoder configuration",
+            b"hvc1" => "HEVC (H.265) sample entry, parameter sets out-of-band",
+            b"hev1" => "HEVC (H.265) sample entry, parameter sets in-band",
+            b"hvcC" => "HEVC (H.265) decoder configuration",
+            b"dvh1" => "Dolby Vision HEVC sample entry, parameter sets out-of-band",
+            b"dvhe" => "Dolby Vision HEVC sample entry, parameter sets in-band",
+            b"dvcC" => "Dolby Vision configuration (profiles up to 7)",
+            b"dvvC" => "Dolby Vision configuration (profiles 8 to 10)",
+            b"dvwC" => "Dolby Vision configuration (profiles above 10)",
+            b"av01" => "AV1 sample entry",
+            b"av1C" => "AV1 decoder configuration",
+            b"vp08" => "VP8 sample entry",
+            b"vp09" => "VP9 sample entry",
+            b"vpcC" => "VP8/VP9 decoder configuration",
+            b"colr" => "Colour information",
+            b"pasp" => "Pixel aspect ratio",
+            b"clap" => "Clean aperture",
+            b"btrt" => "Bitrate",
+            b"mp4a" => "MPEG-4 audio (AAC) sample entry",
+            b".mp3" => "MPEG-1 Layer III (MP3) audio sample entry",
+            b"esds" => "Elementary stream descriptor",
+            b"wave" => "QuickTime sound extension",
+            b"ac-3" => "Dolby Digital (AC-3) sample entry",
+            b"dac3" => "AC-3 decoder configuration",
+            b"ec-3" => "Dolby Digital Plus (E-AC-3) sample entry",
+            b"dec3" => "E-AC-3 decoder configuration",
+            b"lpcm" => "Uncompressed audio sample entry (QuickTime)",
+            b"sowt" => "Uncompressed 16-bit little endian audio sample entry",
+            b"twos" => "Uncompressed big endian audio sample entry",
+            b"in24" => "Uncompressed 24-bit audio sample entry",
+            b"in32" => "Uncompressed 32-bit audio sample entry",
+            b"fl32" => "Uncompressed 32-bit float audio sample entry",
+            b"fl64" => "Uncompressed 64-bit float audio sample entry",
+            b"enda" => "Endianness of uncompressed audio",
+            b"tx3g" => "3GPP timed text (subtitles) sample entry",
+            b"wvtt" => "WebVTT subtitles sample entry",
+            b"vttC" => "WebVTT configuration",
+            b"vlab" => "WebVTT source label",
+            b"stpp" => "XML (TTML) subtitles sample entry",
+            b"mett" => "Text timed metadata sample entry",
+            b"urim" => "URI timed metadata sample entry",
+            b"uri " => "URI of a timed metadata format",
+            b"uriI" => "Initialization data of a timed metadata format",
+            b"gpmd" => "GoPro telemetry (GPMF) sample entry",
+            b"mebx" => "Apple timed metadata sample entry",
+            b"keyd" => "Key of Apple timed metadata",
+            b"dtyp" => "Data type of Apple timed metadata",
+
+            // Handlers
+            b"vide" => "Video handler",
+            b"soun" => "Sound handler",
+            b"pict" => "Picture handler (HEIF images)",
+            b"sbtl" => "Subtitle handler",
+            b"text" => "Text handler",
+            b"mdir" => "Metadata directory handler",
+
+            _ => return None,
+        })
+    }
+}
+
 const DISPLAY_TYPE_VIDEO: &str = "Video";
 const DISPLAY_TYPE_AUDIO: &str = "Audio";
 const DISPLAY_TYPE_SUBTITLE: &str = "Subtitle";
+const DISPLAY_TYPE_METADATA: &str = "Metadata";
 
 const HANDLER_TYPE_VIDEO: &str = "vide";
 const HANDLER_TYPE_VIDEO_FOURCC: [u8; 4] = [b'v', b'i', b'd', b'e'];
@@ -165,13 +313,19 @@ const HANDLER_TYPE_AUDIO_FOURCC: [u8; 4] = [b's', b'o', b'u', b'n'];
 const HANDLER_TYPE_SUBTITLE: &str = "sbtl";
 const HANDLER_TYPE_SUBTITLE_FOURCC: [u8; 4] = [b's', b'b', b't', b'l'];
 
+const HANDLER_TYPE_METADATA: &str = "meta";
+const HANDLER_TYPE_METADATA_FOURCC: [u8; 4] = [b'm', b'e', b't', b'a'];
+
 pub type TrackId = u32;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TrackKind {
     Video,
     Audio,
     Subtitle,
+
+    /// Timed metadata, like the GPS or IMU readings of drones, dashcams and phones.
+    Metadata,
 }
 
 impl fmt::Display for TrackKind {
@@ -180,6 +334,7 @@ impl fmt::Display for TrackKind {
             Self::Video => DISPLAY_TYPE_VIDEO,
             Self::Audio => DISPLAY_TYPE_AUDIO,
             Self::Subtitle => DISPLAY_TYPE_SUBTITLE,
+            Self::Metadata => DISPLAY_TYPE_METADATA,
         };
         write!(f, "{s}")
     }
@@ -192,6 +347,7 @@ impl TryFrom<&str> for TrackKind {
             HANDLER_TYPE_VIDEO => Ok(Self::Video),
             HANDLER_TYPE_AUDIO => Ok(Self::Audio),
             HANDLER_TYPE_SUBTITLE => Ok(Self::Subtitle),
+            HANDLER_TYPE_METADATA => Ok(Self::Metadata),
             _ => Err(Error::InvalidData("unsupported handler type")),
         }
     }
@@ -204,6 +360,7 @@ impl TryFrom<&FourCC> for TrackKind {
             HANDLER_TYPE_VIDEO_FOURCC => Ok(Self::Video),
             HANDLER_TYPE_AUDIO_FOURCC => Ok(Self::Audio),
             HANDLER_TYPE_SUBTITLE_FOURCC => Ok(Self::Subtitle),
+            HANDLER_TYPE_METADATA_FOURCC => Ok(Self::Metadata),
             _ => Err(Error::InvalidData("unsupported handler type")),
         }
     }
@@ -215,10 +372,113 @@ impl From<TrackKind> for FourCC {
             TrackKind::Video => HANDLER_TYPE_VIDEO_FOURCC.into(),
             TrackKind::Audio => HANDLER_TYPE_AUDIO_FOURCC.into(),
             TrackKind::Subtitle => HANDLER_TYPE_SUBTITLE_FOURCC.into(),
+            TrackKind::Metadata => HANDLER_TYPE_METADATA_FOURCC.into(),
         }
     }
 }
 
+/// What kind of file an MP4 file is, see [`crate::Mp4::file_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileKind {
+    /// A movie, with video and possibly other tracks.
+    Video,
+
+    /// An audio file, such as `.m4a` or `.m4b`.
+    Audio,
+
+    /// A HEIF image sequence, which has its images in a track.
+    ImageSequence,
+
+    /// A HEIF or AVIF image, which has its images as items in the file-level `meta` box.
+    Heif,
+}
+
+impl FileKind {
+    /// The kind of file that a brand in `ftyp` implies, if any.
+    pub fn from_brand(brand: FourCC) -> Option<Self> {
+        match &brand.value {
+            b"mif1" | b"mif2" | b"heic" | b"heix" | b"heim" | b"heis" | b"avif" => Some(Self::Heif),
+            b"msf1" | b"hevc" | b"hevx" | b"hevm" | b"hevs" | b"avis" => Some(Self::ImageSequence),
+            b"M4A " | b"M4B " | b"M4P " | b"F4A " | b"F4B " => Some(Self::Audio),
+            _ => None,
+        }
+    }
+}
+
+/// An ISO 639-2/T language code, like `eng`, as stored in the media header (`mdhd`).
+///
+/// `mdhd` packs the three lowercase letters into 15 bits, five bits per letter, see
+/// [`Self::from_packed`] and [`Self::to_packed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Language([u8; 3]);
+
+impl Language {
+    /// `und`, for a language that is not specified.
+    pub const UNDETERMINED: Self = Self(*b"und");
+
+    /// Unpacks a language code as stored in `mdhd`, or returns `None` if it isn't three
+    /// letters from `a` to `z`.
+    pub fn from_packed(code: u16) -> Option<Self> {
+        if code & 0x8000 != 0 {
+            return None;
+        }
+        let letter = |shift: u16| {
+            let value = ((code >> shift) & 0x1F) as u8;
+            (1..=26).contains(&value).then_some(value + 0x60)
+        };
+        Some(Self([letter(10)?, letter(5)?, letter(0)?]))
+    }
+
+    /// Packs the language code the way it is stored in `mdhd`.
+    pub fn to_packed(self) -> u16 {
+        let [a, b, c] = self.0.map(|letter| u16::from(letter - 0x60));
+        (a << 10) | (b << 5) | c
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ever constructed from ASCII letters.
+        std::str::from_utf8(&self.0).unwrap_or("und")
+    }
+
+    pub fn is_undetermined(&self) -> bool {
+        *self == Self::UNDETERMINED
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.as_bytes() {
+            &[a, b, c] if [a, b, c].iter().all(u8::is_ascii_lowercase) => Ok(Self([a, b, c])),
+            _ => Err(Error::InvalidData(
+                "expected a language code of three lowercase letters",
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Language {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.as_str().to_owned()
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 const MEDIA_TYPE_H264: &str = "h264";
 const MEDIA_TYPE_H265: &str = "h265";
 const MEDIA_TYPE_VP9: &str = "vp9";
@@ -671,11 +931,13 @@ pub fn creation_time(creation_time: u64) -> u64 {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DataType {
     Binary = 0x000000,
     Text = 0x000001,
+    /// A JPEG image.
     Image = 0x00000D,
+    PngImage = 0x00000E,
     TempoCpil = 0x000015,
 }
 
@@ -693,13 +955,14 @@ impl TryFrom<u32> for DataType {
             0x000000 => Ok(Self::Binary),
             0x000001 => Ok(Self::Text),
             0x00000D => Ok(Self::Image),
+            0x00000E => Ok(Self::PngImage),
             0x000015 => Ok(Self::TempoCpil),
             _ => Err(Error::InvalidData("invalid data type")),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum MetadataKey {
     Title,
     Year,
@@ -0,0 +1,41 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{
+    AacConfig, AudioInfo, AudioObjectType, ChannelConfig, FixedPointI8, Mp4aBox, SampleFreqIndex,
+    SmhdBox, StsdBox, StsdBoxContent,
+};
+use synthetic::sound_track;
+
+#[test]
+fn audio_info_prefers_decoder_configuration() {
+    let config = AacConfig {
+        freq_index: SampleFreqIndex::Freq96000,
+        chan_conf: ChannelConfig::FiveOne,
+        ..Default::default()
+    };
+    let mut mp4 = sound_track(StsdBox {
+        contents: StsdBoxContent::Mp4a(Mp4aBox::new(&config)),
+        ..Default::default()
+    });
+    let trak = &mut mp4.moov.traks[0];
+    trak.mdia.mdhd.language = String::from("eng");
+    trak.mdia.minf.smhd = Some(SmhdBox {
+        balance: FixedPointI8::new_raw(-128),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        mp4.tracks()[&1].audio_info(&mp4),
+        Some(AudioInfo {
+            // 96 kHz does not fit the 16-bit rate of the sample entry.
+            sample_rate: 96_000,
+            channel_count: 6,
+            sample_size: 16,
+            profile: Some(AudioObjectType::AacLowComplexity),
+            bitrate: None,
+            language: String::from("eng"),
+            balance: -0.5,
+        })
+    );
+}
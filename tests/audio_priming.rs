@@ -0,0 +1,133 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{AacConfig, AudioPriming, EdtsBox, ElstBox, ElstEntry, Mp4, Mp4aBox, StsdBoxContent};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+/// A file with an AAC track of ten 1024-sample frames at 48 kHz, after its `mdat`.
+fn aac_file() -> Vec<u8> {
+    build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![vec![vec![0; 4]; 10]],
+            sync_samples: None,
+        }],
+        false,
+    )
+}
+
+/// Parses `data` and makes its track AAC, with `edits` as its edit list if any.
+fn read_aac(data: &[u8], edits: Option<Vec<ElstEntry>>) -> Mp4 {
+    let mut mp4 = Mp4::read_bytes(data).expect("Failed parsing");
+    let trak = &mut mp4.moov.traks[0];
+    trak.mdia.minf.stbl.stsd.contents = StsdBoxContent::Mp4a(Mp4aBox::new(&AacConfig::default()));
+    // The synthetic `stts` has a delta of one, so give the frames their real duration.
+    trak.mdia.minf.stbl.stts.entries[0].sample_delta = 1024;
+    trak.mdia.mdhd.duration = 10 * 1024;
+    trak.edts = edits.map(|entries| EdtsBox {
+        elst: Some(ElstBox {
+            version: 0,
+            flags: 0,
+            entries,
+        }),
+    });
+    mp4.moov.mvhd.timescale = 48000;
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+    mp4
+}
+
+fn edit(segment_duration: u64, media_time: u64) -> ElstEntry {
+    ElstEntry {
+        segment_duration,
+        media_time,
+        media_rate: 1,
+        media_rate_fraction: 0,
+    }
+}
+
+#[test]
+fn priming_from_edit_list() {
+    let data = aac_file();
+    let mp4 = read_aac(&data, Some(vec![edit(10 * 1024 - 2112 - 100, 2112)]));
+    assert_eq!(
+        mp4.tracks()[&1].audio_priming(&mp4),
+        Some(AudioPriming {
+            delay_samples: 2112,
+            padding_samples: 100,
+        })
+    );
+
+    // An empty edit before the media doesn't count as priming.
+    let mp4 = read_aac(
+        &data,
+        Some(vec![edit(480, u64::from(u32::MAX)), edit(0, 1024)]),
+    );
+    assert_eq!(
+        mp4.tracks()[&1].audio_priming(&mp4),
+        Some(AudioPriming {
+            delay_samples: 1024,
+            padding_samples: 0,
+        })
+    );
+
+    let mp4 = read_aac(&data, None);
+    assert_eq!(mp4.tracks()[&1].audio_priming(&mp4), None);
+}
+
+#[test]
+fn priming_from_itunsmpb() {
+    let full_box = |name, contents: &[u8]| mp4_box(name, &[&[0; 4], contents]);
+    let freeform = mp4_box(
+        b"----",
+        &[
+            &full_box(b"mean", b"com.apple.iTunes"),
+            &full_box(b"name", b"iTunSMPB"),
+            &mp4_box(
+                b"data",
+                &[
+                    &[0, 0, 0, 1, 0, 0, 0, 0],
+                    b" 00000000 00000840 000001CA 00000000000A5E36 00000000",
+                ],
+            ),
+        ],
+    );
+    let hdlr = full_box(b"hdlr", &[&[0; 4][..], b"mdir", &[0; 13]].concat());
+    let meta = full_box(b"meta", &[hdlr, mp4_box(b"ilst", &[&freeform])].concat());
+
+    let mut data = aac_file();
+    append_child(&mut data, &[b"moov"], &meta);
+
+    // `iTunSMPB` takes precedence over the edit list.
+    let mp4 = read_aac(&data, Some(vec![edit(0, 1024)]));
+    let ilst = mp4
+        .moov
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.ilst())
+        .expect("Expected an item list");
+    assert_eq!(ilst.freeform[0].mean, "com.apple.iTunes");
+    assert_eq!(
+        mp4.tracks()[&1].audio_priming(&mp4),
+        Some(AudioPriming {
+            delay_samples: 0x840,
+            padding_samples: 0x1ca,
+        })
+    );
+}
+
+#[test]
+fn no_priming_for_other_tracks() {
+    let data = aac_file();
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    mp4.moov.traks[0].edts = Some(EdtsBox {
+        elst: Some(ElstBox {
+            version: 0,
+            flags: 0,
+            entries: vec![edit(0, 1024)],
+        }),
+    });
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(mp4.tracks()[&1].audio_priming(&mp4), None);
+}
@@ -0,0 +1,157 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{Av1CBox, Av1ColorConfig, Av1OperatingPoint, BoxHeader, ReadBox as _};
+use synthetic::{mp4_box, Bits};
+
+/// An `av1C` box whose config OBUs are a sequence header OBU with `payload`.
+fn read_av1c(payload: &[u8]) -> Av1CBox {
+    let mut av1c = vec![0x81, 0x4c, 0x70, 0, 0x0a, payload.len() as u8];
+    av1c.extend(payload);
+    let av1c = mp4_box(b"av1C", &[&av1c]);
+    let mut reader = Cursor::new(&av1c);
+    let header = BoxHeader::read(&mut reader).expect("Failed reading header");
+    Av1CBox::read_box(&mut reader, header.size).expect("Failed reading av1C")
+}
+
+#[test]
+fn sequence_header_with_operating_points() {
+    let payload = Bits::default()
+        .put(2, 3) // seq_profile
+        .flag(false)
+        .flag(false) // reduced_still_picture_header
+        .flag(true) // timing_info_present_flag
+        .put(1001, 32)
+        .put(60000, 32)
+        .flag(false) // equal_picture_interval
+        .flag(true) // decoder_model_info_present_flag
+        .put(9, 5) // buffer_delay_length_minus_1
+        .put(1, 32)
+        .put(0, 5)
+        .put(0, 5)
+        .flag(false) // initial_display_delay_present_flag
+        .put(1, 5) // operating_points_cnt_minus_1
+        .put(0x103, 12)
+        .put(12, 5) // seq_level_idx
+        .flag(true) // seq_tier
+        .flag(true) // decoder_model_present_for_this_op
+        .put(0, 10)
+        .put(0, 10)
+        .flag(false)
+        .put(0x101, 12)
+        .put(5, 5) // seq_level_idx
+        .flag(false) // decoder_model_present_for_this_op
+        .put(11, 4)
+        .put(11, 4)
+        .put(3839, 12)
+        .put(2159, 12)
+        .flag(false) // frame_id_numbers_present_flag
+        .put(0, 3)
+        .put(0, 4)
+        .flag(false) // enable_order_hint
+        .flag(false) // seq_choose_screen_content_tools
+        .flag(false) // seq_force_screen_content_tools
+        .put(0, 3)
+        .flag(true) // high_bitdepth
+        .flag(true) // twelve_bit
+        .flag(false) // mono_chrome
+        .flag(true) // color_description_present_flag
+        .put(9, 8)
+        .put(16, 8)
+        .put(9, 8)
+        .flag(false) // color_range
+        .flag(true) // subsampling_x
+        .flag(false) // subsampling_y
+        .flag(false) // separate_uv_delta_q
+        .flag(true) // film_grain_params_present
+        .into_bytes();
+
+    let header = read_av1c(&payload).sequence_header().unwrap().unwrap();
+    assert_eq!(header.profile, 2);
+    assert!(!header.still_picture);
+    assert_eq!(
+        header.operating_points,
+        [
+            Av1OperatingPoint {
+                idc: 0x103,
+                level: 12,
+                high_tier: true,
+            },
+            Av1OperatingPoint {
+                idc: 0x101,
+                level: 5,
+                high_tier: false,
+            },
+        ]
+    );
+    assert_eq!(
+        (header.max_frame_width, header.max_frame_height),
+        (3840, 2160)
+    );
+    assert_eq!(
+        header.color,
+        Av1ColorConfig {
+            bit_depth: 12,
+            monochrome: false,
+            subsampling_x: true,
+            subsampling_y: false,
+            color_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            full_range: false,
+            chroma_sample_position: 0,
+        }
+    );
+    assert!(header.film_grain_params_present);
+}
+
+#[test]
+fn reduced_still_picture_header() {
+    let payload = Bits::default()
+        .put(0, 3) // seq_profile
+        .flag(true) // still_picture
+        .flag(true) // reduced_still_picture_header
+        .put(4, 5) // seq_level_idx
+        .put(7, 4)
+        .put(7, 4)
+        .put(255, 8)
+        .put(127, 8)
+        .put(0, 3)
+        .put(0, 3)
+        .flag(false) // high_bitdepth
+        .flag(true) // mono_chrome
+        .flag(false) // color_description_present_flag
+        .flag(true) // color_range
+        .flag(false) // film_grain_params_present
+        .into_bytes();
+
+    let header = read_av1c(&payload).sequence_header().unwrap().unwrap();
+    assert!(header.still_picture && header.reduced_still_picture_header);
+    assert_eq!(
+        header.operating_points,
+        [Av1OperatingPoint {
+            idc: 0,
+            level: 4,
+            high_tier: false,
+        }]
+    );
+    assert_eq!(
+        (header.max_frame_width, header.max_frame_height),
+        (256, 128)
+    );
+    assert!(header.color.monochrome && header.color.full_range);
+    assert_eq!(header.color.bit_depth, 8);
+    assert_eq!(header.color.color_primaries, 2);
+    assert!(!header.film_grain_params_present);
+}
+
+#[test]
+fn missing_and_truncated_sequence_header() {
+    let av1c = Av1CBox::default();
+    assert_eq!(av1c.sequence_header().unwrap(), None);
+
+    let truncated = read_av1c(&[0x40, 0x00]);
+    assert!(truncated.sequence_header().is_err());
+}
@@ -0,0 +1,54 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{BoxNode, FourCC, Mp4};
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+fn types(nodes: &[BoxNode]) -> Vec<FourCC> {
+    nodes.iter().map(|node| node.box_type).collect()
+}
+
+fn fourccs(names: &[&[u8; 4]]) -> Vec<FourCC> {
+    names.iter().map(|name| FourCC::from(**name)).collect()
+}
+
+#[test]
+fn box_tree_lists_all_boxes() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]]],
+        sync_samples: None,
+    }];
+    let mut data = build_progressive(&tracks, true);
+    data.extend(mp4_box(b"abcd", &[&[0; 4]]));
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    let tree = mp4.box_tree();
+    assert_eq!(
+        types(tree),
+        fourccs(&[b"ftyp", b"free", b"moov", b"mdat", b"abcd"])
+    );
+    assert!(tree[2].known);
+    assert!(!tree[4].known);
+    assert_eq!(tree[4].offset + tree[4].size, data.len() as u64);
+    assert_eq!(tree[4].data_range(), tree[4].offset + 8..data.len() as u64);
+
+    let stsd = tree[2]
+        .descendants()
+        .find(|node| node.box_type == FourCC::from(*b"stsd"))
+        .unwrap();
+    assert_eq!(types(&stsd.children), fourccs(&[b"test"]));
+    assert!(!stsd.children[0].known);
+
+    // Every box lies within its parent.
+    for top_level in tree {
+        for node in top_level.descendants() {
+            for child in &node.children {
+                assert!(child.offset >= node.data_range().start);
+                assert!(child.offset + child.size <= node.data_range().end);
+            }
+        }
+    }
+}
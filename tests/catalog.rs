@@ -0,0 +1,78 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::catalog::{probe, scan, ScanOptions};
+use re_mp4::{FileKind, Limits};
+use synthetic::{build_progressive, TestTrack};
+
+fn video_track(num_samples: usize) -> TestTrack {
+    TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 1000,
+        chunks: vec![vec![vec![0; 16]; num_samples]],
+        sync_samples: None,
+    }
+}
+
+#[test]
+fn probe_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("video.mp4");
+    let data = build_progressive(&[video_track(3)], false);
+    std::fs::write(&path, &data).unwrap();
+
+    let info = probe(&path, Limits::UNLIMITED).unwrap();
+    assert_eq!(info.size, data.len() as u64);
+    assert_eq!(info.kind, FileKind::Video);
+    assert!(!info.fragmented);
+    assert_eq!(info.tracks.len(), 1);
+    assert_eq!(info.tracks[0].track_id, 1);
+    assert_eq!(info.tracks[0].num_samples, 3);
+    assert_eq!(info.duration, info.tracks[0].duration);
+}
+
+#[test]
+fn scan_isolates_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("nested");
+    std::fs::create_dir(&nested).unwrap();
+    std::fs::write(
+        dir.path().join("a.mp4"),
+        build_progressive(&[video_track(2)], true),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("b.MOV"), b"not an mp4 file").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), b"skipped").unwrap();
+    std::fs::write(
+        nested.join("c.mp4"),
+        build_progressive(&[video_track(4)], false),
+    )
+    .unwrap();
+
+    let results = scan(dir.path(), &ScanOptions::default()).unwrap();
+    let paths: Vec<_> = results.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(
+        paths,
+        [
+            dir.path().join("a.mp4"),
+            dir.path().join("b.MOV"),
+            nested.join("c.mp4"),
+        ]
+    );
+    assert_eq!(results[0].1.as_ref().unwrap().tracks[0].num_samples, 2);
+    assert!(results[1].1.is_err());
+    assert_eq!(results[2].1.as_ref().unwrap().tracks[0].num_samples, 4);
+
+    let options = ScanOptions {
+        recursive: false,
+        ..Default::default()
+    };
+    assert_eq!(scan(dir.path(), &options).unwrap().len(), 2);
+}
+
+#[test]
+fn scan_missing_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(scan(dir.path().join("missing"), &ScanOptions::default()).is_err());
+}
@@ -0,0 +1,165 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use re_mp4::{
+    BoxHeader, Chapter, ChplBox, ChplEntry, FourCC, Mp4, Mp4Box as _, ReadBox as _, TrackReference,
+    TrefBox, WriteBox as _,
+};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+fn title_sample(title: &str) -> Vec<u8> {
+    [&(title.len() as u16).to_be_bytes()[..], title.as_bytes()].concat()
+}
+
+/// A file with a video track, and a text track with two chapters, after `mdat`.
+fn file() -> Vec<u8> {
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 10], vec![2; 10]]],
+            sync_samples: None,
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"text",
+            timescale: 1000,
+            chunks: vec![vec![title_sample("Intro"), title_sample("Ending")]],
+            sync_samples: None,
+        },
+    ];
+    build_progressive(&tracks, false)
+}
+
+fn chpl() -> ChplBox {
+    ChplBox {
+        version: 1,
+        flags: 0,
+        chapters: vec![
+            ChplEntry {
+                start_time: 0,
+                title: "One".to_owned(),
+            },
+            ChplEntry {
+                start_time: 15_000_000,
+                title: "Two".to_owned(),
+            },
+        ],
+    }
+}
+
+fn to_bytes(write: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write(&mut bytes);
+    bytes
+}
+
+#[test]
+fn chapters_from_chapter_track() {
+    let tref = TrefBox {
+        references: vec![TrackReference {
+            reference_type: FourCC::from(*b"chap"),
+            track_ids: vec![2],
+        }],
+    };
+    let mut data = file();
+    append_child(
+        &mut data,
+        &[b"moov", b"trak"],
+        &to_bytes(|bytes| {
+            tref.write_box(bytes).unwrap();
+        }),
+    );
+    // The chapter track takes precedence over `chpl`.
+    append_child(
+        &mut data,
+        &[b"moov"],
+        &mp4_box(
+            b"udta",
+            &[&to_bytes(|bytes| {
+                chpl().write_box(bytes).unwrap();
+            })],
+        ),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.moov.traks[0].tref.as_ref(), Some(&tref));
+    assert_eq!(
+        mp4.chapters(),
+        [
+            Chapter {
+                start_time: Duration::ZERO,
+                title: "Intro".to_owned(),
+            },
+            Chapter {
+                start_time: Duration::from_millis(1),
+                title: "Ending".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn chapters_from_chpl() {
+    let mut data = file();
+    append_child(
+        &mut data,
+        &[b"moov"],
+        &mp4_box(
+            b"udta",
+            &[&to_bytes(|bytes| {
+                chpl().write_box(bytes).unwrap();
+            })],
+        ),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.moov.udta.as_ref().unwrap().chpl, Some(chpl()));
+    assert_eq!(
+        mp4.chapters(),
+        [
+            Chapter {
+                start_time: Duration::ZERO,
+                title: "One".to_owned(),
+            },
+            Chapter {
+                start_time: Duration::from_millis(1500),
+                title: "Two".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn no_chapters() {
+    let mp4 = Mp4::read_bytes(&file()).unwrap();
+    assert!(mp4.chapters().is_empty());
+}
+
+#[test]
+fn chpl_round_trip() {
+    let long_title = "é".repeat(200);
+    let chpl = ChplBox {
+        version: 0,
+        flags: 0,
+        chapters: vec![ChplEntry {
+            start_time: 42,
+            title: long_title.clone(),
+        }],
+    };
+    let bytes = to_bytes(|bytes| {
+        chpl.write_box(bytes).unwrap();
+    });
+    assert_eq!(bytes.len() as u64, chpl.box_size());
+
+    let mut reader = Cursor::new(&bytes);
+    let header = BoxHeader::read(&mut reader).unwrap();
+    let read = ChplBox::read_box(&mut reader, header.size).unwrap();
+    // The title is cut off at 255 bytes, which is 127 two-byte characters.
+    assert_eq!(read.chapters[0].title, long_title[..254]);
+    assert_eq!(read.chapters[0].start_time, 42);
+}
@@ -0,0 +1,168 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{
+    AacConfig, AacDetails, AudioObjectType, Avc1Box, AvcCBox, AvcDetails, BtrtBox, Codec, FourCC,
+    HevcBox, HevcDecoderConfigurationRecord, HevcDetails, Mp4, Mp4aBox, RawBox, StsdBoxContent,
+};
+use synthetic::{build_progressive, TestTrack};
+
+fn avc1(sps: &[u8], ext: &[u8]) -> StsdBoxContent {
+    StsdBoxContent::Avc1(Avc1Box {
+        avcc: RawBox {
+            contents: AvcCBox {
+                ext: ext.to_vec(),
+                ..AvcCBox::new(sps, &[0x68])
+            },
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    })
+}
+
+#[test]
+fn h264() {
+    let codec = Codec::from_sample_entry(&avc1(&[0x67, 0x42, 0x40, 0x1e], &[]));
+    assert!(
+        matches!(
+            codec,
+            Codec::H264(AvcDetails {
+                profile: 66,
+                constraints: 0x40,
+                level: 30,
+                bit_depth: 8,
+                chroma_format: 1,
+                bitrate: None,
+                ..
+            })
+        ),
+        "{codec:?}"
+    );
+
+    // High 10 with 4:2:2, signaled after the parameter sets.
+    let codec = Codec::from_sample_entry(&avc1(&[0x67, 110, 0x00, 0x29], &[0xfe, 0xfa, 0xfa, 0]));
+    assert_eq!(codec.profile(), Some(110));
+    assert_eq!(codec.level(), Some(41));
+    assert_eq!(codec.bit_depth(), Some(10));
+    assert!(matches!(
+        codec,
+        Codec::H264(AvcDetails {
+            chroma_format: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn h265() {
+    let hevc = HevcBox {
+        hvcc: RawBox {
+            contents: HevcDecoderConfigurationRecord {
+                general_profile_idc: 2,
+                general_tier_flag: true,
+                general_level_idc: 153,
+                chroma_format_idc: 1,
+                bit_depth_luma_minus8: 2,
+                bit_depth_chroma_minus8: 2,
+                ..HevcDecoderConfigurationRecord::new()
+            },
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    };
+    for (entry, dolby_vision) in [
+        (StsdBoxContent::Hvc1(hevc.clone()), false),
+        (StsdBoxContent::Dvh1(hevc), true),
+    ] {
+        let Codec::H265(details) = Codec::from_sample_entry(&entry) else {
+            panic!("expected H.265 for {entry:?}");
+        };
+        assert!(
+            matches!(
+                details,
+                HevcDetails {
+                    profile: 2,
+                    high_tier: true,
+                    level: 153,
+                    bit_depth: 10,
+                    chroma_format: 1,
+                    bitrate: None,
+                    ..
+                }
+            ),
+            "{details:?}"
+        );
+        assert_eq!(details.dolby_vision, dolby_vision);
+    }
+}
+
+#[test]
+fn audio() {
+    let mut mp4a = Mp4aBox::new(&AacConfig::default());
+    let codec = Codec::from_sample_entry(&StsdBoxContent::Mp4a(mp4a.clone()));
+    assert!(
+        matches!(
+            codec,
+            Codec::Aac(AacDetails {
+                object_type: Some(AudioObjectType::AacLowComplexity),
+                sample_rate: Some(48000),
+                channel_config: 2,
+                bitrate: None,
+                ..
+            })
+        ),
+        "{codec:?}"
+    );
+    assert_eq!(codec.profile(), None);
+    assert_eq!(codec.bitrate(), None);
+
+    // Bitrates of the decoder configuration, for lack of a `btrt` box.
+    let codec = Codec::from_sample_entry(&StsdBoxContent::Mp4a(Mp4aBox::new(&AacConfig {
+        bitrate: 128_000,
+        ..Default::default()
+    })));
+    assert_eq!(
+        codec.bitrate(),
+        Some(BtrtBox {
+            buffer_size_db: 0,
+            max_bitrate: 128_000,
+            avg_bitrate: 128_000,
+        })
+    );
+
+    let esds = mp4a.esds.as_mut().expect("mp4a without esds");
+    esds.es_desc.dec_config.object_type_indication = 0x6b;
+    assert_eq!(
+        Codec::from_sample_entry(&StsdBoxContent::Mp4a(mp4a.clone())),
+        Codec::Mp3
+    );
+
+    mp4a.esds = None;
+    assert_eq!(
+        Codec::from_sample_entry(&StsdBoxContent::Mp4a(mp4a)),
+        Codec::Other(FourCC::from(*b"mp4a"))
+    );
+}
+
+#[test]
+fn track_codec() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(
+        mp4.tracks()[&1].codec(),
+        Codec::Other(FourCC::from(*b"test"))
+    );
+
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = avc1(&[0x67, 0x64, 0x00, 0x1f], &[]);
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(mp4.tracks()[&1].codec().profile(), Some(100));
+}
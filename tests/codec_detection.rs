@@ -19,15 +19,19 @@ fn test_codec_parsing(
         track.kind
     );
 
-    let codec_string = track
-        .codec_string(&video)
-        .expect("Failed to read codec string");
+    let codec_string = track.codec_string().expect("Failed to read codec string");
     assert!(
         codec_string.starts_with(expected_codec_starts_with),
         "unexpected codec string: {codec_string}"
     );
 
-    let stsd_box = &track.trak(&video).mdia.minf.stbl.stsd;
+    let stsd_box = &track
+        .trak(&video)
+        .expect("Track without trak")
+        .mdia
+        .minf
+        .stbl
+        .stsd;
     stsd_box_check(stsd_box);
 }
 
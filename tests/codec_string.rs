@@ -0,0 +1,152 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{
+    codec_string_from_av1c, codec_string_from_avcc, codec_string_from_hvcc, codec_string_from_vpcc,
+    Avc1Box, AvcCBox, HevcDecoderConfigurationRecord, Mp4, RawBox, ReadBox as _, StsdBoxContent,
+    WriteBox as _,
+};
+use synthetic::{build_progressive, TestTrack};
+
+#[test]
+fn avcc() {
+    let avcc = [1, 0x64, 0x00, 0x1F, 0xFF, 0xE0, 0x00];
+    assert_eq!(codec_string_from_avcc(&avcc).unwrap(), "avc1.64001F");
+
+    assert!(codec_string_from_avcc(&avcc[..3]).is_err());
+    assert!(codec_string_from_avcc(&[0, 0x64, 0x00, 0x1F]).is_err());
+}
+
+#[test]
+fn hvcc() {
+    #[rustfmt::skip]
+    let hvcc = [
+        1, // configuration_version
+        0x01, // profile space 0, main tier, Main profile
+        0x60, 0x00, 0x00, 0x00, // profile compatibility flags
+        0x90, 0x00, 0x00, 0x00, 0x00, 0x00, // constraint indicator flags
+        93, // level 3.1
+        0xF0, 0x00, 0xFC, 0xFD, 0xF8, 0xF8, 0x00, 0x00, 0x0F,
+        0, // no arrays
+    ];
+    assert_eq!(codec_string_from_hvcc(&hvcc).unwrap(), "hvc1.1.6.L93.90");
+
+    assert!(codec_string_from_hvcc(&hvcc[..10]).is_err());
+}
+
+#[test]
+fn hvcc_constraint_bytes_in_stream_order() {
+    #[rustfmt::skip]
+    let hvcc = [
+        1, // configuration_version
+        0x22, // profile space 0, high tier, Main 10 profile
+        0x20, 0x00, 0x00, 0x00, // profile compatibility flags
+        0xB0, 0x00, 0x00, 0x00, 0x00, 0x01, // constraint indicator flags
+        120, // level 4
+        0xF0, 0x00, 0xFC, 0xFD, 0xFA, 0xFA, 0x00, 0x00, 0x0F,
+        0, // no arrays
+    ];
+    assert_eq!(
+        codec_string_from_hvcc(&hvcc).unwrap(),
+        "hvc1.2.4.H120.B0.0.0.0.0.1"
+    );
+}
+
+#[test]
+fn hvcc_from_parameter_sets() {
+    let vps = [0x40, 0x01, 0x0C];
+    #[rustfmt::skip]
+    let sps = [
+        0x42, 0x01, // NAL unit header
+        0x01, // one sub-layer, temporal ID nesting
+        0x02, // profile space 0, main tier, Main 10 profile
+        0x20, 0x00, 0x00, 0x03, 0x00, // profile compatibility flags, with emulation prevention
+        0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, // constraint indicator flags
+        120, // level 4
+        0xAC, 0xDC, // 4:2:0, 10 bits for luma and chroma
+    ];
+    let pps = [0x44, 0x01, 0xC1];
+
+    let hvcc = HevcDecoderConfigurationRecord::from_parameter_sets(&vps, &sps, &pps).unwrap();
+    assert_eq!(hvcc.general_profile_idc, 2);
+    assert_eq!(hvcc.general_profile_compatibility_flags, 0x2000_0000);
+    assert_eq!(hvcc.general_constraint_indicator_flag, 0x9000_0000_0000);
+    assert_eq!(hvcc.general_level_idc, 120);
+    assert_eq!(hvcc.chroma_format_idc, 1);
+    assert_eq!(hvcc.bit_depth_luma_minus8, 2);
+    assert_eq!(hvcc.bit_depth_chroma_minus8, 2);
+    assert_eq!(hvcc.num_temporal_layers, 1);
+    assert!(hvcc.temporal_id_nested);
+    assert_eq!(
+        hvcc.arrays
+            .iter()
+            .map(|array| (array.nal_unit_type, array.nalus[0].data.as_slice()))
+            .collect::<Vec<_>>(),
+        [(32, &vps[..]), (33, &sps[..]), (34, &pps[..])]
+    );
+
+    let mut written = Vec::new();
+    let size = hvcc.write_box(&mut written).unwrap();
+    assert_eq!(size, written.len() as u64);
+    assert_eq!(&written[4..8], b"hvcC");
+    assert_eq!(
+        codec_string_from_hvcc(&written[8..]).unwrap(),
+        "hvc1.2.4.L120.90"
+    );
+    let read = HevcDecoderConfigurationRecord::read_box(
+        &mut std::io::Cursor::new(&written[8..]),
+        size - 8,
+    )
+    .unwrap();
+    assert_eq!(read, hvcc);
+
+    assert!(HevcDecoderConfigurationRecord::from_parameter_sets(&vps, &sps[..10], &pps).is_err());
+}
+
+#[test]
+fn av1c() {
+    // Main profile, level 4.0, main tier, 10 bits, with a config OBU.
+    let av1c = [0x81, 0x08, 0x4C, 0x00, 0x0A, 0x0B];
+    assert_eq!(codec_string_from_av1c(&av1c).unwrap(), "av01.0.08M.10");
+
+    assert!(codec_string_from_av1c(&[0x01, 0x08, 0x4C, 0x00]).is_err());
+}
+
+#[test]
+fn vpcc() {
+    let vpcc = [1, 0, 0, 0, 2, 31, 0xA0, 1, 1, 1, 0, 0];
+    assert_eq!(codec_string_from_vpcc(&vpcc).unwrap(), "vp09.02.31.10");
+
+    assert!(codec_string_from_vpcc(&vpcc[..6]).is_err());
+}
+
+#[test]
+fn track_keeps_its_sample_entry() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    let avcc = AvcCBox::new(&[0x67, 0x64, 0x00, 0x1f], &[0x68]);
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = StsdBoxContent::Avc1(Avc1Box {
+        avcc: RawBox {
+            contents: avcc,
+            raw: vec![1, 0x64, 0x00, 0x1f],
+        },
+        ..Default::default()
+    });
+    mp4.rebuild_tracks().unwrap();
+    let track = mp4.tracks()[&1].clone();
+
+    mp4.moov.traks.clear();
+    assert!(track.trak(&mp4).is_none());
+    assert_eq!(track.codec_string().as_deref(), Some("avc1.64001F"));
+    assert_eq!(track.raw_codec_config(), Some(vec![1, 0x64, 0x00, 0x1f]));
+    assert!(!track.is_enabled(&mp4));
+}
@@ -0,0 +1,325 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{ColorMismatch, ColorReport, ColorSignaling, ColrBox, Mp4, StsdBoxContent};
+use synthetic::{build_progressive, mp4_box, read_stsd, video_sample_entry, Bits, TestTrack};
+
+/// The VUI up to the chroma location, followed by no timing, HRD or restriction info.
+fn vui(bits: Bits, full_range: bool, colors: [u32; 3], chroma_location: Option<u32>) -> Bits {
+    let bits = bits
+        .flag(false) // aspect_ratio_info_present_flag
+        .flag(false) // overscan_info_present_flag
+        .flag(true) // video_signal_type_present_flag
+        .put(5, 3)
+        .flag(full_range)
+        .flag(true)
+        .put(colors[0], 8)
+        .put(colors[1], 8)
+        .put(colors[2], 8);
+    let bits = match chroma_location {
+        Some(location) => bits.flag(true).ue(location).ue(location),
+        None => bits.flag(false),
+    };
+    bits.put(0, 5)
+}
+
+/// A baseline AVC SPS of 1280x720.
+fn avc_sps(full_range: bool, colors: [u32; 3]) -> Vec<u8> {
+    let bits = Bits::default()
+        .put(66, 8) // profile_idc
+        .put(0, 8)
+        .put(31, 8)
+        .ue(0) // seq_parameter_set_id
+        .ue(0) // log2_max_frame_num_minus4
+        .ue(2) // pic_order_cnt_type
+        .ue(1) // max_num_ref_frames
+        .flag(false)
+        .ue(79)
+        .ue(44)
+        .flag(true) // frame_mbs_only_flag
+        .flag(true)
+        .flag(false) // frame_cropping_flag
+        .flag(true); // vui_parameters_present_flag
+    [vec![0x67], vui(bits, full_range, colors, None).into_bytes()].concat()
+}
+
+/// A Main profile HEVC SPS of 1280x720, with an inter predicted reference picture set.
+fn hevc_sps(full_range: bool, colors: [u32; 3], chroma_location: u32) -> Vec<u8> {
+    let bits = Bits::default()
+        .put(0, 4) // sps_video_parameter_set_id
+        .put(0, 3) // sps_max_sub_layers_minus1
+        .flag(true)
+        .put(1, 8) // general profile space, tier and profile
+        .put(0x6000_0000, 32)
+        .put(0, 24)
+        .put(0, 24)
+        .put(93, 8) // general_level_idc
+        .ue(0) // sps_seq_parameter_set_id
+        .ue(1) // chroma_format_idc
+        .ue(1280)
+        .ue(720)
+        .flag(false) // conformance_window_flag
+        .ue(0)
+        .ue(0)
+        .ue(4) // log2_max_pic_order_cnt_lsb_minus4
+        .flag(true)
+        .ue(4)
+        .ue(0)
+        .ue(0)
+        .ue(0)
+        .ue(3)
+        .ue(0)
+        .ue(3)
+        .ue(0)
+        .ue(0)
+        .flag(false) // scaling_list_enabled_flag
+        .flag(false)
+        .flag(true)
+        .flag(false) // pcm_enabled_flag
+        .ue(2) // num_short_term_ref_pic_sets
+        .ue(1) // num_negative_pics
+        .ue(0)
+        .ue(0)
+        .flag(true)
+        .flag(true) // inter_ref_pic_set_prediction_flag
+        .flag(false)
+        .ue(0)
+        .flag(true)
+        .flag(false) // used_by_curr_pic_flag
+        .flag(true) // use_delta_flag
+        .flag(false) // long_term_ref_pics_present_flag
+        .flag(true)
+        .flag(true)
+        .flag(true); // vui_parameters_present_flag
+    [
+        vec![0x42, 0x01],
+        vui(bits, full_range, colors, Some(chroma_location)).into_bytes(),
+    ]
+    .concat()
+}
+
+/// An AV1 sequence header OBU of main profile with BT.709 colors.
+fn av1_sequence_header(full_range: bool, chroma_sample_position: u32) -> Vec<u8> {
+    let payload = Bits::default()
+        .put(0, 3) // seq_profile
+        .flag(false)
+        .flag(false) // reduced_still_picture_header
+        .flag(false) // timing_info_present_flag
+        .flag(false)
+        .put(0, 5) // operating_points_cnt_minus_1
+        .put(0, 12)
+        .put(8, 5) // seq_level_idx
+        .flag(false)
+        .put(10, 4)
+        .put(10, 4)
+        .put(1279, 11)
+        .put(719, 11)
+        .flag(false) // frame_id_numbers_present_flag
+        .put(0, 3)
+        .put(0, 4)
+        .flag(true) // enable_order_hint
+        .put(0, 2)
+        .flag(true) // seq_choose_screen_content_tools
+        .flag(true) // seq_choose_integer_mv
+        .put(6, 3)
+        .put(0b011, 3)
+        .flag(false) // high_bitdepth
+        .flag(false) // mono_chrome
+        .flag(true) // color_description_present_flag
+        .put(1, 8)
+        .put(1, 8)
+        .put(1, 8)
+        .flag(full_range)
+        .put(chroma_sample_position, 2)
+        .put(0, 2)
+        .into_bytes();
+    [vec![0x0a, payload.len() as u8], payload].concat()
+}
+
+/// The start of a VP9 key frame of profile 0.
+fn vp9_key_frame(color_space: u32, full_range: bool) -> Vec<u8> {
+    Bits::default()
+        .put(2, 2) // frame_marker
+        .put(0, 2) // profile
+        .flag(false) // show_existing_frame
+        .flag(false) // frame_type
+        .flag(true)
+        .flag(false)
+        .put(0x49_83_42, 24)
+        .put(color_space, 3)
+        .flag(full_range)
+        .put(0, 16)
+        .into_bytes()
+}
+
+/// A sample made of the given NAL units, with 4 byte length fields.
+fn nal_sample(nals: &[&[u8]]) -> Vec<u8> {
+    let mut sample = Vec::new();
+    for nal in nals {
+        sample.extend((nal.len() as u32).to_be_bytes());
+        sample.extend(*nal);
+    }
+    sample
+}
+
+fn nclx(colors: [u16; 3], full_range: bool) -> Vec<u8> {
+    let mut contents = b"nclx".to_vec();
+    for color in colors {
+        contents.extend(color.to_be_bytes());
+    }
+    contents.push(if full_range { 0x80 } else { 0 });
+    mp4_box(b"colr", &[&contents])
+}
+
+fn read_entry(entry: &[u8]) -> StsdBoxContent {
+    read_stsd(entry).contents
+}
+
+/// The color report of a file with one video track of `samples` with the sample entry `entry`.
+fn color_report(samples: Vec<Vec<u8>>, entry: StsdBoxContent) -> ColorReport {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![samples],
+        sync_samples: None,
+    }];
+    let data = build_progressive(&tracks, true);
+    let mut mp4 = Mp4::read_bytes(&data).expect("Failed parsing mp4");
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = entry;
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+    mp4.tracks()[&1]
+        .color_report(&data)
+        .expect("Failed comparing colors")
+        .expect("Missing color report")
+}
+
+#[test]
+fn avc_full_range_mismatch() {
+    let sps = avc_sps(false, [1, 1, 1]);
+    let mut avcc = vec![1, 66, 0, 31, 0xff, 0xe1];
+    avcc.extend((sps.len() as u16).to_be_bytes());
+    avcc.extend(&sps);
+    avcc.extend([1, 0, 2, 0x68, 0xce]);
+    let entry = read_entry(&video_sample_entry(
+        b"avc1",
+        &[&mp4_box(b"avcC", &[&avcc]), &nclx([1, 1, 1], true)],
+    ));
+    let StsdBoxContent::Avc1(avc1) = &entry else {
+        panic!("expected avc1, got {entry:?}");
+    };
+    assert_eq!(
+        avc1.colr,
+        Some(ColrBox::Nclx {
+            colour_primaries: 1,
+            transfer_characteristics: 1,
+            matrix_coefficients: 1,
+            full_range: true,
+        })
+    );
+
+    // The samples don't repeat the SPS, so it is taken from `avcC`.
+    let report = color_report(vec![nal_sample(&[&[0x65, 0x88]])], entry);
+    assert_eq!(
+        report.bitstream,
+        ColorSignaling {
+            color_primaries: Some(1),
+            transfer_characteristics: Some(1),
+            matrix_coefficients: Some(1),
+            full_range: Some(false),
+            chroma_location: None,
+        }
+    );
+    assert_eq!(
+        report.mismatches,
+        vec![ColorMismatch::FullRange {
+            container: true,
+            bitstream: false,
+        }]
+    );
+    assert!(!report.is_consistent());
+}
+
+#[test]
+fn hevc_in_band_sps() {
+    let hvcc = [
+        1, 0x01, 0x60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 93, 0xf0, 0, 0xfc, 0xfd, 0xf8, 0xf8, 0, 0, 0x0f,
+        0,
+    ];
+    let entry = read_entry(&video_sample_entry(
+        b"hev1",
+        &[&mp4_box(b"hvcC", &[&hvcc]), &nclx([9, 16, 9], false)],
+    ));
+
+    let sps = hevc_sps(false, [9, 16, 9], 2);
+    let report = color_report(vec![nal_sample(&[&sps, &[0x26, 0x01, 0xaf]])], entry);
+    assert_eq!(
+        report.bitstream,
+        ColorSignaling {
+            color_primaries: Some(9),
+            transfer_characteristics: Some(16),
+            matrix_coefficients: Some(9),
+            full_range: Some(false),
+            chroma_location: Some(2),
+        }
+    );
+    assert!(report.is_consistent(), "{:?}", report.mismatches);
+}
+
+#[test]
+fn av1_chroma_location_and_range_mismatch() {
+    let sequence_header = av1_sequence_header(true, 2);
+    let mut av1c = vec![0x81, 0x08, 0x0d, 0];
+    av1c.extend(&sequence_header);
+    let entry = read_entry(&video_sample_entry(
+        b"av01",
+        &[&mp4_box(b"av1C", &[&av1c]), &nclx([1, 1, 1], false)],
+    ));
+
+    let report = color_report(vec![vec![0x12, 0x00, 0x32, 0x00]], entry);
+    assert_eq!(report.container.chroma_location, Some(0));
+    assert_eq!(
+        report.mismatches,
+        vec![
+            ColorMismatch::FullRange {
+                container: false,
+                bitstream: true,
+            },
+            ColorMismatch::ChromaLocation {
+                container: 0,
+                bitstream: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn vp9_matrix_mismatch() {
+    let vpcc = [1, 0, 0, 0, 0, 31, 0x80, 1, 1, 1, 0, 0];
+    let entry = read_entry(&video_sample_entry(b"vp09", &[&mp4_box(b"vpcC", &[&vpcc])]));
+    let StsdBoxContent::Vp09(vp09) = &entry else {
+        panic!("expected vp09, got {entry:?}");
+    };
+    assert_eq!(
+        (
+            vp09.vpcc.color_primaries,
+            vp09.vpcc.transfer_characteristics,
+            vp09.vpcc.matrix_coefficients
+        ),
+        (1, 1, 1)
+    );
+
+    // CS_SMPTE_170, which is BT.601.
+    let report = color_report(vec![vp9_key_frame(3, false)], entry.clone());
+    assert_eq!(
+        report.mismatches,
+        vec![ColorMismatch::MatrixCoefficients {
+            container: 1,
+            bitstream: 6,
+        }]
+    );
+
+    // CS_BT_709
+    let report = color_report(vec![vp9_key_frame(2, false)], entry);
+    assert!(report.is_consistent(), "{:?}", report.mismatches);
+}
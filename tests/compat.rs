@@ -0,0 +1,52 @@
+use re_mp4::{
+    codec_config_compatible, Avc1Box, AvcCBox, CompatIssue, FourCC, RawBox, StsdBoxContent,
+};
+
+/// An `avc1` sample entry with a Main profile SPS.
+fn avc1(width: u16, level: u8, pps: &[u8]) -> StsdBoxContent {
+    let mut avcc = AvcCBox::new(&[0x67, 77, 0, 31, 0xaa], pps);
+    // Only the level of the `avcC` is changed, so that the parameter sets stay the same.
+    avcc.avc_level_indication = level;
+    StsdBoxContent::Avc1(Avc1Box {
+        width,
+        height: 720,
+        avcc: RawBox {
+            contents: avcc,
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    })
+}
+
+#[test]
+fn identical_configs_are_compatible() {
+    let entry = avc1(1280, 31, &[0x68, 0xee]);
+    assert!(codec_config_compatible(&entry, &entry).is_compatible());
+
+    // A decoder configured for a higher level can decode a lower one, but not the other way around.
+    let lower_level = avc1(1280, 30, &[0x68, 0xee]);
+    assert!(codec_config_compatible(&entry, &lower_level).is_compatible());
+    assert_eq!(
+        codec_config_compatible(&lower_level, &entry).issues,
+        [CompatIssue::Level]
+    );
+}
+
+#[test]
+fn differences_are_reported() {
+    let first = avc1(1280, 31, &[0x68, 0xee]);
+    let second = avc1(1920, 31, &[0x68, 0xef]);
+    assert_eq!(
+        codec_config_compatible(&first, &second).issues,
+        [CompatIssue::Resolution, CompatIssue::ParameterSets]
+    );
+
+    let unknown = StsdBoxContent::Unknown(FourCC::from(*b"mp4v"));
+    assert_eq!(
+        codec_config_compatible(&first, &unknown).issues,
+        [CompatIssue::SampleEntry {
+            first: FourCC::from(*b"avc1"),
+            second: FourCC::from(*b"mp4v"),
+        }]
+    );
+}
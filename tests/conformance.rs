@@ -0,0 +1,108 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::time::Duration;
+
+use re_mp4::{ConformanceIssue, FourCC, Mp4};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+fn test_tracks() -> Vec<TestTrack> {
+    vec![TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4]; 6]],
+        sync_samples: None,
+    }]
+}
+
+fn with_brand(mut mp4: Mp4, brand: &[u8; 4]) -> Mp4 {
+    mp4.ftyp.compatible_brands.push(FourCC::from(*brand));
+    mp4
+}
+
+fn fragmented() -> Vec<u8> {
+    synthetic::fragmented(&test_tracks(), Duration::from_millis(100))
+}
+
+#[test]
+fn cmaf_fragments() {
+    let mp4 = with_brand(Mp4::read_bytes(&fragmented()).unwrap(), b"cmfc");
+    assert_eq!(mp4.conformance_check().issues, []);
+    assert!(mp4.conformance_check().is_conformant());
+
+    let mut mp4 = mp4;
+    mp4.moofs[1].trafs[0].tfdt = None;
+    assert_eq!(
+        mp4.conformance_check().issues,
+        [ConformanceIssue::MissingTfdt {
+            fragment: 1,
+            track_id: 1,
+        }]
+    );
+}
+
+#[test]
+fn cmaf_needs_fragments() {
+    let data = build_progressive(&test_tracks(), false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert!(mp4.conformance_check().is_conformant());
+
+    let mp4 = with_brand(mp4, b"cmf2");
+    assert_eq!(
+        mp4.conformance_check().issues,
+        [
+            ConformanceIssue::NotFragmented,
+            ConformanceIssue::SamplesInMoov { track_id: 1 }
+        ]
+    );
+}
+
+#[test]
+fn cmaf_fragment_followed_by_other_box() {
+    let mut data = fragmented();
+    let mdat = data
+        .windows(4)
+        .position(|window| window == b"mdat")
+        .expect("mdat not found")
+        - 4;
+    data.splice(mdat..mdat, mp4_box(b"free", &[]));
+
+    let mp4 = with_brand(Mp4::read_bytes(&data).unwrap(), b"cmfc");
+    assert_eq!(
+        mp4.conformance_check().issues,
+        [ConformanceIssue::FragmentWithoutMdat { fragment: 0 }]
+    );
+}
+
+#[test]
+fn ftyp_first_and_quicktime_boxes() {
+    let mut data = build_progressive(&test_tracks(), false);
+    append_child(
+        &mut data,
+        &[b"moov", b"trak", b"mdia", b"minf"],
+        &mp4_box(b"gmhd", &[]),
+    );
+    let data = [mp4_box(b"PANA", &[]), data].concat();
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(
+        mp4.conformance_check().issues,
+        [
+            ConformanceIssue::FtypNotFirst {
+                first: FourCC::from(*b"PANA")
+            },
+            ConformanceIssue::QuickTimeBox {
+                box_type: FourCC::from(*b"gmhd")
+            },
+        ]
+    );
+
+    let mp4 = with_brand(mp4, b"qt  ");
+    assert_eq!(
+        mp4.conformance_check().issues,
+        [ConformanceIssue::FtypNotFirst {
+            first: FourCC::from(*b"PANA")
+        }]
+    );
+}
@@ -1,5 +1,11 @@
 mod paths;
 
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Mp4, StszBox};
+use synthetic::{build_progressive, TestTrack};
+
 fn test_codec_parsing(video_path: &str, expected_sample_length: u32) {
     let mp4_file = std::path::Path::new(paths::SAMPLE_BASE_PATH).join(video_path);
     let (video, _) = re_mp4::Mp4::read_file(mp4_file).expect("Failed parsing mp4");
@@ -17,3 +23,54 @@ fn test_codec_parsing(video_path: &str, expected_sample_length: u32) {
 fn check_episode_58() {
     test_codec_parsing("lerobot/episode_000058.mp4", 1);
 }
+
+#[test]
+fn stsz_accessors() {
+    let per_sample = StszBox {
+        sample_count: 2,
+        sample_sizes: vec![5, 6],
+        ..Default::default()
+    };
+    assert_eq!(per_sample.count(), 2);
+    assert_eq!(per_sample.sample_size(1), Some(6));
+    assert_eq!(per_sample.sample_size(2), None);
+
+    let constant = StszBox {
+        sample_size: 4,
+        sample_count: 3,
+        ..Default::default()
+    };
+    assert_eq!(constant.count(), 3);
+    assert_eq!(constant.sample_size(2), Some(4));
+    assert_eq!(constant.sample_size(3), None);
+}
+
+#[test]
+fn constant_size_table() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![vec![vec![1; 3]; 2], vec![vec![2; 3]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    let expected = mp4.tracks()[&1].samples.clone();
+
+    let stsz = &mut mp4.moov.traks[0].mdia.minf.stbl.stsz;
+    stsz.sample_size = 3;
+    stsz.sample_sizes.clear();
+    mp4.rebuild_tracks().unwrap();
+
+    let samples = &mp4.tracks()[&1].samples;
+    assert_eq!(samples.len(), expected.len());
+    for (sample, expected) in samples.iter().zip(&expected) {
+        assert_eq!(
+            (sample.offset, sample.size),
+            (expected.offset, expected.size)
+        );
+    }
+}
@@ -0,0 +1,88 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::debug::{snapshot_tracks, TrackSnapshot};
+use re_mp4::Mp4;
+use synthetic::{build_progressive, TestTrack};
+
+fn tracks(last_byte: u8) -> [TestTrack; 2] {
+    [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 10], vec![2; 10]], vec![vec![last_byte; 5]]],
+            sync_samples: Some(vec![1]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 1000,
+            chunks: vec![vec![vec![3; 4]; 3]],
+            sync_samples: None,
+        },
+    ]
+}
+
+fn snapshots(data: &[u8]) -> Vec<TrackSnapshot> {
+    let mp4 = Mp4::read_bytes(data).expect("Failed parsing mp4");
+    snapshot_tracks(&mp4, data).expect("Failed snapshotting tracks")
+}
+
+#[test]
+fn snapshots_do_not_depend_on_layout() {
+    let moov_first = snapshots(&build_progressive(&tracks(4), true));
+    let moov_last = snapshots(&build_progressive(&tracks(4), false));
+    assert_eq!(moov_first, moov_last);
+
+    assert_eq!(moov_first.len(), 2);
+    assert_eq!(moov_first[0].track_id, 1);
+    assert_eq!(moov_first[0].num_samples, 3);
+    assert_eq!(moov_first[1].num_samples, 3);
+    assert_ne!(moov_first[0].samples_digest, moov_first[1].samples_digest);
+}
+
+#[test]
+fn snapshots_change_with_sample_data() {
+    let before = snapshots(&build_progressive(&tracks(4), true));
+    let after = snapshots(&build_progressive(&tracks(5), true));
+    assert_ne!(before[0].samples_digest, after[0].samples_digest);
+    assert_eq!(before[1], after[1]);
+}
+
+#[test]
+fn snapshot_json_round_trips() {
+    let snapshot = snapshots(&build_progressive(&tracks(4), true)).remove(0);
+    let json = snapshot.to_json();
+    assert!(json.contains(&format!(
+        r#""samples_digest": "{}""#,
+        snapshot
+            .samples_digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    )));
+    assert_eq!(
+        serde_json::from_str::<TrackSnapshot>(&json).unwrap(),
+        snapshot
+    );
+
+    let with_description = TrackSnapshot {
+        description: Some(vec![0x01, 0x64, 0xff]),
+        ..snapshot
+    };
+    let json = with_description.to_json();
+    assert!(json.contains(r#""description": "0164ff""#));
+    assert_eq!(
+        serde_json::from_str::<TrackSnapshot>(&json).unwrap(),
+        with_description
+    );
+}
+
+#[test]
+fn snapshot_fails_on_truncated_data() {
+    let data = build_progressive(&tracks(4), false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert!(TrackSnapshot::new(track, &data[..20]).is_err());
+}
@@ -0,0 +1,78 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{Avc1Box, BtrtBox, Codec, Mp4, ReadBox as _, StsdBoxContent};
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+/// An `avc1` sample entry with an `avcC` and a `btrt` box.
+fn avc1_with_btrt(buffer_size_db: u32) -> Vec<u8> {
+    let mut fields = vec![0; 78];
+    fields[7] = 1; // data_reference_index
+    fields[24..28].copy_from_slice(&[0x05, 0x00, 0x02, 0xd0]); // 1280x720
+    let avcc = mp4_box(
+        b"avcC",
+        &[&[
+            1, 77, 0, 31, 0xff, 0xe1, 0, 4, 0x67, 77, 0, 31, 1, 0, 2, 0x68, 0xee,
+        ]],
+    );
+    let btrt = mp4_box(
+        b"btrt",
+        &[
+            &buffer_size_db.to_be_bytes(),
+            &4_000_000_u32.to_be_bytes(),
+            &2_000_000_u32.to_be_bytes(),
+        ],
+    );
+    mp4_box(b"avc1", &[&fields, &avcc, &btrt])
+}
+
+#[test]
+fn btrt_is_parsed() {
+    let data = avc1_with_btrt(50_000);
+    let mut reader = Cursor::new(&data);
+    reader.set_position(8);
+    let avc1 = Avc1Box::read_box(&mut reader, data.len() as u64).unwrap();
+    assert_eq!((avc1.width, avc1.height), (1280, 720));
+    assert_eq!(
+        avc1.btrt,
+        Some(BtrtBox {
+            buffer_size_db: 50_000,
+            max_bitrate: 4_000_000,
+            avg_bitrate: 2_000_000,
+        })
+    );
+    assert_eq!(reader.position(), data.len() as u64);
+    assert_eq!(
+        Codec::from_sample_entry(&StsdBoxContent::Avc1(avc1))
+            .bitrate()
+            .map(|btrt| (btrt.max_bitrate, btrt.avg_bitrate)),
+        Some((4_000_000, 2_000_000))
+    );
+}
+
+#[test]
+fn decoder_buffer_size() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![0; 40], vec![0; 100], vec![0; 60]]],
+        sync_samples: None,
+    }];
+    let mut mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.max_sample_size(), 100);
+    assert_eq!(track.recommended_decoder_buffer(), 100);
+
+    let data = avc1_with_btrt(500);
+    let mut reader = Cursor::new(&data);
+    reader.set_position(8);
+    let avc1 = Avc1Box::read_box(&mut reader, data.len() as u64).unwrap();
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = StsdBoxContent::Avc1(avc1);
+    mp4.rebuild_tracks().unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.max_sample_size(), 100);
+    assert_eq!(track.recommended_decoder_buffer(), 500);
+}
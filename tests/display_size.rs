@@ -0,0 +1,187 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{ClapBox, CropRect, Matrix, Mp4, PaspBox, StsdBoxContent, VideoGeometry};
+use synthetic::{build_progressive, mp4_box, read_stsd, TestTrack};
+
+/// An `AVCDecoderConfigurationRecord` of High profile at level 3.1 without parameter sets.
+const AVCC: [u8; 7] = [1, 0x64, 0, 0x1f, 0xff, 0xe0, 0];
+
+/// A 720x576 `avc1` sample entry with the given children.
+fn avc1_entry(children: &[&[u8]]) -> Vec<u8> {
+    let mut fields = vec![0, 0, 0, 0, 0, 0, 0, 1];
+    fields.extend([0; 16]);
+    fields.extend(720u16.to_be_bytes());
+    fields.extend(576u16.to_be_bytes());
+    fields.extend(0x0048_0000u32.to_be_bytes());
+    fields.extend(0x0048_0000u32.to_be_bytes());
+    fields.extend([0; 4]);
+    fields.extend(1u16.to_be_bytes());
+    fields.extend([0; 32]);
+    fields.extend(0x0018u16.to_be_bytes());
+    fields.extend((-1i16).to_be_bytes());
+
+    let avcc = mp4_box(b"avcC", &[&AVCC]);
+    let mut parts: Vec<&[u8]> = vec![&fields, &avcc];
+    parts.extend(children);
+    mp4_box(b"avc1", &parts)
+}
+
+fn rotation(degrees: u16) -> Matrix {
+    let (cos, sin) = match degrees {
+        0 => (1, 0),
+        90 => (0, 1),
+        180 => (-1, 0),
+        270 => (0, -1),
+        _ => unreachable!(),
+    };
+    Matrix {
+        a: cos << 16,
+        b: sin << 16,
+        c: -sin << 16,
+        d: cos << 16,
+        ..Matrix::default()
+    }
+}
+
+#[test]
+fn rotation_degrees() {
+    assert_eq!(Matrix::default().rotation_degrees(), Some(0));
+    for degrees in [0, 90, 180, 270] {
+        assert_eq!(rotation(degrees).rotation_degrees(), Some(degrees));
+    }
+
+    // Translation doesn't matter, but mirroring does.
+    let translated = Matrix {
+        x: 1080 << 16,
+        ..rotation(90)
+    };
+    assert_eq!(translated.rotation_degrees(), Some(90));
+    let mirrored = Matrix {
+        a: -0x10000,
+        ..Matrix::default()
+    };
+    assert_eq!(mirrored.rotation_degrees(), None);
+}
+
+fn clap_box(values: [u32; 8]) -> Vec<u8> {
+    let contents: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    mp4_box(b"clap", &[&contents])
+}
+
+#[test]
+fn parse_pasp_and_clap() {
+    let pasp = mp4_box(b"pasp", &[&16u32.to_be_bytes(), &11u32.to_be_bytes()]);
+    let clap = clap_box([704, 1, 576, 1, (-2i32).cast_unsigned(), 1, 0, 1]);
+    let entry = avc1_entry(&[&pasp, &clap]);
+    let stsd = read_stsd(&entry);
+
+    assert_eq!(stsd.get_size(), 16 + entry.len() as u64);
+    let geometry = stsd.contents.video_geometry().unwrap();
+    assert_eq!((geometry.width, geometry.height), (720, 576));
+    assert_eq!(
+        geometry.pasp,
+        Some(PaspBox {
+            h_spacing: 16,
+            v_spacing: 11,
+        })
+    );
+    assert_eq!(
+        geometry.clap,
+        Some(ClapBox {
+            clean_aperture_width_n: 704,
+            clean_aperture_width_d: 1,
+            clean_aperture_height_n: 576,
+            clean_aperture_height_d: 1,
+            horiz_off_n: -2,
+            horiz_off_d: 1,
+            vert_off_n: 0,
+            vert_off_d: 1,
+        })
+    );
+    assert_eq!(
+        geometry.clean_aperture(),
+        CropRect {
+            x: 6,
+            y: 0,
+            width: 704,
+            height: 576,
+        }
+    );
+    assert_eq!(geometry.display_size(), (1024, 576));
+}
+
+#[test]
+fn clean_aperture() {
+    let geometry = |clap| VideoGeometry {
+        width: 1920,
+        height: 1088,
+        pasp: None,
+        clap: Some(clap_box_values(clap)),
+    };
+
+    // The usual cropping of 1080p HEVC and AVC, from the bottom.
+    let crop = geometry([1920, 1, 1080, 1, 0, 1, (-4i32).cast_unsigned(), 1]).clean_aperture();
+    assert_eq!(
+        (crop.x, crop.y, crop.width, crop.height),
+        (0, 0, 1920, 1080)
+    );
+
+    // Fractions are rounded, and the aperture is clamped to the picture.
+    let crop = geometry([3841, 2, 2177, 2, 5, 1, 0, 1]).clean_aperture();
+    assert_eq!(
+        (crop.x, crop.y, crop.width, crop.height),
+        (5, 0, 1915, 1088)
+    );
+
+    // Zero denominators make the aperture invalid.
+    let crop = geometry([1280, 0, 720, 1, 0, 1, 0, 1]).clean_aperture();
+    assert_eq!((crop.width, crop.height), (1920, 1088));
+}
+
+fn clap_box_values(values: [u32; 8]) -> ClapBox {
+    ClapBox {
+        clean_aperture_width_n: values[0],
+        clean_aperture_width_d: values[1],
+        clean_aperture_height_n: values[2],
+        clean_aperture_height_d: values[3],
+        horiz_off_n: values[4].cast_signed(),
+        horiz_off_d: values[5],
+        vert_off_n: values[6].cast_signed(),
+        vert_off_d: values[7],
+    }
+}
+
+#[test]
+fn display_size() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 1000,
+            chunks: vec![vec![vec![0; 8]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    let pasp = mp4_box(b"pasp", &[&16u32.to_be_bytes(), &11u32.to_be_bytes()]);
+    mp4.moov.traks[0].mdia.minf.stbl.stsd = read_stsd(&avc1_entry(&[&pasp]));
+    mp4.rebuild_tracks().unwrap();
+
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.display_size(&mp4), (1047, 576));
+
+    mp4.moov.traks[0].tkhd.matrix = rotation(90);
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.display_width(&mp4), 576);
+    assert_eq!(track.display_height(&mp4), 1047);
+
+    // Without a visual sample entry, the size of the track header is used as it is.
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = StsdBoxContent::default();
+    mp4.moov.traks[0].tkhd.set_width(640);
+    mp4.moov.traks[0].tkhd.set_height(360);
+    mp4.rebuild_tracks().unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.display_size(&mp4), (360, 640));
+}
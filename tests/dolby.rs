@@ -0,0 +1,84 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{AudioInfo, BoxHeader, ReadBox as _, StsdBox, StsdBoxContent, TrackKind};
+use synthetic::{audio_sample_entry, mp4_box, read_stsd, sound_track};
+
+fn audio_info(stsd: StsdBox) -> Option<AudioInfo> {
+    let mp4 = sound_track(stsd);
+    mp4.tracks()[&1].audio_info(&mp4)
+}
+
+#[test]
+fn ac3_sample_entry() {
+    // 44.1 kHz, bsid 8, complete main, 3/2 with LFE, 448 kbit/s.
+    let bits: u32 = (1 << 22) | (8 << 17) | (7 << 11) | (1 << 10) | (15 << 5);
+    let dac3 = mp4_box(b"dac3", &[&bits.to_be_bytes()[1..]]);
+    let stsd = read_stsd(&audio_sample_entry(b"ac-3", 48_000, &dac3));
+    assert_eq!(stsd.kind(), Some(TrackKind::Audio));
+    assert_eq!(stsd.contents.codec_string().as_deref(), Some("ac-3"));
+
+    let StsdBoxContent::Ac3(ac3) = &stsd.contents else {
+        panic!("expected ac-3, got {:?}", stsd.contents);
+    };
+    let config = ac3.dac3.as_ref().expect("missing dac3");
+    assert_eq!((config.bsid, config.acmod, config.lfeon), (8, 7, true));
+
+    let info = audio_info(stsd).expect("missing audio info");
+    assert_eq!(info.sample_rate, 44_100);
+    assert_eq!(info.channel_count, 6);
+    assert_eq!(info.sample_size, 16);
+    assert_eq!(info.bitrate, Some(448_000));
+}
+
+#[test]
+fn ec3_sample_entry() {
+    // 640 kbit/s, one independent substream: 48 kHz, bsid 16, 3/2 with LFE, with a dependent
+    // substream that adds the Lrs/Rrs pair, so 7.1 in total.
+    let bits: u64 = (640 << 35) | (16 << 25) | (7 << 17) | (1 << 16) | (1 << 9) | 0b10;
+    let dec3 = mp4_box(b"dec3", &[&bits.to_be_bytes()[2..]]);
+    let stsd = read_stsd(&audio_sample_entry(b"ec-3", 48_000, &dec3));
+    assert_eq!(stsd.kind(), Some(TrackKind::Audio));
+    assert_eq!(stsd.contents.codec_string().as_deref(), Some("ec-3"));
+
+    let StsdBoxContent::Ec3(ec3) = &stsd.contents else {
+        panic!("expected ec-3, got {:?}", stsd.contents);
+    };
+    let config = ec3.dec3.as_ref().expect("missing dec3");
+    assert_eq!(config.data_rate, 640);
+    assert_eq!(config.substreams.len(), 1);
+    let substream = &config.substreams[0];
+    assert_eq!(
+        (substream.bsid, substream.acmod, substream.lfeon),
+        (16, 7, true)
+    );
+    assert_eq!((substream.num_dep_sub, substream.chan_loc), (1, 0b10));
+
+    let info = audio_info(stsd).expect("missing audio info");
+    assert_eq!(info.sample_rate, 48_000);
+    assert_eq!(info.channel_count, 8);
+    assert_eq!(info.bitrate, Some(640_000));
+}
+
+#[test]
+fn dolby_entries_without_config() {
+    let stsd = read_stsd(&audio_sample_entry(b"ac-3", 48_000, &[]));
+    let info = audio_info(stsd).expect("missing audio info");
+    assert_eq!((info.sample_rate, info.channel_count), (48_000, 2));
+    assert_eq!(info.bitrate, None);
+
+    // A dec3 box that ends within its first substream.
+    let dec3 = mp4_box(b"dec3", &[&[0x14, 0x00, 0x20]]);
+    let stsd = mp4_box(
+        b"stsd",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &audio_sample_entry(b"ec-3", 48_000, &dec3),
+        ],
+    );
+    let mut reader = Cursor::new(&stsd);
+    let header = BoxHeader::read(&mut reader).unwrap();
+    assert!(StsdBox::read_box(&mut reader, header.size).is_err());
+}
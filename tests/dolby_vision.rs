@@ -0,0 +1,104 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{DoviConfigBox, StsdBoxContent, TrackKind};
+use synthetic::{mp4_box, read_stsd, video_sample_entry};
+
+/// An `HEVCDecoderConfigurationRecord` of Main profile at level 3.1 without parameter sets.
+const HVCC: [u8; 23] = [
+    1, 0x01, 0x60, 0, 0, 0, 0x90, 0, 0, 0, 0, 0, 93, 0xf0, 0, 0xfc, 0xfd, 0xf8, 0xf8, 0, 0, 0x0f, 0,
+];
+
+/// A `DOVIDecoderConfigurationRecord` of version 1.0.
+fn dovi_record(profile: u8, level: u8, flags: u8, compatibility_id: u8) -> Vec<u8> {
+    let bits = (u16::from(profile) << 9) | (u16::from(level) << 3) | u16::from(flags);
+    let mut record = vec![1, 0];
+    record.extend(bits.to_be_bytes());
+    record.push(compatibility_id << 4);
+    record.extend([0; 19]);
+    record
+}
+
+#[test]
+fn dvh1_with_dvcc() {
+    let entry = video_sample_entry(
+        b"dvh1",
+        &[
+            &mp4_box(b"hvcC", &[&HVCC]),
+            &mp4_box(b"dvcC", &[&dovi_record(5, 6, 0b101, 0)]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+
+    assert_eq!(stsd.kind(), Some(TrackKind::Video));
+    assert_eq!(stsd.get_size(), 16 + entry.len() as u64);
+    let StsdBoxContent::Dvh1(hevc) = &stsd.contents else {
+        panic!("expected dvh1, got {:?}", stsd.contents);
+    };
+    assert_eq!((hevc.width, hevc.height), (1280, 720));
+    assert_eq!(
+        hevc.dovi,
+        Some(DoviConfigBox {
+            version_major: 1,
+            version_minor: 0,
+            profile: 5,
+            level: 6,
+            rpu_present: true,
+            el_present: false,
+            bl_present: true,
+            bl_signal_compatibility_id: 0,
+        })
+    );
+    assert_eq!(stsd.contents.codec_string().as_deref(), Some("dvh1.05.06"));
+    assert_eq!(
+        stsd.contents.dolby_vision_codec_string().as_deref(),
+        Some("dvh1.05.06")
+    );
+}
+
+#[test]
+fn hev1_with_dvvc() {
+    let entry = video_sample_entry(
+        b"hev1",
+        &[
+            &mp4_box(b"hvcC", &[&HVCC]),
+            &mp4_box(b"dvvC", &[&dovi_record(8, 9, 0b101, 1)]),
+            &mp4_box(b"btrt", &[&[0; 12]]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+
+    assert_eq!(stsd.get_size(), 16 + entry.len() as u64);
+    let StsdBoxContent::Hev1(hevc) = &stsd.contents else {
+        panic!("expected hev1, got {:?}", stsd.contents);
+    };
+    let dovi = hevc
+        .dovi
+        .as_ref()
+        .expect("Missing Dolby Vision configuration");
+    assert_eq!((dovi.profile, dovi.level), (8, 9));
+    assert_eq!(dovi.bl_signal_compatibility_id, 1);
+    assert!(hevc.btrt.is_some());
+
+    // The HEVC base layer stays playable on its own.
+    assert_eq!(
+        stsd.contents.codec_string().as_deref(),
+        Some("hev1.1.6.L93.90")
+    );
+    assert_eq!(
+        stsd.contents.dolby_vision_codec_string().as_deref(),
+        Some("dvhe.08.09")
+    );
+}
+
+#[test]
+fn hvc1_without_dolby_vision() {
+    let entry = video_sample_entry(b"hvc1", &[&mp4_box(b"hvcC", &[&HVCC])]);
+    let stsd = read_stsd(&entry);
+
+    let StsdBoxContent::Hvc1(hevc) = &stsd.contents else {
+        panic!("expected hvc1, got {:?}", stsd.contents);
+    };
+    assert_eq!(hevc.dovi, None);
+    assert_eq!(stsd.contents.dolby_vision_codec_string(), None);
+}
@@ -0,0 +1,166 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::remux::{M4aCodec, M4aTags, M4aWriter, Mp4Editor};
+use re_mp4::{FreeSpace, Metadata as _, Mp4};
+use synthetic::{build_progressive, top_level_boxes, TestTrack};
+
+fn frames() -> Vec<Vec<u8>> {
+    (0..20_u8).map(|i| vec![i; 50 + usize::from(i)]).collect()
+}
+
+/// An M4A file with `moov` ahead of the media data.
+fn tagged_m4a() -> Vec<u8> {
+    let mut writer = M4aWriter::new(
+        M4aCodec::Aac {
+            audio_specific_config: vec![0x12, 0x10],
+        },
+        44100,
+        2,
+    );
+    writer.set_tags(M4aTags {
+        title: Some("Demo".to_owned()),
+        artist: Some("Someone".to_owned()),
+        year: Some("2023".to_owned()),
+        cover: Some(b"\x89PNG cover".to_vec()),
+        ..Default::default()
+    });
+    for frame in frames() {
+        writer
+            .add_sample(&frame, 1024)
+            .expect("Failed adding sample");
+    }
+    let mut data = Vec::new();
+    writer.finish(&mut data).expect("Failed writing");
+    data
+}
+
+fn edit(data: &mut Vec<u8>, editor: &Mp4Editor) -> Mp4 {
+    let mut file = Cursor::new(std::mem::take(data));
+    editor.apply(&mut file).expect("Failed editing");
+    *data = file.into_inner();
+    let mp4 = Mp4::read_bytes(data).expect("Failed parsing");
+    let track = &mp4.tracks()[&1];
+    for (sample, frame) in track.samples.iter().zip(frames()) {
+        assert_eq!(data[sample.byte_range()], frame);
+    }
+    mp4
+}
+
+#[test]
+fn edits_tags_without_moving_media_data() {
+    let mut data = tagged_m4a();
+    let original = Mp4::read_bytes(&data).unwrap();
+    let original_len = data.len();
+
+    // A longer title doesn't fit in place, so `moov` moves to the end.
+    let mut editor = Mp4Editor::new();
+    editor
+        .set_title(Some("A much longer title than before"))
+        .set_year(Some(2024));
+    let mp4 = edit(&mut data, &editor);
+    assert_eq!(
+        top_level_boxes(&data),
+        [*b"ftyp", *b"free", *b"mdat", *b"moov"]
+    );
+    let offsets =
+        |mp4: &Mp4| -> Vec<u64> { mp4.tracks()[&1].samples.iter().map(|s| s.offset).collect() };
+    assert_eq!(offsets(&mp4), offsets(&original));
+    let metadata = mp4.metadata();
+    assert_eq!(
+        metadata.title().as_deref(),
+        Some("A much longer title than before")
+    );
+    assert_eq!(metadata.year(), Some(2024));
+    assert_eq!(metadata.poster(), Some(&b"\x89PNG cover"[..]));
+
+    // A shorter `moov` fits where the first one was again, ahead of the media data, and the
+    // rest of that space and the `moov` at the end become `free`.
+    let len = data.len();
+    let mp4 = edit(
+        &mut data,
+        Mp4Editor::new().set_cover(None).set_title(Some("Short")),
+    );
+    assert_eq!(data.len(), len);
+    assert_eq!(
+        top_level_boxes(&data),
+        [*b"ftyp", *b"moov", *b"free", *b"mdat", *b"free"]
+    );
+    assert_eq!(mp4.metadata().title().as_deref(), Some("Short"));
+    assert_eq!(mp4.metadata().poster(), None);
+    assert!(data.len() > original_len);
+
+    // Now the `free` box after `moov` leaves room to grow.
+    let mp4 = edit(&mut data, Mp4Editor::new().set_title(Some("Shorter")));
+    assert_eq!(data.len(), len);
+    assert_eq!(
+        top_level_boxes(&data),
+        [*b"ftyp", *b"moov", *b"free", *b"mdat", *b"free"]
+    );
+    let free_space = mp4.free_space();
+    assert_eq!(free_space.file_size(), len as u64);
+    assert_eq!(free_space.runs().len(), 2);
+    assert_eq!(mp4.metadata().title().as_deref(), Some("Shorter"));
+    assert_eq!(mp4.metadata().year(), Some(2024));
+}
+
+#[test]
+fn shorter_tags_are_edited_in_place() {
+    let mut data = tagged_m4a();
+    let len = data.len();
+    let mp4 = edit(
+        &mut data,
+        Mp4Editor::new().set_year(None).set_title(Some("D")),
+    );
+    assert_eq!(data.len(), len);
+    assert_eq!(
+        top_level_boxes(&data),
+        [*b"ftyp", *b"moov", *b"free", *b"mdat"]
+    );
+    assert_eq!(mp4.metadata().title().as_deref(), Some("D"));
+    assert_eq!(mp4.metadata().year(), None);
+}
+
+#[test]
+fn adds_tags_to_file_without_any() {
+    let mut data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"soun",
+            timescale: 1000,
+            chunks: vec![frames()],
+            sync_samples: None,
+        }],
+        false,
+    );
+    let mp4 = edit(&mut data, Mp4Editor::new().set_title(Some("New")));
+    assert_eq!(mp4.metadata().title().as_deref(), Some("New"));
+    let udta = mp4.moov.udta.as_ref().unwrap();
+    assert!(udta.meta.as_ref().and_then(|meta| meta.ilst()).is_some());
+}
+
+#[test]
+fn free_space_allocation() {
+    // Free boxes at 100 and 500, and the end of a 1000 byte file.
+    let mut space = FreeSpace::new(1000);
+    space.release(500, 100);
+    space.release(100, 50);
+    space.release(150, 50);
+    assert_eq!(space.runs(), [(100, 100), (500, 100)]);
+    assert_eq!(space.total(), 200);
+
+    // What is left of a run must fit a `free` box.
+    assert_eq!(space.allocate(95), 1000);
+    assert_eq!(space.file_size(), 1095);
+    assert_eq!(space.allocate(92), 100);
+    assert_eq!(space.allocate(100), 500);
+    assert_eq!(space.runs(), [(192, 8)]);
+
+    // The run at the end of the file may grow.
+    space.release(1000, 95);
+    assert_eq!(space.allocate(300), 1000);
+    assert_eq!(space.file_size(), 1300);
+    assert_eq!(space.runs(), [(192, 8)]);
+}
@@ -0,0 +1,185 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{
+    AacConfig, Av01Box, Av1CBox, Avc1Box, AvcCBox, FixedPointU16, Mp4, Mp4aBox, RawBox,
+    StsdBoxContent,
+};
+use synthetic::{build_progressive, TestTrack};
+
+/// Parses a file with one track of `samples`, the first of which is a sync sample, and
+/// gives it the sample entry `contents`.
+fn single_track(samples: Vec<Vec<u8>>, contents: StsdBoxContent) -> (Vec<u8>, Mp4) {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 1000,
+            chunks: vec![samples],
+            sync_samples: Some(vec![1]),
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).expect("Failed parsing");
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = contents;
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+    (data, mp4)
+}
+
+fn elementary_stream(data: &[u8], mp4: &Mp4) -> re_mp4::Result<Vec<u8>> {
+    let mut stream = Vec::new();
+    mp4.tracks()[&1].write_elementary_stream(mp4, &mut Cursor::new(data), &mut stream)?;
+    Ok(stream)
+}
+
+#[test]
+fn avc_as_annex_b() {
+    let sps = [0x67, 0x64, 0x00, 0x1f];
+    let pps = [0x68, 0xee];
+    let avc1 = Avc1Box {
+        avcc: RawBox {
+            contents: AvcCBox::new(&sps, &pps),
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    };
+    let (data, mp4) = single_track(
+        vec![
+            vec![0, 0, 0, 2, 0x65, 0xaa, 0, 0, 0, 1, 0x06],
+            vec![0, 0, 0, 1, 0x41],
+        ],
+        StsdBoxContent::Avc1(avc1),
+    );
+
+    assert_eq!(mp4.tracks()[&1].elementary_stream_extension(), Some("h264"));
+    assert_eq!(
+        elementary_stream(&data, &mp4).unwrap(),
+        [
+            &[0, 0, 0, 1][..],
+            &sps,
+            &[0, 0, 0, 1],
+            &pps,
+            &[0, 0, 0, 1, 0x65, 0xaa],
+            &[0, 0, 0, 1, 0x06],
+            &[0, 0, 0, 1, 0x41],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn truncated_nal_unit_is_an_error() {
+    let avc1 = Avc1Box {
+        avcc: RawBox {
+            contents: AvcCBox::new(&[0x67, 0x64, 0x00, 0x1f], &[0x68]),
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    };
+    let (data, mp4) = single_track(vec![vec![0, 0, 0, 9, 0x65]], StsdBoxContent::Avc1(avc1));
+    assert!(matches!(
+        elementary_stream(&data, &mp4),
+        Err(re_mp4::Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn sample_beyond_the_file_is_an_error() {
+    let avc1 = StsdBoxContent::Avc1(Avc1Box::default());
+    let (data, mut mp4) = single_track(vec![vec![0, 0, 0, 1, 0x65]], avc1);
+
+    // A sample size of 4 GiB must fail at the end of the file, without allocating that much.
+    let stsz = &mut mp4.moov.traks[0].mdia.minf.stbl.stsz;
+    stsz.sample_size = u32::MAX;
+    stsz.sample_sizes.clear();
+    mp4.rebuild_tracks().unwrap();
+    assert!(matches!(
+        elementary_stream(&data, &mp4),
+        Err(re_mp4::Error::Io(_))
+    ));
+}
+
+#[test]
+fn av1_as_low_overhead_obus() {
+    let sequence_header = [0x0a, 0x02, 0xaa, 0xbb];
+    let av01 = Av01Box {
+        data_reference_index: 1,
+        width: 64,
+        height: 64,
+        horizresolution: FixedPointU16::new(0x48),
+        vertresolution: FixedPointU16::new(0x48),
+        frame_count: 1,
+        depth: 24,
+        av1c: RawBox {
+            contents: Av1CBox {
+                config_obus: sequence_header.to_vec(),
+                ..Default::default()
+            },
+            raw: Vec::new(),
+        },
+        btrt: None,
+        colr: None,
+        pasp: None,
+        clap: None,
+    };
+    // A frame OBU with a size field, and a tile group OBU without one.
+    let (data, mp4) = single_track(
+        vec![vec![0x32, 0x01, 0xcc, 0x20, 0xdd, 0xee]],
+        StsdBoxContent::Av01(av01),
+    );
+
+    assert_eq!(mp4.tracks()[&1].elementary_stream_extension(), Some("obu"));
+    assert_eq!(
+        elementary_stream(&data, &mp4).unwrap(),
+        [
+            &[0x12, 0x00][..],
+            &sequence_header,
+            &[0x32, 0x01, 0xcc],
+            &[0x22, 0x02, 0xdd, 0xee],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn aac_with_adts_headers() {
+    let (data, mp4) = single_track(
+        vec![vec![0x21; 3], vec![0x42; 2]],
+        StsdBoxContent::Mp4a(Mp4aBox::new(&AacConfig::default())),
+    );
+
+    assert_eq!(mp4.tracks()[&1].elementary_stream_extension(), Some("aac"));
+    // AAC-LC (profile 1), 48 kHz (index 3) and two channels.
+    assert_eq!(
+        elementary_stream(&data, &mp4).unwrap(),
+        [
+            &[0xff, 0xf1, 0x4c, 0x80, 0x01, 0x5f, 0xfc][..],
+            &[0x21; 3],
+            &[0xff, 0xf1, 0x4c, 0x80, 0x01, 0x3f, 0xfc],
+            &[0x42; 2],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn unsupported_codec_is_an_error() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 1000,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks()[&1].elementary_stream_extension(), None);
+    assert!(matches!(
+        elementary_stream(&data, &mp4),
+        Err(re_mp4::Error::UnsupportedCodec(1, _))
+    ));
+}
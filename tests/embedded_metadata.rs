@@ -0,0 +1,62 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::Mp4;
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+const XMP_USER_TYPE: [u8; 16] = [
+    0xbe, 0x7a, 0xcf, 0xcb, 0x97, 0xa9, 0x42, 0xe8, 0x9c, 0x71, 0x99, 0x94, 0x91, 0xe3, 0xaf, 0xac,
+];
+
+fn test_file() -> Vec<u8> {
+    build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        false,
+    )
+}
+
+#[test]
+fn xmp_in_top_level_uuid_box() {
+    let file = test_file();
+    assert_eq!(Mp4::read_bytes(&file).unwrap().xmp(), None);
+
+    let xmp = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>";
+    let data = [
+        file,
+        mp4_box(b"uuid", &[&[0; 16], b"other vendor"]),
+        mp4_box(b"uuid", &[&XMP_USER_TYPE, xmp]),
+    ]
+    .concat();
+    assert_eq!(Mp4::read_bytes(&data).unwrap().xmp(), Some(&xmp[..]));
+}
+
+#[test]
+fn xmp_and_id3_in_udta() {
+    let xmp = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>";
+    let id3 = b"ID3\x04\0\0\0\0\0\0";
+    let meta = mp4_box(
+        b"meta",
+        &[
+            &[0; 4],
+            &mp4_box(b"hdlr", &[&[0; 8], b"ID32", &[0; 12], b"\0"]),
+            // Version and flags, and the language `und`.
+            &mp4_box(b"ID32", &[&[0; 4], &[0x55, 0xc4], id3]),
+        ],
+    );
+    let mut data = test_file();
+    append_child(
+        &mut data,
+        &[b"moov"],
+        &mp4_box(b"udta", &[&mp4_box(b"XMP_", &[xmp]), &meta]),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.xmp(), Some(&xmp[..]));
+    assert_eq!(mp4.id3(), Some(&id3[..]));
+}
@@ -0,0 +1,84 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{DataEntry, DataSources, ExternalDataRef, Mp4, UrnBox};
+use synthetic::{build_progressive, with_external_video_data, TestTrack};
+
+#[test]
+fn samples_are_read_from_external_files() {
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+            sync_samples: None,
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![vec![vec![4; 3], vec![5; 3]]],
+            sync_samples: None,
+        },
+    ];
+    let mut data = build_progressive(&tracks, false);
+
+    with_external_video_data(&mut data);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let video = &mp4.tracks()[&1];
+    let audio = &mp4.tracks()[&2];
+    assert_eq!(
+        video.external_reference(&mp4),
+        Some(ExternalDataRef {
+            url: None,
+            name: None,
+        })
+    );
+    assert_eq!(audio.external_reference(&mp4), None);
+
+    // The referenced file holds different bytes at the same offsets.
+    let external: Vec<u8> = data.iter().map(|byte| !byte).collect();
+
+    let mut sources = DataSources::new(Cursor::new(data.as_slice()));
+    assert!(matches!(
+        sources.read_sample(&mp4, video, &video.samples[0]),
+        Err(re_mp4::Error::ExternalDataReference(1))
+    ));
+
+    let mut sources = sources.with_external(1, 1, Cursor::new(external.as_slice()));
+    for (sample, expected) in video.samples.iter().zip(tracks[0].samples()) {
+        let inverted: Vec<u8> = expected.iter().map(|byte| !byte).collect();
+        assert_eq!(sources.read_sample(&mp4, video, sample).unwrap(), inverted);
+    }
+    for (sample, expected) in audio.samples.iter().zip(tracks[1].samples()) {
+        assert_eq!(sources.read_sample(&mp4, audio, sample).unwrap(), expected);
+    }
+}
+
+#[test]
+fn urn_entries_have_a_name_and_location() {
+    let entry = DataEntry::Urn(UrnBox {
+        version: 0,
+        flags: 0,
+        name: "urn:example:media".to_owned(),
+        location: "media.mov".to_owned(),
+    });
+    assert_eq!(
+        entry.external_reference(),
+        Some(ExternalDataRef {
+            url: Some("media.mov".to_owned()),
+            name: Some("urn:example:media".to_owned()),
+        })
+    );
+
+    let self_contained = DataEntry::Urn(UrnBox {
+        version: 0,
+        flags: DataEntry::FLAG_SELF_CONTAINED,
+        name: "urn:example:media".to_owned(),
+        location: String::new(),
+    });
+    assert_eq!(self_contained.external_reference(), None);
+}
@@ -1,4 +1,18 @@
 mod paths;
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use re_mp4::{
+    DataSources, DefaultSource, Error, FragmentDefault, FragmentDefaults, FragmentDuration,
+    FtypBox, Limits, MdhdBox, MdiaBox, MehdBox, MoofBox, MoovBox, Mp4, Mp4Box as _, MvexBox,
+    SampleFlags, Skipped, TfdtBox, TfhdBox, TkhdBox, TrackFragmentStats, TrafBox, TrakBox, TrexBox,
+    TrunBox, WriteBox as _,
+};
+use synthetic::{build_progressive, mp4_box, TestTrack};
 
 /// Regression test: sample sync flags in a *fragmented* mp4 must be read from
 /// the `trun`/`tfhd` sample flags with the correct polarity.
@@ -43,3 +57,622 @@ fn fragmented_mp4_reads_sync_flags_with_correct_polarity() {
         "not every sample can be a keyframe"
     );
 }
+
+fn fragmented_mp4(trex: Option<TrexBox>, trafs: Vec<TrafBox>) -> re_mp4::Result<Mp4> {
+    let moov = MoovBox {
+        mvex: trex.map(|trex| MvexBox {
+            mehd: None,
+            trexs: vec![trex],
+        }),
+        traks: vec![TrakBox {
+            tkhd: TkhdBox {
+                track_id: 1,
+                ..Default::default()
+            },
+            mdia: MdiaBox {
+                mdhd: MdhdBox {
+                    timescale: 1000,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let moofs = trafs
+        .into_iter()
+        .enumerate()
+        .map(|(index, traf)| MoofBox {
+            start: 1000 * index as u64,
+            trafs: vec![traf],
+            ..Default::default()
+        })
+        .collect();
+    Mp4::from_parts(FtypBox::default(), moov, moofs)
+}
+
+fn traf(mut tfhd: TfhdBox, tfdt: u64, trun: TrunBox) -> TrafBox {
+    tfhd.track_id = 1;
+    tfhd.flags |= TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF;
+    TrafBox {
+        tfhd,
+        tfdt: Some(TfdtBox {
+            version: 1,
+            flags: 0,
+            base_media_decode_time: tfdt,
+        }),
+        truns: vec![trun],
+    }
+}
+
+fn default_trex() -> TrexBox {
+    TrexBox {
+        track_id: 1,
+        default_sample_description_index: 1,
+        default_sample_duration: 40,
+        default_sample_size: 100,
+        ..Default::default()
+    }
+}
+
+/// A run of `sample_count` samples without any per-sample values.
+fn bare_trun(sample_count: u32) -> TrunBox {
+    TrunBox {
+        flags: TrunBox::FLAG_DATA_OFFSET,
+        sample_count,
+        data_offset: Some(200),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn trun_without_sample_values_uses_defaults() {
+    let tfhd = TfhdBox {
+        flags: TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE,
+        default_sample_size: Some(10),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            traf(TfhdBox::default(), 0, bare_trun(2)),
+            traf(tfhd, 80, bare_trun(2)),
+        ],
+    )
+    .unwrap();
+
+    let samples: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| (s.decode_timestamp, s.duration, s.byte_range()))
+        .collect();
+    assert_eq!(
+        samples,
+        [
+            // Sizes and durations from `trex`.
+            (0, 40, 200..300),
+            (40, 40, 300..400),
+            // Sizes from `tfhd`, durations from `trex`.
+            (80, 40, 1200..1210),
+            (120, 40, 1210..1220),
+        ]
+    );
+}
+
+#[test]
+fn trun_without_sample_values_or_defaults_is_an_error() {
+    let result = fragmented_mp4(None, vec![traf(TfhdBox::default(), 0, bare_trun(2))]);
+    assert!(matches!(
+        result,
+        Err(re_mp4::Error::BoxInTrafNotFound(
+            1,
+            re_mp4::BoxType::TrexBox
+        ))
+    ));
+}
+
+#[test]
+fn empty_duration_fragment_has_no_samples() {
+    let empty = TfhdBox {
+        flags: TfhdBox::FLAG_DURATION_IS_EMPTY | TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION,
+        default_sample_duration: Some(500),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            traf(TfhdBox::default(), 0, bare_trun(1)),
+            // A stray run in an empty fragment is ignored.
+            traf(empty, 40, bare_trun(3)),
+            traf(TfhdBox::default(), 540, bare_trun(1)),
+        ],
+    )
+    .unwrap();
+
+    let timestamps: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| s.decode_timestamp)
+        .collect();
+    // The empty fragment leaves a gap of its duration.
+    assert_eq!(timestamps, [0, 540]);
+}
+
+#[test]
+fn frame_rate_and_duration_come_from_fragments() {
+    let with_durations = TrunBox {
+        flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_DURATION,
+        sample_count: 2,
+        data_offset: Some(200),
+        sample_durations: vec![20, 60],
+        ..Default::default()
+    };
+    let mut traf = traf(TfhdBox::default(), 0, with_durations);
+    // A second run in the same fragment, which uses the default duration of 40.
+    traf.truns.push(bare_trun(2));
+    let mp4 = fragmented_mp4(Some(default_trex()), vec![traf]).unwrap();
+
+    let track = &mp4.tracks()[&1];
+    let durations: Vec<_> = track.samples.iter().map(|s| s.duration).collect();
+    assert_eq!(durations, [20, 60, 40, 40]);
+
+    // The sample tables in `moov` are empty, and `mdhd` has no duration.
+    assert_eq!(track.duration, 160);
+    assert_eq!(track.frame_rate(), Some(25.0));
+}
+
+#[test]
+fn effective_defaults_report_their_source() {
+    let tfhd = TfhdBox {
+        flags: TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE,
+        default_sample_size: Some(10),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            traf(TfhdBox::default(), 0, bare_trun(2)),
+            traf(tfhd, 80, bare_trun(2)),
+        ],
+    )
+    .unwrap();
+    let fragments: Vec<_> = mp4.fragments().collect();
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments[1].trex(1), Some(&default_trex()));
+
+    let trex = |value| {
+        Some(FragmentDefault {
+            value,
+            source: DefaultSource::Trex,
+        })
+    };
+    assert_eq!(
+        fragments[0].effective_defaults(1),
+        Some(FragmentDefaults {
+            sample_duration: trex(40),
+            sample_size: trex(100),
+            sample_flags: trex(0),
+        })
+    );
+    assert_eq!(
+        fragments[1].effective_defaults(1),
+        Some(FragmentDefaults {
+            sample_duration: trex(40),
+            sample_size: Some(FragmentDefault {
+                value: 10,
+                source: DefaultSource::Tfhd,
+            }),
+            sample_flags: trex(0),
+        })
+    );
+    assert_eq!(fragments[0].effective_defaults(2), None);
+}
+
+#[test]
+fn duplicate_fragments_are_skipped() {
+    // Fragments with the sample data at an absolute offset, as if the file was appended twice.
+    let at = |base_data_offset: u64, tfdt: u64| {
+        let tfhd = TfhdBox {
+            flags: TfhdBox::FLAG_BASE_DATA_OFFSET,
+            base_data_offset: Some(base_data_offset),
+            ..Default::default()
+        };
+        traf(tfhd, tfdt, bare_trun(2))
+    };
+
+    let skipped = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&skipped);
+    re_mp4::set_skip_logger(Some(Box::new(move |s: &Skipped| {
+        log.borrow_mut().push(s.clone());
+    })));
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            at(0, 0),
+            at(1000, 80),
+            at(0, 0),
+            at(1000, 80),
+            // Same data, but a different time: not a duplicate.
+            at(1000, 160),
+        ],
+    );
+    re_mp4::set_skip_logger(None);
+    let mp4 = mp4.unwrap();
+
+    let samples: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| (s.id, s.decode_timestamp, s.offset))
+        .collect();
+    assert_eq!(
+        samples,
+        [
+            (0, 0, 200),
+            (1, 40, 300),
+            (2, 80, 1200),
+            (3, 120, 1300),
+            (4, 160, 1200),
+            (5, 200, 1300),
+        ]
+    );
+    assert_eq!(
+        *skipped.borrow(),
+        [
+            Skipped::DuplicateFragment {
+                track_id: 1,
+                offset: 2000,
+                original_offset: 0,
+            },
+            Skipped::DuplicateFragment {
+                track_id: 1,
+                offset: 3000,
+                original_offset: 1000,
+            },
+        ]
+    );
+}
+
+#[test]
+fn fragments_at_the_end_of_the_offset_range() {
+    // A `base_data_offset` so large that the end of the samples doesn't fit in 64 bits.
+    let tfhd = TfhdBox {
+        flags: TfhdBox::FLAG_BASE_DATA_OFFSET,
+        base_data_offset: Some(u64::MAX - 150),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(Some(default_trex()), vec![traf(tfhd, 0, bare_trun(2))]);
+    assert!(mp4.is_ok());
+}
+
+#[test]
+fn negative_composition_offsets_in_fragments() {
+    // Version 1 of `trun` stores signed composition offsets, here of a pre-roll of two samples.
+    let trun = TrunBox {
+        version: 1,
+        flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_CTS,
+        sample_count: 3,
+        data_offset: Some(200),
+        sample_cts: [-80i32, -80, 0]
+            .into_iter()
+            .map(i32::cast_unsigned)
+            .collect(),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![traf(TfhdBox::default(), 80, trun)],
+    )
+    .unwrap();
+
+    let samples: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| {
+            (
+                s.media_decode_timestamp,
+                s.decode_timestamp,
+                s.composition_timestamp,
+            )
+        })
+        .collect();
+    assert_eq!(samples, [(80, 80, 0), (120, 120, 40), (160, 160, 160)]);
+    assert_eq!(mp4.tracks()[&1].samples[1].presentation_time(), 0.04);
+    assert_eq!(mp4.tracks()[&1].samples[1].decode_time(), 0.12);
+}
+
+#[test]
+fn decode_times_beyond_32_bits() {
+    // More than 13 hours at 90 kHz, which does not fit in the 32-bit `tfdt` of version 0.
+    let start = 90_000 * 3600 * 14;
+    let trun = TrunBox {
+        version: 1,
+        flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_CTS,
+        sample_count: 3,
+        data_offset: Some(200),
+        sample_cts: [-40i32, 0, 40]
+            .into_iter()
+            .map(i32::cast_unsigned)
+            .collect(),
+        ..Default::default()
+    };
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            traf(TfhdBox::default(), start, trun.clone()),
+            traf(TfhdBox::default(), start + 120, trun),
+        ],
+    )
+    .unwrap();
+
+    let track = &mp4.tracks()[&1];
+    let samples: Vec<_> = track
+        .samples
+        .iter()
+        .map(|s| (s.media_decode_timestamp, s.composition_timestamp))
+        .collect();
+    let start_i64 = start.cast_signed();
+    assert_eq!(
+        samples,
+        [
+            (start, start_i64 - 40),
+            (start + 40, start_i64 + 40),
+            (start + 80, start_i64 + 120),
+            (start + 120, start_i64 + 80),
+            (start + 160, start_i64 + 160),
+            (start + 200, start_i64 + 240),
+        ]
+    );
+    assert_eq!(track.duration, start + 280);
+    assert_eq!(
+        track.samples[3].decode_time(),
+        (start + 120) as f64 / 1000.0
+    );
+}
+
+#[test]
+fn decode_times_beyond_64_bit_signed_saturate() {
+    let mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![traf(TfhdBox::default(), u64::MAX - 40, bare_trun(3))],
+    )
+    .unwrap();
+
+    let samples: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| (s.media_decode_timestamp, s.decode_timestamp))
+        .collect();
+    assert_eq!(
+        samples,
+        [
+            (u64::MAX - 40, i64::MAX),
+            (u64::MAX, i64::MAX),
+            (u64::MAX, i64::MAX),
+        ]
+    );
+}
+
+#[test]
+fn fragment_stats_and_duration() {
+    let empty = TfhdBox {
+        flags: TfhdBox::FLAG_DURATION_IS_EMPTY,
+        ..Default::default()
+    };
+    let mut mp4 = fragmented_mp4(
+        Some(default_trex()),
+        vec![
+            traf(TfhdBox::default(), 0, bare_trun(3)),
+            traf(empty, 120, TrunBox::default()),
+            traf(TfhdBox::default(), 160, bare_trun(2)),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        mp4.tracks()[&1].fragment_stats(&mp4),
+        Some(TrackFragmentStats {
+            samples_per_fragment: vec![3, 0, 2],
+            earliest_tfdt: Some(0),
+            latest_tfdt: Some(160),
+        })
+    );
+
+    assert_eq!(mp4.fragment_duration(), None);
+    mp4.moov.mvex.as_mut().unwrap().mehd = Some(MehdBox {
+        version: 0,
+        flags: 0,
+        fragment_duration: 240,
+    });
+    let duration = mp4.fragment_duration().unwrap();
+    assert_eq!(
+        duration,
+        FragmentDuration {
+            declared: 240,
+            actual: 240,
+        }
+    );
+    assert!(duration.is_consistent());
+
+    mp4.moov
+        .mvex
+        .as_mut()
+        .unwrap()
+        .mehd
+        .as_mut()
+        .unwrap()
+        .fragment_duration = 1000;
+    assert!(!mp4.fragment_duration().unwrap().is_consistent());
+}
+
+#[test]
+fn every_trun_uses_its_own_data_offset() {
+    let trun = |data_offset: Option<i32>| TrunBox {
+        flags: if data_offset.is_some() {
+            TrunBox::FLAG_DATA_OFFSET
+        } else {
+            0
+        },
+        sample_count: 2,
+        data_offset,
+        ..Default::default()
+    };
+    let mut first = traf(TfhdBox::default(), 0, trun(Some(200)));
+    first.truns.extend([trun(Some(1000)), trun(None)]);
+    // The base data offset is past the data, which a negative data offset points back to.
+    let tfhd = TfhdBox {
+        flags: TfhdBox::FLAG_BASE_DATA_OFFSET,
+        base_data_offset: Some(5000),
+        ..Default::default()
+    };
+    let second = traf(tfhd, 240, trun(Some(-500)));
+    let mp4 = fragmented_mp4(Some(default_trex()), vec![first, second]).unwrap();
+
+    let ranges: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| s.byte_range())
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            200..300,
+            300..400,
+            1000..1100,
+            1100..1200,
+            1200..1300,
+            1300..1400,
+            4500..4600,
+            4600..4700,
+        ]
+    );
+
+    let fragments: Vec<_> = mp4.fragments().collect();
+    let samples = fragments[1].samples(1).unwrap();
+    assert_eq!(
+        samples
+            .iter()
+            .map(|s| (s.file_range(), s.decode_time, s.is_sync()))
+            .collect::<Vec<_>>(),
+        [(4500..4600, Some(240), true), (4600..4700, Some(280), true)]
+    );
+    assert_eq!(fragments[0].samples(1).unwrap().len(), 6);
+    assert_eq!(fragments[0].samples(2).unwrap(), []);
+}
+
+#[test]
+fn sample_flags_from_trun_and_defaults() {
+    let leading = SampleFlags {
+        is_leading: 2,
+        depends_on: 1,
+        is_depended_on: 2,
+        has_redundancy: 2,
+        padding: 3,
+        is_non_sync: true,
+        degradation_priority: 7,
+    };
+    assert_eq!(leading.to_u32(), 0x09A7_0007);
+    assert_eq!(SampleFlags::from_u32(leading.to_u32()), leading);
+    assert_eq!(SampleFlags::SYNC.to_u32(), 0x0200_0000);
+    assert_eq!(SampleFlags::NON_SYNC.to_u32(), 0x0101_0000);
+
+    let trex = TrexBox {
+        default_sample_flags: SampleFlags::NON_SYNC.to_u32(),
+        ..default_trex()
+    };
+    let trun = TrunBox {
+        flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_FIRST_SAMPLE_FLAGS,
+        first_sample_flags: Some(SampleFlags::SYNC.to_u32()),
+        ..bare_trun(2)
+    };
+    let with_flags = TrunBox {
+        flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_FLAGS,
+        sample_flags: vec![leading.to_u32()],
+        ..bare_trun(1)
+    };
+    let mp4 = fragmented_mp4(
+        Some(trex),
+        vec![
+            traf(TfhdBox::default(), 0, trun),
+            traf(TfhdBox::default(), 80, with_flags),
+        ],
+    )
+    .unwrap();
+
+    let samples = &mp4.tracks()[&1].samples;
+    assert_eq!(
+        samples.iter().map(|s| s.flags).collect::<Vec<_>>(),
+        [SampleFlags::SYNC, SampleFlags::NON_SYNC, leading]
+    );
+    assert_eq!(
+        samples.iter().map(|s| s.is_sync).collect::<Vec<_>>(),
+        [true, false, false]
+    );
+}
+
+#[test]
+fn fragments_of_a_track_without_trak() {
+    // A file with track 1 in `moov`, followed by a fragment of track 2, whose samples follow
+    // the `moof` in an `mdat`.
+    let mut data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 1000,
+            chunks: vec![vec![vec![0; 10]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut orphan = traf(
+        TfhdBox {
+            flags: TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION | TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE,
+            default_sample_duration: Some(40),
+            default_sample_size: Some(100),
+            ..Default::default()
+        },
+        1200,
+        bare_trun(3),
+    );
+    orphan.tfhd.track_id = 2;
+    let mut moof = MoofBox {
+        trafs: vec![orphan],
+        ..Default::default()
+    };
+    moof.trafs[0].truns[0].data_offset = Some(moof.box_size() as i32 + 8);
+    moof.write_box(&mut data).unwrap();
+    let sample_data: Vec<u8> = (0..=255).cycle().take(300).collect();
+    data.extend(mp4_box(b"mdat", &[&sample_data]));
+
+    let read = |limits| Mp4::read_with_limits(Cursor::new(&data), data.len() as u64, limits);
+    assert!(matches!(
+        read(Limits::UNLIMITED),
+        Err(Error::TrakNotFound(2))
+    ));
+    assert!(matches!(
+        read(
+            Limits::UNLIMITED
+                .with_synthesize_missing_tracks(true)
+                .with_max_tracks(1)
+        ),
+        Err(Error::LimitExceeded("max_tracks", 1))
+    ));
+
+    let mp4 = read(Limits::UNLIMITED.with_synthesize_missing_tracks(true)).unwrap();
+    let track = &mp4.tracks()[&2];
+    assert!(track.trak(&mp4).is_none());
+    assert_eq!(track.timescale, 1000);
+    assert_eq!(track.duration, 1200 + 3 * 40);
+    assert_eq!(
+        track
+            .samples
+            .iter()
+            .map(|s| (s.decode_timestamp, s.size))
+            .collect::<Vec<_>>(),
+        [(1200, 100), (1240, 100), (1280, 100)]
+    );
+
+    let mut sources = DataSources::new(Cursor::new(&data));
+    let sample = sources.read_sample(&mp4, track, &track.samples[1]).unwrap();
+    assert_eq!(sample, sample_data[100..200]);
+}
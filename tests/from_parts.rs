@@ -0,0 +1,78 @@
+use re_mp4::{
+    FourCC, FtypBox, HdlrBox, MdhdBox, MdiaBox, MinfBox, MoovBox, Mp4, StblBox, StcoBox, StscBox,
+    StscEntry, StszBox, SttsBox, SttsEntry, TkhdBox, TrakBox,
+};
+
+fn video_trak(track_id: u32, sample_sizes: &[u32], chunk_offset: u32) -> TrakBox {
+    TrakBox {
+        tkhd: TkhdBox {
+            track_id,
+            ..Default::default()
+        },
+        mdia: MdiaBox {
+            mdhd: MdhdBox {
+                timescale: 30,
+                duration: sample_sizes.len() as u64,
+                ..Default::default()
+            },
+            hdlr: HdlrBox {
+                handler_type: FourCC::from(*b"vide"),
+                ..Default::default()
+            },
+            minf: MinfBox {
+                stbl: StblBox {
+                    stts: SttsBox {
+                        entries: vec![SttsEntry {
+                            sample_count: sample_sizes.len() as u32,
+                            sample_delta: 1,
+                        }],
+                        ..Default::default()
+                    },
+                    stsc: StscBox {
+                        entries: vec![StscEntry {
+                            first_chunk: 1,
+                            samples_per_chunk: sample_sizes.len() as u32,
+                            sample_description_index: 1,
+                            first_sample: 1,
+                        }],
+                        ..Default::default()
+                    },
+                    stsz: StszBox {
+                        sample_count: sample_sizes.len() as u32,
+                        sample_sizes: sample_sizes.to_vec(),
+                        ..Default::default()
+                    },
+                    stco: Some(StcoBox {
+                        entries: vec![chunk_offset],
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn samples_are_derived_from_assembled_boxes() {
+    let moov = MoovBox {
+        traks: vec![video_trak(1, &[10, 20, 30], 100)],
+        ..Default::default()
+    };
+    let mut mp4 = Mp4::from_parts(FtypBox::default(), moov, Vec::new()).unwrap();
+
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.timescale, 30);
+    let ranges: Vec<_> = track.samples.iter().map(|s| s.byte_range()).collect();
+    assert_eq!(ranges, vec![100..110, 110..130, 130..160]);
+    let timestamps: Vec<_> = track.samples.iter().map(|s| s.decode_timestamp).collect();
+    assert_eq!(timestamps, vec![0, 1, 2]);
+
+    // Editing the boxes is reflected after rebuilding.
+    mp4.moov.traks.push(video_trak(2, &[5], 500));
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(mp4.tracks().len(), 2);
+    assert_eq!(mp4.tracks()[&2].samples[0].byte_range(), 500..505);
+}
@@ -0,0 +1,207 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{BoxType, Error, FileKind, FourCC, ImageCodecConfig, JsonOptions, Mp4};
+use serde_json::Value;
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+const ILOC: [u8; 8] = [0, 0, 0, 0, 0x44, 0x00, 0, 0];
+const IINF: [u8; 6] = [0, 0, 0, 0, 0, 0];
+
+fn heif(major_brand: &[u8; 4], with_meta: bool) -> Vec<u8> {
+    let ftyp = mp4_box(b"ftyp", &[major_brand, &[0; 4], b"mif1", b"miaf"]);
+    let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"pict", &[0; 12], b"\0"]);
+    let meta = mp4_box(
+        b"meta",
+        &[
+            &[0; 4],
+            &hdlr,
+            &mp4_box(b"pitm", &[&[0, 0, 0, 0, 0, 1]]),
+            &mp4_box(b"iinf", &[&IINF]),
+            &mp4_box(b"iloc", &[&ILOC]),
+        ],
+    );
+    let mdat = mp4_box(b"mdat", &[&[0; 16]]);
+    if with_meta {
+        [ftyp, meta, mdat].concat()
+    } else {
+        [ftyp, mdat].concat()
+    }
+}
+
+#[test]
+fn heif_image_has_meta_and_no_tracks() {
+    let mp4 = Mp4::read_bytes(&heif(b"heic", true)).unwrap();
+    assert_eq!(mp4.file_kind(), FileKind::Heif);
+    assert!(mp4.tracks().is_empty());
+
+    let meta = mp4.meta.as_ref().unwrap();
+    assert_eq!(meta.iloc(), Some(&ILOC[..]));
+    assert_eq!(meta.iinf(), Some(&IINF[..]));
+    assert_eq!(meta.child_data(BoxType::FtypBox), None);
+
+    let boxes: Vec<_> = mp4.box_tree().iter().map(|node| node.box_type).collect();
+    assert_eq!(
+        boxes,
+        [FourCC::from(*b"ftyp"), (*b"meta").into(), (*b"mdat").into()]
+    );
+
+    let json: Value = serde_json::from_str(&mp4.to_json(JsonOptions::default())).unwrap();
+    assert_eq!(json["meta"]["hdlr"], "unknown");
+
+    let stream = Mp4::read_stream(&heif(b"heic", true)[..]).unwrap();
+    assert_eq!(stream.meta, mp4.meta);
+}
+
+#[test]
+fn heif_kind_falls_back_to_contents() {
+    // The major brand doesn't tell, but a compatible brand does.
+    let mp4 = Mp4::read_bytes(&heif(b"isom", true)).unwrap();
+    assert_eq!(mp4.file_kind(), FileKind::Heif);
+}
+
+#[test]
+fn file_without_moov_or_meta_is_an_error() {
+    assert!(matches!(
+        Mp4::read_bytes(&heif(b"heic", false)),
+        Err(Error::BoxNotFound(BoxType::MoovBox))
+    ));
+}
+
+#[test]
+fn file_kind_of_movies_and_audio() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4]]],
+        sync_samples: None,
+    }];
+    let mut mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).unwrap();
+    assert_eq!(mp4.meta, None);
+    assert_eq!(mp4.file_kind(), FileKind::Video);
+
+    mp4.ftyp.major_brand = (*b"M4A ").into();
+    assert_eq!(mp4.file_kind(), FileKind::Audio);
+
+    mp4.ftyp.major_brand = (*b"msf1").into();
+    assert_eq!(mp4.file_kind(), FileKind::ImageSequence);
+}
+
+/// An AVIF image with a coded image whose data is in two extents in `mdat`, and Exif data in
+/// `idat`.
+fn avif() -> Vec<u8> {
+    let build = |mdat_data_start: u32| {
+        let ftyp = mp4_box(b"ftyp", &[b"avif", &[0; 4], b"mif1", b"miaf"]);
+        let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"pict", &[0; 12], b"\0"]);
+        let infe = |version_and_flags: u32, item_id: u16, item_type: &[u8; 4], name: &[u8]| {
+            mp4_box(
+                b"infe",
+                &[
+                    &version_and_flags.to_be_bytes(),
+                    &item_id.to_be_bytes(),
+                    &[0, 0],
+                    item_type,
+                    name,
+                ],
+            )
+        };
+        let iinf = mp4_box(
+            b"iinf",
+            &[
+                &[0, 0, 0, 0, 0, 2],
+                &infe(0x0200_0000, 1, b"av01", b"Color\0"),
+                &infe(0x0200_0001, 2, b"Exif", b"\0"),
+            ],
+        );
+        let iloc = mp4_box(
+            b"iloc",
+            &[
+                &[1, 0, 0, 0, 0x44, 0x40, 0, 2],
+                // Item 1, construction method 0, two extents.
+                &[0, 1, 0, 0, 0, 0],
+                &mdat_data_start.to_be_bytes(),
+                &[0, 2],
+                &[0, 0, 0, 0, 0, 0, 0, 3],
+                &[0, 0, 0, 5, 0, 0, 0, 2],
+                // Item 2, construction method 1, one extent.
+                &[0, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1],
+                &[0, 0, 0, 1, 0, 0, 0, 2],
+            ],
+        );
+        let ipco = mp4_box(
+            b"ipco",
+            &[
+                &mp4_box(b"av1C", &[&[0x81, 0x04, 0x0c, 0x00]]),
+                &mp4_box(
+                    b"ispe",
+                    &[&[0; 4], &64u32.to_be_bytes(), &48u32.to_be_bytes()],
+                ),
+            ],
+        );
+        let ipma = mp4_box(
+            b"ipma",
+            &[&[0, 0, 0, 0, 0, 0, 0, 1], &[0, 1, 2, 0x81, 0x02]],
+        );
+        let meta = mp4_box(
+            b"meta",
+            &[
+                &[0; 4],
+                &hdlr,
+                &mp4_box(b"pitm", &[&[0, 0, 0, 0, 0, 1]]),
+                &iinf,
+                &iloc,
+                &mp4_box(b"iprp", &[&ipco, &ipma]),
+                &mp4_box(b"idat", &[&[9, 8, 7, 6]]),
+            ],
+        );
+        let mdat = mp4_box(b"mdat", &[&[10, 11, 12, 13, 14, 15, 16, 17]]);
+        [ftyp, meta, mdat].concat()
+    };
+    // The contents of `mdat` are its last 8 bytes.
+    let mdat_data_start = build(0).len() - 8;
+    build(mdat_data_start as u32)
+}
+
+#[test]
+fn avif_primary_item() {
+    let data = avif();
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.file_kind(), FileKind::Heif);
+
+    let items = mp4.image_items().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1].item_type, FourCC::from(*b"Exif"));
+    assert!(items[1].hidden);
+    assert_eq!(items[1].codec_config, None);
+    assert_eq!(items[1].bytes(&data).unwrap(), [8, 7]);
+
+    let primary = mp4.primary_image_item().unwrap().unwrap();
+    assert_eq!(primary, items[0]);
+    assert_eq!(primary.item_id, 1);
+    assert_eq!(primary.item_type, FourCC::from(*b"av01"));
+    assert_eq!(primary.name, "Color");
+    assert!(!primary.hidden);
+    assert_eq!(primary.dimensions, Some((64, 48)));
+    let Some(ImageCodecConfig::Av1(av1c)) = &primary.codec_config else {
+        panic!("expected av1C, got {:?}", primary.codec_config);
+    };
+    assert_eq!((av1c.profile, av1c.level, av1c.bit_depth), (0, 4, 8));
+    assert_eq!(primary.data_size(), 5);
+    assert_eq!(primary.bytes(&data).unwrap(), [10, 11, 12, 15, 16]);
+    assert_eq!(primary.bytes(&data[..data.len() - 2]), None);
+}
+
+#[test]
+fn image_items_of_a_movie() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4]]],
+        sync_samples: None,
+    }];
+    let mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).unwrap();
+    assert!(mp4.image_items().unwrap().is_empty());
+    assert_eq!(mp4.primary_image_item().unwrap(), None);
+}
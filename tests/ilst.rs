@@ -0,0 +1,70 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{BoxHeader, IlstBox, Metadata as _, MetadataKey, Mp4Box as _, ReadBox as _};
+use synthetic::mp4_box;
+
+/// An `ilst` item of type `name` with a text value.
+fn text_item(name: &[u8; 4], text: &str) -> Vec<u8> {
+    mp4_box(
+        name,
+        &[&mp4_box(
+            b"data",
+            &[&[0, 0, 0, 1, 0, 0, 0, 0], text.as_bytes()],
+        )],
+    )
+}
+
+fn read_ilst(items: &[&[u8]]) -> IlstBox {
+    let ilst = mp4_box(b"ilst", items);
+    let mut reader = Cursor::new(&ilst);
+    let header = BoxHeader::read(&mut reader).expect("Failed reading header");
+    IlstBox::read_box(&mut reader, header.size).expect("Failed reading ilst")
+}
+
+#[test]
+fn items_keep_file_order_and_duplicates() {
+    let ilst = read_ilst(&[
+        &text_item(b"desc", "summary"),
+        &text_item(b"\xa9nam", "first title"),
+        &text_item(b"\xa9day", "2024"),
+        &text_item(b"\xa9nam", "second title"),
+    ]);
+
+    let keys: Vec<_> = ilst.items.iter().map(|(key, _)| *key).collect();
+    assert_eq!(
+        keys,
+        [
+            MetadataKey::Summary,
+            MetadataKey::Title,
+            MetadataKey::Year,
+            MetadataKey::Title
+        ]
+    );
+    let titles: Vec<_> = ilst
+        .items_with_key(MetadataKey::Title)
+        .map(|item| item.data.data.as_slice())
+        .collect();
+    assert_eq!(titles, [b"first title".as_slice(), b"second title"]);
+
+    // Players show the first of repeated items.
+    assert_eq!(ilst.title().as_deref(), Some("first title"));
+    assert_eq!(ilst.year(), Some(2024));
+
+    // Serialization follows the order of the file, repeated items included.
+    let json = ilst.to_json().unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"items":["#,
+            r#"["Summary",{"data":{"data":[115,117,109,109,97,114,121],"data_type":"Text"}}],"#,
+            r#"["Title",{"data":{"data":[102,105,114,115,116,32,116,105,116,108,101],"data_type":"Text"}}],"#,
+            r#"["Year",{"data":{"data":[50,48,50,52],"data_type":"Text"}}],"#,
+            r#"["Title",{"data":{"data":[115,101,99,111,110,100,32,116,105,116,108,101],"data_type":"Text"}}]"#,
+            r#"]}"#
+        )
+    );
+    assert_eq!(serde_json::from_str::<IlstBox>(&json).unwrap(), ilst);
+}
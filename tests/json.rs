@@ -0,0 +1,56 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{JsonOptions, Mp4};
+use serde_json::Value;
+use synthetic::{build_progressive, TestTrack};
+
+fn mp4() -> Mp4 {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+        sync_samples: Some(vec![1]),
+    }];
+    Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing mp4")
+}
+
+#[test]
+fn to_json_is_stable() {
+    let mp4 = mp4();
+    let options = JsonOptions {
+        pretty: true,
+        ..Default::default()
+    };
+    assert_eq!(mp4.to_json(options), mp4.to_json(options));
+    assert_eq!(mp4.to_json(options), self::mp4().to_json(options));
+}
+
+#[test]
+fn to_json_options() {
+    let mp4 = mp4();
+
+    let minimal: Value = serde_json::from_str(&mp4.to_json(JsonOptions::default())).unwrap();
+    let stbl = &minimal["moov"]["trak"][0]["mdia"]["minf"]["stbl"];
+    assert!(stbl["stsz"].get("sample_sizes").is_none());
+    assert!(minimal["tracks"][0].get("samples").is_none());
+    assert_eq!(minimal["tracks"][0]["sample_count"], 3);
+    assert_eq!(minimal["boxes"][0]["type"], "ftyp");
+
+    let full: Value = serde_json::from_str(&mp4.to_json(JsonOptions {
+        sample_tables: true,
+        samples: true,
+        pretty: false,
+    }))
+    .unwrap();
+    let stbl = &full["moov"]["trak"][0]["mdia"]["minf"]["stbl"];
+    assert_eq!(stbl["stsz"]["sample_sizes"], serde_json::json!([4, 5, 6]));
+    assert_eq!(stbl["stss"]["entries"], serde_json::json!([1]));
+    assert_eq!(stbl["stco"]["entries"].as_array().unwrap().len(), 2);
+
+    let samples = full["tracks"][0]["samples"].as_array().unwrap();
+    assert_eq!(samples.len(), 3);
+    assert_eq!(samples[1]["size"], 5);
+    assert_eq!(samples[0]["is_sync"], true);
+}
@@ -0,0 +1,54 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Language, Mp4};
+use synthetic::{build_progressive, TestTrack};
+
+#[test]
+fn packed_roundtrip() {
+    let eng: Language = "eng".parse().unwrap();
+    assert_eq!(eng.to_packed(), 0x15c7);
+    assert_eq!(Language::from_packed(0x15c7), Some(eng));
+    assert_eq!(Language::from_packed(0x55c4), Some(Language::UNDETERMINED));
+    assert_eq!(eng.to_string(), "eng");
+
+    // Macintosh language codes of old `QuickTime` files, and codes with the pad bit set.
+    assert_eq!(Language::from_packed(0), None);
+    assert_eq!(Language::from_packed(0x8000 | 0x15c7), None);
+}
+
+#[test]
+fn invalid_codes() {
+    for code in ["en", "engl", "ENG", "e1g", "\u{e9}n"] {
+        assert!(code.parse::<Language>().is_err(), "{code:?}");
+    }
+}
+
+#[test]
+fn serde_as_string() {
+    let swe: Language = "swe".parse().unwrap();
+    let json = serde_json::to_string(&swe).unwrap();
+    assert_eq!(json, r#""swe""#);
+    assert_eq!(serde_json::from_str::<Language>(&json).unwrap(), swe);
+    assert!(serde_json::from_str::<Language>(r#""SWE""#).is_err());
+}
+
+#[test]
+fn track_language() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![vec![vec![0; 4]]],
+            sync_samples: None,
+        }],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.language(&mp4), Some(Language::UNDETERMINED));
+
+    mp4.moov.traks[0].mdia.mdhd.language = "```".to_owned();
+    assert_eq!(mp4.tracks()[&1].language(&mp4), None);
+}
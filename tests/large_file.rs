@@ -0,0 +1,116 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use re_mp4::{DataSources, Mp4};
+use synthetic::{build_progressive, moov, mp4_box, TestTrack};
+
+/// A file of `len` bytes that are all zero, except for the given parts.
+///
+/// Lets tests cover offsets beyond 4 GiB without allocating that much.
+struct SparseFile {
+    parts: Vec<(u64, Vec<u8>)>,
+    len: u64,
+    position: u64,
+}
+
+impl Read for SparseFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf
+            .len()
+            .min(self.len.saturating_sub(self.position) as usize);
+        let buf = &mut buf[..n];
+        buf.fill(0);
+        let end = self.position + n as u64;
+        for (offset, data) in &self.parts {
+            let from = self.position.max(*offset);
+            let to = end.min(offset + data.len() as u64);
+            if from < to {
+                buf[(from - self.position) as usize..(to - self.position) as usize]
+                    .copy_from_slice(&data[(from - offset) as usize..(to - offset) as usize]);
+            }
+        }
+        self.position = end;
+        Ok(n)
+    }
+}
+
+impl Seek for SparseFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(offset) => self.len.saturating_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.saturating_add_signed(offset),
+        };
+        Ok(self.position)
+    }
+}
+
+fn test_tracks() -> Vec<TestTrack> {
+    vec![TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+        sync_samples: None,
+    }]
+}
+
+#[test]
+fn wide_and_64_bit_mdat_beyond_4_gib() {
+    let tracks = test_tracks();
+
+    // ftyp, wide, then an `mdat` with a 64-bit size whose samples start after 5 GiB of padding.
+    let ftyp = mp4_box(b"ftyp", &[b"isom", &[0, 0, 2, 0], b"isom"]);
+    let wide = mp4_box(b"wide", &[]);
+    let data_start = (ftyp.len() + wide.len() + 16) as u64;
+    let padding = 5 << 30;
+    let samples: Vec<u8> = tracks[0].samples().collect::<Vec<_>>().concat();
+    let mdat_size = 16 + padding + samples.len() as u64;
+    let mdat_header = [&1u32.to_be_bytes()[..], b"mdat", &mdat_size.to_be_bytes()].concat();
+
+    let first_chunk = data_start + padding;
+    let moov = moov(&tracks, &[vec![first_chunk, first_chunk + 9]]);
+    let moov_offset = data_start - 16 + mdat_size;
+
+    let header = [ftyp, wide, mdat_header].concat();
+    let mut file = SparseFile {
+        len: moov_offset + moov.len() as u64,
+        parts: vec![(0, header), (first_chunk, samples), (moov_offset, moov)],
+        position: 0,
+    };
+    let len = file.len;
+    let mp4 = Mp4::read(&mut file, len).unwrap();
+
+    assert_eq!(mp4.mdats, [(data_start, padding + 15)]);
+    assert!(mp4.moov.traks[0].mdia.minf.stbl.co64.is_some());
+
+    let track = &mp4.tracks()[&1];
+    let offsets: Vec<u64> = track.samples.iter().map(|s| s.offset).collect();
+    assert_eq!(offsets, [first_chunk, first_chunk + 4, first_chunk + 9]);
+    assert!(offsets.iter().all(|&offset| offset > u64::from(u32::MAX)));
+
+    let mut sources = DataSources::new(file);
+    for (sample, expected) in track.samples.iter().zip(tracks[0].samples()) {
+        assert_eq!(sources.read_sample(&mp4, track, sample).unwrap(), expected);
+    }
+}
+
+#[test]
+fn mdat_extending_to_end_of_file() {
+    let tracks = test_tracks();
+    let mut data = build_progressive(&tracks, true);
+
+    // Let the trailing `mdat` extend to the end of the file, instead of giving its size.
+    let mdat = data
+        .windows(4)
+        .position(|window| window == b"mdat")
+        .unwrap()
+        - 4;
+    data[mdat..mdat + 4].copy_from_slice(&[0; 4]);
+
+    let mp4 = Mp4::read(Cursor::new(&data), data.len() as u64).unwrap();
+    assert_eq!(mp4.mdats, [(mdat as u64 + 8, 15)]);
+    assert_eq!(mp4.tracks()[&1].samples.len(), 3);
+}
@@ -0,0 +1,121 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use re_mp4::{Error, Limits, Mp4};
+use synthetic::{append_child, build_progressive, fragmented, mp4_box, test_tracks};
+
+fn read(data: &[u8], limits: Limits) -> re_mp4::Result<Mp4> {
+    Mp4::read_with_limits(Cursor::new(data), data.len() as u64, limits)
+}
+
+#[test]
+fn limits_are_enforced() {
+    // Two tracks with 14 samples in total.
+    let data = build_progressive(&test_tracks(), false);
+
+    assert!(matches!(
+        read(
+            &data,
+            Limits {
+                max_tracks: 1,
+                ..Limits::UNLIMITED
+            }
+        ),
+        Err(Error::LimitExceeded("max_tracks", 1))
+    ));
+    assert!(matches!(
+        read(
+            &data,
+            Limits {
+                max_total_samples: 13,
+                ..Limits::UNLIMITED
+            }
+        ),
+        Err(Error::LimitExceeded("max_total_samples", 13))
+    ));
+
+    let mp4 = read(
+        &data,
+        Limits {
+            max_tracks: 2,
+            max_total_samples: 14,
+            ..Limits::UNLIMITED
+        },
+    )
+    .unwrap();
+    assert_eq!(mp4.tracks().len(), 2);
+}
+
+#[test]
+fn sample_limit_covers_fragments() {
+    let fragmented = fragmented(&test_tracks(), Duration::from_millis(100));
+
+    let limits = Limits {
+        max_total_samples: 10,
+        ..Limits::UNLIMITED
+    };
+    assert!(matches!(
+        read(&fragmented, limits),
+        Err(Error::LimitExceeded("max_total_samples", 10))
+    ));
+}
+
+#[test]
+fn sample_limit_per_track() {
+    let data = build_progressive(&test_tracks(), false);
+    assert!(matches!(
+        read(&data, Limits::UNLIMITED.with_max_samples_per_track(7)),
+        Err(Error::LimitExceeded("max_samples_per_track", 7))
+    ));
+    read(&data, Limits::UNLIMITED.with_max_samples_per_track(8)).unwrap();
+
+    let fragmented = fragmented(&test_tracks(), Duration::from_millis(100));
+    assert!(matches!(
+        read(&fragmented, Limits::UNLIMITED.with_max_samples_per_track(7)),
+        Err(Error::LimitExceeded("max_samples_per_track", 7))
+    ));
+    read(&fragmented, Limits::UNLIMITED.with_max_samples_per_track(8)).unwrap();
+}
+
+#[test]
+fn box_depth_limit() {
+    let mut data = build_progressive(&test_tracks(), false);
+    // `udta` boxes nested a thousand levels deep, which take only 8 bytes per level.
+    let mut udta = mp4_box(b"udta", &[]);
+    for _ in 0..1000 {
+        udta = mp4_box(b"udta", &[&udta]);
+    }
+    append_child(&mut data, &[b"moov"], &udta);
+
+    assert!(matches!(
+        read(&data, Limits::UNLIMITED.with_max_box_depth(64)),
+        Err(Error::LimitExceeded("max_box_depth", 64))
+    ));
+    let mp4 = read(&data, Limits::UNLIMITED.with_max_box_depth(1002)).unwrap();
+    assert_eq!(mp4.tracks().len(), 2);
+}
+
+#[test]
+fn table_size_limit() {
+    let data = build_progressive(&test_tracks(), false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let table_bytes: u64 = mp4
+        .box_tree()
+        .iter()
+        .flat_map(|node| node.descendants())
+        .filter(|node| [*b"stts", *b"stsc", *b"stsz", *b"stco"].contains(&node.box_type.value))
+        .map(|node| node.size)
+        .sum();
+
+    assert!(matches!(
+        read(
+            &data,
+            Limits::UNLIMITED.with_max_table_bytes(table_bytes - 1)
+        ),
+        Err(Error::LimitExceeded("max_table_bytes", _))
+    ));
+    read(&data, Limits::UNLIMITED.with_max_table_bytes(table_bytes)).unwrap();
+}
@@ -0,0 +1,244 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use re_mp4::{load_track_data, load_track_data_aligned, LoadOptions, Mp4};
+use synthetic::{build_progressive, test_tracks, with_external_video_data};
+
+#[test]
+fn load_track_data_reads_all_samples() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    for test_track in &tracks {
+        let track = &mp4.tracks()[&test_track.track_id];
+        let samples = load_track_data(&mp4, track, &mut Cursor::new(&data)).unwrap();
+        assert_eq!(
+            samples,
+            test_track.samples().map(<[u8]>::to_vec).collect::<Vec<_>>()
+        );
+    }
+}
+
+/// Counts the reads from the inner reader.
+struct CountingReader<R> {
+    inner: R,
+    reads: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reads += 1;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn load_track_data_reads_each_chunk_at_once() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    for test_track in &tracks {
+        let mut reader = CountingReader {
+            inner: Cursor::new(&data),
+            reads: 0,
+        };
+        let track = &mp4.tracks()[&test_track.track_id];
+        load_track_data(&mp4, track, &mut reader).unwrap();
+
+        // The chunks of the tracks are interleaved, so they can't be merged any further.
+        assert_eq!(reader.reads, test_track.chunks.len());
+    }
+}
+
+#[test]
+fn load_track_data_aligned_pads_samples() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let options = LoadOptions {
+        alignment: 16,
+        padding: 8,
+    };
+
+    for test_track in &tracks {
+        let track = &mp4.tracks()[&test_track.track_id];
+        let loaded =
+            load_track_data_aligned(&mp4, track, &mut Cursor::new(&data), options).unwrap();
+
+        let mut previous_end = 0;
+        for (index, expected) in test_track.samples().enumerate() {
+            let range = &loaded.ranges[index];
+            assert_eq!(range.start % 16, 0);
+            assert!(range.start >= previous_end + 8 || index == 0);
+            assert_eq!(loaded.sample(index).unwrap(), expected);
+            assert!(loaded.data[range.end..range.end + 8]
+                .iter()
+                .all(|&b| b == 0));
+            previous_end = range.end;
+        }
+        assert_eq!(loaded.data.len(), previous_end + 8);
+    }
+}
+
+#[test]
+fn sample_file_and_data_ranges() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let options = LoadOptions {
+        alignment: 16,
+        padding: 0,
+    };
+
+    let video = &mp4.tracks()[&1];
+    let audio = &mp4.tracks()[&2];
+    let loaded = load_track_data_aligned(&mp4, video, &mut Cursor::new(&data), options).unwrap();
+    for (sample, expected) in video.samples.iter().zip(tracks[0].samples()) {
+        let file_range = sample.file_range();
+        assert_eq!(
+            &data[file_range.start as usize..file_range.end as usize],
+            expected
+        );
+        let data_range = sample.data_range(&loaded).unwrap();
+        assert_eq!(data_range.start % 16, 0);
+        assert_eq!(&loaded.data[data_range], expected);
+    }
+    // The loaded data has fewer samples than the audio track.
+    assert_eq!(audio.samples[7].data_range(&loaded), None);
+}
+
+#[test]
+fn sample_ranges_past_the_address_space() {
+    let sample = re_mp4::Sample {
+        offset: u64::MAX - 4,
+        size: 10,
+        ..Default::default()
+    };
+    assert_eq!(sample.file_range(), u64::MAX - 4..u64::MAX);
+    let end = usize::try_from(u64::MAX).unwrap_or(usize::MAX);
+    assert_eq!(sample.byte_range().end, end);
+}
+
+#[test]
+fn load_track_data_rejects_external_data() {
+    let mut data = build_progressive(&test_tracks(), false);
+
+    with_external_video_data(&mut data);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    let result = load_track_data(&mp4, &mp4.tracks()[&1], &mut Cursor::new(&data));
+    assert!(matches!(
+        result,
+        Err(re_mp4::Error::ExternalDataReference(1))
+    ));
+}
+
+#[test]
+fn load_track_data_rejects_samples_beyond_the_file() {
+    let mut data = build_progressive(&test_tracks(), false);
+
+    // Claim that the first video sample is 2 GiB, which must fail without allocating that much.
+    let stsz = data
+        .windows(4)
+        .position(|window| window == b"stsz")
+        .unwrap();
+    data[stsz + 16..stsz + 20].copy_from_slice(&0x8000_0000_u32.to_be_bytes());
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks()[&1].samples[0].size, 0x8000_0000);
+
+    let result = load_track_data(&mp4, &mp4.tracks()[&1], &mut Cursor::new(&data));
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn load_all_track_data_in_parallel() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    let loaded = re_mp4::load_all_track_data(&mp4, || Ok(Cursor::new(&data))).unwrap();
+    assert_eq!(loaded.len(), tracks.len());
+    for test_track in &tracks {
+        assert_eq!(
+            loaded[&test_track.track_id],
+            test_track.samples().map(<[u8]>::to_vec).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn parsing_and_loading_are_traced() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    const SPANS: [&str; 3] = ["read_box", "build_trak", "load_track_data"];
+
+    /// Counts the spans of each of [`SPANS`], and the debug events.
+    #[derive(Clone, Default)]
+    struct Collector {
+        spans: Arc<[AtomicUsize; 3]>,
+        debug_events: Arc<AtomicUsize>,
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            if let Some(index) = SPANS
+                .iter()
+                .position(|&name| name == span.metadata().name())
+            {
+                self.spans[index].fetch_add(1, Ordering::Relaxed);
+            }
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            if *event.metadata().level() == Level::DEBUG {
+                self.debug_events.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    let collector = Collector::default();
+    let data = build_progressive(&test_tracks(), true);
+    tracing::subscriber::with_default(collector.clone(), || {
+        let mp4 = Mp4::read_bytes(&data).unwrap();
+        load_track_data(&mp4, &mp4.tracks()[&1], &mut Cursor::new(&data)).unwrap();
+    });
+
+    let counts = collector
+        .spans
+        .each_ref()
+        .map(|count| count.load(Ordering::Relaxed));
+    // `ftyp`, `free`, `moov` and `mdat`, two tracks and one of them loaded.
+    assert_eq!(counts, [4, 2, 1]);
+
+    // The sample entries of the synthetic tracks are of an unknown codec, and skipped.
+    assert!(collector.debug_events.load(Ordering::Relaxed) > 0);
+}
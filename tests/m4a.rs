@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use re_mp4::remux::{M4aCodec, M4aTags, M4aWriter};
+use re_mp4::{AudioObjectType, FourCC, Metadata as _, Mp4, StsdBoxContent, TrackKind};
+
+fn frames() -> Vec<Vec<u8>> {
+    (0..50_u8).map(|i| vec![i; 100 + usize::from(i)]).collect()
+}
+
+#[test]
+fn write_aac_m4a() {
+    // AAC LC, 44.1 kHz, stereo.
+    let mut writer = M4aWriter::new(
+        M4aCodec::Aac {
+            audio_specific_config: vec![0x12, 0x10],
+        },
+        44100,
+        2,
+    );
+    writer.set_tags(M4aTags {
+        title: Some("Episode 1".to_owned()),
+        artist: Some("Someone".to_owned()),
+        year: Some("2024".to_owned()),
+        track_number: Some((1, Some(10))),
+        ..Default::default()
+    });
+    for frame in frames() {
+        writer.add_sample(&frame, 1024).unwrap();
+    }
+    writer
+        .add_chapter(Duration::from_millis(500), "Second")
+        .unwrap();
+    writer.add_chapter(Duration::ZERO, "First").unwrap();
+    let mut data = Vec::new();
+    writer.finish(&mut data).unwrap();
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.ftyp.major_brand, FourCC::from(*b"M4A "));
+
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.kind, Some(TrackKind::Audio));
+    assert_eq!(track.duration, 50 * 1024);
+    let audio = track.audio_info(&mp4).unwrap();
+    assert_eq!((audio.sample_rate, audio.channel_count), (44100, 2));
+    assert_eq!(audio.profile, Some(AudioObjectType::AacLowComplexity));
+
+    for (sample, frame) in track.samples.iter().zip(frames()) {
+        assert_eq!(&data[sample.byte_range()], frame);
+    }
+
+    let metadata = mp4.metadata();
+    assert_eq!(metadata.title().as_deref(), Some("Episode 1"));
+    assert_eq!(metadata.year(), Some(2024));
+
+    let udta = mp4.box_tree()[1]
+        .children
+        .iter()
+        .find(|node| node.box_type == FourCC::from(*b"udta"))
+        .unwrap();
+    let types: Vec<FourCC> = udta.descendants().map(|node| node.box_type).collect();
+    for expected in [*b"\xa9ART", *b"trkn", *b"chpl"] {
+        assert!(types.contains(&FourCC::from(expected)));
+    }
+
+    // The chapters are sorted, with their starts in units of 100 ns.
+    let chpl = udta
+        .descendants()
+        .find(|node| node.box_type == FourCC::from(*b"chpl"))
+        .unwrap();
+    let contents = &data[chpl.data_range().start as usize..chpl.data_range().end as usize];
+    assert_eq!(contents[8], 2);
+    assert_eq!(&contents[9..17], 0_u64.to_be_bytes());
+    assert_eq!(&contents[18..23], b"First");
+    assert_eq!(&contents[23..31], 5_000_000_u64.to_be_bytes());
+}
+
+#[test]
+fn write_alac_m4a() {
+    let mut magic_cookie = vec![0; 24];
+    magic_cookie[5] = 24; // bit depth
+    let mut writer = M4aWriter::new(M4aCodec::Alac { magic_cookie }, 96000, 2);
+    for frame in frames() {
+        writer.add_sample(&frame, 4096).unwrap();
+    }
+    let mut data = Vec::new();
+    writer.finish(&mut data).unwrap();
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.timescale, 96000);
+    assert_eq!(
+        *track.sample_entry(),
+        StsdBoxContent::Unknown(FourCC::from(*b"alac"))
+    );
+    for (sample, frame) in track.samples.iter().zip(frames()) {
+        assert_eq!(&data[sample.byte_range()], frame);
+    }
+}
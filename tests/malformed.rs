@@ -0,0 +1,286 @@
+//! Damaged files must fail to parse with an error, never panic.
+//!
+//! A deterministic stand-in for the fuzz target in `fuzz/`, which runs the same check on
+//! random input.
+
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::io::Cursor;
+use std::sync::Once;
+use std::time::Duration;
+
+use re_mp4::{Limits, Mp4};
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+/// Fails allocations of more than 1 GiB, like the malloc limit of the fuzzer, so that sizes
+/// taken from the file unchecked abort the tests.
+struct LimitedAllocator;
+
+const ALLOCATION_LIMIT: usize = 1 << 30;
+
+#[expect(unsafe_code)]
+// SAFETY: allocations within the limit are forwarded to the system allocator unchanged, and
+// those beyond it fail as allowed by the contract of `GlobalAlloc`.
+unsafe impl GlobalAlloc for LimitedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > ALLOCATION_LIMIT {
+            return std::ptr::null_mut();
+        }
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::alloc`.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > ALLOCATION_LIMIT {
+            return std::ptr::null_mut();
+        }
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::realloc`.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: LimitedAllocator = LimitedAllocator;
+
+fn test_tracks() -> Vec<TestTrack> {
+    vec![
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 1000,
+            chunks: vec![vec![vec![1; 5], vec![2; 6]], vec![vec![3; 7]]],
+            sync_samples: Some(vec![1, 3]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![vec![vec![4; 3]; 3]],
+            sync_samples: None,
+        },
+    ]
+}
+
+thread_local! {
+    /// Whether this thread runs [`panics`], and the panic it caught if so.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    static PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Parses `data`, and returns where and why it panicked if it did.
+#[expect(
+    clippy::disallowed_methods,
+    reason = "tests unwind, and this reports all failing inputs at once"
+)]
+fn panics(data: &[u8]) -> Option<String> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if CATCHING.get() {
+                PANIC.set(Some(info.to_string()));
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+
+    let limits = Limits::UNLIMITED
+        .with_max_tracks(16)
+        .with_max_total_samples(1 << 16)
+        .with_max_box_depth(64);
+    CATCHING.set(true);
+    drop(std::panic::catch_unwind(|| {
+        Mp4::read_with_limits(Cursor::new(data), data.len() as u64, limits)
+    }));
+    CATCHING.set(false);
+    PANIC.take()
+}
+
+/// Truncates `data` at every position, and overwrites every byte and every 32-bit field with
+/// values that tend to break sizes, counts and indices.
+fn check_mutations(data: &[u8]) {
+    let mut failures = Vec::new();
+    let mut check = |what: String, data: &[u8]| {
+        if let Some(panic) = panics(data) {
+            failures.push(format!("{what}: {panic}"));
+        }
+    };
+
+    for len in 0..data.len() {
+        check(format!("truncated to {len}"), &data[..len]);
+    }
+    for position in 0..data.len() {
+        for value in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+            let mut mutated = data.to_vec();
+            mutated[position] = value;
+            check(format!("byte {position} = {value:#x}"), &mutated);
+        }
+    }
+    for position in 0..data.len().saturating_sub(3) {
+        for value in [0, 1, 2, 8, 0x7fff_ffff, 0xffff_ffff] {
+            let mut mutated = data.to_vec();
+            mutated[position..position + 4].copy_from_slice(&u32::to_be_bytes(value));
+            check(format!("u32 at {position} = {value:#x}"), &mutated);
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn progressive() {
+    check_mutations(&build_progressive(&test_tracks(), true));
+    check_mutations(&build_progressive(&test_tracks(), false));
+}
+
+#[test]
+fn fragmented_file() {
+    check_mutations(&synthetic::fragmented(&test_tracks(), Duration::ZERO));
+}
+
+fn first_track_stbl(mp4: &mut Mp4) -> &mut re_mp4::StblBox {
+    &mut mp4.moov.traks[0].mdia.minf.stbl
+}
+
+#[test]
+fn samples_past_the_shortest_table_are_dropped() {
+    let data = build_progressive(&test_tracks(), true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks()[&1].samples.len(), 3);
+
+    first_track_stbl(&mut mp4).stts.entries[0].sample_count = 2;
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(mp4.tracks()[&1].samples.len(), 2);
+
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    first_track_stbl(&mut mp4)
+        .stco
+        .as_mut()
+        .unwrap()
+        .entries
+        .pop();
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(mp4.tracks()[&1].samples.len(), 2);
+
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    first_track_stbl(&mut mp4).stsc.entries.clear();
+    mp4.rebuild_tracks().unwrap();
+    assert!(mp4.tracks()[&1].samples.is_empty());
+}
+
+#[test]
+fn chunks_without_samples_are_skipped() {
+    let data = build_progressive(&test_tracks(), true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    let ranges = |mp4: &Mp4| {
+        mp4.tracks()[&1]
+            .samples
+            .iter()
+            .map(|sample| (sample.offset, sample.size))
+            .collect::<Vec<_>>()
+    };
+    let expected = ranges(&mp4);
+
+    // An empty chunk between the two chunks of samples.
+    let stbl = first_track_stbl(&mut mp4);
+    let stco = stbl.stco.as_mut().unwrap();
+    stco.entries.insert(1, 0);
+    let mut empty_run = stbl.stsc.entries[1].clone();
+    empty_run.samples_per_chunk = 0;
+    stbl.stsc.entries.insert(1, empty_run);
+    stbl.stsc.entries[2].first_chunk = 3;
+    mp4.rebuild_tracks().unwrap();
+    assert_eq!(ranges(&mp4), expected);
+}
+
+#[test]
+fn decreasing_stsc_first_chunks_are_an_error() {
+    let data = build_progressive(&test_tracks(), true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    first_track_stbl(&mut mp4).stsc.entries[1].first_chunk = 1;
+    assert!(matches!(
+        mp4.rebuild_tracks(),
+        Err(re_mp4::Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn errors_name_the_box_that_failed_to_parse() {
+    let mut data = build_progressive(&test_tracks(), true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let stsz = mp4
+        .box_tree()
+        .iter()
+        .flat_map(|node| node.descendants())
+        .find(|node| &node.box_type.value == b"stsz")
+        .unwrap()
+        .clone();
+
+    // A sample count that doesn't fit in the box.
+    let sample_count = stsz.offset as usize + 16;
+    data[sample_count..sample_count + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+    let err = Mp4::read_bytes(&data).unwrap_err();
+    let re_mp4::Error::InBox { path, offset, .. } = &err else {
+        panic!("unexpected error {err}");
+    };
+    let path: Vec<_> = path.iter().map(ToString::to_string).collect();
+    assert_eq!(path, ["moov", "trak", "mdia", "minf", "stbl", "stsz"]);
+    assert!(stsz.data_range().contains(offset));
+    assert!(matches!(
+        err.without_context(),
+        re_mp4::Error::InvalidData(_)
+    ));
+    assert!(std::error::Error::source(&err).is_some());
+    assert!(err.to_string().contains("moov/trak/mdia/minf/stbl/stsz"));
+}
+
+/// Gives the box in `bytes` the size `size`, regardless of how much it holds.
+fn with_size(mut bytes: Vec<u8>, size: u32) -> Vec<u8> {
+    bytes[..4].copy_from_slice(&size.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn box_sizes_beyond_the_file_fail_without_allocating() {
+    // A tiny file with an `hdlr` whose name claims almost 4 GiB.
+    let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"mdir", &[0; 12], b"name"]);
+    let meta = mp4_box(b"meta", &[&[0; 4], &with_size(hdlr, 0xf000_0000)]);
+    let moov = mp4_box(b"moov", &[&mp4_box(b"udta", &[&meta])]);
+    let data = [mp4_box(b"ftyp", &[b"isom", &[0; 4]]), moov].concat();
+    assert!(Mp4::read_bytes(&data).is_err());
+}
+
+#[test]
+fn trun_sample_counts_beyond_the_file_fail_without_allocating() {
+    let mut data = synthetic::fragmented(&synthetic::test_tracks(), Duration::from_secs(1));
+    assert_eq!(data.len(), 1260);
+
+    let trun = Mp4::read_bytes(&data)
+        .unwrap()
+        .box_tree()
+        .iter()
+        .flat_map(|node| node.descendants())
+        .find(|node| &node.box_type.value == b"trun")
+        .unwrap()
+        .offset as usize;
+
+    // Only a data offset, so that every sample has the size and duration of `trex`.
+    data[trun + 8..trun + 12].copy_from_slice(&1_u32.to_be_bytes());
+    for sample_count in [50_000_000_u32, 4_000_000_000] {
+        data[trun + 12..trun + 16].copy_from_slice(&sample_count.to_be_bytes());
+        assert!(matches!(
+            Mp4::read_bytes(&data),
+            Err(re_mp4::Error::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{DataSources, Error, Limits, Mp4};
+use synthetic::{build_progressive, TestTrack};
+
+fn test_track() -> TestTrack {
+    TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 1000,
+        chunks: vec![vec![vec![1; 10], vec![2; 20]], vec![vec![3; 30]]],
+        sync_samples: None,
+    }
+}
+
+/// The file up to the start of its `mdat`, which comes last.
+fn without_mdat(data: &[u8]) -> &[u8] {
+    let mdat = data
+        .windows(4)
+        .rposition(|window| window == b"mdat")
+        .expect("Missing mdat");
+    &data[..mdat - 4]
+}
+
+#[test]
+fn missing_mdat() {
+    let data = build_progressive(&[test_track()], true);
+    let moov_only = without_mdat(&data);
+
+    let Err(Error::MediaDataMissing { expected_bytes }) = Mp4::read_bytes(moov_only) else {
+        panic!("expected missing media data");
+    };
+    assert_eq!(expected_bytes, data.len() as u64);
+
+    // The structure can still be read, and the samples loaded from the complete file.
+    let mp4 = Mp4::read_with_missing_data(
+        Cursor::new(moov_only),
+        moov_only.len() as u64,
+        Limits::UNLIMITED,
+    )
+    .unwrap();
+    let track = &mp4.tracks()[&1];
+    let mut sources = DataSources::new(Cursor::new(&data));
+    let samples: Vec<Vec<u8>> = track
+        .samples
+        .iter()
+        .map(|sample| sources.read_sample(&mp4, track, sample).unwrap())
+        .collect();
+    assert_eq!(samples, [vec![1; 10], vec![2; 20], vec![3; 30]]);
+}
+
+#[test]
+fn partial_mdat_is_not_missing() {
+    // A file with only some of its samples is cut off, not missing its media data.
+    let data = build_progressive(&[test_track()], true);
+    let mut truncated = data[..data.len() - 30].to_vec();
+    let mdat = without_mdat(&data).len();
+    let mdat_size = (truncated.len() - mdat) as u32;
+    truncated[mdat..mdat + 4].copy_from_slice(&mdat_size.to_be_bytes());
+
+    let mp4 = Mp4::read_bytes(&truncated).unwrap();
+    assert_eq!(mp4.tracks()[&1].samples.len(), 3);
+}
+
+#[test]
+fn stream_without_mdat() {
+    let data = build_progressive(&[test_track()], true);
+    let result = Mp4::read_stream(without_mdat(&data));
+    assert!(
+        matches!(result, Err(Error::MediaDataMissing { .. })),
+        "{result:?}"
+    );
+
+    // Without the size of `mdat`, the samples can't be told to be missing.
+    let mut unsized_mdat = without_mdat(&data).to_vec();
+    unsized_mdat.extend([0, 0, 0, 0]);
+    unsized_mdat.extend(b"mdat");
+    assert!(Mp4::read_stream(unsized_mdat.as_slice()).is_ok());
+}
@@ -0,0 +1,148 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use re_mp4::{FourCC, Mp4};
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+/// A progressive file of one track with a sync sample every three samples, and its init and
+/// media segments.
+fn segments() -> (Vec<u8>, Mp4, Vec<u8>, Vec<Vec<u8>>) {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![(0..9).map(|index| vec![index; 3]).collect()],
+            sync_samples: Some(vec![1, 4, 7]),
+        }],
+        true,
+    );
+    let mp4 = Mp4::read_bytes(&data).expect("Failed parsing");
+    let fragments =
+        re_mp4::remux::fragment(&mp4, &mut Cursor::new(&data), Duration::from_millis(100))
+            .expect("Failed fragmenting");
+    let media_segments = fragments
+        .media_segments
+        .into_iter()
+        .map(|segment| {
+            [
+                mp4_box(b"styp", &[b"msdh", &[0; 4], b"msdh", b"dash"]),
+                mp4_box(b"sidx", &[&[0; 24]]),
+                segment.data,
+            ]
+            .concat()
+        })
+        .collect();
+    (data, mp4, fragments.init_segment, media_segments)
+}
+
+#[test]
+fn media_segment_with_init_segment() {
+    let (data, mp4, init_segment, media_segments) = segments();
+    let init = Mp4::read_bytes(&init_segment).unwrap();
+    assert_eq!(media_segments.len(), 3);
+
+    let segment = &media_segments[1];
+    assert!(matches!(
+        Mp4::read_bytes(segment),
+        Err(re_mp4::Error::BoxNotFound(_))
+    ));
+    let parsed = init
+        .read_media_segment(Cursor::new(segment), segment.len() as u64)
+        .unwrap();
+    assert_eq!(parsed.ftyp, init.ftyp);
+    assert_eq!(parsed.styps.len(), 1);
+    assert_eq!(parsed.styps[0].major_brand, FourCC::from(*b"msdh"));
+    assert_eq!(
+        parsed.styps[0].compatible_brands,
+        [FourCC::from(*b"msdh"), FourCC::from(*b"dash")]
+    );
+    assert_eq!(
+        parsed.top_level_extras()[0].box_type,
+        FourCC::from(*b"sidx")
+    );
+    assert_eq!(parsed.moofs.len(), 1);
+
+    let samples = &parsed.tracks()[&1].samples;
+    let originals = &mp4.tracks()[&1].samples[3..6];
+    assert_eq!(samples.len(), 3);
+    for (sample, original) in samples.iter().zip(originals) {
+        assert_eq!(
+            segment[sample.byte_range()],
+            data[original.byte_range()],
+            "sample {}",
+            original.id
+        );
+        assert_eq!(sample.decode_timestamp, original.decode_timestamp);
+        assert_eq!(sample.is_sync, original.is_sync);
+    }
+}
+
+#[test]
+fn media_segment_with_moov_is_an_error() {
+    let (data, mp4, _, _) = segments();
+    assert!(matches!(
+        mp4.read_media_segment(Cursor::new(&data), data.len() as u64),
+        Err(re_mp4::Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn emsg_events_of_media_segment() {
+    let (_data, _mp4, init_segment, media_segments) = segments();
+    let init = Mp4::read_bytes(&init_segment).unwrap();
+
+    // An event 30 ms into the segment, whose duration is unknown, and one at 10 s.
+    let relative = mp4_box(
+        b"emsg",
+        &[
+            &[0; 4],
+            b"urn:example:relative\0",
+            b"1\0",
+            &1000_u32.to_be_bytes(),
+            &30_u32.to_be_bytes(),
+            &u32::MAX.to_be_bytes(),
+            &7_u32.to_be_bytes(),
+            b"payload",
+        ],
+    );
+    let absolute = mp4_box(
+        b"emsg",
+        &[
+            &[1, 0, 0, 0],
+            &90_000_u32.to_be_bytes(),
+            &900_000_u64.to_be_bytes(),
+            &45_000_u32.to_be_bytes(),
+            &8_u32.to_be_bytes(),
+            b"urn:example:absolute\0",
+            b"\0",
+        ],
+    );
+    let segment = [relative.as_slice(), &absolute, &media_segments[1][..]].concat();
+    let parsed = init
+        .read_media_segment(Cursor::new(&segment), segment.len() as u64)
+        .unwrap();
+
+    // The segment starts at 100 ms.
+    let events: Vec<_> = parsed.events(1000).collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].scheme_id_uri(), "urn:example:relative");
+    assert_eq!(events[0].message_data(), b"payload");
+    assert_eq!(events[0].moof_index, Some(0));
+    assert_eq!(events[0].presentation_time, Some(130));
+    assert_eq!(events[0].duration, None);
+    assert_eq!(events[1].emsg.id, 8);
+    assert_eq!(events[1].presentation_time, Some(10_000));
+    assert_eq!(events[1].duration, Some(500));
+
+    // Relative events after the last fragment don't belong to any segment.
+    let segment = [&media_segments[1][..], &relative].concat();
+    let parsed = init
+        .read_media_segment(Cursor::new(&segment), segment.len() as u64)
+        .unwrap();
+    let event = parsed.events(1000).next().unwrap();
+    assert_eq!((event.moof_index, event.presentation_time), (None, None));
+}
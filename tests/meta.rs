@@ -0,0 +1,122 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{BoxHeader, BoxType, Error, MetaBox, Metadata as _, Mp4, ReadBox as _};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+fn test_file() -> Vec<u8> {
+    build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        true,
+    )
+}
+
+fn ilst() -> Vec<u8> {
+    let data = mp4_box(b"data", &[&[0, 0, 0, 1, 0, 0, 0, 0], b"title"]);
+    mp4_box(b"ilst", &[&mp4_box(b"\xa9nam", &[&data])])
+}
+
+fn read_meta(contents: &[&[u8]]) -> re_mp4::Result<MetaBox> {
+    let meta = mp4_box(b"meta", contents);
+    let mut reader = Cursor::new(&meta);
+    let header = BoxHeader::read(&mut reader).expect("Failed reading header");
+    MetaBox::read_box(&mut reader, header.size)
+}
+
+#[test]
+fn version_less_meta_in_trak() {
+    // As written by `QuickTime`, without the version and flags.
+    let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"mdir", &[0; 12], b"\0"]);
+    let mut data = test_file();
+    append_child(
+        &mut data,
+        &[b"moov", b"trak"],
+        &mp4_box(b"meta", &[&hdlr, &ilst()]),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let meta = mp4.moov.traks[0].meta.as_ref().unwrap();
+    let title = meta.ilst().and_then(|ilst| ilst.title());
+    assert_eq!(title.as_deref(), Some("title"));
+
+    // The box tree finds the children all the same.
+    let moov = &mp4.box_tree()[2];
+    let meta = moov.children[1].children.last().unwrap();
+    let children: Vec<_> = meta.children.iter().map(|node| node.box_type).collect();
+    assert_eq!(children, [BoxType::HdlrBox.into(), BoxType::IlstBox.into()]);
+}
+
+#[test]
+fn meta_without_hdlr() {
+    let keys = mp4_box(b"keys", &[&[0; 8]]);
+    for contents in [[&[0; 4][..], &keys, &ilst()], [&[][..], &keys, &ilst()]] {
+        let meta = read_meta(&contents).unwrap();
+        let MetaBox::Unknown { hdlr, data } = &meta else {
+            panic!("expected an unknown meta box, got {meta:?}");
+        };
+        assert_eq!(*hdlr, None);
+        let names: Vec<_> = data.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                BoxType::from(u32::from_be_bytes(*b"keys")),
+                BoxType::IlstBox
+            ]
+        );
+        assert_eq!(meta.child_data(BoxType::IlstBox), Some(&ilst()[8..]));
+    }
+}
+
+#[test]
+fn meta_with_unsupported_version() {
+    let result = read_meta(&[&[1, 0, 0, 0], &[0, 0, 0, 8], &ilst()]);
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedBoxVersion(BoxType::MetaBox, 1))
+    ));
+}
+
+#[test]
+fn movie_metadata_merges_udta_and_moov_item_lists() {
+    let mp4 = Mp4::read_bytes(&test_file()).unwrap();
+    let metadata = mp4.metadata();
+    assert_eq!(metadata.title(), None);
+    assert_eq!(metadata.year(), None);
+
+    let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"mdir", &[0; 12], b"\0"]);
+    let meta = |items: &[&[u8]]| mp4_box(b"meta", &[&[0; 4], &hdlr, &mp4_box(b"ilst", items)]);
+    let text_item = |name: &[u8; 4], text: &str| {
+        let data = mp4_box(b"data", &[&[0, 0, 0, 1, 0, 0, 0, 0], text.as_bytes()]);
+        mp4_box(name, &[&data])
+    };
+    let mut data = test_file();
+    append_child(
+        &mut data,
+        &[b"moov"],
+        &mp4_box(b"udta", &[&meta(&[&text_item(b"\xa9nam", "udta title")])]),
+    );
+    append_child(
+        &mut data,
+        &[b"moov"],
+        &meta(&[
+            &text_item(b"\xa9nam", "moov title"),
+            &text_item(b"\xa9day", "2020"),
+        ]),
+    );
+
+    // `udta` takes precedence, and `moov.meta` fills in the items it does not have.
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let metadata = mp4.metadata();
+    assert_eq!(metadata.title().as_deref(), Some("udta title"));
+    assert_eq!(metadata.year(), Some(2020));
+    assert_eq!(metadata.summary(), None);
+    assert_eq!(metadata.poster(), None);
+}
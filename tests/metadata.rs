@@ -0,0 +1,210 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{FourCC, GminBox, Mp4, StsdBoxContent, TrackKind};
+use synthetic::{append_child, build_progressive, mp4_box, read_stsd, TestTrack};
+
+#[test]
+fn mett_sample_entry() {
+    let entry = mp4_box(
+        b"mett",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            b"\0application/json\0",
+            &mp4_box(b"btrt", &[&[0; 12]]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Metadata));
+
+    let StsdBoxContent::Mett(mett) = stsd.contents else {
+        panic!("expected mett, got {:?}", stsd.contents);
+    };
+    assert_eq!(mett.data_reference_index, 1);
+    assert_eq!(mett.content_encoding, "");
+    assert_eq!(mett.mime_format, "application/json");
+    assert!(mett.btrt.is_some());
+}
+
+#[test]
+fn urim_sample_entry() {
+    let entry = mp4_box(
+        b"urim",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &mp4_box(b"uri ", &[&[0; 4], b"urn:example:gps\0"]),
+            &mp4_box(b"uriI", &[&[0; 4], &[1, 2, 3]]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Metadata));
+
+    let StsdBoxContent::Urim(urim) = stsd.contents else {
+        panic!("expected urim, got {:?}", stsd.contents);
+    };
+    assert_eq!(urim.uri, "urn:example:gps");
+    assert_eq!(urim.uri_init, Some(vec![1, 2, 3]));
+    assert_eq!(urim.btrt, None);
+}
+
+#[test]
+fn mebx_sample_entry() {
+    let key = |local_id: u32, name: &[u8], well_known_type: u32| {
+        mp4_box(
+            &local_id.to_be_bytes(),
+            &[
+                &mp4_box(b"keyd", &[b"mdta", name]),
+                &mp4_box(b"dtyp", &[&[0; 4], &well_known_type.to_be_bytes()]),
+            ],
+        )
+    };
+    let entry = mp4_box(
+        b"mebx",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &mp4_box(
+                b"keys",
+                &[
+                    &key(1, b"com.apple.quicktime.still-image-time", 66),
+                    &key(2, b"com.apple.quicktime.live-photo-info", 1),
+                ],
+            ),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Metadata));
+
+    let StsdBoxContent::Mebx(mebx) = &stsd.contents else {
+        panic!("expected mebx, got {:?}", stsd.contents);
+    };
+    assert_eq!(mebx.data_reference_index, 1);
+    let keys: Vec<_> = mebx
+        .keys
+        .iter()
+        .map(|key| (key.local_id, key.name(), key.well_known_type))
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            (1, Some("com.apple.quicktime.still-image-time"), Some(66)),
+            (2, Some("com.apple.quicktime.live-photo-info"), Some(1)),
+        ]
+    );
+    assert_eq!(mebx.keys[0].namespace, FourCC::from(*b"mdta"));
+
+    let sample = [
+        mp4_box(&2u32.to_be_bytes(), &[b"info"]),
+        mp4_box(&1u32.to_be_bytes(), &[&[0xff]]),
+    ]
+    .concat();
+    let items: Vec<_> = mebx
+        .items(&sample)
+        .unwrap()
+        .into_iter()
+        .map(|(key, value)| (key.local_id, value))
+        .collect();
+    assert_eq!(items, [(2, &b"info"[..]), (1, &[0xff][..])]);
+    assert!(mebx.items(&mp4_box(&3u32.to_be_bytes(), &[])).is_err());
+    assert!(mebx.items(&sample[..sample.len() - 1]).is_err());
+}
+
+#[test]
+fn metadata_samples_with_timestamps() {
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![0; 10]; 3]],
+            sync_samples: None,
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"meta",
+            timescale: 10,
+            chunks: vec![
+                vec![b"fix 1".to_vec(), b"fix 2".to_vec()],
+                vec![b"fix 3".to_vec()],
+            ],
+            sync_samples: None,
+        },
+    ];
+    let data = build_progressive(&tracks, true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    // The sample entry is unknown, but the handler says what the track holds.
+    assert_eq!(mp4.tracks()[&1].kind, None);
+    let track = &mp4.tracks()[&2];
+    assert_eq!(track.kind, Some(TrackKind::Metadata));
+
+    let samples: Vec<_> = track
+        .samples_with_data(&data)
+        .map(|(sample, bytes)| (sample.composition_timestamp, bytes))
+        .collect();
+    assert_eq!(
+        samples,
+        [(0, &b"fix 1"[..]), (1, &b"fix 2"[..]), (2, &b"fix 3"[..])]
+    );
+
+    // Samples beyond the end of the data are left out.
+    let end = track.samples[2].offset as usize;
+    assert_eq!(track.samples_with_data(&data[..end]).count(), 2);
+}
+
+#[test]
+fn gopro_telemetry_track() {
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"meta",
+            timescale: 1000,
+            chunks: vec![vec![b"DEVC".to_vec(), b"DEVC".to_vec()]],
+            sync_samples: None,
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![0; 10]; 2]],
+            sync_samples: None,
+        },
+    ];
+    let mut data = build_progressive(&tracks, false);
+
+    // The synthetic sample entry has the same size and layout as `gpmd`.
+    let entry = data
+        .windows(4)
+        .position(|window| window == b"test")
+        .expect("sample entry not found");
+    data[entry..entry + 4].copy_from_slice(b"gpmd");
+    let gmin = mp4_box(
+        b"gmin",
+        &[&[0; 4], &[0, 0x40, 0x80, 0, 0x80, 0, 0x80, 0, 0, 0, 0, 0]],
+    );
+    append_child(
+        &mut data,
+        &[b"moov", b"trak", b"mdia", b"minf"],
+        &mp4_box(b"gmhd", &[&gmin]),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.kind, Some(TrackKind::Metadata));
+
+    let minf = &track.trak(&mp4).unwrap().mdia.minf;
+    assert!(matches!(minf.stbl.stsd.contents, StsdBoxContent::Gpmd(_)));
+    assert_eq!(
+        minf.gmhd.as_ref().and_then(|gmhd| gmhd.gmin.clone()),
+        Some(GminBox {
+            graphics_mode: 0x40,
+            opcolor: [0x8000; 3],
+            ..Default::default()
+        })
+    );
+
+    let samples: Vec<_> = track
+        .samples_with_data(&data)
+        .map(|(sample, bytes)| (sample.composition_timestamp, bytes))
+        .collect();
+    assert_eq!(samples, [(0, &b"DEVC"[..]), (1, &b"DEVC"[..])]);
+}
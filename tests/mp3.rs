@@ -0,0 +1,66 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Codec, FourCC, Mp4, StsdBoxContent, TrackKind};
+use synthetic::{audio_sample_entry, mp4_box, read_stsd, sound_track};
+
+/// An `esds` box with the given object type and no decoder specific info.
+fn esds(object_type_indication: u8) -> Vec<u8> {
+    mp4_box(
+        b"esds",
+        &[
+            &[0; 4],
+            &[3, 18, 0, 1, 0],
+            &[4, 13, object_type_indication, 0x15, 0, 0, 0],
+            &320_000_u32.to_be_bytes(),
+            &192_000_u32.to_be_bytes(),
+            &[6, 1, 2],
+        ],
+    )
+}
+
+/// A file with a single sound track that has the sample `entry`.
+fn mp4_with_entry(entry: &[u8]) -> Mp4 {
+    sound_track(read_stsd(entry))
+}
+
+#[test]
+fn mp3_sample_entry() {
+    let mp4 = mp4_with_entry(&audio_sample_entry(b".mp3", 44_100, &[]));
+    let stsd = &mp4.moov.traks[0].mdia.minf.stbl.stsd;
+    let StsdBoxContent::Mp3(mp3) = &stsd.contents else {
+        panic!("expected .mp3, got {:?}", stsd.contents);
+    };
+    assert_eq!(mp3.sample_rate(), 44_100);
+    assert_eq!(stsd.kind(), Some(TrackKind::Audio));
+    assert_eq!(stsd.contents.fourcc(), FourCC::from(*b".mp3"));
+
+    let track = &mp4.tracks()[&1];
+    assert_eq!(track.kind, Some(TrackKind::Audio));
+    assert_eq!(track.codec(), Codec::Mp3);
+    assert_eq!(track.codec_string().as_deref(), Some("mp3"));
+    let info = track.audio_info(&mp4).unwrap();
+    assert_eq!((info.sample_rate, info.channel_count), (44_100, 2));
+}
+
+#[test]
+fn mp4a_with_mp3_object_type() {
+    for object_type_indication in [0x69, 0x6b] {
+        let mp4 = mp4_with_entry(&audio_sample_entry(
+            b"mp4a",
+            44_100,
+            &esds(object_type_indication),
+        ));
+        let track = &mp4.tracks()[&1];
+        assert_eq!(track.kind, Some(TrackKind::Audio));
+        assert_eq!(track.codec(), Codec::Mp3);
+        assert_eq!(track.codec_string().as_deref(), Some("mp3"));
+        let info = track.audio_info(&mp4).unwrap();
+        assert_eq!((info.sample_rate, info.channel_count), (44_100, 2));
+        assert_eq!(info.bitrate, Some(192_000));
+    }
+
+    // Other object types of `mp4a` have no codec string yet.
+    let mp4 = mp4_with_entry(&audio_sample_entry(b"mp4a", 44_100, &esds(0x40)));
+    assert_eq!(mp4.tracks()[&1].codec_string(), None);
+}
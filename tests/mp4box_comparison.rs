@@ -7,6 +7,8 @@ mod common;
 
 use std::path::Path;
 
+use re_mp4::{StsdBoxContent, WriteBox as _};
+
 fn assert_snapshot(snapshot_path: &Path, contents: &[u8]) {
     // if file doesn't exist, create it
     // otherwise, compare the contents and report failure if they don't match
@@ -55,6 +57,15 @@ fn compare_video_snapshot_with_mp4box_output(video_path: &Path) {
 
     let (video, data) = re_mp4::Mp4::read_file(base_path.join(video_path)).unwrap();
 
+    // mp4box only looks at the first video track.
+    if let Some(track) = video
+        .tracks()
+        .values()
+        .find(|track| track.kind == Some(re_mp4::TrackKind::Video))
+    {
+        compare_hevc_config_with_mp4box_output(video_path, track);
+    }
+
     for (id, track) in video.tracks() {
         if track.kind == Some(re_mp4::TrackKind::Video) {
             assert_snapshot(
@@ -67,22 +78,52 @@ fn compare_video_snapshot_with_mp4box_output(video_path: &Path) {
             );
             assert_snapshot(
                 &base_path.join(format!("{video_path_str}.track_{id}.json")),
-                format!(
-                    r#"{{ "codec": {:?}, "width": {}, "height": {}, "num_samples": {}, "description": {:?} }}"#,
-                    track
-                        .codec_string(&video)
-                        .unwrap_or_else(|| "unknown".to_owned()),
-                    track.width,
-                    track.height,
-                    track.samples.len(),
-                    track.raw_codec_config(&video),
-                )
-                .as_bytes(),
+                re_mp4::debug::TrackSnapshot::new(track, &data)
+                    .unwrap()
+                    .to_json()
+                    .as_bytes(),
             );
         }
     }
 }
 
+/// Compares the codec string and decoder configuration of HEVC tracks with what mp4box passes
+/// to `VideoDecoder.configure`, which browsers also expect in `MediaSource.isTypeSupported`.
+fn compare_hevc_config_with_mp4box_output(video_path: &Path, track: &re_mp4::Track) {
+    let (StsdBoxContent::Hvc1(hevc)
+    | StsdBoxContent::Hev1(hevc)
+    | StsdBoxContent::Dvh1(hevc)
+    | StsdBoxContent::Dvhe(hevc)) = track.sample_entry()
+    else {
+        return;
+    };
+    let codec = std::fs::read_to_string(video_path.with_extension("codec")).unwrap();
+    let description = std::fs::read(video_path.with_extension("description")).unwrap();
+
+    let codec_string = track.codec_string().unwrap();
+    if matches!(
+        track.sample_entry(),
+        StsdBoxContent::Dvh1(_) | StsdBoxContent::Dvhe(_)
+    ) {
+        // mp4box has no codec strings for Dolby Vision, only the sample entry type.
+        assert!(
+            codec_string.starts_with(&format!("{codec}.")),
+            "{codec_string} is not a {codec} codec string"
+        );
+    } else {
+        // Browsers compare the hexadecimal parts regardless of case.
+        assert!(
+            codec_string.eq_ignore_ascii_case(&codec),
+            "codec string {codec_string}, mp4box has {codec}"
+        );
+    }
+
+    assert_eq!(track.raw_codec_config().unwrap(), description);
+    let mut written = Vec::new();
+    hevc.hvcc.contents.write_box(&mut written).unwrap();
+    assert_eq!(written[8..], description);
+}
+
 #[test]
 fn compare_video_snapshot_with_mp4box_output_bigbuckbunny() {
     // List all mp4 files in the bigbuckbunny directory.
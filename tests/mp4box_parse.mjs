@@ -33,7 +33,14 @@ function unboxVideo(file) {
     let description = null;
     const trak = mp4.getTrackById(track.id);
     for (const entry of trak.mdia.minf.stbl.stsd.entries) {
-      const box = entry.avcC || entry.hvcC || entry.vpcC || entry.av1C;
+      if (entry.hvcC) {
+        // The `hvcC` writer of mp4box.js drops the profile byte and the reserved bits, so use
+        // the bytes of the file instead, which are what players get as the description.
+        const box = entry.hvcC;
+        description = new Uint8Array(file, box.start + box.hdr_size, box.size - box.hdr_size);
+        break;
+      }
+      const box = entry.avcC || entry.vpcC || entry.av1C;
       if (box) {
         const stream = new MP4Box.DataStream(undefined, 0, MP4Box.DataStream.BIG_ENDIAN);
         box.write(stream);
@@ -129,6 +136,8 @@ const video = unboxVideo(fs.readFileSync(file).buffer);
 console.log(video.videoDecoderConfig);
 
 fs.writeFileSync(file.replace(".mp4", ".bin"), new Uint8Array(video.data));
+fs.writeFileSync(file.replace(".mp4", ".codec"), video.videoDecoderConfig.codec);
+fs.writeFileSync(file.replace(".mp4", ".description"), video.videoDecoderConfig.description);
 
 let timeOffset = -1;
 let out = "[";
@@ -17,7 +17,7 @@ fn multitrack_video_with_subtitles_and_sound() {
         let track = video.tracks().get(&1).unwrap();
         let data = common::get_sample_data(&data, track);
         assert_eq!(track.kind, Some(re_mp4::TrackKind::Video));
-        assert_eq!(track.codec_string(&video), Some("avc1.640028".to_owned()));
+        assert_eq!(track.codec_string(), Some("avc1.640028".to_owned()));
         assert_eq!(track.track_id, 1);
         assert_eq!(track.width, 600);
         assert_eq!(track.height, 600);
@@ -30,7 +30,7 @@ fn multitrack_video_with_subtitles_and_sound() {
         let track = video.tracks().get(&2).unwrap();
         let data = common::get_sample_data(&data, track);
         assert_eq!(track.kind, Some(re_mp4::TrackKind::Audio));
-        assert_eq!(track.codec_string(&video), None);
+        assert_eq!(track.codec_string(), None);
         assert_eq!(track.track_id, 2);
         assert_eq!(track.width, 0);
         assert_eq!(track.height, 0);
@@ -43,7 +43,7 @@ fn multitrack_video_with_subtitles_and_sound() {
         let track = video.tracks().get(&3).unwrap();
         let data = common::get_sample_data(&data, track);
         assert_eq!(track.kind, Some(re_mp4::TrackKind::Subtitle));
-        assert_eq!(track.codec_string(&video), None);
+        assert_eq!(track.codec_string(), None);
         assert_eq!(track.track_id, 3);
         assert_eq!(track.width, 0);
         assert_eq!(track.height, 0);
@@ -0,0 +1,115 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{CttsBox, CttsEntry, Mp4, StblBox, StscEntry, SttsEntry};
+use synthetic::{build_progressive, TestTrack};
+
+fn stts(runs: &[(u32, u32)]) -> Vec<SttsEntry> {
+    runs.iter()
+        .map(|&(sample_count, sample_delta)| SttsEntry {
+            sample_count,
+            sample_delta,
+        })
+        .collect()
+}
+
+fn stsc(runs: &[(u32, u32, u32)]) -> Vec<StscEntry> {
+    runs.iter()
+        .map(
+            |&(first_chunk, samples_per_chunk, first_sample)| StscEntry {
+                first_chunk,
+                samples_per_chunk,
+                sample_description_index: 1,
+                first_sample,
+            },
+        )
+        .collect()
+}
+
+#[test]
+fn merges_adjacent_runs() {
+    let mut stbl = StblBox::default();
+    stbl.stts.entries = stts(&[(1, 10), (2, 10), (0, 20), (1, 10), (1, 20), (u32::MAX, 20)]);
+    stbl.ctts = Some(CttsBox {
+        entries: vec![
+            CttsEntry {
+                sample_count: 2,
+                sample_offset: -5,
+            },
+            CttsEntry {
+                sample_count: 3,
+                sample_offset: -5,
+            },
+        ],
+        ..Default::default()
+    });
+    // Out of order, with redundant runs and stale first samples.
+    stbl.stsc.entries = stsc(&[(4, 1, 0), (1, 2, 0), (3, 2, 0), (2, 2, 0), (6, 1, 0)]);
+
+    stbl.normalize().unwrap();
+
+    // Runs are not merged past the largest count.
+    assert_eq!(stbl.stts.entries, stts(&[(4, 10), (1, 20), (u32::MAX, 20)]));
+    assert_eq!(
+        stbl.ctts.unwrap().entries,
+        [CttsEntry {
+            sample_count: 5,
+            sample_offset: -5,
+        }]
+    );
+    assert_eq!(stbl.stsc.entries, stsc(&[(1, 2, 1), (4, 1, 7)]));
+}
+
+#[test]
+fn rejects_ambiguous_stsc() {
+    let mut stbl = StblBox::default();
+    stbl.stsc.entries = stsc(&[(1, 2, 1), (3, 1, 5), (3, 2, 6)]);
+    assert!(stbl.normalize().is_err());
+
+    stbl.stsc.entries = stsc(&[(2, 2, 1)]);
+    assert!(stbl.normalize().is_err());
+}
+
+#[test]
+fn normalized_tables_describe_the_same_samples() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![
+            vec![vec![0; 10]; 3],
+            vec![vec![0; 10]; 3],
+            vec![vec![0; 10]; 2],
+        ],
+        sync_samples: None,
+    }];
+    let data = build_progressive(&tracks, true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let stbl = &mp4.tracks()[&1].trak(&mp4).unwrap().mdia.minf.stbl;
+
+    // Split every run into runs of one.
+    let mut fragmented = stbl.clone();
+    fragmented.stts.entries = stbl
+        .stts
+        .entries
+        .iter()
+        .flat_map(|entry| {
+            std::iter::repeat_n(
+                SttsEntry {
+                    sample_count: 1,
+                    sample_delta: entry.sample_delta,
+                },
+                entry.sample_count as usize,
+            )
+        })
+        .collect();
+    fragmented.stsc.entries = stsc(&[(1, 3, 1), (2, 3, 4), (3, 2, 7)]);
+
+    fragmented.normalize().unwrap();
+
+    // The synthetic file has an `stsc` entry per chunk.
+    let mut expected = stbl.clone();
+    expected.normalize().unwrap();
+    assert_eq!(expected.stsc.entries, stsc(&[(1, 3, 1), (3, 2, 7)]));
+    assert_eq!(fragmented, expected);
+}
@@ -0,0 +1,161 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{
+    Codec, FixedPointU16, FourCC, Mp4, PcmBox, PcmFormat, StsdBoxContent, StszBox, TrackKind,
+};
+use synthetic::{build_progressive, mp4_box, read_stsd, versioned_audio_sample_entry, TestTrack};
+
+fn read_pcm(entry: &[u8]) -> PcmBox {
+    let stsd = read_stsd(entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Audio));
+    match stsd.contents {
+        StsdBoxContent::Pcm(pcm) => pcm,
+        contents => panic!("expected a PCM entry, got {contents:?}"),
+    }
+}
+
+#[test]
+fn pcm_formats() {
+    let sowt = read_pcm(&versioned_audio_sample_entry(b"sowt", 0, 48_000, &[], &[]));
+    let format = sowt.format();
+    assert_eq!(
+        format,
+        PcmFormat {
+            bits_per_sample: 16,
+            float: false,
+            signed: true,
+            little_endian: true,
+            channel_count: 2,
+            sample_rate: 48_000,
+        }
+    );
+    assert_eq!(format.bytes_per_frame(), 4);
+    assert_eq!(
+        Codec::from_sample_entry(&StsdBoxContent::Pcm(sowt)),
+        Codec::Pcm(format)
+    );
+
+    // `in24` is big endian unless an `enda` box in `wave` says otherwise.
+    let in24 = read_pcm(&versioned_audio_sample_entry(b"in24", 0, 48_000, &[], &[]));
+    assert_eq!(
+        (in24.format().bits_per_sample, in24.format().little_endian),
+        (24, false)
+    );
+    let wave = mp4_box(
+        b"wave",
+        &[
+            &mp4_box(b"frma", &[b"in24"]),
+            &mp4_box(b"enda", &[&[0, 1]]),
+            &[0, 0, 0, 8, 0, 0, 0, 0],
+        ],
+    );
+    let in24 = read_pcm(&versioned_audio_sample_entry(
+        b"in24", 1, 48_000, &[0; 16], &wave,
+    ));
+    assert!(in24.format().little_endian);
+
+    // `lpcm` describes its format in version 2 of the sound sample description, here 6
+    // channels of 32-bit little endian floats at 96 kHz.
+    let mut extension = 72_u32.to_be_bytes().to_vec();
+    extension.extend(96_000_f64.to_bits().to_be_bytes());
+    for field in [6_u32, 0x7f00_0000, 32, 1 | 8, 24, 1] {
+        extension.extend(field.to_be_bytes());
+    }
+    let lpcm = read_pcm(&versioned_audio_sample_entry(
+        b"lpcm",
+        2,
+        48_000,
+        &extension,
+        &[],
+    ));
+    assert_eq!(
+        lpcm.format(),
+        PcmFormat {
+            bits_per_sample: 32,
+            float: true,
+            signed: true,
+            little_endian: true,
+            channel_count: 6,
+            sample_rate: 96_000,
+        }
+    );
+}
+
+#[test]
+fn pcm_to_f32() {
+    let format = |bits_per_sample, float, signed, little_endian| PcmFormat {
+        bits_per_sample,
+        float,
+        signed,
+        little_endian,
+        channel_count: 1,
+        sample_rate: 48_000,
+    };
+    assert_eq!(
+        format(16, false, true, true).to_f32(&[0x00, 0x40, 0x00, 0xc0, 0xff]),
+        [0.5, -0.5]
+    );
+    assert_eq!(
+        format(24, false, true, false).to_f32(&[0x40, 0, 0, 0x80, 0, 0]),
+        [0.5, -1.0]
+    );
+    assert_eq!(
+        format(8, false, false, false).to_f32(&[0xc0, 0x80]),
+        [0.5, 0.0]
+    );
+    assert_eq!(
+        format(32, true, true, false).to_f32(&0.25_f32.to_be_bytes()),
+        [0.25]
+    );
+}
+
+#[test]
+fn pcm_samples_of_one_byte_are_whole_frames() {
+    // 10 stereo frames of 16 bits, in chunks of 5.
+    let frames: Vec<Vec<u8>> = (0..10_u8).map(|i| vec![i, 0, 0, i]).collect();
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"soun",
+        timescale: 48_000,
+        chunks: frames.chunks(5).map(<[_]>::to_vec).collect(),
+        sync_samples: None,
+    }];
+    let data = build_progressive(&tracks, true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+
+    // As written by `QuickTime`, with a size of 1 for every frame.
+    let stbl = &mut mp4.moov.traks[0].mdia.minf.stbl;
+    stbl.stsd.contents = StsdBoxContent::Pcm(PcmBox {
+        fourcc: FourCC::from(*b"sowt"),
+        data_reference_index: 1,
+        channelcount: 2,
+        samplesize: 16,
+        samplerate: FixedPointU16::new(48_000),
+        qt_sound: None,
+        little_endian: false,
+        btrt: None,
+    });
+    stbl.stsz = StszBox {
+        sample_size: 1,
+        sample_count: 10,
+        ..Default::default()
+    };
+    mp4.rebuild_tracks().unwrap();
+
+    let track = &mp4.tracks()[&1];
+    let Codec::Pcm(format) = track.codec() else {
+        panic!("expected PCM, got {:?}", track.codec());
+    };
+    let info = track.audio_info(&mp4).unwrap();
+    assert_eq!((info.sample_rate, info.channel_count), (48_000, 2));
+    for (sample, frame) in track.samples.iter().zip(&frames) {
+        assert_eq!(&data[sample.byte_range()], frame);
+    }
+    let sample = &track.samples[4];
+    let value = f32::from(4_u8) / 32768.0;
+    assert_eq!(
+        format.to_f32(&data[sample.byte_range()]),
+        [value, value * 256.0]
+    );
+}
@@ -0,0 +1,187 @@
+//! Peak heap usage of parsing files, compared against the budgets in `peak_memory.txt`.
+//!
+//! Run with `cargo test --features alloc-tracking --test peak_memory`. Set
+//! `UPDATE_MEMORY_BUDGETS` to write the measured usage, plus some headroom, as the new budgets.
+//! Every file needs a budget, so the sample files have to be fetched with `git lfs pull` first.
+
+mod paths;
+
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use re_mp4::Mp4;
+use synthetic::{build_progressive, fragmented, TestTrack};
+
+/// Counts the bytes allocated on the heap, and the most that were allocated at once.
+struct CountingAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[expect(unsafe_code)]
+// SAFETY: all allocations are forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::alloc`.
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            self.add(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) };
+        self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::realloc`.
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+            self.add(new_size);
+        }
+        new_ptr
+    }
+}
+
+impl CountingAllocator {
+    fn add(&self, size: usize) {
+        let current = self.current.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak.fetch_max(current, Ordering::Relaxed);
+    }
+
+    /// The peak heap usage while running `f`, on top of what was allocated before.
+    fn measure(&self, f: impl FnOnce()) -> usize {
+        let start = self.current.load(Ordering::Relaxed);
+        self.peak.store(start, Ordering::Relaxed);
+        f();
+        self.peak.load(Ordering::Relaxed) - start
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+const BUDGETS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/peak_memory.txt");
+
+/// Measured usage is rounded up by this much when writing new budgets.
+const HEADROOM_PERCENT: usize = 10;
+
+/// Lines of `<name> <bytes>`, where the name is the path of a sample file relative to
+/// `tests/samples`, or `synthetic/...` for a file built by the test.
+fn read_budgets() -> BTreeMap<String, usize> {
+    let Ok(contents) = std::fs::read_to_string(BUDGETS_PATH) else {
+        return BTreeMap::new();
+    };
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, bytes) = line.rsplit_once(' ').expect("expected `<name> <bytes>`");
+            let bytes = bytes.parse().expect("invalid budget");
+            (name.to_owned(), bytes)
+        })
+        .collect()
+}
+
+fn write_budgets(budgets: &BTreeMap<String, usize>) {
+    let mut contents = String::from("# Peak heap usage of parsing a file, see `peak_memory.rs`.\n");
+    for (name, bytes) in budgets {
+        writeln!(contents, "{name} {bytes}").expect("failed to format budget");
+    }
+    std::fs::write(BUDGETS_PATH, contents).expect("failed to write budgets");
+}
+
+/// Two tracks of many small samples, so that the sample tables dominate.
+fn synthetic_tracks() -> Vec<TestTrack> {
+    (1..=2)
+        .map(|track_id| TestTrack {
+            track_id,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: (0..100).map(|_| vec![vec![0; 4]; 100]).collect(),
+            sync_samples: Some((1..=10_000).step_by(30).collect()),
+        })
+        .collect()
+}
+
+fn sample_files(dir: &Path, files: &mut Vec<(String, Vec<u8>)>) {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .expect("failed to read samples directory")
+        .map(|entry| entry.expect("failed to read samples directory").path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            sample_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "mp4") {
+            let name = path
+                .strip_prefix(paths::SAMPLE_BASE_PATH)
+                .expect("sample outside of the samples directory")
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.push((name, std::fs::read(&path).expect("failed to read sample")));
+        }
+    }
+}
+
+#[test]
+fn peak_memory_of_parsing_is_within_budget() {
+    let mut files = vec![
+        (
+            "synthetic/progressive".to_owned(),
+            build_progressive(&synthetic_tracks(), true),
+        ),
+        (
+            "synthetic/fragmented".to_owned(),
+            // The same samples in fragments of one second, without a single `mdat`.
+            fragmented(&synthetic_tracks(), Duration::from_secs(1)),
+        ),
+    ];
+    sample_files(Path::new(paths::SAMPLE_BASE_PATH), &mut files);
+
+    let update = std::env::var("UPDATE_MEMORY_BUDGETS").is_ok();
+    let mut budgets = read_budgets();
+    let mut failures = Vec::new();
+    for (name, data) in &files {
+        let mut result = None;
+        let peak = ALLOCATOR.measure(|| result = Some(Mp4::read_bytes(data).map(drop)));
+        let parsed = matches!(result, Some(Ok(())));
+
+        match budgets.get(name) {
+            _ if update && parsed => {
+                budgets.insert(name.clone(), peak + peak * HEADROOM_PERCENT / 100);
+            }
+            _ if !parsed => failures.push(format!("{name}: failed to parse")),
+            Some(&budget) => {
+                println!("{name}: {peak} bytes, budget {budget}");
+                if peak > budget {
+                    failures.push(format!("{name}: {peak} bytes, budget {budget}"));
+                }
+            }
+            None => failures.push(format!("{name}: {peak} bytes, no budget")),
+        }
+    }
+
+    if update {
+        write_budgets(&budgets);
+    }
+    assert!(
+        failures.is_empty(),
+        "peak memory not within budget, see `tests/peak_memory.rs`:\n{}",
+        failures.join("\n")
+    );
+}
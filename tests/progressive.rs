@@ -0,0 +1,61 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::time::Duration;
+
+use re_mp4::Mp4;
+use synthetic::{build_progressive, TestTrack};
+
+/// A video and an audio track of one second samples, with `chunks_per_track` chunks each.
+fn tracks(chunks_per_track: usize) -> [TestTrack; 2] {
+    let samples_per_chunk = 10 / chunks_per_track;
+    let track = |track_id, handler, sample_size| TestTrack {
+        track_id,
+        handler,
+        timescale: 1,
+        chunks: vec![vec![vec![0; sample_size]; samples_per_chunk]; chunks_per_track],
+        sync_samples: None,
+    };
+    [track(1, *b"vide", 10_000), track(2, *b"soun", 1000)]
+}
+
+#[test]
+fn interleaved_file_with_moov_first_plays_progressively() {
+    let mp4 = Mp4::read_bytes(&build_progressive(&tracks(10), true)).unwrap();
+    let download = mp4.progressive_download(11_000);
+    assert!(download.moov_before_media);
+    assert!(download.is_progressive());
+
+    // Every second of media takes a second to download, so only the first one is buffered.
+    assert!(download.startup_delay > Duration::from_secs(1));
+    assert!(download.startup_delay < Duration::from_secs(2));
+    assert!(download.download_time > Duration::from_secs(10));
+    assert_eq!(
+        download.critical_sample.map(|(track_id, _)| track_id),
+        Some(2)
+    );
+
+    // At half the bandwidth, half of the file has to be buffered.
+    let download = mp4.progressive_download(5500);
+    assert!(download.startup_delay > Duration::from_secs(11));
+    assert!(download.startup_delay < download.download_time);
+}
+
+#[test]
+fn moov_at_the_end_needs_the_whole_file() {
+    let mp4 = Mp4::read_bytes(&build_progressive(&tracks(10), false)).unwrap();
+    let download = mp4.progressive_download(11_000);
+    assert!(!download.moov_before_media);
+    assert!(!download.is_progressive());
+    assert_eq!(download.startup_delay, download.download_time);
+}
+
+#[test]
+fn tracks_that_are_not_interleaved_stall() {
+    // All video comes before all audio, so the first audio sample is due long before it arrives.
+    let mp4 = Mp4::read_bytes(&build_progressive(&tracks(1), true)).unwrap();
+    let download = mp4.progressive_download(11_000);
+    assert!(download.moov_before_media);
+    assert!(download.startup_delay > Duration::from_secs(9));
+    assert_eq!(download.critical_sample, Some((2, 0)));
+}
@@ -0,0 +1,103 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{AudioInfo, QtSoundDescription, StsdBox, StsdBoxContent};
+use synthetic::{mp4_box, read_stsd, sound_track};
+
+/// An `mp4a` sample entry of `version` with the fields of version 0, the `extension` of the
+/// version and an `esds` box with the AAC `audio_specific_config`.
+fn mp4a(
+    version: u16,
+    fields: [u16; 2],
+    samplerate: u32,
+    extension: &[u8],
+    audio_specific_config: [u8; 2],
+) -> Vec<u8> {
+    let esds = mp4_box(
+        b"esds",
+        &[
+            &[0; 4],
+            &[3, 25, 0, 1, 0],
+            &[4, 17, 0x40, 0x15, 0, 0, 0],
+            &256_000_u32.to_be_bytes(),
+            &128_000_u32.to_be_bytes(),
+            &[5, 2],
+            &audio_specific_config,
+            &[6, 1, 2],
+        ],
+    );
+    let [channelcount, samplesize] = fields;
+    mp4_box(
+        b"mp4a",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &version.to_be_bytes(),
+            &[0; 6],
+            &channelcount.to_be_bytes(),
+            &samplesize.to_be_bytes(),
+            &[0; 4],
+            &samplerate.to_be_bytes(),
+            extension,
+            &esds,
+        ],
+    )
+}
+
+fn read_mp4a(entry: &[u8]) -> (StsdBox, AudioInfo) {
+    let stsd = read_stsd(entry);
+    let mp4 = sound_track(stsd.clone());
+    let info = mp4.tracks()[&1]
+        .audio_info(&mp4)
+        .expect("Failed getting audio info");
+    (stsd, info)
+}
+
+#[test]
+fn sound_description_version_1() {
+    let extension: Vec<u8> = [1024_u32, 0, 4, 2]
+        .iter()
+        .flat_map(|field| field.to_be_bytes())
+        .collect();
+    let (stsd, info) = read_mp4a(&mp4a(1, [2, 16], 44_100 << 16, &extension, [0x12, 0x10]));
+    let StsdBoxContent::Mp4a(mp4a) = &stsd.contents else {
+        panic!("expected mp4a, got {:?}", stsd.contents);
+    };
+    assert_eq!(
+        mp4a.qt_sound,
+        Some(QtSoundDescription::V1 {
+            samples_per_packet: 1024,
+            bytes_per_packet: 0,
+            bytes_per_frame: 4,
+            bytes_per_sample: 2,
+        })
+    );
+    assert!(mp4a.esds.is_some());
+    assert_eq!((info.sample_rate, info.channel_count), (44_100, 2));
+    assert_eq!(info.bitrate, Some(128_000));
+}
+
+#[test]
+fn sound_description_version_2() {
+    // 6 channels of 24 bits at 96 kHz, which doesn't fit the fields of version 0.
+    let mut extension = 72_u32.to_be_bytes().to_vec();
+    extension.extend(96_000_f64.to_bits().to_be_bytes());
+    for field in [6_u32, 0x7f00_0000, 24, 0, 0, 1024] {
+        extension.extend(field.to_be_bytes());
+    }
+    let (stsd, info) = read_mp4a(&mp4a(2, [3, 16], 1 << 16, &extension, [0x10, 0x30]));
+    let StsdBoxContent::Mp4a(mp4a) = &stsd.contents else {
+        panic!("expected mp4a, got {:?}", stsd.contents);
+    };
+    assert_eq!(
+        mp4a.qt_sound.and_then(|qt_sound| qt_sound.sample_rate()),
+        Some(96_000.0)
+    );
+    assert_eq!((mp4a.channelcount, mp4a.samplesize), (6, 24));
+    assert_eq!(mp4a.samplerate.raw_value(), 0);
+    assert_eq!(mp4a.sample_rate(), 96_000);
+    assert!(mp4a.esds.is_some());
+    assert_eq!(
+        (info.sample_rate, info.channel_count, info.sample_size),
+        (96_000, 6, 24)
+    );
+}
@@ -0,0 +1,76 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::ops::Range;
+
+use re_mp4::{Bytes, Mp4, RangeReader, Result};
+use synthetic::{build_progressive, TestTrack};
+
+/// Serves ranges of a file in memory, and records them.
+struct Remote {
+    data: Bytes,
+    requests: Vec<Range<u64>>,
+}
+
+impl RangeReader for Remote {
+    fn fetch(&mut self, offset: u64, len: u64) -> Result<Bytes> {
+        self.requests.push(offset..offset + len);
+        Ok(self.data.slice(offset as usize..(offset + len) as usize))
+    }
+}
+
+fn tracks() -> Vec<TestTrack> {
+    vec![TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 400], vec![2; 500]], vec![vec![3; 600]]],
+        sync_samples: Some(vec![1]),
+    }]
+}
+
+#[test]
+fn read_with_ranges_skips_media_data() {
+    for moov_first in [true, false] {
+        let data = build_progressive(&tracks(), moov_first);
+        let expected = Mp4::read_bytes(&data).unwrap();
+        let mut remote = Remote {
+            data: Bytes::from(data.clone()),
+            requests: Vec::new(),
+        };
+
+        let mp4 = Mp4::read_with_ranges(&mut remote, data.len() as u64).unwrap();
+        assert_eq!(mp4.moov, expected.moov);
+        assert_eq!(mp4.mdats, expected.mdats);
+        assert_eq!(mp4.box_tree(), expected.box_tree());
+
+        // Only the header of `mdat` was fetched, not the samples in it.
+        let (mdat_start, mdat_size) = mp4.mdats[0];
+        let fetched_media: u64 = remote
+            .requests
+            .iter()
+            .map(|request| {
+                request.end.min(mdat_start + mdat_size)
+                    - request.start.max(mdat_start).min(request.end)
+            })
+            .sum();
+        assert!(fetched_media <= 8, "fetched {fetched_media} bytes of mdat");
+
+        remote.requests.clear();
+        for sample in &mp4.tracks()[&1].samples {
+            let bytes = sample.fetch(&mut remote).unwrap();
+            assert_eq!(bytes, &data[sample.byte_range()]);
+        }
+        assert_eq!(remote.requests.len(), 3);
+    }
+}
+
+#[test]
+fn read_with_ranges_truncated_file() {
+    let data = build_progressive(&tracks(), false);
+    let mut remote = Remote {
+        data: Bytes::from(data.clone()),
+        requests: Vec::new(),
+    };
+    assert!(Mp4::read_with_ranges(&mut remote, data.len() as u64 - 10).is_err());
+}
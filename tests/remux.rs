@@ -0,0 +1,594 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::time::Duration;
+
+use re_mp4::remux::Segment;
+use re_mp4::{CttsBox, CttsEntry, EdtsBox, ElstBox, ElstEntry, Mp4, WriteBox as _};
+use synthetic::{
+    append_child, build_progressive, fragment_file, fragmented, test_tracks, top_level_boxes,
+    with_external_video_data, TestTrack,
+};
+
+fn sample_data(mp4: &Mp4, data: &[u8], track_id: u32) -> Vec<Vec<u8>> {
+    mp4.tracks()[&track_id]
+        .samples
+        .iter()
+        .map(|sample| data[sample.byte_range()].to_vec())
+        .collect()
+}
+
+#[test]
+fn strip_audio_track() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+
+    mp4.retain_tracks(|track| track.track_id != 2);
+    assert_eq!(mp4.tracks().keys().copied().collect::<Vec<_>>(), vec![1]);
+    assert_eq!(mp4.moov.traks.len(), 1);
+
+    let mut output = Vec::new();
+    re_mp4::remux::write(&mp4, &mut std::io::Cursor::new(&data), &mut output).unwrap();
+    assert_eq!(top_level_boxes(&output), [*b"ftyp", *b"moov", *b"mdat"]);
+
+    // `mdat` holds nothing but the video samples.
+    let video = tracks[0].samples().collect::<Vec<_>>().concat();
+    let mdat = [&(video.len() as u32 + 8).to_be_bytes()[..], b"mdat", &video].concat();
+    assert!(output.ends_with(&mdat));
+
+    // The opaque sample entry is copied as-is.
+    let entry = [
+        &[0, 0, 0, 20][..],
+        b"test",
+        &[0, 0, 0, 0, 0, 0, 0, 1],
+        b"vide",
+    ]
+    .concat();
+    assert!(output.windows(entry.len()).any(|window| window == entry));
+
+    let remuxed = Mp4::read_bytes(&output).unwrap();
+    assert_eq!(remuxed.tracks().len(), 1);
+    assert_eq!(
+        sample_data(&remuxed, &output, 1),
+        tracks[0].samples().map(<[u8]>::to_vec).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn keeping_all_tracks_preserves_samples() {
+    let tracks = test_tracks();
+    for moov_first in [false, true] {
+        let data = build_progressive(&tracks, moov_first);
+        let mp4 = Mp4::read_bytes(&data).unwrap();
+
+        let mut output = Vec::new();
+        re_mp4::remux::write(&mp4, &mut std::io::Cursor::new(&data), &mut output).unwrap();
+        let remuxed = Mp4::read_bytes(&output).unwrap();
+
+        // Only the `free` box is gone.
+        assert_eq!(output.len(), data.len() - 12);
+        for track in &tracks {
+            assert_eq!(
+                sample_data(&remuxed, &output, track.track_id),
+                sample_data(&mp4, &data, track.track_id)
+            );
+            assert_eq!(
+                remuxed.tracks()[&track.track_id].samples.len(),
+                track.samples().count()
+            );
+        }
+    }
+}
+
+#[test]
+fn faststart_moves_moov_ahead_of_mdat() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    assert_eq!(
+        top_level_boxes(&data),
+        [*b"ftyp", *b"free", *b"mdat", *b"moov"]
+    );
+
+    let mut output = Vec::new();
+    re_mp4::faststart(&mut std::io::Cursor::new(&data), &mut output).unwrap();
+    assert_eq!(
+        top_level_boxes(&output),
+        [*b"ftyp", *b"free", *b"moov", *b"mdat"]
+    );
+    assert_eq!(output.len(), data.len());
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let relocated = Mp4::read_bytes(&output).unwrap();
+    for track in &tracks {
+        assert_eq!(
+            sample_data(&relocated, &output, track.track_id),
+            sample_data(&mp4, &data, track.track_id)
+        );
+    }
+
+    // Running it again leaves the file untouched.
+    let mut again = Vec::new();
+    re_mp4::faststart(&mut std::io::Cursor::new(&output), &mut again).unwrap();
+    assert_eq!(again, output);
+}
+
+#[test]
+fn concat_is_gapless() {
+    let first = test_tracks();
+    let mut second = test_tracks();
+    for track in &mut second {
+        track.chunks.reverse();
+    }
+    let first_data = build_progressive(&first, false);
+    let second_data = build_progressive(&second, true);
+    let first_mp4 = Mp4::read_bytes(&first_data).unwrap();
+    let second_mp4 = Mp4::read_bytes(&second_data).unwrap();
+
+    let mut output = Vec::new();
+    re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&first_data)),
+            (&second_mp4, std::io::Cursor::new(&second_data)),
+        ],
+        &mut output,
+    )
+    .unwrap();
+    let joined = Mp4::read_bytes(&output).unwrap();
+
+    for (a, b) in first.iter().zip(&second) {
+        let expected: Vec<Vec<u8>> = a.samples().chain(b.samples()).map(<[u8]>::to_vec).collect();
+        assert_eq!(sample_data(&joined, &output, a.track_id), expected);
+
+        let track = &joined.tracks()[&a.track_id];
+        let timestamps: Vec<u64> = track
+            .samples
+            .iter()
+            .map(|s| s.decode_timestamp as u64)
+            .collect();
+        assert_eq!(timestamps, (0..expected.len() as u64).collect::<Vec<_>>());
+        assert_eq!(track.duration, expected.len() as u64);
+    }
+}
+
+#[test]
+fn concat_keeps_edits_of_later_inputs() {
+    let tracks = test_tracks();
+    let first_data = build_progressive(&tracks, false);
+
+    // The second input leaves out its first video sample, like the priming of audio.
+    let mut second_data = build_progressive(&tracks, false);
+    let mut edts = Vec::new();
+    EdtsBox {
+        elst: Some(ElstBox {
+            entries: vec![ElstEntry {
+                segment_duration: 166,
+                media_time: 1,
+                media_rate: 1,
+                media_rate_fraction: 0,
+            }],
+            ..Default::default()
+        }),
+    }
+    .write_box(&mut edts)
+    .unwrap();
+    append_child(&mut second_data, &[b"moov", b"trak"], &edts);
+
+    let first_mp4 = Mp4::read_bytes(&first_data).unwrap();
+    let second_mp4 = Mp4::read_bytes(&second_data).unwrap();
+    let mut output = Vec::new();
+    re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&first_data)),
+            (&second_mp4, std::io::Cursor::new(&second_data)),
+        ],
+        &mut output,
+    )
+    .unwrap();
+    let joined = Mp4::read_bytes(&output).unwrap();
+
+    // The first input presents all of its 6 samples, then the second skips one.
+    let video = &joined.moov.traks[0];
+    let elst = video.edts.as_ref().unwrap().elst.as_ref().unwrap();
+    let edits: Vec<_> = elst
+        .entries
+        .iter()
+        .map(|entry| (entry.segment_duration, entry.media_time))
+        .collect();
+    assert_eq!(edits, [(200, 0), (166, 7)]);
+    assert_eq!(video.tkhd.duration, 366);
+    assert_eq!(video.mdia.mdhd.duration, 12);
+
+    // Tracks without edit lists in any input still have none.
+    let audio = &joined.moov.traks[1];
+    assert!(audio.edts.is_none());
+}
+
+#[test]
+fn concat_after_retain_tracks() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let mut first_mp4 = Mp4::read_bytes(&data).unwrap();
+    let mut second_mp4 = Mp4::read_bytes(&data).unwrap();
+    first_mp4.retain_tracks(|track| track.track_id == 1);
+    second_mp4.retain_tracks(|track| track.track_id == 1);
+
+    let mut output = Vec::new();
+    re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&data)),
+            (&second_mp4, std::io::Cursor::new(&data)),
+        ],
+        &mut output,
+    )
+    .unwrap();
+    let joined = Mp4::read_bytes(&output).unwrap();
+
+    assert_eq!(joined.tracks().keys().copied().collect::<Vec<_>>(), vec![1]);
+    let video: Vec<Vec<u8>> = tracks[0].samples().map(<[u8]>::to_vec).collect();
+    assert_eq!(
+        sample_data(&joined, &output, 1),
+        [video.clone(), video].concat()
+    );
+}
+
+#[test]
+fn concat_rejects_short_stsz() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let first_mp4 = Mp4::read_bytes(&data).unwrap();
+    let mut second_mp4 = Mp4::read_bytes(&data).unwrap();
+    let stsz = &mut second_mp4.moov.traks[0].mdia.minf.stbl.stsz;
+    stsz.sample_sizes.pop();
+
+    let result = re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&data)),
+            (&second_mp4, std::io::Cursor::new(&data)),
+        ],
+        &mut Vec::new(),
+    );
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[test]
+fn concat_rejects_overflowing_tables() {
+    let tracks = test_tracks();
+    let data = build_progressive(&tracks, false);
+    let first_mp4 = Mp4::read_bytes(&data).unwrap();
+    let mut second_mp4 = Mp4::read_bytes(&data).unwrap();
+    let stsc = &mut second_mp4.moov.traks[0].mdia.minf.stbl.stsc;
+    stsc.entries[0].first_sample = u32::MAX;
+
+    let result = re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&data)),
+            (&second_mp4, std::io::Cursor::new(&data)),
+        ],
+        &mut Vec::new(),
+    );
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[test]
+fn concat_rejects_mismatched_timescales() {
+    let first = test_tracks();
+    let mut second = test_tracks();
+    second[0].timescale = 25;
+    let first_data = build_progressive(&first, false);
+    let second_data = build_progressive(&second, false);
+    let first_mp4 = Mp4::read_bytes(&first_data).unwrap();
+    let second_mp4 = Mp4::read_bytes(&second_data).unwrap();
+
+    let result = re_mp4::remux::concat(
+        &mut [
+            (&first_mp4, std::io::Cursor::new(&first_data)),
+            (&second_mp4, std::io::Cursor::new(&second_data)),
+        ],
+        &mut Vec::new(),
+    );
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[test]
+fn remux_rejects_external_data() {
+    let mut data = build_progressive(&test_tracks(), false);
+
+    with_external_video_data(&mut data);
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let tracks = mp4.tracks();
+    assert!(tracks[&1].has_external_data(&mp4));
+    assert!(!tracks[&2].has_external_data(&mp4));
+    assert!(matches!(
+        tracks[&1].data_reference(&mp4),
+        Some(re_mp4::DataEntry::Url(url)) if url.flags == 0
+    ));
+
+    let result = re_mp4::remux::write(&mp4, &mut std::io::Cursor::new(&data), &mut Vec::new());
+    assert!(matches!(
+        result,
+        Err(re_mp4::Error::ExternalDataReference(1))
+    ));
+}
+
+fn keyframe_tracks() -> Vec<TestTrack> {
+    let samples = |track: u8, count: u8| -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|sample| vec![track; 2 + sample as usize])
+            .collect()
+    };
+    vec![
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 10,
+            chunks: vec![samples(1, 10)],
+            sync_samples: Some(vec![1, 4, 7, 9]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 20,
+            chunks: vec![samples(2, 20)],
+            sync_samples: None,
+        },
+    ]
+}
+
+fn sample_timing(mp4: &Mp4, track_id: u32) -> Vec<(i64, i64, u64, bool)> {
+    mp4.tracks()[&track_id]
+        .samples
+        .iter()
+        .map(|s| {
+            (
+                s.decode_timestamp,
+                s.composition_timestamp,
+                s.duration,
+                s.is_sync,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn fragment_splits_at_keyframes() {
+    let data = build_progressive(&keyframe_tracks(), true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    let fragments = re_mp4::remux::fragment(
+        &mp4,
+        &mut std::io::Cursor::new(&data),
+        Duration::from_millis(250),
+    )
+    .unwrap();
+    assert_eq!(
+        top_level_boxes(&fragments.init_segment),
+        [*b"ftyp", *b"moov"]
+    );
+
+    let timing: Vec<_> = fragments
+        .media_segments
+        .iter()
+        .map(|segment| (segment.start.as_millis(), segment.duration.as_millis()))
+        .collect();
+    assert_eq!(timing, [(0, 300), (300, 300), (600, 400)]);
+
+    for segment in &fragments.media_segments {
+        assert_eq!(top_level_boxes(&segment.data), [*b"moof", *b"mdat"]);
+    }
+    let output = fragmented(&keyframe_tracks(), Duration::from_millis(250));
+    let fragmented = Mp4::read_bytes(&output).unwrap();
+    assert_eq!(fragmented.moofs.len(), 3);
+    for track_id in [1, 2] {
+        assert_eq!(
+            sample_data(&fragmented, &output, track_id),
+            sample_data(&mp4, &data, track_id)
+        );
+        let timestamps = |mp4: &Mp4| -> Vec<_> {
+            mp4.tracks()[&track_id]
+                .samples
+                .iter()
+                .map(|s| {
+                    (
+                        s.decode_timestamp,
+                        s.composition_timestamp,
+                        s.duration,
+                        s.is_sync,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(timestamps(&fragmented), timestamps(&mp4));
+
+        let (track, fragmented_track) = (&mp4.tracks()[&track_id], &fragmented.tracks()[&track_id]);
+        assert_eq!(fragmented_track.frame_rate(), track.frame_rate());
+        assert_eq!(fragmented_track.duration, track.duration);
+    }
+}
+
+#[test]
+fn fragment_rejects_overflowing_timestamps() {
+    let data = build_progressive(&keyframe_tracks(), true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+
+    // The last video sample lasts until the end of the media, just past `i64::MAX`.
+    let mdhd = &mut mp4.moov.traks[0].mdia.mdhd;
+    mdhd.version = 1;
+    mdhd.duration = i64::MAX as u64 + 1;
+    mp4.rebuild_tracks().unwrap();
+
+    let result = re_mp4::remux::fragment(
+        &mp4,
+        &mut std::io::Cursor::new(&data),
+        Duration::from_millis(250),
+    );
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[test]
+fn fragment_streaming_hands_out_segments_in_order() {
+    let data = build_progressive(&keyframe_tracks(), true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let fragments = re_mp4::remux::fragment(
+        &mp4,
+        &mut std::io::Cursor::new(&data),
+        Duration::from_millis(250),
+    )
+    .unwrap();
+
+    let mut segments = Vec::new();
+    re_mp4::remux::fragment_streaming(
+        &mp4,
+        &mut std::io::Cursor::new(&data),
+        Duration::from_millis(250),
+        |segment| {
+            segments.push(match segment {
+                Segment::Init(data) => data,
+                Segment::Media(segment) => segment.data,
+            });
+            Ok(())
+        },
+    )
+    .unwrap();
+    let expected: Vec<_> = std::iter::once(fragments.init_segment)
+        .chain(fragments.media_segments.into_iter().map(|s| s.data))
+        .collect();
+    assert_eq!(segments, expected);
+
+    // An error from the callback stops the fragmentation.
+    let mut count = 0;
+    let result = re_mp4::remux::fragment_streaming(
+        &mp4,
+        &mut std::io::Cursor::new(&data),
+        Duration::from_millis(250),
+        |segment| {
+            count += 1;
+            match segment {
+                Segment::Init(_) => Ok(()),
+                Segment::Media(_) => Err(re_mp4::Error::InvalidData("source buffer is full")),
+            }
+        },
+    );
+    assert!(result.is_err());
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn defragment_restores_progressive_file() {
+    let data = build_progressive(&keyframe_tracks(), true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let fragmented = fragmented(&keyframe_tracks(), Duration::from_millis(250));
+    let fragmented_mp4 = Mp4::read_bytes(&fragmented).unwrap();
+
+    let mut output = Vec::new();
+    re_mp4::remux::defragment(
+        &fragmented_mp4,
+        &mut std::io::Cursor::new(&fragmented),
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(top_level_boxes(&output), [*b"ftyp", *b"moov", *b"mdat"]);
+
+    let defragmented = Mp4::read_bytes(&output).unwrap();
+    assert!(defragmented.moofs.is_empty());
+    assert!(defragmented.moov.mvex.is_none());
+    for track_id in [1, 2] {
+        assert_eq!(
+            sample_data(&defragmented, &output, track_id),
+            sample_data(&mp4, &data, track_id)
+        );
+        assert_eq!(
+            sample_timing(&defragmented, track_id),
+            sample_timing(&mp4, track_id)
+        );
+        assert_eq!(
+            defragmented.tracks()[&track_id].duration,
+            mp4.tracks()[&track_id].duration
+        );
+    }
+    // Samples that were stored back to back in a fragment are one chunk again.
+    let stbl = &defragmented.moov.traks[0].mdia.minf.stbl;
+    assert_eq!(stbl.stco.as_ref().unwrap().entries.len(), 3);
+    assert_eq!(stbl.stss.as_ref().unwrap().entries, [1, 4, 7, 9]);
+}
+
+#[test]
+fn defragment_rejects_overflowing_durations() {
+    let data = build_progressive(&keyframe_tracks(), true);
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+
+    // In the movie timescale, the video track lasts longer than fits in 64 bits.
+    mp4.moov.mvhd.timescale = u32::MAX;
+    let trak = &mut mp4.moov.traks[0];
+    trak.mdia.mdhd.timescale = 1;
+    for entry in &mut trak.mdia.minf.stbl.stts.entries {
+        entry.sample_delta = u32::MAX;
+    }
+    mp4.rebuild_tracks().unwrap();
+
+    let result = re_mp4::remux::defragment(&mp4, &mut std::io::Cursor::new(&data), &mut Vec::new());
+    assert!(matches!(result, Err(re_mp4::Error::InvalidData(_))));
+}
+
+#[test]
+fn b_frames_survive_fragment_and_defragment() {
+    // GOPs of an I, a P and a B frame, where the B frame is shown before the P frame, with
+    // composition offsets relative to the I frames (version 0) and to the B frames (version 1).
+    for (version, offsets) in [
+        (0, [1, 2, 0, 1, 2, 0, 1, 1, 1, 1]),
+        (1, [0, 1, -1, 0, 1, -1, 0, 0, 0, 0]),
+    ] {
+        let mut data = build_progressive(&keyframe_tracks(), false);
+        let mut ctts = Vec::new();
+        CttsBox {
+            version,
+            flags: 0,
+            entries: offsets
+                .iter()
+                .map(|&sample_offset| CttsEntry {
+                    sample_count: 1,
+                    sample_offset,
+                })
+                .collect(),
+        }
+        .write_box(&mut ctts)
+        .unwrap();
+        append_child(
+            &mut data,
+            &[b"moov", b"trak", b"mdia", b"minf", b"stbl"],
+            &ctts,
+        );
+        let mp4 = Mp4::read_bytes(&data).unwrap();
+        let timing = sample_timing(&mp4, 1);
+        assert!(timing.windows(2).any(|pair| pair[1].1 < pair[0].1));
+
+        let fragmented = fragment_file(&data, Duration::from_millis(250));
+        // Fragments keep the composition offsets, but not the shift of the timeline that
+        // makes the first frame start at zero, see `Sample::media_decode_timestamp`.
+        let fragmented_mp4 = Mp4::read_bytes(&fragmented).unwrap();
+        let offsets = |timing: &[(i64, i64, u64, bool)]| -> Vec<_> {
+            timing.iter().map(|(dts, cts, ..)| cts - dts).collect()
+        };
+        assert_eq!(
+            offsets(&sample_timing(&fragmented_mp4, 1)),
+            offsets(&timing)
+        );
+
+        let mut output = Vec::new();
+        re_mp4::remux::defragment(
+            &fragmented_mp4,
+            &mut std::io::Cursor::new(&fragmented),
+            &mut output,
+        )
+        .unwrap();
+        let defragmented = Mp4::read_bytes(&output).unwrap();
+        assert_eq!(sample_timing(&defragmented, 1), timing);
+
+        // Negative offsets are read as a shift of the decode timestamps, so they are written
+        // back as the equivalent offsets of version 0.
+        let stbl = &defragmented.moov.traks[0].mdia.minf.stbl;
+        assert_eq!(stbl.ctts.as_ref().unwrap().version, 0);
+        assert_eq!(stbl.stss.as_ref().unwrap().entries, [1, 4, 7, 9]);
+    }
+}
@@ -0,0 +1,99 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Co64Box, Mp4, StscEntry, StszBox, SttsEntry};
+use synthetic::{build_progressive, TestTrack};
+
+/// A video track with samples of 10 to 14 bytes in chunks of 2 and 3, and a sync sample every
+/// 3, and an audio track without `stss`.
+fn mp4() -> Mp4 {
+    let samples: Vec<Vec<u8>> = (0..5).map(|i| vec![0; 10 + i]).collect();
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![samples[..2].to_vec(), samples[2..].to_vec()],
+            sync_samples: Some(vec![1, 4]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 48_000,
+            chunks: vec![samples.clone()],
+            sync_samples: None,
+        },
+    ];
+    Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing")
+}
+
+#[test]
+fn sample_tables() {
+    let mp4 = mp4();
+    let track = &mp4.tracks()[&1];
+    assert_eq!(
+        track.stts_entries(&mp4),
+        [SttsEntry {
+            sample_count: 5,
+            sample_delta: 1,
+        }]
+    );
+    assert!(track.ctts_entries(&mp4).is_empty());
+    let samples_per_chunk: Vec<_> = track
+        .stsc_entries(&mp4)
+        .iter()
+        .map(|entry| (entry.first_chunk, entry.samples_per_chunk))
+        .collect();
+    assert_eq!(samples_per_chunk, [(1, 2), (2, 3)]);
+    assert_eq!(
+        track.chunk_offsets(&mp4).collect::<Vec<_>>(),
+        [track.samples[0].offset, track.samples[2].offset]
+    );
+    assert_eq!(
+        track.sample_sizes(&mp4).collect::<Vec<_>>(),
+        [10, 11, 12, 13, 14]
+    );
+    assert_eq!(track.sync_sample_numbers(&mp4), Some([1, 4].as_slice()));
+
+    assert_eq!(mp4.tracks()[&2].sync_sample_numbers(&mp4), None);
+}
+
+#[test]
+fn sample_tables_regardless_of_representation() {
+    let mut mp4 = mp4();
+    let offsets: Vec<u64> = mp4.tracks()[&2].chunk_offsets(&mp4).collect();
+
+    // 64-bit chunk offsets and one size for all samples.
+    let stbl = &mut mp4.moov.traks[1].mdia.minf.stbl;
+    stbl.co64 = Some(Co64Box {
+        entries: offsets.clone(),
+        ..Default::default()
+    });
+    stbl.stco = None;
+    stbl.stsz = StszBox {
+        sample_size: 12,
+        sample_count: 5,
+        ..Default::default()
+    };
+
+    let track = &mp4.tracks()[&2];
+    assert_eq!(track.chunk_offsets(&mp4).collect::<Vec<_>>(), offsets);
+    assert_eq!(track.sample_sizes(&mp4).len(), 5);
+    assert!(track.sample_sizes(&mp4).all(|size| size == 12));
+    assert_eq!(
+        track.stsc_entries(&mp4),
+        [StscEntry {
+            first_chunk: 1,
+            samples_per_chunk: 5,
+            sample_description_index: 1,
+            first_sample: 1,
+        }]
+    );
+
+    // Tracks whose `trak` was removed have no tables.
+    mp4.moov.traks.clear();
+    let track = &mp4.tracks()[&2];
+    assert!(track.stts_entries(&mp4).is_empty());
+    assert_eq!(track.chunk_offsets(&mp4).count(), 0);
+    assert_eq!(track.sample_sizes(&mp4).len(), 0);
+}
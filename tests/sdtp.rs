@@ -0,0 +1,81 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Mp4, SampleFlags};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+/// A video track of an I-frame, a P-frame and two B-frames that nothing depends on, with an
+/// `sdtp` that only covers the first three samples.
+fn test_file() -> Vec<u8> {
+    let mut data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4], vec![2; 4], vec![3; 4], vec![4; 4]]],
+            sync_samples: Some(vec![1]),
+        }],
+        false,
+    );
+    let entries = [0b0010_0100, 0b0001_0100, 0b1101_1010];
+    append_child(
+        &mut data,
+        &[b"moov", b"trak", b"mdia", b"minf", b"stbl"],
+        &mp4_box(b"sdtp", &[&[0; 4], &entries]),
+    );
+    data
+}
+
+#[test]
+fn sample_dependencies() {
+    let mp4 = Mp4::read_bytes(&test_file()).unwrap();
+    let sdtp = mp4.moov.traks[0].mdia.minf.stbl.sdtp.as_ref().unwrap();
+    assert_eq!(sdtp.entries.len(), 3);
+
+    let samples = &mp4.tracks()[&1].samples;
+    let dependencies: Vec<_> = samples
+        .iter()
+        .map(|sample| (sample.depends_on(), sample.is_disposable()))
+        .collect();
+    assert_eq!(
+        dependencies,
+        [
+            (Some(false), false),
+            (Some(true), false),
+            (Some(true), true),
+            (None, false)
+        ]
+    );
+
+    // The flags are the same as those of fragments, along with the sync flag of `stss`.
+    assert_eq!(
+        samples[2].flags,
+        SampleFlags {
+            is_leading: 3,
+            depends_on: 1,
+            is_depended_on: 2,
+            has_redundancy: 2,
+            is_non_sync: true,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        samples[3].flags,
+        SampleFlags {
+            is_non_sync: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn sdtp_survives_serde() {
+    let mp4 = Mp4::read_bytes(&test_file()).unwrap();
+    let json = serde_json::to_string(&mp4).unwrap();
+    let copy: Mp4 = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        copy.moov.traks[0].mdia.minf.stbl.sdtp,
+        mp4.moov.traks[0].mdia.minf.stbl.sdtp
+    );
+    assert!(copy.tracks()[&1].samples[2].is_disposable());
+}
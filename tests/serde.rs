@@ -0,0 +1,88 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::time::Duration;
+
+use re_mp4::{Bytes, Mp4};
+use synthetic::{build_progressive, TestTrack};
+
+fn tracks() -> Vec<TestTrack> {
+    vec![
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6], vec![4; 7]]],
+            sync_samples: Some(vec![1, 3]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 1000,
+            chunks: vec![vec![vec![5; 3]], vec![vec![6; 3]]],
+            sync_samples: None,
+        },
+    ]
+}
+
+/// Everything about the samples of all tracks.
+fn samples(mp4: &Mp4) -> Vec<String> {
+    mp4.tracks()
+        .values()
+        .flat_map(|track| track.samples.iter().map(|sample| format!("{sample:?}")))
+        .collect()
+}
+
+fn round_trip(mp4: &Mp4) -> Mp4 {
+    let json = serde_json::to_string(mp4).expect("Failed to serialize");
+    serde_json::from_str(&json).expect("Failed to deserialize")
+}
+
+#[test]
+fn progressive_round_trip() {
+    let data = build_progressive(&tracks(), true);
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+
+    let mut restored = round_trip(&mp4);
+    assert_eq!(restored.ftyp, mp4.ftyp);
+    assert_eq!(restored.moov, mp4.moov);
+    assert_eq!(restored.mdats, mp4.mdats);
+    assert_eq!(restored.box_tree(), mp4.box_tree());
+    assert_eq!(samples(&restored), samples(&mp4));
+    assert_eq!(
+        restored.tracks()[&1].codec_string(),
+        mp4.tracks()[&1].codec_string()
+    );
+
+    // The sample tables survived, so the tracks can be rebuilt from the restored boxes.
+    restored.rebuild_tracks().unwrap();
+    assert_eq!(samples(&restored), samples(&mp4));
+}
+
+#[test]
+fn fragmented_round_trip() {
+    let output = synthetic::fragmented(&tracks(), Duration::from_millis(50));
+    let fragmented = Mp4::read_bytes(&output).unwrap();
+    assert!(!fragmented.moofs.is_empty());
+
+    let mut restored = round_trip(&fragmented);
+    assert_eq!(restored.moofs, fragmented.moofs);
+    restored.rebuild_tracks().unwrap();
+    assert_eq!(samples(&restored), samples(&fragmented));
+}
+
+#[test]
+fn shared_data_is_optional() {
+    let data = Bytes::from(build_progressive(&tracks(), true));
+    let mut mp4 = Mp4::read_shared(data.clone()).unwrap();
+    let sample = mp4.tracks()[&1].samples[2];
+
+    let restored = round_trip(&mp4);
+    assert_eq!(sample.bytes(&restored), Some(Bytes::from(vec![3; 6])));
+
+    assert_eq!(mp4.take_data(), Some(data.clone()));
+    let mut restored = round_trip(&mp4);
+    assert_eq!(sample.bytes(&restored), None);
+    restored.set_data(data);
+    assert_eq!(sample.bytes(&restored), Some(Bytes::from(vec![3; 6])));
+}
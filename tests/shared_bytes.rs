@@ -0,0 +1,53 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Bytes, Mp4};
+use synthetic::{build_progressive, TestTrack};
+
+#[test]
+fn samples_are_slices_of_the_shared_buffer() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+        sync_samples: None,
+    }];
+    let data = Bytes::from(build_progressive(&tracks, false));
+    let mp4 = Mp4::read_shared(data.clone()).unwrap();
+
+    let track = &mp4.tracks()[&1];
+    for (sample, expected) in track.samples.iter().zip(tracks[0].samples()) {
+        let bytes = sample.bytes(&mp4).unwrap();
+        assert_eq!(bytes, expected);
+
+        // The slice points into the original buffer.
+        assert_eq!(bytes.as_ptr(), data[sample.byte_range()].as_ptr());
+    }
+
+    // Without a shared buffer, there is nothing to slice.
+    let copied = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(copied.tracks()[&1].samples[0].bytes(&copied), None);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn samples_are_read_from_a_memory_mapped_file() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+        sync_samples: None,
+    }];
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut file, &build_progressive(&tracks, false)).unwrap();
+
+    let (mp4, mmap) = Mp4::read_mmap(file.path()).unwrap();
+    let samples: Vec<&[u8]> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|sample| &mmap[sample.byte_range()])
+        .collect();
+    assert_eq!(samples, tracks[0].samples().collect::<Vec<_>>());
+}
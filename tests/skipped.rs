@@ -0,0 +1,93 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use re_mp4::{BoxHeader, FourCC, ReadBox as _, Skipped, StsdBox, StsdBoxContent};
+use synthetic::mp4_box;
+
+fn descriptor(tag: u8, contents: &[&[u8]]) -> Vec<u8> {
+    let contents = contents.concat();
+    [&[tag, contents.len() as u8][..], &contents].concat()
+}
+
+#[test]
+fn skipped_descriptors_and_boxes_are_logged() {
+    let dec_config = descriptor(
+        0x04,
+        &[
+            &[0x40, 0x15, 0, 0, 0],
+            &[0; 8],
+            &descriptor(0x05, &[&[0x12, 0x10]]),
+            // Not a descriptor this crate knows.
+            &descriptor(0x14, &[&[1, 2, 3]]),
+        ],
+    );
+    let es = descriptor(0x03, &[&[0, 1, 0], &dec_config, &descriptor(0x06, &[&[2]])]);
+    let esds = mp4_box(b"esds", &[&[0; 4], &es]);
+    let mp4a = mp4_box(
+        b"mp4a",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &[0; 8],
+            &[0, 2, 0, 16],
+            &[0; 4],
+            &[0xbb, 0x80, 0, 0],
+            &esds,
+            &mp4_box(b"chnl", &[&[0; 12]]),
+        ],
+    );
+    let stsd = mp4_box(
+        b"stsd",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 2],
+            &mp4a,
+            &mp4_box(b"abcd", &[&[0; 8]]),
+        ],
+    );
+
+    let skipped = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&skipped);
+    re_mp4::set_skip_logger(Some(Box::new(move |s: &Skipped| {
+        log.borrow_mut().push(s.clone());
+    })));
+
+    let mut reader = Cursor::new(&stsd);
+    let header = BoxHeader::read(&mut reader).unwrap();
+    let stsd = StsdBox::read_box(&mut reader, header.size).unwrap();
+    re_mp4::set_skip_logger(None);
+
+    let StsdBoxContent::Mp4a(mp4a) = stsd.contents else {
+        panic!("expected mp4a, got {:?}", stsd.contents);
+    };
+    assert_eq!(
+        mp4a.esds.unwrap().es_desc.dec_config.dec_specific.profile,
+        2
+    );
+
+    assert_eq!(
+        *skipped.borrow(),
+        [
+            Skipped::Descriptor {
+                parent_tag: 0x04,
+                tag: 0x14,
+                offset: 88,
+                size: 5,
+            },
+            Skipped::Box {
+                parent: FourCC::from(*b"mp4a"),
+                box_type: FourCC::from(*b"chnl"),
+                offset: 96,
+                size: 20,
+            },
+            Skipped::Box {
+                parent: FourCC::from(*b"stsd"),
+                box_type: FourCC::from(*b"abcd"),
+                offset: 116,
+                size: 16,
+            },
+        ]
+    );
+}
@@ -0,0 +1,63 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{sniff, FourCC};
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+fn brand(brand: &[u8; 4]) -> FourCC {
+    FourCC::from(*brand)
+}
+
+#[test]
+fn sniff_ftyp_brands() {
+    let data = build_progressive(
+        &[TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![vec![vec![1; 4]]],
+            sync_samples: None,
+        }],
+        false,
+    );
+
+    let result = sniff(&data).unwrap();
+    assert_eq!(result.first_box, brand(b"ftyp"));
+    assert_eq!(result.major_brand, Some(brand(b"isom")));
+    assert_eq!(result.minor_version, 0x200);
+    assert_eq!(
+        result.compatible_brands().collect::<Vec<_>>(),
+        [brand(b"isom"), brand(b"iso2"), brand(b"mp41")]
+    );
+    assert!(result.has_brand(brand(b"mp41")));
+    assert!(!result.has_brand(brand(b"qt  ")));
+
+    // Brands that are cut off by the end of the prefix are left out.
+    let result = sniff(&data[..26]).unwrap();
+    assert_eq!(
+        result.compatible_brands().collect::<Vec<_>>(),
+        [brand(b"isom"), brand(b"iso2")]
+    );
+}
+
+#[test]
+fn sniff_quicktime_without_ftyp() {
+    let data = mp4_box(b"wide", &[]);
+    let result = sniff(&data).unwrap();
+    assert_eq!(result.first_box, brand(b"wide"));
+    assert_eq!(result.major_brand, None);
+    assert_eq!(result.compatible_brands().count(), 0);
+}
+
+#[test]
+fn sniff_rejects_other_data() {
+    assert!(sniff(b"").is_none());
+    assert!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").is_none());
+    assert!(sniff(&mp4_box(b"abcd", &[&[0; 8]])).is_none());
+
+    // Too small to hold a major brand and minor version.
+    assert!(sniff(&mp4_box(b"ftyp", &[b"isom"])).is_none());
+
+    // Brands are printable.
+    assert!(sniff(&mp4_box(b"ftyp", &[&[0xff, 0, 1, 2], &[0; 4]])).is_none());
+}
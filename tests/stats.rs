@@ -0,0 +1,66 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{BtrtBox, Mp4, Mp4aBox, StsdBoxContent};
+use synthetic::{build_progressive, TestTrack};
+
+/// 30 samples of a tenth of a second, 100 to 129 bytes large, with a sync sample every 10.
+fn mp4() -> Mp4 {
+    let samples: Vec<Vec<u8>> = (0..30).map(|i| vec![0; 100 + i]).collect();
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"soun",
+        timescale: 10,
+        chunks: samples.chunks(10).map(<[_]>::to_vec).collect(),
+        sync_samples: Some(vec![1, 11, 21]),
+    }];
+    Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing")
+}
+
+#[test]
+fn stats_of_sample_table() {
+    let stats = mp4().tracks()[&1].stats();
+    assert_eq!(stats.sample_count, 30);
+    assert_eq!(stats.sync_sample_count, 3);
+    assert_eq!(stats.avg_gop_length(), Some(10.0));
+    assert_eq!(stats.total_size, 3435);
+    assert_eq!((stats.min_sample_size, stats.max_sample_size), (100, 129));
+    assert_eq!(stats.mean_sample_size, 114.5);
+    assert_eq!(stats.total_duration, 30);
+    assert_eq!(
+        (stats.min_sample_duration, stats.max_sample_duration),
+        (1, 1)
+    );
+    assert_eq!(stats.mean_sample_duration, 1.0);
+    assert_eq!(stats.avg_bitrate, 9160.0);
+
+    // The last second has the largest samples.
+    assert_eq!(stats.max_bitrate, (120..130).sum::<u64>() * 8);
+    assert_eq!(stats.btrt, None);
+    assert!(stats.matches_btrt(0.0));
+}
+
+#[test]
+fn stats_are_reconciled_with_btrt() {
+    let mut mp4 = mp4();
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = StsdBoxContent::Mp4a(Mp4aBox {
+        btrt: Some(BtrtBox {
+            buffer_size_db: 0,
+            max_bitrate: 10_000,
+            avg_bitrate: 9000,
+        }),
+        ..Default::default()
+    });
+    mp4.rebuild_tracks().unwrap();
+    let mut stats = mp4.tracks()[&1].stats();
+    assert!(stats.matches_btrt(0.05));
+    assert!(!stats.matches_btrt(0.01));
+
+    // A `btrt` that understates the maximum bitrate.
+    stats.btrt = Some(BtrtBox {
+        buffer_size_db: 0,
+        max_bitrate: 9000,
+        avg_bitrate: 0,
+    });
+    assert!(!stats.matches_btrt(0.05));
+}
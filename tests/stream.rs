@@ -0,0 +1,52 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::Mp4;
+use synthetic::{build_progressive, mp4_box, TestTrack};
+
+fn tracks() -> Vec<TestTrack> {
+    vec![TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]], vec![vec![3; 6]]],
+        sync_samples: Some(vec![1]),
+    }]
+}
+
+#[test]
+fn read_stream_stops_at_mdat() {
+    let mut data = build_progressive(&tracks(), true);
+    data.extend(mp4_box(b"free", &[&[0; 4]]));
+    let expected = Mp4::read_bytes(&data).unwrap();
+
+    // A byte slice can be read from, but not seeked.
+    let mut reader = data.as_slice();
+    let mp4 = Mp4::read_stream(&mut reader).unwrap();
+    assert_eq!(mp4.moov, expected.moov);
+    assert_eq!(mp4.mdats, expected.mdats[..1]);
+
+    // The reader is left at the contents of `mdat`, so the samples can be read from it in order.
+    let mdat_start = data.len() - reader.len();
+    assert_eq!(mdat_start as u64, mp4.mdats[0].0);
+    for sample in &mp4.tracks()[&1].samples {
+        let (contents, rest) = reader.split_at(sample.size as usize);
+        assert_eq!(
+            contents,
+            &data[sample.byte_range()],
+            "sample {} is not next in the stream",
+            sample.id
+        );
+        reader = rest;
+    }
+}
+
+#[test]
+fn read_stream_requires_moov_first() {
+    let data = build_progressive(&tracks(), false);
+    assert!(Mp4::read_stream(data.as_slice()).is_err());
+
+    // A stream that ends within a box.
+    let data = build_progressive(&tracks(), true);
+    assert!(Mp4::read_stream(&data[..100]).is_err());
+}
@@ -0,0 +1,157 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::subtitle::{decode_sample, CueStyling, SubtitleCue};
+use re_mp4::{Sample, StsdBoxContent, TrackKind, Tx3gBox, WvttBox};
+use synthetic::{mp4_box, read_stsd};
+
+#[test]
+fn wvtt_sample_entry() {
+    let entry = mp4_box(
+        b"wvtt",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &mp4_box(b"vttC", &[b"WEBVTT\n\nSTYLE\n::cue { color: red }"]),
+            &mp4_box(b"vlab", &[b"urn:example:subtitles"]),
+            &mp4_box(b"btrt", &[&[0; 12]]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Subtitle));
+    assert_eq!(stsd.contents.codec_string().as_deref(), Some("wvtt"));
+
+    let StsdBoxContent::Wvtt(wvtt) = stsd.contents else {
+        panic!("expected wvtt, got {:?}", stsd.contents);
+    };
+    assert_eq!(wvtt.data_reference_index, 1);
+    assert_eq!(wvtt.config, "WEBVTT\n\nSTYLE\n::cue { color: red }");
+    assert_eq!(wvtt.label.as_deref(), Some("urn:example:subtitles"));
+    assert!(wvtt.btrt.is_some());
+}
+
+#[test]
+fn stpp_sample_entry() {
+    let entry = mp4_box(
+        b"stpp",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            b"http://www.w3.org/ns/ttml http://www.w3.org/ns/ttml/profile/imsc1/text\0",
+            b"\0",
+            b"image/png\0",
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.kind(), Some(TrackKind::Subtitle));
+    assert_eq!(
+        stsd.contents.codec_string().as_deref(),
+        Some("stpp.ttml.im1t")
+    );
+
+    let StsdBoxContent::Stpp(stpp) = stsd.contents else {
+        panic!("expected stpp, got {:?}", stsd.contents);
+    };
+    assert_eq!(stpp.schema_location, "");
+    assert_eq!(stpp.auxiliary_mime_types, "image/png");
+    assert_eq!(stpp.btrt, None);
+}
+
+#[test]
+fn stpp_without_optional_strings() {
+    let entry = mp4_box(
+        b"stpp",
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            b"http://www.w3.org/ns/ttml\0",
+            &mp4_box(b"btrt", &[&[0; 12]]),
+        ],
+    );
+    let stsd = read_stsd(&entry);
+    assert_eq!(stsd.contents.codec_string().as_deref(), Some("stpp"));
+
+    let StsdBoxContent::Stpp(stpp) = stsd.contents else {
+        panic!("expected stpp, got {:?}", stsd.contents);
+    };
+    assert_eq!(stpp.namespace, "http://www.w3.org/ns/ttml");
+    assert_eq!(stpp.schema_location, "");
+    assert_eq!(stpp.auxiliary_mime_types, "");
+    assert!(stpp.btrt.is_some());
+}
+
+fn sample() -> Sample {
+    Sample {
+        timescale: 10,
+        composition_timestamp: 30,
+        duration: 15,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn decode_wvtt_cues() {
+    let entry = StsdBoxContent::Wvtt(WvttBox::default());
+    let bytes = [
+        mp4_box(
+            b"vttc",
+            &[
+                &mp4_box(b"iden", &[b"intro"]),
+                &mp4_box(b"sttg", &[b"align:start line:0"]),
+                &mp4_box(b"payl", &[b"<i>Hello</i>"]),
+            ],
+        ),
+        mp4_box(b"vtta", &[b"NOTE a comment"]),
+        mp4_box(b"vttc", &[&mp4_box(b"payl", &[b"world"])]),
+    ]
+    .concat();
+
+    let cues = decode_sample(&entry, &sample(), &bytes).unwrap();
+    assert_eq!(
+        cues,
+        [
+            SubtitleCue {
+                start: 3.0,
+                duration: 1.5,
+                text: "<i>Hello</i>".to_owned(),
+                styling: CueStyling::WebVtt {
+                    id: "intro".to_owned(),
+                    settings: "align:start line:0".to_owned(),
+                },
+            },
+            SubtitleCue {
+                start: 3.0,
+                duration: 1.5,
+                text: "world".to_owned(),
+                styling: CueStyling::WebVtt {
+                    id: String::new(),
+                    settings: String::new(),
+                },
+            },
+        ]
+    );
+
+    // Gaps between cues are filled with empty samples.
+    let empty = mp4_box(b"vtte", &[]);
+    assert_eq!(decode_sample(&entry, &sample(), &empty).unwrap(), []);
+
+    assert!(decode_sample(&entry, &sample(), &bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn decode_tx3g_cues() {
+    let entry = StsdBoxContent::Tx3g(Tx3gBox::default());
+    let bytes = [&[0, 5][..], b"Hello", &mp4_box(b"twrp", &[&[1]])].concat();
+
+    let cues = decode_sample(&entry, &sample(), &bytes).unwrap();
+    assert_eq!(cues.len(), 1);
+    assert_eq!((cues[0].start, cues[0].duration), (3.0, 1.5));
+    assert_eq!(cues[0].text, "Hello");
+    let CueStyling::Tx3g(styling) = &cues[0].styling else {
+        panic!("expected tx3g styling, got {:?}", cues[0].styling);
+    };
+    assert_eq!(styling.wrap, Some(true));
+
+    // An empty sample clears the previous cue.
+    assert_eq!(decode_sample(&entry, &sample(), &[0, 0]).unwrap(), []);
+
+    let other = StsdBoxContent::Unknown(Default::default());
+    assert!(decode_sample(&other, &sample(), &bytes).is_err());
+}
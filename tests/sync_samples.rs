@@ -0,0 +1,122 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{Avc1Box, AvcCBox, HevcBox, Mp4, RawBox, StsdBoxContent};
+use synthetic::{build_progressive, TestTrack};
+
+/// A sample made of NAL units with the given headers, with 4 byte length fields.
+fn sample(headers: &[&[u8]]) -> Vec<u8> {
+    let mut sample = Vec::new();
+    for header in headers {
+        let nal = [*header, &[0xaa; 3]].concat();
+        sample.extend((nal.len() as u32).to_be_bytes());
+        sample.extend(nal);
+    }
+    sample
+}
+
+/// A file with one video track without `stss`, whose sample entry is replaced by `contents`.
+fn read(samples: Vec<Vec<u8>>, contents: StsdBoxContent) -> (Mp4, Vec<u8>) {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![samples],
+        sync_samples: None,
+    }];
+    let data = build_progressive(&tracks, true);
+    let mut mp4 = Mp4::read_bytes(&data).expect("Failed parsing mp4");
+    mp4.moov.traks[0].mdia.minf.stbl.stsd.contents = contents;
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+    (mp4, data)
+}
+
+#[test]
+fn detect_avc_sync_samples() {
+    let samples = vec![
+        sample(&[&[0x67], &[0x68], &[0x65]]), // SPS, PPS, IDR
+        sample(&[&[0x41]]),
+        sample(&[&[0x06], &[0x65]]), // SEI, IDR
+        sample(&[&[0x01]]),
+    ];
+    let avc1 = StsdBoxContent::Avc1(Avc1Box {
+        avcc: RawBox {
+            contents: AvcCBox::new(&[0x67, 77, 0, 31], &[0x68]),
+            raw: Vec::new(),
+        },
+        ..Default::default()
+    });
+    let (mut mp4, data) = read(samples, avc1);
+    assert!(mp4.tracks()[&1].samples.iter().all(|sample| sample.is_sync));
+
+    let track = &mp4.tracks()[&1];
+    let detected = track
+        .detected_sync_samples(&mp4, &mut Cursor::new(&data))
+        .unwrap();
+    assert_eq!(detected, Some(vec![1, 3]));
+
+    let changed = mp4.add_missing_stss(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(changed, vec![1]);
+    let is_sync: Vec<bool> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|sample| sample.is_sync)
+        .collect();
+    assert_eq!(is_sync, [true, false, true, false]);
+
+    // The track has an `stss` now.
+    assert!(mp4
+        .add_missing_stss(&mut Cursor::new(&data))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn detect_hevc_sync_samples() {
+    // NAL unit types in the upper bits of the first header byte.
+    let nal = |nal_type: u8| [nal_type << 1, 1];
+    let samples = vec![
+        sample(&[&nal(32), &nal(19)]), // VPS, IDR_W_RADL
+        sample(&[&nal(1)]),
+        sample(&[&nal(21)]), // CRA
+        sample(&[&nal(16)]), // BLA_W_LP
+    ];
+    let mut hvc1 = HevcBox::default();
+    hvc1.hvcc.length_size_minus_one = 3;
+    let (mp4, data) = read(samples, StsdBoxContent::Hvc1(hvc1));
+
+    let track = &mp4.tracks()[&1];
+    let detected = track
+        .detected_sync_samples(&mp4, &mut Cursor::new(&data))
+        .unwrap();
+    assert_eq!(detected, Some(vec![1, 3, 4]));
+}
+
+#[test]
+fn other_codecs_are_not_detected() {
+    let (mp4, data) = read(vec![vec![0; 8]], StsdBoxContent::default());
+    let track = &mp4.tracks()[&1];
+    assert_eq!(
+        track
+            .detected_sync_samples(&mp4, &mut Cursor::new(&data))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn detection_fails_on_samples_beyond_the_file() {
+    let avc1 = StsdBoxContent::Avc1(Avc1Box::default());
+    let (mut mp4, data) = read(vec![sample(&[&[0x65]])], avc1);
+
+    // A sample size of 4 GiB must fail at the end of the file, without allocating that much.
+    let stsz = &mut mp4.moov.traks[0].mdia.minf.stbl.stsz;
+    stsz.sample_size = u32::MAX;
+    stsz.sample_sizes.clear();
+    mp4.rebuild_tracks().unwrap();
+
+    let result = mp4.tracks()[&1].detected_sync_samples(&mp4, &mut Cursor::new(&data));
+    assert!(matches!(result, Err(re_mp4::Error::Io(_))));
+}
@@ -0,0 +1,401 @@
+//! Builders for small synthetic MP4 files, so tests don't depend on the LFS sample files.
+
+#![allow(clippy::allow_attributes)]
+#![allow(dead_code, reason = "not every test uses every builder")]
+
+/// A track of a synthetic file built by [`build_progressive`].
+pub struct TestTrack {
+    pub track_id: u32,
+    pub handler: [u8; 4],
+    pub timescale: u32,
+
+    /// The samples of each chunk.
+    pub chunks: Vec<Vec<Vec<u8>>>,
+
+    /// 1-based numbers of the sync samples, or `None` if all samples are sync samples.
+    pub sync_samples: Option<Vec<u32>>,
+}
+
+impl TestTrack {
+    pub fn samples(&self) -> impl Iterator<Item = &[u8]> {
+        self.chunks.iter().flatten().map(Vec::as_slice)
+    }
+}
+
+/// Serializes a box with the given type and contents.
+pub fn mp4_box(name: &[u8; 4], contents: &[&[u8]]) -> Vec<u8> {
+    let size = 8 + contents.iter().map(|c| c.len()).sum::<usize>();
+    let mut bytes = Vec::with_capacity(size);
+    bytes.extend((size as u32).to_be_bytes());
+    bytes.extend(name);
+    for c in contents {
+        bytes.extend(*c);
+    }
+    bytes
+}
+
+fn full_box(name: &[u8; 4], version: u8, flags: u32, contents: &[&[u8]]) -> Vec<u8> {
+    let mut header = flags.to_be_bytes();
+    header[0] = version;
+    mp4_box(name, &[&[&header[..]], contents].concat())
+}
+
+fn be32(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+fn trak(track: &TestTrack, chunk_offsets: &[u64]) -> Vec<u8> {
+    let sample_count = track.samples().count() as u32;
+    let mut tkhd = be32(&[0, 0, track.track_id, 0, sample_count, 0, 0, 0, 0]);
+    tkhd.extend(be32(&[
+        0x10000,
+        0,
+        0,
+        0,
+        0x10000,
+        0,
+        0,
+        0,
+        0x4000_0000,
+        0,
+        0,
+    ]));
+    let mdhd = be32(&[0, 0, track.timescale, sample_count, 0x55c4_0000]);
+    let hdlr = [&be32(&[0])[..], &track.handler, &[0; 13]].concat();
+    let url = full_box(b"url ", 0, 1, &[]);
+    let dinf = mp4_box(b"dinf", &[&full_box(b"dref", 0, 0, &[&be32(&[1]), &url])]);
+
+    // An opaque sample entry, which must survive rewriting byte for byte.
+    let entry = mp4_box(b"test", &[&[0, 0, 0, 0, 0, 0, 0, 1], &track.handler]);
+    let stsd = full_box(b"stsd", 0, 0, &[&be32(&[1]), &entry]);
+    let stts = full_box(b"stts", 0, 0, &[&be32(&[1, sample_count, 1])]);
+    let mut stsc = vec![0];
+    for (index, chunk) in track.chunks.iter().enumerate() {
+        stsc.extend([index as u32 + 1, chunk.len() as u32, 1]);
+    }
+    stsc[0] = (stsc.len() as u32 - 1) / 3;
+    let stsc = full_box(b"stsc", 0, 0, &[&be32(&stsc)]);
+    let sizes: Vec<u32> = track.samples().map(|s| s.len() as u32).collect();
+    let stsz = full_box(b"stsz", 0, 0, &[&be32(&[0, sample_count]), &be32(&sizes)]);
+    let chunk_count = be32(&[chunk_offsets.len() as u32]);
+    let stco = if let Ok(offsets) = chunk_offsets
+        .iter()
+        .map(|&offset| u32::try_from(offset))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        full_box(b"stco", 0, 0, &[&chunk_count, &be32(&offsets)])
+    } else {
+        let offsets: Vec<u8> = chunk_offsets.iter().flat_map(|o| o.to_be_bytes()).collect();
+        full_box(b"co64", 0, 0, &[&chunk_count, &offsets])
+    };
+    let stss = track.sync_samples.as_ref().map(|sync_samples| {
+        let entries = be32(sync_samples);
+        full_box(
+            b"stss",
+            0,
+            0,
+            &[&be32(&[sync_samples.len() as u32]), &entries],
+        )
+    });
+    let mut stbl = vec![stsd, stts];
+    stbl.extend(stss);
+    stbl.extend([stsc, stsz, stco]);
+    let stbl: Vec<&[u8]> = stbl.iter().map(Vec::as_slice).collect();
+    let stbl = mp4_box(b"stbl", &stbl);
+
+    let minf = mp4_box(b"minf", &[&dinf, &stbl]);
+    let mdia = mp4_box(
+        b"mdia",
+        &[
+            &full_box(b"mdhd", 0, 0, &[&mdhd]),
+            &full_box(b"hdlr", 0, 0, &[&hdlr]),
+            &minf,
+        ],
+    );
+    mp4_box(b"trak", &[&full_box(b"tkhd", 0, 3, &[&tkhd]), &mdia])
+}
+
+/// Builds a `moov` for `tracks`, with the given file offsets of their chunks.
+///
+/// Chunk offsets are written to `co64` if they don't fit in `stco`.
+pub fn moov(tracks: &[TestTrack], chunk_offsets: &[Vec<u64>]) -> Vec<u8> {
+    let mut mvhd = be32(&[0, 0, 1000, 0, 0x10000]);
+    mvhd.extend([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    mvhd.extend(be32(&[0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000]));
+    mvhd.extend(be32(&[0, 0, 0, 0, 0, 0, tracks.len() as u32 + 1]));
+    let mut contents = vec![full_box(b"mvhd", 0, 0, &[&mvhd])];
+    contents.extend(tracks.iter().zip(chunk_offsets).map(|(t, o)| trak(t, o)));
+    let contents: Vec<&[u8]> = contents.iter().map(Vec::as_slice).collect();
+    mp4_box(b"moov", &contents)
+}
+
+/// Builds a progressive file in which the chunks of all tracks are interleaved in a single `mdat`.
+pub fn build_progressive(tracks: &[TestTrack], moov_first: bool) -> Vec<u8> {
+    let ftyp = mp4_box(b"ftyp", &[b"isom", &be32(&[0x200]), b"isomiso2mp41"]);
+    let free = mp4_box(b"free", &[&[0; 4]]);
+
+    let moov = |offsets: &[Vec<u64>]| moov(tracks, offsets);
+
+    // Chunks are interleaved round-robin between tracks.
+    let mut order = Vec::new();
+    let max_chunks = tracks.iter().map(|t| t.chunks.len()).max().unwrap_or(0);
+    for chunk in 0..max_chunks {
+        for (track, t) in tracks.iter().enumerate() {
+            if chunk < t.chunks.len() {
+                order.push((track, chunk));
+            }
+        }
+    }
+
+    let placeholder: Vec<Vec<u64>> = tracks.iter().map(|t| vec![0; t.chunks.len()]).collect();
+    let moov_size = moov(&placeholder).len();
+    let mut position = ftyp.len() + free.len() + 8 + if moov_first { moov_size } else { 0 };
+    let mut offsets = placeholder;
+    let mut mdat = Vec::new();
+    for (track, chunk) in order {
+        offsets[track][chunk] = position as u64;
+        for sample in &tracks[track].chunks[chunk] {
+            mdat.extend(sample);
+            position += sample.len();
+        }
+    }
+
+    let moov = moov(&offsets);
+    let mdat = mp4_box(b"mdat", &[&mdat]);
+    if moov_first {
+        [ftyp, free, moov, mdat].concat()
+    } else {
+        [ftyp, free, mdat, moov].concat()
+    }
+}
+
+/// Builds a fragmented file of `tracks`: the progressive file split into segments of about
+/// `segment` by [`re_mp4::remux::fragment`], joined after their init segment.
+pub fn fragmented(tracks: &[TestTrack], segment: std::time::Duration) -> Vec<u8> {
+    fragment_file(&build_progressive(tracks, true), segment)
+}
+
+/// Like [`fragmented`], for a progressive file that was built or edited some other way.
+pub fn fragment_file(progressive: &[u8], segment: std::time::Duration) -> Vec<u8> {
+    let mp4 = re_mp4::Mp4::read_bytes(progressive).expect("Failed parsing mp4");
+    let fragments = re_mp4::remux::fragment(&mp4, &mut std::io::Cursor::new(progressive), segment)
+        .expect("Failed fragmenting mp4");
+    let mut data = fragments.init_segment;
+    for segment in fragments.media_segments {
+        data.extend(segment.data);
+    }
+    data
+}
+
+/// Clears the self-contained flag of the first `url ` in `data`, a file built by
+/// [`build_progressive`], so that the first track refers to its data in another file.
+pub fn with_external_video_data(data: &mut [u8]) {
+    let url = data
+        .windows(4)
+        .position(|window| window == b"url ")
+        .expect("no url box");
+    data[url + 7] = 0;
+}
+
+/// The types of the top-level boxes of a file, in order.
+pub fn top_level_boxes(data: &[u8]) -> Vec<[u8; 4]> {
+    let mut boxes = Vec::new();
+    let mut data = data;
+    while let [a, b, c, d, name @ ..] = data {
+        let size = u32::from_be_bytes([*a, *b, *c, *d]) as usize;
+        boxes.push([name[0], name[1], name[2], name[3]]);
+        data = &data[size..];
+    }
+    boxes
+}
+
+/// Appends `child` to the contents of the box at `path`, taking the first box of each type,
+/// and grows the sizes of the box and its parents to match.
+///
+/// Only use it for boxes after `mdat`, since the offsets of the data after `child` change, and
+/// for boxes whose children follow right after their header, unlike `meta` or `stsd`.
+pub fn append_child(data: &mut Vec<u8>, path: &[&[u8; 4]], child: &[u8]) {
+    let mut parents = Vec::new();
+    let (mut start, mut end) = (0, data.len());
+    for (depth, name) in path.iter().enumerate() {
+        let mut offset = start + if depth == 0 { 0 } else { 8 };
+        loop {
+            assert!(offset + 8 <= end, "box {name:?} not found");
+            let size = box_size(data, offset) as usize;
+            if &data[offset + 4..offset + 8] == *name {
+                parents.push(offset);
+                (start, end) = (offset, offset + size);
+                break;
+            }
+            offset += size;
+        }
+    }
+
+    data.splice(end..end, child.iter().copied());
+    for offset in parents {
+        let size = box_size(data, offset) + child.len() as u32;
+        data[offset..offset + 4].copy_from_slice(&size.to_be_bytes());
+    }
+}
+
+fn box_size(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// A visual sample entry of 1280x720 with the given children.
+pub fn video_sample_entry(name: &[u8; 4], children: &[&[u8]]) -> Vec<u8> {
+    let mut fields = vec![0, 0, 0, 0, 0, 0, 0, 1];
+    fields.extend([0; 16]);
+    fields.extend(1280u16.to_be_bytes());
+    fields.extend(720u16.to_be_bytes());
+    fields.extend(0x0048_0000u32.to_be_bytes());
+    fields.extend(0x0048_0000u32.to_be_bytes());
+    fields.extend([0; 4]);
+    fields.extend(1u16.to_be_bytes());
+    fields.extend([0; 32]);
+    fields.extend(0x0018u16.to_be_bytes());
+    fields.extend((-1i16).to_be_bytes());
+
+    let mut parts: Vec<&[u8]> = vec![&fields];
+    parts.extend(children);
+    mp4_box(name, &parts)
+}
+
+/// An audio sample entry with 2 channels of 16 bits at `sample_rate`, followed by `children`.
+pub fn audio_sample_entry(name: &[u8; 4], sample_rate: u16, children: &[u8]) -> Vec<u8> {
+    versioned_audio_sample_entry(name, 0, sample_rate, &[], children)
+}
+
+/// Like [`audio_sample_entry`], but of the `QuickTime` sound description `version`, with the
+/// fields that `version` adds in `extension`.
+pub fn versioned_audio_sample_entry(
+    name: &[u8; 4],
+    version: u16,
+    sample_rate: u16,
+    extension: &[u8],
+    children: &[u8],
+) -> Vec<u8> {
+    mp4_box(
+        name,
+        &[
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &version.to_be_bytes(),
+            &[0; 6],
+            &[0, 2, 0, 16, 0, 0, 0, 0],
+            &(u32::from(sample_rate) << 16).to_be_bytes(),
+            extension,
+            children,
+        ],
+    )
+}
+
+/// Parses an `stsd` box with the single sample `entry`.
+pub fn read_stsd(entry: &[u8]) -> re_mp4::StsdBox {
+    use re_mp4::ReadBox as _;
+
+    let stsd = mp4_box(b"stsd", &[&[0, 0, 0, 0, 0, 0, 0, 1], entry]);
+    let mut reader = std::io::Cursor::new(&stsd);
+    let header = re_mp4::BoxHeader::read(&mut reader).expect("Failed reading header");
+    re_mp4::StsdBox::read_box(&mut reader, header.size).expect("Failed reading stsd")
+}
+
+/// A file of a single sound track with the sample descriptions `stsd` and no samples, built
+/// from parts.
+pub fn sound_track(stsd: re_mp4::StsdBox) -> re_mp4::Mp4 {
+    use re_mp4::{FourCC, HdlrBox, MdiaBox, MinfBox, MoovBox, StblBox, StcoBox, TkhdBox, TrakBox};
+
+    let trak = TrakBox {
+        tkhd: TkhdBox {
+            track_id: 1,
+            ..Default::default()
+        },
+        mdia: MdiaBox {
+            hdlr: HdlrBox {
+                handler_type: FourCC::from(*b"soun"),
+                ..Default::default()
+            },
+            minf: MinfBox {
+                stbl: StblBox {
+                    stsd,
+                    stco: Some(StcoBox::default()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let moov = MoovBox {
+        traks: vec![trak],
+        ..Default::default()
+    };
+    re_mp4::Mp4::from_parts(re_mp4::FtypBox::default(), moov, Vec::new())
+        .expect("Failed building mp4")
+}
+
+/// A video track of 6 samples in three chunks and a sound track of 8 samples in two chunks,
+/// where every sample is filled with a byte made of the track, chunk and sample number.
+pub fn test_tracks() -> Vec<TestTrack> {
+    let chunk = |track: u8, chunk: u8, samples: usize| -> Vec<Vec<u8>> {
+        (0..samples)
+            .map(|sample| vec![track * 100 + chunk * 10 + sample as u8; 3 + sample])
+            .collect()
+    };
+    vec![
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 30,
+            chunks: vec![chunk(1, 0, 2), chunk(1, 1, 3), chunk(1, 2, 1)],
+            sync_samples: None,
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 48000,
+            chunks: vec![chunk(2, 0, 4), chunk(2, 1, 4)],
+            sync_samples: None,
+        },
+    ]
+}
+
+/// Writes big-endian bit fields.
+#[derive(Default)]
+pub struct Bits(Vec<bool>);
+
+impl Bits {
+    pub fn put(mut self, value: u32, width: usize) -> Self {
+        self.0
+            .extend((0..width).rev().map(|i| (value >> i) & 1 == 1));
+        self
+    }
+
+    pub fn flag(self, value: bool) -> Self {
+        self.put(u32::from(value), 1)
+    }
+
+    /// An unsigned Exp-Golomb code.
+    pub fn ue(self, value: u32) -> Self {
+        let code = value + 1;
+        let width = 32 - code.leading_zeros() as usize;
+        self.put(0, width - 1).put(code, width)
+    }
+
+    /// The bytes, ended by the RBSP stop bit.
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bits = self.flag(true).0;
+        bits.resize(bits.len().div_ceil(8) * 8, false);
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .fold(0, |byte, &bit| (byte << 1) | u8::from(bit))
+            })
+            .collect()
+    }
+}
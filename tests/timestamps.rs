@@ -0,0 +1,95 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{CttsBox, CttsEntry, Mp4, SampleOrder};
+use synthetic::{build_progressive, TestTrack};
+
+#[test]
+fn b_frames_are_decoded_before_time_zero() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 10,
+        chunks: vec![(0..4).map(|i| vec![i as u8; 10]).collect()],
+        sync_samples: Some(vec![1]),
+    }];
+    let mut mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing mp4");
+    // I P B B, shown as I B B P.
+    mp4.moov.traks[0].mdia.minf.stbl.ctts = Some(CttsBox {
+        version: 0,
+        flags: 0,
+        entries: [1, 3, 0, 0]
+            .into_iter()
+            .map(|sample_offset| CttsEntry {
+                sample_count: 1,
+                sample_offset,
+            })
+            .collect(),
+    });
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+
+    let samples: Vec<_> = mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|s| {
+            (
+                s.media_decode_timestamp,
+                s.decode_timestamp,
+                s.composition_timestamp,
+            )
+        })
+        .collect();
+    assert_eq!(samples, [(0, -1, 0), (1, 0, 3), (2, 1, 1), (3, 2, 2)]);
+
+    let first = &mp4.tracks()[&1].samples[0];
+    assert_eq!(first.decode_time(), -0.1);
+    assert_eq!(first.presentation_time(), 0.0);
+}
+
+#[test]
+fn interleaved_samples_across_timescales() {
+    let tracks = [
+        TestTrack {
+            track_id: 1,
+            handler: *b"vide",
+            timescale: 10,
+            chunks: vec![(0..4).map(|i| vec![i as u8; 10]).collect()],
+            sync_samples: Some(vec![1]),
+        },
+        TestTrack {
+            track_id: 2,
+            handler: *b"soun",
+            timescale: 4,
+            chunks: vec![vec![vec![0; 4], vec![1; 4]]],
+            sync_samples: None,
+        },
+    ];
+    let mut mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing mp4");
+    // I P B B, shown as I B B P, at 0.1 seconds per sample. Audio is at 0.25 seconds per sample.
+    mp4.moov.traks[0].mdia.minf.stbl.ctts = Some(CttsBox {
+        version: 0,
+        flags: 0,
+        entries: [1, 3, 0, 0]
+            .into_iter()
+            .map(|sample_offset| CttsEntry {
+                sample_count: 1,
+                sample_offset,
+            })
+            .collect(),
+    });
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+
+    let order = |order| -> Vec<_> {
+        mp4.interleaved_samples(order)
+            .map(|(track_id, sample)| (track_id, sample.id))
+            .collect()
+    };
+    assert_eq!(
+        order(SampleOrder::Decode),
+        [(1, 0), (1, 1), (2, 0), (1, 2), (1, 3), (2, 1)]
+    );
+    assert_eq!(
+        order(SampleOrder::Presentation),
+        [(1, 0), (2, 0), (1, 2), (1, 3), (2, 1), (1, 1)]
+    );
+}
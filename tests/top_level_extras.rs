@@ -0,0 +1,94 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{ExtraBox, FourCC, Mp4};
+use synthetic::{append_child, build_progressive, mp4_box, TestTrack};
+
+#[test]
+fn unknown_top_level_boxes_are_collected() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]]],
+        sync_samples: None,
+    }];
+    let file = build_progressive(&tracks, true);
+    let large = vec![7; ExtraBox::MAX_PAYLOAD_SIZE as usize + 1];
+    let data = [
+        file.clone(),
+        mp4_box(b"PANA", &[b"vendor"]),
+        mp4_box(b"skip", &[&[0; 4]]),
+        mp4_box(b"udta", &[&large]),
+    ]
+    .concat();
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    assert_eq!(
+        mp4.top_level_extras(),
+        [
+            ExtraBox {
+                box_type: FourCC::from(*b"PANA"),
+                user_type: None,
+                offset: file.len() as u64,
+                size: 14,
+                payload: Some(b"vendor".to_vec()),
+            },
+            ExtraBox {
+                box_type: FourCC::from(*b"udta"),
+                user_type: None,
+                offset: file.len() as u64 + 14 + 12,
+                size: large.len() as u64 + 8,
+                payload: None,
+            },
+        ]
+    );
+    assert_eq!(mp4.tracks()[&1].samples.len(), 2);
+
+    assert!(Mp4::read_bytes(&file)
+        .unwrap()
+        .top_level_extras()
+        .is_empty());
+}
+
+#[test]
+fn unknown_boxes_in_moov_and_trak_are_kept() {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![vec![vec![1; 4], vec![2; 5]]],
+        sync_samples: None,
+    }];
+    let user_type = *b"\xbe\x7a\xcf\xcb\x97\xa9\x42\xe8\x9c\x71\x99\x94\x91\xe3\xaf\xac";
+    let mut data = build_progressive(&tracks, false);
+    append_child(&mut data, &[b"moov"], &mp4_box(b"iods", &[&[0; 4]]));
+    append_child(&mut data, &[b"moov"], &mp4_box(b"free", &[&[0; 4]]));
+    append_child(
+        &mut data,
+        &[b"moov", b"trak"],
+        &mp4_box(b"uuid", &[&user_type, b"vendor"]),
+    );
+
+    let mp4 = Mp4::read_bytes(&data).unwrap();
+    let moov_extras: Vec<_> = mp4
+        .moov
+        .extras
+        .iter()
+        .map(|extra| (extra.box_type, extra.user_type, extra.payload.clone()))
+        .collect();
+    assert_eq!(
+        moov_extras,
+        [(FourCC::from(*b"iods"), None, Some(vec![0; 4]))]
+    );
+
+    let [extra] = mp4.moov.traks[0].extras.as_slice() else {
+        panic!("expected one box in trak");
+    };
+    assert_eq!(extra.box_type, FourCC::from(*b"uuid"));
+    assert_eq!(extra.user_type, Some(user_type));
+    assert_eq!(extra.payload.as_deref(), Some(&b"vendor"[..]));
+    assert_eq!(extra.size, 30);
+    assert_eq!(&data[extra.offset as usize + 4..][..4], b"uuid");
+    assert_eq!(mp4.tracks()[&1].samples.len(), 2);
+}
@@ -0,0 +1,83 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{
+    Avc1Box, BoxHeader, Mp4, Mp4aBox, ReadBox as _, StsdBoxContent, TkhdBox, TkhdFlags,
+    WriteBox as _,
+};
+use synthetic::{build_progressive, TestTrack};
+
+fn round_trip(tkhd: &TkhdBox) -> TkhdBox {
+    let mut bytes = Vec::new();
+    tkhd.write_box(&mut bytes).expect("Failed writing tkhd");
+    let mut reader = Cursor::new(&bytes);
+    let header = BoxHeader::read(&mut reader).expect("Failed reading header");
+    TkhdBox::read_box(&mut reader, header.size).expect("Failed reading tkhd")
+}
+
+#[test]
+fn default_track_is_enabled() {
+    let tkhd = TkhdBox::default();
+    assert_eq!(tkhd.track_flags(), TkhdFlags::ENABLED);
+    assert!(tkhd.is_enabled());
+    assert!(!tkhd.is_in_movie());
+    assert!(!tkhd.is_in_preview());
+    assert!(!tkhd.size_is_aspect_ratio());
+}
+
+#[test]
+fn track_flags_survive_a_round_trip() {
+    let mut tkhd = TkhdBox::default();
+    tkhd.set_track_flags(TkhdFlags::IN_MOVIE | TkhdFlags::SIZE_IS_ASPECT_RATIO);
+    assert_eq!(tkhd.flags, 0xa);
+
+    let read = round_trip(&tkhd);
+    assert!(!read.is_enabled());
+    assert!(read.is_in_movie());
+    assert!(!read.is_in_preview());
+    assert!(read.size_is_aspect_ratio());
+
+    // Bits that are not defined are kept.
+    tkhd.flags = 0x100 | TkhdFlags::IN_PREVIEW.bits();
+    let flags = round_trip(&tkhd).track_flags();
+    assert_eq!(flags.bits(), 0x104);
+    assert!(flags.contains(TkhdFlags::IN_PREVIEW));
+}
+
+#[test]
+fn enabled_tracks() {
+    let track = |track_id, handler: &[u8; 4]| TestTrack {
+        track_id,
+        handler: *handler,
+        timescale: 1000,
+        chunks: vec![vec![vec![0; 8]]],
+        sync_samples: None,
+    };
+    let data = build_progressive(
+        &[track(1, b"vide"), track(2, b"vide"), track(3, b"soun")],
+        true,
+    );
+    let mut mp4 = Mp4::read_bytes(&data).unwrap();
+    for trak in &mut mp4.moov.traks[..2] {
+        trak.mdia.minf.stbl.stsd.contents = StsdBoxContent::Avc1(Avc1Box::default());
+    }
+    mp4.moov.traks[2].mdia.minf.stbl.stsd.contents = StsdBoxContent::Mp4a(Mp4aBox::default());
+    mp4.moov.traks[0].tkhd.set_track_flags(TkhdFlags::IN_MOVIE);
+    mp4.rebuild_tracks().unwrap();
+
+    let track_1 = &mp4.tracks()[&1];
+    assert_eq!(track_1.flags(&mp4), TkhdFlags::IN_MOVIE);
+    assert!(!track_1.is_enabled(&mp4));
+    assert_eq!(
+        mp4.enabled_tracks().map(|t| t.track_id).collect::<Vec<_>>(),
+        [2, 3]
+    );
+    assert_eq!(
+        mp4.enabled_video_tracks()
+            .map(|t| t.track_id)
+            .collect::<Vec<_>>(),
+        [2]
+    );
+}
@@ -0,0 +1,84 @@
+use re_mp4::tx3g::{decode_sample, KaraokeEntry, StyleRecord};
+use re_mp4::{RgbaColor, Tx3gBox};
+
+fn modifier(name: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+    let mut bytes = ((8 + contents.len()) as u32).to_be_bytes().to_vec();
+    bytes.extend(name);
+    bytes.extend(contents);
+    bytes
+}
+
+#[test]
+fn decode_styled_sample() {
+    let text = "Hello world";
+    let mut sample = (text.len() as u16).to_be_bytes().to_vec();
+    sample.extend(text.as_bytes());
+    sample.extend(modifier(
+        b"styl",
+        &[0, 1, 0, 0, 0, 5, 0, 1, 0x3, 24, 255, 0, 0, 255],
+    ));
+    sample.extend(modifier(b"hlit", &[0, 6, 0, 11]));
+    sample.extend(modifier(
+        b"krok",
+        &[0, 0, 0, 0, 0, 1, 0, 0, 0, 100, 0, 0, 0, 5],
+    ));
+    sample.extend(modifier(
+        b"href",
+        &[0, 0, 0, 5, 3, b'a', b'b', b'c', 1, b'x'],
+    ));
+    sample.extend(modifier(b"abcd", &[1, 2, 3]));
+    sample.extend(modifier(b"twrp", &[1]));
+
+    let decoded = decode_sample(&sample).unwrap();
+    assert_eq!(decoded.text, text);
+    assert_eq!(
+        decoded.styles,
+        [StyleRecord {
+            chars: 0..5,
+            font_id: 1,
+            face_style_flags: 0x3,
+            font_size: 24,
+            text_color: RgbaColor {
+                red: 255,
+                green: 0,
+                blue: 0,
+                alpha: 255,
+            },
+        }]
+    );
+    assert!(decoded.styles[0].is_bold() && decoded.styles[0].is_italic());
+    assert!(!decoded.styles[0].is_underlined());
+    assert_eq!(decoded.highlight, Some(6..11));
+    assert_eq!(
+        decoded.karaoke.unwrap().entries,
+        [KaraokeEntry {
+            end_time: 100,
+            chars: 0..5,
+        }]
+    );
+    assert_eq!(decoded.hyperlinks[0].url, "abc");
+    assert_eq!(decoded.hyperlinks[0].alt_text, "x");
+    assert_eq!(decoded.wrap, Some(true));
+    assert_eq!(decoded.text_box, None);
+}
+
+#[test]
+fn decode_utf16_and_empty_samples() {
+    let mut sample = vec![0, 6, 0xfe, 0xff, 0, b'h', 0, 0xe9];
+    assert_eq!(decode_sample(&sample).unwrap().text, "hé");
+
+    assert_eq!(decode_sample(&[0, 0]).unwrap().text, "");
+    assert_eq!(decode_sample(&[]).unwrap().text, "");
+
+    // The text is longer than the sample.
+    sample[1] = 7;
+    assert!(decode_sample(&sample).is_err());
+}
+
+#[test]
+fn default_style_of_sample_entry() {
+    let style = Tx3gBox::default().default_style();
+    assert_eq!(style.font_id, 1);
+    assert_eq!(style.font_size, 16);
+    assert_eq!(style.text_color.alpha, 255);
+}
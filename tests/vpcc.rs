@@ -0,0 +1,76 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use std::io::Cursor;
+
+use re_mp4::{BoxHeader, Mp4Box as _, ReadBox as _, VpccBox};
+use synthetic::mp4_box;
+
+fn read_vpcc(version: u8, record: &[u8]) -> re_mp4::Result<VpccBox> {
+    let vpcc = mp4_box(b"vpcC", &[&[version, 0, 0, 0], record]);
+    let mut reader = Cursor::new(&vpcc);
+    let header = BoxHeader::read(&mut reader)?;
+    VpccBox::read_box(&mut reader, header.size)
+}
+
+#[test]
+fn vp9_hdr() {
+    // The vpcC of VP9 profile 2 HDR video as uploaded to YouTube: level 5.1, 10 bits, 4:2:0
+    // with colocated chroma, limited range, BT.2020 primaries and matrix, and PQ.
+    let vpcc = read_vpcc(1, &[2, 51, 0xa2, 9, 16, 9, 0, 0]).unwrap();
+    assert_eq!(
+        vpcc,
+        VpccBox {
+            version: 1,
+            flags: 0,
+            profile: 2,
+            level: 51,
+            bit_depth: 10,
+            chroma_subsampling: 1,
+            video_full_range_flag: false,
+            color_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            codec_initialization_data_size: 0,
+            codec_initialization_data: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn full_range_hlg() {
+    let vpcc = read_vpcc(1, &[2, 41, 0xa3, 9, 18, 9, 0, 0]).unwrap();
+    assert_eq!(vpcc.chroma_subsampling, 1);
+    assert!(vpcc.video_full_range_flag);
+    assert_eq!(vpcc.transfer_characteristics, 18);
+}
+
+#[test]
+fn codec_initialization_data() {
+    let vpcc = read_vpcc(1, &[0, 10, 0x80, 1, 1, 1, 0, 2, 0xaa, 0xbb]).unwrap();
+    assert_eq!(vpcc.codec_initialization_data_size, 2);
+    assert_eq!(vpcc.codec_initialization_data, [0xaa, 0xbb]);
+    assert_eq!(vpcc.box_size(), 22);
+
+    assert!(matches!(
+        read_vpcc(1, &[0, 10, 0x80, 1, 1, 1, 0, 3, 0xaa, 0xbb]),
+        Err(re_mp4::Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn version_0() {
+    // 10 bits with the BT.2020 color space, then 4:2:0 and full range.
+    let vpcc = read_vpcc(0, &[2, 31, 0xa5, 0x11, 0, 0]).unwrap();
+    assert_eq!(vpcc.bit_depth, 10);
+    assert_eq!(vpcc.chroma_subsampling, 1);
+    assert!(vpcc.video_full_range_flag);
+    assert_eq!(
+        (
+            vpcc.color_primaries,
+            vpcc.transfer_characteristics,
+            vpcc.matrix_coefficients
+        ),
+        (2, 2, 9)
+    );
+}
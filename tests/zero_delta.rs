@@ -0,0 +1,93 @@
+#[path = "synthetic/mod.rs"]
+mod synthetic;
+
+use re_mp4::{Mp4, SttsEntry};
+use synthetic::{build_progressive, TestTrack};
+
+fn with_stts(sample_count: usize, entries: &[(u32, u32)], duration: u64) -> Mp4 {
+    let tracks = [TestTrack {
+        track_id: 1,
+        handler: *b"vide",
+        timescale: 30,
+        chunks: vec![(0..sample_count).map(|i| vec![i as u8; 10]).collect()],
+        sync_samples: None,
+    }];
+    let mut mp4 = Mp4::read_bytes(&build_progressive(&tracks, true)).expect("Failed parsing mp4");
+    let trak = &mut mp4.moov.traks[0];
+    trak.mdia.mdhd.duration = duration;
+    trak.mdia.minf.stbl.stts.entries = entries
+        .iter()
+        .map(|&(sample_count, sample_delta)| SttsEntry {
+            sample_count,
+            sample_delta,
+        })
+        .collect();
+    mp4.rebuild_tracks().expect("Failed rebuilding tracks");
+    mp4
+}
+
+fn timestamps(mp4: &Mp4) -> Vec<(i64, u64)> {
+    mp4.tracks()[&1]
+        .samples
+        .iter()
+        .map(|sample| (sample.decode_timestamp, sample.duration))
+        .collect()
+}
+
+#[test]
+fn zero_deltas_are_distributed() {
+    let mp4 = with_stts(6, &[(1, 3), (2, 0), (1, 6), (2, 3)], 15);
+    assert_eq!(
+        timestamps(&mp4),
+        [(0, 3), (3, 2), (5, 2), (7, 2), (9, 3), (12, 3)]
+    );
+    for sample in &mp4.tracks()[&1].samples {
+        assert_eq!(sample.composition_timestamp, sample.decode_timestamp);
+    }
+    assert_eq!(mp4.tracks()[&1].zero_delta_samples(&mp4), [1, 2]);
+}
+
+#[test]
+fn zero_deltas_at_the_end() {
+    // The last sample lasts until the end of the media, which is shared with the ones before it.
+    let mp4 = with_stts(4, &[(1, 2), (3, 0)], 8);
+    assert_eq!(timestamps(&mp4), [(0, 2), (2, 2), (4, 2), (6, 2)]);
+    assert_eq!(mp4.tracks()[&1].zero_delta_samples(&mp4), [1, 2]);
+}
+
+#[test]
+fn zero_deltas_too_short_to_distribute() {
+    let mp4 = with_stts(4, &[(2, 0), (2, 1)], 4);
+    assert_eq!(timestamps(&mp4), [(0, 0), (0, 0), (0, 1), (1, 3)]);
+    assert_eq!(mp4.tracks()[&1].zero_delta_samples(&mp4), [0, 1]);
+}
+
+#[test]
+fn no_zero_deltas() {
+    let mp4 = with_stts(3, &[(3, 5)], 15);
+    assert_eq!(timestamps(&mp4), [(0, 5), (5, 5), (10, 5)]);
+    assert!(mp4.tracks()[&1].zero_delta_samples(&mp4).is_empty());
+}
+
+#[test]
+fn zero_deltas_with_a_huge_duration() {
+    let mp4 = with_stts(6, &[(6, 0)], u64::MAX / 2);
+    let total = u64::MAX / 2;
+    let expected: Vec<(i64, u64)> = (0..6u128)
+        .map(|k| {
+            let offset = |k: u128| (u128::from(total) * k / 6) as u64;
+            (offset(k).cast_signed(), offset(k + 1) - offset(k))
+        })
+        .collect();
+    assert_eq!(timestamps(&mp4), expected);
+}
+
+#[test]
+fn zero_deltas_with_timestamps_out_of_range() {
+    // Spreading this duration would move timestamps past `i64::MAX`, so the run is left alone.
+    let mp4 = with_stts(6, &[(6, 0)], u64::MAX);
+    assert_eq!(
+        timestamps(&mp4),
+        [(0, 0), (0, 0), (0, 0), (0, 0), (0, 0), (0, u64::MAX)]
+    );
+}